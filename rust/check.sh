@@ -0,0 +1,8 @@
+#!/bin/sh
+# Checks to pass before merging: every target of the workspace builds, lints and tests, with the
+# default features and with all of them, so feature-gated code (e.g. `http3`) can't rot unseen
+set -e
+cargo build --workspace
+cargo clippy --workspace --all-targets -- -D warnings
+cargo clippy --workspace --all-targets --all-features -- -D warnings
+cargo test --workspace
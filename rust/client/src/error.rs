@@ -0,0 +1,70 @@
+use reqwest::StatusCode;
+use serde::Deserialize;
+use std::fmt;
+
+/// Error body of the API (RFC 9457 problem details).
+#[derive(Debug, Clone, Deserialize)]
+pub struct Problem {
+    /// Stable, machine-readable error code, e.g. `not_found`.
+    pub code: String,
+
+    /// Summary of the problem type, possibly localized.
+    pub title: String,
+
+    /// Explanation specific to this occurrence of the problem.
+    #[serde(default)]
+    pub detail: Option<String>,
+}
+
+/// Error of a [`Client`](crate::Client) call.
+#[derive(Debug)]
+pub enum Error {
+    /// The client is misconfigured, e.g. a header isn't a valid header value.
+    Config(String),
+
+    /// The request couldn't be sent or its response couldn't be read, e.g. because the server is
+    /// down, the request timed out or the body isn't the expected JSON.
+    Request(reqwest::Error),
+
+    /// The server answered with an error status, and the problem details it sent, if any.
+    Status {
+        status: StatusCode,
+        problem: Option<Problem>,
+    },
+}
+
+impl Error {
+    /// Returns the status the server answered with, if it answered with an error.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Self::Status { status, .. } => Some(*status),
+            Self::Config(_) | Self::Request(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Config(err) => write!(f, "invalid client config: {err}"),
+            Self::Request(err) => write!(f, "request failed: {err}"),
+            Self::Status {
+                status,
+                problem:
+                    Some(Problem {
+                        detail: Some(detail),
+                        ..
+                    }),
+            } => write!(f, "server answered with {status}: {detail}"),
+            Self::Status { status, .. } => write!(f, "server answered with {status}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self {
+        Self::Request(err)
+    }
+}
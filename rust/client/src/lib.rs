@@ -0,0 +1,519 @@
+//! Typed client of the PerCom API, used by the tests and the load generator of the Rust server,
+//! and usable against any of the backends.
+//!
+//! A [`Client`] holds the base URL of a server, its credentials, timeouts and retry policy; it has
+//! a method per endpoint, sending the [`model`] types (the `percom-model` crate, re-exported) at
+//! the paths of [`model::urls`] and decoding the responses. Error statuses become [`Error::Status`] with the problem details of the server. Requests the typed methods
+//! don't cover, e.g. with malformed bodies or extra headers, are built with [`Client::request`],
+//! which applies the same base URL and credentials, and sent with [`Client::send`].
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), percom_client::Error> {
+//! use percom_client::{Client, model::PostInput};
+//!
+//! let client = Client::builder("http://127.0.0.1:8080")
+//!     .bearer("token")
+//!     .build()?;
+//! let post = client
+//!     .create_post(&PostInput {
+//!         author: "alice".to_owned(),
+//!         date: chrono::Utc::now().fixed_offset(),
+//!         content: "Hello".to_owned(),
+//!         publish_at: None,
+//!     })
+//!     .await?;
+//! client.delete_post(&post.id).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod error;
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::{
+    Method, RequestBuilder, Response, StatusCode, Url, Version,
+    header::{HeaderMap, HeaderName, HeaderValue, RETRY_AFTER},
+};
+use serde::{Serialize, de::DeserializeOwned};
+use sha2::Sha256;
+use std::time::Duration;
+
+pub use error::{Error, Problem};
+use model::{Introspection, Page, PageRequest, Post, PostInput, User, UserInput, urls};
+pub use percom_model as model;
+
+/// Headers of signed requests (see `RUST_SERVER_SIGNING_KEYS`).
+pub const CLIENT_HEADER: &str = "X-Client-Id";
+pub const SIGNATURE_HEADER: &str = "X-Signature";
+pub const TIMESTAMP_HEADER: &str = "X-Timestamp";
+
+/// Timeout of a request, from sending it to reading its whole response, unless configured.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Credentials sent with requests.
+#[derive(Debug, Clone, Default)]
+pub enum Auth {
+    #[default]
+    None,
+
+    /// `Authorization: Bearer <token>`.
+    Bearer(String),
+
+    /// Any other header, e.g. an API key.
+    Header(HeaderName, HeaderValue),
+}
+
+/// How requests are retried. Requests shed by the server (`429` or `503`) are retried whatever
+/// their method, as the server didn't process them; requests which failed to get an answer are
+/// retried only if their method is idempotent.
+#[derive(Debug, Clone)]
+pub struct Retry {
+    /// Maximum number of retries of a request; `0` disables retries.
+    pub max_retries: u32,
+
+    /// Delay before the first retry, doubled for every further retry. A `Retry-After` of the
+    /// server takes precedence.
+    pub backoff: Duration,
+
+    /// Maximum delay between two attempts.
+    pub max_backoff: Duration,
+}
+
+impl Default for Retry {
+    /// No retries.
+    fn default() -> Self {
+        Self {
+            max_retries: 0,
+            backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+impl Retry {
+    /// Returns the delay before retry `attempt` (0-based) of a request answered with `response`,
+    /// if it was answered.
+    fn delay(&self, attempt: u32, response: Option<&Response>) -> Duration {
+        let retry_after = response
+            .and_then(|response| response.headers().get(RETRY_AFTER))
+            .and_then(|value| value.to_str().ok()?.parse().ok())
+            .map(Duration::from_secs);
+        retry_after
+            .unwrap_or_else(|| self.backoff.saturating_mul(1 << attempt.min(16)))
+            .min(self.max_backoff)
+    }
+}
+
+/// Client ID and secret of signed requests.
+#[derive(Debug, Clone)]
+struct Signing {
+    client: HeaderValue,
+    secret: Vec<u8>,
+}
+
+/// Builder of a [`Client`], created with [`Client::builder`].
+#[derive(Debug)]
+pub struct ClientBuilder {
+    base: String,
+    http: Option<reqwest::Client>,
+    version: Option<Version>,
+    auth: Auth,
+    signing: Option<(String, String)>,
+    headers: Vec<(String, String)>,
+    timeout: Option<Duration>,
+    retry: Retry,
+}
+
+impl ClientBuilder {
+    /// Sends requests with `client`, e.g. one configured for a transport (HTTP/2, HTTP/3); a
+    /// default client is used otherwise.
+    pub fn http(mut self, client: reqwest::Client) -> Self {
+        self.http = Some(client);
+        self
+    }
+
+    /// Sends every request with HTTP `version`, as some transports are only used by requests
+    /// asking for them (HTTP/3).
+    pub fn version(mut self, version: Version) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    /// Authenticates requests with `Authorization: Bearer <token>`.
+    pub fn bearer(mut self, token: impl Into<String>) -> Self {
+        self.auth = Auth::Bearer(token.into());
+        self
+    }
+
+    /// Authenticates requests as `auth` says.
+    pub fn auth(mut self, auth: Auth) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Signs every request with the secret of `client` (see `RUST_SERVER_SIGNING_KEYS`).
+    pub fn signing(mut self, client: impl Into<String>, secret: impl Into<String>) -> Self {
+        self.signing = Some((client.into(), secret.into()));
+        self
+    }
+
+    /// Adds a header to every request.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sets the timeout of every request, 30 seconds by default.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Lets requests take as long as they take, e.g. to measure the latency of a stalled server.
+    pub fn no_timeout(mut self) -> Self {
+        self.timeout = None;
+        self
+    }
+
+    /// Sets how requests are retried; they aren't by default.
+    pub fn retry(mut self, retry: Retry) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Builds the client.
+    ///
+    /// # Errors
+    /// Returns [`Error::Config`] if the base URL, a header or the signing client is invalid.
+    pub fn build(self) -> Result<Client, Error> {
+        let base = Url::parse(&self.base)
+            .map_err(|err| Error::Config(format!("base URL {}: {err}", self.base)))?;
+        if base.cannot_be_a_base() {
+            return Err(Error::Config(format!("{} isn't a base URL", self.base)));
+        }
+        let headers = self
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                Ok((
+                    HeaderName::from_bytes(name.as_bytes())
+                        .map_err(|err| Error::Config(format!("header {name}: {err}")))?,
+                    HeaderValue::from_str(value)
+                        .map_err(|err| Error::Config(format!("header {name}: {err}")))?,
+                ))
+            })
+            .collect::<Result<_, Error>>()?;
+        let signing = self
+            .signing
+            .map(|(client, secret)| {
+                Ok::<_, Error>(Signing {
+                    client: HeaderValue::from_str(&client)
+                        .map_err(|err| Error::Config(format!("signing client: {err}")))?,
+                    secret: secret.into_bytes(),
+                })
+            })
+            .transpose()?;
+        Ok(Client {
+            http: self.http.unwrap_or_default(),
+            base,
+            version: self.version,
+            auth: self.auth,
+            signing,
+            headers,
+            timeout: self.timeout,
+            retry: self.retry,
+        })
+    }
+}
+
+/// Client of a PerCom server (see the [crate documentation](crate)).
+///
+/// Cloning is cheap and clones share their connections.
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base: Url,
+    version: Option<Version>,
+    auth: Auth,
+    signing: Option<Signing>,
+    headers: HeaderMap,
+    timeout: Option<Duration>,
+    retry: Retry,
+}
+
+impl Client {
+    /// Returns a builder of a client of the server at `base`, e.g. `http://127.0.0.1:8080`.
+    pub fn builder(base: impl Into<String>) -> ClientBuilder {
+        ClientBuilder {
+            base: base.into(),
+            http: None,
+            version: None,
+            auth: Auth::None,
+            signing: None,
+            headers: Vec::new(),
+            timeout: Some(DEFAULT_TIMEOUT),
+            retry: Retry::default(),
+        }
+    }
+
+    /// Returns a client sharing the config and connections of this one, but authenticating with
+    /// `token`, e.g. the ID of a registered user.
+    pub fn with_bearer(&self, token: impl Into<String>) -> Self {
+        Self {
+            auth: Auth::Bearer(token.into()),
+            ..self.clone()
+        }
+    }
+
+    /// Returns the URL of `path` (which starts with `/` and may have a query) on the server.
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{path}", self.base.as_str().trim_end_matches('/'))
+    }
+
+    /// Starts a request of `path` (see [`Client::url`]) with the configured version, headers,
+    /// timeout and credentials.
+    pub fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.prepare(self.http.request(method, self.url(path)))
+    }
+
+    /// Starts a request without credentials, e.g. to check that an endpoint requires them.
+    pub fn anonymous(&self, method: Method, path: &str) -> RequestBuilder {
+        self.base_request(self.http.request(method, self.url(path)))
+    }
+
+    fn base_request(&self, mut request: RequestBuilder) -> RequestBuilder {
+        if let Some(version) = self.version {
+            request = request.version(version);
+        }
+        if let Some(timeout) = self.timeout {
+            request = request.timeout(timeout);
+        }
+        request.headers(self.headers.clone())
+    }
+
+    fn prepare(&self, request: RequestBuilder) -> RequestBuilder {
+        let request = self.base_request(request);
+        match &self.auth {
+            Auth::None => request,
+            Auth::Bearer(token) => request.bearer_auth(token),
+            Auth::Header(name, value) => request.header(name, value),
+        }
+    }
+
+    /// Signs `request` if signing is configured; the request must be complete. [`Client::send`]
+    /// does it already.
+    ///
+    /// # Errors
+    /// Returns [`Error::Request`] if the request can't be built.
+    pub fn sign(&self, request: RequestBuilder) -> Result<RequestBuilder, Error> {
+        let Some(signing) = &self.signing else {
+            return Ok(request);
+        };
+        let (client, request) = request.build_split();
+        let mut request = request?;
+        let timestamp = Utc::now().timestamp().to_string();
+        let url = request.url();
+        let path = match url.query() {
+            Some(query) => format!("{}?{query}", url.path()),
+            None => url.path().to_owned(),
+        };
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&signing.secret).expect("HMAC takes keys of any size");
+        mac.update(format!("{timestamp}\n{}\n{path}\n", request.method()).as_bytes());
+        mac.update(
+            request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .unwrap_or_default(),
+        );
+        let signature = hex::encode(mac.finalize().into_bytes());
+        let headers = request.headers_mut();
+        headers.insert(CLIENT_HEADER, signing.client.clone());
+        headers.insert(
+            TIMESTAMP_HEADER,
+            HeaderValue::from_str(&timestamp).expect("Numbers are valid header values"),
+        );
+        headers.insert(
+            SIGNATURE_HEADER,
+            HeaderValue::from_str(&signature).expect("Hex digits are valid header values"),
+        );
+        Ok(RequestBuilder::from_parts(client, request))
+    }
+
+    /// Signs and sends `request`, retrying it as configured (see [`Retry`]).
+    ///
+    /// # Errors
+    /// Returns [`Error::Status`] if the server answers with an error status in the end, and
+    /// [`Error::Request`] if it doesn't answer.
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response, Error> {
+        // Retries resend the same signature, which stays valid within the server's skew
+        let (http, request) = self.sign(request)?.build_split();
+        let request = request?;
+        let idempotent = !matches!(*request.method(), Method::POST | Method::PATCH);
+        let mut attempt = 0;
+        loop {
+            // Bodies of the typed methods are in memory; streamed bodies can only be sent once
+            let Some(sent) = request.try_clone() else {
+                return check(http.execute(request).await?).await;
+            };
+            let retry = attempt < self.retry.max_retries;
+            match http.execute(sent).await {
+                Ok(response)
+                    if retry
+                        && matches!(
+                            response.status(),
+                            StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE
+                        ) =>
+                {
+                    let delay = self.retry.delay(attempt, Some(&response));
+                    // Drain the body, so the connection can be reused
+                    let _ = response.bytes().await;
+                    tokio::time::sleep(delay).await;
+                }
+                Err(_) if retry && idempotent => {
+                    tokio::time::sleep(self.retry.delay(attempt, None)).await;
+                }
+                Ok(response) => return check(response).await,
+                Err(err) => return Err(err.into()),
+            }
+            attempt += 1;
+        }
+    }
+
+    /// Sends `request` and decodes its JSON response.
+    async fn call<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, Error> {
+        Ok(self.send(request).await?.json().await?)
+    }
+
+    /// Sends `request`, expecting no content in the response.
+    async fn call_empty(&self, request: RequestBuilder) -> Result<(), Error> {
+        self.send(request).await?;
+        Ok(())
+    }
+
+    fn get(&self, path: &str) -> RequestBuilder {
+        self.prepare(self.http.get(self.url(path)))
+    }
+
+    fn post(&self, path: &str, body: &impl Serialize) -> RequestBuilder {
+        self.prepare(self.http.post(self.url(path))).json(body)
+    }
+
+    fn put(&self, path: &str) -> RequestBuilder {
+        self.prepare(self.http.put(self.url(path)))
+    }
+
+    fn delete(&self, path: &str) -> RequestBuilder {
+        self.prepare(self.http.delete(self.url(path)))
+    }
+
+    /// `GET /posts`: all published posts.
+    pub async fn list_posts(&self) -> Result<Vec<Post>, Error> {
+        self.call(self.get(&urls::posts::list())).await
+    }
+
+    /// `GET /posts?date=<prefix>`: the published posts whose UTC date starts with `prefix`, e.g.
+    /// `2024-01`, oldest first.
+    pub async fn list_posts_by_date(&self, prefix: &str) -> Result<Vec<Post>, Error> {
+        self.call(self.get(&urls::posts::list()).query(&[("date", prefix)]))
+            .await
+    }
+
+    /// `GET /posts/{id}`.
+    pub async fn get_post(&self, id: &str) -> Result<Post, Error> {
+        self.call(self.get(&urls::posts::by_id(id))).await
+    }
+
+    /// `POST /posts`.
+    pub async fn create_post(&self, input: &PostInput) -> Result<Post, Error> {
+        self.call(self.post(&urls::posts::list(), input)).await
+    }
+
+    /// `PUT /posts/{id}`.
+    pub async fn update_post(&self, id: &str, input: &PostInput) -> Result<Post, Error> {
+        self.call(self.put(&urls::posts::by_id(id)).json(input))
+            .await
+    }
+
+    /// `DELETE /posts/{id}`.
+    pub async fn delete_post(&self, id: &str) -> Result<(), Error> {
+        self.call_empty(self.delete(&urls::posts::by_id(id))).await
+    }
+
+    /// `GET /users`: all users, or those with `email` and `nickname` if given.
+    pub async fn find_users(
+        &self,
+        email: Option<&str>,
+        nickname: Option<&str>,
+    ) -> Result<Vec<User>, Error> {
+        let query: Vec<_> = [("email", email), ("nickname", nickname)]
+            .into_iter()
+            .filter_map(|(name, value)| Some((name, value?)))
+            .collect();
+        self.call(self.get(&urls::users::list()).query(&query))
+            .await
+    }
+
+    /// `POST /users`.
+    pub async fn create_user(&self, input: &UserInput) -> Result<User, Error> {
+        self.call(self.post(&urls::users::list(), input)).await
+    }
+
+    /// `GET /users/{id}`.
+    pub async fn get_user(&self, id: &str) -> Result<User, Error> {
+        self.call(self.get(&urls::users::by_id(id))).await
+    }
+
+    /// `PUT /users/{id}`.
+    pub async fn update_user(&self, id: &str, input: &UserInput) -> Result<User, Error> {
+        self.call(self.put(&urls::users::by_id(id)).json(input))
+            .await
+    }
+
+    /// `DELETE /users/{id}`: deletes the account, its tokens and follows; its posts are kept
+    /// without an author.
+    pub async fn delete_user(&self, id: &str) -> Result<(), Error> {
+        self.call_empty(self.delete(&urls::users::by_id(id))).await
+    }
+
+    /// `GET /users/{id}/following`: the authors the user follows.
+    pub async fn following(&self, id: &str) -> Result<Vec<String>, Error> {
+        self.call(self.get(&urls::users::following(id))).await
+    }
+
+    /// `PUT /users/{id}/following/{author}`.
+    pub async fn follow(&self, id: &str, author: &str) -> Result<(), Error> {
+        self.call_empty(self.put(&urls::users::follow(id, author)))
+            .await
+    }
+
+    /// `DELETE /users/{id}/following/{author}`.
+    pub async fn unfollow(&self, id: &str, author: &str) -> Result<(), Error> {
+        self.call_empty(self.delete(&urls::users::follow(id, author)))
+            .await
+    }
+
+    /// `GET /feed`: the posts of the authors the authenticated user follows, newest first.
+    pub async fn feed(&self, page: PageRequest) -> Result<Page<Post>, Error> {
+        self.call(self.get(&urls::feed::list()).query(&page)).await
+    }
+
+    /// `POST /auth/introspect`: what the server knows about `token`, e.g. whether it's still
+    /// active. The client's own credentials authorize the call.
+    pub async fn introspect(&self, token: &str) -> Result<Introspection, Error> {
+        let request = self.request(Method::POST, &urls::auth::introspect());
+        self.call(request.form(&[("token", token)])).await
+    }
+}
+
+/// Turns error statuses into [`Error::Status`], with the problem details of the body; others, like
+/// `304 Not Modified` of a conditional request, are passed on.
+async fn check(response: Response) -> Result<Response, Error> {
+    let status = response.status();
+    if !status.is_client_error() && !status.is_server_error() {
+        return Ok(response);
+    }
+    let problem = response.json().await.ok();
+    Err(Error::Status { status, problem })
+}
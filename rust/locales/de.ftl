@@ -0,0 +1,42 @@
+# German messages of error responses (see `en.ftl`).
+
+title-bad_request = Ungültige Anfrage
+title-unauthorized = Nicht authentifiziert
+title-forbidden = Verboten
+title-not_found = Nicht gefunden
+title-method_not_allowed = Methode nicht erlaubt
+title-request_timeout = Zeitüberschreitung der Anfrage
+title-conflict = Konflikt
+title-unsupported_media_type = Nicht unterstützter Medientyp
+title-unprocessable_entity = Nicht verarbeitbarer Inhalt
+title-request_header_fields_too_large = Header-Felder der Anfrage zu groß
+title-too_many_requests = Zu viele Anfragen
+title-service_unavailable = Dienst nicht verfügbar
+title-gateway_timeout = Zeitüberschreitung des Gateways
+title-internal = Interner Serverfehler
+
+auth-invalid-token = Ungültiges Token
+auth-missing-token = Nicht authentifiziert
+auth-unbound-token = Das Token gehört zu keinem Benutzer
+user-disabled = Der Benutzer { $id } ist deaktiviert
+read-only = Diese Instanz ist schreibgeschützt; Schreibzugriffe gehen an die primäre Instanz
+acl-denied = Die Client-Adresse darf nicht auf { $path } zugreifen
+signature-invalid = Ungültige Anfragesignatur: { $reason }
+post-id-invalid = { $header } muss eine UUID sein
+post-exists = Der Beitrag { $id } existiert bereits
+email-taken = Die E-Mail-Adresse { $email } ist bereits registriert
+nickname-taken = Der Spitzname { $nickname } ist bereits registriert
+protobuf-invalid = Ungültiger Protobuf-Inhalt: { $error }
+post-invalid = Ungültiger Beitrag: { $error }
+json-invalid = Ungültiger JSON-Body: { $error }
+tz-invalid = tz muss ein UTC-Offset wie -05:00 oder Z sein, nicht { $value }
+field-unknown = Unbekanntes Feld { $name }: erwartet werden { $expected }
+relation-unknown = Unbekannte Relation { $name }: erwartet werden { $expected }
+query-unknown = Unbekannter Query-Parameter { $name }: erwartet werden { $expected }
+query-unexpected = Unbekannter Query-Parameter { $name }: der Endpunkt erwartet keine
+request-stalled = Der Anfrage-Body stockte länger als { $ms } ms
+request-too-slow = Der Anfrage-Body brauchte länger als { $ms } ms
+headers-too-large = Die Header der Anfrage sind { $size } Bytes groß, mehr als { $limit }
+sanitize-control = Der Inhalt enthält Steuerzeichen
+sanitize-html = Der Inhalt enthält HTML
+moderation-banned = Der Inhalt enthält das gesperrte Schlüsselwort { $keyword }
@@ -0,0 +1,46 @@
+# Messages of error responses (see `src/scheme/locale.rs`).
+#
+# `title-<code>` is the title of every error code. The other messages are the details sent by the
+# server, which must match the code word for word; `{ $name }` stands for a variable part, copied
+# into the translation as it is.
+
+title-bad_request = Bad Request
+title-unauthorized = Unauthorized
+title-forbidden = Forbidden
+title-not_found = Not Found
+title-method_not_allowed = Method Not Allowed
+title-request_timeout = Request Timeout
+title-conflict = Conflict
+title-unsupported_media_type = Unsupported Media Type
+title-unprocessable_entity = Unprocessable Entity
+title-request_header_fields_too_large = Request Header Fields Too Large
+title-too_many_requests = Too Many Requests
+title-service_unavailable = Service Unavailable
+title-gateway_timeout = Gateway Timeout
+title-internal = Internal Server Error
+
+auth-invalid-token = Invalid token
+auth-missing-token = Unauthorized
+auth-unbound-token = token isn't bound to a user
+user-disabled = user { $id } is disabled
+read-only = this instance is read-only; send writes to the primary
+acl-denied = client address may not access { $path }
+signature-invalid = invalid request signature: { $reason }
+post-id-invalid = { $header } must be a UUID
+post-exists = post { $id } exists
+email-taken = email { $email } is already registered
+nickname-taken = nickname { $nickname } is already registered
+protobuf-invalid = Invalid protobuf body: { $error }
+post-invalid = Invalid post: { $error }
+json-invalid = Invalid JSON body: { $error }
+tz-invalid = tz must be a UTC offset like -05:00 or Z, not { $value }
+field-unknown = unknown field { $name }: expected { $expected }
+relation-unknown = unknown relation { $name }: expected { $expected }
+query-unknown = unknown query parameter { $name }: expected { $expected }
+query-unexpected = unknown query parameter { $name }: the endpoint takes none
+request-stalled = request body stalled for more than { $ms } ms
+request-too-slow = request body took more than { $ms } ms
+headers-too-large = request headers take { $size } bytes, more than { $limit }
+sanitize-control = content contains control characters
+sanitize-html = content contains HTML
+moderation-banned = content contains the banned keyword { $keyword }
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+/// Token type of the introspected tokens: the server only takes bearer tokens.
+pub const BEARER: &str = "Bearer";
+
+/// What the server knows about a token, as returned by `POST /auth/introspect`.
+///
+/// The fields loosely follow RFC 7662 (OAuth 2.0 Token Introspection): an inactive token, e.g. a
+/// revoked one, is only `{"active": false}`, while the other fields describe active tokens. The
+/// server has no scopes, so there's no `scope`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Introspection {
+    /// Whether the server accepts the token.
+    pub active: bool,
+
+    /// ID of the user the token was issued to, if it's bound to one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sub: Option<String>,
+
+    /// Expiry of the token, in seconds since the Unix epoch; `None` if it doesn't expire.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exp: Option<i64>,
+
+    /// Type of the token, always [`BEARER`] for active tokens.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_type: Option<String>,
+}
@@ -0,0 +1,139 @@
+//! Dates of posts.
+//!
+//! Dates are exchanged as strict RFC 3339 date-times with an explicit offset (`Z` or `±hh:mm`).
+//! A post's `date` keeps the offset it was sent with, so it's echoed as written; the server's `?tz=`
+//! renders it at another offset. `publish_at` is only an instant to the scheduler and always comes
+//! back in UTC.
+//!
+//! All dates have the same [`Precision`]: they're truncated to it when read and written with
+//! exactly its digits, so a date reads back exactly as it was stored. The precision is a setting of
+//! the process (see [`set_precision`]), so a client keeping the default sends dates with
+//! microseconds.
+
+use chrono::{DateTime, FixedOffset, SecondsFormat, TimeZone, Timelike, Utc};
+use serde::{Deserialize, Deserializer, Serializer, de::Error};
+use std::sync::OnceLock;
+
+/// Example shown in errors about malformed dates.
+const EXAMPLE: &str = "2024-05-01T12:00:00+02:00";
+
+/// Precision of dates, set once at startup (see [`set_precision`]).
+static PRECISION: OnceLock<Precision> = OnceLock::new();
+
+/// Fraction of a second dates are kept with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Precision {
+    /// Milliseconds, e.g. for clients storing dates as JavaScript `Date`s.
+    Millis,
+
+    /// Microseconds, the precision of most databases.
+    #[default]
+    Micros,
+}
+
+impl Precision {
+    /// Returns the precision named `millis` or `micros`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "millis" => Some(Self::Millis),
+            "micros" => Some(Self::Micros),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the precision, as accepted by [`Precision::from_name`].
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Millis => "millis",
+            Self::Micros => "micros",
+        }
+    }
+
+    /// Returns the number of nanoseconds of a unit.
+    fn unit(self) -> u32 {
+        match self {
+            Self::Millis => 1_000_000,
+            Self::Micros => 1_000,
+        }
+    }
+
+    /// Returns the format of fractional seconds.
+    fn format(self) -> SecondsFormat {
+        match self {
+            Self::Millis => SecondsFormat::Millis,
+            Self::Micros => SecondsFormat::Micros,
+        }
+    }
+}
+
+/// Sets the precision of dates (`RUST_SERVER_DATE_PRECISION` of the server). Only the first call has an effect;
+/// without one, dates have [`Precision::Micros`].
+pub fn set_precision(precision: Precision) {
+    let _ = PRECISION.set(precision);
+}
+
+/// Returns the precision of dates.
+pub fn precision() -> Precision {
+    PRECISION.get().copied().unwrap_or_default()
+}
+
+/// Truncates `date` to the [`precision`].
+pub fn normalize<Tz: TimeZone>(date: DateTime<Tz>) -> DateTime<Tz> {
+    let unit = precision().unit();
+    let nanos = date.nanosecond();
+    date.with_nanosecond(nanos - nanos % unit)
+        .expect("Truncated nanoseconds are valid")
+}
+
+/// Parses a strict RFC 3339 date-time, keeping its offset, and truncates it to the [`precision`].
+pub fn parse(value: &str) -> Result<DateTime<FixedOffset>, String> {
+    DateTime::parse_from_rfc3339(value)
+        .map(normalize)
+        .map_err(|err| {
+            format!("{value:?} isn't an RFC 3339 date-time with an offset, like {EXAMPLE} ({err})")
+        })
+}
+
+/// Formats `date` as RFC 3339 with the digits of the [`precision`].
+pub fn format<Tz: TimeZone>(date: &DateTime<Tz>) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    normalize(date.clone()).to_rfc3339_opts(precision().format(), true)
+}
+
+/// Serializes a date with [`format()`], for `#[serde(serialize_with)]`.
+pub fn serialize<S: Serializer>(
+    date: &DateTime<FixedOffset>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format(date))
+}
+
+/// Serializes an optional UTC date with [`format()`], for `#[serde(serialize_with)]`.
+pub fn serialize_utc<S: Serializer>(
+    date: &Option<DateTime<Utc>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match date {
+        Some(date) => serializer.serialize_some(&format(date)),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Deserializes a date with [`parse`], for `#[serde(deserialize_with)]`.
+pub fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<DateTime<FixedOffset>, D::Error> {
+    parse(&String::deserialize(deserializer)?).map_err(D::Error::custom)
+}
+
+/// Deserializes an optional date with [`parse`] as a UTC instant, for `#[serde(deserialize_with)]`.
+pub fn deserialize_utc<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<DateTime<Utc>>, D::Error> {
+    Option::<String>::deserialize(deserializer)?
+        .map(|value| parse(&value).map(|date| date.to_utc()))
+        .transpose()
+        .map_err(D::Error::custom)
+}
@@ -0,0 +1,20 @@
+//! Resources of the PerCom API, as they're exchanged over the wire, and the paths they're
+//! exchanged at (see [`urls`]).
+//!
+//! Shared by the server, the API client (`percom-client`) and the test harness, so they agree on
+//! the JSON and the URL of every resource without depending on the server's internals. With the `proptest`
+//! feature, the models implement `Arbitrary` (see `proptests`).
+
+pub mod auth;
+pub mod date;
+pub mod page;
+pub mod posts;
+#[cfg(feature = "proptest")]
+pub mod proptests;
+pub mod urls;
+pub mod users;
+
+pub use auth::*;
+pub use page::*;
+pub use posts::*;
+pub use users::*;
@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+
+/// A single page of a larger collection, returned by paginated list endpoints, e.g. `GET /feed`.
+///
+/// Besides the items, the page carries enough metadata for the client to navigate the collection.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Page<T> {
+    /// Items of the current page.
+    pub items: Vec<T>,
+
+    /// Current page number (1-based).
+    pub page: usize,
+
+    /// Requested page size.
+    pub per_page: usize,
+
+    /// Total number of items across all pages.
+    pub total: usize,
+}
+
+impl<T> Page<T> {
+    /// Builds a page from already sliced items.
+    pub fn new(items: Vec<T>, page: usize, per_page: usize, total: usize) -> Self {
+        Self {
+            items,
+            page,
+            per_page,
+            total,
+        }
+    }
+
+    /// Returns the number of the previous page, if this isn't the first one.
+    pub fn prev(&self) -> Option<usize> {
+        (self.page > 1).then(|| self.page - 1)
+    }
+
+    /// Returns the number of the next page, if there are items after this one.
+    pub fn next(&self) -> Option<usize> {
+        (self.page.saturating_mul(self.per_page) < self.total).then(|| self.page + 1)
+    }
+}
+
+/// Page requested from a paginated collection, sent as the `page` and `per_page` query
+/// parameters; the server's defaults apply to missing values.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageRequest {
+    /// Page number (1-based).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<usize>,
+
+    /// Number of items per page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub per_page: Option<usize>,
+}
+
+impl PageRequest {
+    /// Requests page `page` of `per_page` items.
+    pub fn new(page: usize, per_page: usize) -> Self {
+        Self {
+            page: Some(page),
+            per_page: Some(per_page),
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use chrono::{DateTime, FixedOffset, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Publication state of a [`Post`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PostStatus {
+    /// The post is visible in listings and feeds.
+    #[default]
+    Published,
+
+    /// The post waits for its `publish_at` time and is hidden from listings and feeds until then.
+    Scheduled,
+}
+
+/// Represents a blog post returned by the `/posts` API.
+///
+/// This structure includes a unique identifier, metadata, and content.
+/// It is used both internally and in JSON responses.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Post {
+    /// Unique identifier for the post (e.g., UUID).
+    pub id: String,
+
+    /// Name of the person who authored the post.
+    pub author: String,
+
+    /// Time the post was created or last updated, with the offset it was written with.
+    #[serde(
+        serialize_with = "crate::date::serialize",
+        deserialize_with = "crate::date::deserialize"
+    )]
+    pub date: DateTime<FixedOffset>,
+
+    /// Main content body of the post.
+    pub content: String,
+
+    /// Publication state of the post.
+    #[serde(default)]
+    pub status: PostStatus,
+
+    /// Time at which a scheduled post gets published.
+    #[serde(
+        default,
+        serialize_with = "crate::date::serialize_utc",
+        deserialize_with = "crate::date::deserialize_utc",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub publish_at: Option<DateTime<Utc>>,
+}
+
+impl Post {
+    /// Names of the fields of a post in JSON, e.g. to pick some with `?fields=`.
+    pub const FIELDS: &[&str] = &["id", "author", "date", "content", "status", "publish_at"];
+
+    /// Builds a post with the given ID from the input.
+    ///
+    /// The post is [`PostStatus::Scheduled`] if the input asks for publication later than `now`,
+    /// and [`PostStatus::Published`] otherwise.
+    pub fn new(id: String, input: PostInput, now: DateTime<Utc>) -> Self {
+        let scheduled = input.publish_at.is_some_and(|at| at > now);
+        Self {
+            id,
+            author: input.author,
+            date: input.date,
+            content: input.content,
+            status: if scheduled {
+                PostStatus::Scheduled
+            } else {
+                PostStatus::Published
+            },
+            publish_at: input.publish_at,
+        }
+    }
+
+    /// Returns the date of the post in UTC (see [`date_key`]).
+    pub fn utc_date(&self) -> String {
+        date_key(self.date.with_timezone(&Utc))
+    }
+
+    /// Returns `true` if the post is visible at `now`.
+    ///
+    /// A scheduled post whose publication time has passed is treated as published even before the
+    /// scheduler gets to flip its status, so visibility doesn't depend on the scheduler's interval.
+    pub fn is_published(&self, now: DateTime<Utc>) -> bool {
+        match self.status {
+            PostStatus::Published => true,
+            PostStatus::Scheduled => self.publish_at.is_none_or(|at| at <= now),
+        }
+    }
+}
+
+/// Formats `date` with microseconds, e.g. `2024-01-01T10:00:00.000000Z`, so dates sort
+/// chronologically as strings and a prefix selects a period, e.g. `2024-01`. Used as a key of
+/// ordered indexes and by the `?date=` filter of `GET /posts`.
+pub fn date_key(date: DateTime<Utc>) -> String {
+    date.format("%Y-%m-%dT%H:%M:%S%.6fZ").to_string()
+}
+
+/// Input structure used to create or update a blog post via API requests.
+///
+/// This struct excludes the `id` field, which is generated by the server.
+/// It is used in `POST /posts` and `PUT /posts/{id}` requests.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PostInput {
+    /// Name of the post's author.
+    pub author: String,
+
+    /// Time of the post (typically the authored time), as an RFC 3339 date-time with an offset,
+    /// which is kept (see [`date`](crate::date)).
+    #[serde(
+        serialize_with = "crate::date::serialize",
+        deserialize_with = "crate::date::deserialize"
+    )]
+    pub date: DateTime<FixedOffset>,
+
+    /// Content to be stored in the post.
+    pub content: String,
+
+    /// Optional publication time. A post with `publish_at` in the future is scheduled and stays
+    /// hidden from listings until then.
+    #[serde(
+        default,
+        serialize_with = "crate::date::serialize_utc",
+        deserialize_with = "crate::date::deserialize_utc",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub publish_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,161 @@
+//! `Arbitrary` strategies of the models, for property-based tests (feature `proptest`).
+//!
+//! Generated inputs are deliberately hostile: texts mix scripts, emoji, control characters and
+//! very long words, and dates include boundary values, so the round-trips through a backend
+//! exercise encoding, escaping and date handling.
+
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+use proptest::{prelude::*, sample, string};
+use uuid::Uuid;
+
+use crate::{
+    date,
+    posts::{Post, PostInput, PostStatus},
+    users::{User, UserInput},
+};
+
+/// Builds a strategy from a regex which is known to be valid.
+fn regex(pattern: &str) -> BoxedStrategy<String> {
+    string::string_regex(pattern)
+        .expect("Regex is valid")
+        .boxed()
+}
+
+/// Generates free-form user text: a mix of ASCII words, emoji, CJK, right-to-left scripts,
+/// combining marks, control characters and occasional very long single words.
+///
+/// The text is built from `min..=max` fragments, so its length in characters varies widely.
+/// Plain `[a-zA-Z0-9]` input never exercises UTF-8 handling, escaping or byte/char length
+/// confusion; this strategy does.
+pub fn text(min: usize, max: usize) -> BoxedStrategy<String> {
+    let fragment = prop_oneof![
+        8 => regex("[a-zA-Z0-9]{1,12}"),
+        4 => Just(" ".to_owned()),
+        2 => regex("[\\x{1F300}-\\x{1F5FF}\\x{1F600}-\\x{1F64F}\\x{1F680}-\\x{1F6FF}]{1,4}"),
+        2 => regex("[\\x{4E00}-\\x{9FFF}\\x{3040}-\\x{30FF}\\x{AC00}-\\x{D7A3}]{1,12}"),
+        2 => regex("[\\x{05D0}-\\x{05EA}\\x{0620}-\\x{064A}]{1,12}"),
+        1 => regex("[a-z][\\x{0300}-\\x{036F}]{1,3}"),
+        1 => regex("[\\x{0000}-\\x{001F}\\x{007F}\\x{200B}-\\x{200F}\\x{FEFF}]{1,3}"),
+        1 => regex("[a-zA-Z]{256,1024}"),
+    ];
+    proptest::collection::vec(fragment, min..=max)
+        .prop_map(|fragments| fragments.concat())
+        .boxed()
+}
+
+/// Generates a date: mostly the current time at an offset between -14:00 and +14:00 (in quarter
+/// hours), sometimes a boundary value in UTC (epoch, pre-epoch, leap day, 2038 overflow of 32-bit
+/// timestamps, first and last representable 4-digit years), truncated to the date precision.
+pub fn date() -> BoxedStrategy<DateTime<FixedOffset>> {
+    let boundaries = vec![
+        DateTime::UNIX_EPOCH,
+        Utc.with_ymd_and_hms(1969, 12, 31, 23, 59, 59).unwrap(),
+        Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap(),
+        Utc.with_ymd_and_hms(2024, 2, 29, 12, 0, 0).unwrap(),
+        Utc.with_ymd_and_hms(2038, 1, 19, 3, 14, 8).unwrap(),
+        Utc.with_ymd_and_hms(1, 1, 1, 0, 0, 0).unwrap(),
+        Utc.with_ymd_and_hms(9999, 12, 31, 23, 59, 59).unwrap()
+            + chrono::Duration::microseconds(999_999),
+    ];
+    prop_oneof![
+        4 => (-56..=56).prop_map(|quarters| {
+            let offset = FixedOffset::east_opt(quarters * 15 * 60).expect("Offset is within a day");
+            Utc::now().with_timezone(&offset)
+        }),
+        1 => sample::select(boundaries).prop_map(|date| date.fixed_offset()),
+    ]
+    .prop_map(date::normalize)
+    .boxed()
+}
+
+/// Implements `Arbitrary` for [`PostInput`] to enable property-based testing using `proptest`.
+///
+/// This strategy generates randomized `PostInput` values that simulate realistic user input for
+/// creating or updating blog posts. The generated data includes:
+///
+/// - `author`: A short unicode string (see [`text`]), from 1 to 4 fragments.
+/// - `content`: A longer unicode string, from 20 to 200 fragments, which may include emoji, CJK,
+///   right-to-left scripts, control characters and very long single words.
+/// - `date`: Mostly the current time at some offset, sometimes a boundary date (see [`date`]).
+impl Arbitrary for PostInput {
+    type Parameters = ();
+
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (text(1, 4), text(20, 200), date())
+            .prop_map(|(author, content, date)| PostInput {
+                author,
+                content,
+                date,
+                publish_at: None,
+            })
+            .boxed()
+    }
+}
+
+/// Implements `Arbitrary` for [`Post`] to enable property-based testing using `proptest`.
+///
+/// This strategy wraps a generated [`PostInput`] and adds a randomly generated UUID (`v4`) as the `id`
+/// field. The resulting `Post` represents a realistic, complete blog post as it might exist in the system.
+///
+/// This implementation allows testing parts of the application that work with fully constructed posts,
+/// rather than just inputs.
+///
+/// # Note
+/// The `date` is set to the current UTC time rather than derived from the original input, which may
+/// slightly differ from real-life update flows where `date` may be preserved.
+impl Arbitrary for Post {
+    type Parameters = ();
+
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        any::<PostInput>()
+            .prop_map(|inputs| Post {
+                id: Uuid::new_v4().to_string(),
+                author: inputs.author,
+                content: inputs.content,
+                date: Utc::now().fixed_offset(),
+                status: PostStatus::Published,
+                publish_at: None,
+            })
+            .boxed()
+    }
+}
+
+/// Generates users with a plain ASCII email (so it stays a valid address) and a unicode nickname.
+impl Arbitrary for UserInput {
+    type Parameters = ();
+
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (
+            string::string_regex("[a-zA-Z0-9]{5,20}").expect("Author is generated"),
+            string::string_regex("[a-zA-Z0-9]{5,20}").expect("Author is generated"),
+            text(1, 4),
+        )
+            .prop_map(|(email_name, email_host, nickname)| UserInput {
+                email: format!("{email_name}@{email_host}.com"),
+                nickname,
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for User {
+    type Parameters = ();
+
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        any::<UserInput>()
+            .prop_map(|inputs| User {
+                id: Uuid::new_v4().to_string(),
+                email: inputs.email,
+                nickname: inputs.nickname,
+            })
+            .boxed()
+    }
+}
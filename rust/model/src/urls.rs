@@ -0,0 +1,159 @@
+//! Paths of the API endpoints, so the server's scopes and `Location` headers, the client and the
+//! tests build them in one place.
+//!
+//! Paths start with `/` and are relative to the server's base URL. Every segment taken from an
+//! argument (an ID, a nickname) is percent-encoded, so e.g. a nickname with a space or a `/` stays
+//! one segment.
+
+use std::fmt::Write;
+
+/// Returns `scope` followed by the percent-encoded `segments`.
+fn path(scope: &str, segments: &[&str]) -> String {
+    let mut path = scope.to_owned();
+    for segment in segments {
+        path.push('/');
+        encode(&mut path, segment);
+    }
+    path
+}
+
+/// Appends `segment` to `path`, percent-encoding everything but the unreserved characters of
+/// RFC 3986 (letters, digits, `-`, `.`, `_` and `~`).
+fn encode(path: &mut String, segment: &str) {
+    for byte in segment.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            path.push(char::from(byte));
+        } else {
+            write!(path, "%{byte:02X}").expect("Writing to a string doesn't fail");
+        }
+    }
+}
+
+/// Endpoints of posts.
+pub mod posts {
+    use super::path;
+
+    /// Scope of the post endpoints.
+    pub const SCOPE: &str = "/posts";
+
+    /// `GET /posts` and `POST /posts`.
+    pub fn list() -> String {
+        SCOPE.to_owned()
+    }
+
+    /// `GET`, `PUT` and `DELETE /posts/{id}`; the `Location` of a created post.
+    pub fn by_id(id: &str) -> String {
+        path(SCOPE, &[id])
+    }
+}
+
+/// Endpoints of users.
+pub mod users {
+    use super::path;
+
+    /// Scope of the user endpoints.
+    pub const SCOPE: &str = "/users";
+
+    /// `GET /users` and `POST /users`.
+    pub fn list() -> String {
+        SCOPE.to_owned()
+    }
+
+    /// `GET`, `PUT` and `DELETE /users/{id}`; the `Location` of a created user.
+    pub fn by_id(id: &str) -> String {
+        path(SCOPE, &[id])
+    }
+
+    /// `GET` and `PUT /users/{id}/avatar`.
+    pub fn avatar(id: &str) -> String {
+        path(SCOPE, &[id, "avatar"])
+    }
+
+    /// `GET /users/{id}/following`: the authors the user follows.
+    pub fn following(id: &str) -> String {
+        path(SCOPE, &[id, "following"])
+    }
+
+    /// `PUT` and `DELETE /users/{id}/following/{author}`.
+    pub fn follow(id: &str, author: &str) -> String {
+        path(SCOPE, &[id, "following", author])
+    }
+}
+
+/// Endpoints of the feed.
+pub mod feed {
+    /// Scope of the feed endpoints.
+    pub const SCOPE: &str = "/feed";
+
+    /// `GET /feed`: the posts of the authors the authenticated user follows.
+    pub fn list() -> String {
+        SCOPE.to_owned()
+    }
+}
+
+/// Endpoints of the authentication API.
+pub mod auth {
+    use super::path;
+
+    /// Scope of the authentication endpoints.
+    pub const SCOPE: &str = "/auth";
+
+    /// `POST /auth/introspect`.
+    pub fn introspect() -> String {
+        path(SCOPE, &["introspect"])
+    }
+}
+
+/// Endpoints of the administration API.
+pub mod admin {
+    use super::path;
+
+    /// Scope of the administration endpoints.
+    pub const SCOPE: &str = "/admin";
+
+    /// `GET /admin/posts/export`: every post, as a dataset.
+    pub fn export_posts() -> String {
+        path(SCOPE, &["posts", "export"])
+    }
+
+    /// `GET /admin/changes`: the change stream of posts, as server-sent events.
+    pub fn changes() -> String {
+        path(SCOPE, &["changes"])
+    }
+
+    /// `GET /admin/users`: every user with the state of their account.
+    pub fn users() -> String {
+        path(SCOPE, &["users"])
+    }
+
+    /// `POST /admin/users/{id}/disable`.
+    pub fn disable_user(id: &str) -> String {
+        path(SCOPE, &["users", id, "disable"])
+    }
+
+    /// `POST /admin/users/{id}/enable`.
+    pub fn enable_user(id: &str) -> String {
+        path(SCOPE, &["users", id, "enable"])
+    }
+
+    /// `POST /admin/users/{id}/logout`: invalidates the tokens of the user.
+    pub fn logout_user(id: &str) -> String {
+        path(SCOPE, &["users", id, "logout"])
+    }
+
+    /// `GET` or `POST /admin/maintenance`: the maintenance mode.
+    pub fn maintenance() -> String {
+        path(SCOPE, &["maintenance"])
+    }
+}
+
+/// Endpoints of the server metrics.
+pub mod metrics {
+    /// Scope of the metrics endpoints.
+    pub const SCOPE: &str = "/metrics";
+
+    /// `GET /metrics`.
+    pub fn get() -> String {
+        SCOPE.to_owned()
+    }
+}
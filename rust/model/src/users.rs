@@ -2,8 +2,9 @@ use serde::{Deserialize, Serialize};
 
 /// Represents a user entity returned by the `/users` API.
 ///
-/// This structure is used both internally and in API responses.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// This structure is used both internally and in API responses. With the dummy auth of the
+/// server, the ID of a user is also their bearer token.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct User {
     /// Unique identifier for the user (e.g., UUID).
     pub id: String,
@@ -15,11 +16,16 @@ pub struct User {
     pub email: String,
 }
 
+impl User {
+    /// Names of the fields of a user in JSON, e.g. to pick some with `?fields=`.
+    pub const FIELDS: &[&str] = &["id", "nickname", "email"];
+}
+
 /// Input structure used for creating a new user via API requests.
 ///
 /// Unlike [`User`], this struct does not include an `id` field,
 /// as the ID is generated by the server upon creation.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct UserInput {
     /// Display nickname to be associated with the new user.
     pub nickname: String,
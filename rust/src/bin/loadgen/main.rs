@@ -0,0 +1,204 @@
+//! Open-loop load generator for the PerCom API.
+//!
+//! Unlike the proptest suite, which sends requests one after another, the load generator issues
+//! requests on a fixed cadence regardless of how fast the server answers. Latency is measured from
+//! the moment a request was *supposed* to be sent, so a stalled server is charged for every request
+//! it delayed (no coordinated omission).
+//!
+//! # Usage
+//! ```text
+//! cargo run --release --bin loadgen -- scenarios/default.json [--pid <server pid>]
+//!     [--target <url>] [--timeseries <path.csv>] [--json <path.json>]
+//!     [--transport http1|http2|http3] [--run-id <id>]
+//! ```
+//!
+//! With `--pid`, CPU and memory usage of the (local) server process are sampled during the run and
+//! included in the report. `--target` and `--timeseries` override the respective scenario fields,
+//! so the same scenario can be run against several servers (see the `orchestrator` binary).
+//! `--json` additionally writes the report in a machine-readable form, and `--transport` overrides
+//! the HTTP version of the requests (see [`scenario::Transport`]). `--run-id` sends the given ID
+//! with every request (`X-Benchmark-Run-Id`), so the server keeps the stats of this run apart from
+//! those of other runs against it.
+//!
+//! The exit code is `2` if the run completed but violated an objective of the scenario (see
+//! [`slo::Slo`]), and `1` if it failed.
+//!
+//! See [`scenario::Scenario`] for the scenario file format.
+
+mod monitor;
+mod ops;
+mod profile;
+mod report;
+mod retry;
+mod scenario;
+mod scheduler;
+mod session;
+mod slo;
+mod timeseries;
+
+use std::{env, process::ExitCode, sync::Arc, time::Instant};
+
+use crate::{
+    monitor::Monitor,
+    ops::Target,
+    report::Report,
+    scenario::{Scenario, Transport},
+};
+
+/// Command line usage.
+const USAGE: &str = "Usage: loadgen <scenario.json> [--pid <server pid>] [--target <url>] [--timeseries <path.csv>] [--json <path.json>] [--transport http1|http2|http3] [--run-id <id>]";
+
+/// Parsed command line.
+struct Args {
+    /// Path of the scenario file.
+    scenario: String,
+
+    /// PID of the server process to sample.
+    pid: Option<u32>,
+
+    /// Base URL of the server, overriding the scenario's `target`.
+    target: Option<String>,
+
+    /// Path of the time series CSV, overriding the scenario's `timeseries`.
+    timeseries: Option<String>,
+
+    /// Path of the JSON report.
+    json: Option<String>,
+
+    /// HTTP version of the requests, overriding the scenario's `transport`.
+    transport: Option<Transport>,
+
+    /// Benchmark run ID sent with every request.
+    run_id: Option<String>,
+}
+
+impl Args {
+    fn parse() -> Option<Self> {
+        let mut args = env::args().skip(1);
+        let mut scenario = None;
+        let mut pid = None;
+        let mut target = None;
+        let mut timeseries = None;
+        let mut json = None;
+        let mut transport = None;
+        let mut run_id = None;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--pid" => pid = Some(args.next()?.parse().ok()?),
+                "--target" => target = Some(args.next()?),
+                "--timeseries" => timeseries = Some(args.next()?),
+                "--json" => json = Some(args.next()?),
+                "--transport" => transport = Some(Transport::from_name(&args.next()?)?),
+                "--run-id" => run_id = Some(args.next()?),
+                _ if scenario.is_none() => scenario = Some(arg),
+                _ => return None,
+            }
+        }
+        Some(Self {
+            scenario: scenario?,
+            pid,
+            target,
+            timeseries,
+            json,
+            transport,
+            run_id,
+        })
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let Some(Args {
+        scenario: path,
+        pid,
+        target,
+        timeseries,
+        json,
+        transport,
+        run_id,
+    }) = Args::parse()
+    else {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    };
+    let mut scenario = match Scenario::load(&path) {
+        Ok(scenario) => scenario,
+        Err(err) => {
+            eprintln!("Fail to load scenario {path}: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Some(target) = target {
+        scenario.target = target;
+    }
+    if timeseries.is_some() {
+        scenario.timeseries = timeseries;
+    }
+    if let Some(transport) = transport {
+        scenario.transport = transport;
+    }
+    if let Some(run_id) = run_id {
+        scenario
+            .headers
+            .insert("X-Benchmark-Run-Id".to_owned(), run_id);
+    }
+    let target = match Target::new(&scenario) {
+        Ok(target) => Arc::new(target),
+        Err(err) => {
+            eprintln!("Fail to set up requests of scenario {path}: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if scenario.existing_posts {
+        match target.load_existing().await {
+            Ok(count) => println!("Using {count} existing posts"),
+            Err(err) => {
+                eprintln!("Fail to load existing posts: {err}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    if let Err(err) = target.seed(scenario.seed_posts).await {
+        eprintln!("Fail to seed posts: {err}");
+        return ExitCode::FAILURE;
+    }
+    let start = Instant::now();
+    let monitor = match pid.map(|pid| Monitor::start(pid, start)).transpose() {
+        Ok(monitor) => monitor,
+        Err(err) => {
+            eprintln!("Fail to sample the server process: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let samples = scheduler::run(&scenario, target, start).await;
+    let resources = match monitor {
+        Some(monitor) => match monitor.stop().await {
+            Ok(resources) => resources,
+            Err(err) => {
+                eprintln!("Fail to sample the server process: {err}");
+                Vec::new()
+            }
+        },
+        None => Vec::new(),
+    };
+    let report = Report::new(&samples, &resources, &scenario);
+    report.print();
+    if let Some(path) = json.as_deref()
+        && let Err(err) = report.write_json(path)
+    {
+        eprintln!("Fail to write JSON report to {path}: {err}");
+        return ExitCode::FAILURE;
+    }
+    if let Some(path) = scenario.timeseries.as_deref() {
+        if let Err(err) = timeseries::write(path, &samples, &resources) {
+            eprintln!("Fail to write time series to {path}: {err}");
+            return ExitCode::FAILURE;
+        }
+        println!("Time series written to {path}");
+    }
+    if report.slos_met() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(slo::VIOLATED_EXIT_CODE)
+    }
+}
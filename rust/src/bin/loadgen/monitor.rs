@@ -0,0 +1,95 @@
+use std::{
+    fs, io,
+    time::{Duration, Instant},
+};
+use tokio::{sync::oneshot, task::JoinHandle, time};
+
+/// Interval between two samples of the server process.
+const INTERVAL: Duration = Duration::from_secs(1);
+
+/// Clock ticks per second used by `/proc/<pid>/stat` (`USER_HZ`), which is 100 on Linux.
+const USER_HZ: f64 = 100.0;
+
+/// Resource usage of the server process at a point of the run.
+#[derive(Debug, Clone, Copy)]
+pub struct ResourceSample {
+    /// Offset from the start of the run.
+    pub offset: Duration,
+
+    /// CPU usage since the previous sample, in percent of one core (may exceed 100).
+    pub cpu: f64,
+
+    /// Resident set size, in bytes.
+    pub rss: u64,
+}
+
+/// Samples CPU and memory usage of a local process from `/proc` (Linux only).
+pub struct Monitor {
+    stop: oneshot::Sender<()>,
+    task: JoinHandle<io::Result<Vec<ResourceSample>>>,
+}
+
+impl Monitor {
+    /// Starts sampling process `pid` every second; offsets are relative to `start`.
+    ///
+    /// Fails right away if the process can't be read.
+    pub fn start(pid: u32, start: Instant) -> io::Result<Self> {
+        let mut prev = (Instant::now(), cpu_ticks(pid)?);
+        let (stop, mut stopped) = oneshot::channel();
+        let task = tokio::spawn(async move {
+            let mut samples = Vec::new();
+            let mut ticks = time::interval(INTERVAL);
+            ticks.tick().await;
+            loop {
+                tokio::select! {
+                    _ = &mut stopped => return Ok(samples),
+                    _ = ticks.tick() => {}
+                }
+                let now = (Instant::now(), cpu_ticks(pid)?);
+                let elapsed = (now.0 - prev.0).as_secs_f64();
+                samples.push(ResourceSample {
+                    offset: now.0.saturating_duration_since(start),
+                    cpu: (now.1 - prev.1) as f64 / USER_HZ / elapsed * 100.0,
+                    rss: rss(pid)?,
+                });
+                prev = now;
+            }
+        });
+        Ok(Self { stop, task })
+    }
+
+    /// Stops sampling and returns the collected samples.
+    pub async fn stop(self) -> io::Result<Vec<ResourceSample>> {
+        // The task may have already ended with an error; the result below reports it
+        let _ = self.stop.send(());
+        self.task.await.map_err(io::Error::other)?
+    }
+}
+
+/// Returns the CPU time (user + system) consumed by the process, in clock ticks.
+fn cpu_ticks(pid: u32) -> io::Result<u64> {
+    let stat = fs::read_to_string(format!("/proc/{pid}/stat"))?;
+    // The command name (2nd field) may contain spaces; fields are counted after its closing paren
+    let fields: Vec<&str> = stat
+        .rsplit_once(')')
+        .map(|(_, rest)| rest.split_whitespace().collect())
+        .unwrap_or_default();
+    // `utime` and `stime` are the 14th and 15th fields, i.e. the 12th and 13th after the name
+    let field = |idx: usize| -> io::Result<u64> {
+        fields
+            .get(idx)
+            .and_then(|v| v.parse().ok())
+            .ok_or_else(|| io::Error::other(format!("malformed /proc/{pid}/stat")))
+    };
+    Ok(field(11)? + field(12)?)
+}
+
+/// Returns the resident set size of the process, in bytes.
+fn rss(pid: u32) -> io::Result<u64> {
+    fs::read_to_string(format!("/proc/{pid}/status"))?
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))
+        .and_then(|v| v.trim().trim_end_matches("kB").trim().parse::<u64>().ok())
+        .map(|kb| kb * 1024)
+        .ok_or_else(|| io::Error::other(format!("no VmRSS in /proc/{pid}/status")))
+}
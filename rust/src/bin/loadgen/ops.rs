@@ -0,0 +1,436 @@
+use chrono::Utc;
+use flate2::write::GzEncoder;
+use percom_client::{Client, model::urls};
+use prost::Message;
+use rand::Rng;
+use rand_distr::{Distribution, Zipf};
+use reqwest::{
+    Method, RequestBuilder, StatusCode, Version,
+    header::{
+        ACCEPT, AUTHORIZATION, CONTENT_ENCODING, CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue,
+    },
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::Write,
+    sync::Mutex,
+};
+use tokio::time;
+
+use crate::{
+    retry::{self, Retry},
+    scenario::{Access, Auth, Compression, Encoding, Operation, Scenario, Transport},
+};
+
+/// Media type of protobuf bodies of the posts endpoints.
+const PROTOBUF: &str = "application/x-protobuf";
+
+/// Result of a single request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The server answered with a success status.
+    Ok,
+
+    /// The server answered with an error status.
+    Failed(StatusCode),
+
+    /// The server refused the request to protect itself (`429` or `503`), retries included.
+    Shed(StatusCode),
+
+    /// The request didn't get an answer (connection error, timeout).
+    NetworkError,
+
+    /// The operation needs an existing post, but none is known; nothing was sent.
+    Skipped,
+}
+
+/// Result of [`Target::call_as`].
+pub struct Call {
+    pub outcome: Outcome,
+
+    /// Number of times the request was retried after being shed.
+    pub retries: u32,
+
+    /// ID of the user registered by [`Operation::Login`].
+    pub user: Option<String>,
+}
+
+impl Call {
+    fn new(outcome: Outcome, retries: u32) -> Self {
+        Self {
+            outcome,
+            retries,
+            user: None,
+        }
+    }
+}
+
+/// Only the part of a created post or user the load generator needs, which is also the first field
+/// of the protobuf `Post` message (`proto/posts.proto`); decoding the whole response would be
+/// measured as part of the latency.
+#[derive(Clone, PartialEq, Deserialize, prost::Message)]
+struct Created {
+    #[prost(string, tag = "1")]
+    id: String,
+}
+
+/// Protobuf `PostInput` message (`proto/posts.proto`).
+#[derive(Clone, PartialEq, prost::Message)]
+struct PostInput {
+    #[prost(string, tag = "1")]
+    author: String,
+    #[prost(message, optional, tag = "2")]
+    date: Option<prost_types::Timestamp>,
+    #[prost(string, tag = "3")]
+    content: String,
+}
+
+/// The server under test, together with the IDs of the posts known to exist on it.
+pub struct Target {
+    /// Client of the server with the scenario's transport, credentials, signing and headers.
+    api: Client,
+
+    /// Whether a session's login token replaces the credentials.
+    session_auth: bool,
+
+    operation_headers: HashMap<Operation, HeaderMap>,
+    access: Access,
+    retry: Option<Retry>,
+    encoding: Encoding,
+    compression: Option<Compression>,
+
+    /// Known posts, in the order they became known (which is their rank for [`Access::Zipf`]).
+    ids: Mutex<Vec<String>>,
+}
+
+impl Target {
+    /// Fails if the scenario's credentials or headers aren't valid HTTP headers, or if its
+    /// transport isn't available.
+    pub fn new(scenario: &Scenario) -> Result<Self, String> {
+        let auth = match &scenario.auth {
+            Auth::None | Auth::Session => percom_client::Auth::None,
+            Auth::Bearer { token } => percom_client::Auth::Header(AUTHORIZATION, bearer(token)?),
+            Auth::ApiKey { header, key } => {
+                let (name, value) = header_pair(header, key)?;
+                percom_client::Auth::Header(name, value)
+            }
+        };
+        let (client, version) = client(scenario.transport, &scenario.target)?;
+        // Latency is measured however long requests take; shed requests are retried below, so
+        // retries are counted
+        let mut api = Client::builder(&scenario.target)
+            .http(client)
+            .version(version)
+            .auth(auth)
+            .no_timeout();
+        if let Some(signing) = &scenario.signing {
+            api = api.signing(&signing.client, &signing.secret);
+        }
+        for (name, value) in &scenario.headers {
+            api = api.header(name, value);
+        }
+        Ok(Self {
+            api: api.build().map_err(|err| err.to_string())?,
+            session_auth: scenario.auth != Auth::None,
+            operation_headers: scenario
+                .operation_headers
+                .iter()
+                .map(|(op, headers)| Ok((*op, header_map(headers)?)))
+                .collect::<Result<_, String>>()?,
+            access: scenario.access,
+            retry: scenario.retry.clone(),
+            encoding: scenario.encoding,
+            compression: scenario.compression,
+            ids: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Starts a request of `op` to `path` with the credentials (the session's `token` if set) and
+    /// the configured headers.
+    fn request(
+        &self,
+        op: Operation,
+        method: Method,
+        path: &str,
+        token: Option<&str>,
+    ) -> RequestBuilder {
+        let mut request = match token {
+            _ if !requires_auth(op) => self.api.anonymous(method, path),
+            Some(token) if self.session_auth => self.api.anonymous(method, path).bearer_auth(token),
+            _ => self.api.request(method, path),
+        };
+        if let Some(headers) = self.operation_headers.get(&op) {
+            request = request.headers(headers.clone());
+        }
+        request
+    }
+
+    /// Creates `count` posts before the measured run.
+    pub async fn seed(&self, count: usize) -> Result<(), String> {
+        for _ in 0..count {
+            match self.call(Operation::CreatePost).await {
+                Outcome::Ok => {}
+                outcome => return Err(format!("{outcome:?}")),
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the posts already stored on the server to the known posts.
+    pub async fn load_existing(&self) -> Result<usize, String> {
+        let posts = self
+            .api
+            .list_posts()
+            .await
+            .map_err(|err| format!("GET /posts: {err}"))?;
+        let count = posts.len();
+        self.ids
+            .lock()
+            .unwrap()
+            .extend(posts.into_iter().map(|post| post.id));
+        Ok(count)
+    }
+
+    /// Performs a single operation with the scenario's credentials.
+    pub async fn call(&self, op: Operation) -> Outcome {
+        self.call_as(op, None).await.outcome
+    }
+
+    /// Performs a single operation with the bearer `token` of a logged in session, or the
+    /// scenario's credentials if `None`.
+    ///
+    /// Shed requests are retried as configured by the scenario's [`Retry`].
+    pub async fn call_as(&self, op: Operation, token: Option<&str>) -> Call {
+        let request = |method, path: &str| self.request(op, method, path, token);
+        let mut request = match op {
+            Operation::CreatePost => self.payload(request(Method::POST, &urls::posts::list())),
+            Operation::ListPosts => request(Method::GET, &urls::posts::list()),
+            Operation::Login => {
+                let name = uuid::Uuid::new_v4();
+                request(Method::POST, &urls::users::list()).json(&json!({
+                    "nickname": format!("loadgen-{name}"),
+                    "email": format!("{name}@loadgen.local"),
+                }))
+            }
+            Operation::GetFeed => request(Method::GET, &urls::feed::list()),
+            Operation::GetPost | Operation::UpdatePost | Operation::DeletePost => {
+                let Some(id) = self.pick(op == Operation::DeletePost) else {
+                    return Call::new(Outcome::Skipped, 0);
+                };
+                let path = urls::posts::by_id(&id);
+                match op {
+                    Operation::GetPost => request(Method::GET, &path),
+                    Operation::UpdatePost => self.payload(request(Method::PUT, &path)),
+                    _ => request(Method::DELETE, &path),
+                }
+            }
+        };
+        if self.encoding == Encoding::Protobuf && op != Operation::Login && op != Operation::GetFeed
+        {
+            request = request.header(ACCEPT, PROTOBUF);
+        }
+        // Retries resend the same signature, which stays valid within the server's skew
+        let request = match self.api.sign(request) {
+            Ok(request) => request,
+            Err(_) => return Call::new(Outcome::NetworkError, 0),
+        };
+        let mut retries = 0;
+        let response = loop {
+            // Bodies are in memory, so requests can always be cloned
+            let attempt = request.try_clone().expect("Request is cloneable");
+            let response = match attempt.send().await {
+                Ok(response) => response,
+                Err(_) => return Call::new(Outcome::NetworkError, retries),
+            };
+            let status = response.status();
+            if !retry::is_shed(status) {
+                break response;
+            }
+            match self.retry.as_ref() {
+                Some(retry) if retries < retry.max_retries => {
+                    let delay = retry.delay(retries, &response);
+                    // Drain the body, so the connection can be reused
+                    let _ = response.bytes().await;
+                    time::sleep(delay).await;
+                    retries += 1;
+                }
+                _ => return Call::new(Outcome::Shed(status), retries),
+            }
+        };
+        let status = response.status();
+        if !status.is_success() {
+            return Call::new(Outcome::Failed(status), retries);
+        }
+        // Read the body in any case, so its transfer is part of the measured latency
+        let body = match response.bytes().await {
+            Ok(body) => body,
+            Err(_) => return Call::new(Outcome::NetworkError, retries),
+        };
+        let created = || match self.encoding {
+            Encoding::Protobuf if op == Operation::CreatePost => {
+                Created::decode(body.clone()).ok().map(|created| created.id)
+            }
+            _ => serde_json::from_slice::<Created>(&body)
+                .ok()
+                .map(|created| created.id),
+        };
+        let mut call = Call::new(Outcome::Ok, retries);
+        match op {
+            Operation::CreatePost => {
+                if let Some(id) = created() {
+                    self.ids.lock().unwrap().push(id);
+                }
+            }
+            Operation::Login => call.user = created(),
+            _ => {}
+        }
+        call
+    }
+
+    /// Adds the body of a created or updated post, in the scenario's [`Encoding`].
+    /// The body is compressed if the scenario sets a [`Compression`].
+    fn payload(&self, request: RequestBuilder) -> RequestBuilder {
+        let (author, content, date) = ("loadgen", "Generated by the load generator", Utc::now());
+        let (content_type, body) = match self.encoding {
+            Encoding::Json => (
+                "application/json",
+                json!({
+                    "author": author,
+                    "date": date,
+                    "content": content,
+                })
+                .to_string()
+                .into_bytes(),
+            ),
+            Encoding::Protobuf => (
+                PROTOBUF,
+                PostInput {
+                    author: author.to_owned(),
+                    date: Some(prost_types::Timestamp {
+                        seconds: date.timestamp(),
+                        nanos: date.timestamp_subsec_nanos() as i32,
+                    }),
+                    content: content.to_owned(),
+                }
+                .encode_to_vec(),
+            ),
+        };
+        let request = request.header(CONTENT_TYPE, content_type);
+        match self.compression {
+            Some(compression) => request
+                .header(CONTENT_ENCODING, compression.name())
+                .body(compress(compression, &body)),
+            None => request.body(body),
+        }
+    }
+
+    /// Picks a known post following the [`Access`] pattern; if `remove` is set, the post is
+    /// forgotten, as it's about to be deleted.
+    fn pick(&self, remove: bool) -> Option<String> {
+        let mut ids = self.ids.lock().unwrap();
+        if ids.is_empty() {
+            return None;
+        }
+        match self.access {
+            Access::Uniform => {
+                let idx = rand::rng().random_range(0..ids.len());
+                Some(if remove {
+                    ids.swap_remove(idx)
+                } else {
+                    ids[idx].clone()
+                })
+            }
+            Access::Zipf { exponent } => {
+                // Ranks are 1-based; the exponent was validated with the scenario
+                let zipf = Zipf::new(ids.len() as f64, exponent).ok()?;
+                let idx = (zipf.sample(&mut rand::rng()) as usize - 1).min(ids.len() - 1);
+                // Keep the order, so the ranks of the other posts don't change
+                Some(if remove {
+                    ids.remove(idx)
+                } else {
+                    ids[idx].clone()
+                })
+            }
+        }
+    }
+}
+
+/// Builds the client sending requests to `target` over `transport`, along with the HTTP version of
+/// its requests.
+fn client(transport: Transport, target: &str) -> Result<(reqwest::Client, Version), String> {
+    let builder = reqwest::Client::builder();
+    let (builder, version) = match transport {
+        Transport::Http1 => (builder.http1_only(), Version::HTTP_11),
+        Transport::Http2 => (builder.http2_prior_knowledge(), Version::HTTP_2),
+        Transport::Http3 if !target.starts_with("https://") => {
+            return Err(format!("HTTP/3 requires an https:// target, not {target}"));
+        }
+        // The server's certificate is self-signed (see `RUST_SERVER_H3_ADDR`)
+        #[cfg(feature = "http3")]
+        Transport::Http3 => (
+            builder
+                .use_rustls_tls()
+                .danger_accept_invalid_certs(true)
+                .http3_prior_knowledge(),
+            Version::HTTP_3,
+        ),
+        #[cfg(not(feature = "http3"))]
+        Transport::Http3 => {
+            return Err("HTTP/3 requires a loadgen built with the `http3` feature".to_owned());
+        }
+    };
+    let client = builder
+        .build()
+        .map_err(|err| format!("invalid {transport:?} client: {err}"))?;
+    Ok((client, version))
+}
+
+/// Compresses a request body with the default level of the algorithm.
+fn compress(compression: Compression, body: &[u8]) -> Vec<u8> {
+    match compression {
+        Compression::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder
+                .write_all(body)
+                .and_then(|_| encoder.finish())
+                .expect("Writing to memory doesn't fail")
+        }
+        Compression::Br => {
+            let mut compressed = Vec::new();
+            let mut encoder = brotli::CompressorWriter::new(&mut compressed, 4096, 5, 22);
+            encoder
+                .write_all(body)
+                .expect("Writing to memory doesn't fail");
+            drop(encoder);
+            compressed
+        }
+    }
+}
+
+/// Returns `true` if the server requires authentication for `op`.
+fn requires_auth(op: Operation) -> bool {
+    matches!(
+        op,
+        Operation::CreatePost | Operation::UpdatePost | Operation::DeletePost | Operation::GetFeed
+    )
+}
+
+fn bearer(token: &str) -> Result<HeaderValue, String> {
+    HeaderValue::from_str(&format!("Bearer {token}")).map_err(|err| format!("bearer token: {err}"))
+}
+
+fn header_pair(name: &str, value: &str) -> Result<(HeaderName, HeaderValue), String> {
+    Ok((
+        HeaderName::from_bytes(name.as_bytes()).map_err(|err| format!("header {name}: {err}"))?,
+        HeaderValue::from_str(value).map_err(|err| format!("header {name}: {err}"))?,
+    ))
+}
+
+fn header_map(headers: &BTreeMap<String, String>) -> Result<HeaderMap, String> {
+    headers
+        .iter()
+        .map(|(name, value)| header_pair(name, value))
+        .collect()
+}
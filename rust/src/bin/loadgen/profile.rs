@@ -0,0 +1,183 @@
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Default number of reporting steps of a linear ramp.
+const DEFAULT_RAMP_STEPS: u32 = 10;
+
+/// How the request rate evolves over the run.
+///
+/// Every profile is split into stages, which are reported separately, so the rate at which latency
+/// degrades (the breaking point of the backend) can be read from the report.
+///
+/// # Example
+/// ```json
+/// { "type": "constant", "rate": 200 }
+/// { "type": "ramp", "from": 10, "to": 1000, "steps": 10 }
+/// { "type": "step", "from": 50, "step": 50, "steps": 8 }
+/// { "type": "spike", "base": 100, "peak": 1000, "start_secs": 20, "length_secs": 5 }
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Profile {
+    /// The same rate for the whole run; a single stage.
+    Constant { rate: f64 },
+
+    /// Rate growing linearly from `from` to `to` over the run, reported in `steps` equal stages.
+    Ramp {
+        from: f64,
+        to: f64,
+        #[serde(default = "default_ramp_steps")]
+        steps: u32,
+    },
+
+    /// The run is split into `steps` equal stages; stage `k` (0-based) runs at `from + k * step`.
+    Step { from: f64, step: f64, steps: u32 },
+
+    /// `base` rate, except for `length_secs` starting at `start_secs`, which run at `peak`.
+    /// Reported as three stages: before, during and after the spike.
+    Spike {
+        base: f64,
+        peak: f64,
+        start_secs: f64,
+        length_secs: f64,
+    },
+}
+
+fn default_ramp_steps() -> u32 {
+    DEFAULT_RAMP_STEPS
+}
+
+/// A reporting window of the run.
+#[derive(Debug, Clone)]
+pub struct Stage {
+    /// Offset of the stage from the start of the run.
+    pub start: Duration,
+
+    /// Offset of the end of the stage (exclusive).
+    pub end: Duration,
+
+    /// Target rate of the stage, for the report (e.g. `200` or `100..200`).
+    pub rate: String,
+}
+
+impl Profile {
+    /// Checks that all rates are positive and the stages are well-formed.
+    pub fn validate(&self, duration: Duration) -> Result<(), String> {
+        let rates = match self {
+            Self::Constant { rate } => vec![*rate],
+            Self::Ramp { from, to, steps } => {
+                if *steps == 0 {
+                    return Err("ramp needs at least one step".to_owned());
+                }
+                vec![*from, *to]
+            }
+            Self::Step { from, step, steps } => {
+                if *steps == 0 {
+                    return Err("step profile needs at least one step".to_owned());
+                }
+                vec![*from, from + step * (*steps - 1) as f64]
+            }
+            Self::Spike {
+                base,
+                peak,
+                start_secs,
+                length_secs,
+            } => {
+                if *start_secs < 0.0
+                    || *length_secs <= 0.0
+                    || start_secs + length_secs > duration.as_secs_f64()
+                {
+                    return Err("spike must lie within the run".to_owned());
+                }
+                vec![*base, *peak]
+            }
+        };
+        if rates.iter().all(|rate| *rate > 0.0) {
+            Ok(())
+        } else {
+            Err("rates must be positive".to_owned())
+        }
+    }
+
+    /// Returns the target rate (requests per second) at offset `at` of a run lasting `duration`.
+    pub fn rate(&self, at: Duration, duration: Duration) -> f64 {
+        let progress = at.as_secs_f64() / duration.as_secs_f64().max(f64::EPSILON);
+        match self {
+            Self::Constant { rate } => *rate,
+            Self::Ramp { from, to, .. } => from + (to - from) * progress.min(1.0),
+            Self::Step { from, step, steps } => {
+                let k = ((progress * *steps as f64) as u32).min(steps - 1);
+                from + step * k as f64
+            }
+            Self::Spike {
+                base,
+                peak,
+                start_secs,
+                length_secs,
+            } => {
+                let at = at.as_secs_f64();
+                if at >= *start_secs && at < start_secs + length_secs {
+                    *peak
+                } else {
+                    *base
+                }
+            }
+        }
+    }
+
+    /// Returns the reporting stages of a run lasting `duration`.
+    pub fn stages(&self, duration: Duration) -> Vec<Stage> {
+        let equal = |steps: u32, label: &dyn Fn(u32) -> String| {
+            (0..steps)
+                .map(|k| Stage {
+                    start: duration.mul_f64(k as f64 / steps as f64),
+                    end: duration.mul_f64((k + 1) as f64 / steps as f64),
+                    rate: label(k),
+                })
+                .collect()
+        };
+        match self {
+            Self::Constant { rate } => vec![Stage {
+                start: Duration::ZERO,
+                end: duration,
+                rate: format!("{rate}"),
+            }],
+            Self::Ramp { from, to, steps } => equal(*steps, &|k| {
+                let at = |k: u32| from + (to - from) * k as f64 / *steps as f64;
+                format!("{:.0}..{:.0}", at(k), at(k + 1))
+            }),
+            Self::Step { from, step, steps } => {
+                equal(*steps, &|k| format!("{}", from + step * k as f64))
+            }
+            Self::Spike {
+                base,
+                peak,
+                start_secs,
+                length_secs,
+            } => {
+                let start = Duration::from_secs_f64(*start_secs);
+                let end = Duration::from_secs_f64(start_secs + length_secs);
+                vec![
+                    Stage {
+                        start: Duration::ZERO,
+                        end: start,
+                        rate: format!("{base}"),
+                    },
+                    Stage {
+                        start,
+                        end,
+                        rate: format!("{peak}"),
+                    },
+                    Stage {
+                        start: end,
+                        end: duration,
+                        rate: format!("{base}"),
+                    },
+                ]
+                .into_iter()
+                .filter(|stage| stage.end > stage.start)
+                .collect()
+            }
+        }
+    }
+}
@@ -0,0 +1,466 @@
+use hdrhistogram::Histogram;
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, BufWriter},
+    time::Duration,
+};
+
+use crate::{
+    monitor::ResourceSample,
+    profile::Stage,
+    scenario::{Operation, Scenario},
+    scheduler::Sample,
+    slo::Verdict,
+};
+
+/// Highest trackable latency, in microseconds (one minute).
+const MAX_LATENCY_US: u64 = 60_000_000;
+
+/// Percentiles printed in the report.
+const PERCENTILES: [f64; 4] = [50.0, 90.0, 99.0, 99.9];
+
+/// Creates an empty latency histogram (microseconds, 3 significant digits).
+pub fn histogram() -> Histogram<u64> {
+    Histogram::new_with_max(MAX_LATENCY_US, 3).expect("Histogram is valid")
+}
+
+/// Latency distributions of a set of requests.
+struct Stats {
+    /// Latency from the intended start (corrected for coordinated omission), in microseconds.
+    latency: Histogram<u64>,
+
+    /// Latency from the actual send, in microseconds.
+    service: Histogram<u64>,
+
+    /// Number of requests which failed or got no answer.
+    errors: u64,
+
+    /// Number of requests shed by the server (after retries, if any).
+    shed: u64,
+
+    /// Number of retries of shed requests.
+    retries: u64,
+}
+
+impl Stats {
+    fn new() -> Self {
+        Self {
+            latency: histogram(),
+            service: histogram(),
+            errors: 0,
+            shed: 0,
+            retries: 0,
+        }
+    }
+
+    fn record(&mut self, sample: &Sample) {
+        self.latency
+            .saturating_record(sample.latency().as_micros() as u64);
+        self.service
+            .saturating_record(sample.service_time().as_micros() as u64);
+        if sample.is_shed() {
+            self.shed += 1;
+        } else if !sample.is_ok() {
+            self.errors += 1;
+        }
+        self.retries += u64::from(sample.retries);
+    }
+
+    /// Merges the counts and distributions of `other` into these.
+    fn add(&mut self, other: &Self) {
+        self.latency
+            .add(&other.latency)
+            .expect("Histograms have the same range");
+        self.service
+            .add(&other.service)
+            .expect("Histograms have the same range");
+        self.errors += other.errors;
+        self.shed += other.shed;
+        self.retries += other.retries;
+    }
+
+    /// Formats the percentiles and maximum of `histogram` as table cells.
+    fn cells(histogram: &Histogram<u64>) -> String {
+        PERCENTILES
+            .iter()
+            .map(|p| histogram.value_at_percentile(*p))
+            .chain([histogram.max()])
+            .map(|v| format!("{:>10.2}", ms(v)))
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+}
+
+/// Latency percentiles and maximum, in milliseconds, as written to the JSON report.
+#[derive(Serialize)]
+struct Latency {
+    p50: f64,
+    p90: f64,
+    p99: f64,
+    p999: f64,
+    max: f64,
+}
+
+impl From<&Histogram<u64>> for Latency {
+    fn from(histogram: &Histogram<u64>) -> Self {
+        let at = |p| ms(histogram.value_at_percentile(p));
+        Self {
+            p50: at(50.0),
+            p90: at(90.0),
+            p99: at(99.0),
+            p999: at(99.9),
+            max: ms(histogram.max()),
+        }
+    }
+}
+
+/// Resource usage of the server, as written to the JSON report.
+#[derive(Serialize)]
+struct Resources {
+    cpu_avg: f64,
+    cpu_max: f64,
+    rss_max_mib: f64,
+}
+
+impl Resources {
+    fn new(resources: &[ResourceSample]) -> Option<Self> {
+        if resources.is_empty() {
+            return None;
+        }
+        Some(Self {
+            cpu_avg: resources.iter().map(|r| r.cpu).sum::<f64>() / resources.len() as f64,
+            cpu_max: resources.iter().map(|r| r.cpu).fold(0.0, f64::max),
+            rss_max_mib: mib(resources.iter().map(|r| r.rss).max().unwrap_or_default()),
+        })
+    }
+}
+
+/// Machine-readable form of [`Report`], written with `--json`.
+#[derive(Serialize)]
+struct Summary<'a> {
+    requests: u64,
+    elapsed_secs: f64,
+    throughput: f64,
+    operations: Vec<OperationSummary>,
+    stages: Vec<StageSummary>,
+    resources: Option<Resources>,
+    slos: &'a [Verdict],
+}
+
+#[derive(Serialize)]
+struct OperationSummary {
+    operation: &'static str,
+    count: u64,
+    errors: u64,
+    shed: u64,
+    retries: u64,
+    /// Latency from the intended start.
+    latency: Latency,
+    /// Latency from the actual send.
+    service: Latency,
+}
+
+#[derive(Serialize)]
+struct StageSummary {
+    start_secs: f64,
+    end_secs: f64,
+    target_rps: String,
+    actual_rps: f64,
+    count: u64,
+    errors: u64,
+    shed: u64,
+    latency: Latency,
+    resources: Option<Resources>,
+}
+
+/// Results of a single stage of the load profile.
+struct StageStats {
+    stage: Stage,
+
+    /// Requests scheduled within the stage.
+    stats: Stats,
+
+    /// Number of responses received within the stage (the achieved throughput).
+    completed: u64,
+
+    /// Resource usage of the server sampled within the stage.
+    resources: Vec<ResourceSample>,
+}
+
+impl StageStats {
+    /// Returns the achieved throughput of the stage, in responses per second.
+    fn actual_rps(&self) -> f64 {
+        let length = (self.stage.end - self.stage.start)
+            .as_secs_f64()
+            .max(f64::EPSILON);
+        self.completed as f64 / length
+    }
+}
+
+/// Formats the average and peak CPU usage and the peak RSS of `resources` as table cells.
+fn resource_cells(resources: &[ResourceSample]) -> String {
+    match Resources::new(resources) {
+        Some(Resources {
+            cpu_avg,
+            cpu_max,
+            rss_max_mib,
+        }) => format!("{cpu_avg:>8.1} | {cpu_max:>8.1} | {rss_max_mib:>9.1}"),
+        None => format!("{:>8} | {:>8} | {:>9}", "-", "-", "-"),
+    }
+}
+
+/// Summary of a run: latency percentiles per operation and per stage of the load profile, along with
+/// resource usage of the server if it was sampled.
+pub struct Report {
+    operations: BTreeMap<Operation, Stats>,
+    stages: Vec<StageStats>,
+    resources: Vec<ResourceSample>,
+    elapsed: Duration,
+    requests: u64,
+
+    /// Outcomes of the scenario's objectives.
+    slos: Vec<Verdict>,
+}
+
+impl Report {
+    pub fn new(samples: &[Sample], resources: &[ResourceSample], scenario: &Scenario) -> Self {
+        let mut operations = BTreeMap::new();
+        let mut stages: Vec<StageStats> = scenario
+            .profile
+            .stages(scenario.duration())
+            .into_iter()
+            .map(|stage| StageStats {
+                stage,
+                stats: Stats::new(),
+                completed: 0,
+                resources: Vec::new(),
+            })
+            .collect();
+        let within = |stage: &Stage, offset: Duration| offset >= stage.start && offset < stage.end;
+        for sample in samples {
+            operations
+                .entry(sample.op)
+                .or_insert_with(Stats::new)
+                .record(sample);
+            // Offset of the response from the start of the run
+            let done = sample.offset + (sample.done - sample.intended);
+            for stage in stages.iter_mut() {
+                if within(&stage.stage, sample.offset) {
+                    stage.stats.record(sample);
+                }
+                if within(&stage.stage, done) {
+                    stage.completed += 1;
+                }
+            }
+        }
+        for resource in resources {
+            if let Some(stage) = stages
+                .iter_mut()
+                .find(|stage| within(&stage.stage, resource.offset))
+            {
+                stage.resources.push(*resource);
+            }
+        }
+        let elapsed = match (
+            samples.iter().map(|s| s.intended).min(),
+            samples.iter().map(|s| s.done).max(),
+        ) {
+            (Some(first), Some(last)) => last - first,
+            _ => Duration::ZERO,
+        };
+        let mut all = Stats::new();
+        for stats in operations.values() {
+            all.add(stats);
+        }
+        let slos = scenario
+            .slos
+            .iter()
+            .flat_map(|slo| match slo.operation {
+                Some(op) => match operations.get(&op) {
+                    Some(stats) => slo.check(&stats.latency, stats.errors + stats.shed),
+                    None => slo.check(&histogram(), 0),
+                },
+                None => slo.check(&all.latency, all.errors + all.shed),
+            })
+            .collect();
+        Self {
+            operations,
+            stages,
+            resources: resources.to_vec(),
+            elapsed,
+            requests: samples.len() as u64,
+            slos,
+        }
+    }
+
+    /// Returns `true` if no objective of the scenario was violated.
+    pub fn slos_met(&self) -> bool {
+        self.slos.iter().all(|verdict| verdict.passed)
+    }
+
+    /// Returns the throughput over the whole run, in requests per second.
+    fn throughput(&self) -> f64 {
+        self.requests as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+
+    /// Writes the report as JSON, for further processing (see the `orchestrator` binary).
+    pub fn write_json(&self, path: &str) -> io::Result<()> {
+        let summary = Summary {
+            requests: self.requests,
+            elapsed_secs: self.elapsed.as_secs_f64(),
+            throughput: self.throughput(),
+            operations: self
+                .operations
+                .iter()
+                .map(|(op, stats)| OperationSummary {
+                    operation: op.name(),
+                    count: stats.latency.len(),
+                    errors: stats.errors,
+                    shed: stats.shed,
+                    retries: stats.retries,
+                    latency: (&stats.latency).into(),
+                    service: (&stats.service).into(),
+                })
+                .collect(),
+            stages: self
+                .stages
+                .iter()
+                .map(|stage| StageSummary {
+                    start_secs: stage.stage.start.as_secs_f64(),
+                    end_secs: stage.stage.end.as_secs_f64(),
+                    target_rps: stage.stage.rate.clone(),
+                    actual_rps: stage.actual_rps(),
+                    count: stage.stats.latency.len(),
+                    errors: stage.stats.errors,
+                    shed: stage.stats.shed,
+                    latency: (&stage.stats.latency).into(),
+                    resources: Resources::new(&stage.resources),
+                })
+                .collect(),
+            resources: Resources::new(&self.resources),
+            slos: &self.slos,
+        };
+        serde_json::to_writer_pretty(BufWriter::new(File::create(path)?), &summary)
+            .map_err(io::Error::other)
+    }
+
+    /// Prints the report to stdout.
+    pub fn print(&self) {
+        println!("\n=== Load Report ===\n");
+        println!(
+            "Requests: {}, elapsed: {:.2} s, throughput: {:.1} req/s\n",
+            self.requests,
+            self.elapsed.as_secs_f64(),
+            self.throughput()
+        );
+        println!(
+            "{:<12} | {:<8} | {:>8} | {:>6} | {:>6} | {:>7} | {:>10} | {:>10} | {:>10} | {:>10} | {:>10}",
+            "Operation",
+            "Latency",
+            "Count",
+            "Errors",
+            "Shed",
+            "Retries",
+            "p50 (ms)",
+            "p90 (ms)",
+            "p99 (ms)",
+            "p99.9 (ms)",
+            "max (ms)"
+        );
+        println!("{}", "-".repeat(131));
+        for (op, stats) in self.operations.iter() {
+            for (kind, histogram) in [("intended", &stats.latency), ("service", &stats.service)] {
+                println!(
+                    "{:<12} | {:<8} | {:>8} | {:>6} | {:>6} | {:>7} | {}",
+                    op.name(),
+                    kind,
+                    histogram.len(),
+                    stats.errors,
+                    stats.shed,
+                    stats.retries,
+                    Stats::cells(histogram)
+                );
+            }
+        }
+        println!("\n=== Stages (latency from intended start, all operations) ===\n");
+        let sampled = !self.resources.is_empty();
+        print!(
+            "{:>15} | {:>11} | {:>11} | {:>8} | {:>6} | {:>6} | {:>10} | {:>10} | {:>10} | {:>10} | {:>10}",
+            "Time (s)",
+            "Target rps",
+            "Actual rps",
+            "Count",
+            "Errors",
+            "Shed",
+            "p50 (ms)",
+            "p90 (ms)",
+            "p99 (ms)",
+            "p99.9 (ms)",
+            "max (ms)"
+        );
+        if sampled {
+            print!(
+                " | {:>8} | {:>8} | {:>9}",
+                "CPU avg%", "CPU max%", "RSS (MiB)"
+            );
+        }
+        println!();
+        println!("{}", "-".repeat(if sampled { 180 } else { 145 }));
+        for stage_stats in self.stages.iter() {
+            let StageStats {
+                stage,
+                stats,
+                resources,
+                ..
+            } = stage_stats;
+            print!(
+                "{:>15} | {:>11} | {:>11.1} | {:>8} | {:>6} | {:>6} | {}",
+                format!(
+                    "{:.1}..{:.1}",
+                    stage.start.as_secs_f64(),
+                    stage.end.as_secs_f64()
+                ),
+                stage.rate,
+                stage_stats.actual_rps(),
+                stats.latency.len(),
+                stats.errors,
+                stats.shed,
+                Stats::cells(&stats.latency)
+            );
+            if sampled {
+                print!(" | {}", resource_cells(resources));
+            }
+            println!();
+        }
+        if sampled {
+            println!(
+                "\nServer resources (CPU avg% | CPU max% | RSS max MiB): {}",
+                resource_cells(&self.resources)
+            );
+        }
+        if !self.slos.is_empty() {
+            println!("\n=== SLOs ===\n");
+            for verdict in self.slos.iter() {
+                println!(
+                    "{} {} (actual: {})",
+                    if verdict.passed { "PASS" } else { "FAIL" },
+                    verdict.objective,
+                    verdict.actual
+                );
+            }
+        }
+        println!();
+    }
+}
+
+/// Converts bytes to mebibytes.
+pub fn mib(bytes: u64) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0)
+}
+
+/// Converts microseconds to milliseconds.
+pub fn ms(micros: u64) -> f64 {
+    micros as f64 / 1_000.0
+}
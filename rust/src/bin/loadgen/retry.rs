@@ -0,0 +1,80 @@
+use rand::Rng;
+use reqwest::{
+    Response, StatusCode,
+    header::{HeaderMap, RETRY_AFTER},
+};
+use serde::Deserialize;
+use std::time::Duration;
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_backoff_ms() -> u64 {
+    50
+}
+
+fn default_max_backoff_ms() -> u64 {
+    2_000
+}
+
+/// Returns `true` if the server refused the request to protect itself (rate limiting or load
+/// shedding) rather than failed it, i.e. it may succeed later.
+pub fn is_shed(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::SERVICE_UNAVAILABLE
+}
+
+/// Retries of requests shed by the server (`429` and `503`), as a well-behaved client would do.
+///
+/// Without it, a shed request is counted as shed right away. Retries are delayed with exponential
+/// backoff and full jitter, starting at `backoff_ms` and capped at `max_backoff_ms`; a
+/// `Retry-After` header (in seconds) takes precedence, within the same cap. The latency of a
+/// retried request covers all its attempts, as seen by the user.
+///
+/// # Example
+/// ```json
+/// "retry": { "max_retries": 5, "backoff_ms": 100, "max_backoff_ms": 5000 }
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Retry {
+    /// Retries after the first attempt.
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+
+    /// Upper bound of the delay before the first retry, doubled with every further retry.
+    #[serde(default = "default_backoff_ms")]
+    pub backoff_ms: u64,
+
+    /// Upper bound of any delay.
+    #[serde(default = "default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+}
+
+impl Retry {
+    /// Returns the delay before retry number `retry` (starting at `0`) of a shed `response`.
+    pub fn delay(&self, retry: u32, response: &Response) -> Duration {
+        let max = Duration::from_millis(self.max_backoff_ms);
+        if let Some(after) = retry_after(response.headers()) {
+            return after.min(max);
+        }
+        let ceiling = self
+            .backoff_ms
+            .saturating_mul(1 << retry.min(32))
+            .min(self.max_backoff_ms);
+        Duration::from_millis(rand::rng().random_range(0..=ceiling))
+    }
+}
+
+/// Parses `Retry-After` given in seconds; the HTTP date form isn't used by rate limiters in
+/// practice and is ignored.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
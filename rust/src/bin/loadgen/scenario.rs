@@ -0,0 +1,330 @@
+use serde::Deserialize;
+use std::{collections::BTreeMap, fs, io, time::Duration};
+
+use crate::{profile::Profile, retry::Retry, session::Session, slo::Slo};
+
+/// Bearer token used when the scenario doesn't configure authentication. The server accepts any
+/// token.
+const DEFAULT_TOKEN: &str = "loadgen";
+
+/// Header carrying the key of [`Auth::ApiKey`] if the scenario doesn't name one.
+const DEFAULT_API_KEY_HEADER: &str = "X-Api-Key";
+
+/// Load scenario, read from a JSON file.
+///
+/// # Example
+/// ```json
+/// {
+///     "target": "http://127.0.0.1:8080",
+///     "profile": { "type": "ramp", "from": 50, "to": 500 },
+///     "duration_secs": 30,
+///     "seed_posts": 100,
+///     "access": { "type": "zipf", "exponent": 1.1 },
+///     "operations": ["create_post", "get_post", "get_post", "update_post", "list_posts", "delete_post"],
+///     "slos": [{ "operation": "create_post", "percentile": 99, "max_ms": 5 }],
+///     "auth": { "type": "api_key", "key": "secret" },
+///     "signing": { "client": "loadgen", "secret": "secret" },
+///     "headers": { "X-Client": "loadgen" },
+///     "operation_headers": { "create_post": { "X-Priority": "low" } }
+/// }
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    /// Base URL of the server under test.
+    pub target: String,
+
+    /// Request rate over the run (see [`Profile`]).
+    pub profile: Profile,
+
+    /// Length of the measured run, in seconds.
+    pub duration_secs: u64,
+
+    /// Number of posts created before the run, so that reads have something to hit.
+    #[serde(default)]
+    pub seed_posts: usize,
+
+    /// Whether posts already stored on the server (`GET /posts`) are used as well, e.g. after
+    /// importing a dataset.
+    #[serde(default)]
+    pub existing_posts: bool,
+
+    /// Which known posts are picked by operations on existing posts (see [`Access`]).
+    #[serde(default)]
+    pub access: Access,
+
+    /// Operations issued in round-robin order; repeat an operation to give it more weight.
+    /// Not used with a `session`.
+    #[serde(default)]
+    pub operations: Vec<Operation>,
+
+    /// User sessions started at the profile's rate instead of independent requests (see
+    /// [`Session`]).
+    #[serde(default)]
+    pub session: Option<Session>,
+
+    /// Objectives checked at the end of the run (see [`Slo`]).
+    #[serde(default)]
+    pub slos: Vec<Slo>,
+
+    /// Path of a CSV file receiving per-second latency aggregates (see [`crate::timeseries`]).
+    #[serde(default)]
+    pub timeseries: Option<String>,
+
+    /// HTTP version of the requests (see [`Transport`]).
+    #[serde(default)]
+    pub transport: Transport,
+
+    /// Serialization of the bodies of the posts endpoints (see [`Encoding`]).
+    #[serde(default)]
+    pub encoding: Encoding,
+
+    /// Compression of the bodies of created and updated posts (see [`Compression`]).
+    #[serde(default)]
+    pub compression: Option<Compression>,
+
+    /// Retries of requests shed by the server (see [`Retry`]); shed requests aren't retried if
+    /// not set.
+    #[serde(default)]
+    pub retry: Option<Retry>,
+
+    /// Credentials sent with requests which require authentication (see [`Auth`]).
+    #[serde(default)]
+    pub auth: Auth,
+
+    /// Signature of every request, for servers requiring signed requests (see [`Signing`]).
+    #[serde(default)]
+    pub signing: Option<Signing>,
+
+    /// Headers sent with every request; they take precedence over the credentials.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+
+    /// Headers sent with requests of a single operation, on top of (and overriding) `headers`.
+    #[serde(default)]
+    pub operation_headers: BTreeMap<Operation, BTreeMap<String, String>>,
+}
+
+impl Scenario {
+    /// Reads and validates a scenario file.
+    pub fn load(path: &str) -> io::Result<Self> {
+        let scenario: Self =
+            serde_json::from_str(&fs::read_to_string(path)?).map_err(io::Error::other)?;
+        scenario
+            .profile
+            .validate(scenario.duration())
+            .map_err(io::Error::other)?;
+        scenario.access.validate().map_err(io::Error::other)?;
+        match scenario.session.as_ref() {
+            Some(session) => session.validate().map_err(io::Error::other)?,
+            None if scenario.operations.is_empty() => {
+                return Err(io::Error::other(
+                    "at least one operation or a session is required",
+                ));
+            }
+            None => {}
+        }
+        if scenario.auth == Auth::Session && scenario.session.is_none() {
+            return Err(io::Error::other(
+                "session authentication requires a session",
+            ));
+        }
+        for slo in scenario.slos.iter() {
+            slo.validate().map_err(io::Error::other)?;
+        }
+        Ok(scenario)
+    }
+
+    /// Returns the length of the measured run.
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs(self.duration_secs)
+    }
+}
+
+fn default_api_key_header() -> String {
+    DEFAULT_API_KEY_HEADER.to_owned()
+}
+
+/// HTTP version of the requests, to compare transports on the same server.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Transport {
+    #[default]
+    Http1,
+
+    /// HTTP/2 with prior knowledge; over `http://`, that's `h2c`, which actix accepts on its
+    /// plain listener.
+    Http2,
+
+    /// HTTP/3 with prior knowledge, to an `https://` target (e.g. the server's
+    /// `RUST_SERVER_H3_ADDR`) whose certificate isn't verified. Requires a loadgen built with the
+    /// `http3` feature.
+    Http3,
+}
+
+impl Transport {
+    /// Parses the name used in scenario files, e.g. `http2`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "http1" => Some(Self::Http1),
+            "http2" => Some(Self::Http2),
+            "http3" => Some(Self::Http3),
+            _ => None,
+        }
+    }
+}
+
+/// Serialization of post bodies, to measure its impact on the same endpoints.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoding {
+    #[default]
+    Json,
+
+    /// `application/x-protobuf` request and response bodies (`proto/posts.proto`); users and feed
+    /// requests stay JSON.
+    Protobuf,
+}
+
+/// `Content-Encoding` of request bodies, so the server's decompression cost is part of the
+/// measured latency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Compression {
+    Gzip,
+    Br,
+}
+
+impl Compression {
+    /// Returns the name of the content coding.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Br => "br",
+        }
+    }
+}
+
+/// How requests which require authentication are authenticated.
+///
+/// Within a [`Session`], a `login` step replaces the credentials with a bearer token of the logged
+/// in user until a `logout` step, unless authentication is disabled.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Auth {
+    /// No credentials are sent.
+    None,
+
+    /// `Authorization: Bearer <token>`.
+    Bearer { token: String },
+
+    /// The key in a header, `X-Api-Key` unless `header` is set.
+    ApiKey {
+        #[serde(default = "default_api_key_header")]
+        header: String,
+        key: String,
+    },
+
+    /// Only the bearer token of the session's logged in user; requires a `session`, requests
+    /// outside of a logged in part of it carry no credentials.
+    Session,
+}
+
+impl Default for Auth {
+    fn default() -> Self {
+        Self::Bearer {
+            token: DEFAULT_TOKEN.to_owned(),
+        }
+    }
+}
+
+/// Request signing with the secret of a client, as required by servers started with
+/// `RUST_SERVER_SIGNING_KEYS`: every request carries an HMAC-SHA256 signature over its timestamp,
+/// method, path and body in `X-Signature`, along with `X-Client-Id` and `X-Timestamp`. Applies on
+/// top of [`Auth`], so the cost of signing shows next to the same scenario without it.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Signing {
+    /// ID of the client, sent in `X-Client-Id`.
+    pub client: String,
+
+    /// Secret shared with the server.
+    pub secret: String,
+}
+
+/// Exponent used when a Zipf access pattern doesn't set one.
+const DEFAULT_ZIPF_EXPONENT: f64 = 1.0;
+
+fn default_zipf_exponent() -> f64 {
+    DEFAULT_ZIPF_EXPONENT
+}
+
+/// Distribution of the posts picked by `get_post`, `update_post` and `delete_post`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Access {
+    /// Every known post is equally likely.
+    #[default]
+    Uniform,
+
+    /// Known posts are ranked in the order they became known (seeded and existing posts first) and
+    /// picked with a Zipf distribution, so a few hot posts get most of the traffic and contend for
+    /// locks. A higher `exponent` concentrates the traffic further.
+    Zipf {
+        #[serde(default = "default_zipf_exponent")]
+        exponent: f64,
+    },
+}
+
+impl Access {
+    fn validate(&self) -> Result<(), String> {
+        match self {
+            Self::Zipf { exponent } if exponent.is_nan() || *exponent <= 0.0 => {
+                Err(format!("zipf exponent must be positive, got {exponent}"))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// A single kind of API request issued by the load generator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    /// `POST /posts`
+    CreatePost,
+
+    /// `GET /posts/{id}` of a random known post.
+    GetPost,
+
+    /// `PUT /posts/{id}` of a random known post.
+    UpdatePost,
+
+    /// `DELETE /posts/{id}` of a random known post.
+    DeletePost,
+
+    /// `GET /posts`
+    ListPosts,
+
+    /// `POST /users`, registering a new user. Within a session, the user's ID becomes the session's
+    /// bearer token (the server binds a token equal to a user ID to that user).
+    Login,
+
+    /// `GET /feed` of the session's user; fails with `401` outside of a logged in session.
+    GetFeed,
+}
+
+impl Operation {
+    /// Returns the name used in reports.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::CreatePost => "CreatePost",
+            Self::GetPost => "GetPost",
+            Self::UpdatePost => "UpdatePost",
+            Self::DeletePost => "DeletePost",
+            Self::ListPosts => "ListPosts",
+            Self::Login => "Login",
+            Self::GetFeed => "GetFeed",
+        }
+    }
+}
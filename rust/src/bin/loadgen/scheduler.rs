@@ -0,0 +1,100 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{task::JoinSet, time};
+
+use crate::{
+    ops::{Outcome, Target},
+    scenario::{Operation, Scenario},
+};
+
+/// Timing of a single request.
+#[derive(Debug, Clone, Copy)]
+pub struct Sample {
+    pub op: Operation,
+
+    pub outcome: Outcome,
+
+    /// Number of times the request was retried after being shed; its latency covers all attempts.
+    pub retries: u32,
+
+    /// When the request was due according to the schedule.
+    pub intended: Instant,
+
+    /// Offset of `intended` from the start of the run.
+    pub offset: Duration,
+
+    /// When the request was actually sent (later than `intended` if the generator fell behind).
+    pub sent: Instant,
+
+    /// When the response was fully read.
+    pub done: Instant,
+}
+
+impl Sample {
+    /// Latency as seen by a user arriving on schedule: from the intended start until the response.
+    pub fn latency(&self) -> Duration {
+        self.done - self.intended
+    }
+
+    /// Returns `true` if the request was shed by the server.
+    pub fn is_shed(&self) -> bool {
+        matches!(self.outcome, Outcome::Shed(_))
+    }
+
+    /// Returns `true` if the request succeeded.
+    pub fn is_ok(&self) -> bool {
+        self.outcome == Outcome::Ok
+    }
+
+    /// Time the server needed once the request was actually sent.
+    pub fn service_time(&self) -> Duration {
+        self.done - self.sent
+    }
+}
+
+/// Issues the scenario's requests on the cadence of its profile (open loop) and returns their samples.
+///
+/// Every request is started in its own task at its scheduled time, without waiting for previous
+/// responses; a slow response therefore can't delay later requests, and the number of requests in
+/// flight grows while the server is stalled, as it would with independent users.
+///
+/// With a [`Session`](crate::session::Session), the profile's cadence starts sessions instead of
+/// single requests.
+///
+/// `start` is the moment the run starts, i.e. when the first request is due.
+pub async fn run(scenario: &Scenario, target: Arc<Target>, start: Instant) -> Vec<Sample> {
+    let duration = scenario.duration();
+    let mut tasks = JoinSet::new();
+    let mut offset = Duration::ZERO;
+    let mut n = 0;
+    while offset < duration {
+        let (intended, at) = (start + offset, offset);
+        time::sleep_until(intended.into()).await;
+        offset += Duration::from_secs_f64(1.0 / scenario.profile.rate(offset, duration));
+        let target = target.clone();
+        if let Some(session) = scenario.session.clone() {
+            tasks.spawn(async move { session.run(target, start, start + duration).await });
+            continue;
+        }
+        let op = scenario.operations[n % scenario.operations.len()];
+        n += 1;
+        tasks.spawn(async move {
+            let sent = Instant::now();
+            let call = target.call_as(op, None).await;
+            vec![Sample {
+                op,
+                outcome: call.outcome,
+                retries: call.retries,
+                intended,
+                offset: at,
+                sent,
+                done: Instant::now(),
+            }]
+        });
+    }
+    let mut samples: Vec<Sample> = tasks.join_all().await.into_iter().flatten().collect();
+    samples.retain(|sample| sample.outcome != Outcome::Skipped);
+    samples
+}
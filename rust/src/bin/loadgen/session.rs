@@ -0,0 +1,181 @@
+use rand::Rng;
+use serde::Deserialize;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::time;
+
+use crate::{ops::Target, scenario::Operation, scheduler::Sample};
+
+/// A number, or a range `[min, max]` (inclusive) drawn from uniformly for every use.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(untagged)]
+pub enum Range {
+    Fixed(u64),
+    Between([u64; 2]),
+}
+
+impl Range {
+    fn sample(&self) -> u64 {
+        match *self {
+            Self::Fixed(value) => value,
+            Self::Between([min, max]) => rand::rng().random_range(min..=max),
+        }
+    }
+
+    fn validate(&self, name: &str) -> Result<(), String> {
+        match self {
+            Self::Between([min, max]) if min > max => {
+                Err(format!("{name}: [{min}, {max}] isn't a valid range"))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn default_repeat() -> Range {
+    Range::Fixed(1)
+}
+
+fn default_probability() -> f64 {
+    1.0
+}
+
+/// Action of a session step.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    /// Ends the logged in part of the session: later steps use the scenario's credentials again.
+    /// The API is stateless, so nothing is sent.
+    Logout,
+
+    /// Issues a request.
+    #[serde(untagged)]
+    Request(Operation),
+}
+
+/// A step of a [`Session`].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Step {
+    pub action: Action,
+
+    /// How many times the action is repeated (e.g. reading a few posts), each time after a think
+    /// time.
+    #[serde(default = "default_repeat")]
+    pub repeat: Range,
+
+    /// Chance of the step being taken at all, between `0` and `1`.
+    #[serde(default = "default_probability")]
+    pub probability: f64,
+
+    /// Think time before the step, in milliseconds; overrides the session's `think_ms`.
+    #[serde(default)]
+    pub think_ms: Option<Range>,
+}
+
+/// Behavior of a simulated user, run from start to end by every session.
+///
+/// New sessions start at the rate of the scenario's profile (sessions per second), regardless of
+/// how many are still running; within a session, every request waits for the previous response
+/// and a think time, as a real user would. The number of concurrent sessions thus grows with the
+/// think times and with the server's latency, instead of requests arriving in a tight loop.
+/// Sessions are cut off at the end of the run.
+///
+/// # Example
+/// ```json
+/// "session": {
+///     "think_ms": [500, 2000],
+///     "steps": [
+///         { "action": "login" },
+///         { "action": "list_posts" },
+///         { "action": "get_post", "repeat": [1, 5] },
+///         { "action": "create_post", "probability": 0.2, "think_ms": 5000 },
+///         { "action": "get_feed" },
+///         { "action": "logout" }
+///     ]
+/// }
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Session {
+    /// Think time before each step but the first, in milliseconds.
+    #[serde(default = "default_think_ms")]
+    pub think_ms: Range,
+
+    pub steps: Vec<Step>,
+}
+
+fn default_think_ms() -> Range {
+    Range::Fixed(0)
+}
+
+impl Session {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.steps.is_empty() {
+            return Err("a session needs at least one step".to_owned());
+        }
+        self.think_ms.validate("think_ms")?;
+        for step in self.steps.iter() {
+            step.repeat.validate("repeat")?;
+            if let Some(think) = step.think_ms.as_ref() {
+                think.validate("think_ms")?;
+            }
+            if !(0.0..=1.0).contains(&step.probability) {
+                return Err(format!(
+                    "probability must be within [0, 1], got {}",
+                    step.probability
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs a single session, returning the samples of its requests.
+    ///
+    /// Requests are due right after the preceding think time; none is started after `end`.
+    pub async fn run(&self, target: Arc<Target>, start: Instant, end: Instant) -> Vec<Sample> {
+        let mut samples = Vec::new();
+        let mut token: Option<String> = None;
+        let mut first = true;
+        for step in self.steps.iter() {
+            if !rand::rng().random_bool(step.probability) {
+                continue;
+            }
+            for _ in 0..step.repeat.sample() {
+                if !first {
+                    let think = step.think_ms.as_ref().unwrap_or(&self.think_ms).sample();
+                    time::sleep(Duration::from_millis(think)).await;
+                }
+                first = false;
+                let intended = Instant::now();
+                if intended >= end {
+                    return samples;
+                }
+                let op = match step.action {
+                    Action::Logout => {
+                        token = None;
+                        continue;
+                    }
+                    Action::Request(op) => op,
+                };
+                let call = target.call_as(op, token.as_deref()).await;
+                if op == Operation::Login && call.user.is_some() {
+                    token = call.user;
+                }
+                samples.push(Sample {
+                    op,
+                    outcome: call.outcome,
+                    retries: call.retries,
+                    intended,
+                    offset: intended - start,
+                    // Session requests are sent when due, there is no schedule to fall behind
+                    sent: intended,
+                    done: Instant::now(),
+                });
+            }
+        }
+        samples
+    }
+}
@@ -0,0 +1,126 @@
+use hdrhistogram::Histogram;
+use serde::{Deserialize, Serialize};
+
+use crate::{report::ms, scenario::Operation};
+
+/// Service level objective, checked at the end of a run.
+///
+/// An objective bounds a latency percentile (from the intended start, as in the report), the error
+/// rate or both, of a single operation or of all operations together. A violated objective makes
+/// `loadgen` exit with [`VIOLATED_EXIT_CODE`], so performance requirements can gate a CI job.
+///
+/// # Example
+/// ```json
+/// "slos": [
+///     { "operation": "create_post", "percentile": 99, "max_ms": 5 },
+///     { "percentile": 99.9, "max_ms": 50, "max_error_rate": 0.001 }
+/// ]
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Slo {
+    /// Operation the objective applies to; all operations together if not set.
+    #[serde(default)]
+    pub operation: Option<Operation>,
+
+    /// Percentile of the latency bound by `max_ms`, e.g. `99` or `99.9`.
+    #[serde(default)]
+    pub percentile: Option<f64>,
+
+    /// Upper bound of the latency at `percentile`, in milliseconds.
+    #[serde(default)]
+    pub max_ms: Option<f64>,
+
+    /// Upper bound of the share of failed requests, shed ones included, between `0` and `1`.
+    #[serde(default)]
+    pub max_error_rate: Option<f64>,
+}
+
+/// Exit code of `loadgen` when the run completed but an objective was violated, as opposed to
+/// `1` when the run itself failed.
+pub const VIOLATED_EXIT_CODE: u8 = 2;
+
+/// Outcome of checking a single bound of an [`Slo`].
+#[derive(Debug, Serialize)]
+pub struct Verdict {
+    /// The bound, e.g. `CreatePost p99 <= 5 ms`.
+    pub objective: String,
+
+    /// The measured value, e.g. `3.21 ms`.
+    pub actual: String,
+
+    pub passed: bool,
+}
+
+impl Slo {
+    pub fn validate(&self) -> Result<(), String> {
+        match (self.percentile, self.max_ms) {
+            (Some(percentile), Some(max_ms)) => {
+                if percentile.is_nan() || percentile <= 0.0 || percentile > 100.0 {
+                    return Err(format!(
+                        "slo percentile must be within (0, 100], got {percentile}"
+                    ));
+                }
+                if max_ms.is_nan() || max_ms < 0.0 {
+                    return Err(format!("slo max_ms must not be negative, got {max_ms}"));
+                }
+            }
+            (None, None) if self.max_error_rate.is_none() => {
+                return Err("slo needs percentile and max_ms, max_error_rate or both".to_owned());
+            }
+            (None, None) => {}
+            _ => return Err("slo percentile and max_ms must be set together".to_owned()),
+        }
+        match self.max_error_rate {
+            Some(rate) if !(0.0..=1.0).contains(&rate) => Err(format!(
+                "slo max_error_rate must be within [0, 1], got {rate}"
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the objective against the latency (in microseconds) and the number of failed
+    /// requests of the operation, or of all operations.
+    ///
+    /// An objective of an operation which wasn't issued at all is violated: it can't be met.
+    pub fn check(&self, latency: &Histogram<u64>, errors: u64) -> Vec<Verdict> {
+        let scope = self.operation.map_or("all", |op| op.name());
+        let requests = latency.len();
+        let mut verdicts = Vec::new();
+        if let (Some(percentile), Some(max_ms)) = (self.percentile, self.max_ms) {
+            let objective = format!("{scope} p{percentile} <= {max_ms} ms");
+            verdicts.push(if requests == 0 {
+                Verdict {
+                    objective,
+                    actual: "no requests".to_owned(),
+                    passed: false,
+                }
+            } else {
+                let actual = ms(latency.value_at_percentile(percentile));
+                Verdict {
+                    objective,
+                    actual: format!("{actual:.2} ms"),
+                    passed: actual <= max_ms,
+                }
+            });
+        }
+        if let Some(max_rate) = self.max_error_rate {
+            let objective = format!("{scope} error rate <= {}%", max_rate * 100.0);
+            verdicts.push(if requests == 0 {
+                Verdict {
+                    objective,
+                    actual: "no requests".to_owned(),
+                    passed: false,
+                }
+            } else {
+                let rate = errors as f64 / requests as f64;
+                Verdict {
+                    objective,
+                    actual: format!("{:.3}%", rate * 100.0),
+                    passed: rate <= max_rate,
+                }
+            });
+        }
+        verdicts
+    }
+}
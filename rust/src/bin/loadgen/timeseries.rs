@@ -0,0 +1,68 @@
+use hdrhistogram::Histogram;
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, BufWriter, Write},
+};
+
+use crate::{
+    monitor::ResourceSample,
+    report::{histogram, mib},
+    scenario::Operation,
+    scheduler::Sample,
+};
+
+/// Writes per-second latency aggregates of every operation as CSV.
+///
+/// Samples are bucketed by the second (since the start of the run) their response arrived in, so a
+/// server stall shows up as a gap or a latency spike at the time it happened, which end-of-run
+/// percentiles hide. Columns:
+///
+/// ```text
+/// second,operation,count,errors,shed,p50_ms,p99_ms,max_ms,cpu_pct,rss_mib
+/// ```
+///
+/// `shed` counts requests refused by the server with `429` or `503`, which `errors` doesn't include.
+/// Latencies are measured from the intended start of the request. `cpu_pct` and `rss_mib` hold the
+/// server's resource usage over that second and are empty if the server wasn't sampled.
+pub fn write(path: &str, samples: &[Sample], resources: &[ResourceSample]) -> io::Result<()> {
+    let mut buckets: BTreeMap<(u64, Operation), (Histogram<u64>, u64, u64)> = BTreeMap::new();
+    for sample in samples {
+        let second = (sample.offset + sample.latency()).as_secs();
+        let (latency, errors, shed) = buckets
+            .entry((second, sample.op))
+            .or_insert_with(|| (histogram(), 0, 0));
+        latency.saturating_record(sample.latency().as_micros() as u64);
+        if sample.is_shed() {
+            *shed += 1;
+        } else if !sample.is_ok() {
+            *errors += 1;
+        }
+    }
+    // A resource sample covers the second before it was taken
+    let resources: BTreeMap<u64, &ResourceSample> = resources
+        .iter()
+        .map(|r| (r.offset.as_secs().saturating_sub(1), r))
+        .collect();
+    let mut file = BufWriter::new(File::create(path)?);
+    writeln!(
+        file,
+        "second,operation,count,errors,shed,p50_ms,p99_ms,max_ms,cpu_pct,rss_mib"
+    )?;
+    for ((second, op), (latency, errors, shed)) in buckets {
+        let (cpu, rss) = resources
+            .get(&second)
+            .map(|r| (format!("{:.1}", r.cpu), format!("{:.1}", mib(r.rss))))
+            .unwrap_or_default();
+        writeln!(
+            file,
+            "{second},{},{},{errors},{shed},{},{},{},{cpu},{rss}",
+            op.name(),
+            latency.len(),
+            latency.value_at_percentile(50.0) as f64 / 1_000.0,
+            latency.value_at_percentile(99.0) as f64 / 1_000.0,
+            latency.max() as f64 / 1_000.0
+        )?;
+    }
+    file.flush()
+}
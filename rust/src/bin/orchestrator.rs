@@ -0,0 +1,525 @@
+//! Benchmark orchestrator for the PerCom backends.
+//!
+//! Runs the whole benchmark from a single config file: for every backend it starts the server,
+//! waits until it answers, runs every scenario through the `loadgen` binary, stores the reports and
+//! stops the server again, so results don't depend on hand-written shell scripts.
+//!
+//! # Usage
+//! ```text
+//! cargo build --release --bins
+//! ./target/release/orchestrator bench.json
+//! ```
+//!
+//! Results of a run are written to `<results_dir>/<run>/<backend>/<scenario>.{txt,csv,json}`, where
+//! `<run>` is `<UTC start time>-<host name>`, e.g. `20250101T120000Z-bench1`: the printed report,
+//! the per-second time series and the [`BenchResult`] (JSON report with run metadata). The names
+//! never collide across machines and runs, so result directories can be merged as they are. Servers
+//! are stopped with `SIGTERM`, so they can write their session summary, which they're told to write
+//! to `<results_dir>/<run>/<backend>/server-summary.json` (`RUST_SERVER_SUMMARY_PATH`). Requests
+//! of every scenario carry the run ID `<run>/<backend>/<scenario>` (`X-Benchmark-Run-Id`), and the
+//! latency the server measured for them is stored with the result. With
+//! `upload` configured, every [`BenchResult`] is also posted to a results API. See [`Config`] for
+//! the config file format.
+
+use chrono::{DateTime, Utc};
+use percom_model::urls;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    env, fs, io,
+    path::{Path, PathBuf},
+    process::{ExitCode, Stdio},
+    time::Duration,
+};
+use tokio::{
+    process::{Child, Command},
+    time::{Instant, sleep, timeout},
+};
+
+/// Delay between two readiness probes.
+const PROBE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long a server may take to exit once asked to stop, before it's killed.
+const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Benchmark config, read from a JSON file.
+///
+/// # Example
+/// ```json
+/// {
+///     "results_dir": "results",
+///     "scenarios": ["scenarios/default.json", "scenarios/ramp.json"],
+///     "upload": { "url": "https://results.example.com/api/results", "token": "secret" },
+///     "backends": [
+///         {
+///             "name": "rust",
+///             "command": ["./target/release/server"],
+///             "url": "http://127.0.0.1:8080",
+///             "sample_resources": true
+///         },
+///         {
+///             "name": "go",
+///             "command": ["docker", "run", "--rm", "--name", "percom-go", "-p", "8081:8080", "percom-go"],
+///             "stop": ["docker", "stop", "percom-go"],
+///             "url": "http://127.0.0.1:8081"
+///         }
+///     ]
+/// }
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    /// Directory receiving the reports.
+    results_dir: PathBuf,
+
+    /// Paths of the loadgen scenario files, run in order against every backend.
+    scenarios: Vec<PathBuf>,
+
+    /// Servers under test, benchmarked one after another.
+    backends: Vec<Backend>,
+
+    /// Results API receiving every [`BenchResult`].
+    #[serde(default)]
+    upload: Option<Upload>,
+}
+
+/// Endpoint of a results API.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Upload {
+    /// URL the results are posted to as JSON.
+    url: String,
+
+    /// Bearer token sent with the results.
+    #[serde(default)]
+    token: Option<String>,
+}
+
+impl Upload {
+    async fn send(&self, result: &BenchResult<'_>) -> io::Result<()> {
+        let mut request = reqwest::Client::new().post(&self.url).json(result);
+        if let Some(token) = self.token.as_ref() {
+            request = request.bearer_auth(token);
+        }
+        let response = request.send().await.map_err(io::Error::other)?;
+        if !response.status().is_success() {
+            return Err(io::Error::other(format!(
+                "results API answered with {}",
+                response.status()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// A single benchmark run of the orchestrator.
+struct Run {
+    /// Unique name of the run, `<UTC start time>-<host name>`.
+    id: String,
+    host: String,
+    started_at: DateTime<Utc>,
+}
+
+impl Run {
+    fn new() -> Self {
+        // `/proc` is Linux only; elsewhere rely on the shell's variable
+        let host = fs::read_to_string("/proc/sys/kernel/hostname")
+            .ok()
+            .or_else(|| env::var("HOSTNAME").ok())
+            .map(|host| host.trim().to_owned())
+            .filter(|host| !host.is_empty())
+            .unwrap_or_else(|| "unknown".to_owned());
+        let started_at = Utc::now();
+        Self {
+            id: format!("{}-{host}", started_at.format("%Y%m%dT%H%M%SZ")),
+            host,
+            started_at,
+        }
+    }
+}
+
+/// Result of one scenario against one backend: the loadgen JSON report along with the run metadata.
+///
+/// Stored as `<scenario>.json` and posted to the results API.
+#[derive(Debug, Serialize)]
+struct BenchResult<'a> {
+    run: &'a str,
+    host: &'a str,
+    started_at: DateTime<Utc>,
+    backend: &'a str,
+    scenario: &'a str,
+
+    /// What the backend reported at `GET /admin/runtime`, e.g. whether it runs on io_uring;
+    /// `null` for backends without that endpoint.
+    runtime: Option<&'a serde_json::Value>,
+
+    /// HTTP version of the requests if the backend overrides the scenario's.
+    transport: Option<&'a str>,
+
+    /// ID the requests of the scenario were sent with (`X-Benchmark-Run-Id`).
+    run_id: &'a str,
+
+    /// What the backend reported for `run_id` in the `runs` metric of `GET /metrics`, e.g. its
+    /// latency histogram; `null` for backends without that metric.
+    server: Option<serde_json::Value>,
+    report: serde_json::Value,
+}
+
+/// A server under test.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Backend {
+    /// Name used for the results subdirectory.
+    name: String,
+
+    /// Program and arguments starting the server. The server must keep running in the foreground.
+    command: Vec<String>,
+
+    /// Working directory of `command`; the current directory by default.
+    #[serde(default)]
+    cwd: Option<PathBuf>,
+
+    /// Extra environment variables of `command`.
+    #[serde(default)]
+    env: HashMap<String, String>,
+
+    /// Program and arguments run after the server process is killed, e.g. to stop a container.
+    #[serde(default)]
+    stop: Option<Vec<String>>,
+
+    /// Base URL of the server, probed for readiness; overrides the scenarios' `target`.
+    url: String,
+
+    /// Base URL handed to loadgen instead of `url`, e.g. the HTTP/3 listener of the server, which
+    /// only answers over QUIC.
+    #[serde(default)]
+    target: Option<String>,
+
+    /// HTTP version of loadgen's requests (`http1`, `http2` or `http3`), overriding the
+    /// scenarios' `transport`, so the same server can be compared over several transports.
+    #[serde(default)]
+    transport: Option<String>,
+
+    /// Path probed until it answers with a success status.
+    #[serde(default = "default_ready_path")]
+    ready_path: String,
+
+    /// How long to wait for the server to become ready, in seconds.
+    #[serde(default = "default_ready_timeout_secs")]
+    ready_timeout_secs: u64,
+
+    /// Whether to sample CPU and memory of the started process. Only meaningful if `command` runs
+    /// the server itself (not a container client) on this machine.
+    #[serde(default)]
+    sample_resources: bool,
+}
+
+fn default_ready_path() -> String {
+    urls::posts::list()
+}
+
+fn default_ready_timeout_secs() -> u64 {
+    60
+}
+
+/// Builds a command from a program and its arguments.
+fn command(args: &[String]) -> io::Result<Command> {
+    let (program, args) = args
+        .split_first()
+        .ok_or_else(|| io::Error::other("empty command"))?;
+    let mut command = Command::new(program);
+    command.args(args);
+    Ok(command)
+}
+
+/// A started server, stopped when dropped even if the benchmark fails halfway.
+struct Server<'a> {
+    backend: &'a Backend,
+    child: Child,
+
+    /// Answer of `GET /admin/runtime`, if the server has it.
+    runtime: Option<serde_json::Value>,
+}
+
+impl<'a> Server<'a> {
+    /// Starts the server, writing its session summary to `summary` once it's stopped, and waits
+    /// until it answers on [`Backend::ready_path`].
+    async fn start(backend: &'a Backend, summary: &Path) -> io::Result<Self> {
+        let mut command = command(&backend.command)?;
+        if let Some(cwd) = backend.cwd.as_ref() {
+            command.current_dir(cwd);
+        }
+        let child = command
+            // Relative to the orchestrator, whatever the working directory of the server
+            .env("RUST_SERVER_SUMMARY_PATH", std::path::absolute(summary)?)
+            .envs(&backend.env)
+            .stdout(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()?;
+        let mut server = Self {
+            backend,
+            child,
+            runtime: None,
+        };
+        server.wait_ready().await?;
+        server.runtime = server.fetch_runtime().await;
+        Ok(server)
+    }
+
+    /// Asks the server which runtime it runs on; other backends than this one don't know.
+    async fn fetch_runtime(&self) -> Option<serde_json::Value> {
+        let response = reqwest::Client::new()
+            .get(format!("{}/admin/runtime", self.backend.url))
+            .bearer_auth("orchestrator")
+            .send()
+            .await
+            .ok()?;
+        if !response.status().is_success() {
+            return None;
+        }
+        response.json().await.ok()
+    }
+
+    /// Asks the server for its stats of the requests sent with `run_id`; other backends than this
+    /// one don't keep them.
+    async fn fetch_run_stats(&self, run_id: &str) -> Option<serde_json::Value> {
+        let response = reqwest::Client::new()
+            .get(format!("{}{}", self.backend.url, urls::metrics::get()))
+            .send()
+            .await
+            .ok()?;
+        if !response.status().is_success() {
+            return None;
+        }
+        let mut metrics: serde_json::Value = response.json().await.ok()?;
+        Some(metrics["runs"].get_mut(run_id)?.take())
+    }
+
+    async fn wait_ready(&mut self) -> io::Result<()> {
+        let url = format!("{}{}", self.backend.url, self.backend.ready_path);
+        let deadline = Instant::now() + Duration::from_secs(self.backend.ready_timeout_secs);
+        let client = reqwest::Client::new();
+        loop {
+            if let Some(status) = self.child.try_wait()? {
+                return Err(io::Error::other(format!("server exited with {status}")));
+            }
+            if client
+                .get(&url)
+                .send()
+                .await
+                .is_ok_and(|response| response.status().is_success())
+            {
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                return Err(io::Error::other(format!("{url} isn't ready")));
+            }
+            sleep(PROBE_INTERVAL).await;
+        }
+    }
+
+    /// Asks the server process to stop with `SIGTERM`, killing it if it doesn't exit within
+    /// [`STOP_TIMEOUT`], and runs [`Backend::stop`], if any.
+    async fn stop(mut self) -> io::Result<()> {
+        if let Some(pid) = self.child.id() {
+            // Tokio can only kill; if `kill` isn't available, the timeout kills the server instead
+            let _ = Command::new("kill")
+                .arg("-TERM")
+                .arg(pid.to_string())
+                .status()
+                .await;
+        }
+        if timeout(STOP_TIMEOUT, self.child.wait()).await.is_err() {
+            self.child.kill().await?;
+        }
+        if let Some(stop) = self.backend.stop.as_ref() {
+            let status = command(stop)?.status().await?;
+            if !status.success() {
+                return Err(io::Error::other(format!(
+                    "stop command exited with {status}"
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Exit code of `loadgen` when the run completed but violated an objective of the scenario.
+const SLO_VIOLATED_EXIT_CODE: i32 = 2;
+
+/// Runs a scenario through `loadgen`, stores its reports in `dir` and uploads the result.
+///
+/// # Returns
+/// `false` if the scenario's objectives (SLOs) were violated; the result is stored all the same.
+async fn run_scenario(
+    ctx: &Context<'_>,
+    scenario: &Path,
+    server: &Server<'_>,
+    dir: &Path,
+) -> io::Result<bool> {
+    let name = scenario
+        .file_stem()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| io::Error::other(format!("invalid scenario path {}", scenario.display())))?;
+    let report = dir.join(name).with_extension("txt");
+    let timeseries = dir.join(name).with_extension("csv");
+    let json = dir.join(name).with_extension("json");
+    // Servers only accept a few punctuation characters in run IDs
+    let run_id: String = format!("{}/{}/{name}", ctx.run.id, server.backend.name)
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "-_.:/".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let mut command = Command::new(ctx.loadgen);
+    command
+        .arg(scenario)
+        .arg("--target")
+        .arg(
+            server
+                .backend
+                .target
+                .as_ref()
+                .unwrap_or(&server.backend.url),
+        )
+        .arg("--timeseries")
+        .arg(&timeseries)
+        .arg("--json")
+        .arg(&json)
+        .arg("--run-id")
+        .arg(&run_id);
+    if let Some(transport) = server.backend.transport.as_ref() {
+        command.arg("--transport").arg(transport);
+    }
+    if server.backend.sample_resources
+        && let Some(pid) = server.child.id()
+    {
+        command.arg("--pid").arg(pid.to_string());
+    }
+    let output = command.stderr(Stdio::inherit()).output().await?;
+    fs::write(&report, &output.stdout)?;
+    let slos_met = output.status.code() != Some(SLO_VIOLATED_EXIT_CODE);
+    if !output.status.success() && slos_met {
+        return Err(io::Error::other(format!(
+            "loadgen exited with {}",
+            output.status
+        )));
+    }
+    let result = BenchResult {
+        run: &ctx.run.id,
+        host: &ctx.run.host,
+        started_at: ctx.run.started_at,
+        backend: &server.backend.name,
+        scenario: name,
+        runtime: server.runtime.as_ref(),
+        transport: server.backend.transport.as_deref(),
+        server: server.fetch_run_stats(&run_id).await,
+        run_id: &run_id,
+        report: serde_json::from_slice(&fs::read(&json)?).map_err(io::Error::other)?,
+    };
+    fs::write(
+        &json,
+        serde_json::to_vec_pretty(&result).map_err(io::Error::other)?,
+    )?;
+    println!("  {} -> {}", scenario.display(), report.display());
+    if !slos_met {
+        println!("  SLOs violated, see the report");
+    }
+    if let Some(upload) = ctx.config.upload.as_ref() {
+        // The result is kept on disk anyway, so a failed upload doesn't fail the benchmark
+        match upload.send(&result).await {
+            Ok(()) => println!("  uploaded to {}", upload.url),
+            Err(err) => eprintln!("  fail to upload to {}: {err}", upload.url),
+        }
+    }
+    Ok(slos_met)
+}
+
+/// Everything shared by the backends of a run.
+struct Context<'a> {
+    config: &'a Config,
+    run: &'a Run,
+
+    /// Path of the `loadgen` binary.
+    loadgen: &'a Path,
+}
+
+/// Benchmarks a single backend: starts it, runs all scenarios and stops it.
+///
+/// # Returns
+/// `false` if a scenario violated its objectives.
+async fn run_backend(ctx: &Context<'_>, backend: &Backend) -> io::Result<bool> {
+    let dir = ctx.config.results_dir.join(&ctx.run.id).join(&backend.name);
+    fs::create_dir_all(&dir)?;
+    println!("[{}] starting: {}", backend.name, backend.command.join(" "));
+    let server = Server::start(backend, &dir.join("server-summary.json")).await?;
+    println!("[{}] ready at {}", backend.name, backend.url);
+    let mut result = Ok(true);
+    for scenario in ctx.config.scenarios.iter() {
+        match run_scenario(ctx, scenario, &server, &dir).await {
+            Ok(slos_met) => result = result.map(|met| met && slos_met),
+            Err(err) => {
+                result = Err(err);
+                break;
+            }
+        }
+    }
+    server.stop().await?;
+    println!("[{}] stopped", backend.name);
+    result
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let Some(path) = env::args().nth(1) else {
+        eprintln!("Usage: orchestrator <bench.json>");
+        return ExitCode::FAILURE;
+    };
+    let config: Config = match fs::read_to_string(&path)
+        .and_then(|content| serde_json::from_str(&content).map_err(io::Error::other))
+    {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Fail to load config {path}: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    // `loadgen` is built next to this binary (`cargo build --bins`)
+    let loadgen = match env::current_exe() {
+        Ok(exe) => exe.with_file_name("loadgen"),
+        Err(err) => {
+            eprintln!("Fail to locate loadgen: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let run = Run::new();
+    println!("Run {}", run.id);
+    let ctx = Context {
+        config: &config,
+        run: &run,
+        loadgen: &loadgen,
+    };
+    let mut failed = false;
+    for backend in config.backends.iter() {
+        match run_backend(&ctx, backend).await {
+            Ok(true) => {}
+            Ok(false) => {
+                eprintln!("[{}] SLOs violated", backend.name);
+                failed = true;
+            }
+            Err(err) => {
+                eprintln!("[{}] benchmark failed: {err}", backend.name);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
@@ -0,0 +1,346 @@
+//! Consistent-hashing router sharding the posts of the PerCom API over several server instances.
+//!
+//! Every backend instance owns the posts whose ID hashes to its arcs of a hash ring, so the same
+//! codebase can be benchmarked scaled out horizontally: point the load generator at the router
+//! instead of a single server.
+//!
+//! # Usage
+//! ```text
+//! cargo run --release --bin router -- [--listen <addr>] [--vnodes <n>] <backend url>...
+//! ```
+//!
+//! # Routing
+//! - `/posts/{id}` (any method): the shard owning `id`;
+//! - `POST /posts`: the router picks a fresh ID and sends it in `X-Post-Id` to the shard owning it;
+//! - `GET /posts`: every shard, the lists being concatenated;
+//! - anything else (users, feed, admin, metrics): the first backend, which is the only one holding
+//!   users. The feed thus only shows the posts of the first shard.
+//!
+//! `GET /router` returns the backends and the number of requests sent to each of them.
+//!
+//! Request and response bodies are buffered, not streamed.
+
+use actix_web::{
+    App, HttpRequest, HttpResponse, HttpServer, get,
+    http::{Method, StatusCode},
+    web,
+};
+use futures_util::{StreamExt, future::try_join_all};
+use std::{
+    collections::BTreeMap,
+    env,
+    process::ExitCode,
+    sync::atomic::{AtomicU64, Ordering},
+};
+use uuid::Uuid;
+
+/// Command line usage.
+const USAGE: &str = "Usage: router [--listen <addr>] [--vnodes <n>] <backend url>...";
+
+/// Default address the router listens on.
+const DEFAULT_LISTEN: &str = "127.0.0.1:8070";
+
+/// Default number of points of every backend on the hash ring. More points spread the posts more
+/// evenly, at the cost of a larger ring.
+const DEFAULT_VNODES: usize = 160;
+
+/// Header of `POST /posts` choosing the ID of the new post (see the server's `create_post`).
+const POST_ID_HEADER: &str = "X-Post-Id";
+
+/// Headers which only apply to a single connection and aren't forwarded (RFC 9110, section 7.6.1),
+/// along with those the HTTP clients set themselves.
+const HOP_BY_HOP: [&str; 9] = [
+    "connection",
+    "keep-alive",
+    "proxy-connection",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+    "host",
+    "content-length",
+];
+
+/// Parsed command line.
+struct Args {
+    listen: String,
+    vnodes: usize,
+    backends: Vec<String>,
+}
+
+impl Args {
+    fn parse() -> Option<Self> {
+        let mut args = env::args().skip(1);
+        let mut listen = DEFAULT_LISTEN.to_owned();
+        let mut vnodes = DEFAULT_VNODES;
+        let mut backends = Vec::new();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--listen" => listen = args.next()?,
+                "--vnodes" => vnodes = args.next()?.parse().ok().filter(|n| *n > 0)?,
+                _ if arg.starts_with("--") => return None,
+                _ => backends.push(arg.trim_end_matches('/').to_owned()),
+            }
+        }
+        (!backends.is_empty()).then_some(Self {
+            listen,
+            vnodes,
+            backends,
+        })
+    }
+}
+
+/// Hashes `key` with 64-bit FNV-1a, which is stable across builds and platforms, so every router
+/// instance maps IDs to the same shards.
+fn hash(key: &str) -> u64 {
+    key.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Consistent hash ring: every backend is placed at `vnodes` points, and a key belongs to the
+/// backend of the first point at or after its hash. Adding or removing a backend only moves the
+/// keys of its own arcs.
+struct Ring {
+    /// Backend index of every point.
+    points: BTreeMap<u64, usize>,
+}
+
+impl Ring {
+    /// Places the points of a backend by hashing its URL, so the ring doesn't depend on the order
+    /// of the backends on the command line.
+    fn new(backends: &[String], vnodes: usize) -> Self {
+        let mut points = BTreeMap::new();
+        for (idx, backend) in backends.iter().enumerate() {
+            for vnode in 0..vnodes {
+                points.insert(hash(&format!("{backend}#{vnode}")), idx);
+            }
+        }
+        Self { points }
+    }
+
+    /// Returns the index of the backend owning `key`.
+    fn shard(&self, key: &str) -> usize {
+        let hash = hash(key);
+        self.points
+            .range(hash..)
+            .next()
+            .or_else(|| self.points.iter().next())
+            .map_or(0, |(_, idx)| *idx)
+    }
+}
+
+/// Shared state of the router.
+struct Router {
+    backends: Vec<String>,
+    ring: Ring,
+    client: reqwest::Client,
+
+    /// Number of requests sent to every backend.
+    requests: Vec<AtomicU64>,
+}
+
+/// Error of a request to a backend, answered with `502 Bad Gateway`.
+fn bad_gateway(backend: &str, err: impl std::fmt::Display) -> HttpResponse {
+    eprintln!("Request to {backend} failed: {err}");
+    HttpResponse::BadGateway()
+        .content_type("application/problem+json")
+        .json(serde_json::json!({
+            "type": "about:blank",
+            "title": "Bad Gateway",
+            "status": 502,
+            "detail": format!("backend {backend} failed"),
+        }))
+}
+
+impl Router {
+    /// Sends `req` with `body` to the backend `idx` and returns its response. `extra` headers
+    /// replace those of `req`.
+    async fn forward(
+        &self,
+        idx: usize,
+        req: &HttpRequest,
+        body: web::Bytes,
+        extra: &[(&str, String)],
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        let backend = &self.backends[idx];
+        self.requests[idx].fetch_add(1, Ordering::Relaxed);
+        let path = req.uri().path_and_query().map_or("/", |path| path.as_str());
+        let method = reqwest::Method::from_bytes(req.method().as_str().as_bytes())
+            .unwrap_or(reqwest::Method::GET);
+        let mut request = self.client.request(method, format!("{backend}{path}"));
+        for (name, value) in req.headers() {
+            let replaced = extra
+                .iter()
+                .any(|(extra, _)| name.as_str().eq_ignore_ascii_case(extra));
+            if !replaced && !HOP_BY_HOP.contains(&name.as_str()) {
+                request = request.header(name.as_str(), value.as_bytes());
+            }
+        }
+        for (name, value) in extra {
+            request = request.header(*name, value.as_str());
+        }
+        request.body(body).send().await
+    }
+
+    /// Sends `req` to the backend `idx` (see [`Router::forward`]) and relays its response.
+    async fn proxy(
+        &self,
+        idx: usize,
+        req: &HttpRequest,
+        body: web::Bytes,
+        extra: &[(&str, String)],
+    ) -> HttpResponse {
+        let backend = &self.backends[idx];
+        let response = match self.forward(idx, req, body, extra).await {
+            Ok(response) => response,
+            Err(err) => return bad_gateway(backend, err),
+        };
+        let status = StatusCode::from_u16(response.status().as_u16())
+            .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        let mut relayed = HttpResponse::build(status);
+        for (name, value) in response.headers() {
+            if !HOP_BY_HOP.contains(&name.as_str()) {
+                relayed.append_header((name.as_str(), value.as_bytes()));
+            }
+        }
+        match response.bytes().await {
+            Ok(bytes) => relayed.body(bytes),
+            Err(err) => bad_gateway(backend, err),
+        }
+    }
+
+    /// Lists the posts of every shard in a single response, in the format the first shard picked
+    /// for the client: JSON arrays are concatenated, and so are protobuf lists, whose encodings
+    /// merge into a single list.
+    async fn list_posts(&self, req: &HttpRequest) -> HttpResponse {
+        let shards = (0..self.backends.len()).map(|idx| async move {
+            let response = self
+                .forward(idx, req, web::Bytes::new(), &[])
+                .await
+                .and_then(reqwest::Response::error_for_status)
+                .map_err(|err| (idx, err.to_string()))?;
+            let content_type = response
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or("application/json")
+                .to_owned();
+            let bytes = response
+                .bytes()
+                .await
+                .map_err(|err| (idx, err.to_string()))?;
+            Ok((content_type, bytes))
+        });
+        let shards = match try_join_all(shards).await {
+            Ok(shards) => shards,
+            Err((idx, err)) => return bad_gateway(&self.backends[idx], err),
+        };
+        let content_type = shards[0].0.clone();
+        if !content_type.starts_with("application/json") {
+            let bytes: Vec<u8> = shards
+                .iter()
+                .flat_map(|(_, bytes)| bytes.to_vec())
+                .collect();
+            return HttpResponse::Ok().content_type(content_type).body(bytes);
+        }
+        let mut posts = Vec::new();
+        for (idx, (_, bytes)) in shards.iter().enumerate() {
+            match serde_json::from_slice::<Vec<serde_json::Value>>(bytes) {
+                Ok(shard) => posts.extend(shard),
+                Err(err) => return bad_gateway(&self.backends[idx], err),
+            }
+        }
+        HttpResponse::Ok().json(posts)
+    }
+}
+
+/// Routes every request but `GET /router` (see the module documentation).
+///
+/// The body is read as it is, without decoding its `Content-Encoding`, so the backend sees the
+/// original request.
+async fn route(
+    req: HttpRequest,
+    mut payload: web::Payload,
+    router: web::Data<Router>,
+) -> HttpResponse {
+    let mut body = web::BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        match chunk {
+            Ok(chunk) => body.extend_from_slice(&chunk),
+            Err(err) => return HttpResponse::BadRequest().body(err.to_string()),
+        }
+    }
+    let body = body.freeze();
+    let segments: Vec<&str> = req.path().trim_matches('/').split('/').collect();
+    match (req.method(), segments.as_slice()) {
+        (&Method::GET, ["posts"]) => router.list_posts(&req).await,
+        (&Method::POST, ["posts"]) => {
+            let id = Uuid::new_v4().to_string();
+            let shard = router.ring.shard(&id);
+            router
+                .proxy(shard, &req, body, &[(POST_ID_HEADER, id)])
+                .await
+        }
+        (_, ["posts", id, ..]) => router.proxy(router.ring.shard(id), &req, body, &[]).await,
+        _ => router.proxy(0, &req, body, &[]).await,
+    }
+}
+
+/// Handles `GET /router`: the backends and the number of requests sent to each of them.
+#[get("/router")]
+async fn get_router(router: web::Data<Router>) -> HttpResponse {
+    let backends: Vec<_> = router
+        .backends
+        .iter()
+        .zip(router.requests.iter())
+        .map(|(url, requests)| {
+            serde_json::json!({ "url": url, "requests": requests.load(Ordering::Relaxed) })
+        })
+        .collect();
+    HttpResponse::Ok().json(serde_json::json!({ "backends": backends }))
+}
+
+#[actix_web::main]
+async fn main() -> ExitCode {
+    let Some(Args {
+        listen,
+        vnodes,
+        backends,
+    }) = Args::parse()
+    else {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    };
+    let router = web::Data::new(Router {
+        ring: Ring::new(&backends, vnodes),
+        requests: backends.iter().map(|_| AtomicU64::new(0)).collect(),
+        backends,
+        client: reqwest::Client::new(),
+    });
+    println!(
+        "Routing {listen} to {} backends: {}",
+        router.backends.len(),
+        router.backends.join(", ")
+    );
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(router.clone())
+            .service(get_router)
+            .default_service(web::to(route))
+    })
+    .bind(&listen);
+    match server {
+        Ok(server) => match server.run().await {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(err) => {
+                eprintln!("Router failed: {err}");
+                ExitCode::FAILURE
+            }
+        },
+        Err(err) => {
+            eprintln!("Fail to listen on {listen}: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,388 @@
+//! Replays a captured traffic file against two backends and reports where their responses diverge.
+//!
+//! Used to check that two implementations of the PerCom API (e.g. the Go and the Rust backend)
+//! behave the same before their performance is compared: every request of the traffic file is sent
+//! to both, one after the other, and their status codes and normalized bodies are compared.
+//!
+//! # Usage
+//! ```text
+//! cargo run --release --bin shadow-diff -- [--ignore <field>]... [--unordered] \
+//!     <traffic.jsonl> <base url> <base url>
+//! ```
+//!
+//! # Traffic file
+//! One request per line (JSON Lines), e.g. `scenarios/traffic.jsonl`:
+//! ```json
+//! {"method": "POST", "path": "/posts", "body": {"author": "a", "content": "hi"}, "capture": {"post": "/id"}}
+//! {"method": "GET", "path": "/posts/${post}", "headers": {"Accept-Language": "de"}}
+//! ```
+//! - `headers`: sent as they are; `Authorization: Bearer shadow-diff` is added unless given;
+//! - `body`: a JSON value sent as `application/json`, or a string sent as it is;
+//! - `capture`: names bound to the values at the given JSON pointers of the response. Every backend
+//!   keeps its own values, so `${name}` in the path, headers and body of later requests refers to
+//!   the resource each backend created itself.
+//!
+//! Blank lines and lines starting with `#` are skipped.
+//!
+//! # Normalization
+//! Before comparing, in the JSON bodies of both responses:
+//! - captured values are replaced by `${name}`, also within longer strings (e.g. `/posts/<id>`);
+//! - other UUIDs, which are generated and thus differ, are replaced by `<uuid>`;
+//! - RFC 3339 dates are rewritten with the shortest fraction of seconds, keeping their offset, so
+//!   `2024-01-01T00:00:00Z` equals `2024-01-01T00:00:00.000000Z`;
+//! - fields named with `--ignore` (e.g. `detail`, whose wording differs) are removed;
+//! - with `--unordered`, arrays are sorted, for lists whose order isn't specified.
+//!
+//! Bodies which aren't JSON are compared byte by byte.
+//!
+//! The tool exits with a failure if any request diverged.
+
+use chrono::{DateTime, SecondsFormat};
+use serde::Deserialize;
+use serde_json::Value;
+use std::{collections::BTreeMap, env, fmt, fs, path::PathBuf, process::ExitCode};
+use uuid::Uuid;
+
+/// Command line usage.
+const USAGE: &str =
+    "Usage: shadow-diff [--ignore <field>]... [--unordered] <traffic.jsonl> <base url> <base url>";
+
+/// Bearer token of requests without an `Authorization` header.
+const DEFAULT_TOKEN: &str = "shadow-diff";
+
+/// Parsed command line.
+struct Args {
+    traffic: PathBuf,
+    backends: [String; 2],
+    ignore: Vec<String>,
+    unordered: bool,
+}
+
+impl Args {
+    fn parse() -> Option<Self> {
+        let mut args = env::args().skip(1);
+        let mut ignore = Vec::new();
+        let mut unordered = false;
+        let mut positional = Vec::new();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--ignore" => ignore.push(args.next()?),
+                "--unordered" => unordered = true,
+                _ if arg.starts_with("--") => return None,
+                _ => positional.push(arg),
+            }
+        }
+        let [traffic, left, right] = <[String; 3]>::try_from(positional).ok()?;
+        Some(Self {
+            traffic: PathBuf::from(traffic),
+            backends: [left, right].map(|url| url.trim_end_matches('/').to_owned()),
+            ignore,
+            unordered,
+        })
+    }
+}
+
+/// Request of the traffic file (see the module documentation).
+#[derive(Debug, Deserialize)]
+struct Entry {
+    method: String,
+    path: String,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    #[serde(default)]
+    body: Option<Value>,
+    #[serde(default)]
+    capture: BTreeMap<String, String>,
+}
+
+/// Reads the requests of a traffic file, with their line numbers.
+fn read_traffic(path: &PathBuf) -> Result<Vec<(usize, Entry)>, String> {
+    let content = fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.display()))?;
+    content
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| (idx + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(line, entry)| {
+            serde_json::from_str(entry)
+                .map(|entry| (line, entry))
+                .map_err(|err| format!("{}:{line}: {err}", path.display()))
+        })
+        .collect()
+}
+
+/// Response of a backend, or the reason there is none.
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Response { status: u16, body: Body },
+    Failed(String),
+}
+
+/// Normalized body of a response.
+#[derive(Debug, PartialEq)]
+enum Body {
+    Json(Value),
+    Raw(Vec<u8>),
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Response { status, .. } => write!(f, "{status}"),
+            Self::Failed(err) => write!(f, "failed ({err})"),
+        }
+    }
+}
+
+/// One of the compared backends, with the values it returned for the captured names.
+struct Backend {
+    url: String,
+    captured: BTreeMap<String, String>,
+}
+
+impl Backend {
+    /// Replaces the `${name}` placeholders of `text` by the captured values.
+    fn substitute(&self, text: &str) -> String {
+        self.captured
+            .iter()
+            .fold(text.to_owned(), |text, (name, value)| {
+                text.replace(&format!("${{{name}}}"), value)
+            })
+    }
+
+    /// Sends `entry` and captures the values it names from the response.
+    async fn send(&mut self, client: &reqwest::Client, entry: &Entry) -> Outcome {
+        let method = match reqwest::Method::from_bytes(entry.method.as_bytes()) {
+            Ok(method) => method,
+            Err(err) => return Outcome::Failed(err.to_string()),
+        };
+        let url = format!("{}{}", self.url, self.substitute(&entry.path));
+        let mut request = client.request(method, url);
+        for (name, value) in entry.headers.iter() {
+            request = request.header(name, self.substitute(value));
+        }
+        if !entry
+            .headers
+            .keys()
+            .any(|name| name.eq_ignore_ascii_case("authorization"))
+        {
+            request = request.bearer_auth(DEFAULT_TOKEN);
+        }
+        match &entry.body {
+            None => {}
+            Some(Value::String(body)) => request = request.body(self.substitute(body)),
+            Some(body) => {
+                if !entry
+                    .headers
+                    .keys()
+                    .any(|name| name.eq_ignore_ascii_case("content-type"))
+                {
+                    request = request.header(reqwest::header::CONTENT_TYPE, "application/json");
+                }
+                request = request.body(self.substitute(&body.to_string()));
+            }
+        }
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(err) => return Outcome::Failed(err.to_string()),
+        };
+        let status = response.status().as_u16();
+        let bytes = match response.bytes().await {
+            Ok(bytes) => bytes,
+            Err(err) => return Outcome::Failed(err.to_string()),
+        };
+        let body = match serde_json::from_slice::<Value>(&bytes) {
+            Ok(json) => {
+                for (name, pointer) in entry.capture.iter() {
+                    let value = match json.pointer(pointer) {
+                        Some(Value::String(value)) => value.clone(),
+                        Some(value) => value.to_string(),
+                        None => continue,
+                    };
+                    self.captured.insert(name.clone(), value);
+                }
+                Body::Json(json)
+            }
+            Err(_) => Body::Raw(bytes.to_vec()),
+        };
+        Outcome::Response { status, body }
+    }
+}
+
+/// Normalization of the JSON bodies (see the module documentation).
+struct Normalizer<'a> {
+    ignore: &'a [String],
+    unordered: bool,
+}
+
+impl Normalizer<'_> {
+    /// Normalizes a body returned by `backend`.
+    fn normalize(&self, value: Value, backend: &Backend) -> Value {
+        match value {
+            Value::String(text) => Value::String(self.normalize_str(text, backend)),
+            Value::Array(items) => {
+                let mut items: Vec<Value> = items
+                    .into_iter()
+                    .map(|item| self.normalize(item, backend))
+                    .collect();
+                if self.unordered {
+                    items.sort_by_cached_key(Value::to_string);
+                }
+                Value::Array(items)
+            }
+            Value::Object(fields) => Value::Object(
+                fields
+                    .into_iter()
+                    .filter(|(name, _)| !self.ignore.contains(name))
+                    .map(|(name, value)| (name, self.normalize(value, backend)))
+                    .collect(),
+            ),
+            value => value,
+        }
+    }
+
+    fn normalize_str(&self, text: String, backend: &Backend) -> String {
+        let text = backend
+            .captured
+            .iter()
+            .filter(|(_, value)| !value.is_empty())
+            .fold(text, |text, (name, value)| {
+                text.replace(value.as_str(), &format!("${{{name}}}"))
+            });
+        if Uuid::parse_str(&text).is_ok() {
+            return "<uuid>".to_owned();
+        }
+        match DateTime::parse_from_rfc3339(&text) {
+            Ok(date) => date.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            Err(_) => text,
+        }
+    }
+}
+
+/// Returns the JSON pointer of the first difference between `left` and `right`, with both values
+/// there.
+fn first_difference<'a>(
+    left: &'a Value,
+    right: &'a Value,
+    pointer: String,
+) -> Option<(String, Option<&'a Value>, Option<&'a Value>)> {
+    match (left, right) {
+        (Value::Object(left), Value::Object(right)) => {
+            let mut names: Vec<&String> = left.keys().chain(right.keys()).collect();
+            names.sort();
+            names.dedup();
+            names.into_iter().find_map(|name| {
+                let field = format!("{pointer}/{}", name.replace('~', "~0").replace('/', "~1"));
+                match (left.get(name), right.get(name)) {
+                    (Some(left), Some(right)) => first_difference(left, right, field),
+                    (left, right) => Some((field, left, right)),
+                }
+            })
+        }
+        (Value::Array(left), Value::Array(right)) => {
+            (0..left.len().max(right.len())).find_map(|idx| {
+                let item = format!("{pointer}/{idx}");
+                match (left.get(idx), right.get(idx)) {
+                    (Some(left), Some(right)) => first_difference(left, right, item),
+                    (left, right) => Some((item, left, right)),
+                }
+            })
+        }
+        (left, right) if left == right => None,
+        (left, right) => Some((pointer, Some(left), Some(right))),
+    }
+}
+
+/// Describes how `left` and `right` diverge, or returns `None` if they don't.
+fn divergence(left: &Outcome, right: &Outcome) -> Option<String> {
+    let (
+        Outcome::Response {
+            status: left_status,
+            body: left_body,
+        },
+        Outcome::Response {
+            status: right_status,
+            body: right_body,
+        },
+    ) = (left, right)
+    else {
+        return Some(format!("{left} != {right}"));
+    };
+    if left_status != right_status {
+        return Some(format!("status {left_status} != {right_status}"));
+    }
+    match (left_body, right_body) {
+        (Body::Json(left), Body::Json(right)) => {
+            let (pointer, left, right) = first_difference(left, right, String::new())?;
+            let show =
+                |value: Option<&Value>| value.map_or("(missing)".to_owned(), Value::to_string);
+            let pointer = if pointer.is_empty() { "/" } else { &pointer };
+            Some(format!(
+                "body differs at {pointer}: {} != {}",
+                show(left),
+                show(right)
+            ))
+        }
+        (Body::Raw(left), Body::Raw(right)) if left == right => None,
+        (Body::Raw(left), Body::Raw(right)) => Some(format!(
+            "body differs ({} != {} bytes)",
+            left.len(),
+            right.len()
+        )),
+        _ => Some("body is JSON on one side only".to_owned()),
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let Some(args) = Args::parse() else {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    };
+    let traffic = match read_traffic(&args.traffic) {
+        Ok(traffic) => traffic,
+        Err(err) => {
+            eprintln!("Fail to read traffic file {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let client = reqwest::Client::new();
+    let normalizer = Normalizer {
+        ignore: &args.ignore,
+        unordered: args.unordered,
+    };
+    let [mut left, mut right] = args.backends.map(|url| Backend {
+        url,
+        captured: BTreeMap::new(),
+    });
+    println!(
+        "Replaying {} requests against {} and {}",
+        traffic.len(),
+        left.url,
+        right.url
+    );
+    let mut diverged = 0;
+    for (line, entry) in traffic.iter() {
+        let (mut left_outcome, mut right_outcome) =
+            tokio::join!(left.send(&client, entry), right.send(&client, entry));
+        for (outcome, backend) in [(&mut left_outcome, &left), (&mut right_outcome, &right)] {
+            if let Outcome::Response {
+                body: Body::Json(json),
+                ..
+            } = outcome
+            {
+                *json = normalizer.normalize(json.take(), backend);
+            }
+        }
+        if let Some(divergence) = divergence(&left_outcome, &right_outcome) {
+            println!("line {line}: {} {}: {divergence}", entry.method, entry.path);
+            diverged += 1;
+        }
+    }
+    println!("{diverged} of {} requests diverged", traffic.len());
+    if diverged == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
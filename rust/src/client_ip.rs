@@ -0,0 +1,231 @@
+//! Client addresses of requests which may arrive through reverse proxies.
+//!
+//! Behind a proxy (e.g. nginx in the benchmark rig), the peer of every connection is the proxy
+//! itself, and the address of the client is only known from the `Forwarded` (RFC 7239) or
+//! `X-Forwarded-For` headers the proxy adds. Anybody can send these headers though, so they're
+//! only believed when the peer is one of the [`TrustedProxies`] configured with
+//! `RUST_SERVER_TRUSTED_PROXIES`, and only the one the proxies rewrite (see [`ForwardedHeader`]):
+//! a proxy passes the other one on as the client sent it. Handlers and middlewares get the result
+//! with [`ClientIp::of`].
+
+use actix_web::{HttpMessage, HttpRequest, http::header::HeaderMap, web};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+use std::{fmt, net::IpAddr, str::FromStr};
+
+use crate::state::GlobalServerState;
+
+/// Range of IP addresses in CIDR notation (`10.0.0.0/8`, `fd00::/8`); a bare address is a range of
+/// a single address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cidr {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    /// Returns `true` if `ip` is in the range. IPv4 addresses mapped to IPv6 (`::ffff:a.b.c.d`)
+    /// count as IPv4.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip.to_canonical()) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for Cidr {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix) = value.trim().split_once('/').unwrap_or((value.trim(), ""));
+        let addr = addr
+            .parse::<IpAddr>()
+            .map_err(|_| format!("invalid address in {value:?}"))?
+            .to_canonical();
+        let bits = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = if prefix.is_empty() {
+            bits
+        } else {
+            prefix
+                .parse()
+                .ok()
+                .filter(|prefix| *prefix <= bits)
+                .ok_or_else(|| format!("invalid prefix length in {value:?}"))?
+        };
+        Ok(Self { addr, prefix })
+    }
+}
+
+impl fmt::Display for Cidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+
+/// Serialized as a string in CIDR notation.
+impl Serialize for Cidr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Cidr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+/// Forwarding header the trusted proxies append to (`RUST_SERVER_FORWARDED_HEADER`).
+///
+/// Only this header is believed: a proxy appending to `X-Forwarded-For` passes a `Forwarded`
+/// header of the client through unchanged (and the other way around), so trusting both would let
+/// clients pick their address.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ForwardedHeader {
+    /// `X-Forwarded-For`, as set by nginx's `$proxy_add_x_forwarded_for`.
+    #[default]
+    XForwardedFor,
+
+    /// `Forwarded` (RFC 7239).
+    Forwarded,
+}
+
+impl ForwardedHeader {
+    /// Returns the header named `x-forwarded-for` or `forwarded`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "x-forwarded-for" => Some(Self::XForwardedFor),
+            "forwarded" => Some(Self::Forwarded),
+            _ => None,
+        }
+    }
+}
+
+/// Proxies whose forwarding headers are believed.
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies {
+    ranges: Vec<Cidr>,
+    header: ForwardedHeader,
+}
+
+impl TrustedProxies {
+    /// Parses a comma-separated list of CIDR ranges, e.g. `127.0.0.1,10.0.0.0/8`, of proxies
+    /// appending to `header`. An empty list trusts nobody, so forwarding headers are ignored.
+    pub fn from_spec(spec: &str, header: ForwardedHeader) -> Result<Self, String> {
+        let ranges = spec
+            .split(',')
+            .filter(|range| !range.trim().is_empty())
+            .map(str::parse)
+            .collect::<Result<_, _>>()?;
+        Ok(Self { ranges, header })
+    }
+
+    /// Returns `true` if `ip` belongs to a trusted proxy.
+    pub fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.ranges.iter().any(|range| range.contains(ip))
+    }
+
+    /// Returns the address of the client of a request received from `peer` with `headers`.
+    ///
+    /// Every proxy appends the address it received the request from, so the chain of addresses is
+    /// walked from the right, from the peer on, as long as the addresses belong to trusted proxies:
+    /// the first untrusted one is the client. Entries further left were written by the client or
+    /// an untrusted proxy and can't be believed. If the chain ends (or holds an entry which isn't
+    /// an address, like `unknown`), the last trusted address is the best one known. Only the
+    /// [`ForwardedHeader`] of the proxies is read.
+    pub fn resolve(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        let mut client = peer.to_canonical();
+        if !self.is_trusted(client) {
+            return client;
+        }
+        for hop in forwarded_chain(headers, self.header).iter().rev() {
+            let Some(ip) = parse_node(hop) else {
+                break;
+            };
+            client = ip;
+            if !self.is_trusted(ip) {
+                break;
+            }
+        }
+        client
+    }
+}
+
+/// Returns the forwarded-for addresses of `header` in `headers`, leftmost (the original client)
+/// first. Several header lines form a single list, in order.
+fn forwarded_chain(headers: &HeaderMap, header: ForwardedHeader) -> Vec<String> {
+    let values = |name: &str| {
+        headers
+            .get_all(name)
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .collect::<Vec<_>>()
+    };
+    if header == ForwardedHeader::XForwardedFor {
+        return values("x-forwarded-for")
+            .into_iter()
+            .map(str::to_owned)
+            .collect();
+    }
+    // Elements without a `for` parameter say nothing about the client; they're kept as
+    // unparsable entries, which stop the walk
+    values("forwarded")
+        .into_iter()
+        .map(|element| {
+            element
+                .split(';')
+                .filter_map(|pair| pair.split_once('='))
+                .find(|(name, _)| name.trim().eq_ignore_ascii_case("for"))
+                .map_or(String::new(), |(_, value)| {
+                    value.trim().trim_matches('"').to_owned()
+                })
+        })
+        .collect()
+}
+
+/// Parses a node of a forwarding header: an IPv4 address, an IPv6 address (bracketed in
+/// `Forwarded`), either with an optional port. Obfuscated identifiers and `unknown` give `None`.
+fn parse_node(node: &str) -> Option<IpAddr> {
+    let addr = match node.strip_prefix('[') {
+        Some(rest) => rest.split_once(']')?.0,
+        None if node.matches(':').count() == 1 => node.split_once(':')?.0,
+        None => node,
+    };
+    addr.parse::<IpAddr>().ok().map(|ip| ip.to_canonical())
+}
+
+/// Client address of a request, cached in its extensions.
+#[derive(Debug, Clone, Copy)]
+pub struct ClientIp(pub IpAddr);
+
+impl ClientIp {
+    /// Returns the address of the client which sent `req`, resolved with the trusted proxies of
+    /// [`GlobalServerState`] (see [`TrustedProxies::resolve`]), or `None` if the peer address is
+    /// unknown (e.g. in test requests).
+    pub fn of(req: &HttpRequest) -> Option<IpAddr> {
+        if let Some(ClientIp(ip)) = req.extensions().get::<ClientIp>() {
+            return Some(*ip);
+        }
+        let peer = req.peer_addr()?.ip();
+        let ip = match req.app_data::<web::Data<GlobalServerState>>() {
+            Some(state) => state.trusted_proxies.resolve(peer, req.headers()),
+            None => peer.to_canonical(),
+        };
+        req.extensions_mut().insert(ClientIp(ip));
+        Some(ip)
+    }
+}
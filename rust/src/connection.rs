@@ -0,0 +1,206 @@
+//! Detection of clients disconnecting while their request is handled.
+//!
+//! actix doesn't read from an HTTP/1 connection while a handler runs, so a client going away goes
+//! unnoticed until the response is written. [`on_connect`] keeps a duplicate handle of the socket
+//! of every connection; a handler waiting on slow work can get it with [`Peer::of`] and check the
+//! socket for end-of-stream without consuming any data.
+//!
+//! Streaming responses are wrapped with [`watch`], which releases the stream as soon as the
+//! client disconnects.
+//!
+//! Connections of clients which stop reading responses are dropped after the write timeout (see
+//! [`write_timeout`]).
+
+use actix_web::{HttpRequest, dev::Extensions, rt};
+use futures_util::Stream;
+use std::{
+    any::Any,
+    io,
+    net::TcpStream,
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard, Weak},
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+use crate::{middleware::run_id::ConnectionRun, state::Metrics};
+
+/// How often [`Peer::gone`] checks the connection.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Client side of the connection a request arrived on.
+#[derive(Clone)]
+pub struct Peer(Arc<TcpStream>);
+
+/// Connection hook registered with `HttpServer::on_connect` (see [`write_timeout`]): stores a
+/// [`Peer`] in the connection data, along with the slot of its benchmark run (see
+/// [`ConnectionRun`]). No [`Peer`] is stored for connections other than plain TCP, or on
+/// platforms without file descriptors.
+pub fn on_connect(connection: &dyn Any, data: &mut Extensions) {
+    ConnectionRun::attach(data);
+    #[cfg(unix)]
+    if let Some(stream) = connection.downcast_ref::<tokio::net::TcpStream>() {
+        use std::os::fd::AsFd;
+        // The duplicate shares the socket, including its non-blocking mode
+        match stream.as_fd().try_clone_to_owned() {
+            Ok(fd) => {
+                data.insert(Peer(Arc::new(TcpStream::from(fd))));
+            }
+            Err(err) => tracing::warn!("Failed to duplicate connection socket: {err}"),
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = (connection, data);
+}
+
+/// Returns a connection hook doing [`on_connect`] and, if `timeout` is set, dropping the
+/// connection once data sent to the client stays unacknowledged for longer, e.g. because the
+/// client stopped reading (`TCP_USER_TIMEOUT`, Linux only).
+pub fn write_timeout(
+    timeout: Option<Duration>,
+) -> impl Fn(&dyn Any, &mut Extensions) + Send + Sync + 'static {
+    move |connection, data| {
+        #[cfg(any(target_os = "linux", target_os = "android", target_os = "fuchsia"))]
+        if let (Some(timeout), Some(stream)) =
+            (timeout, connection.downcast_ref::<tokio::net::TcpStream>())
+            && let Err(err) = socket2::SockRef::from(stream).set_tcp_user_timeout(Some(timeout))
+        {
+            tracing::warn!("Failed to set the write timeout of a connection: {err}");
+        }
+        #[cfg(not(any(target_os = "linux", target_os = "android", target_os = "fuchsia")))]
+        let _ = timeout;
+        on_connect(connection, data);
+    }
+}
+
+impl Peer {
+    /// Returns the peer of the connection `req` arrived on, if it's known.
+    pub fn of(req: &HttpRequest) -> Option<Self> {
+        req.conn_data::<Self>().cloned()
+    }
+
+    /// Returns `true` if the client closed or reset the connection.
+    ///
+    /// Pending data, e.g. a pipelined request, means the client is still there. A client which
+    /// only shut down its sending side is reported as gone, too.
+    pub fn is_gone(&self) -> bool {
+        match self.0.peek(&mut [0; 1]) {
+            Ok(0) => true,
+            Ok(_) => false,
+            Err(err) => !matches!(
+                err.kind(),
+                io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted
+            ),
+        }
+    }
+
+    /// Completes once the client is gone (see [`Peer::is_gone`]); never completes otherwise.
+    pub async fn gone(&self) {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            if self.is_gone() {
+                return;
+            }
+        }
+    }
+}
+
+/// Stream of a response body which is released once the client disconnects.
+///
+/// Created with [`watch`]. Counts itself in the `streams.open` gauge while alive and in the
+/// `streams.abandoned` counter if it's dropped before it's finished.
+pub struct Watched<S> {
+    slot: Arc<Mutex<Slot<S>>>,
+
+    metrics: Arc<Metrics>,
+}
+
+/// State of a [`Watched`] stream, shared with the task watching the connection.
+struct Slot<S> {
+    /// The wrapped stream; `None` once it's finished or released.
+    stream: Option<Pin<Box<S>>>,
+
+    /// Waker of the pending poll, woken on release so the body ends without waiting for the
+    /// wrapped stream.
+    waker: Option<Waker>,
+}
+
+/// Wraps the body `stream` of a response to `req`, so it's dropped as soon as the client
+/// disconnects, together with everything it holds (iterators, subscriptions, etc.).
+///
+/// actix itself drops a body only after writing to the connection fails, which doesn't happen
+/// while the socket buffers are full or the stream is waiting for data. A background task checks
+/// the connection instead (see [`Peer::is_gone`]); after a disconnect, the body ends early.
+pub fn watch<S: Stream + 'static>(
+    req: &HttpRequest,
+    stream: S,
+    metrics: Arc<Metrics>,
+) -> Watched<S> {
+    let slot = Arc::new(Mutex::new(Slot {
+        stream: Some(Box::pin(stream)),
+        waker: None,
+    }));
+    Metrics::inc(&metrics.streams_open);
+    if let Some(peer) = Peer::of(req) {
+        let (slot, metrics) = (Arc::downgrade(&slot), metrics.clone());
+        rt::spawn(async move {
+            let mut interval = tokio::time::interval(POLL_INTERVAL);
+            loop {
+                interval.tick().await;
+                // The response is gone, finished or not
+                let Some(slot) = Weak::upgrade(&slot) else {
+                    return;
+                };
+                if peer.is_gone() {
+                    tracing::debug!("Client disconnected, releasing the response stream");
+                    release(&slot, &metrics);
+                    return;
+                }
+            }
+        });
+    }
+    Watched { slot, metrics }
+}
+
+/// Locks the slot. It's only ever locked for a single poll or release, so a poisoned lock can't
+/// leave it in an inconsistent state.
+fn lock<S>(slot: &Mutex<Slot<S>>) -> MutexGuard<'_, Slot<S>> {
+    slot.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Drops the wrapped stream, counting it as abandoned unless it's already finished.
+fn release<S>(slot: &Mutex<Slot<S>>, metrics: &Metrics) {
+    let mut slot = lock(slot);
+    if slot.stream.take().is_some() {
+        Metrics::inc(&metrics.streams_abandoned);
+    }
+    if let Some(waker) = slot.waker.take() {
+        waker.wake();
+    }
+}
+
+impl<S: Stream> Stream for Watched<S> {
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut slot = lock(&self.slot);
+        let Some(stream) = slot.stream.as_mut() else {
+            return Poll::Ready(None);
+        };
+        let item = stream.as_mut().poll_next(cx);
+        match item {
+            Poll::Ready(None) => slot.stream = None,
+            Poll::Ready(Some(_)) => slot.waker = None,
+            Poll::Pending => slot.waker = Some(cx.waker().clone()),
+        }
+        item
+    }
+}
+
+impl<S> Drop for Watched<S> {
+    fn drop(&mut self) {
+        release(&self.slot, &self.metrics);
+        Metrics::dec(&self.metrics.streams_open);
+    }
+}
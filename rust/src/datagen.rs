@@ -0,0 +1,187 @@
+use chrono::{DateTime, Duration, Utc};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use rand_distr::{Distribution, LogNormal, Zipf, weighted::WeightedIndex};
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+};
+use uuid::Builder;
+
+use crate::scheme::posts::{Post, PostStatus, versions::Stored};
+
+/// Command line usage of `server gen-dataset`.
+const USAGE: &str =
+    "Usage: server gen-dataset <out.jsonl> [--posts <n>] [--authors <n>] [--days <n>] [--seed <n>]";
+
+/// Default number of generated posts.
+const DEFAULT_POSTS: usize = 100_000;
+
+/// Default number of distinct authors.
+const DEFAULT_AUTHORS: usize = 1_000;
+
+/// Default length of the covered period, in days, ending now.
+const DEFAULT_DAYS: i64 = 365;
+
+/// Exponent of the Zipf distribution of authors and words; around `1` for social content, where a
+/// few authors write most of the posts.
+const ZIPF_EXPONENT: f64 = 1.1;
+
+/// Median content length, in characters.
+const MEDIAN_CONTENT_LEN: f64 = 200.0;
+
+/// Spread of content lengths (sigma of the underlying normal distribution): most posts are short,
+/// a long tail is several times longer.
+const CONTENT_LEN_SIGMA: f64 = 1.0;
+
+/// Upper bound of content length, in characters.
+const MAX_CONTENT_LEN: usize = 10_000;
+
+/// Relative posting activity per hour of the day (UTC): quiet at night, peaking in the evening.
+const HOURLY_ACTIVITY: [u32; 24] = [
+    3, 2, 1, 1, 1, 2, 4, 6, 8, 9, 10, 10, 11, 10, 10, 10, 11, 12, 14, 16, 16, 14, 10, 6,
+];
+
+/// Words content is made of, most frequent first (picked with a Zipf distribution).
+const WORDS: &str = "the a to and of in is it for on this that with was just today new post like time \
+    people really good great day think rust server latency benchmark release coffee weekend project \
+    update thread question idea music travel photo code review deploy cache memory async runtime";
+
+/// Options of the generated dataset.
+struct Options {
+    /// Path of the JSON Lines file to write.
+    out: String,
+    posts: usize,
+    authors: usize,
+    days: i64,
+
+    /// Seed of the random generator; the same seed produces the same dataset.
+    seed: u64,
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Option<Self> {
+        let mut options = Self {
+            out: String::new(),
+            posts: DEFAULT_POSTS,
+            authors: DEFAULT_AUTHORS,
+            days: DEFAULT_DAYS,
+            seed: 0,
+        };
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--posts" => options.posts = args.next()?.parse().ok()?,
+                "--authors" => options.authors = args.next()?.parse().ok()?,
+                "--days" => options.days = args.next()?.parse().ok()?,
+                "--seed" => options.seed = args.next()?.parse().ok()?,
+                _ if options.out.is_empty() => options.out = arg,
+                _ => return None,
+            }
+        }
+        (!options.out.is_empty() && options.authors > 0 && options.days > 0).then_some(options)
+    }
+}
+
+/// Random distributions the dataset is drawn from.
+struct Generator {
+    rng: StdRng,
+    authors: Zipf<f64>,
+    words: Vec<&'static str>,
+    word: Zipf<f64>,
+    content_len: LogNormal<f64>,
+    hours: WeightedIndex<u32>,
+    days: i64,
+    now: DateTime<Utc>,
+}
+
+impl Generator {
+    fn new(options: &Options) -> Result<Self, String> {
+        Ok(Self {
+            rng: StdRng::seed_from_u64(options.seed),
+            authors: Zipf::new(options.authors as f64, ZIPF_EXPONENT)
+                .map_err(|err| err.to_string())?,
+            words: WORDS.split_whitespace().collect(),
+            word: Zipf::new(WORDS.split_whitespace().count() as f64, ZIPF_EXPONENT)
+                .map_err(|err| err.to_string())?,
+            content_len: LogNormal::new(MEDIAN_CONTENT_LEN.ln(), CONTENT_LEN_SIGMA)
+                .map_err(|err| err.to_string())?,
+            hours: WeightedIndex::new(HOURLY_ACTIVITY).map_err(|err| err.to_string())?,
+            days: options.days,
+            now: Utc::now(),
+        })
+    }
+
+    /// Draws a creation time: a random day of the period, an hour following [`HOURLY_ACTIVITY`]
+    /// and a random moment within that hour. Times in the future are moved a day back.
+    fn date(&mut self) -> DateTime<Utc> {
+        let day = self.now.date_naive() - Duration::days(self.rng.random_range(0..self.days));
+        let hour = self.hours.sample(&mut self.rng) as u32;
+        let date = day
+            .and_hms_opt(hour, 0, 0)
+            .expect("Hour is within a day")
+            .and_utc()
+            + Duration::seconds(self.rng.random_range(0..3600));
+        if date > self.now {
+            date - Duration::days(1)
+        } else {
+            date
+        }
+    }
+
+    /// Draws content of a log-normally distributed length made of Zipf-distributed words.
+    fn content(&mut self) -> String {
+        let len = (self.content_len.sample(&mut self.rng) as usize).clamp(1, MAX_CONTENT_LEN);
+        let mut content = String::with_capacity(len + 16);
+        while content.len() < len {
+            if !content.is_empty() {
+                content.push(' ');
+            }
+            content.push_str(self.words[self.word.sample(&mut self.rng) as usize - 1]);
+        }
+        content.truncate(len);
+        content
+    }
+
+    fn post(&mut self) -> Post {
+        Post {
+            // Drawn from the seeded generator, so IDs are reproducible too
+            id: Builder::from_random_bytes(self.rng.random())
+                .into_uuid()
+                .to_string(),
+            author: format!("author-{}", self.authors.sample(&mut self.rng) as u64),
+            date: self.date().fixed_offset(),
+            content: self.content(),
+            status: PostStatus::Published,
+            publish_at: None,
+        }
+    }
+}
+
+/// Generates a posts dataset with realistic distributions (`server gen-dataset`) and writes it as
+/// JSON Lines, ready for `POST /admin/posts/import`.
+///
+/// Uniform random data hides cache effects, so the dataset mimics real traffic instead:
+/// - authors follow a Zipf distribution (`author-1` writes the most posts);
+/// - content lengths are log-normal around [`MEDIAN_CONTENT_LEN`] characters, with frequent words
+///   repeating as in natural text;
+/// - creation times span the last `--days` days with a daily activity cycle.
+///
+/// Posts are written ordered by date. The output only depends on the options (and the current
+/// date), so a `--seed` reproduces a dataset.
+pub fn run(args: impl Iterator<Item = String>) -> io::Result<()> {
+    let options = Options::parse(args).ok_or_else(|| io::Error::other(USAGE))?;
+    let mut generator = Generator::new(&options).map_err(io::Error::other)?;
+    let mut posts: Vec<Post> = (0..options.posts).map(|_| generator.post()).collect();
+    posts.sort_unstable_by(|a, b| a.date.cmp(&b.date).then_with(|| a.id.cmp(&b.id)));
+    let count = posts.len();
+    let mut file = BufWriter::new(File::create(&options.out)?);
+    for post in posts {
+        serde_json::to_writer(&mut file, &Stored(post)).map_err(io::Error::other)?;
+        file.write_all(b"\n")?;
+    }
+    file.flush()?;
+    println!(
+        "Generated {count} posts of {} authors into {}",
+        options.authors, options.out
+    );
+    Ok(())
+}
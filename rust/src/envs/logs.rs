@@ -1,5 +1,5 @@
 use chrono::prelude::*;
-use std::io;
+use std::{io, path::PathBuf};
 use tracing::debug;
 use tracing_appender::non_blocking::WorkerGuard;
 use tracing_subscriber::{EnvFilter, fmt};
@@ -17,18 +17,19 @@ use crate::*;
 /// the default level is `debug`.
 ///
 /// # Returns
-/// Returns a `WorkerGuard` that must be held for the duration of the program to ensure proper flushing of log data.
+/// Returns a `WorkerGuard` that must be held for the duration of the program to ensure proper flushing of log data,
+/// along with the path of the log file.
 ///
 /// # Errors
 /// Returns an `io::Result::Err` if the log directory path cannot be determined or if any other I/O error occurs.
 ///
 /// # Panics
 /// Will panic if the `EnvFilter` cannot be created from the environment and the fallback filter creation fails.
-pub fn init() -> io::Result<WorkerGuard> {
+pub fn init() -> io::Result<(WorkerGuard, PathBuf)> {
     let path = envs::paths::get_logs()?;
     let now = Utc::now();
     let filename = now.format("%Y%m%dT%H%M%S.logs").to_string();
-    let file_appender = tracing_appender::rolling::never(&path, filename);
+    let file_appender = tracing_appender::rolling::never(&path, &filename);
     let (non_blocking, guard) = tracing_appender::non_blocking(file_appender);
     fmt()
         .with_writer(non_blocking)
@@ -37,5 +38,5 @@ pub fn init() -> io::Result<WorkerGuard> {
         )
         .init();
     debug!("Log is inited at {}", now.to_rfc2822());
-    Ok(guard)
+    Ok((guard, path.join(filename)))
 }
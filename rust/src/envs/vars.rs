@@ -1,4 +1,6 @@
-use std::{env, net::SocketAddr};
+use std::{env, io, net::SocketAddr, path::PathBuf, time::Duration};
+
+use crate::{envs::paths::get_home, scheme::retry::RetryPolicy};
 
 /// Name of the environment variable used to configure the server's bind address.
 const RUST_SERVER_ADDR_ENVVAR: &str = "RUST_SERVER_ADDR";
@@ -24,6 +26,825 @@ pub fn get_server_addr() -> std::io::Result<SocketAddr> {
         .map_err(|err| std::io::Error::other(err.to_string()))
 }
 
+/// Name of the environment variable selecting the notifier used by the job queue (`log` or `smtp`).
+const RUST_SERVER_NOTIFIER_ENVVAR: &str = "RUST_SERVER_NOTIFIER";
+
+/// Name of the environment variable configuring the number of job queue workers.
+const RUST_SERVER_JOB_WORKERS_ENVVAR: &str = "RUST_SERVER_JOB_WORKERS";
+
+/// Default number of job queue workers.
+const RUST_SERVER_DEFAULT_JOB_WORKERS: usize = 2;
+
+/// Name of the environment variable configuring how many jobs may wait in the queue.
+const RUST_SERVER_JOB_QUEUE_CAPACITY_ENVVAR: &str = "RUST_SERVER_JOB_QUEUE_CAPACITY";
+
+/// Default capacity of the job queue.
+const RUST_SERVER_DEFAULT_JOB_QUEUE_CAPACITY: usize = 1024;
+
+/// Name of the environment variable configuring how often (in milliseconds) scheduled posts are checked.
+const RUST_SERVER_SCHEDULER_INTERVAL_ENVVAR: &str = "RUST_SERVER_SCHEDULER_INTERVAL_MS";
+
+/// Default interval of the post publication scheduler, in milliseconds.
+const RUST_SERVER_DEFAULT_SCHEDULER_INTERVAL: usize = 1000;
+
+/// Name of the environment variable selecting the posts provider (`memory`, `wal` or `tiered`).
+const RUST_SERVER_POSTS_PROVIDER_ENVVAR: &str = "RUST_SERVER_POSTS_PROVIDER";
+
+/// Name of the environment variable with the number of server workers.
+const RUST_SERVER_WORKERS_ENVVAR: &str = "RUST_SERVER_WORKERS";
+
+/// Name of the environment variable selecting whether server workers share the posts provider
+/// (`shared`) or each get a shard of their own (`worker-local`).
+const RUST_SERVER_STATE_MODE_ENVVAR: &str = "RUST_SERVER_STATE_MODE";
+
+/// Name of the environment variable making the TCP listener accept HTTP/2 without TLS (if set to
+/// `1`).
+const RUST_SERVER_H2C_ENVVAR: &str = "RUST_SERVER_H2C";
+
+/// Name of the environment variable selecting what happens to trailing and repeated slashes of
+/// paths (`trim`, `merge` or `strict`).
+const RUST_SERVER_TRAILING_SLASH_ENVVAR: &str = "RUST_SERVER_TRAILING_SLASH";
+
+/// Name of the environment variable making the first segment of paths case-insensitive (if set to
+/// `1`).
+const RUST_SERVER_LOWERCASE_PATHS_ENVVAR: &str = "RUST_SERVER_LOWERCASE_PATHS";
+
+/// Name of the environment variable configuring what happens to unknown query parameters.
+const RUST_SERVER_QUERY_PARAMS_ENVVAR: &str = "RUST_SERVER_QUERY_PARAMS";
+
+/// Name of the environment variable with the routing policy of the `tiered` posts provider.
+const RUST_SERVER_TIER_ROUTING_ENVVAR: &str = "RUST_SERVER_TIER_ROUTING";
+
+/// Default routing policy of the `tiered` posts provider: posts of the last day are hot.
+const RUST_SERVER_DEFAULT_TIER_ROUTING: &str = "age:86400000";
+
+/// Name of the environment variable configuring how often (in milliseconds) aged posts are moved
+/// to the cold tier.
+const RUST_SERVER_TIER_DEMOTION_INTERVAL_ENVVAR: &str = "RUST_SERVER_TIER_DEMOTION_INTERVAL_MS";
+
+/// Default interval of the demotion of aged posts, in milliseconds.
+const RUST_SERVER_DEFAULT_TIER_DEMOTION_INTERVAL: usize = 1000;
+
+/// Name of the environment variable selecting the compression of stored post content (`none`,
+/// `lz4` or `zstd`).
+const RUST_SERVER_CONTENT_COMPRESSION_ENVVAR: &str = "RUST_SERVER_CONTENT_COMPRESSION";
+
+/// Name of the environment variable with the path of the posts WAL file.
+const RUST_SERVER_WAL_PATH_ENVVAR: &str = "RUST_SERVER_WAL_PATH";
+
+/// Default name of the posts WAL file, relative to the application directory.
+const RUST_SERVER_DEFAULT_WAL_FILE: &str = "posts.wal";
+
+/// Name of the environment variable with the path of the store of the `kv` posts provider.
+const RUST_SERVER_KV_PATH_ENVVAR: &str = "RUST_SERVER_KV_PATH";
+
+/// Default file name of the store of the `kv` posts provider, in the application directory.
+const RUST_SERVER_DEFAULT_KV_FILE: &str = "posts.redb";
+
+/// Name of the environment variable with the number of posts whose JSON is cached.
+const RUST_SERVER_POST_CACHE_SIZE_ENVVAR: &str = "RUST_SERVER_POST_CACHE_SIZE";
+
+/// Name of the environment variable configuring how often (in milliseconds) the posts WAL is compacted.
+const RUST_SERVER_WAL_COMPACTION_INTERVAL_ENVVAR: &str = "RUST_SERVER_WAL_COMPACTION_INTERVAL_MS";
+
+/// Default interval of the posts WAL compaction, in milliseconds.
+const RUST_SERVER_DEFAULT_WAL_COMPACTION_INTERVAL: usize = 60_000;
+
+/// Name of the environment variable configuring how many WAL records are flushed together.
+const RUST_SERVER_WAL_BATCH_SIZE_ENVVAR: &str = "RUST_SERVER_WAL_BATCH_SIZE";
+
+/// Name of the environment variable configuring how often (in milliseconds) pending WAL records are
+/// flushed in batched mode.
+const RUST_SERVER_WAL_BATCH_INTERVAL_ENVVAR: &str = "RUST_SERVER_WAL_BATCH_MS";
+
+/// Default interval of WAL flushes in batched mode, in milliseconds.
+const RUST_SERVER_DEFAULT_WAL_BATCH_INTERVAL: usize = 5;
+
+/// Name of the environment variable enabling idempotent `DELETE /posts/{id}` (if set to `1`).
+const RUST_SERVER_IDEMPOTENT_DELETE_ENVVAR: &str = "RUST_SERVER_IDEMPOTENT_DELETE";
+
+/// Name of the environment variable selecting what happens to unsafe post content (`off`, `clean`
+/// or `reject`).
+const RUST_SERVER_SANITIZE_ENVVAR: &str = "RUST_SERVER_SANITIZE";
+
+/// Name of the environment variable making the sanitizer treat HTML as unsafe (if set to `1`).
+const RUST_SERVER_SANITIZE_HTML_ENVVAR: &str = "RUST_SERVER_SANITIZE_HTML";
+
+/// Name of the environment variable with the comma-separated keywords of rejected post content.
+const RUST_SERVER_MODERATION_REJECT_ENVVAR: &str = "RUST_SERVER_MODERATION_REJECT";
+
+/// Name of the environment variable with the comma-separated keywords of flagged post content.
+const RUST_SERVER_MODERATION_FLAG_ENVVAR: &str = "RUST_SERVER_MODERATION_FLAG";
+
+/// Name of the environment variable configuring the precision of dates (`millis` or `micros`).
+const RUST_SERVER_DATE_PRECISION_ENVVAR: &str = "RUST_SERVER_DATE_PRECISION";
+
+/// Name of the environment variable configuring the maximum size of request bodies, in bytes.
+const RUST_SERVER_MAX_BODY_SIZE_ENVVAR: &str = "RUST_SERVER_MAX_BODY_SIZE";
+
+/// Default maximum size of request bodies (2 MiB, the default of `web::JsonConfig`).
+const RUST_SERVER_DEFAULT_MAX_BODY_SIZE: usize = 2 * 1024 * 1024;
+
+/// Name of the environment variable configuring the deadline of provider calls of a request, in
+/// milliseconds.
+const RUST_SERVER_PROVIDER_DEADLINE_ENVVAR: &str = "RUST_SERVER_PROVIDER_DEADLINE_MS";
+
+/// Name of the environment variable configuring how often (in milliseconds) provider stats are
+/// logged.
+const RUST_SERVER_PROVIDER_STATS_INTERVAL_ENVVAR: &str = "RUST_SERVER_PROVIDER_STATS_INTERVAL_MS";
+
+/// Name of the environment variable with the base URL of the primary instance to replicate posts
+/// from.
+const RUST_SERVER_REPLICATE_FROM_ENVVAR: &str = "RUST_SERVER_REPLICATE_FROM";
+
+/// Name of the environment variable with the base URL of the primary instance a read-only instance
+/// redirects writes to.
+const RUST_SERVER_PRIMARY_URL_ENVVAR: &str = "RUST_SERVER_PRIMARY_URL";
+
+/// Name of the environment variable with the bearer token a replica presents to its primary.
+const RUST_SERVER_REPLICATION_TOKEN_ENVVAR: &str = "RUST_SERVER_REPLICATION_TOKEN";
+
+/// Default bearer token of a replica.
+const RUST_SERVER_DEFAULT_REPLICATION_TOKEN: &str = "replica";
+
+/// Name of the environment variable with the comma-separated CIDR ranges of trusted reverse
+/// proxies.
+const RUST_SERVER_TRUSTED_PROXIES_ENVVAR: &str = "RUST_SERVER_TRUSTED_PROXIES";
+
+/// Name of the environment variable with the forwarding header the trusted proxies append to.
+const RUST_SERVER_FORWARDED_HEADER_ENVVAR: &str = "RUST_SERVER_FORWARDED_HEADER";
+
+/// Name of the environment variable with the rules of the IP access control list.
+const RUST_SERVER_ACL_ENVVAR: &str = "RUST_SERVER_ACL";
+
+/// Name of the environment variable configuring after how many rejected authentication attempts
+/// a client address is locked out.
+const RUST_SERVER_AUTH_LOCKOUT_FAILURES_ENVVAR: &str = "RUST_SERVER_AUTH_LOCKOUT_FAILURES";
+
+/// Name of the environment variable configuring how long (in milliseconds) a client address is
+/// locked out.
+const RUST_SERVER_AUTH_LOCKOUT_ENVVAR: &str = "RUST_SERVER_AUTH_LOCKOUT_MS";
+
+/// Default lockout of client addresses, in milliseconds.
+const RUST_SERVER_DEFAULT_AUTH_LOCKOUT: usize = 60_000;
+
+/// Name of the environment variable with the comma-separated `<client>:<secret>` pairs of clients
+/// signing their requests.
+const RUST_SERVER_SIGNING_KEYS_ENVVAR: &str = "RUST_SERVER_SIGNING_KEYS";
+
+/// Name of the environment variable configuring how far (in milliseconds) the timestamp of a
+/// signed request may be off the server clock.
+const RUST_SERVER_SIGNATURE_MAX_SKEW_ENVVAR: &str = "RUST_SERVER_SIGNATURE_MAX_SKEW_MS";
+
+/// Default maximum skew of the timestamp of a signed request, in milliseconds.
+const RUST_SERVER_DEFAULT_SIGNATURE_MAX_SKEW: usize = 300_000;
+
+/// Name of the environment variable with the comma-separated `<flag>=<on|off>` feature flags and
+/// `<experiment>=<percent>%` splits.
+const RUST_SERVER_FEATURE_FLAGS_ENVVAR: &str = "RUST_SERVER_FEATURE_FLAGS";
+
+/// Name of the environment variable with the comma-separated `<METHOD> <pattern>=<iterations>`
+/// artificial CPU work of endpoints.
+const RUST_SERVER_WORK_FACTORS_ENVVAR: &str = "RUST_SERVER_WORK_FACTORS";
+
+/// Name of the environment variable configuring how many CPU-heavy jobs may run on the blocking
+/// pool at a time.
+const RUST_SERVER_OFFLOAD_POOL_SIZE_ENVVAR: &str = "RUST_SERVER_OFFLOAD_POOL_SIZE";
+
+/// Name of the environment variable with the comma-separated `<lane>=<limit>` concurrency limits
+/// of classes of requests.
+const RUST_SERVER_LANES_ENVVAR: &str = "RUST_SERVER_LANES";
+
+/// Name of the environment variable configuring how long (in milliseconds) a client may take to
+/// send the head of a request.
+const RUST_SERVER_CLIENT_HEADER_TIMEOUT_ENVVAR: &str = "RUST_SERVER_CLIENT_HEADER_TIMEOUT_MS";
+
+/// Default time a client may take to send the head of a request, in milliseconds.
+const RUST_SERVER_DEFAULT_CLIENT_HEADER_TIMEOUT: usize = 5_000;
+
+/// Name of the environment variable configuring how long (in milliseconds) the body of a request
+/// may stall before the request is answered with `408`.
+const RUST_SERVER_CLIENT_READ_TIMEOUT_ENVVAR: &str = "RUST_SERVER_CLIENT_READ_TIMEOUT_MS";
+
+/// Default time the body of a request may stall, in milliseconds.
+const RUST_SERVER_DEFAULT_CLIENT_READ_TIMEOUT: usize = 10_000;
+
+/// Name of the environment variable configuring how long (in milliseconds) the whole body of a
+/// request may take before the request is answered with `408`.
+const RUST_SERVER_CLIENT_BODY_TIMEOUT_ENVVAR: &str = "RUST_SERVER_CLIENT_BODY_TIMEOUT_MS";
+
+/// Default time the whole body of a request may take, in milliseconds.
+const RUST_SERVER_DEFAULT_CLIENT_BODY_TIMEOUT: usize = 60_000;
+
+/// Name of the environment variable configuring how long (in milliseconds) data sent to a client
+/// may stay unacknowledged before the connection is dropped.
+const RUST_SERVER_CLIENT_WRITE_TIMEOUT_ENVVAR: &str = "RUST_SERVER_CLIENT_WRITE_TIMEOUT_MS";
+
+/// Default time data sent to a client may stay unacknowledged, in milliseconds.
+const RUST_SERVER_DEFAULT_CLIENT_WRITE_TIMEOUT: usize = 30_000;
+
+/// Name of the environment variable configuring the maximum total size of the headers of a
+/// request, in bytes.
+const RUST_SERVER_MAX_HEADER_SIZE_ENVVAR: &str = "RUST_SERVER_MAX_HEADER_SIZE";
+
+/// Default maximum total size of the headers of a request, in bytes.
+const RUST_SERVER_DEFAULT_MAX_HEADER_SIZE: usize = 16 * 1024;
+
+/// Name of the environment variable with the base URL of a shadow backend receiving copies of
+/// requests.
+const RUST_SERVER_MIRROR_URL_ENVVAR: &str = "RUST_SERVER_MIRROR_URL";
+
+/// Name of the environment variable configuring the share of requests mirrored, in percent.
+const RUST_SERVER_MIRROR_PERCENT_ENVVAR: &str = "RUST_SERVER_MIRROR_PERCENT";
+
+/// Default share of requests mirrored, in percent.
+const RUST_SERVER_DEFAULT_MIRROR_PERCENT: usize = 100;
+
+/// Name of the environment variable configuring after how many consecutive failures the circuit
+/// breaker of an external storage opens.
+const RUST_SERVER_BREAKER_THRESHOLD_ENVVAR: &str = "RUST_SERVER_BREAKER_THRESHOLD";
+
+/// Default number of consecutive failures opening a circuit breaker.
+const RUST_SERVER_DEFAULT_BREAKER_THRESHOLD: usize = 5;
+
+/// Name of the environment variable configuring how long (in milliseconds) an open circuit breaker
+/// rejects calls before it lets a probe through.
+const RUST_SERVER_BREAKER_COOLDOWN_ENVVAR: &str = "RUST_SERVER_BREAKER_COOLDOWN_MS";
+
+/// Default cooldown of an open circuit breaker, in milliseconds.
+const RUST_SERVER_DEFAULT_BREAKER_COOLDOWN: usize = 5_000;
+
+/// Default upper bound of the delay before the first retry of a storage call, in milliseconds.
+const RUST_SERVER_DEFAULT_RETRY_BACKOFF: usize = 10;
+
+/// Default upper bound of any delay between retries of a storage call, in milliseconds.
+const RUST_SERVER_DEFAULT_RETRY_MAX_BACKOFF: usize = 200;
+
+/// Default share of storage calls which may be retried, in percent.
+const RUST_SERVER_DEFAULT_RETRY_BUDGET: usize = 20;
+
+#[cfg(feature = "smtp")]
+/// Name of the environment variable with the `host:port` of the SMTP relay.
+const RUST_SERVER_SMTP_ADDR_ENVVAR: &str = "RUST_SERVER_SMTP_ADDR";
+
+#[cfg(feature = "smtp")]
+/// Default SMTP relay, e.g. a local mail catcher.
+const RUST_SERVER_DEFAULT_SMTP_ADDR: &str = "127.0.0.1:1025";
+
+#[cfg(feature = "smtp")]
+/// Name of the environment variable with the sender address of notification emails.
+const RUST_SERVER_SMTP_FROM_ENVVAR: &str = "RUST_SERVER_SMTP_FROM";
+
+#[cfg(feature = "smtp")]
+/// Default sender address of notification emails.
+const RUST_SERVER_DEFAULT_SMTP_FROM: &str = "PerCom <noreply@percom.local>";
+
+#[cfg(feature = "ui")]
+/// Name of the environment variable with the directory of the demo frontend.
+const RUST_SERVER_UI_DIR_ENVVAR: &str = "RUST_SERVER_UI_DIR";
+
+#[cfg(feature = "ui")]
+/// Default directory of the demo frontend, relative to the working directory.
+const RUST_SERVER_DEFAULT_UI_DIR: &str = "ui";
+
+#[cfg(feature = "http3")]
+/// Name of the environment variable with the UDP address of the HTTP/3 listener.
+const RUST_SERVER_H3_ADDR_ENVVAR: &str = "RUST_SERVER_H3_ADDR";
+
+/// Name of the environment variable with the path of the session summary written on shutdown.
+const RUST_SERVER_SUMMARY_PATH_ENVVAR: &str = "RUST_SERVER_SUMMARY_PATH";
+
+/// Reads a numeric environment variable, falling back to `default` if it's missing or malformed.
+fn get_usize(name: &str, default: usize) -> usize {
+    env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+/// Returns the name of the notifier selected via `RUST_SERVER_NOTIFIER`, defaulting to `log`.
+pub fn get_notifier() -> String {
+    env::var(RUST_SERVER_NOTIFIER_ENVVAR).unwrap_or("log".to_owned())
+}
+
+/// Returns the number of job queue workers (`RUST_SERVER_JOB_WORKERS`, default `2`, at least `1`).
+pub fn get_job_workers() -> usize {
+    get_usize(
+        RUST_SERVER_JOB_WORKERS_ENVVAR,
+        RUST_SERVER_DEFAULT_JOB_WORKERS,
+    )
+    .max(1)
+}
+
+/// Returns the job queue capacity (`RUST_SERVER_JOB_QUEUE_CAPACITY`, default `1024`, at least `1`).
+pub fn get_job_queue_capacity() -> usize {
+    get_usize(
+        RUST_SERVER_JOB_QUEUE_CAPACITY_ENVVAR,
+        RUST_SERVER_DEFAULT_JOB_QUEUE_CAPACITY,
+    )
+    .max(1)
+}
+
+/// Returns the interval of the post publication scheduler (`RUST_SERVER_SCHEDULER_INTERVAL_MS`,
+/// default `1000`, at least `1`).
+pub fn get_scheduler_interval() -> Duration {
+    Duration::from_millis(
+        get_usize(
+            RUST_SERVER_SCHEDULER_INTERVAL_ENVVAR,
+            RUST_SERVER_DEFAULT_SCHEDULER_INTERVAL,
+        )
+        .max(1) as u64,
+    )
+}
+
+/// Returns the name of the posts provider selected via `RUST_SERVER_POSTS_PROVIDER`, defaulting to `memory`.
+pub fn get_posts_provider() -> String {
+    env::var(RUST_SERVER_POSTS_PROVIDER_ENVVAR).unwrap_or("memory".to_owned())
+}
+
+/// Returns the number of server workers (`RUST_SERVER_WORKERS`, at least `1`), defaulting to the
+/// available parallelism like actix does.
+pub fn get_workers() -> usize {
+    let default = std::thread::available_parallelism().map_or(1, |cpus| cpus.get());
+    get_usize(RUST_SERVER_WORKERS_ENVVAR, default).max(1)
+}
+
+/// Returns how server workers hold posts, selected via `RUST_SERVER_STATE_MODE` (`shared` or
+/// `worker-local`), defaulting to `shared` (see
+/// [`ShardedProvider`](crate::scheme::posts::ShardedProvider)).
+pub fn get_state_mode() -> String {
+    env::var(RUST_SERVER_STATE_MODE_ENVVAR).unwrap_or("shared".to_owned())
+}
+
+/// Returns `true` if `RUST_SERVER_H2C` is set to `1`: the TCP listener then also accepts HTTP/2
+/// with prior knowledge (`h2c`), told apart from HTTP/1 by the connection preface. Only HTTP/1 is
+/// accepted by default.
+pub fn get_h2c() -> bool {
+    env::var(RUST_SERVER_H2C_ENVVAR)
+        .map(|v| v == "1")
+        .unwrap_or(false)
+}
+
+/// Returns what happens to trailing and repeated slashes of paths (`RUST_SERVER_TRAILING_SLASH`:
+/// `trim`, the default, `merge` or `strict`; see
+/// [`Slashes`](crate::middleware::normalize::Slashes)).
+pub fn get_trailing_slash() -> String {
+    env::var(RUST_SERVER_TRAILING_SLASH_ENVVAR).unwrap_or("trim".to_owned())
+}
+
+/// Returns `true` if the first segment of paths is lowercased before routing
+/// (`RUST_SERVER_LOWERCASE_PATHS=1`), so `/Posts` is `/posts`.
+pub fn get_lowercase_paths() -> bool {
+    env::var(RUST_SERVER_LOWERCASE_PATHS_ENVVAR)
+        .map(|v| v == "1")
+        .unwrap_or(false)
+}
+
+/// Returns what happens to query parameters unknown to an endpoint (`RUST_SERVER_QUERY_PARAMS`:
+/// `lenient`, the default, or `strict`; see [`QueryMode`](crate::scheme::params::QueryMode)).
+pub fn get_query_params() -> String {
+    env::var(RUST_SERVER_QUERY_PARAMS_ENVVAR).unwrap_or("lenient".to_owned())
+}
+
+/// Returns the routing policy of the `tiered` posts provider (`RUST_SERVER_TIER_ROUTING`): either
+/// `age:<milliseconds>`, keeping posts dated within that age in the hot tier (the default is one
+/// day), or `prefix:<prefix>,...`, keeping posts whose ID starts with one of the prefixes there.
+pub fn get_tier_routing() -> String {
+    env::var(RUST_SERVER_TIER_ROUTING_ENVVAR).unwrap_or(RUST_SERVER_DEFAULT_TIER_ROUTING.to_owned())
+}
+
+/// Returns how often aged posts are moved to the cold tier of the `tiered` posts provider
+/// (`RUST_SERVER_TIER_DEMOTION_INTERVAL_MS`, default `1000`, at least `1`).
+pub fn get_tier_demotion_interval() -> Duration {
+    Duration::from_millis(
+        get_usize(
+            RUST_SERVER_TIER_DEMOTION_INTERVAL_ENVVAR,
+            RUST_SERVER_DEFAULT_TIER_DEMOTION_INTERVAL,
+        )
+        .max(1) as u64,
+    )
+}
+
+/// Returns the name of the codec compressing the content of stored posts, selected via
+/// `RUST_SERVER_CONTENT_COMPRESSION`, defaulting to `none`.
+pub fn get_content_compression() -> String {
+    env::var(RUST_SERVER_CONTENT_COMPRESSION_ENVVAR).unwrap_or("none".to_owned())
+}
+
+/// Returns the path of the posts WAL (`RUST_SERVER_WAL_PATH`, default `posts.wal` in the
+/// application directory).
+///
+/// # Errors
+/// Returns an `io::Error` if the application directory cannot be created.
+pub fn get_wal_path() -> io::Result<PathBuf> {
+    match env::var(RUST_SERVER_WAL_PATH_ENVVAR) {
+        Ok(path) => Ok(PathBuf::from(path)),
+        Err(_) => Ok(get_home()?.join(RUST_SERVER_DEFAULT_WAL_FILE)),
+    }
+}
+
+/// Returns the path of the store of the `kv` posts provider (`RUST_SERVER_KV_PATH`, default
+/// `posts.redb` in the application directory).
+///
+/// # Errors
+/// Returns an `io::Error` if the application directory cannot be created.
+pub fn get_kv_path() -> io::Result<PathBuf> {
+    match env::var(RUST_SERVER_KV_PATH_ENVVAR) {
+        Ok(path) => Ok(PathBuf::from(path)),
+        Err(_) => Ok(get_home()?.join(RUST_SERVER_DEFAULT_KV_FILE)),
+    }
+}
+
+/// Returns the number of posts whose JSON is cached (`RUST_SERVER_POST_CACHE_SIZE`, default `0`,
+/// which disables the cache; see [`CacheProvider`](crate::scheme::posts::CacheProvider)).
+pub fn get_post_cache_size() -> usize {
+    get_usize(RUST_SERVER_POST_CACHE_SIZE_ENVVAR, 0)
+}
+
+/// Returns the interval of the posts WAL compaction (`RUST_SERVER_WAL_COMPACTION_INTERVAL_MS`,
+/// default `60000`, at least `1`).
+pub fn get_wal_compaction_interval() -> Duration {
+    Duration::from_millis(
+        get_usize(
+            RUST_SERVER_WAL_COMPACTION_INTERVAL_ENVVAR,
+            RUST_SERVER_DEFAULT_WAL_COMPACTION_INTERVAL,
+        )
+        .max(1) as u64,
+    )
+}
+
+/// Returns the number of WAL records flushed together (`RUST_SERVER_WAL_BATCH_SIZE`, default `1`,
+/// at least `1`). Above `1`, the WAL works in write-behind mode.
+pub fn get_wal_batch_size() -> usize {
+    get_usize(RUST_SERVER_WAL_BATCH_SIZE_ENVVAR, 1).max(1)
+}
+
+/// Returns the interval of WAL flushes in batched mode (`RUST_SERVER_WAL_BATCH_MS`, default `5`,
+/// at least `1`): pending records are written within it even if the batch isn't full.
+pub fn get_wal_batch_interval() -> Duration {
+    Duration::from_millis(
+        get_usize(
+            RUST_SERVER_WAL_BATCH_INTERVAL_ENVVAR,
+            RUST_SERVER_DEFAULT_WAL_BATCH_INTERVAL,
+        )
+        .max(1) as u64,
+    )
+}
+
+/// Returns `true` if `RUST_SERVER_IDEMPOTENT_DELETE` is set to `1`: deleting a missing post then
+/// answers `204` instead of `404`. Strict mode is the default.
+pub fn get_idempotent_delete() -> bool {
+    env::var(RUST_SERVER_IDEMPOTENT_DELETE_ENVVAR)
+        .map(|v| v == "1")
+        .unwrap_or(false)
+}
+
+/// Returns what happens to unsafe content of created and updated posts (`RUST_SERVER_SANITIZE`:
+/// `off`, the default, `clean` or `reject`).
+pub fn get_sanitize_mode() -> String {
+    env::var(RUST_SERVER_SANITIZE_ENVVAR).unwrap_or("off".to_owned())
+}
+
+/// Returns `true` if HTML in post content is unsafe (`RUST_SERVER_SANITIZE_HTML=1`).
+pub fn get_sanitize_html() -> bool {
+    env::var(RUST_SERVER_SANITIZE_HTML_ENVVAR)
+        .map(|v| v == "1")
+        .unwrap_or(false)
+}
+
+/// Reads a comma-separated list, empty if the variable is missing.
+fn get_list(name: &str) -> Vec<String> {
+    env::var(name)
+        .map(|v| v.split(',').map(str::to_owned).collect())
+        .unwrap_or_default()
+}
+
+/// Returns the keywords rejecting post content (`RUST_SERVER_MODERATION_REJECT`, none by default).
+pub fn get_moderation_reject() -> Vec<String> {
+    get_list(RUST_SERVER_MODERATION_REJECT_ENVVAR)
+}
+
+/// Returns the keywords flagging post content for review (`RUST_SERVER_MODERATION_FLAG`, none by
+/// default).
+pub fn get_moderation_flag() -> Vec<String> {
+    get_list(RUST_SERVER_MODERATION_FLAG_ENVVAR)
+}
+
+/// Returns the precision of post dates (`RUST_SERVER_DATE_PRECISION`: `millis` or `micros`, the
+/// default).
+pub fn get_date_precision() -> String {
+    env::var(RUST_SERVER_DATE_PRECISION_ENVVAR).unwrap_or("micros".to_owned())
+}
+
+/// Returns the maximum size of request bodies of the API endpoints, in bytes
+/// (`RUST_SERVER_MAX_BODY_SIZE`, default 2 MiB); larger bodies are rejected with `413`.
+///
+/// The limit applies to the decompressed body, so a small compressed body can't expand past it.
+/// `POST /admin/posts/import` has its own, larger limit.
+pub fn get_max_body_size() -> usize {
+    get_usize(
+        RUST_SERVER_MAX_BODY_SIZE_ENVVAR,
+        RUST_SERVER_DEFAULT_MAX_BODY_SIZE,
+    )
+}
+
+/// Returns the deadline of provider calls of a request (`RUST_SERVER_PROVIDER_DEADLINE_MS`), or
+/// `None` if it's not set or `0`: provider calls then run inline, without a deadline.
+pub fn get_provider_deadline() -> Option<Duration> {
+    match get_usize(RUST_SERVER_PROVIDER_DEADLINE_ENVVAR, 0) {
+        0 => None,
+        ms => Some(Duration::from_millis(ms as u64)),
+    }
+}
+
+/// Returns the base URL of the primary instance (`RUST_SERVER_REPLICATE_FROM`, e.g.
+/// `http://10.0.0.1:8080`), or `None` if it's not set or empty: the instance is then a primary.
+pub fn get_replicate_from() -> Option<String> {
+    env::var(RUST_SERVER_REPLICATE_FROM_ENVVAR)
+        .ok()
+        .map(|url| url.trim_end_matches('/').to_owned())
+        .filter(|url| !url.is_empty())
+}
+
+/// Returns the base URL of the primary instance, sent to clients whose writes a read-only instance
+/// rejects (`RUST_SERVER_PRIMARY_URL`, defaulting to `RUST_SERVER_REPLICATE_FROM`), or `None` if
+/// neither is set.
+pub fn get_primary_url() -> Option<String> {
+    env::var(RUST_SERVER_PRIMARY_URL_ENVVAR)
+        .ok()
+        .map(|url| url.trim_end_matches('/').to_owned())
+        .filter(|url| !url.is_empty())
+        .or_else(get_replicate_from)
+}
+
+/// Returns the CIDR ranges of reverse proxies whose `Forwarded`/`X-Forwarded-For` headers are
+/// believed (`RUST_SERVER_TRUSTED_PROXIES`, e.g. `127.0.0.1,10.0.0.0/8`; none by default).
+pub fn get_trusted_proxies() -> String {
+    env::var(RUST_SERVER_TRUSTED_PROXIES_ENVVAR).unwrap_or_default()
+}
+
+/// Returns the forwarding header the trusted proxies append to (`RUST_SERVER_FORWARDED_HEADER`:
+/// `x-forwarded-for`, the default, or `forwarded`; see
+/// [`ForwardedHeader`](crate::client_ip::ForwardedHeader)).
+pub fn get_forwarded_header() -> String {
+    env::var(RUST_SERVER_FORWARDED_HEADER_ENVVAR).unwrap_or("x-forwarded-for".to_owned())
+}
+
+/// Returns the rules of the IP access control list (`RUST_SERVER_ACL`, e.g.
+/// `/admin allow 127.0.0.1,10.0.0.0/8; / deny 203.0.113.0/24`; none by default, see
+/// [`Acl::from_spec`](crate::middleware::acl::Acl::from_spec)).
+pub fn get_acl() -> String {
+    env::var(RUST_SERVER_ACL_ENVVAR).unwrap_or_default()
+}
+
+/// Returns after how many rejected authentication attempts within the lockout a client address
+/// is locked out (`RUST_SERVER_AUTH_LOCKOUT_FAILURES`), or `None` if it's not set or `0`, the
+/// default (see [`AuthFailures`](crate::middleware::auth_failures::AuthFailures)).
+pub fn get_auth_lockout_failures() -> Option<u32> {
+    match get_usize(RUST_SERVER_AUTH_LOCKOUT_FAILURES_ENVVAR, 0) {
+        0 => None,
+        failures => Some(u32::try_from(failures).unwrap_or(u32::MAX)),
+    }
+}
+
+/// Returns how long a client address is locked out (`RUST_SERVER_AUTH_LOCKOUT_MS`, default
+/// `60000`); failures older than that are forgotten too.
+pub fn get_auth_lockout() -> Duration {
+    Duration::from_millis(get_usize(
+        RUST_SERVER_AUTH_LOCKOUT_ENVVAR,
+        RUST_SERVER_DEFAULT_AUTH_LOCKOUT,
+    ) as u64)
+}
+
+/// Returns the secrets of clients signing their requests (`RUST_SERVER_SIGNING_KEYS`, e.g.
+/// `loadgen:secret,admin:other`); requests aren't signed if it's empty, the default.
+pub fn get_signing_keys() -> String {
+    env::var(RUST_SERVER_SIGNING_KEYS_ENVVAR).unwrap_or_default()
+}
+
+/// Returns how far the timestamp of a signed request may be off the server clock
+/// (`RUST_SERVER_SIGNATURE_MAX_SKEW_MS`, default `300000`).
+pub fn get_signature_max_skew() -> Duration {
+    Duration::from_millis(get_usize(
+        RUST_SERVER_SIGNATURE_MAX_SKEW_ENVVAR,
+        RUST_SERVER_DEFAULT_SIGNATURE_MAX_SKEW,
+    ) as u64)
+}
+
+/// Returns the feature flags (`RUST_SERVER_FEATURE_FLAGS`, e.g.
+/// `enable_search=off,list_posts=25%`; every flag has its default if empty, see
+/// [`FeatureFlags::from_spec`](crate::scheme::flags::FeatureFlags::from_spec)).
+pub fn get_feature_flags() -> String {
+    env::var(RUST_SERVER_FEATURE_FLAGS_ENVVAR).unwrap_or_default()
+}
+
+/// Returns the artificial CPU work of endpoints (`RUST_SERVER_WORK_FACTORS`, e.g. `GET
+/// /posts=10000`; none if empty, the default, see
+/// [`WorkFactors::from_spec`](crate::middleware::work::WorkFactors::from_spec)).
+pub fn get_work_factors() -> String {
+    env::var(RUST_SERVER_WORK_FACTORS_ENVVAR).unwrap_or_default()
+}
+
+/// Returns how many CPU-heavy jobs may run on the blocking pool at a time
+/// (`RUST_SERVER_OFFLOAD_POOL_SIZE`, default the number of CPUs; `0` runs them inline, see
+/// [`Offload`](crate::offload::Offload)).
+pub fn get_offload_pool_size() -> usize {
+    let cpus = std::thread::available_parallelism().map_or(1, |cpus| cpus.get());
+    get_usize(RUST_SERVER_OFFLOAD_POOL_SIZE_ENVVAR, cpus)
+}
+
+/// Returns the concurrency limits of classes of requests (`RUST_SERVER_LANES`, e.g.
+/// `read=256,bulk=2`; no limits if empty, the default, see
+/// [`Lanes::from_spec`](crate::middleware::lanes::Lanes::from_spec)).
+pub fn get_lanes() -> String {
+    env::var(RUST_SERVER_LANES_ENVVAR).unwrap_or_default()
+}
+
+/// Returns how long a client may take to send the head of a request
+/// (`RUST_SERVER_CLIENT_HEADER_TIMEOUT_MS`, default `5000`; `0` disables it). Slower clients are
+/// answered with `408` and disconnected.
+pub fn get_client_header_timeout() -> Duration {
+    Duration::from_millis(get_usize(
+        RUST_SERVER_CLIENT_HEADER_TIMEOUT_ENVVAR,
+        RUST_SERVER_DEFAULT_CLIENT_HEADER_TIMEOUT,
+    ) as u64)
+}
+
+/// Returns how long the body of a request may stall (`RUST_SERVER_CLIENT_READ_TIMEOUT_MS`,
+/// default `10000`), or `None` if it's `0` (see
+/// [`guard_slow_clients`](crate::middleware::slow_clients::guard_slow_clients)).
+pub fn get_client_read_timeout() -> Option<Duration> {
+    match get_usize(
+        RUST_SERVER_CLIENT_READ_TIMEOUT_ENVVAR,
+        RUST_SERVER_DEFAULT_CLIENT_READ_TIMEOUT,
+    ) {
+        0 => None,
+        ms => Some(Duration::from_millis(ms as u64)),
+    }
+}
+
+/// Returns how long the whole body of a request may take (`RUST_SERVER_CLIENT_BODY_TIMEOUT_MS`,
+/// default `60000`), or `None` if it's `0` (see
+/// [`guard_slow_clients`](crate::middleware::slow_clients::guard_slow_clients)).
+pub fn get_client_body_timeout() -> Option<Duration> {
+    match get_usize(
+        RUST_SERVER_CLIENT_BODY_TIMEOUT_ENVVAR,
+        RUST_SERVER_DEFAULT_CLIENT_BODY_TIMEOUT,
+    ) {
+        0 => None,
+        ms => Some(Duration::from_millis(ms as u64)),
+    }
+}
+
+/// Returns how long data sent to a client may stay unacknowledged
+/// (`RUST_SERVER_CLIENT_WRITE_TIMEOUT_MS`, default `30000`), or `None` if it's `0` (see
+/// [`write_timeout`](crate::connection::write_timeout)).
+pub fn get_client_write_timeout() -> Option<Duration> {
+    match get_usize(
+        RUST_SERVER_CLIENT_WRITE_TIMEOUT_ENVVAR,
+        RUST_SERVER_DEFAULT_CLIENT_WRITE_TIMEOUT,
+    ) {
+        0 => None,
+        ms => Some(Duration::from_millis(ms as u64)),
+    }
+}
+
+/// Returns the maximum total size of the headers of a request (`RUST_SERVER_MAX_HEADER_SIZE`,
+/// default `16384` bytes). Larger headers are answered with `431`.
+pub fn get_max_header_size() -> usize {
+    get_usize(
+        RUST_SERVER_MAX_HEADER_SIZE_ENVVAR,
+        RUST_SERVER_DEFAULT_MAX_HEADER_SIZE,
+    )
+}
+
+/// Returns the base URL of the shadow backend requests are mirrored to (`RUST_SERVER_MIRROR_URL`,
+/// e.g. `http://10.0.0.2:8080`), or `None` if it's not set or empty: nothing is mirrored then.
+pub fn get_mirror_url() -> Option<String> {
+    env::var(RUST_SERVER_MIRROR_URL_ENVVAR)
+        .ok()
+        .map(|url| url.trim_end_matches('/').to_owned())
+        .filter(|url| !url.is_empty())
+}
+
+/// Returns the share of requests mirrored to the shadow backend, in percent
+/// (`RUST_SERVER_MIRROR_PERCENT`, default `100`, at most `100`).
+pub fn get_mirror_percent() -> u8 {
+    get_usize(
+        RUST_SERVER_MIRROR_PERCENT_ENVVAR,
+        RUST_SERVER_DEFAULT_MIRROR_PERCENT,
+    )
+    .min(100) as u8
+}
+
+/// Returns the bearer token a replica presents to its primary (`RUST_SERVER_REPLICATION_TOKEN`,
+/// default `replica`).
+pub fn get_replication_token() -> String {
+    env::var(RUST_SERVER_REPLICATION_TOKEN_ENVVAR)
+        .unwrap_or(RUST_SERVER_DEFAULT_REPLICATION_TOKEN.to_owned())
+}
+
+/// Returns how often provider stats are logged (`RUST_SERVER_PROVIDER_STATS_INTERVAL_MS`), or
+/// `None` if it's not set or `0`: stats are then only available at `GET /admin/providers`.
+pub fn get_provider_stats_interval() -> Option<Duration> {
+    match get_usize(RUST_SERVER_PROVIDER_STATS_INTERVAL_ENVVAR, 0) {
+        0 => None,
+        ms => Some(Duration::from_millis(ms as u64)),
+    }
+}
+
+/// Returns the number of consecutive failures opening a circuit breaker
+/// (`RUST_SERVER_BREAKER_THRESHOLD`, default `5`, at least `1`).
+pub fn get_breaker_threshold() -> u32 {
+    get_usize(
+        RUST_SERVER_BREAKER_THRESHOLD_ENVVAR,
+        RUST_SERVER_DEFAULT_BREAKER_THRESHOLD,
+    )
+    .clamp(1, u32::MAX as usize) as u32
+}
+
+/// Returns how long an open circuit breaker rejects calls (`RUST_SERVER_BREAKER_COOLDOWN_MS`,
+/// default `5000`).
+pub fn get_breaker_cooldown() -> Duration {
+    Duration::from_millis(get_usize(
+        RUST_SERVER_BREAKER_COOLDOWN_ENVVAR,
+        RUST_SERVER_DEFAULT_BREAKER_COOLDOWN,
+    ) as u64)
+}
+
+/// Returns the retry policy of the storage `backend` (e.g. `WAL`), or `None` if its calls aren't
+/// retried. Each backend is configured with its own variables:
+/// - `RUST_SERVER_<BACKEND>_RETRIES`: retries after the first attempt (default `0`, disabled);
+/// - `RUST_SERVER_<BACKEND>_RETRY_BACKOFF_MS`: delay bound of the first retry (default `10`);
+/// - `RUST_SERVER_<BACKEND>_RETRY_MAX_BACKOFF_MS`: delay bound of any retry (default `200`);
+/// - `RUST_SERVER_<BACKEND>_RETRY_BUDGET_PERCENT`: share of calls which may be retried (default `20`).
+pub fn get_retry_policy(backend: &str) -> Option<RetryPolicy> {
+    let var = |suffix: &str| format!("RUST_SERVER_{backend}_{suffix}");
+    let max_retries = get_usize(&var("RETRIES"), 0).min(u32::MAX as usize) as u32;
+    if max_retries == 0 {
+        return None;
+    }
+    let ms = |suffix: &str, default: usize| {
+        Duration::from_millis(get_usize(&var(suffix), default) as u64)
+    };
+    Some(RetryPolicy {
+        max_retries,
+        backoff: ms("RETRY_BACKOFF_MS", RUST_SERVER_DEFAULT_RETRY_BACKOFF),
+        max_backoff: ms(
+            "RETRY_MAX_BACKOFF_MS",
+            RUST_SERVER_DEFAULT_RETRY_MAX_BACKOFF,
+        ),
+        budget: get_usize(
+            &var("RETRY_BUDGET_PERCENT"),
+            RUST_SERVER_DEFAULT_RETRY_BUDGET,
+        ) as f64
+            / 100.0,
+    })
+}
+
+#[cfg(feature = "smtp")]
+/// Returns the `host:port` of the SMTP relay (`RUST_SERVER_SMTP_ADDR`, default `127.0.0.1:1025`).
+pub fn get_smtp_addr() -> String {
+    env::var(RUST_SERVER_SMTP_ADDR_ENVVAR).unwrap_or(RUST_SERVER_DEFAULT_SMTP_ADDR.to_owned())
+}
+
+#[cfg(feature = "smtp")]
+/// Returns the sender address of notification emails (`RUST_SERVER_SMTP_FROM`).
+pub fn get_smtp_from() -> String {
+    env::var(RUST_SERVER_SMTP_FROM_ENVVAR).unwrap_or(RUST_SERVER_DEFAULT_SMTP_FROM.to_owned())
+}
+
+/// Returns the path of the session summary written on shutdown (`RUST_SERVER_SUMMARY_PATH`), or
+/// `None` if it's not set or empty: it's then written next to the log file (see
+/// [`crate::summary`]).
+pub fn get_summary_path() -> Option<PathBuf> {
+    env::var(RUST_SERVER_SUMMARY_PATH_ENVVAR)
+        .ok()
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+}
+
+#[cfg(feature = "ui")]
+/// Returns the directory with the static files of the demo frontend (`RUST_SERVER_UI_DIR`,
+/// default `ui`).
+pub fn get_ui_dir() -> String {
+    env::var(RUST_SERVER_UI_DIR_ENVVAR).unwrap_or(RUST_SERVER_DEFAULT_UI_DIR.to_owned())
+}
+
+#[cfg(feature = "http3")]
+/// Returns the UDP address of the HTTP/3 listener (`RUST_SERVER_H3_ADDR`, e.g. `0.0.0.0:8443`), or
+/// `None` if it's not set or empty: the app is then only served over TCP (see [`crate::http3`]).
+///
+/// # Errors
+/// Returns an `io::Error` if the address can't be parsed as a `SocketAddr`.
+pub fn get_h3_addr() -> io::Result<Option<SocketAddr>> {
+    env::var(RUST_SERVER_H3_ADDR_ENVVAR)
+        .ok()
+        .filter(|addr| !addr.is_empty())
+        .map(|addr| addr.parse().map_err(io::Error::other))
+        .transpose()
+}
+
 #[cfg(test)]
 /// Name of the environment variable used during testing to configure the target server address.
 const RUST_CLIENT_ADDR_ENVVAR: &str = "RUST_CLIENT_ADDR";
@@ -45,6 +866,23 @@ pub fn get_client_url() -> String {
     env::var(RUST_CLIENT_ADDR_ENVVAR).unwrap_or(RUST_CLIENT_DEFAULT_ADDR.to_owned())
 }
 
+#[cfg(test)]
+/// Name of the environment variable used during testing to configure the bearer token sent to the
+/// target server.
+const RUST_CLIENT_TOKEN_ENVVAR: &str = "RUST_CLIENT_TOKEN";
+
+#[cfg(test)]
+/// Default bearer token used during testing if the `RUST_CLIENT_TOKEN` environment variable is not
+/// set; the dummy users provider accepts any token which wasn't revoked.
+const RUST_CLIENT_DEFAULT_TOKEN: &str = "fake_test_token";
+
+#[cfg(test)]
+/// Returns the bearer token tests authenticate with (`RUST_CLIENT_TOKEN`, `fake_test_token` by
+/// default), so they also pass against a server which only accepts its own tokens.
+pub fn get_client_token() -> String {
+    env::var(RUST_CLIENT_TOKEN_ENVVAR).unwrap_or(RUST_CLIENT_DEFAULT_TOKEN.to_owned())
+}
+
 #[cfg(test)]
 /// If set in "1", will write test data into file in $TEMP folder
 const WRITE_TEST_RESULT_TO_FILE: &str = "WRITE_TEST_RESULT_TO_FILE";
@@ -0,0 +1,323 @@
+//! HTTP/3 listener serving the same app as the TCP one, so transports can be compared on the same
+//! code (see `RUST_SERVER_H3_ADDR`; only available with the `http3` cargo feature).
+//!
+//! actix has no HTTP/3 support: [`start`] accepts QUIC connections with quinn on a dedicated
+//! thread, decodes their requests with h3 and hands them to an instance of the app built by the
+//! same factory as the TCP workers, as `HttpServer` builds it. The thread is an additional worker,
+//! so a server with N workers serves HTTP/3 with one.
+//!
+//! Request bodies are read entirely before the app sees them, up to the body size limit; response
+//! bodies are sent frame by frame as the app produces them. The timeouts of the TCP listener apply
+//! as well (see [`Limits`]). The certificate is self-signed and generated on every start, so
+//! clients have to skip its verification.
+
+use actix_http::{Payload, Request, error::PayloadError};
+use actix_service::IntoServiceFactory;
+use actix_web::{
+    App, Error, HttpResponse, ResponseError,
+    body::{BoxBody, MessageBody},
+    dev::{AppConfig, Service, ServiceFactory, ServiceRequest, ServiceResponse},
+    http::{
+        Method, Uri, Version,
+        header::{self, HeaderName, HeaderValue},
+    },
+    rt,
+};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use std::{
+    future::{Future, poll_fn},
+    io,
+    net::{SocketAddr, UdpSocket},
+    pin::pin,
+    rc::Rc,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+use tracing::{debug, error, info};
+
+use crate::scheme::error::ApiError;
+
+/// Request stream of a QUIC connection.
+type Stream = h3::server::RequestStream<h3_quinn::BidiStream<Bytes>, Bytes>;
+
+/// Limits of HTTP/3 requests, the same as those of the TCP listener.
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    /// Maximum size of request bodies (`RUST_SERVER_MAX_BODY_SIZE`); larger ones are answered
+    /// with `413 Payload Too Large` without reaching the app.
+    pub max_body_size: usize,
+
+    /// How long a client may take to send the head of a request
+    /// (`RUST_SERVER_CLIENT_HEADER_TIMEOUT_MS`); zero disables it.
+    pub header_timeout: Duration,
+
+    /// How long the body of a request may stall (`RUST_SERVER_CLIENT_READ_TIMEOUT_MS`) before
+    /// it's answered with `408 Request Timeout`.
+    pub read_timeout: Option<Duration>,
+
+    /// How long the whole body of a request may take (`RUST_SERVER_CLIENT_BODY_TIMEOUT_MS`)
+    /// before it's answered with `408 Request Timeout`.
+    pub body_timeout: Option<Duration>,
+
+    /// How long sending a part of a response may take (`RUST_SERVER_CLIENT_WRITE_TIMEOUT_MS`)
+    /// before the request is abandoned.
+    pub write_timeout: Option<Duration>,
+}
+
+/// Binds `addr` (UDP) and serves the app built by `factory` over HTTP/3 on a dedicated thread.
+///
+/// # Errors
+/// Returns an error if the address can't be bound or the certificate can't be generated; errors
+/// of connections and requests are only logged.
+pub fn start<F, T, B>(addr: SocketAddr, limits: Limits, factory: F) -> io::Result<()>
+where
+    F: FnOnce() -> App<T> + Send + 'static,
+    T: ServiceFactory<
+            ServiceRequest,
+            Config = (),
+            Response = ServiceResponse<B>,
+            Error = Error,
+            InitError = (),
+        > + 'static,
+    B: MessageBody + 'static,
+{
+    let config = server_config()?;
+    let socket = UdpSocket::bind(addr)?;
+    thread::Builder::new()
+        .name("http3".to_owned())
+        .spawn(move || {
+            rt::System::new().block_on(async move {
+                let endpoint = match quinn::Endpoint::new(
+                    quinn::EndpointConfig::default(),
+                    Some(config),
+                    socket,
+                    Arc::new(quinn::TokioRuntime),
+                ) {
+                    Ok(endpoint) => endpoint,
+                    Err(err) => {
+                        error!("Fail to start the HTTP/3 listener: {err}");
+                        return;
+                    }
+                };
+                let app = match factory()
+                    .into_factory()
+                    .new_service(AppConfig::default())
+                    .await
+                {
+                    Ok(app) => Rc::new(app),
+                    Err(()) => {
+                        error!("Fail to build the app of the HTTP/3 listener");
+                        return;
+                    }
+                };
+                info!("Serving HTTP/3 on {addr}");
+                while let Some(incoming) = endpoint.accept().await {
+                    rt::spawn(serve_connection(incoming, limits, app.clone()));
+                }
+            })
+        })?;
+    Ok(())
+}
+
+/// Builds the QUIC config of the listener, with a self-signed certificate for `localhost`.
+fn server_config() -> io::Result<quinn::ServerConfig> {
+    let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_owned()])
+        .map_err(io::Error::other)?;
+    let key = rustls::pki_types::PrivatePkcs8KeyDer::from(cert.key_pair.serialize_der());
+    let mut tls = rustls::ServerConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_protocol_versions(&[&rustls::version::TLS13])
+    .map_err(io::Error::other)?
+    .with_no_client_auth()
+    .with_single_cert(vec![cert.cert.der().clone()], key.into())
+    .map_err(io::Error::other)?;
+    tls.alpn_protocols = vec![b"h3".to_vec()];
+    let crypto =
+        quinn::crypto::rustls::QuicServerConfig::try_from(tls).map_err(io::Error::other)?;
+    Ok(quinn::ServerConfig::with_crypto(Arc::new(crypto)))
+}
+
+/// Runs `future`, failing with `TimedOut` if it takes longer than `timeout`.
+async fn within<T, E>(
+    timeout: Option<Duration>,
+    future: impl Future<Output = Result<T, E>>,
+) -> Result<T, Box<dyn std::error::Error>>
+where
+    E: std::error::Error + 'static,
+{
+    match timeout {
+        Some(timeout) => match rt::time::timeout(timeout, future).await {
+            Ok(result) => Ok(result?),
+            Err(_) => Err(io::Error::from(io::ErrorKind::TimedOut).into()),
+        },
+        None => Ok(future.await?),
+    }
+}
+
+/// Serves the requests of a QUIC connection until the client closes it, each on its own task.
+async fn serve_connection<A, B>(incoming: quinn::Incoming, limits: Limits, app: Rc<A>)
+where
+    A: Service<Request, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    let peer = incoming.remote_address();
+    let connection = match incoming.await {
+        Ok(connection) => connection,
+        Err(err) => {
+            debug!("HTTP/3 handshake with {peer} failed: {err}");
+            return;
+        }
+    };
+    let mut connection = match h3::server::Connection::<_, Bytes>::new(h3_quinn::Connection::new(
+        connection,
+    ))
+    .await
+    {
+        Ok(connection) => connection,
+        Err(err) => {
+            debug!("HTTP/3 connection with {peer} failed: {err}");
+            return;
+        }
+    };
+    loop {
+        match connection.accept().await {
+            Ok(Some(resolver)) => {
+                let app = app.clone();
+                rt::spawn(async move {
+                    let timeout = Some(limits.header_timeout).filter(|timeout| !timeout.is_zero());
+                    let result = match within(timeout, resolver.resolve_request()).await {
+                        Ok((request, stream)) => {
+                            serve_request(request, stream, peer, limits, &*app).await
+                        }
+                        Err(err) => Err(err),
+                    };
+                    if let Err(err) = result {
+                        debug!("HTTP/3 request of {peer} failed: {err}");
+                    }
+                });
+            }
+            Ok(None) => break,
+            Err(err) => {
+                debug!("HTTP/3 connection with {peer} closed: {err}");
+                break;
+            }
+        }
+    }
+}
+
+/// Reads the body of a request, up to `limits.max_body_size`; `Ok(Err(_))` is the response to
+/// send instead of passing the request on, e.g. once the body stalled for longer than
+/// `limits.read_timeout` or took longer than `limits.body_timeout`.
+async fn read_body(
+    stream: &mut Stream,
+    limits: Limits,
+) -> Result<Result<Bytes, HttpResponse>, Box<dyn std::error::Error>> {
+    let deadline = limits
+        .body_timeout
+        .map(|timeout| (Instant::now() + timeout, timeout));
+    let mut body = BytesMut::new();
+    loop {
+        let left = deadline.map(|(deadline, _)| deadline.saturating_duration_since(Instant::now()));
+        // The limit which runs out first, and whether it's the stall timeout
+        let wait = match (limits.read_timeout, left) {
+            (Some(stall), Some(left)) if left <= stall => Some((left, false)),
+            (Some(stall), _) => Some((stall, true)),
+            (None, Some(left)) => Some((left, false)),
+            (None, None) => None,
+        };
+        let chunk = match wait {
+            Some((wait, stall)) => match rt::time::timeout(wait, stream.recv_data()).await {
+                Ok(chunk) => chunk?,
+                Err(_) => {
+                    let detail = match deadline {
+                        Some((_, total)) if !stall => {
+                            format!("request body took more than {} ms", total.as_millis())
+                        }
+                        _ => format!("request body stalled for more than {} ms", wait.as_millis()),
+                    };
+                    return Ok(Err(ApiError::RequestTimeout(detail).error_response()));
+                }
+            },
+            None => stream.recv_data().await?,
+        };
+        let Some(chunk) = chunk else {
+            return Ok(Ok(body.freeze()));
+        };
+        if body.len() + chunk.remaining() > limits.max_body_size {
+            return Ok(Err(Error::from(PayloadError::Overflow).error_response()));
+        }
+        body.put(chunk);
+    }
+}
+
+/// Converts an h3 request into an actix one with `body`. actix and h3 don't share the version of
+/// the `http` crate, so everything is converted by name.
+fn convert(
+    request: &http::Request<()>,
+    body: Bytes,
+    peer: SocketAddr,
+) -> Result<Request, Box<dyn std::error::Error>> {
+    let mut converted = Request::with_payload(Payload::from(body));
+    let head = converted.head_mut();
+    head.method = Method::from_bytes(request.method().as_str().as_bytes())?;
+    head.uri = request
+        .uri()
+        .path_and_query()
+        .map_or("/", |path| path.as_str())
+        .parse::<Uri>()?;
+    head.version = Version::HTTP_3;
+    head.peer_addr = Some(peer);
+    if let Some(authority) = request.uri().authority() {
+        head.headers
+            .insert(header::HOST, HeaderValue::from_str(authority.as_str())?);
+    }
+    for (name, value) in request.headers() {
+        head.headers.append(
+            HeaderName::from_bytes(name.as_str().as_bytes())?,
+            HeaderValue::from_bytes(value.as_bytes())?,
+        );
+    }
+    Ok(converted)
+}
+
+/// Passes a request to the app and sends back its response.
+async fn serve_request<A, B>(
+    request: http::Request<()>,
+    mut stream: Stream,
+    peer: SocketAddr,
+    limits: Limits,
+    app: &A,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    A: Service<Request, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody + 'static,
+{
+    let head = request.method() == http::Method::HEAD;
+    let response: HttpResponse<BoxBody> = match read_body(&mut stream, limits).await? {
+        Ok(body) => match app.call(convert(&request, body, peer)?).await {
+            Ok(response) => response.map_into_boxed_body().into_parts().1,
+            Err(err) => err.as_response_error().error_response(),
+        },
+        Err(response) => response,
+    };
+
+    let mut head_only = http::Response::builder().status(response.status().as_u16());
+    for (name, value) in response.headers() {
+        // Connection-specific headers are forbidden in HTTP/3
+        if name != header::CONNECTION && name != header::TRANSFER_ENCODING {
+            head_only = head_only.header(name.as_str(), value.as_bytes());
+        }
+    }
+    let timeout = limits.write_timeout;
+    within(timeout, stream.send_response(head_only.body(())?)).await?;
+    if !head {
+        let mut body = pin!(response.into_body());
+        while let Some(chunk) = poll_fn(|cx| body.as_mut().poll_next(cx)).await {
+            within(timeout, stream.send_data(chunk?)).await?;
+        }
+    }
+    within(timeout, stream.finish()).await?;
+    Ok(())
+}
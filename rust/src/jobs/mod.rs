@@ -0,0 +1,142 @@
+pub mod notifier;
+pub mod replication;
+pub mod scheduler;
+pub mod stats;
+
+use actix_web::{rt, web};
+use std::sync::Arc;
+use tokio::sync::{
+    Mutex,
+    mpsc::{self, Receiver, Sender, error::TrySendError},
+};
+use tracing::{debug, warn};
+
+use crate::{
+    scheme::{posts::Post, users::UsersProvider},
+    state::Metrics,
+};
+pub use notifier::*;
+use percom_model::urls;
+
+/// Maximum length (in characters) of the post excerpt included in notifications.
+const EXCERPT_LEN: usize = 200;
+
+/// Background work enqueued by request handlers.
+#[derive(Debug, Clone)]
+pub enum Job {
+    /// A post was created; followers of its author should be notified.
+    PostCreated(Post),
+}
+
+/// Handle to the internal asynchronous job queue.
+///
+/// The queue is a bounded `tokio` channel drained by a pool of worker tasks. Handlers enqueue jobs
+/// without waiting for them; when the queue is full, new jobs are dropped (and counted) instead of
+/// slowing down the request path.
+///
+/// The number of queued jobs is exposed as the `jobs.queue_depth` gauge of [`Metrics`].
+#[derive(Clone)]
+pub struct JobQueue {
+    sender: Sender<Job>,
+    metrics: Arc<Metrics>,
+}
+
+impl JobQueue {
+    /// Creates the queue and spawns `workers` worker tasks on the current Actix runtime.
+    ///
+    /// Notifications are resolved through `users` and delivered via `notifier`.
+    pub fn start(
+        workers: usize,
+        capacity: usize,
+        notifier: Arc<dyn Notifier>,
+        users: Arc<dyn UsersProvider>,
+        metrics: Arc<Metrics>,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel(capacity);
+        let receiver = Arc::new(Mutex::new(receiver));
+        for idx in 0..workers {
+            rt::spawn(worker(
+                idx,
+                receiver.clone(),
+                notifier.clone(),
+                users.clone(),
+                metrics.clone(),
+            ));
+        }
+        debug!("Job queue started with {workers} workers and capacity {capacity}");
+        Self { sender, metrics }
+    }
+
+    /// Enqueues a job without waiting. The job is dropped if the queue is full or closed.
+    ///
+    /// The depth is counted before the job is sent, as a worker on another thread may take it and
+    /// count it out before `try_send` returns.
+    pub fn enqueue(&self, job: Job) {
+        Metrics::inc(&self.metrics.jobs_queue_depth);
+        match self.sender.try_send(job) {
+            Ok(()) => return,
+            Err(TrySendError::Full(job)) => warn!("Job queue is full; dropping {job:?}"),
+            Err(TrySendError::Closed(job)) => warn!("Job queue is closed; dropping {job:?}"),
+        }
+        Metrics::dec(&self.metrics.jobs_queue_depth);
+        Metrics::inc(&self.metrics.jobs_dropped);
+    }
+}
+
+/// Worker loop: takes jobs from the shared receiver until the queue is closed.
+async fn worker(
+    idx: usize,
+    receiver: Arc<Mutex<Receiver<Job>>>,
+    notifier: Arc<dyn Notifier>,
+    users: Arc<dyn UsersProvider>,
+    metrics: Arc<Metrics>,
+) {
+    loop {
+        let Some(job) = receiver.lock().await.recv().await else {
+            debug!("Job worker #{idx} stopped");
+            return;
+        };
+        Metrics::dec(&metrics.jobs_queue_depth);
+        let notifications = match &job {
+            Job::PostCreated(post) => post_created(post, users.as_ref()),
+        };
+        for notification in notifications {
+            let notifier = notifier.clone();
+            // Notifiers are allowed to block
+            match web::block(move || notifier.notify(&notification)).await {
+                Ok(Ok(())) => Metrics::inc(&metrics.notifications_sent),
+                Ok(Err(err)) => {
+                    warn!("Fail to deliver notification: {err}");
+                    Metrics::inc(&metrics.notifications_failed);
+                }
+                Err(err) => {
+                    warn!("Fail to run notifier: {err}");
+                    Metrics::inc(&metrics.notifications_failed);
+                }
+            }
+        }
+        Metrics::inc(&metrics.jobs_processed);
+    }
+}
+
+/// Builds notifications about a new post for every follower of its author.
+///
+/// If followers can't be resolved, the failure is logged and nobody is notified.
+fn post_created(post: &Post, users: &dyn UsersProvider) -> Vec<Notification> {
+    let excerpt: String = post.content.chars().take(EXCERPT_LEN).collect();
+    let followers = match users.followers(&post.author) {
+        Ok(followers) => followers,
+        Err(err) => {
+            warn!("Fail to get followers of {}: {err}", post.author);
+            return Vec::new();
+        }
+    };
+    followers
+        .into_iter()
+        .map(|user| Notification {
+            to: user.email,
+            subject: format!("New post by {}", post.author),
+            body: format!("{excerpt}\n\n{}", urls::posts::by_id(&post.id)),
+        })
+        .collect()
+}
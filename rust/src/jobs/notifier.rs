@@ -0,0 +1,116 @@
+use std::{io, sync::Arc};
+use tracing::{debug, warn};
+
+use crate::envs;
+
+/// A message to be delivered to a single recipient.
+#[derive(Debug, Clone)]
+pub struct Notification {
+    /// Email address of the recipient.
+    pub to: String,
+
+    /// Short summary of the notification.
+    pub subject: String,
+
+    /// Plain-text body of the notification.
+    pub body: String,
+}
+
+/// Delivery channel for [`Notification`]s.
+///
+/// Notifiers are called by the job queue workers on the blocking pool, so implementations may
+/// perform blocking I/O (e.g. talk to an SMTP relay).
+pub trait Notifier: Send + Sync {
+    /// Delivers a single notification.
+    ///
+    /// # Errors
+    /// Returns an `io::Error` if the notification couldn't be delivered.
+    fn notify(&self, notification: &Notification) -> io::Result<()>;
+}
+
+/// Notifier which only writes notifications to the log.
+///
+/// Used by default, so the job queue can be exercised in benchmarks without any mail infrastructure.
+#[derive(Debug, Default)]
+pub struct LogNotifier {}
+
+impl Notifier for LogNotifier {
+    fn notify(&self, notification: &Notification) -> io::Result<()> {
+        debug!(
+            "Notification to {}: {} ({} bytes)",
+            notification.to,
+            notification.subject,
+            notification.body.len()
+        );
+        Ok(())
+    }
+}
+
+#[cfg(feature = "smtp")]
+/// Notifier delivering notifications as emails through an SMTP relay.
+///
+/// The connection is unencrypted and unauthenticated, which suits local mail catchers used in
+/// benchmark environments. Available with the `smtp` cargo feature.
+pub struct SmtpNotifier {
+    transport: lettre::SmtpTransport,
+    from: lettre::message::Mailbox,
+}
+
+#[cfg(feature = "smtp")]
+impl SmtpNotifier {
+    /// Creates a notifier for the relay at `addr` (`host:port`), sending from `from`.
+    ///
+    /// # Errors
+    /// Returns an `io::Error` if the address or the sender can't be parsed.
+    pub fn new(addr: &str, from: &str) -> io::Result<Self> {
+        let (host, port) = addr
+            .rsplit_once(':')
+            .and_then(|(host, port)| Some((host, port.parse::<u16>().ok()?)))
+            .ok_or_else(|| io::Error::other(format!("invalid SMTP address: {addr}")))?;
+        Ok(Self {
+            transport: lettre::SmtpTransport::builder_dangerous(host)
+                .port(port)
+                .build(),
+            from: from.parse().map_err(io::Error::other)?,
+        })
+    }
+}
+
+#[cfg(feature = "smtp")]
+impl Notifier for SmtpNotifier {
+    fn notify(&self, notification: &Notification) -> io::Result<()> {
+        use lettre::Transport;
+
+        let message = lettre::Message::builder()
+            .from(self.from.clone())
+            .to(notification.to.parse().map_err(io::Error::other)?)
+            .subject(&notification.subject)
+            .body(notification.body.clone())
+            .map_err(io::Error::other)?;
+        self.transport
+            .send(&message)
+            .map(|_| ())
+            .map_err(io::Error::other)
+    }
+}
+
+/// Creates the notifier selected with the `RUST_SERVER_NOTIFIER` environment variable.
+///
+/// Unknown names, as well as `smtp` in builds without the `smtp` feature, fall back to [`LogNotifier`].
+///
+/// # Errors
+/// Returns an `io::Error` if the selected notifier can't be configured.
+pub fn from_env() -> io::Result<Arc<dyn Notifier>> {
+    match envs::vars::get_notifier().as_str() {
+        #[cfg(feature = "smtp")]
+        "smtp" => Ok(Arc::new(SmtpNotifier::new(
+            &envs::vars::get_smtp_addr(),
+            &envs::vars::get_smtp_from(),
+        )?)),
+        "log" => Ok(Arc::new(LogNotifier::default())),
+        other => {
+            warn!("Notifier \"{other}\" isn't available; falling back to \"log\"");
+            Ok(Arc::new(LogNotifier::default()))
+        }
+    }
+}
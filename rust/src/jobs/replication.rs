@@ -0,0 +1,216 @@
+use actix_web::{rt, web};
+use reqwest::{Client, Response};
+use std::{collections::HashSet, convert::Infallible, sync::Arc, time::Duration};
+use tracing::{debug, error, info, warn};
+
+use crate::scheme::{
+    admin::dataset::{self, Format},
+    posts::PostsProvider,
+    replication::{Change, ChangeEvent, Heartbeat, Replica},
+};
+use percom_model::urls;
+
+/// Delay before reconnecting to the primary after the change stream ends.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Starts a task on the current Actix runtime replicating the posts of the `primary` instance
+/// into `posts` (see [`crate::scheme::replication`]).
+///
+/// The task subscribes to `GET /admin/changes` of the primary first, then loads a snapshot with
+/// `GET /admin/posts/export`, replacing every local post, and applies the streamed changes from
+/// then on. Changes made between subscribing and exporting are applied twice, which is harmless
+/// since they carry whole posts. Whenever the stream ends (e.g. the primary restarts, or the
+/// replica falls too far behind), it starts over after [`RECONNECT_DELAY`].
+pub fn start(primary: String, token: String, posts: Arc<dyn PostsProvider>, replica: Arc<Replica>) {
+    rt::spawn(async move {
+        // Connections aren't kept: a stopping primary waits for idle ones to close, and a
+        // request sent on one would hang until it's gone
+        let client = match Client::builder().pool_max_idle_per_host(0).build() {
+            Ok(client) => client,
+            Err(err) => {
+                error!("Fail to create the replication client: {err}");
+                return;
+            }
+        };
+        loop {
+            let Err(err) = replicate(&client, &primary, &token, &posts, &replica).await;
+            warn!("Replication from {primary} stopped: {err}");
+            replica.disconnected(err);
+            rt::time::sleep(RECONNECT_DELAY).await;
+        }
+    });
+}
+
+/// Syncs with the primary and applies its changes until the change stream ends.
+async fn replicate(
+    client: &Client,
+    primary: &str,
+    token: &str,
+    posts: &Arc<dyn PostsProvider>,
+    replica: &Replica,
+) -> Result<Infallible, String> {
+    let get = |path: String| {
+        let request = client.get(format!("{primary}{path}")).bearer_auth(token);
+        async move {
+            request
+                .send()
+                .await
+                .and_then(Response::error_for_status)
+                .map_err(|err| format!("GET {path}: {err}"))
+        }
+    };
+    let mut stream = Events::new(get(urls::admin::changes()).await?);
+    // The stream opens with a heartbeat, which tells the changes the snapshot includes
+    let mut pending = Vec::new();
+    let seq = loop {
+        match stream.next().await? {
+            Event::Heartbeat(heartbeat) => break heartbeat.seq,
+            Event::Change(event) => pending.push(event),
+        }
+    };
+    let snapshot = get(format!("{}?format=jsonl", urls::admin::export_posts()))
+        .await?
+        .bytes()
+        .await
+        .map_err(|err| format!("fail to read snapshot: {err}"))?;
+    let target = posts.clone();
+    let synced = web::block(move || sync(target.as_ref(), &snapshot))
+        .await
+        .map_err(|err| err.to_string())??;
+    replica.synced(seq);
+    info!("Replica synced {synced} posts from {primary} at change {seq}");
+    loop {
+        if !pending.is_empty() {
+            apply(posts, std::mem::take(&mut pending), replica).await?;
+        }
+        match stream.next().await? {
+            Event::Heartbeat(heartbeat) => replica.heard(&heartbeat),
+            Event::Change(event) => {
+                pending.push(event);
+                // Applies everything received so far at once
+                pending.extend(stream.buffered()?);
+            }
+        }
+    }
+}
+
+/// Replaces the local posts with the `snapshot` of the primary, a JSON Lines export. Returns the
+/// number of posts.
+fn sync(posts: &dyn PostsProvider, snapshot: &[u8]) -> Result<usize, String> {
+    let snapshot = dataset::decode(Format::Jsonl, snapshot)?;
+    let ids: HashSet<&str> = snapshot.iter().map(|post| post.id.as_str()).collect();
+    let stale: Vec<String> = posts
+        .get_all()
+        .map_err(|err| err.to_string())?
+        .into_iter()
+        .filter(|post| !ids.contains(post.id.as_str()))
+        .map(|post| post.id)
+        .collect();
+    for id in stale {
+        posts.delete(&id).map_err(|err| err.to_string())?;
+    }
+    posts.import(snapshot).map_err(|err| err.to_string())
+}
+
+/// Applies `events` in order on the blocking pool.
+async fn apply(
+    posts: &Arc<dyn PostsProvider>,
+    events: Vec<ChangeEvent>,
+    replica: &Replica,
+) -> Result<(), String> {
+    let target = posts.clone();
+    let events = web::block(move || {
+        for event in events.iter() {
+            match &event.change {
+                Change::Upsert { post } => target.import(vec![post.clone()]).map(|_| ()),
+                Change::Delete { id } => target.delete(id).map(|_| ()),
+            }
+            .map_err(|err| format!("fail to apply change {}: {err}", event.seq))?;
+        }
+        Ok::<_, String>(events)
+    })
+    .await
+    .map_err(|err| err.to_string())??;
+    if let Some(last) = events.last() {
+        debug!("Applied {} changes, up to {}", events.len(), last.seq);
+        replica.applied(last, events.len());
+    }
+    Ok(())
+}
+
+/// Event of the change stream.
+enum Event {
+    Change(ChangeEvent),
+    Heartbeat(Heartbeat),
+}
+
+/// Parser of the `text/event-stream` body of `GET /admin/changes`.
+struct Events {
+    response: Response,
+
+    /// Received bytes not parsed yet.
+    buffer: Vec<u8>,
+}
+
+impl Events {
+    fn new(response: Response) -> Self {
+        Self {
+            response,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Returns the next event, waiting for it if needed.
+    async fn next(&mut self) -> Result<Event, String> {
+        loop {
+            if let Some(event) = self.parse()? {
+                return Ok(event);
+            }
+            match self.response.chunk().await {
+                Ok(Some(chunk)) => self.buffer.extend_from_slice(&chunk),
+                Ok(None) => return Err("change stream closed by the primary".to_owned()),
+                Err(err) => return Err(format!("change stream failed: {err}")),
+            }
+        }
+    }
+
+    /// Returns the changes received already, without waiting. Heartbeats are skipped.
+    fn buffered(&mut self) -> Result<Vec<ChangeEvent>, String> {
+        let mut changes = Vec::new();
+        while let Some(event) = self.parse()? {
+            if let Event::Change(event) = event {
+                changes.push(event);
+            }
+        }
+        Ok(changes)
+    }
+
+    /// Takes the first complete event off the buffer.
+    fn parse(&mut self) -> Result<Option<Event>, String> {
+        let Some(end) = self.buffer.windows(2).position(|bytes| bytes == b"\n\n") else {
+            return Ok(None);
+        };
+        let block: Vec<u8> = self.buffer.drain(..end + 2).collect();
+        let block = String::from_utf8_lossy(&block);
+        let (mut name, mut data) = ("", "");
+        for line in block.lines() {
+            if let Some(value) = line.strip_prefix("event: ") {
+                name = value;
+            } else if let Some(value) = line.strip_prefix("data: ") {
+                data = value;
+            }
+        }
+        let malformed = |err: serde_json::Error| format!("malformed {name} event: {err}");
+        match name {
+            "change" => Ok(Some(Event::Change(
+                serde_json::from_str(data).map_err(malformed)?,
+            ))),
+            "heartbeat" => Ok(Some(Event::Heartbeat(
+                serde_json::from_str(data).map_err(malformed)?,
+            ))),
+            "lagged" => Err("replica fell too far behind the primary".to_owned()),
+            // Unknown events are skipped, so the primary may add new ones
+            _ => self.parse(),
+        }
+    }
+}
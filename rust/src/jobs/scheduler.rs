@@ -0,0 +1,34 @@
+use actix_web::rt;
+use chrono::Utc;
+use std::{sync::Arc, time::Duration};
+use tracing::{debug, warn};
+
+use crate::{
+    jobs::{Job, JobQueue},
+    scheme::posts::PostsProvider,
+};
+
+/// Starts the post publication scheduler on the current Actix runtime.
+///
+/// Every `interval`, scheduled posts whose `publish_at` time has passed are switched to published,
+/// and a [`Job::PostCreated`] is enqueued for each of them, so followers are notified when the post
+/// actually becomes visible rather than when it was submitted.
+pub fn start(provider: Arc<dyn PostsProvider>, jobs: JobQueue, interval: Duration) {
+    rt::spawn(async move {
+        let mut ticks = rt::time::interval(interval);
+        loop {
+            ticks.tick().await;
+            let published = match provider.publish_due(Utc::now()) {
+                Ok(published) => published,
+                Err(err) => {
+                    warn!("Fail to publish scheduled posts: {err}");
+                    continue;
+                }
+            };
+            for post in published {
+                debug!("Scheduled post {} is published", post.id);
+                jobs.enqueue(Job::PostCreated(post));
+            }
+        }
+    });
+}
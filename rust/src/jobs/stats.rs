@@ -0,0 +1,22 @@
+use actix_web::rt;
+use serde_json::json;
+use std::{sync::Arc, time::Duration};
+use tracing::info;
+
+use crate::scheme::{posts::PostsProvider, users::UsersProvider};
+
+/// Starts a task on the current Actix runtime which logs the stats of the providers every
+/// `interval`, the same as `GET /admin/providers` returns.
+///
+/// Useful during long benchmark runs, to see how indexes, lock waits and compactions evolve
+/// without polling the endpoint.
+pub fn start(posts: Arc<dyn PostsProvider>, users: Arc<dyn UsersProvider>, interval: Duration) {
+    rt::spawn(async move {
+        let mut ticks = rt::time::interval(interval);
+        loop {
+            ticks.tick().await;
+            let stats = json!({ "posts": posts.stats(), "users": users.stats() });
+            info!("Provider stats: {stats}");
+        }
+    });
+}
@@ -1,15 +1,43 @@
 #[cfg(test)]
 mod tests;
 
+mod client_ip;
+mod connection;
+mod datagen;
 pub(crate) mod envs;
+#[cfg(feature = "http3")]
+mod http3;
+mod jobs;
+mod middleware;
+mod offload;
+mod results;
 pub(crate) mod scheme;
+mod smoke;
 mod state;
+mod summary;
+mod ui;
 
-use actix_web::{App, HttpServer, web};
+use actix_web::{
+    App, HttpServer,
+    dev::Server,
+    middleware::{Condition, from_fn},
+    web,
+};
+use percom_model::urls;
+use std::{
+    env, iter,
+    net::TcpListener,
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
 
-use crate::envs::vars::get_server_addr;
+use crate::envs::vars::{get_max_body_size, get_server_addr};
 
-/// Launches the HTTP server and binds the route handlers for two resource families: `/posts` and `/users`.
+/// Launches the HTTP server and binds the route handlers for two resource families: `/posts` and `/users`,
+/// plus the `/feed` read path combining both of them.
 ///
 /// The `/posts` endpoints implement the required functionality as defined in the original OpenAPI specification,
 /// and are fully covered by the automated test suite using property-based testing (`proptest`).
@@ -17,42 +45,528 @@ use crate::envs::vars::get_server_addr;
 /// The `/users` endpoints are included as an example to demonstrate how the project can be extended with additional
 /// resource groups. These endpoints are not covered by tests and are meant for illustrative purposes only.
 ///
-/// This is the main entry point of the application, executed using the Actix-Web asynchronous runtime.
+/// The server accepts connections on the listener returned by `bind` once the returned [`Server`]
+/// is polled; `bind` is called once the state is set up, including a restored snapshot (see
+/// [`ServeOptions`]). With the `http3` feature, the app is also served over HTTP/3 on
+/// `RUST_SERVER_H3_ADDR` right away, if it's set.
 ///
 /// # Returns
-/// Returns an `std::io::Result<()>` indicating whether the server launched successfully or encountered an I/O error.
-#[actix_web::main]
-async fn main() -> std::io::Result<()> {
-    // Init logs
-    let guard = envs::logs::init()?;
+/// Returns an `std::io::Result` indicating whether the server was set up successfully or encountered an I/O error,
+/// with the [`Server`] and its [`summary::Session`] on success.
+fn start(
+    bind: impl FnOnce() -> std::io::Result<TcpListener>,
+    options: &ServeOptions,
+) -> std::io::Result<(Server, summary::Session)> {
+    let read_only = options.read_only || options.mmap.is_some();
+    let metrics = Arc::new(state::Metrics::default());
+    // Before any post is read, e.g. from the WAL
+    let precision = envs::vars::get_date_precision();
+    scheme::posts::date::set_precision(
+        scheme::posts::date::Precision::from_name(&precision)
+            .ok_or_else(|| std::io::Error::other(format!("unknown date precision: {precision}")))?,
+    );
     // Create providers
     let users_provider = scheme::users::DummyProvider::wrapped();
-    let posts_provider = scheme::posts::DummyProvider::wrapped();
+    // Circuit breakers of external storages, listed at /admin/breakers
+    let mut breakers = Vec::new();
+    let codec = envs::vars::get_content_compression();
+    let compression = scheme::posts::Compression::new(
+        scheme::posts::Codec::from_name(&codec).ok_or_else(|| {
+            std::io::Error::other(format!("unknown content compression: {codec}"))
+        })?,
+        metrics.clone(),
+    );
+    let slashes = envs::vars::get_trailing_slash();
+    let slashes = middleware::normalize::Slashes::from_name(&slashes).ok_or_else(|| {
+        std::io::Error::other(format!("unknown trailing slash behavior: {slashes}"))
+    })?;
+    let lowercase_paths = envs::vars::get_lowercase_paths();
+    let query_mode = envs::vars::get_query_params();
+    let query_mode = scheme::params::QueryMode::from_name(&query_mode).ok_or_else(|| {
+        std::io::Error::other(format!("unknown query parameter behavior: {query_mode}"))
+    })?;
+    // Known upfront, so there's a shard for every worker
+    let workers = envs::vars::get_workers();
+    let worker_local = match envs::vars::get_state_mode().as_str() {
+        "shared" => false,
+        "worker-local" => true,
+        other => {
+            return Err(std::io::Error::other(format!(
+                "unknown state mode: {other}"
+            )));
+        }
+    };
+    // Set with worker-local state, to give every worker its view (see the app factory below)
+    let mut sharded = None;
+    let posts_provider: Arc<dyn scheme::posts::PostsProvider> = match &options.mmap {
+        Some(path) => scheme::posts::MmapProvider::open(path)?,
+        None => match envs::vars::get_posts_provider().as_str() {
+            "memory" if worker_local => {
+                let shards = (0..workers)
+                    .map(|_| {
+                        scheme::posts::DummyProvider::wrapped(compression.clone())
+                            as Arc<dyn scheme::posts::PostsProvider>
+                    })
+                    .collect();
+                let provider = scheme::posts::ShardedProvider::wrapped(shards);
+                sharded = Some(provider.clone());
+                provider
+            }
+            "memory" => scheme::posts::DummyProvider::wrapped(compression),
+            "wal" => wal_provider(compression, &metrics, &mut breakers)?,
+            "kv" => guarded(
+                scheme::posts::KvProvider::open(&envs::vars::get_kv_path()?)?,
+                "KV",
+                &metrics,
+                &mut breakers,
+            ),
+            "tiered" => {
+                let spec = envs::vars::get_tier_routing();
+                let routing = scheme::posts::Routing::from_spec(&spec).ok_or_else(|| {
+                    std::io::Error::other(format!("invalid tier routing: {spec}"))
+                })?;
+                let provider = scheme::posts::TieredProvider::wrapped(
+                    scheme::posts::DummyProvider::wrapped(compression.clone()),
+                    wal_provider(compression, &metrics, &mut breakers)?,
+                    routing,
+                );
+                provider.spawn_demotion(envs::vars::get_tier_demotion_interval());
+                provider
+            }
+            other => {
+                return Err(std::io::Error::other(format!(
+                    "unknown posts provider: {other}"
+                )));
+            }
+        },
+    };
+    if worker_local && sharded.is_none() {
+        return Err(std::io::Error::other(
+            "worker-local state requires the memory posts provider",
+        ));
+    }
+    // Publish changes of posts for replicas; a replica follows its primary
+    let changes = Arc::new(scheme::replication::Changes::default());
+    changes.close_on_shutdown();
+    let mut posts_provider: Arc<dyn scheme::posts::PostsProvider> =
+        scheme::posts::ChangesProvider::wrapped(posts_provider, changes.clone());
+    // Cache outermost, so that every write drops what it changed
+    let cache_size = envs::vars::get_post_cache_size();
+    if cache_size > 0 && worker_local {
+        return Err(std::io::Error::other(
+            "the post cache can't be combined with worker-local state, whose workers write \
+             around it",
+        ));
+    }
+    if cache_size > 0 {
+        posts_provider = scheme::posts::CacheProvider::wrapped(posts_provider, cache_size);
+    }
+    if let Some(path) = &options.restore {
+        restore(path, &posts_provider)?;
+    }
+    let replica = envs::vars::get_replicate_from().map(|primary| {
+        let replica = Arc::new(scheme::replication::Replica::new(primary.clone()));
+        jobs::replication::start(
+            primary,
+            envs::vars::get_replication_token(),
+            posts_provider.clone(),
+            replica.clone(),
+        );
+        replica
+    });
+    let header = envs::vars::get_forwarded_header();
+    let header = client_ip::ForwardedHeader::from_name(&header)
+        .ok_or_else(|| std::io::Error::other(format!("unknown forwarding header: {header}")))?;
+    let spec = envs::vars::get_trusted_proxies();
+    let trusted_proxies = client_ip::TrustedProxies::from_spec(&spec, header)
+        .map_err(|err| std::io::Error::other(format!("invalid trusted proxies: {err}")))?;
+    let spec = envs::vars::get_acl();
+    let acl = middleware::acl::Acl::from_spec(&spec)
+        .map_err(|err| std::io::Error::other(format!("invalid ACL: {err}")))?;
+    let signing = middleware::signature::Signing::from_spec(
+        &envs::vars::get_signing_keys(),
+        envs::vars::get_signature_max_skew(),
+        get_max_body_size(),
+    )
+    .map_err(|err| std::io::Error::other(format!("invalid signing keys: {err}")))?;
+    let flags = scheme::flags::FeatureFlags::from_spec(&envs::vars::get_feature_flags())
+        .map_err(|err| std::io::Error::other(format!("invalid feature flags: {err}")))?;
+    let lanes = middleware::lanes::Lanes::from_spec(&envs::vars::get_lanes())
+        .map_err(|err| std::io::Error::other(format!("invalid lanes: {err}")))?;
+    let work = middleware::work::WorkFactors::from_spec(&envs::vars::get_work_factors())
+        .map_err(|err| std::io::Error::other(format!("invalid work factors: {err}")))?;
     // Create global states
-    let global_state = web::Data::new(state::GlobalServerState::new(users_provider.clone()));
+    let global_state = web::Data::new(state::GlobalServerState {
+        provider: users_provider.clone(),
+        metrics: metrics.clone(),
+        provider_deadline: envs::vars::get_provider_deadline(),
+        read_only: read_only.then(|| middleware::read_only::ReadOnly {
+            primary: envs::vars::get_primary_url(),
+        }),
+        trusted_proxies,
+        acl: Arc::new(acl),
+        signing,
+        flags: Arc::new(flags),
+        work: Arc::new(work),
+        offload: offload::Offload::new(envs::vars::get_offload_pool_size(), metrics.clone()),
+        lanes,
+        client_limits: middleware::slow_clients::ClientLimits {
+            read_timeout: envs::vars::get_client_read_timeout(),
+            body_timeout: envs::vars::get_client_body_timeout(),
+            max_header_size: envs::vars::get_max_header_size(),
+        },
+        maintenance: Arc::default(),
+        mirror: envs::vars::get_mirror_url().map(|url| {
+            middleware::mirror::Mirror::new(
+                url,
+                envs::vars::get_mirror_percent(),
+                envs::vars::get_max_body_size(),
+            )
+        }),
+        auth_failures: Arc::new(middleware::auth_failures::AuthFailures::new(
+            envs::vars::get_auth_lockout_failures().map(|failures| {
+                middleware::auth_failures::Lockout {
+                    failures,
+                    duration: envs::vars::get_auth_lockout(),
+                }
+            }),
+        )),
+    });
+    // Start background jobs
+    let jobs = jobs::JobQueue::start(
+        envs::vars::get_job_workers(),
+        envs::vars::get_job_queue_capacity(),
+        jobs::notifier::from_env()?,
+        users_provider.clone(),
+        metrics.clone(),
+    );
+    jobs::scheduler::start(
+        posts_provider.clone(),
+        jobs.clone(),
+        envs::vars::get_scheduler_interval(),
+    );
+    if let Some(interval) = envs::vars::get_provider_stats_interval() {
+        jobs::stats::start(posts_provider.clone(), users_provider.clone(), interval);
+    }
+    let mode = envs::vars::get_sanitize_mode();
+    let sanitizer = scheme::posts::sanitize::Sanitizer {
+        mode: scheme::posts::sanitize::SanitizeMode::from_name(&mode)
+            .ok_or_else(|| std::io::Error::other(format!("unknown sanitize mode: {mode}")))?,
+        strip_html: envs::vars::get_sanitize_html(),
+    };
+    let moderation = Arc::new(scheme::moderation::ModerationQueue::default());
     // Create local/context states
-    let posts_state = web::Data::new(scheme::posts::routes::PostsState::new(posts_provider));
-    let users_state = web::Data::new(scheme::users::routes::UsersState::new(users_provider));
-    HttpServer::new(move || {
+    let posts_state = web::Data::new(scheme::posts::routes::PostsState::new(
+        posts_provider.clone(),
+        jobs,
+        envs::vars::get_idempotent_delete(),
+        sanitizer,
+        scheme::moderation::from_env(),
+        moderation.clone(),
+    ));
+    let feed_state = web::Data::new(scheme::feed::routes::FeedState::new(
+        users_provider.clone(),
+        posts_provider.clone(),
+    ));
+    let admin_state = web::Data::new(scheme::admin::routes::AdminState::new(
+        posts_provider.clone(),
+        users_provider.clone(),
+        metrics.clone(),
+        breakers,
+        changes.clone(),
+        replica,
+        moderation,
+    ));
+    let session = summary::Session::start(
+        metrics.clone(),
+        posts_provider.clone(),
+        users_provider.clone(),
+    );
+    let users_state = web::Data::new(scheme::users::routes::UsersState::new(
+        users_provider,
+        posts_provider,
+    ));
+    let max_body_size = get_max_body_size();
+    let next_worker = Arc::new(AtomicUsize::new(0));
+    let app = move || {
+        // The factory runs once per worker; with worker-local state, each creates posts in its own
+        // shard
+        let posts_state = match &sharded {
+            Some(sharded) => web::Data::new(scheme::posts::routes::PostsState {
+                provider: scheme::posts::ChangesProvider::wrapped(
+                    sharded.worker(next_worker.fetch_add(1, Ordering::Relaxed)),
+                    changes.clone(),
+                ),
+                ..posts_state.as_ref().clone()
+            }),
+            None => posts_state.clone(),
+        };
         App::new()
             // Create global state
             .app_data(global_state.clone())
+            // Limits of decompressed bodies; scopes may register their own
+            .app_data(
+                web::JsonConfig::default()
+                    .limit(max_body_size)
+                    .error_handler(scheme::error::json_error),
+            )
+            .app_data(web::PayloadConfig::new(max_body_size))
+            .app_data(query_mode)
             .service(
-                web::scope("/posts")
+                web::scope(urls::posts::SCOPE)
                     // Create local state
-                    .app_data(posts_state.clone())
+                    .app_data(posts_state)
                     .configure(scheme::posts::routes::configure),
             )
             .service(
-                web::scope("/users")
+                web::scope(urls::users::SCOPE)
                     // Create local state
                     .app_data(users_state.clone())
                     .configure(scheme::users::routes::configure),
             )
-    })
-    .bind(get_server_addr()?)?
-    .run()
-    .await?;
+            .service(
+                web::scope(urls::feed::SCOPE)
+                    // Create local state
+                    .app_data(feed_state.clone())
+                    .configure(scheme::feed::routes::configure),
+            )
+            .service(
+                web::scope(urls::admin::SCOPE)
+                    // Create local state
+                    .app_data(admin_state.clone())
+                    .configure(scheme::admin::routes::configure),
+            )
+            .service(web::scope(urls::auth::SCOPE).configure(scheme::auth::routes::configure))
+            .service(web::scope(urls::metrics::SCOPE).configure(scheme::metrics::routes::configure))
+            .configure(ui::configure)
+            // Innermost, so only requests which reach their handler pay for the work
+            .wrap(from_fn(middleware::work::burn_cpu))
+            .wrap(from_fn(
+                middleware::maintenance::reject_writes_in_maintenance,
+            ))
+            .wrap(from_fn(middleware::read_only::reject_writes))
+            .wrap(from_fn(middleware::signature::verify_signature))
+            .wrap(from_fn(
+                middleware::content_encoding::check_content_encoding,
+            ))
+            // Before the body is decompressed or checked, so the shadow gets it as sent
+            .wrap(from_fn(middleware::mirror::mirror_requests))
+            // Outside of the middlewares reading the body, so they can't wait forever either
+            .wrap(from_fn(middleware::slow_clients::guard_slow_clients))
+            // Inside the ACL, so denied clients don't take slots
+            .wrap(from_fn(middleware::lanes::limit_lanes))
+            // Before routing, so denied clients can't even tell which paths exist
+            .wrap(from_fn(middleware::acl::check_acl))
+            // Outside of the ACL, so its denials are counted, and locked out clients don't reach it
+            .wrap(from_fn(middleware::auth_failures::track_auth_failures))
+            // Outside of the middlewares routing by the path, so they see it normalized; the
+            // signature covers the path as sent, which is kept in the request's extensions
+            .wrap(slashes.middleware())
+            .wrap(Condition::new(
+                lowercase_paths,
+                from_fn(middleware::normalize::lowercase_scope),
+            ))
+            .wrap(from_fn(middleware::normalize::keep_original_path))
+            .wrap(from_fn(middleware::access_log::log_access))
+            // So panics anywhere down the stack become 500 responses
+            .wrap(from_fn(middleware::catch_panic::catch_panic))
+            // Outside of catch_panic, so the 500 responses of panics are translated too
+            .wrap(from_fn(middleware::localize::localize_errors))
+            // Outside of every other middleware, so every response is counted with its final status
+            .wrap(from_fn(middleware::route_stats::count_requests))
+            // Outermost, so everything logged for a request carries its run
+            .wrap(from_fn(middleware::run_id::tag_runs))
+    };
+    #[cfg(feature = "http3")]
+    if let Some(addr) = envs::vars::get_h3_addr()? {
+        let limits = http3::Limits {
+            max_body_size,
+            header_timeout: envs::vars::get_client_header_timeout(),
+            read_timeout: envs::vars::get_client_read_timeout(),
+            body_timeout: envs::vars::get_client_body_timeout(),
+            write_timeout: envs::vars::get_client_write_timeout(),
+        };
+        http3::start(addr, limits, app.clone())?;
+    }
+    let server = HttpServer::new(app)
+        .workers(workers)
+        .client_request_timeout(envs::vars::get_client_header_timeout())
+        .on_connect(connection::write_timeout(
+            envs::vars::get_client_write_timeout(),
+        ));
+    let server = if envs::vars::get_h2c() {
+        server.listen_auto_h2c(bind()?)?
+    } else {
+        server.listen(bind()?)?
+    };
+    Ok((server.run(), session))
+}
+
+/// Options of the API server, given on the command line (see [`main`]).
+#[derive(Debug, Default)]
+struct ServeOptions {
+    /// Set by `--read-only`: requests which may write are rejected (see
+    /// [`middleware::read_only::reject_writes`]).
+    read_only: bool,
+
+    /// Set by `--restore <path>`: a dataset loaded into the posts provider before the server
+    /// binds its address, e.g. an export of an earlier run (see [`restore`]).
+    restore: Option<PathBuf>,
+
+    /// Set by `--mmap <path>`: a JSON Lines dataset served read-only from a memory-mapped file
+    /// instead of the configured posts provider (see [`scheme::posts::MmapProvider`]); implies
+    /// `--read-only`.
+    mmap: Option<PathBuf>,
+}
+
+impl ServeOptions {
+    /// Parses the options from the command line arguments.
+    fn parse(mut args: impl Iterator<Item = String>) -> std::io::Result<Self> {
+        let mut options = Self::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--read-only" => options.read_only = true,
+                "--restore" => {
+                    let path = args
+                        .next()
+                        .ok_or_else(|| std::io::Error::other("--restore expects a path"))?;
+                    options.restore = Some(path.into());
+                }
+                "--mmap" => {
+                    let path = args
+                        .next()
+                        .ok_or_else(|| std::io::Error::other("--mmap expects a path"))?;
+                    options.mmap = Some(path.into());
+                }
+                other => {
+                    return Err(std::io::Error::other(format!(
+                        "unknown option: {other}; expected `--read-only`, `--restore <path>` or \
+                         `--mmap <path>`"
+                    )));
+                }
+            }
+        }
+        if options.restore.is_some() && options.mmap.is_some() {
+            return Err(std::io::Error::other(
+                "--restore can't be combined with --mmap, which serves its dataset read-only",
+            ));
+        }
+        Ok(options)
+    }
+}
+
+/// Loads the dataset at `path` into `provider`, as `POST /admin/posts/import` would, so benchmark
+/// runs can start from identical data. The format follows the extension (see
+/// [`scheme::admin::dataset::Format::from_path`]).
+fn restore(path: &Path, provider: &Arc<dyn scheme::posts::PostsProvider>) -> std::io::Result<()> {
+    let format = scheme::admin::dataset::Format::from_path(path).ok_or_else(|| {
+        std::io::Error::other(format!(
+            "unknown snapshot format of {}; expected .csv, .jsonl or .ndjson",
+            path.display()
+        ))
+    })?;
+    let bytes = std::fs::read(path)?;
+    let posts = scheme::admin::dataset::decode(format, &bytes).map_err(|err| {
+        std::io::Error::other(format!("invalid snapshot {}: {err}", path.display()))
+    })?;
+    let restored = provider
+        .import(posts)
+        .map_err(|err| std::io::Error::other(format!("failed to restore snapshot: {err}")))?;
+    tracing::info!("Restored {restored} posts from {}", path.display());
+    Ok(())
+}
+
+/// Opens the posts WAL configured with the `RUST_SERVER_WAL_*` variables, starts its background
+/// tasks and guards it (see [`guarded`]).
+fn wal_provider(
+    compression: scheme::posts::Compression,
+    metrics: &Arc<state::Metrics>,
+    breakers: &mut Vec<Arc<scheme::breaker::Breaker>>,
+) -> std::io::Result<Arc<dyn scheme::posts::PostsProvider>> {
+    let batch = envs::vars::get_wal_batch_size();
+    let provider = scheme::posts::WalProvider::open(
+        &envs::vars::get_wal_path()?,
+        compression,
+        metrics.clone(),
+        batch,
+    )?;
+    provider.spawn_compaction(envs::vars::get_wal_compaction_interval());
+    if batch > 1 {
+        provider.spawn_flusher(envs::vars::get_wal_batch_interval());
+    }
+    Ok(guarded(provider, "WAL", metrics, breakers))
+}
+
+/// Wraps a posts provider backed by storage with a circuit breaker (added to `breakers`) and, if
+/// `RUST_SERVER_<backend>_RETRIES` is set, retries.
+fn guarded(
+    provider: Arc<dyn scheme::posts::PostsProvider>,
+    backend: &str,
+    metrics: &Arc<state::Metrics>,
+    breakers: &mut Vec<Arc<scheme::breaker::Breaker>>,
+) -> Arc<dyn scheme::posts::PostsProvider> {
+    let breaker = Arc::new(scheme::breaker::Breaker::new(
+        "posts",
+        envs::vars::get_breaker_threshold(),
+        envs::vars::get_breaker_cooldown(),
+    ));
+    breakers.push(breaker.clone());
+    let provider = scheme::posts::BreakerProvider::wrapped(provider, breaker);
+    match envs::vars::get_retry_policy(backend) {
+        Some(policy) => scheme::posts::RetryProvider::wrapped(
+            provider,
+            scheme::retry::Retrier::new("posts", policy, metrics.clone()),
+        ),
+        None => provider,
+    }
+}
+
+/// Runs the API server on the address configured with `RUST_SERVER_ADDR` (see [`start`]). Once
+/// it's stopped, the session summary is written to `RUST_SERVER_SUMMARY_PATH` or next to the `log`
+/// file (see [`summary`]).
+async fn serve(options: ServeOptions, log: &Path) -> std::io::Result<()> {
+    let (server, session) = start(|| TcpListener::bind(get_server_addr()?), &options)?;
+    server.await?;
+    let path = envs::vars::get_summary_path().unwrap_or_else(|| log.with_extension("summary.json"));
+    session.write(&path)
+}
+
+/// This is the main entry point of the application, executed using the Actix-Web asynchronous runtime.
+///
+/// Without arguments the API server is started (see [`serve`]); with `--read-only`, it rejects
+/// writes, e.g. as a replica (see [`middleware::read_only`]), with `--restore <path>`, it
+/// starts from a snapshot, and with `--mmap <path>`, it serves a dataset read-only (see
+/// [`ServeOptions`]). Subcommands:
+/// - `results [dir]` serves the dashboard of benchmark results stored by the `orchestrator`
+///   binary (see [`results::serve`]);
+/// - `smoke` runs a post/user lifecycle against the server on an ephemeral port and exits with a
+///   failure if any step fails (see [`smoke::run`]);
+/// - `gen-dataset <out.jsonl> [options]` writes a posts dataset with realistic distributions for
+///   `POST /admin/posts/import` (see [`datagen::run`]).
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    // Init logs
+    let (guard, log) = envs::logs::init()?;
+    let mut args = env::args().skip(1);
+    match args.next().as_deref() {
+        None => serve(ServeOptions::default(), &log).await?,
+        Some(option) if option.starts_with("--") => {
+            let options = ServeOptions::parse(iter::once(option.to_owned()).chain(args))?;
+            serve(options, &log).await?
+        }
+        Some("results") => results::serve(args.next()).await?,
+        Some("smoke") => {
+            if !smoke::run().await? {
+                return Err(std::io::Error::other("smoke test failed"));
+            }
+        }
+        Some("gen-dataset") => datagen::run(args)?,
+        Some(other) => {
+            return Err(std::io::Error::other(format!(
+                "unknown subcommand: {other}; expected options (`--read-only`, `--restore <path>`, `--mmap <path>`), `results [dir]`, `smoke` or `gen-dataset`"
+            )));
+        }
+    }
 
     // Technically it's useless, but it helps to remember `guard` should live until end of application
     drop(guard);
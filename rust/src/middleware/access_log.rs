@@ -0,0 +1,38 @@
+use actix_web::{
+    Error,
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    middleware::Next,
+};
+use std::time::Instant;
+use tracing::{enabled, trace};
+
+use crate::client_ip::ClientIp;
+
+/// Middleware logging every request at `trace` level: the address of the client (see
+/// [`ClientIp`]), method, path, response status and duration.
+///
+/// Off with the default `debug` log level, so benchmarks don't pay for it; enable it with
+/// `RUST_LOG=server=trace`.
+pub async fn log_access(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    if !enabled!(tracing::Level::TRACE) {
+        return next.call(req).await;
+    }
+    let client = ClientIp::of(req.request()).map_or("-".to_owned(), |ip| ip.to_string());
+    let context = format!("{} {}", req.method(), req.path());
+    let started = Instant::now();
+    let response = next.call(req).await;
+    let status = match &response {
+        Ok(response) => response.status(),
+        Err(err) => err.as_response_error().status_code(),
+    };
+    trace!(
+        "{client} {context} {} {}ms",
+        status.as_u16(),
+        started.elapsed().as_millis()
+    );
+    response
+}
@@ -0,0 +1,184 @@
+use actix_web::{
+    Error, ResponseError,
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    middleware::Next,
+    web,
+};
+use percent_encoding::percent_decode_str;
+use serde::{Deserialize, Serialize};
+use std::{
+    net::IpAddr,
+    sync::{RwLock, RwLockReadGuard},
+};
+
+use crate::{
+    client_ip::{Cidr, ClientIp},
+    scheme::error::ApiError,
+    state::{GlobalServerState, Metrics},
+};
+
+/// Rule of an [`Acl`]: which client addresses may access the paths under `path`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AclRule {
+    /// Path prefix the rule applies to, e.g. `/admin` for `/admin` and `/admin/...`; `/` applies
+    /// to every path.
+    pub path: String,
+
+    /// If not empty, only clients in these ranges are allowed.
+    #[serde(default)]
+    pub allow: Vec<Cidr>,
+
+    /// Clients in these ranges are denied, even if they're allowed as well.
+    #[serde(default)]
+    pub deny: Vec<Cidr>,
+}
+
+impl AclRule {
+    /// Returns `true` if the rule applies to `path`, which must be [`normalize`]d.
+    fn matches(&self, path: &str) -> bool {
+        let prefix = self.path.trim_end_matches('/');
+        path.strip_prefix(prefix)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    }
+
+    /// Returns `true` if the rule lets `ip` through.
+    fn allows(&self, ip: IpAddr) -> bool {
+        !self.deny.iter().any(|range| range.contains(ip))
+            && (self.allow.is_empty() || self.allow.iter().any(|range| range.contains(ip)))
+    }
+}
+
+/// IP access control list, enforced by [`check_acl`] before requests are routed.
+///
+/// A request must pass every rule matching its path; a client whose address is unknown passes
+/// none. The rules are configured with `RUST_SERVER_ACL` (see [`Acl::from_spec`]) and can be
+/// replaced at runtime with `PUT /admin/acl`.
+#[derive(Debug, Default)]
+pub struct Acl {
+    rules: RwLock<Vec<AclRule>>,
+}
+
+impl Acl {
+    /// Parses rules separated by `;`, each made of a path prefix, `allow` or `deny`, and a
+    /// comma-separated list of CIDR ranges, e.g. `/admin allow 127.0.0.1,10.0.0.0/8; / deny
+    /// 203.0.113.0/24`. Rules of the same path are merged. An empty spec allows everybody.
+    pub fn from_spec(spec: &str) -> Result<Self, String> {
+        let mut rules: Vec<AclRule> = Vec::new();
+        for rule in spec.split(';').filter(|rule| !rule.trim().is_empty()) {
+            let [path, kind, ranges] = rule.split_whitespace().collect::<Vec<_>>()[..] else {
+                return Err(format!(
+                    "expected `<path> allow|deny <ranges>`, got {rule:?}"
+                ));
+            };
+            let ranges = ranges
+                .split(',')
+                .filter(|range| !range.is_empty())
+                .map(str::parse)
+                .collect::<Result<Vec<Cidr>, _>>()?;
+            let index = match rules.iter().position(|rule| rule.path == path) {
+                Some(index) => index,
+                None => {
+                    rules.push(AclRule {
+                        path: path.to_owned(),
+                        allow: Vec::new(),
+                        deny: Vec::new(),
+                    });
+                    rules.len() - 1
+                }
+            };
+            match kind {
+                "allow" => rules[index].allow.extend(ranges),
+                "deny" => rules[index].deny.extend(ranges),
+                other => return Err(format!("expected `allow` or `deny`, got {other:?}")),
+            }
+        }
+        let acl = Self::default();
+        acl.set_rules(rules)?;
+        Ok(acl)
+    }
+
+    /// Returns a copy of the rules.
+    pub fn rules(&self) -> Vec<AclRule> {
+        self.read().clone()
+    }
+
+    /// Replaces the rules. Fails if a path doesn't start with `/`.
+    pub fn set_rules(&self, rules: Vec<AclRule>) -> Result<(), String> {
+        if let Some(rule) = rules.iter().find(|rule| !rule.path.starts_with('/')) {
+            return Err(format!("path {:?} must start with `/`", rule.path));
+        }
+        *self.rules.write().unwrap_or_else(|err| err.into_inner()) = rules;
+        Ok(())
+    }
+
+    /// Returns `true` if a client may access `path`. `client` is only called if a rule matches
+    /// the path, so requests aren't slowed down by resolving addresses nobody checks.
+    ///
+    /// The path is matched as the router sees it, see [`normalize`].
+    pub fn is_allowed(&self, path: &str, client: impl FnOnce() -> Option<IpAddr>) -> bool {
+        let path = normalize(path);
+        let path = path.as_str();
+        let rules = self.read();
+        let mut matching = rules.iter().filter(|rule| rule.matches(path)).peekable();
+        if matching.peek().is_none() {
+            return true;
+        }
+        let Some(ip) = client() else {
+            return false;
+        };
+        matching.all(|rule| rule.allows(ip))
+    }
+
+    /// Locks the rules for reading. They're only ever replaced as a whole, so a poisoned lock
+    /// can't hold inconsistent rules.
+    fn read(&self) -> RwLockReadGuard<'_, Vec<AclRule>> {
+        self.rules.read().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// Returns `path` percent-decoded, with empty and `.` segments dropped and `..` segments
+/// resolved, so `/%61dmin`, `//admin` and `/x/../admin` are all matched as `/admin`.
+///
+/// Decoding comes first: otherwise an encoded `.` or `/` would survive the normalization.
+pub fn normalize(path: &str) -> String {
+    let decoded = percent_decode_str(path).decode_utf8_lossy();
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in decoded.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    format!("/{}", segments.join("/"))
+}
+
+/// Middleware rejecting requests of clients the [`Acl`] of [`GlobalServerState`] doesn't allow
+/// with `403 Forbidden`, counted in the `http.acl_denied` metric.
+///
+/// The client address is resolved with [`ClientIp`], so behind trusted proxies the forwarded
+/// address is checked rather than the proxy's.
+pub async fn check_acl(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let state = req.app_data::<web::Data<GlobalServerState>>().cloned();
+    let denied = state.filter(|state| {
+        !state
+            .acl
+            .is_allowed(req.path(), || ClientIp::of(req.request()))
+    });
+    let Some(state) = denied else {
+        return next
+            .call(req)
+            .await
+            .map(ServiceResponse::map_into_left_body);
+    };
+    Metrics::inc(&state.metrics.http_acl_denied);
+    let response = ApiError::Forbidden(format!("client address may not access {}", req.path()))
+        .error_response();
+    Ok(req.into_response(response).map_into_right_body())
+}
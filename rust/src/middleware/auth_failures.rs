@@ -0,0 +1,304 @@
+use actix_web::{
+    Error, HttpRequest, ResponseError,
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::StatusCode,
+    middleware::Next,
+    web,
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, HashMap},
+    net::IpAddr,
+    sync::{Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+use tracing::{info, warn};
+
+use crate::{
+    client_ip::ClientIp,
+    scheme::{auth, error::ApiError},
+    state::{GlobalServerState, Metrics},
+};
+
+/// Number of hex digits of the hashed tokens the failures are counted by.
+const TOKEN_KEY_LEN: usize = 12;
+
+/// Key the failures of requests without a bearer token are counted by.
+const NO_TOKEN: &str = "-";
+
+/// Number of tokens counted on their own; failures of further tokens are counted under
+/// [`OTHER`], so clients trying out random tokens can't grow the counts without bound.
+const MAX_TOKENS: usize = 1000;
+
+/// Number of routes counted on their own, as for [`MAX_TOKENS`]: requests with made-up methods
+/// are counted by route too.
+const MAX_ROUTES: usize = 256;
+
+/// Key of the failures of the tokens and routes beyond [`MAX_TOKENS`] and [`MAX_ROUTES`].
+pub const OTHER: &str = "other";
+
+/// Lockout of client addresses after repeated authentication failures.
+#[derive(Debug, Clone, Copy)]
+pub struct Lockout {
+    /// Number of failures within `duration` which lock the client out.
+    pub failures: u32,
+
+    /// How long the client is locked out, and how long its failures are remembered.
+    pub duration: Duration,
+}
+
+/// Requests rejected with `401 Unauthorized` or `403 Forbidden`, counted by route and by token,
+/// and shown at `GET /admin/auth-failures` (see [`track_auth_failures`]).
+///
+/// Tokens are counted by a prefix of their SHA-256 (see [`token_key`]), so the counts don't leak
+/// them. With a [`Lockout`], clients failing too often are answered with `429 Too Many Requests`
+/// for a while, e.g. to test how a load generator copes with being locked out.
+#[derive(Debug, Default)]
+pub struct AuthFailures {
+    lockout: Option<Lockout>,
+    counts: Mutex<Counts>,
+}
+
+/// Counts of [`AuthFailures`].
+#[derive(Debug, Default)]
+struct Counts {
+    routes: BTreeMap<String, RouteFailures>,
+    tokens: BTreeMap<String, u64>,
+
+    /// Recent failures of every client, only tracked with a [`Lockout`].
+    clients: HashMap<IpAddr, ClientFailures>,
+}
+
+/// Recent failures of a client address.
+#[derive(Debug)]
+struct ClientFailures {
+    /// Failures since `since`.
+    failures: u32,
+
+    /// First of the counted failures.
+    since: Instant,
+
+    /// End of the lockout of the client, if it's locked out.
+    locked_until: Option<Instant>,
+}
+
+/// Rejected requests of a route.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct RouteFailures {
+    /// Requests rejected with `401 Unauthorized`.
+    pub unauthorized: u64,
+
+    /// Requests rejected with `403 Forbidden`.
+    pub forbidden: u64,
+}
+
+/// A locked out client address.
+#[derive(Debug, Clone, Serialize)]
+pub struct LockedClient {
+    pub ip: IpAddr,
+
+    /// Remaining time of the lockout, in whole seconds, rounded up.
+    pub retry_after_secs: u64,
+}
+
+/// Body of `GET /admin/auth-failures`.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuthFailuresReport {
+    /// Rejected requests by route, e.g. `GET /users`; `other` for the routes beyond the first 256.
+    pub routes: BTreeMap<String, RouteFailures>,
+
+    /// Rejected requests by hashed token (see [`token_key`]); `-` for requests without a token,
+    /// `other` for the tokens beyond the first thousand.
+    pub tokens: BTreeMap<String, u64>,
+
+    /// Number of failures locking a client out, if clients are locked out at all.
+    pub lockout_failures: Option<u32>,
+
+    /// Duration of lockouts, in seconds, if clients are locked out at all.
+    pub lockout_secs: Option<u64>,
+
+    /// Clients locked out right now.
+    pub locked: Vec<LockedClient>,
+}
+
+/// Returns the key the failures of `token` are counted by: the first hex digits of its SHA-256.
+pub fn token_key(token: &str) -> String {
+    let mut key = hex::encode(Sha256::digest(token));
+    key.truncate(TOKEN_KEY_LEN);
+    key
+}
+
+impl AuthFailures {
+    /// Returns empty counts, locking clients out as `lockout` says.
+    pub fn new(lockout: Option<Lockout>) -> Self {
+        Self {
+            lockout,
+            counts: Mutex::default(),
+        }
+    }
+
+    /// Returns the counts and the locked out clients.
+    pub fn report(&self) -> AuthFailuresReport {
+        let now = Instant::now();
+        let counts = self.lock();
+        let mut locked: Vec<LockedClient> = counts
+            .clients
+            .iter()
+            .filter_map(|(ip, client)| {
+                let remaining = client.locked_until?.checked_duration_since(now)?;
+                Some(LockedClient {
+                    ip: *ip,
+                    retry_after_secs: remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0),
+                })
+            })
+            .collect();
+        locked.sort_by_key(|client| client.ip);
+        AuthFailuresReport {
+            routes: counts.routes.clone(),
+            tokens: counts.tokens.clone(),
+            lockout_failures: self.lockout.map(|lockout| lockout.failures),
+            lockout_secs: self.lockout.map(|lockout| lockout.duration.as_secs()),
+            locked,
+        }
+    }
+
+    /// Counts `token`, found invalid by the handler of `req` rather than rejected, as a
+    /// `401 Unauthorized` of the route, e.g. an introspected token: probing tokens that way counts
+    /// towards the lockout of the client as much as trying them out.
+    pub fn reject(&self, req: &HttpRequest, token: &str) {
+        let route = route_of(req);
+        let ip = ClientIp::of(req);
+        let token = token_key(token);
+        log_rejection(StatusCode::UNAUTHORIZED, &route, ip, &token);
+        self.record(route, token, ip, StatusCode::UNAUTHORIZED);
+    }
+
+    /// Forgets every failure and lifts every lockout.
+    pub fn reset(&self) {
+        *self.lock() = Counts::default();
+    }
+
+    /// Returns the remaining lockout of `ip`, if it's locked out.
+    fn locked_out(&self, ip: IpAddr) -> Option<Duration> {
+        self.lockout?;
+        let until = self.lock().clients.get(&ip)?.locked_until?;
+        until
+            .checked_duration_since(Instant::now())
+            .filter(|remaining| !remaining.is_zero())
+    }
+
+    /// Counts a request to `route` rejected with `status`, locking `ip` out if it failed too often.
+    fn record(&self, route: String, token: String, ip: Option<IpAddr>, status: StatusCode) {
+        let mut counts = self.lock();
+        let failures = bounded_entry(&mut counts.routes, route, MAX_ROUTES);
+        if status == StatusCode::UNAUTHORIZED {
+            failures.unauthorized += 1;
+        } else {
+            failures.forbidden += 1;
+        }
+        *bounded_entry(&mut counts.tokens, token, MAX_TOKENS) += 1;
+        let (Some(lockout), Some(ip)) = (self.lockout, ip) else {
+            return;
+        };
+        let now = Instant::now();
+        // Forgets the clients whose failures and lockouts are over, so the map stays small
+        counts.clients.retain(|_, client| {
+            now.duration_since(client.since) < lockout.duration
+                || client.locked_until.is_some_and(|until| until > now)
+        });
+        let client = counts.clients.entry(ip).or_insert(ClientFailures {
+            failures: 0,
+            since: now,
+            locked_until: None,
+        });
+        if now.duration_since(client.since) >= lockout.duration {
+            client.failures = 0;
+            client.since = now;
+        }
+        client.failures += 1;
+        if client.failures >= lockout.failures && client.locked_until.is_none_or(|u| u <= now) {
+            client.locked_until = Some(now + lockout.duration);
+            client.failures = 0;
+            client.since = now;
+            warn!(
+                "Client {ip} locked out for {}s after {} rejected authentication attempts",
+                lockout.duration.as_secs(),
+                lockout.failures
+            );
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Counts> {
+        self.counts.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// Returns the count of `key` in `map`, or the count of [`OTHER`] if `key` is new and `map`
+/// already counts `max` keys besides it.
+fn bounded_entry<V: Default>(map: &mut BTreeMap<String, V>, key: String, max: usize) -> &mut V {
+    let counted = map.len() - usize::from(map.contains_key(OTHER));
+    let key = if counted >= max && !map.contains_key(&key) {
+        OTHER.to_owned()
+    } else {
+        key
+    };
+    map.entry(key).or_default()
+}
+
+/// Returns the route of `req` as counted, e.g. `GET /users/{id}`.
+fn route_of(req: &HttpRequest) -> String {
+    format!(
+        "{} {}",
+        req.method(),
+        req.match_pattern().as_deref().unwrap_or("(unmatched)")
+    )
+}
+
+/// Logs a rejected request in the format of [`track_auth_failures`].
+fn log_rejection(status: StatusCode, route: &str, ip: Option<IpAddr>, token: &str) {
+    let client = ip.map_or("-".to_owned(), |ip| ip.to_string());
+    info!(
+        "Auth rejected: {} {route} client={client} token={token}",
+        status.as_u16()
+    );
+}
+
+/// Middleware counting and logging requests rejected with `401 Unauthorized` or `403 Forbidden`
+/// in the [`AuthFailures`] of [`GlobalServerState`], whichever middleware or extractor rejected
+/// them. Every rejection is logged at `info` level in the same format: status, route, client
+/// address (see [`ClientIp`]) and hashed token.
+///
+/// Clients locked out after too many failures are answered with `429 Too Many Requests` and
+/// `Retry-After` before anything else looks at their requests, counted in the `http.auth_locked`
+/// metric.
+pub async fn track_auth_failures(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let Some(state) = req.app_data::<web::Data<GlobalServerState>>().cloned() else {
+        return next
+            .call(req)
+            .await
+            .map(ServiceResponse::map_into_left_body);
+    };
+    let ip = ClientIp::of(req.request());
+    if let Some(retry_after) = ip.and_then(|ip| state.auth_failures.locked_out(ip)) {
+        Metrics::inc(&state.metrics.http_auth_locked);
+        let response = ApiError::TooManyRequests { retry_after }.error_response();
+        return Ok(req.into_response(response).map_into_right_body());
+    }
+    let route = route_of(req.request());
+    let token = auth::bearer(req.request()).map_or(NO_TOKEN.to_owned(), token_key);
+    let response = next.call(req).await;
+    let status = match &response {
+        Ok(response) => response.status(),
+        Err(err) => err.as_response_error().status_code(),
+    };
+    if matches!(status, StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN) {
+        log_rejection(status, &route, ip, &token);
+        state.auth_failures.record(route, token, ip, status);
+    }
+    response.map(ServiceResponse::map_into_left_body)
+}
@@ -0,0 +1,57 @@
+use actix_web::{
+    Error,
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    middleware::Next,
+    web,
+};
+use futures_util::FutureExt;
+use std::{any::Any, panic::AssertUnwindSafe};
+use tracing::error;
+
+use crate::{
+    client_ip::ClientIp,
+    scheme::error::ApiError,
+    state::{GlobalServerState, Metrics},
+};
+
+/// Middleware converting panics of downstream services into `500 Internal Server Error`.
+///
+/// Without it, a panicking handler drops the connection, and the client only sees a network error.
+/// The panic is logged together with the method, path and client address of the request and
+/// counted in the `http.panics` metric; the client receives a problem+json body like any other
+/// [`ApiError`].
+///
+/// Should be registered outside of the other middlewares (but inside of
+/// [`localize_errors`](super::localize::localize_errors)), so it also covers them.
+pub async fn catch_panic(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    // The request itself is moved into the handler and lost on panic; keep what's needed to report it
+    let context = format!("{} {}", req.method(), req.path());
+    let client = ClientIp::of(req.request()).map_or("-".to_owned(), |ip| ip.to_string());
+    let state = req.app_data::<web::Data<GlobalServerState>>().cloned();
+    match AssertUnwindSafe(next.call(req)).catch_unwind().await {
+        Ok(response) => response,
+        Err(panic) => {
+            error!(
+                "Handler of {context} from {client} panicked: {}",
+                panic_message(panic.as_ref())
+            );
+            if let Some(state) = state {
+                Metrics::inc(&state.metrics.http_panics);
+            }
+            Err(ApiError::Internal(format!("handler of {context} panicked")).into())
+        }
+    }
+}
+
+/// Extracts the message passed to `panic!`, if it's a string.
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("<non-string payload>")
+}
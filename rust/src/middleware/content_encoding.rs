@@ -0,0 +1,46 @@
+use actix_web::{
+    Error, ResponseError,
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::{ACCEPT_ENCODING, CONTENT_ENCODING, HeaderValue},
+    middleware::Next,
+};
+
+use crate::scheme::error::ApiError;
+
+/// Content codings of request bodies decoded by the body extractors (`web::Json`, `web::Bytes`).
+const SUPPORTED: [&str; 6] = ["identity", "gzip", "x-gzip", "deflate", "br", "zstd"];
+
+/// Codings advertised in `Accept-Encoding` of `415` responses.
+const ADVERTISED: &str = "gzip, br, zstd, deflate";
+
+/// Middleware rejecting request bodies in a content coding the server can't decode.
+///
+/// Compressed bodies (`Content-Encoding: gzip`, `br`, ...) are decompressed by the body extractors
+/// themselves, and size limits apply to the decompressed body. An unknown coding, or several
+/// stacked ones, would be passed on undecoded though, and fail later as a confusing parse error;
+/// such requests are answered with `415 Unsupported Media Type` and the supported codings in
+/// `Accept-Encoding` instead (RFC 9110, section 15.5.16).
+pub async fn check_content_encoding(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let supported = req.headers().get(CONTENT_ENCODING).is_none_or(|value| {
+        value.to_str().is_ok_and(|coding| {
+            SUPPORTED
+                .iter()
+                .any(|supported| supported.eq_ignore_ascii_case(coding.trim()))
+        })
+    });
+    if supported {
+        return next
+            .call(req)
+            .await
+            .map(ServiceResponse::map_into_left_body);
+    }
+    let mut response = ApiError::UnsupportedMediaType.error_response();
+    response
+        .headers_mut()
+        .insert(ACCEPT_ENCODING, HeaderValue::from_static(ADVERTISED));
+    Ok(req.into_response(response).map_into_right_body())
+}
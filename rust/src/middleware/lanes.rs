@@ -0,0 +1,136 @@
+use actix_web::{
+    Error,
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    http::Method,
+    middleware::Next,
+    web,
+};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+use crate::{
+    scheme::error::ApiError,
+    state::{Gauge, GlobalServerState},
+};
+use percom_model::urls;
+
+/// Class of requests sharing a concurrency limit, see [`Lanes`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lane {
+    /// Monitoring: `/metrics`.
+    Health,
+
+    /// Other `GET`, `HEAD` and `OPTIONS` requests.
+    Read,
+
+    /// Other requests which may write.
+    Write,
+
+    /// Administration under `/admin`, e.g. dataset imports and exports.
+    Bulk,
+}
+
+impl Lane {
+    /// All lanes, by priority.
+    pub const ALL: [Lane; 4] = [Lane::Health, Lane::Read, Lane::Write, Lane::Bulk];
+
+    /// Name of the lane in `RUST_SERVER_LANES` and in the metrics.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Health => "health",
+            Self::Read => "read",
+            Self::Write => "write",
+            Self::Bulk => "bulk",
+        }
+    }
+
+    /// Position of the lane in [`Lane::ALL`].
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Returns the lane of a request of `method` to `path`.
+    fn classify(method: &Method, path: &str) -> Self {
+        let under = |prefix: &str| {
+            path.strip_prefix(prefix)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        };
+        if under(urls::metrics::SCOPE) {
+            Self::Health
+        } else if under(urls::admin::SCOPE) {
+            Self::Bulk
+        } else if matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) {
+            Self::Read
+        } else {
+            Self::Write
+        }
+    }
+}
+
+/// Separate concurrency limits per class of requests, enforced by [`limit_lanes`], so cheap,
+/// important requests (health checks) keep being answered while a benchmark saturates the others.
+///
+/// The limits are configured with `RUST_SERVER_LANES` (see [`Lanes::from_spec`]); lanes without a
+/// limit, all of them by default, aren't limited.
+#[derive(Debug, Clone, Default)]
+pub struct Lanes {
+    /// Slots of each lane, by [`Lane::index`]; `None` for lanes without a limit.
+    slots: [Option<Arc<Semaphore>>; 4],
+}
+
+impl Lanes {
+    /// Parses comma-separated `<lane>=<limit>` entries, e.g. `read=256,write=64,bulk=2`, where the
+    /// lanes are `health`, `read`, `write` and `bulk`.
+    ///
+    /// # Errors
+    /// Returns a description of the first unknown lane or malformed limit.
+    pub fn from_spec(spec: &str) -> Result<Self, String> {
+        let mut lanes = Self::default();
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (name, limit) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("{entry:?} isn't <lane>=<limit>"))?;
+            let lane = Lane::ALL
+                .into_iter()
+                .find(|lane| lane.name() == name.trim())
+                .ok_or_else(|| format!("unknown lane {name:?}"))?;
+            let limit: usize = limit
+                .trim()
+                .parse()
+                .ok()
+                .filter(|limit| *limit > 0)
+                .ok_or_else(|| format!("limit of {name} isn't a positive number"))?;
+            lanes.slots[lane.index()] = Some(Arc::new(Semaphore::new(limit)));
+        }
+        Ok(lanes)
+    }
+}
+
+/// Middleware limiting how many requests of each lane (see [`Lanes`]) are handled at a time;
+/// further requests of a full lane wait for a free slot.
+///
+/// A slot is held until the handler returns its response, so the rest of a streamed body doesn't
+/// count. Per lane, `/metrics` shows the requests being handled (`active`) and waiting (`queued`)
+/// in its `lanes` section.
+pub async fn limit_lanes(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let Some(state) = req.app_data::<web::Data<GlobalServerState>>().cloned() else {
+        return next.call(req).await;
+    };
+    let lane = Lane::classify(req.method(), req.path());
+    let metrics = &state.metrics;
+    let _slot = match &state.lanes.slots[lane.index()] {
+        Some(slots) => {
+            let _waiting = Gauge::track(&metrics.lanes_queued[lane.index()]);
+            Some(slots.clone().acquire_owned().await.map_err(|err| {
+                ApiError::Internal(format!("{} lane is closed: {err}", lane.name()))
+            })?)
+        }
+        None => None,
+    };
+    let _active = Gauge::track(&metrics.lanes_active[lane.index()]);
+    next.call(req).await
+}
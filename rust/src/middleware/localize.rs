@@ -0,0 +1,83 @@
+use actix_web::{
+    Error, HttpResponse,
+    body::{self, BoxBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    error::InternalError,
+    http::header::{ACCEPT_LANGUAGE, CONTENT_LANGUAGE, CONTENT_TYPE, HeaderValue, VARY},
+    middleware::Next,
+};
+use serde_json::Value;
+
+use crate::scheme::{error::PROBLEM_CONTENT_TYPE, locale};
+
+/// Middleware translating error responses (problem details, see
+/// [`Problem`](crate::scheme::error::Problem)) into the language the client prefers in
+/// `Accept-Language`, using the catalogs of [`locale`].
+///
+/// The `title` and, if the catalogs know it, the `detail` are translated; `code` and `status` stay
+/// as they are, so clients can rely on them in any language. Translated responses carry
+/// `Content-Language`, and every error response `Vary: Accept-Language`. Successful responses and
+/// clients preferring English pass through untouched.
+///
+/// Should be registered outside of [`catch_panic`](super::catch_panic::catch_panic), so it also
+/// covers the `500` responses of panics.
+pub async fn localize_errors(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let language = req
+        .headers()
+        .get(ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok())
+        .map_or(locale::DEFAULT_LANGUAGE, locale::negotiate);
+    let res = match next.call(req).await {
+        Ok(res) => res,
+        // Errors of inner middlewares (e.g. caught panics) are only turned into responses by the
+        // server, so the response is built here and passed on in place of the error
+        Err(err) => {
+            let res = translate(err.error_response(), language).await;
+            return Err(InternalError::from_response(err, res).into());
+        }
+    };
+    let (req, res) = res.into_parts();
+    let res = translate(res.map_into_boxed_body(), language).await;
+    Ok(ServiceResponse::new(req, res))
+}
+
+/// Translates `res` into `language`, if it's an error response.
+async fn translate(mut res: HttpResponse, language: &'static str) -> HttpResponse {
+    let is_problem = res
+        .headers()
+        .get(CONTENT_TYPE)
+        .is_some_and(|value| value.as_bytes() == PROBLEM_CONTENT_TYPE.as_bytes());
+    if !is_problem {
+        return res;
+    }
+    res.headers_mut()
+        .append(VARY, HeaderValue::from_static("Accept-Language"));
+    if language == locale::DEFAULT_LANGUAGE {
+        return res;
+    }
+    let (mut res, body) = res.into_parts();
+    // Error bodies are small documents built in memory, so they can always be read
+    let body = body::to_bytes(body).await.unwrap_or_default();
+    let Ok(mut problem) = serde_json::from_slice::<Value>(&body) else {
+        return res.set_body(BoxBody::new(body));
+    };
+    if let Some(title) = problem["code"]
+        .as_str()
+        .and_then(|code| locale::title(language, code))
+    {
+        problem["title"] = Value::String(title);
+    }
+    if let Some(detail) = problem["detail"]
+        .as_str()
+        .and_then(|detail| locale::detail(language, detail))
+    {
+        problem["detail"] = Value::String(detail);
+    }
+    res.headers_mut()
+        .insert(CONTENT_LANGUAGE, HeaderValue::from_static(language));
+    let body = serde_json::to_vec(&problem).map_or(body, Into::into);
+    res.set_body(BoxBody::new(body))
+}
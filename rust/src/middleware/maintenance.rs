@@ -0,0 +1,107 @@
+use actix_web::{
+    Error, ResponseError,
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    middleware::Next,
+    web,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{sync::RwLock, time::Duration};
+
+use crate::{
+    middleware::read_only,
+    scheme::error::ApiError,
+    state::{GlobalServerState, Metrics},
+};
+use percom_model::urls;
+
+/// Default time clients are told to wait before retrying a write during maintenance, in seconds.
+pub const DEFAULT_RETRY_AFTER_SECS: u64 = 30;
+
+/// Maintenance mode, toggled with `POST /admin/maintenance`: writes are answered with `503` (see
+/// [`reject_writes_in_maintenance`]) while reads continue, e.g. to take a consistent export during
+/// a soak test without stopping the traffic.
+#[derive(Debug, Default)]
+pub struct Maintenance {
+    /// Set while in maintenance: the `Retry-After` of rejected writes and when it started.
+    window: RwLock<Option<(Duration, DateTime<Utc>)>>,
+}
+
+/// State of the [`Maintenance`] mode, as shown and changed at `/admin/maintenance`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MaintenanceStatus {
+    /// Whether writes are rejected.
+    pub enabled: bool,
+
+    /// `Retry-After` of rejected writes, in seconds.
+    #[serde(default = "default_retry_after_secs")]
+    pub retry_after_secs: u64,
+
+    /// When the maintenance started; ignored when changing the mode.
+    #[serde(default)]
+    pub since: Option<DateTime<Utc>>,
+}
+
+fn default_retry_after_secs() -> u64 {
+    DEFAULT_RETRY_AFTER_SECS
+}
+
+impl Maintenance {
+    /// Returns the current state.
+    pub fn status(&self) -> MaintenanceStatus {
+        let window = *self.window.read().unwrap_or_else(|err| err.into_inner());
+        MaintenanceStatus {
+            enabled: window.is_some(),
+            retry_after_secs: window.map_or(DEFAULT_RETRY_AFTER_SECS, |(retry, _)| retry.as_secs()),
+            since: window.map(|(_, since)| since),
+        }
+    }
+
+    /// Enters or leaves maintenance as `status` says; a maintenance which is already on keeps its
+    /// start time, taking the new `Retry-After`.
+    pub fn set(&self, status: &MaintenanceStatus) {
+        let mut window = self.window.write().unwrap_or_else(|err| err.into_inner());
+        *window = status.enabled.then(|| {
+            let since = window.map_or_else(Utc::now, |(_, since)| since);
+            (Duration::from_secs(status.retry_after_secs.max(1)), since)
+        });
+    }
+
+    /// Returns the `Retry-After` of rejected writes while in maintenance.
+    fn retry_after(&self) -> Option<Duration> {
+        self.window
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .map(|(retry_after, _)| retry_after)
+    }
+}
+
+/// Middleware rejecting every request which may write (see
+/// [`may_write`](crate::middleware::read_only::may_write)) with `503 Service Unavailable` and
+/// `Retry-After` while in [`Maintenance`], counted in the `http.maintenance_rejected` metric.
+///
+/// Requests under `/admin` pass, so the maintenance can be ended, and datasets imported meanwhile.
+pub async fn reject_writes_in_maintenance(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let writes = read_only::may_write(&req);
+    let admin = req
+        .path()
+        .strip_prefix(urls::admin::SCOPE)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'));
+    let rejected = req
+        .app_data::<web::Data<GlobalServerState>>()
+        .filter(|_| writes && !admin)
+        .and_then(|state| Some((state.maintenance.retry_after()?, state.metrics.clone())));
+    let Some((retry_after, metrics)) = rejected else {
+        return next
+            .call(req)
+            .await
+            .map(ServiceResponse::map_into_left_body);
+    };
+    Metrics::inc(&metrics.http_maintenance_rejected);
+    let response = ApiError::ServiceUnavailable { retry_after }.error_response();
+    Ok(req.into_response(response).map_into_right_body())
+}
@@ -0,0 +1,155 @@
+use actix_web::{
+    Error, HttpMessage,
+    body::MessageBody,
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    http::header,
+    middleware::Next,
+    rt, web,
+};
+use futures_util::{StreamExt, stream};
+use reqwest::Client;
+use std::{sync::Arc, time::Duration};
+use tokio::sync::Semaphore;
+use tracing::debug;
+
+use crate::state::{GlobalServerState, Metrics};
+use percom_model::urls;
+
+/// Header marking mirrored requests, so the shadow backend can tell them apart.
+pub const MIRRORED_HEADER: &str = "X-Mirrored";
+
+/// Maximum number of mirrored requests in flight; further requests aren't mirrored.
+const MAX_IN_FLIGHT: usize = 256;
+
+/// Time the shadow backend has to answer a mirrored request, so a hanging shadow doesn't hold
+/// the slots of [`MAX_IN_FLIGHT`] forever.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+thread_local! {
+    /// Client of the shadow backend, one per worker: connections of a client are bound to the
+    /// runtime which opened them, and every worker has a runtime of its own.
+    static CLIENT: Client = Client::builder()
+        .timeout(TIMEOUT)
+        .build()
+        .expect("the client of the shadow backend is built");
+}
+
+/// Shadow backend receiving copies of a share of the requests (see [`mirror_requests`]), e.g. a
+/// new provider implementation validated under production-like load.
+#[derive(Debug, Clone)]
+pub struct Mirror {
+    /// Base URL of the shadow backend, e.g. `http://10.0.0.2:8080`.
+    pub url: String,
+
+    /// Share of requests mirrored, in percent.
+    pub percent: u8,
+
+    /// Largest body mirrored, in bytes; requests with larger bodies aren't mirrored.
+    pub max_body_size: usize,
+
+    /// Slots of mirrored requests in flight.
+    in_flight: Arc<Semaphore>,
+}
+
+impl Mirror {
+    /// Creates a mirror sending `percent` of the requests with bodies of up to `max_body_size`
+    /// bytes to `url`.
+    pub fn new(url: String, percent: u8, max_body_size: usize) -> Self {
+        Self {
+            url,
+            percent: percent.min(100),
+            max_body_size,
+            in_flight: Arc::new(Semaphore::new(MAX_IN_FLIGHT)),
+        }
+    }
+}
+
+/// Middleware forwarding a copy of [`Mirror::percent`] of the requests to the shadow backend,
+/// if one is configured (see [`GlobalServerState::mirror`]). Requests under `/admin` and
+/// `/metrics` aren't mirrored.
+///
+/// Copies are sent in the background with the same method, path, query, headers and body, and
+/// their responses are discarded, so the shadow backend doesn't add to the latency of the request,
+/// except that the body of a mirrored request is read into memory first. Bodies are read up to
+/// [`Mirror::max_body_size`] only: requests with larger bodies are passed on unmirrored, with the
+/// part already read put back in front of the rest. At most [`MAX_IN_FLIGHT`] copies are pending
+/// at a time, each for up to [`TIMEOUT`]; requests beyond that aren't mirrored. Copies are counted
+/// in the `mirror` section of `/metrics`.
+pub async fn mirror_requests(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let Some(state) = req.app_data::<web::Data<GlobalServerState>>().cloned() else {
+        return next.call(req).await;
+    };
+    let internal = [urls::admin::SCOPE, urls::metrics::SCOPE]
+        .iter()
+        .any(|prefix| {
+            req.path()
+                .strip_prefix(prefix)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        });
+    let Some(mirror) = state
+        .mirror
+        .as_ref()
+        .filter(|mirror| !internal && rand::random_range(0..100) < mirror.percent)
+    else {
+        return next.call(req).await;
+    };
+    // actix and reqwest depend on different versions of `http`, so types are converted by name
+    let Ok(method) = reqwest::Method::from_bytes(req.method().as_str().as_bytes()) else {
+        return next.call(req).await;
+    };
+    let length = req
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok()?.parse::<usize>().ok());
+    if length.is_some_and(|length| length > mirror.max_body_size) {
+        Metrics::inc(&state.metrics.mirror_oversized);
+        return next.call(req).await;
+    }
+    let Ok(slot) = mirror.in_flight.clone().try_acquire_owned() else {
+        Metrics::inc(&state.metrics.mirror_dropped);
+        return next.call(req).await;
+    };
+    let mut payload = req.take_payload();
+    let mut body = web::BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        body.extend_from_slice(&chunk?);
+        if body.len() > mirror.max_body_size {
+            // Chunked bodies tell their size only once they're read
+            Metrics::inc(&state.metrics.mirror_oversized);
+            let read = stream::once(async move { Ok(body.freeze()) });
+            req.set_payload(Payload::from(read.chain(payload).boxed_local()));
+            return next.call(req).await;
+        }
+    }
+    let body = body.freeze();
+    req.set_payload(Payload::from(body.clone()));
+    let path = req.uri().path_and_query().map_or("/", |path| path.as_str());
+    let mut copy = CLIENT
+        .with(Client::clone)
+        .request(method, format!("{}{path}", mirror.url))
+        .header(MIRRORED_HEADER, "1")
+        .body(body);
+    for (name, value) in req.headers() {
+        if !matches!(
+            *name,
+            header::HOST | header::CONTENT_LENGTH | header::CONNECTION | header::TRANSFER_ENCODING
+        ) {
+            copy = copy.header(name.as_str(), value.as_bytes());
+        }
+    }
+    let metrics = state.metrics.clone();
+    rt::spawn(async move {
+        match copy.send().await {
+            Ok(_) => Metrics::inc(&metrics.mirror_sent),
+            Err(err) => {
+                debug!("Failed to mirror a request: {err}");
+                Metrics::inc(&metrics.mirror_failed);
+            }
+        }
+        drop(slot);
+    });
+    next.call(req).await
+}
@@ -0,0 +1,16 @@
+pub mod access_log;
+pub mod acl;
+pub mod auth_failures;
+pub mod catch_panic;
+pub mod content_encoding;
+pub mod lanes;
+pub mod localize;
+pub mod maintenance;
+pub mod mirror;
+pub mod normalize;
+pub mod read_only;
+pub mod route_stats;
+pub mod run_id;
+pub mod signature;
+pub mod slow_clients;
+pub mod work;
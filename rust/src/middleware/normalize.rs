@@ -0,0 +1,109 @@
+use actix_web::{
+    Error, HttpMessage,
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    http::uri::{PathAndQuery, Uri},
+    middleware::{Condition, Next, NormalizePath, TrailingSlash},
+};
+
+/// What happens to trailing and repeated slashes of paths before routing
+/// (`RUST_SERVER_TRAILING_SLASH`).
+///
+/// Backends compared with this server differ here, e.g. some answer `GET /posts/` like
+/// `GET /posts` and others with `404`, so the behavior can be aligned with theirs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Slashes {
+    /// Trailing slashes are removed and repeated ones merged: `/posts/` is `/posts`.
+    #[default]
+    Trim,
+
+    /// Repeated slashes are merged, but trailing ones kept: `/posts//{id}` is `/posts/{id}`, while
+    /// `/posts/` is unknown.
+    Merge,
+
+    /// Paths are routed as they are.
+    Strict,
+}
+
+impl Slashes {
+    /// Returns the behavior named `trim`, `merge` or `strict`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "trim" => Some(Self::Trim),
+            "merge" => Some(Self::Merge),
+            "strict" => Some(Self::Strict),
+            _ => None,
+        }
+    }
+
+    /// Returns the middleware normalizing paths this way, to register outside of every middleware
+    /// routing by the path, and inside of [`keep_original_path`].
+    pub fn middleware(self) -> Condition<NormalizePath> {
+        let trailing = match self {
+            Self::Merge => TrailingSlash::MergeOnly,
+            Self::Trim | Self::Strict => TrailingSlash::Trim,
+        };
+        Condition::new(self != Self::Strict, NormalizePath::new(trailing))
+    }
+}
+
+/// Middleware lowercasing the first segment of paths (`RUST_SERVER_LOWERCASE_PATHS=1`), so
+/// `/Posts/{id}` is `/posts/{id}`, like on backends routing case-insensitively.
+///
+/// The rest of the path is kept as it is, as it holds IDs and nicknames, which are case-sensitive.
+pub async fn lowercase_scope(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let path = req.path();
+    let end = path
+        .get(1..)
+        .and_then(|rest| rest.find('/'))
+        .map_or(path.len(), |at| at + 1);
+    if path.starts_with('/') && path[..end].bytes().any(|byte| byte.is_ascii_uppercase()) {
+        let mut lowered = path[..end].to_ascii_lowercase();
+        lowered.push_str(&path[end..]);
+        if !req.query_string().is_empty() {
+            lowered.push('?');
+            lowered.push_str(req.query_string());
+        }
+        let mut parts = req.uri().clone().into_parts();
+        parts.path_and_query = PathAndQuery::try_from(lowered).ok();
+        if let Ok(uri) = Uri::from_parts(parts) {
+            req.match_info_mut().get_mut().update(&uri);
+            req.head_mut().uri = uri;
+        }
+    }
+    next.call(req).await
+}
+
+/// Path and query of a request as the client sent it, before [`Slashes`] and [`lowercase_scope`]
+/// rewrote them (see [`keep_original_path`]).
+#[derive(Debug, Clone)]
+pub struct OriginalPath(pub String);
+
+impl OriginalPath {
+    /// Returns the path and query of `req` as sent, or as they are now if they weren't kept.
+    pub fn of(req: &ServiceRequest) -> String {
+        match req.extensions().get::<Self>() {
+            Some(Self(path)) => path.clone(),
+            None => req
+                .uri()
+                .path_and_query()
+                .map_or("/", |path| path.as_str())
+                .to_owned(),
+        }
+    }
+}
+
+/// Middleware keeping the path and query of requests as [`OriginalPath`] before they're
+/// normalized, for middlewares which have to see them as the client sent them, e.g. the request
+/// signature, which covers the path as signed. Registered outside of the normalization.
+pub async fn keep_original_path(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let path = OriginalPath::of(&req);
+    req.extensions_mut().insert(OriginalPath(path));
+    next.call(req).await
+}
@@ -0,0 +1,65 @@
+use actix_web::{
+    Error, ResponseError,
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::{
+        Method,
+        header::{ALLOW, HeaderValue, LOCATION},
+    },
+    middleware::Next,
+    web,
+};
+
+use crate::{scheme::error::ApiError, state::GlobalServerState};
+use percom_model::urls;
+
+/// Read-only mode of an instance, e.g. a replica (see [`crate::scheme::replication`]).
+#[derive(Debug, Clone, Default)]
+pub struct ReadOnly {
+    /// Base URL of the primary instance accepting writes, if known.
+    pub primary: Option<String>,
+}
+
+/// Returns whether `req` may write: any method other than `GET`, `HEAD` and `OPTIONS`, except for
+/// `POST /auth/introspect`, which only reads but takes the token in its body as RFC 7662 asks.
+pub fn may_write(req: &ServiceRequest) -> bool {
+    match *req.method() {
+        Method::GET | Method::HEAD | Method::OPTIONS => false,
+        Method::POST => req.path() != urls::auth::introspect(),
+        _ => true,
+    }
+}
+
+/// Middleware rejecting every request which may write (see [`may_write`]) while the server is
+/// read-only (see [`GlobalServerState::read_only`]).
+///
+/// Writes are answered with `405 Method Not Allowed`, the allowed methods in `Allow` and, if the
+/// primary is known, the same URL on the primary in `Location`, so clients can resend them there.
+pub async fn reject_writes(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let read_only = req
+        .app_data::<web::Data<GlobalServerState>>()
+        .and_then(|state| state.read_only.clone());
+    let Some(read_only) = read_only.filter(|_| may_write(&req)) else {
+        return next
+            .call(req)
+            .await
+            .map(ServiceResponse::map_into_left_body);
+    };
+    let mut response = ApiError::MethodNotAllowed(
+        "this instance is read-only; send writes to the primary".to_owned(),
+    )
+    .error_response();
+    let headers = response.headers_mut();
+    headers.insert(ALLOW, HeaderValue::from_static("GET, HEAD, OPTIONS"));
+    let location = read_only.primary.and_then(|primary| {
+        let path = req.uri().path_and_query().map_or("/", |path| path.as_str());
+        HeaderValue::try_from(format!("{primary}{path}")).ok()
+    });
+    if let Some(location) = location {
+        headers.insert(LOCATION, location);
+    }
+    Ok(req.into_response(response).map_into_right_body())
+}
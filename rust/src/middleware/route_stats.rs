@@ -0,0 +1,38 @@
+use actix_web::{
+    Error,
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    middleware::Next,
+    web,
+};
+
+use crate::state::GlobalServerState;
+
+/// Middleware counting responses by route and status class in the `routes` metric (see
+/// [`Metrics::record_route`](crate::state::Metrics::record_route)).
+///
+/// Routes are named by method and matched pattern, e.g. `GET /posts/{id}`, so requests for
+/// different posts add up; requests matching no route are counted as `<method> (unmatched)`.
+///
+/// Should be registered outside of every other middleware, so responses produced by them (e.g.
+/// rejections) are counted with their final status.
+pub async fn count_requests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let Some(state) = req.app_data::<web::Data<GlobalServerState>>().cloned() else {
+        return next.call(req).await;
+    };
+    let route = format!(
+        "{} {}",
+        req.method(),
+        req.match_pattern().as_deref().unwrap_or("(unmatched)")
+    );
+    let response = next.call(req).await;
+    let status = match &response {
+        Ok(response) => response.status(),
+        Err(err) => err.as_response_error().status_code(),
+    };
+    state.metrics.record_route(route, status);
+    response
+}
@@ -0,0 +1,118 @@
+use actix_web::{
+    Error, ResponseError,
+    body::{EitherBody, MessageBody},
+    dev::{Extensions, ServiceRequest, ServiceResponse},
+    middleware::Next,
+    web,
+};
+use std::{
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+use tracing::Instrument;
+
+use crate::{
+    scheme::error::ApiError,
+    state::{GlobalServerState, NO_RUN},
+};
+
+/// Header naming the benchmark run a request belongs to.
+pub const RUN_ID_HEADER: &str = "x-benchmark-run-id";
+
+/// Maximum length of a run ID.
+const MAX_RUN_ID_LEN: usize = 128;
+
+/// Run ID of the last request of a connection which had one, so the following requests on the
+/// same connection belong to the same run even without the header.
+///
+/// Stored in the connection data by [`on_connect`](crate::connection::on_connect); connections
+/// without it (HTTP/3) only take the run ID of every request from its header.
+#[derive(Debug, Default)]
+pub struct ConnectionRun(Mutex<Option<Arc<str>>>);
+
+impl ConnectionRun {
+    /// Adds an empty slot to the data of a new connection.
+    pub fn attach(data: &mut Extensions) {
+        data.insert(Arc::new(Self::default()));
+    }
+
+    fn get(&self) -> Option<Arc<str>> {
+        self.0.lock().unwrap_or_else(|err| err.into_inner()).clone()
+    }
+
+    fn set(&self, run: Arc<str>) {
+        *self.0.lock().unwrap_or_else(|err| err.into_inner()) = Some(run);
+    }
+}
+
+/// Returns `true` if `id` is a valid run ID: 1 to [`MAX_RUN_ID_LEN`] ASCII letters, digits or
+/// `-`, `_`, `.`, `:` and `/`, so it can be logged and used as a metrics key as it is.
+fn is_valid(id: &str) -> bool {
+    (1..=MAX_RUN_ID_LEN).contains(&id.len())
+        && id
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || b"-_.:/".contains(&byte))
+}
+
+/// Middleware attributing requests to the benchmark run named by their `X-Benchmark-Run-Id`
+/// header, or by the last request of their connection which had one.
+///
+/// Everything logged while the request is handled is logged in a `run` span carrying the ID, and
+/// the latency of the response is recorded in the histogram of its run in the `runs` metric (see
+/// [`Metrics::record_run`](crate::state::Metrics::record_run)), so overlapping runs against the
+/// same server keep separate stats. Requests of no run are counted under `(none)`. Malformed IDs
+/// are rejected with `400 Bad Request`.
+///
+/// Should be registered outside of every other middleware, so their logs are in the span too.
+pub async fn tag_runs(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let Some(state) = req.app_data::<web::Data<GlobalServerState>>().cloned() else {
+        return next
+            .call(req)
+            .await
+            .map(ServiceResponse::map_into_left_body);
+    };
+    let started = Instant::now();
+    let slot = req.conn_data::<Arc<ConnectionRun>>().cloned();
+    let run = match req.headers().get(RUN_ID_HEADER) {
+        Some(value) => match value.to_str() {
+            Ok(id) if is_valid(id) => {
+                let id: Arc<str> = Arc::from(id);
+                if let Some(slot) = &slot {
+                    slot.set(id.clone());
+                }
+                Some(id)
+            }
+            _ => {
+                let response = ApiError::BadRequest(format!(
+                    "invalid {RUN_ID_HEADER}: expected 1 to {MAX_RUN_ID_LEN} letters, digits or \
+                     `-_.:/`"
+                ))
+                .error_response();
+                state
+                    .metrics
+                    .record_run(NO_RUN, response.status(), started.elapsed());
+                return Ok(req.into_response(response).map_into_right_body());
+            }
+        },
+        None => slot.and_then(|slot| slot.get()),
+    };
+    let response = match &run {
+        Some(run) => {
+            next.call(req)
+                .instrument(tracing::info_span!("run", id = %run))
+                .await
+        }
+        None => next.call(req).await,
+    };
+    let status = match &response {
+        Ok(response) => response.status(),
+        Err(err) => err.as_response_error().status_code(),
+    };
+    state
+        .metrics
+        .record_run(run.as_deref().unwrap_or(NO_RUN), status, started.elapsed());
+    response.map(ServiceResponse::map_into_left_body)
+}
@@ -0,0 +1,156 @@
+use actix_web::{
+    Error, HttpMessage, ResponseError,
+    body::{EitherBody, MessageBody},
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    error::PayloadError,
+    middleware::Next,
+    web,
+};
+use chrono::Utc;
+use futures_util::StreamExt;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::{collections::HashMap, time::Duration};
+
+use crate::{
+    middleware::normalize::OriginalPath,
+    scheme::error::ApiError,
+    state::{GlobalServerState, Metrics},
+};
+
+/// Header naming the client whose secret signed the request.
+pub const CLIENT_HEADER: &str = "X-Client-Id";
+
+/// Header with the signature: the hex-encoded HMAC-SHA256 of the signed message (see
+/// [`Signing::message`]).
+pub const SIGNATURE_HEADER: &str = "X-Signature";
+
+/// Header with the time the request was signed at, in Unix seconds.
+pub const TIMESTAMP_HEADER: &str = "X-Timestamp";
+
+/// Request signing mode: every request must be signed with the secret of a known client.
+#[derive(Debug, Clone)]
+pub struct Signing {
+    /// Secret of every client, by ID.
+    secrets: HashMap<String, Vec<u8>>,
+
+    /// How far the timestamp of a request may be off the server clock, in either direction.
+    /// Bounds how long a captured request can be replayed.
+    max_skew: Duration,
+
+    /// Maximum size of a request body, which is buffered to check the signature.
+    max_body_size: usize,
+}
+
+impl Signing {
+    /// Parses the comma-separated `<client>:<secret>` pairs of `spec`. Returns `None` if there are
+    /// none, i.e. signing is disabled.
+    pub fn from_spec(
+        spec: &str,
+        max_skew: Duration,
+        max_body_size: usize,
+    ) -> Result<Option<Self>, String> {
+        let secrets = spec
+            .split(',')
+            .filter(|pair| !pair.trim().is_empty())
+            .map(|pair| match pair.trim().split_once(':') {
+                Some((client, secret)) if !client.is_empty() && !secret.is_empty() => {
+                    Ok((client.to_owned(), secret.as_bytes().to_vec()))
+                }
+                _ => Err(format!("expected `<client>:<secret>`, got {pair:?}")),
+            })
+            .collect::<Result<HashMap<_, _>, _>>()?;
+        Ok((!secrets.is_empty()).then_some(Self {
+            secrets,
+            max_skew,
+            max_body_size,
+        }))
+    }
+
+    /// Returns the signed message: the timestamp, the method, the path with the query as sent
+    /// (see [`OriginalPath`]) and the body, the first three each followed by a line feed. The body is signed as it was sent,
+    /// i.e. still compressed if it has a `Content-Encoding`.
+    pub fn message(timestamp: &str, method: &str, path: &str, body: &[u8]) -> Vec<u8> {
+        let mut message = format!("{timestamp}\n{method}\n{path}\n").into_bytes();
+        message.extend_from_slice(body);
+        message
+    }
+
+    /// Checks the signature of a request; the error describes what's wrong with it.
+    fn verify(&self, req: &ServiceRequest, body: &[u8]) -> Result<(), String> {
+        let header = |name: &str| {
+            req.headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .ok_or_else(|| format!("missing {name} header"))
+        };
+        let (client, timestamp) = (header(CLIENT_HEADER)?, header(TIMESTAMP_HEADER)?);
+        let signature =
+            hex::decode(header(SIGNATURE_HEADER)?).map_err(|_| "malformed signature".to_owned())?;
+        let secret = self
+            .secrets
+            .get(client)
+            .ok_or_else(|| format!("unknown client {client:?}"))?;
+        let signed_at: i64 = timestamp
+            .parse()
+            .map_err(|_| "malformed timestamp".to_owned())?;
+        if Utc::now().timestamp().abs_diff(signed_at) > self.max_skew.as_secs() {
+            return Err("timestamp too far from the server time".to_owned());
+        }
+        // As the client signed it, not as normalized for routing
+        let path = OriginalPath::of(req);
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret)
+            .map_err(|err| format!("invalid secret: {err}"))?;
+        mac.update(&Self::message(
+            timestamp,
+            req.method().as_str(),
+            &path,
+            body,
+        ));
+        // Compares in constant time, so the signature can't be guessed byte by byte
+        mac.verify_slice(&signature)
+            .map_err(|_| "signature mismatch".to_owned())
+    }
+}
+
+/// Middleware rejecting requests without a valid signature with `401 Unauthorized` while request
+/// signing is enabled (see [`GlobalServerState::signing`]), counted in the
+/// `http.signature_failures` metric.
+///
+/// The body is read into memory up front to compute its HMAC, then handed on to the handler as it
+/// was. Bodies over the size limit are rejected with `413 Payload Too Large`. Signing only proves
+/// who sent a request; protected endpoints still require their bearer token.
+pub async fn verify_signature(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let state = req.app_data::<web::Data<GlobalServerState>>().cloned();
+    let Some((signing, metrics)) = state
+        .as_ref()
+        .and_then(|state| Some((state.signing.as_ref()?, &state.metrics)))
+    else {
+        return next
+            .call(req)
+            .await
+            .map(ServiceResponse::map_into_left_body);
+    };
+    let mut payload = req.take_payload();
+    let mut body = web::BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        body.extend_from_slice(&chunk?);
+        if body.len() > signing.max_body_size {
+            return Err(PayloadError::Overflow.into());
+        }
+    }
+    let body = body.freeze();
+    if let Err(err) = signing.verify(&req, &body) {
+        Metrics::inc(&metrics.http_signature_failures);
+        let response =
+            ApiError::Unauthorized(format!("invalid request signature: {err}")).error_response();
+        return Ok(req.into_response(response).map_into_right_body());
+    }
+    req.set_payload(Payload::from(body));
+    next.call(req)
+        .await
+        .map(ServiceResponse::map_into_left_body)
+}
@@ -0,0 +1,128 @@
+use actix_web::{
+    Error, HttpMessage, ResponseError,
+    body::{EitherBody, MessageBody},
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    error::{InternalError, PayloadError},
+    http::ConnectionType,
+    middleware::Next,
+    web,
+};
+use futures_util::{StreamExt, stream};
+use std::{
+    cell::Cell,
+    io,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use crate::{scheme::error::ApiError, state::GlobalServerState};
+
+/// Limits protecting the server from slow or oversized clients, e.g. slowloris-style load tests
+/// holding connections open with requests which never complete (see [`guard_slow_clients`]).
+///
+/// Only the limits checked per request live here: the time to send the head of a request and the
+/// write timeout are limits of the connection (see `main`).
+#[derive(Debug, Clone)]
+pub struct ClientLimits {
+    /// How long the body of a request may stall between two chunks; `None` waits forever.
+    pub read_timeout: Option<Duration>,
+
+    /// How long the whole body of a request may take, from the time the request reached the
+    /// middleware; `None` waits forever. Unlike `read_timeout`, it stops clients trickling a
+    /// chunk just before every stall would time out.
+    pub body_timeout: Option<Duration>,
+
+    /// Maximum total size of the headers of a request, names and values, in bytes.
+    pub max_header_size: usize,
+}
+
+/// Middleware answering requests whose headers are larger than
+/// [`ClientLimits::max_header_size`] with `431 Request Header Fields Too Large`, and requests
+/// whose body stalls for longer than [`ClientLimits::read_timeout`], or takes longer than
+/// [`ClientLimits::body_timeout`] as a whole, with `408 Request Timeout`.
+///
+/// A stalled request ends its connection, so a client trickling bytes can't hold it any longer.
+pub async fn guard_slow_clients(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let Some(limits) = req
+        .app_data::<web::Data<GlobalServerState>>()
+        .map(|state| state.client_limits.clone())
+    else {
+        return next
+            .call(req)
+            .await
+            .map(ServiceResponse::map_into_left_body);
+    };
+    let size: usize = req
+        .headers()
+        .iter()
+        .map(|(name, value)| name.as_str().len() + value.len())
+        .sum();
+    if size > limits.max_header_size {
+        let response = ApiError::HeaderFieldsTooLarge(format!(
+            "request headers take {size} bytes, more than {}",
+            limits.max_header_size
+        ))
+        .error_response();
+        return Ok(req.into_response(response).map_into_right_body());
+    }
+    if limits.read_timeout.is_none() && limits.body_timeout.is_none() {
+        return next
+            .call(req)
+            .await
+            .map(ServiceResponse::map_into_left_body);
+    }
+    let deadline = limits
+        .body_timeout
+        .map(|timeout| (Instant::now() + timeout, timeout));
+    // Set to the detail of the error once a limit is hit
+    let timed_out: Rc<Cell<Option<String>>> = Rc::default();
+    let payload = req.take_payload();
+    let watched = timed_out.clone();
+    let body = stream::unfold(Some(payload), move |payload| {
+        let timed_out = watched.clone();
+        async move {
+            let mut payload = payload?;
+            let left =
+                deadline.map(|(deadline, _)| deadline.saturating_duration_since(Instant::now()));
+            // The limit which runs out first, and whether it's the stall timeout
+            let (wait, stall) = match (limits.read_timeout, left) {
+                (Some(stall), Some(left)) if left <= stall => (left, false),
+                (Some(stall), _) => (stall, true),
+                (None, Some(left)) => (left, false),
+                (None, None) => return payload.next().await.map(|chunk| (chunk, Some(payload))),
+            };
+            match tokio::time::timeout(wait, payload.next()).await {
+                Ok(Some(chunk)) => Some((chunk, Some(payload))),
+                Ok(None) => None,
+                Err(_) => {
+                    let detail = match deadline {
+                        Some((_, total)) if !stall => {
+                            format!("request body took more than {} ms", total.as_millis())
+                        }
+                        _ => format!("request body stalled for more than {} ms", wait.as_millis()),
+                    };
+                    let err = io::Error::new(io::ErrorKind::TimedOut, detail.clone());
+                    timed_out.set(Some(detail));
+                    Some((Err(PayloadError::Io(err)), None))
+                }
+            }
+        }
+    });
+    req.set_payload(Payload::Stream {
+        payload: Box::pin(body),
+    });
+    let result = next.call(req).await;
+    let Some(detail) = timed_out.take() else {
+        return result.map(ServiceResponse::map_into_left_body);
+    };
+    // Passed on as an error, as the request may have been handed to the handler already
+    let err = ApiError::RequestTimeout(detail);
+    let mut response = err.error_response();
+    response
+        .head_mut()
+        .set_connection_type(ConnectionType::Close);
+    Err(InternalError::from_response(err, response).into())
+}
@@ -0,0 +1,114 @@
+use actix_web::{
+    Error,
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    http::Method,
+    middleware::Next,
+    web,
+};
+use sha2::{Digest, Sha256};
+use std::hint::black_box;
+
+use crate::state::GlobalServerState;
+
+/// Artificial CPU work of an endpoint, see [`WorkFactors`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct WorkFactor {
+    /// Method of the endpoint; `None` for any method.
+    method: Option<Method>,
+
+    /// Route pattern of the endpoint, e.g. `/posts/{id}`.
+    pattern: String,
+
+    /// Number of SHA-256 rounds per request.
+    iterations: u64,
+}
+
+/// Artificial CPU work added to requests of chosen endpoints by [`burn_cpu`], so CPU-bound and
+/// IO-bound behavior of the runtime can be compared with other backends under the same load.
+///
+/// The work factors are configured with `RUST_SERVER_WORK_FACTORS` (see
+/// [`WorkFactors::from_spec`]); there are none by default.
+#[derive(Debug, Clone, Default)]
+pub struct WorkFactors {
+    factors: Vec<WorkFactor>,
+}
+
+impl WorkFactors {
+    /// Parses comma-separated `<METHOD> <pattern>=<iterations>` entries, e.g. `GET
+    /// /posts=10000,* /posts/{id}=2000`, where `*` is any method and patterns are routes as
+    /// registered, parameters included.
+    ///
+    /// # Errors
+    /// Returns a description of the first malformed entry.
+    pub fn from_spec(spec: &str) -> Result<Self, String> {
+        let factors = spec
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let malformed =
+                    || format!("expected `<METHOD> <pattern>=<iterations>`, got {entry:?}");
+                let (endpoint, iterations) = entry.rsplit_once('=').ok_or_else(malformed)?;
+                let [method, pattern] = endpoint.split_whitespace().collect::<Vec<_>>()[..] else {
+                    return Err(malformed());
+                };
+                let method = match method {
+                    "*" => None,
+                    method => Some(
+                        Method::from_bytes(method.to_ascii_uppercase().as_bytes())
+                            .map_err(|_| format!("invalid method {method:?}"))?,
+                    ),
+                };
+                Ok(WorkFactor {
+                    method,
+                    pattern: pattern.to_owned(),
+                    iterations: iterations.trim().parse().map_err(|_| malformed())?,
+                })
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Self { factors })
+    }
+
+    /// Returns the number of rounds for a request of `method` matching `pattern`: that of the
+    /// first matching entry, 0 if none matches.
+    fn iterations(&self, method: &Method, pattern: &str) -> u64 {
+        self.factors
+            .iter()
+            .find(|factor| {
+                factor.pattern == pattern && factor.method.as_ref().is_none_or(|m| m == method)
+            })
+            .map_or(0, |factor| factor.iterations)
+    }
+}
+
+/// Hashes a 32-byte block `iterations` times, each round hashing the digest of the previous one.
+fn work(iterations: u64) {
+    let mut digest = [0u8; 32];
+    for _ in 0..iterations {
+        digest = Sha256::digest(digest).into();
+    }
+    black_box(digest);
+}
+
+/// Middleware doing the artificial work of the endpoint of a request (see [`WorkFactors`]) before
+/// handling it.
+///
+/// The work runs on the offload pool (see [`Offload`](crate::offload::Offload)), or on the worker
+/// thread handling the request if offloading is off, blocking the other requests of that worker
+/// meanwhile.
+pub async fn burn_cpu(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    if let (Some(state), Some(pattern)) = (
+        req.app_data::<web::Data<GlobalServerState>>(),
+        req.match_pattern(),
+    ) {
+        let iterations = state.work.iterations(req.method(), &pattern);
+        if iterations > 0 {
+            state.offload.run(move || work(iterations)).await?;
+        }
+    }
+    next.call(req).await
+}
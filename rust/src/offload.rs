@@ -0,0 +1,79 @@
+//! Offloading of CPU-heavy work from the worker threads.
+//!
+//! Handlers and middlewares run on the actix worker threads, each serving many connections: CPU
+//! work done there, e.g. rendering a large response or hashing, holds up every other request of
+//! the worker meanwhile. [`Offload::run`] moves such work to the blocking pool instead, at most
+//! `RUST_SERVER_OFFLOAD_POOL_SIZE` jobs at a time; further jobs wait for a free slot. How busy the
+//! pool is shows in the `offload` section of `GET /metrics`. With a size of 0, jobs run inline as
+//! before, so both can be compared.
+
+use actix_web::web;
+use std::{
+    sync::{Arc, atomic::Ordering},
+    time::Instant,
+};
+use tokio::sync::Semaphore;
+
+use crate::{
+    scheme::error::ApiError,
+    state::{Gauge, Metrics},
+};
+
+/// Bounded pool of CPU-heavy jobs, running on the blocking pool.
+#[derive(Debug, Clone)]
+pub struct Offload {
+    /// One permit per job which may run at a time; `None` runs jobs inline.
+    slots: Option<Arc<Semaphore>>,
+
+    metrics: Arc<Metrics>,
+}
+
+impl Offload {
+    /// Creates a pool running at most `size` jobs at a time, or running them inline if `size` is 0.
+    pub fn new(size: usize, metrics: Arc<Metrics>) -> Self {
+        metrics.offload_size.store(size as u64, Ordering::Relaxed);
+        Self {
+            slots: (size > 0).then(|| Arc::new(Semaphore::new(size))),
+            metrics,
+        }
+    }
+
+    /// Runs `job` on the blocking pool once a slot is free and returns its result.
+    ///
+    /// If the caller stops waiting, a job still waiting for a slot is dropped; one handed to the
+    /// blocking pool runs to completion.
+    ///
+    /// # Errors
+    /// Returns [`ApiError::Internal`] if the job panicked.
+    pub async fn run<T, F>(&self, job: F) -> Result<T, ApiError>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let Some(slots) = &self.slots else {
+            return Ok(job());
+        };
+        let queued = Instant::now();
+        let slot = {
+            let _waiting = Gauge::track(&self.metrics.offload_queued);
+            slots
+                .clone()
+                .acquire_owned()
+                .await
+                .map_err(|err| ApiError::Internal(format!("offload pool is closed: {err}")))?
+        };
+        self.metrics
+            .offload_wait_ns
+            .fetch_add(queued.elapsed().as_nanos() as u64, Ordering::Relaxed);
+        let metrics = self.metrics.clone();
+        web::block(move || {
+            let _running = Gauge::track(&metrics.offload_running);
+            let result = job();
+            Metrics::inc(&metrics.offload_completed);
+            drop(slot);
+            result
+        })
+        .await
+        .map_err(|err| ApiError::Internal(format!("offloaded job failed: {err}")))
+    }
+}
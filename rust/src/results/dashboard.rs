@@ -0,0 +1,263 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use crate::results::BenchResult;
+
+/// Size of a trend chart, in pixels.
+const CHART_WIDTH: f64 = 640.0;
+const CHART_HEIGHT: f64 = 220.0;
+
+/// Space around the plot area for labels, in pixels.
+const CHART_MARGIN: f64 = 40.0;
+
+/// Line colors of the backends, assigned in alphabetical order of backend names.
+const COLORS: [&str; 8] = [
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#7f7f7f",
+];
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em;color:#222}\
+table{border-collapse:collapse;margin:1em 0}\
+th,td{border:1px solid #ccc;padding:4px 8px;text-align:right}\
+th:first-child,td:first-child{text-align:left}\
+.charts{display:flex;flex-wrap:wrap;gap:1em}\
+svg{border:1px solid #eee}";
+
+/// A line of a trend chart: values of a backend, keyed by the index of the run.
+struct Series<'a> {
+    backend: &'a str,
+    points: Vec<(usize, f64)>,
+}
+
+/// Escapes text for use in HTML content and attributes.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Renders the dashboard: per scenario, the latest result of every backend and trend lines of
+/// throughput and per-operation p99 latency across runs.
+pub fn render(results: &[BenchResult]) -> String {
+    let mut html = String::new();
+    let runs: BTreeSet<&str> = results.iter().map(|r| r.run.as_str()).collect();
+    let backends: BTreeSet<&str> = results.iter().map(|r| r.backend.as_str()).collect();
+    let color = |backend: &str| {
+        let idx = backends.iter().position(|b| *b == backend).unwrap_or(0);
+        COLORS[idx % COLORS.len()]
+    };
+    let _ = write!(
+        html,
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>PerCom results</title>\
+         <style>{STYLE}</style></head><body><h1>PerCom benchmark results</h1>\
+         <p>{} results of {} runs, {} backends.</p>",
+        results.len(),
+        runs.len(),
+        backends.len()
+    );
+    if results.is_empty() {
+        html.push_str("<p>No results yet. Run the <code>orchestrator</code> binary first.</p>");
+    }
+    let mut scenarios: BTreeMap<&str, Vec<&BenchResult>> = BTreeMap::new();
+    for result in results {
+        scenarios.entry(&result.scenario).or_default().push(result);
+    }
+    for (scenario, results) in scenarios {
+        let _ = write!(html, "<h2>Scenario: {}</h2>", escape(scenario));
+        latest_table(&mut html, &results);
+        // Results are ordered by start time, so runs are too
+        let mut runs: Vec<&str> = Vec::new();
+        for result in results.iter() {
+            if !runs.contains(&result.run.as_str()) {
+                runs.push(&result.run);
+            }
+        }
+        let series = |value: &dyn Fn(&BenchResult) -> Option<f64>| -> Vec<Series> {
+            let mut by_backend: BTreeMap<&str, Vec<(usize, f64)>> = BTreeMap::new();
+            for result in results.iter() {
+                let run = runs.iter().position(|r| *r == result.run).unwrap_or(0);
+                if let Some(value) = value(result) {
+                    by_backend
+                        .entry(&result.backend)
+                        .or_default()
+                        .push((run, value));
+                }
+            }
+            by_backend
+                .into_iter()
+                .map(|(backend, points)| Series { backend, points })
+                .collect()
+        };
+        html.push_str("<div class=\"charts\">");
+        chart(
+            &mut html,
+            "Throughput (req/s)",
+            &runs,
+            &series(&|r| Some(r.report.throughput)),
+            &color,
+        );
+        let operations: BTreeSet<&str> = results
+            .iter()
+            .flat_map(|r| r.report.operations.iter().map(|op| op.operation.as_str()))
+            .collect();
+        for operation in operations {
+            chart(
+                &mut html,
+                &format!("{operation}: p99 latency (ms)"),
+                &runs,
+                &series(&|r| {
+                    r.report
+                        .operations
+                        .iter()
+                        .find(|op| op.operation == operation)
+                        .map(|op| op.latency.p99)
+                }),
+                &color,
+            );
+        }
+        html.push_str("</div>");
+    }
+    html.push_str("</body></html>");
+    html
+}
+
+/// Renders a table with the latest result of every backend.
+fn latest_table(html: &mut String, results: &[&BenchResult]) {
+    let mut latest: BTreeMap<&str, &BenchResult> = BTreeMap::new();
+    for result in results {
+        // Ordered by start time, so later results win
+        latest.insert(&result.backend, result);
+    }
+    html.push_str(
+        "<table><tr><th>Backend</th><th>Run</th><th>Requests</th><th>Throughput (req/s)</th>\
+         <th>Operation</th><th>Errors</th><th>p50 (ms)</th><th>p99 (ms)</th><th>max (ms)</th>\
+         <th>CPU avg %</th><th>RSS max (MiB)</th></tr>",
+    );
+    for (backend, result) in latest {
+        let report = &result.report;
+        let (cpu, rss) = report
+            .resources
+            .as_ref()
+            .map(|r| (format!("{:.1}", r.cpu_avg), format!("{:.1}", r.rss_max_mib)))
+            .unwrap_or_else(|| ("-".to_owned(), "-".to_owned()));
+        let rows = report.operations.len().max(1);
+        let _ = write!(
+            html,
+            "<tr><td rowspan=\"{rows}\">{}</td><td rowspan=\"{rows}\">{}</td>\
+             <td rowspan=\"{rows}\">{}</td><td rowspan=\"{rows}\">{:.1}</td>",
+            escape(backend),
+            escape(&result.run),
+            report.requests,
+            report.throughput
+        );
+        for (idx, op) in report.operations.iter().enumerate() {
+            if idx > 0 {
+                html.push_str("<tr>");
+            }
+            let _ = write!(
+                html,
+                "<td>{}</td><td>{}</td><td>{:.2}</td><td>{:.2}</td><td>{:.2}</td>",
+                escape(&op.operation),
+                op.errors,
+                op.latency.p50,
+                op.latency.p99,
+                op.latency.max
+            );
+            if idx == 0 {
+                let _ = write!(
+                    html,
+                    "<td rowspan=\"{rows}\">{cpu}</td><td rowspan=\"{rows}\">{rss}</td>"
+                );
+            }
+            html.push_str("</tr>");
+        }
+        if report.operations.is_empty() {
+            let _ = write!(
+                html,
+                "<td colspan=\"5\"></td><td>{cpu}</td><td>{rss}</td></tr>"
+            );
+        }
+    }
+    html.push_str("</table>");
+}
+
+/// Renders an SVG line chart with a line per backend across `runs`.
+fn chart(
+    html: &mut String,
+    title: &str,
+    runs: &[&str],
+    series: &[Series],
+    color: &dyn Fn(&str) -> &'static str,
+) {
+    let max = series
+        .iter()
+        .flat_map(|s| s.points.iter().map(|(_, v)| *v))
+        .fold(0.0, f64::max)
+        .max(f64::EPSILON);
+    let plot_width = CHART_WIDTH - 2.0 * CHART_MARGIN;
+    let plot_height = CHART_HEIGHT - 2.0 * CHART_MARGIN;
+    let x = |run: usize| {
+        CHART_MARGIN + plot_width * run as f64 / (runs.len().saturating_sub(1).max(1)) as f64
+    };
+    let y = |value: f64| CHART_MARGIN + plot_height * (1.0 - value / max);
+    let _ = write!(
+        html,
+        "<svg width=\"{CHART_WIDTH}\" height=\"{CHART_HEIGHT}\" xmlns=\"http://www.w3.org/2000/svg\">\
+         <text x=\"{CHART_MARGIN}\" y=\"20\" font-weight=\"bold\">{}</text>\
+         <line x1=\"{CHART_MARGIN}\" y1=\"{bottom}\" x2=\"{right}\" y2=\"{bottom}\" stroke=\"#999\"/>\
+         <line x1=\"{CHART_MARGIN}\" y1=\"{CHART_MARGIN}\" x2=\"{CHART_MARGIN}\" y2=\"{bottom}\" stroke=\"#999\"/>\
+         <text x=\"4\" y=\"{top}\" font-size=\"10\">{max:.1}</text>\
+         <text x=\"4\" y=\"{bottom}\" font-size=\"10\">0</text>",
+        escape(title),
+        bottom = CHART_MARGIN + plot_height,
+        right = CHART_MARGIN + plot_width,
+        top = CHART_MARGIN + 4.0,
+    );
+    // Label the first and the last run, the rest is available as tooltips of the points
+    for (idx, anchor) in [(0, "start"), (runs.len().saturating_sub(1), "end")] {
+        if let Some(run) = runs.get(idx) {
+            let _ = write!(
+                html,
+                "<text x=\"{}\" y=\"{}\" font-size=\"10\" text-anchor=\"{anchor}\">{}</text>",
+                x(idx),
+                CHART_HEIGHT - CHART_MARGIN / 2.0,
+                escape(run)
+            );
+        }
+    }
+    for (idx, series) in series.iter().enumerate() {
+        let color = color(series.backend);
+        let points = series
+            .points
+            .iter()
+            .map(|(run, value)| format!("{:.1},{:.1}", x(*run), y(*value)))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let _ = write!(
+            html,
+            "<polyline fill=\"none\" stroke=\"{color}\" stroke-width=\"2\" points=\"{points}\"/>"
+        );
+        for (run, value) in series.points.iter() {
+            let _ = write!(
+                html,
+                "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"3\" fill=\"{color}\">\
+                 <title>{}: {} = {value:.2}</title></circle>",
+                x(*run),
+                y(*value),
+                escape(series.backend),
+                escape(runs.get(*run).copied().unwrap_or_default())
+            );
+        }
+        let _ = write!(
+            html,
+            "<text x=\"{}\" y=\"{}\" font-size=\"11\" fill=\"{color}\" text-anchor=\"end\">{}</text>",
+            CHART_WIDTH - 4.0,
+            20.0 + 12.0 * idx as f64,
+            escape(series.backend)
+        );
+    }
+    html.push_str("</svg>");
+}
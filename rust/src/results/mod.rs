@@ -0,0 +1,137 @@
+pub mod dashboard;
+pub mod routes;
+
+use actix_web::{App, HttpServer, web};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+use tracing::{info, warn};
+
+use crate::envs::vars::get_server_addr;
+
+/// Directory read by `server results` when none is given.
+const DEFAULT_RESULTS_DIR: &str = "results";
+
+/// Latency percentiles and maximum, in milliseconds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Latency {
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub p999: f64,
+    pub max: f64,
+}
+
+/// Results of a single operation within a benchmark.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OperationResult {
+    pub operation: String,
+    pub count: u64,
+    pub errors: u64,
+
+    /// Latency from the intended start of the requests.
+    pub latency: Latency,
+}
+
+/// Resource usage of the server during a benchmark.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Resources {
+    pub cpu_avg: f64,
+    pub cpu_max: f64,
+    pub rss_max_mib: f64,
+}
+
+/// The part of the loadgen JSON report shown by the dashboard.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Report {
+    pub requests: u64,
+
+    /// Achieved throughput, in requests per second.
+    pub throughput: f64,
+    pub operations: Vec<OperationResult>,
+    pub resources: Option<Resources>,
+}
+
+/// Result of one scenario against one backend, as stored by the `orchestrator` binary in
+/// `<results_dir>/<run>/<backend>/<scenario>.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchResult {
+    pub run: String,
+    pub host: String,
+    pub started_at: DateTime<Utc>,
+    pub backend: String,
+    pub scenario: String,
+    pub report: Report,
+}
+
+/// Reads all results stored under `dir`, oldest first.
+///
+/// Files which aren't valid results are skipped with a warning, so a single broken file doesn't
+/// hide the rest of the history. A missing directory yields no results.
+pub fn load(dir: &Path) -> io::Result<Vec<BenchResult>> {
+    let mut results = Vec::new();
+    if !dir.exists() {
+        return Ok(results);
+    }
+    for run in fs::read_dir(dir)? {
+        let run = run?.path();
+        if !run.is_dir() {
+            continue;
+        }
+        for backend in fs::read_dir(&run)? {
+            let backend = backend?.path();
+            if !backend.is_dir() {
+                continue;
+            }
+            for file in fs::read_dir(&backend)? {
+                let file = file?.path();
+                if file.extension().is_none_or(|ext| ext != "json") {
+                    continue;
+                }
+                match fs::read(&file)
+                    .and_then(|bytes| serde_json::from_slice(&bytes).map_err(io::Error::other))
+                {
+                    Ok(result) => results.push(result),
+                    Err(err) => warn!("Skipping result {}: {err}", file.display()),
+                }
+            }
+        }
+    }
+    results.sort_by(|a: &BenchResult, b| {
+        a.started_at
+            .cmp(&b.started_at)
+            .then_with(|| a.backend.cmp(&b.backend))
+            .then_with(|| a.scenario.cmp(&b.scenario))
+    });
+    Ok(results)
+}
+
+/// Directory the results dashboard reads from, shared with the route handlers.
+pub struct ResultsState {
+    pub dir: PathBuf,
+}
+
+/// Serves the dashboard of historical benchmark results stored in `dir` (`server results [dir]`).
+///
+/// Results are re-read on every request, so new runs show up without a restart.
+pub async fn serve(dir: Option<String>) -> io::Result<()> {
+    let state = web::Data::new(ResultsState {
+        dir: PathBuf::from(dir.unwrap_or_else(|| DEFAULT_RESULTS_DIR.to_owned())),
+    });
+    let addr = get_server_addr()?;
+    info!(
+        "Serving results of {} at http://{addr}",
+        state.dir.display()
+    );
+    HttpServer::new(move || {
+        App::new()
+            .app_data(state.clone())
+            .configure(routes::configure)
+    })
+    .bind(addr)?
+    .run()
+    .await
+}
@@ -0,0 +1,47 @@
+use actix_web::{HttpResponse, Responder, get, web};
+
+use crate::{
+    results::{BenchResult, ResultsState, dashboard, load},
+    scheme::error::ApiError,
+};
+
+/// Reads the stored results on the blocking pool.
+async fn load_results(state: web::Data<ResultsState>) -> Result<Vec<BenchResult>, ApiError> {
+    web::block(move || load(&state.dir))
+        .await
+        .map_err(|err| ApiError::Internal(format!("fail to read results: {err}")))?
+        .map_err(|err| ApiError::Internal(format!("fail to read results: {err}")))
+}
+
+/// Handles `GET /`
+///
+/// Renders the HTML dashboard of all stored results.
+///
+/// # Response
+/// - `200 OK` with an HTML page
+/// - `500 Internal Server Error` if the results directory can't be read
+#[get("/")]
+async fn get_dashboard(state: web::Data<ResultsState>) -> Result<HttpResponse, ApiError> {
+    let results = load_results(state).await?;
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(dashboard::render(&results)))
+}
+
+/// Handles `GET /results`
+///
+/// Returns all stored results as JSON, oldest first.
+///
+/// # Response
+/// - `200 OK` with a JSON array of results
+/// - `500 Internal Server Error` if the results directory can't be read
+#[get("/results")]
+async fn get_results(state: web::Data<ResultsState>) -> Result<impl Responder, ApiError> {
+    let results = load_results(state).await?;
+    Ok(HttpResponse::Ok().json(results))
+}
+
+/// Registers the results dashboard route handlers into the Actix-Web service configuration.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_dashboard).service(get_results);
+}
@@ -0,0 +1,107 @@
+//! Order-independent checksums of the stored data (`GET /admin/checksum`).
+//!
+//! After replaying identical traffic against two backends, equal checksums mean equal final
+//! states. Every record is hashed on its own (SHA-256 of the JSON form of its content), and the
+//! sorted digests are hashed together, so the order in which a provider returns its records
+//! doesn't matter. IDs generated by the server are left out, as every backend generates its own:
+//! records are compared by what clients sent. Dates are hashed as they're rendered, so both
+//! backends must run with the same date precision.
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use percom_model::date;
+
+use crate::scheme::{
+    posts::{Post, PostStatus},
+    users::User,
+};
+
+/// Content of a post, as hashed: the post without its ID.
+#[derive(Serialize)]
+struct PostContent<'a> {
+    author: &'a str,
+    date: String,
+    content: &'a str,
+    status: &'a PostStatus,
+    publish_at: Option<String>,
+}
+
+/// Content of a user, as hashed: the user without its ID, with the authors it follows.
+#[derive(Serialize)]
+struct UserContent<'a> {
+    nickname: &'a str,
+    email: &'a str,
+    following: Vec<&'a str>,
+}
+
+/// Checksum of a set of records.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Checksum {
+    /// Number of records.
+    pub count: usize,
+
+    /// Hex-encoded SHA-256 over the sorted digests of the records.
+    pub hash: String,
+}
+
+impl Checksum {
+    /// Computes the checksum of records, each given by its encoded form.
+    fn of(records: impl IntoIterator<Item = Vec<u8>>) -> Self {
+        let mut digests: Vec<[u8; 32]> = records
+            .into_iter()
+            .map(|record| Sha256::digest(record).into())
+            .collect();
+        digests.sort_unstable();
+        let mut hasher = Sha256::new();
+        for digest in &digests {
+            hasher.update(digest);
+        }
+        Self {
+            count: digests.len(),
+            hash: hex::encode(hasher.finalize()),
+        }
+    }
+}
+
+/// Returns the checksum of `posts`, scheduled ones included.
+pub fn posts(posts: &[Post]) -> Result<Checksum, serde_json::Error> {
+    let records = posts
+        .iter()
+        .map(|post| {
+            serde_json::to_vec(&PostContent {
+                author: &post.author,
+                date: date::format(&post.date),
+                content: &post.content,
+                status: &post.status,
+                publish_at: post.publish_at.as_ref().map(date::format),
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Checksum::of(records))
+}
+
+/// Returns the checksum of `users`, each with the names of the authors it follows, in any order.
+pub fn users(users: &[(User, Vec<String>)]) -> Result<Checksum, serde_json::Error> {
+    let records = users
+        .iter()
+        .map(|(user, following)| {
+            let mut following: Vec<&str> = following.iter().map(String::as_str).collect();
+            following.sort_unstable();
+            serde_json::to_vec(&UserContent {
+                nickname: &user.nickname,
+                email: &user.email,
+                following,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Checksum::of(records))
+}
+
+/// Combines the checksums of posts and users into the checksum of the whole state.
+pub fn combine(posts: &Checksum, users: &Checksum) -> String {
+    hex::encode(Sha256::digest(format!(
+        "posts:{};users:{}",
+        posts.hash, users.hash
+    )))
+}
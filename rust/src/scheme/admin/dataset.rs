@@ -0,0 +1,164 @@
+use chrono::{DateTime, FixedOffset, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::scheme::posts::{Post, PostStatus, date, versions::Stored};
+
+/// Maximum accepted size of an imported dataset, in bytes.
+pub const MAX_IMPORT_SIZE: usize = 512 * 1024 * 1024;
+
+/// Serialization format of an exported or imported posts dataset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// Comma-separated values with a header row; see [`CsvRow`] for the columns.
+    Csv,
+
+    /// JSON Lines: one [`Post`] object per line, as returned by `GET /posts/{id}` plus the
+    /// `version` of the model (see [`Stored`]), so exports of older builds can be imported.
+    Jsonl,
+}
+
+impl Format {
+    /// Returns the `Content-Type` of the format.
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Jsonl => "application/x-ndjson",
+        }
+    }
+
+    /// Maps the `Content-Type` of an upload to a format.
+    ///
+    /// Returns `None` for content types that don't denote a dataset.
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        let mime = content_type.split(';').next().unwrap_or_default().trim();
+        match mime {
+            "text/csv" => Some(Self::Csv),
+            "application/x-ndjson" | "application/jsonl" | "application/jsonlines" => {
+                Some(Self::Jsonl)
+            }
+            _ => None,
+        }
+    }
+
+    /// Maps the extension of a dataset file to a format: `.csv`, or `.jsonl`, `.ndjson`.
+    ///
+    /// Returns `None` for other extensions.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "csv" => Some(Self::Csv),
+            "jsonl" | "ndjson" => Some(Self::Jsonl),
+            _ => None,
+        }
+    }
+}
+
+/// A row of the CSV format. Columns follow the field order:
+///
+/// ```text
+/// id,author,date,content,status,publish_at
+/// ```
+///
+/// Dates are RFC 3339; `publish_at` is empty for posts which were never scheduled.
+#[derive(Debug, Serialize, Deserialize)]
+struct CsvRow {
+    id: String,
+    author: String,
+    #[serde(
+        serialize_with = "date::serialize",
+        deserialize_with = "date::deserialize"
+    )]
+    date: DateTime<FixedOffset>,
+    content: String,
+    status: PostStatus,
+    #[serde(
+        serialize_with = "date::serialize_utc",
+        deserialize_with = "date::deserialize_utc"
+    )]
+    publish_at: Option<DateTime<Utc>>,
+}
+
+impl From<Post> for CsvRow {
+    fn from(post: Post) -> Self {
+        Self {
+            id: post.id,
+            author: post.author,
+            date: post.date,
+            content: post.content,
+            status: post.status,
+            publish_at: post.publish_at,
+        }
+    }
+}
+
+impl From<CsvRow> for Post {
+    fn from(row: CsvRow) -> Self {
+        Self {
+            id: row.id,
+            author: row.author,
+            date: row.date,
+            content: row.content,
+            status: row.status,
+            publish_at: row.publish_at,
+        }
+    }
+}
+
+/// Returns the bytes preceding the first post of an export (the CSV header row).
+pub fn header(format: Format) -> Vec<u8> {
+    match format {
+        Format::Csv => b"id,author,date,content,status,publish_at\n".to_vec(),
+        Format::Jsonl => Vec::new(),
+    }
+}
+
+/// Encodes a batch of posts; batches can be concatenated after the [`header`].
+pub fn encode(format: Format, posts: Vec<Post>) -> Result<Vec<u8>, String> {
+    match format {
+        Format::Csv => {
+            let mut writer = csv::WriterBuilder::new()
+                .has_headers(false)
+                .from_writer(Vec::new());
+            for post in posts {
+                writer
+                    .serialize(CsvRow::from(post))
+                    .map_err(|err| err.to_string())?;
+            }
+            writer.into_inner().map_err(|err| err.to_string())
+        }
+        Format::Jsonl => {
+            let mut bytes = Vec::new();
+            for post in posts {
+                serde_json::to_writer(&mut bytes, &Stored(post)).map_err(|err| err.to_string())?;
+                bytes.push(b'\n');
+            }
+            Ok(bytes)
+        }
+    }
+}
+
+/// Decodes a whole dataset.
+///
+/// Empty JSON Lines are skipped. Fails on the first malformed record, naming its line, so that
+/// nothing is imported from a broken file.
+///
+/// This is CPU-bound work and should be called from the blocking pool (e.g. via `web::block`).
+pub fn decode(format: Format, bytes: &[u8]) -> Result<Vec<Post>, String> {
+    match format {
+        Format::Csv => csv::Reader::from_reader(bytes)
+            .deserialize::<CsvRow>()
+            .map(|row| row.map(Post::from).map_err(|err| err.to_string()))
+            .collect(),
+        Format::Jsonl => bytes
+            .split(|b| *b == b'\n')
+            .enumerate()
+            .filter(|(_, line)| !line.trim_ascii().is_empty())
+            .map(|(idx, line)| {
+                serde_json::from_slice(line)
+                    .map(|Stored(post)| post)
+                    .map_err(|err| format!("line {}: {err}", idx + 1))
+            })
+            .collect(),
+    }
+}
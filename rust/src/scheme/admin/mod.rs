@@ -0,0 +1,3 @@
+pub mod checksum;
+pub mod dataset;
+pub mod routes;
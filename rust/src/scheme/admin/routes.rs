@@ -0,0 +1,866 @@
+use actix_web::{
+    HttpRequest, HttpResponse, delete, get,
+    http::{Method, header},
+    post, put, web,
+};
+use futures_util::{StreamExt, stream};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, io, sync::Arc};
+use tracing::debug;
+
+use crate::{
+    connection,
+    envs::vars,
+    middleware::{acl::AclRule, maintenance::MaintenanceStatus},
+    scheme::{
+        admin::{
+            checksum,
+            dataset::{self, Format},
+        },
+        auth::AuthToken,
+        breaker::Breaker,
+        error::ApiError,
+        flags::FlagValue,
+        methods::{self, Route},
+        moderation::ModerationQueue,
+        params::{NoParams, Params, Query},
+        posts::{PostsProvider, PostsQuery},
+        replication::{Changes, Replica},
+        users::{User, UsersProvider},
+    },
+    state::{GlobalServerState, Metrics},
+};
+
+/// Number of posts encoded into a single chunk of an export.
+const EXPORT_CHUNK: usize = 1000;
+
+/// Shared application state for the `/admin` route group.
+#[derive(Clone)]
+pub struct AdminState {
+    /// Provider of the posts being exported and imported.
+    pub posts: Arc<dyn PostsProvider>,
+
+    /// Provider of the users, whose accounts are managed at `/admin/users` and whose stats are
+    /// shown at `/admin/providers`.
+    pub users: Arc<dyn UsersProvider>,
+
+    /// Server-wide metrics, counting abandoned exports.
+    pub metrics: Arc<Metrics>,
+
+    /// Circuit breakers of external storages.
+    pub breakers: Vec<Arc<Breaker>>,
+
+    /// Changes of the posts, streamed at `/admin/changes`.
+    pub changes: Arc<Changes>,
+
+    /// Replication progress, if this instance is a replica.
+    pub replica: Option<Arc<Replica>>,
+
+    /// Items flagged by the content moderator.
+    pub moderation: Arc<ModerationQueue>,
+}
+
+impl AdminState {
+    /// Constructs a new [`AdminState`] with the given providers.
+    pub fn new(
+        posts: Arc<dyn PostsProvider>,
+        users: Arc<dyn UsersProvider>,
+        metrics: Arc<Metrics>,
+        breakers: Vec<Arc<Breaker>>,
+        changes: Arc<Changes>,
+        replica: Option<Arc<Replica>>,
+        moderation: Arc<ModerationQueue>,
+    ) -> Self {
+        Self {
+            posts,
+            users,
+            metrics,
+            breakers,
+            changes,
+            replica,
+            moderation,
+        }
+    }
+}
+
+/// Query parameters of the dataset endpoints.
+#[derive(Debug, Deserialize)]
+pub struct FormatQuery {
+    /// Dataset format; defaults to JSON Lines for exports and to the `Content-Type` for imports.
+    pub format: Option<Format>,
+}
+
+impl Params for FormatQuery {
+    const NAMES: &'static [&'static str] = &["format"];
+}
+
+/// Handles `GET /admin/posts/export`
+///
+/// Streams all posts, including scheduled ones, ordered by date and ID. Requires a valid
+/// [`AuthToken`].
+///
+/// Chunks are encoded on the offload pool (see [`Offload`](crate::offload::Offload)). If the client
+/// disconnects, the rest of the export is dropped right away (see [`connection::watch`]).
+///
+/// # Query Parameters
+/// - `format`: `csv` or `jsonl` (default)
+///
+/// # Response
+/// - `200 OK` with the dataset as `text/csv` or `application/x-ndjson`
+/// - `400 Bad Request` for an unknown format
+#[get("/posts/export")]
+async fn export_posts(
+    _auth: AuthToken,
+    req: HttpRequest,
+    state: web::Data<AdminState>,
+    global: web::Data<GlobalServerState>,
+    query: Query<FormatQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let format = query.format.unwrap_or(Format::Jsonl);
+    let mut posts = state.posts.get_all()?;
+    posts.sort_unstable_by(|a, b| a.date.cmp(&b.date).then_with(|| a.id.cmp(&b.id)));
+    debug!("Exporting {} posts as {format:?}", posts.len());
+    let header = stream::iter([Ok(web::Bytes::from(dataset::header(format)))]);
+    // Posts are encoded chunk by chunk while the response is sent
+    let offload = global.offload.clone();
+    let body = stream::unfold(posts.into_iter(), move |mut posts| {
+        let offload = offload.clone();
+        async move {
+            let chunk: Vec<_> = posts.by_ref().take(EXPORT_CHUNK).collect();
+            if chunk.is_empty() {
+                return None;
+            }
+            let bytes = offload
+                .run(move || dataset::encode(format, chunk))
+                .await
+                .map_err(|err| err.to_string())
+                .and_then(|bytes| bytes)
+                .map(web::Bytes::from)
+                .map_err(io::Error::other);
+            Some((bytes, posts))
+        }
+    });
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .streaming(connection::watch(
+            &req,
+            header.chain(body),
+            state.metrics.clone(),
+        )))
+}
+
+/// Handles `POST /admin/posts/import`
+///
+/// Imports a dataset produced by [`export_posts`] (of this or another backend), keeping the IDs,
+/// so backends can be compared on identical data. Posts with existing IDs are replaced. Nothing is
+/// imported if any record is malformed. Requires a valid [`AuthToken`].
+///
+/// # Query Parameters
+/// - `format`: `csv` or `jsonl`; if missing, the format is taken from the `Content-Type`
+///   (`text/csv` or `application/x-ndjson`)
+///
+/// # Request Body
+/// The dataset, at most [`dataset::MAX_IMPORT_SIZE`] bytes.
+///
+/// # Response
+/// - `200 OK` with `{"imported": <number of posts>}`
+/// - `400 Bad Request` if a record is malformed
+/// - `413 Payload Too Large` if the dataset exceeds the size limit
+/// - `415 Unsupported Media Type` if the format can't be determined
+#[post("/posts/import")]
+async fn import_posts(
+    _auth: AuthToken,
+    req: HttpRequest,
+    state: web::Data<AdminState>,
+    query: Query<FormatQuery>,
+    body: web::Bytes,
+) -> Result<HttpResponse, ApiError> {
+    let format = query
+        .format
+        .or_else(|| {
+            req.headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .and_then(Format::from_content_type)
+        })
+        .ok_or(ApiError::UnsupportedMediaType)?;
+    let posts = web::block(move || dataset::decode(format, &body))
+        .await
+        .map_err(|err| ApiError::Internal(format!("fail to decode dataset: {err}")))?
+        .map_err(ApiError::BadRequest)?;
+    let imported = state.posts.import(posts)?;
+    debug!("Imported {imported} posts");
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "imported": imported })))
+}
+
+/// Query parameters of [`explain_query`].
+#[derive(Debug, Deserialize)]
+pub struct ExplainQuery {
+    /// Filter combination to explain (see [`PostsQuery`]); empty for all posts.
+    #[serde(default)]
+    pub query: String,
+}
+
+impl Params for ExplainQuery {
+    const NAMES: &'static [&'static str] = &["query"];
+}
+
+/// Handles `GET /admin/explain`
+///
+/// Shows which index or scan the posts provider would use to find the posts matching a filter
+/// combination, and how many posts it would read, so list endpoints silently degrading to full
+/// scans can be spotted. Nothing is read besides the indexes. Requires a valid [`AuthToken`].
+///
+/// # Query Parameters
+/// - `query`: comma-separated `field=value` terms, e.g. `author=alice,author=bob` (see
+///   [`PostsQuery`])
+///
+/// # Response
+/// - `200 OK` with `{"query": <parsed query>, "plan": <plan>}` (see
+///   [`QueryPlan`](crate::scheme::posts::QueryPlan))
+/// - `400 Bad Request` if the query is malformed
+#[get("/explain")]
+async fn explain_query(
+    _auth: AuthToken,
+    state: web::Data<AdminState>,
+    query: Query<ExplainQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let query: PostsQuery = query.query.parse().map_err(ApiError::BadRequest)?;
+    let plan = state.posts.explain(&query)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "query": query, "plan": plan })))
+}
+
+/// Handles `GET /admin/providers`
+///
+/// Returns the statistics of every provider (see
+/// [`ProviderStats`](crate::scheme::provider::ProviderStats)): item counts, index sizes,
+/// time spent waiting for locks, WAL compactions, etc. Decorators such as the circuit breaker
+/// nest the stats of the provider they wrap. Requires a valid [`AuthToken`].
+///
+/// # Response
+/// - `200 OK` with `{"posts": <stats>, "users": <stats>}`
+#[get("/providers")]
+async fn get_providers(
+    _auth: AuthToken,
+    state: web::Data<AdminState>,
+    _query: Query<NoParams>,
+) -> HttpResponse {
+    HttpResponse::Ok().json(serde_json::json!({
+        "posts": state.posts.stats(),
+        "users": state.users.stats(),
+    }))
+}
+
+/// Handles `GET /admin/checksum`
+///
+/// Returns order-independent checksums of all posts, scheduled ones included, and all users with
+/// whom they follow (see [`checksum`]), e.g. to check that two backends end in the same state
+/// after replaying identical traffic. The state is read on the blocking pool and hashed on the
+/// offload pool (see [`Offload`](crate::offload::Offload)). Requires a valid [`AuthToken`].
+///
+/// # Response
+/// - `200 OK` with `{"hash": <hex>, "posts": {"count", "hash"}, "users": {"count", "hash"}}`
+#[get("/checksum")]
+async fn get_checksum(
+    _auth: AuthToken,
+    state: web::Data<AdminState>,
+    global: web::Data<GlobalServerState>,
+    _query: Query<NoParams>,
+) -> Result<HttpResponse, ApiError> {
+    let (posts, users) = (state.posts.clone(), state.users.clone());
+    let (posts, users) = web::block(move || {
+        let posts = posts.get_all()?;
+        let users = users
+            .get_all()?
+            .into_iter()
+            .map(|user| {
+                let following = users.following(&user.id)?.unwrap_or_default();
+                Ok((user, following))
+            })
+            .collect::<Result<Vec<_>, ApiError>>()?;
+        Ok::<_, ApiError>((posts, users))
+    })
+    .await
+    .map_err(|err| ApiError::Internal(format!("fail to read the state: {err}")))??;
+    let (posts, users) = global
+        .offload
+        .run(move || {
+            Ok::<_, serde_json::Error>((checksum::posts(&posts)?, checksum::users(&users)?))
+        })
+        .await?
+        .map_err(|err| ApiError::Internal(format!("failed to hash the state: {err}")))?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "hash": checksum::combine(&posts, &users),
+        "posts": posts,
+        "users": users,
+    })))
+}
+
+/// Handles `GET /admin/changes`
+///
+/// Streams the changes of the posts as server-sent events, for replicas to follow (see
+/// [`Changes::subscribe`]). The stream starts with a heartbeat carrying the sequence number of the
+/// latest change and never ends on its own. Requires a valid [`AuthToken`].
+///
+/// # Response
+/// - `200 OK` with a `text/event-stream` of `change`, `heartbeat` and `lagged` events
+#[get("/changes")]
+async fn stream_changes(
+    _auth: AuthToken,
+    req: HttpRequest,
+    state: web::Data<AdminState>,
+    _query: Query<NoParams>,
+) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        .streaming(connection::watch(
+            &req,
+            state.changes.clone().subscribe(),
+            state.metrics.clone(),
+        ))
+}
+
+/// Handles `GET /admin/replication`
+///
+/// Returns the role of this instance, whether it rejects writes and, on a replica, how far it lags
+/// behind its primary (see [`ReplicaStatus`](crate::scheme::replication::ReplicaStatus)). Requires
+/// a valid [`AuthToken`].
+///
+/// # Response
+/// - `200 OK` with `{"role": "primary"|"replica", "read_only": <bool>, "changes": {"seq",
+///   "subscribers"}, "replica": <status or null>}`
+#[get("/replication")]
+async fn get_replication(
+    _auth: AuthToken,
+    global: web::Data<GlobalServerState>,
+    state: web::Data<AdminState>,
+    _query: Query<NoParams>,
+) -> HttpResponse {
+    let replica = state.replica.as_ref().map(|replica| replica.status());
+    HttpResponse::Ok().json(serde_json::json!({
+        "role": if replica.is_some() { "replica" } else { "primary" },
+        "read_only": global.read_only.is_some(),
+        "changes": state.changes.status(),
+        "replica": replica,
+    }))
+}
+
+/// Body of `GET` and `PUT /admin/acl`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AclRules {
+    /// Rules of the access control list, each checked on the requests of its path.
+    pub rules: Vec<AclRule>,
+}
+
+/// Handles `GET /admin/acl`
+///
+/// Returns the rules of the IP access control list (see
+/// [`Acl`](crate::middleware::acl::Acl)). Requires a valid [`AuthToken`].
+///
+/// # Response
+/// - `200 OK` with `{"rules": [{"path", "allow": [<range>], "deny": [<range>]}]}`
+#[get("/acl")]
+async fn get_acl(
+    _auth: AuthToken,
+    global: web::Data<GlobalServerState>,
+    _query: Query<NoParams>,
+) -> HttpResponse {
+    HttpResponse::Ok().json(AclRules {
+        rules: global.acl.rules(),
+    })
+}
+
+/// Handles `PUT /admin/acl`
+///
+/// Replaces the rules of the IP access control list; they apply to the next request, including
+/// requests to this endpoint, so a client can lock itself out. Requires a valid [`AuthToken`].
+///
+/// # Request Body
+/// - The same document `GET /admin/acl` returns; ranges are addresses or CIDR ranges
+///
+/// # Response
+/// - `200 OK` with the new rules
+/// - `400 Bad Request` if a rule is malformed
+#[put("/acl")]
+async fn put_acl(
+    _auth: AuthToken,
+    global: web::Data<GlobalServerState>,
+    body: web::Json<AclRules>,
+    _query: Query<NoParams>,
+) -> Result<HttpResponse, ApiError> {
+    let AclRules { rules } = body.into_inner();
+    global
+        .acl
+        .set_rules(rules.clone())
+        .map_err(ApiError::BadRequest)?;
+    debug!("ACL replaced with {} rules", rules.len());
+    Ok(HttpResponse::Ok().json(AclRules { rules }))
+}
+
+/// Handles `GET /admin/auth-failures`
+///
+/// Returns the requests rejected with `401 Unauthorized` or `403 Forbidden` by route and by hashed
+/// token, and the clients locked out after repeated failures (see
+/// [`AuthFailures`](crate::middleware::auth_failures::AuthFailures)). Requires a valid
+/// [`AuthToken`].
+///
+/// # Response
+/// - `200 OK` with an
+///   [`AuthFailuresReport`](crate::middleware::auth_failures::AuthFailuresReport)
+#[get("/auth-failures")]
+async fn get_auth_failures(
+    _auth: AuthToken,
+    global: web::Data<GlobalServerState>,
+    _query: Query<NoParams>,
+) -> HttpResponse {
+    HttpResponse::Ok().json(global.auth_failures.report())
+}
+
+/// Handles `DELETE /admin/auth-failures`
+///
+/// Forgets the rejected requests and lifts every lockout, e.g. between test scenarios. Requires a
+/// valid [`AuthToken`].
+///
+/// # Response
+/// - `204 No Content`
+#[delete("/auth-failures")]
+async fn delete_auth_failures(
+    _auth: AuthToken,
+    global: web::Data<GlobalServerState>,
+    _query: Query<NoParams>,
+) -> HttpResponse {
+    global.auth_failures.reset();
+    debug!("Auth failures reset");
+    HttpResponse::NoContent().finish()
+}
+
+/// A user with the state of their account, as listed at `GET /admin/users`.
+#[derive(Debug, Serialize)]
+pub struct Account {
+    #[serde(flatten)]
+    pub user: User,
+
+    /// Whether the account is disabled: the tokens of the user are then rejected with
+    /// `403 Forbidden`.
+    pub disabled: bool,
+}
+
+impl Account {
+    /// Returns the account of `user`.
+    fn of(users: &dyn UsersProvider, user: User) -> Result<Self, ApiError> {
+        let disabled = users.is_disabled(&user.id)?;
+        Ok(Self { user, disabled })
+    }
+}
+
+/// Handles `GET /admin/users`
+///
+/// Lists every user with the state of their account, ordered by nickname. Requires a valid
+/// [`AuthToken`].
+///
+/// # Response
+/// - `200 OK` with a JSON array of [`Account`] objects: `{"id", "nickname", "email", "disabled"}`
+#[get("/users")]
+async fn list_accounts(
+    _auth: AuthToken,
+    state: web::Data<AdminState>,
+    _query: Query<NoParams>,
+) -> Result<HttpResponse, ApiError> {
+    let mut users = state.users.get_all()?;
+    users.sort_by(|a, b| a.nickname.cmp(&b.nickname).then_with(|| a.id.cmp(&b.id)));
+    let accounts = users
+        .into_iter()
+        .map(|user| Account::of(state.users.as_ref(), user))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(HttpResponse::Ok().json(accounts))
+}
+
+/// Disables or re-enables the account of the user `id`, returning it.
+fn set_disabled(state: &AdminState, id: &str, disabled: bool) -> Result<HttpResponse, ApiError> {
+    if !state.users.set_disabled(id, disabled)? {
+        return Err(ApiError::NotFound);
+    }
+    let user = state.users.get(id)?.ok_or(ApiError::NotFound)?;
+    debug!(
+        "User {id} {}",
+        if disabled { "disabled" } else { "enabled" }
+    );
+    Ok(HttpResponse::Ok().json(Account::of(state.users.as_ref(), user)?))
+}
+
+/// Handles `POST /admin/users/{id}/disable`
+///
+/// Disables the account of a user: the auth extractor rejects the tokens of the user with
+/// `403 Forbidden` until the account is enabled again, while the user and their data are kept.
+/// Requires a valid [`AuthToken`].
+///
+/// # Response
+/// - `200 OK` with the [`Account`]
+/// - `404 Not Found` if the user does not exist
+#[post("/users/{id}/disable")]
+async fn disable_user(
+    _auth: AuthToken,
+    state: web::Data<AdminState>,
+    path: web::Path<String>,
+    _query: Query<NoParams>,
+) -> Result<HttpResponse, ApiError> {
+    set_disabled(&state, &path.into_inner(), true)
+}
+
+/// Handles `POST /admin/users/{id}/enable`
+///
+/// Enables the account of a disabled user again; tokens which weren't revoked meanwhile are
+/// accepted again. Requires a valid [`AuthToken`].
+///
+/// # Response
+/// - `200 OK` with the [`Account`]
+/// - `404 Not Found` if the user does not exist
+#[post("/users/{id}/enable")]
+async fn enable_user(
+    _auth: AuthToken,
+    state: web::Data<AdminState>,
+    path: web::Path<String>,
+    _query: Query<NoParams>,
+) -> Result<HttpResponse, ApiError> {
+    set_disabled(&state, &path.into_inner(), false)
+}
+
+/// Handles `POST /admin/users/{id}/logout`
+///
+/// Logs the user out everywhere by revoking every token issued to them (see
+/// [`UsersProvider::revoke_tokens`]), so they're rejected with `401 Unauthorized`. Unlike
+/// disabling, this can't be undone. Requires a valid [`AuthToken`].
+///
+/// # Response
+/// - `204 No Content` once the tokens are revoked
+/// - `404 Not Found` if the user does not exist
+#[post("/users/{id}/logout")]
+async fn logout_user(
+    _auth: AuthToken,
+    state: web::Data<AdminState>,
+    path: web::Path<String>,
+    _query: Query<NoParams>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+    if state.users.get(&id)?.is_none() {
+        return Err(ApiError::NotFound);
+    }
+    state.users.revoke_tokens(&id)?;
+    debug!("Tokens of user {id} revoked");
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Handles `GET /admin/maintenance`
+///
+/// Returns the state of the maintenance mode (see
+/// [`Maintenance`](crate::middleware::maintenance::Maintenance)). Requires a valid [`AuthToken`].
+///
+/// # Response
+/// - `200 OK` with `{"enabled": <bool>, "retry_after_secs": <secs>, "since": <date or null>}`
+#[get("/maintenance")]
+async fn get_maintenance(
+    _auth: AuthToken,
+    global: web::Data<GlobalServerState>,
+    _query: Query<NoParams>,
+) -> HttpResponse {
+    HttpResponse::Ok().json(global.maintenance.status())
+}
+
+/// Handles `POST /admin/maintenance`
+///
+/// Enters or leaves the maintenance mode (see
+/// [`Maintenance`](crate::middleware::maintenance::Maintenance)): meanwhile writes outside of
+/// `/admin` are answered with `503 Service Unavailable` and `Retry-After`, while reads continue.
+/// Requires a valid [`AuthToken`].
+///
+/// # Request Body
+/// - `{"enabled": <bool>, "retry_after_secs": <secs>}`; `retry_after_secs` defaults to
+///   [`DEFAULT_RETRY_AFTER_SECS`](crate::middleware::maintenance::DEFAULT_RETRY_AFTER_SECS)
+///
+/// # Response
+/// - `200 OK` with the new state, as `GET /admin/maintenance` returns it
+#[post("/maintenance")]
+async fn post_maintenance(
+    _auth: AuthToken,
+    global: web::Data<GlobalServerState>,
+    body: web::Json<MaintenanceStatus>,
+    _query: Query<NoParams>,
+) -> HttpResponse {
+    global.maintenance.set(&body);
+    let status = global.maintenance.status();
+    debug!("Maintenance mode changed: {status:?}");
+    HttpResponse::Ok().json(status)
+}
+
+/// Handles `GET /admin/flags`
+///
+/// Returns the feature flags (see [`FeatureFlags`](crate::scheme::flags::FeatureFlags)). Requires
+/// a valid [`AuthToken`].
+///
+/// # Response
+/// - `200 OK` with `{"<flag>": <enabled>, "<experiment>": <percent>}`
+#[get("/flags")]
+async fn get_flags(
+    _auth: AuthToken,
+    global: web::Data<GlobalServerState>,
+    _query: Query<NoParams>,
+) -> HttpResponse {
+    HttpResponse::Ok().json(global.flags.values())
+}
+
+/// Handles `PUT /admin/flags`
+///
+/// Turns the given feature flags on or off and sets the share of requests sent to the second
+/// variant of experiments, e.g. between benchmark runs; the other flags keep their values.
+/// Requires a valid [`AuthToken`].
+///
+/// # Request Body
+/// - `{"<flag>": <enabled>, "<experiment>": <percent>}` with some of the flags `GET /admin/flags`
+///   returns
+///
+/// # Response
+/// - `200 OK` with all flags
+/// - `400 Bad Request` if a flag is unknown or gets a value of the wrong kind; no flag is changed
+///   then
+#[put("/flags")]
+async fn put_flags(
+    _auth: AuthToken,
+    global: web::Data<GlobalServerState>,
+    body: web::Json<BTreeMap<String, FlagValue>>,
+    _query: Query<NoParams>,
+) -> Result<HttpResponse, ApiError> {
+    let values = body.into_inner();
+    global
+        .flags
+        .set(values.clone())
+        .map_err(ApiError::BadRequest)?;
+    debug!("Feature flags changed: {values:?}");
+    Ok(HttpResponse::Ok().json(global.flags.values()))
+}
+
+/// Handles `GET /admin/moderation/queue`
+///
+/// Lists the items flagged by the content moderator (see
+/// [`ContentModerator`](crate::scheme::moderation::ContentModerator)), oldest first; only the
+/// latest ones are kept. Requires a valid [`AuthToken`].
+///
+/// # Response
+/// - `200 OK` with a JSON array of [`Flagged`](crate::scheme::moderation::Flagged) items
+#[get("/moderation/queue")]
+async fn get_moderation_queue(
+    _auth: AuthToken,
+    state: web::Data<AdminState>,
+    _query: Query<NoParams>,
+) -> HttpResponse {
+    HttpResponse::Ok().json(state.moderation.items())
+}
+
+/// Handles `GET /admin/breakers`
+///
+/// Returns the state of the circuit breakers guarding external storages (see [`Breaker`]); the
+/// list is empty if all storages are in memory. Requires a valid [`AuthToken`].
+///
+/// # Response
+/// - `200 OK` with a JSON array of [`BreakerStatus`](crate::scheme::breaker::BreakerStatus)
+#[get("/breakers")]
+async fn get_breakers(
+    _auth: AuthToken,
+    state: web::Data<AdminState>,
+    _query: Query<NoParams>,
+) -> HttpResponse {
+    let breakers: Vec<_> = state
+        .breakers
+        .iter()
+        .map(|breaker| breaker.status())
+        .collect();
+    HttpResponse::Ok().json(breakers)
+}
+
+/// Runtime the server runs on, shown at `GET /admin/runtime` so results of different builds can be
+/// told apart.
+#[derive(Debug, Serialize)]
+pub struct RuntimeInfo {
+    /// `tokio-uring` if the server was built with the `io-uring` feature on Linux, `tokio`
+    /// otherwise.
+    pub runtime: &'static str,
+
+    /// Number of server workers (see `RUST_SERVER_WORKERS`).
+    pub workers: usize,
+
+    /// Whether workers share the posts provider (see `RUST_SERVER_STATE_MODE`).
+    pub state_mode: String,
+
+    /// HTTP versions the server accepts: `http1`, plus `http2` with `RUST_SERVER_H2C` and `http3`
+    /// with `RUST_SERVER_H3_ADDR`.
+    pub transports: Vec<&'static str>,
+
+    /// Optional Cargo features the server was built with.
+    pub features: Vec<&'static str>,
+
+    /// Operating system, e.g. `linux`.
+    pub os: &'static str,
+
+    /// Kernel release, on Linux.
+    pub kernel: Option<String>,
+}
+
+impl RuntimeInfo {
+    fn current() -> Self {
+        let features = [
+            ("http3", cfg!(feature = "http3")),
+            ("io-uring", cfg!(feature = "io-uring")),
+            ("smtp", cfg!(feature = "smtp")),
+            ("ui", cfg!(feature = "ui")),
+        ];
+        #[cfg(feature = "http3")]
+        let http3 = vars::get_h3_addr().is_ok_and(|addr| addr.is_some());
+        #[cfg(not(feature = "http3"))]
+        let http3 = false;
+        let transports = [
+            ("http1", true),
+            ("http2", vars::get_h2c()),
+            ("http3", http3),
+        ];
+        Self {
+            runtime: if cfg!(all(target_os = "linux", feature = "io-uring")) {
+                "tokio-uring"
+            } else {
+                "tokio"
+            },
+            workers: vars::get_workers(),
+            state_mode: vars::get_state_mode(),
+            transports: transports
+                .into_iter()
+                .filter_map(|(name, enabled)| enabled.then_some(name))
+                .collect(),
+            features: features
+                .into_iter()
+                .filter_map(|(name, enabled)| enabled.then_some(name))
+                .collect(),
+            os: std::env::consts::OS,
+            // `/proc` is Linux only
+            kernel: std::fs::read_to_string("/proc/sys/kernel/osrelease")
+                .ok()
+                .map(|release| release.trim().to_owned()),
+        }
+    }
+}
+
+/// Handles `GET /admin/runtime`
+///
+/// Returns the runtime the server workers run on (see [`RuntimeInfo`]), so syscall-layer
+/// differences between builds show up next to their benchmark results. Requires a valid
+/// [`AuthToken`].
+///
+/// # Response
+/// - `200 OK` with a [`RuntimeInfo`]
+#[get("/runtime")]
+async fn get_runtime(_auth: AuthToken, _query: Query<NoParams>) -> HttpResponse {
+    HttpResponse::Ok().json(RuntimeInfo::current())
+}
+
+/// Routes of `/admin`, for [`methods::configure`].
+const ROUTES: &[Route] = &[
+    Route {
+        pattern: "/posts/export",
+        methods: &[Method::GET],
+    },
+    Route {
+        pattern: "/posts/import",
+        methods: &[Method::POST],
+    },
+    Route {
+        pattern: "/explain",
+        methods: &[Method::GET],
+    },
+    Route {
+        pattern: "/providers",
+        methods: &[Method::GET],
+    },
+    Route {
+        pattern: "/checksum",
+        methods: &[Method::GET],
+    },
+    Route {
+        pattern: "/changes",
+        methods: &[Method::GET],
+    },
+    Route {
+        pattern: "/replication",
+        methods: &[Method::GET],
+    },
+    Route {
+        pattern: "/acl",
+        methods: &[Method::GET, Method::PUT],
+    },
+    Route {
+        pattern: "/auth-failures",
+        methods: &[Method::GET, Method::DELETE],
+    },
+    Route {
+        pattern: "/users",
+        methods: &[Method::GET],
+    },
+    Route {
+        pattern: "/users/{id}/disable",
+        methods: &[Method::POST],
+    },
+    Route {
+        pattern: "/users/{id}/enable",
+        methods: &[Method::POST],
+    },
+    Route {
+        pattern: "/users/{id}/logout",
+        methods: &[Method::POST],
+    },
+    Route {
+        pattern: "/maintenance",
+        methods: &[Method::GET, Method::POST],
+    },
+    Route {
+        pattern: "/flags",
+        methods: &[Method::GET, Method::PUT],
+    },
+    Route {
+        pattern: "/moderation/queue",
+        methods: &[Method::GET],
+    },
+    Route {
+        pattern: "/breakers",
+        methods: &[Method::GET],
+    },
+    Route {
+        pattern: "/runtime",
+        methods: &[Method::GET],
+    },
+];
+
+/// Registers the `/admin` route handlers into the Actix-Web service configuration.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    // Datasets are much larger than the default payload limit
+    cfg.app_data(web::PayloadConfig::new(dataset::MAX_IMPORT_SIZE));
+    cfg.service(export_posts);
+    cfg.service(import_posts);
+    cfg.service(explain_query);
+    cfg.service(get_breakers);
+    cfg.service(get_providers);
+    cfg.service(get_checksum);
+    cfg.service(stream_changes);
+    cfg.service(get_replication);
+    cfg.service(get_acl);
+    cfg.service(put_acl);
+    cfg.service(get_auth_failures);
+    cfg.service(delete_auth_failures);
+    cfg.service(list_accounts);
+    cfg.service(disable_user);
+    cfg.service(enable_user);
+    cfg.service(logout_user);
+    cfg.service(get_maintenance);
+    cfg.service(post_maintenance);
+    cfg.service(get_flags);
+    cfg.service(put_flags);
+    cfg.service(get_moderation_queue);
+    cfg.service(get_runtime);
+    methods::configure(cfg, ROUTES);
+}
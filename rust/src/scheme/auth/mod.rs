@@ -1,7 +1,17 @@
-use actix_web::{Error, FromRequest, HttpRequest, dev::Payload, web};
+use actix_web::{FromRequest, HttpRequest, dev::Payload, web};
 use futures_util::future::{Ready, ready};
 
-use crate::state::GlobalServerState;
+use crate::{scheme::error::ApiError, state::GlobalServerState};
+
+pub mod routes;
+
+/// Returns the bearer token of the `Authorization` header of `req`, if any.
+pub fn bearer(req: &HttpRequest) -> Option<&str> {
+    req.headers()
+        .get("Authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.strip_prefix("Bearer "))
+}
 
 /// Represents an authorization token extracted from the `Authorization` header of an incoming HTTP request.
 ///
@@ -9,6 +19,8 @@ use crate::state::GlobalServerState;
 /// If a request contains a valid token in the header, an instance of `AuthToken` is created and injected
 /// into the handler. Otherwise, the request is rejected with a `401 Unauthorized` error.
 ///
+/// When the token is bound to a known user, the user's ID is available as [`AuthToken::subject`].
+///
 /// This extractor is compatible with Actix-Web's request guards.
 ///
 /// # Expected Header Format
@@ -19,11 +31,34 @@ use crate::state::GlobalServerState;
 /// # Failure Cases
 /// - If the `Authorization` header is missing or malformed
 /// - If the token is invalid or not recognized by the application state
+/// - If the token is bound to a user whose account is disabled
+///
+/// Failures are reported as [`ApiError::Unauthorized`], or [`ApiError::Forbidden`] for disabled
+/// users; a failing provider results in [`ApiError::Internal`].
 #[derive(Debug, Default)]
-pub struct AuthToken {}
+pub struct AuthToken {
+    /// ID of the user the token was issued to, if the token is bound to a user.
+    pub subject: Option<String>,
+}
+
+impl AuthToken {
+    /// Checks `token` against the users provider of `state`, as the extractor does.
+    pub fn check(state: &GlobalServerState, token: &str) -> Result<Self, ApiError> {
+        if !state.is_token_valid(token)? {
+            return Err(ApiError::Unauthorized("Invalid token".to_owned()));
+        }
+        let subject = state.token_subject(token)?;
+        if let Some(id) = &subject
+            && state.provider.is_disabled(id)?
+        {
+            return Err(ApiError::Forbidden(format!("user {id} is disabled")));
+        }
+        Ok(AuthToken { subject })
+    }
+}
 
 impl FromRequest for AuthToken {
-    type Error = Error;
+    type Error = ApiError;
     type Future = Ready<Result<Self, Self::Error>>;
 
     /// Extracts the `AuthToken` from an HTTP request if the bearer token is present and valid.
@@ -33,26 +68,16 @@ impl FromRequest for AuthToken {
     ///
     /// # Returns
     /// - `Ok(AuthToken)` if the header exists and the token is valid
-    /// - `Err(ErrorUnauthorized)` if the token is missing or invalid
+    /// - `Err(ApiError::Unauthorized)` if the token is missing or invalid
+    /// - `Err(ApiError::Forbidden)` if the user of the token is disabled
     fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
-        let auth_header = req
-            .headers()
-            .get("Authorization")
-            .and_then(|v| v.to_str().ok())
-            .and_then(|s| s.strip_prefix("Bearer "))
-            .map(str::to_string);
+        let auth_header = bearer(req).map(str::to_string);
 
         let auth_state = req.app_data::<web::Data<GlobalServerState>>().cloned();
 
-        match (auth_header, auth_state) {
-            (Some(token), Some(state)) => {
-                if state.is_token_valid(token) {
-                    ready(Ok(AuthToken::default()))
-                } else {
-                    ready(Err(actix_web::error::ErrorUnauthorized("Invalid token")))
-                }
-            }
-            _ => ready(Err(actix_web::error::ErrorUnauthorized("Unauthorized"))),
-        }
+        ready(match (auth_header, auth_state) {
+            (Some(token), Some(state)) => AuthToken::check(&state, &token),
+            _ => Err(ApiError::Unauthorized("Unauthorized".to_owned())),
+        })
     }
 }
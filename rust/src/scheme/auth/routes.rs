@@ -0,0 +1,72 @@
+use actix_web::{HttpRequest, HttpResponse, http::Method, post, web};
+use serde::Deserialize;
+
+use crate::{
+    scheme::{
+        auth::AuthToken,
+        error::ApiError,
+        methods::{self, Route},
+        params::{NoParams, Query},
+    },
+    state::GlobalServerState,
+};
+use percom_model::auth::{BEARER, Introspection};
+
+/// Form body of `POST /auth/introspect`.
+#[derive(Debug, Deserialize)]
+struct IntrospectionRequest {
+    /// The token to describe.
+    token: String,
+}
+
+/// Handles `POST /auth/introspect`
+///
+/// Describes the `token` of the form body (see [`Introspection`]), loosely after RFC 7662, so
+/// clients can check a token without guessing from the status of another endpoint. As RFC 7662
+/// asks, the caller has to be authorized itself, with a valid [`AuthToken`] of its own.
+///
+/// A revoked token, or the token of a disabled user, is `{"active": false}`; as probing tokens
+/// here is no different from trying them out, it counts as a failed authentication attempt of
+/// the caller (see [`AuthFailures::reject`](crate::middleware::auth_failures::AuthFailures::reject)).
+/// An active token has the user it's bound to as `sub`. Tokens don't expire, so `exp` is never
+/// set, and there are no scopes.
+///
+/// # Response
+/// - `200 OK` with an [`Introspection`]
+/// - `401 Unauthorized` if the caller has no valid token
+#[post("/introspect")]
+async fn introspect(
+    req: HttpRequest,
+    _auth: AuthToken,
+    global: web::Data<GlobalServerState>,
+    form: web::Form<IntrospectionRequest>,
+    _query: Query<NoParams>,
+) -> Result<HttpResponse, ApiError> {
+    let token = form.into_inner().token;
+    let sub = match AuthToken::check(&global, &token) {
+        Ok(auth) => auth.subject,
+        Err(ApiError::Unauthorized(_) | ApiError::Forbidden(_)) => {
+            global.auth_failures.reject(&req, &token);
+            return Ok(HttpResponse::Ok().json(Introspection::default()));
+        }
+        Err(err) => return Err(err),
+    };
+    Ok(HttpResponse::Ok().json(Introspection {
+        active: true,
+        sub,
+        exp: None,
+        token_type: Some(BEARER.to_owned()),
+    }))
+}
+
+/// Routes of `/auth`, for [`methods::configure`].
+const ROUTES: &[Route] = &[Route {
+    pattern: "/introspect",
+    methods: &[Method::POST],
+}];
+
+/// Registers the `/auth` route handlers into the Actix-Web service configuration.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(introspect);
+    methods::configure(cfg, ROUTES);
+}
@@ -0,0 +1,211 @@
+use serde::Serialize;
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tracing::{debug, info, warn};
+
+use crate::scheme::provider::{ProviderError, lock};
+
+/// State of a [`Breaker`].
+#[derive(Debug, Clone, Copy)]
+enum State {
+    /// Calls pass through; counts consecutive failures.
+    Closed { failures: u32 },
+
+    /// Calls are rejected until `until`.
+    Open { until: Instant },
+
+    /// A single probe call is in flight; other calls are rejected until it completes.
+    HalfOpen,
+}
+
+/// Circuit breaker protecting a storage backend which keeps failing.
+///
+/// After `threshold` consecutive failures, the breaker opens and rejects calls right away with
+/// [`ProviderError::Unavailable`] instead of sending them to the failing backend. Once `cooldown`
+/// has passed, a single call is let through as a probe: if it succeeds, the breaker closes again;
+/// otherwise it stays open for another `cooldown`.
+///
+/// Only [`ProviderError::Internal`] counts as a failure; conflicts are caused by the request, not
+/// by the backend.
+///
+/// # Example
+/// ```ignore
+/// let breaker = Breaker::new("posts", 5, Duration::from_secs(5));
+/// let post = breaker.call(|| provider.get(id))?;
+/// ```
+pub struct Breaker {
+    /// Name shown at `/admin/breakers`, e.g. the guarded resource.
+    name: &'static str,
+
+    /// Number of consecutive failures which opens the breaker.
+    threshold: u32,
+
+    /// How long the breaker stays open before it lets a probe through.
+    cooldown: Duration,
+
+    inner: Mutex<Inner>,
+}
+
+/// Mutable part of a [`Breaker`].
+struct Inner {
+    state: State,
+
+    /// Number of times the breaker opened.
+    opened: u64,
+
+    /// Number of rejected calls.
+    rejected: u64,
+}
+
+/// Outcome of a call let through a [`Breaker`], recorded when it's dropped: when the call
+/// returns, or when it panics, as a failure.
+struct Outcome<'a> {
+    breaker: &'a Breaker,
+    failed: bool,
+}
+
+impl Drop for Outcome<'_> {
+    fn drop(&mut self) {
+        self.breaker.record(self.failed);
+    }
+}
+
+/// Point-in-time state of a [`Breaker`], as returned by `GET /admin/breakers`.
+#[derive(Debug, Serialize)]
+pub struct BreakerStatus {
+    pub name: &'static str,
+
+    /// `closed`, `open` or `half_open`.
+    pub state: &'static str,
+
+    /// Consecutive failures so far (closed breakers only).
+    pub failures: u32,
+
+    pub threshold: u32,
+
+    pub cooldown_ms: u64,
+
+    /// Time left until the next probe (open breakers only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub probe_in_ms: Option<u64>,
+
+    /// Number of times the breaker opened since startup.
+    pub opened: u64,
+
+    /// Number of calls rejected since startup.
+    pub rejected: u64,
+}
+
+impl Breaker {
+    /// Creates a closed breaker. A `threshold` of `0` is treated as `1`.
+    pub fn new(name: &'static str, threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            name,
+            threshold: threshold.max(1),
+            cooldown,
+            inner: Mutex::new(Inner {
+                state: State::Closed { failures: 0 },
+                opened: 0,
+                rejected: 0,
+            }),
+        }
+    }
+
+    /// Runs `call` unless the breaker is open, and records its outcome; a panic of `call` is
+    /// recorded as a failure before it's passed on.
+    ///
+    /// # Errors
+    /// Returns [`ProviderError::Unavailable`] without running `call` while the breaker is open or a
+    /// probe is in flight; otherwise the error of `call`, if any.
+    pub fn call<T>(
+        &self,
+        call: impl FnOnce() -> Result<T, ProviderError>,
+    ) -> Result<T, ProviderError> {
+        {
+            let mut inner = lock(&self.inner);
+            match inner.state {
+                State::Closed { .. } => {}
+                State::Open { until } if Instant::now() >= until => {
+                    debug!("Circuit breaker {} is half-open, probing", self.name);
+                    inner.state = State::HalfOpen;
+                }
+                State::Open { until } => {
+                    inner.rejected += 1;
+                    return Err(ProviderError::Unavailable {
+                        retry_after: until.saturating_duration_since(Instant::now()),
+                    });
+                }
+                State::HalfOpen => {
+                    inner.rejected += 1;
+                    return Err(ProviderError::Unavailable {
+                        retry_after: Duration::ZERO,
+                    });
+                }
+            }
+        }
+        // The lock isn't held during the call, so calls still run concurrently. A call which
+        // panics is recorded as a failure, so a panicking probe doesn't leave the breaker
+        // half-open, rejecting every call, forever
+        let mut outcome = Outcome {
+            breaker: self,
+            failed: true,
+        };
+        let result = call();
+        outcome.failed = matches!(result, Err(ProviderError::Internal(_)));
+        result
+    }
+
+    /// Records the outcome of a call let through.
+    fn record(&self, failed: bool) {
+        let mut inner = lock(&self.inner);
+        inner.state = match (inner.state, failed) {
+            (State::HalfOpen, false) => {
+                info!("Circuit breaker {} closed", self.name);
+                State::Closed { failures: 0 }
+            }
+            (State::Closed { .. }, false) => State::Closed { failures: 0 },
+            (State::Closed { failures }, true) if failures + 1 < self.threshold => State::Closed {
+                failures: failures + 1,
+            },
+            (State::Closed { .. } | State::HalfOpen, true) => {
+                warn!(
+                    "Circuit breaker {} opened for {:?}",
+                    self.name, self.cooldown
+                );
+                inner.opened += 1;
+                State::Open {
+                    until: Instant::now() + self.cooldown,
+                }
+            }
+            // Another call completed the probe in the meantime, or the breaker opened while this
+            // call was running: the state is already up to date
+            (state @ State::Open { .. }, _) => state,
+        };
+    }
+
+    /// Returns the current state of the breaker.
+    pub fn status(&self) -> BreakerStatus {
+        let inner = lock(&self.inner);
+        let (state, failures, probe_in) = match inner.state {
+            State::Closed { failures } => ("closed", failures, None),
+            State::Open { until } => (
+                "open",
+                0,
+                Some(until.saturating_duration_since(Instant::now())),
+            ),
+            State::HalfOpen => ("half_open", 0, None),
+        };
+        BreakerStatus {
+            name: self.name,
+            state,
+            failures,
+            threshold: self.threshold,
+            cooldown_ms: self.cooldown.as_millis() as u64,
+            probe_in_ms: probe_in.map(|left| left.as_millis() as u64),
+            opened: inner.opened,
+            rejected: inner.rejected,
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use actix_web::{FromRequest, HttpRequest, dev::Payload, web};
+use futures_util::future::{Ready, ready};
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+use tokio::time::{Instant, timeout_at};
+use tracing::{debug, warn};
+
+use crate::{
+    connection::Peer,
+    scheme::{error::ApiError, provider::ProviderError},
+    state::{GlobalServerState, Metrics},
+};
+
+/// Deadline of the provider calls made while handling a request.
+///
+/// The deadline starts when the request is extracted and is shared by all calls made with
+/// [`Deadline::run`]. It's enabled with `RUST_SERVER_PROVIDER_DEADLINE_MS`; without it, provider
+/// calls run inline on the worker thread, as they are synchronous and fast for in-memory storage.
+///
+/// With a deadline, each call runs on the blocking pool and the handler stops waiting for it:
+/// - when the deadline passes, answering `504 Gateway Timeout` (`provider.timeouts` metric);
+/// - when the client disconnects (`provider.cancelled` metric), noticed on the connection (see
+///   [`Peer`]) or by actix dropping the handler.
+///
+/// An abandoned call which hasn't started yet (e.g. still queued for the blocking pool) is skipped.
+/// Providers are synchronous, so a call which has already started runs to completion; its result
+/// is discarded.
+///
+/// # Example
+/// ```ignore
+/// let provider = state.provider.clone();
+/// let post = deadline.run(move || provider.get(&id)).await?;
+/// ```
+pub struct Deadline {
+    /// Point in time after which calls are abandoned; `None` runs calls inline.
+    at: Option<Instant>,
+
+    /// Client connection, watched for disconnects while a call is pending.
+    peer: Option<Peer>,
+
+    metrics: Arc<Metrics>,
+}
+
+impl Deadline {
+    /// Runs a provider call within the deadline.
+    ///
+    /// # Errors
+    /// - the error of the call itself, converted into [`ApiError`];
+    /// - [`ApiError::GatewayTimeout`] if the deadline passed or the client disconnected before the
+    ///   call completed;
+    /// - [`ApiError::Internal`] if the call panicked on the blocking pool.
+    pub async fn run<T, F>(&self, call: F) -> Result<T, ApiError>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> Result<T, ProviderError> + Send + 'static,
+    {
+        let Some(at) = self.at else {
+            return Ok(call()?);
+        };
+        let mut guard = Abandon {
+            cancelled: Arc::new(AtomicBool::new(false)),
+            metrics: Some(self.metrics.clone()),
+        };
+        let cancelled = guard.cancelled.clone();
+        let blocking = web::block(move || {
+            if cancelled.load(Ordering::Relaxed) {
+                return None;
+            }
+            Some(call())
+        });
+        let gone = async {
+            match &self.peer {
+                Some(peer) => peer.gone().await,
+                None => std::future::pending().await,
+            }
+        };
+        let result = tokio::select! {
+            result = timeout_at(at, blocking) => result,
+            () = gone => {
+                debug!("Client disconnected, abandoning provider call");
+                drop(guard);
+                // Nobody is left to receive the response
+                return Err(ApiError::GatewayTimeout);
+            }
+        };
+        match result {
+            Ok(result) => {
+                guard.metrics = None;
+                match result {
+                    Ok(Some(result)) => Ok(result?),
+                    // Only a dropped guard cancels the call, and the guard is still alive
+                    Ok(None) => Err(ApiError::Internal("provider call was cancelled".to_owned())),
+                    Err(err) => Err(ApiError::Internal(format!("provider call failed: {err}"))),
+                }
+            }
+            Err(_) => {
+                warn!("Provider call exceeded the request deadline");
+                guard.cancelled.store(true, Ordering::Relaxed);
+                if let Some(metrics) = guard.metrics.take() {
+                    Metrics::inc(&metrics.provider_timeouts);
+                }
+                Err(ApiError::GatewayTimeout)
+            }
+        }
+    }
+}
+
+/// Cancels a pending provider call if the handler is dropped while waiting for it.
+struct Abandon {
+    /// Flag checked by the call right before it starts.
+    cancelled: Arc<AtomicBool>,
+
+    /// Set while the call is pending; taken once the handler stops waiting on its own.
+    metrics: Option<Arc<Metrics>>,
+}
+
+impl Drop for Abandon {
+    fn drop(&mut self) {
+        if let Some(metrics) = self.metrics.take() {
+            self.cancelled.store(true, Ordering::Relaxed);
+            Metrics::inc(&metrics.provider_cancelled);
+        }
+    }
+}
+
+impl FromRequest for Deadline {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    /// Starts the deadline configured in [`GlobalServerState`], which must be registered as
+    /// application data.
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(match req.app_data::<web::Data<GlobalServerState>>() {
+            Some(state) => Ok(Self {
+                at: state
+                    .provider_deadline
+                    .map(|deadline: Duration| Instant::now() + deadline),
+                peer: Peer::of(req),
+                metrics: state.metrics.clone(),
+            }),
+            None => Err(ApiError::Internal(
+                "global server state is not registered".to_owned(),
+            )),
+        })
+    }
+}
@@ -0,0 +1,209 @@
+use actix_web::{
+    HttpRequest, HttpResponse, ResponseError,
+    error::JsonPayloadError,
+    http::{StatusCode, header},
+};
+use serde::Serialize;
+use std::time::Duration;
+use tracing::error;
+
+use crate::scheme::provider::ProviderError;
+
+/// Media type of error bodies, as defined by RFC 9457 (Problem Details for HTTP APIs).
+pub const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+
+/// Error returned by request handlers and extractors.
+///
+/// Every variant maps to an HTTP status; the response body is a problem details document
+/// ([`Problem`]). Handlers return `Result<HttpResponse, ApiError>` and use `?` on provider calls,
+/// as [`ProviderError`] converts into `ApiError`.
+#[derive(Debug)]
+pub enum ApiError {
+    /// `400 Bad Request`, with a description of what's wrong with the request.
+    BadRequest(String),
+
+    /// `401 Unauthorized`, with a description of why the request isn't authenticated.
+    Unauthorized(String),
+
+    /// `403 Forbidden`, with a description of why the request isn't allowed.
+    Forbidden(String),
+
+    /// `404 Not Found`.
+    NotFound,
+
+    /// `405 Method Not Allowed`, with a description of why the method isn't allowed.
+    MethodNotAllowed(String),
+
+    /// `408 Request Timeout`: the client stopped sending the request, with a description of how.
+    RequestTimeout(String),
+
+    /// `409 Conflict`, with a description of the conflict.
+    Conflict(String),
+
+    /// `415 Unsupported Media Type`.
+    UnsupportedMediaType,
+
+    /// `422 Unprocessable Entity`: the request is well-formed, but its content isn't accepted,
+    /// with a description of why.
+    UnprocessableEntity(String),
+
+    /// `431 Request Header Fields Too Large`, with a description of the size of the headers.
+    HeaderFieldsTooLarge(String),
+
+    /// `429 Too Many Requests`: the client is locked out for now. `Retry-After` tells it when to
+    /// try again, in whole seconds.
+    TooManyRequests { retry_after: Duration },
+
+    /// `503 Service Unavailable`: the storage is failing and requests aren't sent to it for now.
+    /// `Retry-After` tells clients when to try again, in whole seconds.
+    ServiceUnavailable { retry_after: Duration },
+
+    /// `504 Gateway Timeout`: the storage didn't answer within the request deadline.
+    GatewayTimeout,
+
+    /// `500 Internal Server Error`. The description is logged, but never sent to the client.
+    Internal(String),
+}
+
+/// Body of error responses (RFC 9457).
+#[derive(Debug, Serialize)]
+pub struct Problem {
+    /// URI identifying the problem type; `about:blank` means the status code says it all.
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+
+    /// Stable, machine-readable error code (see [`ApiError::code`]). Unlike `title` and `detail`,
+    /// it's never localized.
+    pub code: &'static str,
+
+    /// Short summary of the problem type (the reason phrase of the status code), in the language
+    /// of the client (see [`localize_errors`](crate::middleware::localize::localize_errors)).
+    pub title: &'static str,
+
+    /// HTTP status code.
+    pub status: u16,
+
+    /// Explanation specific to this occurrence of the problem.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl ApiError {
+    /// Returns the machine-readable code of the variant, e.g. `not_found`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::BadRequest(_) => "bad_request",
+            Self::Unauthorized(_) => "unauthorized",
+            Self::Forbidden(_) => "forbidden",
+            Self::NotFound => "not_found",
+            Self::MethodNotAllowed(_) => "method_not_allowed",
+            Self::RequestTimeout(_) => "request_timeout",
+            Self::Conflict(_) => "conflict",
+            Self::UnsupportedMediaType => "unsupported_media_type",
+            Self::UnprocessableEntity(_) => "unprocessable_entity",
+            Self::HeaderFieldsTooLarge(_) => "request_header_fields_too_large",
+            Self::TooManyRequests { .. } => "too_many_requests",
+            Self::ServiceUnavailable { .. } => "service_unavailable",
+            Self::GatewayTimeout => "gateway_timeout",
+            Self::Internal(_) => "internal",
+        }
+    }
+
+    /// Returns the client-facing description, if the variant has one.
+    fn detail(&self) -> Option<String> {
+        match self {
+            Self::BadRequest(msg)
+            | Self::Unauthorized(msg)
+            | Self::Forbidden(msg)
+            | Self::MethodNotAllowed(msg)
+            | Self::RequestTimeout(msg)
+            | Self::Conflict(msg)
+            | Self::UnprocessableEntity(msg)
+            | Self::HeaderFieldsTooLarge(msg) => Some(msg.clone()),
+            Self::NotFound
+            | Self::UnsupportedMediaType
+            | Self::TooManyRequests { .. }
+            | Self::ServiceUnavailable { .. }
+            | Self::GatewayTimeout
+            | Self::Internal(_) => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Internal(msg) => write!(f, "internal error: {msg}"),
+            other => match other.detail() {
+                Some(detail) => write!(f, "{}: {detail}", other.status_code()),
+                None => write!(f, "{}", other.status_code()),
+            },
+        }
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::BadRequest(_) => StatusCode::BAD_REQUEST,
+            Self::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            Self::Forbidden(_) => StatusCode::FORBIDDEN,
+            Self::NotFound => StatusCode::NOT_FOUND,
+            Self::MethodNotAllowed(_) => StatusCode::METHOD_NOT_ALLOWED,
+            Self::RequestTimeout(_) => StatusCode::REQUEST_TIMEOUT,
+            Self::Conflict(_) => StatusCode::CONFLICT,
+            Self::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Self::UnprocessableEntity(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::HeaderFieldsTooLarge(_) => StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+            Self::TooManyRequests { .. } => StatusCode::TOO_MANY_REQUESTS,
+            Self::ServiceUnavailable { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            Self::GatewayTimeout => StatusCode::GATEWAY_TIMEOUT,
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        if let Self::Internal(msg) = self {
+            error!("Internal error: {msg}");
+        }
+        let status = self.status_code();
+        let mut response = HttpResponse::build(status);
+        if let Self::TooManyRequests { retry_after } | Self::ServiceUnavailable { retry_after } =
+            self
+        {
+            // Rounded up, so clients don't come back before the breaker lets a probe through, or
+            // before their lockout ends
+            let secs = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+            response.insert_header((header::RETRY_AFTER, secs.max(1)));
+        }
+        response.content_type(PROBLEM_CONTENT_TYPE).json(Problem {
+            kind: "about:blank",
+            code: self.code(),
+            title: status.canonical_reason().unwrap_or_default(),
+            status: status.as_u16(),
+            detail: self.detail(),
+        })
+    }
+}
+
+/// Error handler of JSON bodies (see `web::JsonConfig`): bodies which don't deserialize, e.g.
+/// with a date which isn't RFC 3339, are answered like any other [`ApiError::BadRequest`]. Other
+/// errors, e.g. of too large bodies, keep their responses.
+pub fn json_error(err: JsonPayloadError, _: &HttpRequest) -> actix_web::Error {
+    match err {
+        JsonPayloadError::Deserialize(err) => {
+            ApiError::BadRequest(format!("Invalid JSON body: {err}")).into()
+        }
+        err => err.into(),
+    }
+}
+
+impl From<ProviderError> for ApiError {
+    fn from(err: ProviderError) -> Self {
+        match err {
+            ProviderError::Conflict(msg) => Self::Conflict(msg),
+            ProviderError::Internal(msg) => Self::Internal(msg),
+            ProviderError::Unavailable { retry_after } => Self::ServiceUnavailable { retry_after },
+        }
+    }
+}
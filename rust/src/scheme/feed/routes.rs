@@ -0,0 +1,82 @@
+use actix_web::{HttpRequest, HttpResponse, get, http::Method, web};
+use std::sync::Arc;
+use tracing::debug;
+
+use crate::{
+    scheme::{
+        auth::AuthToken,
+        error::ApiError,
+        flags,
+        methods::{self, Route},
+        pagination::{self, PageQuery},
+        params::Query,
+        posts::PostsProvider,
+        users::UsersProvider,
+    },
+    state::GlobalServerState,
+};
+
+/// Shared application state for the `/feed` route group.
+///
+/// The feed combines two resource families: the users provider knows which authors a user follows,
+/// and the posts provider fans out over those authors to collect their posts.
+#[derive(Clone)]
+pub struct FeedState {
+    /// Provider used to resolve the list of followed authors.
+    pub users: Arc<dyn UsersProvider>,
+
+    /// Provider used to collect posts of the followed authors.
+    pub posts: Arc<dyn PostsProvider>,
+}
+
+impl FeedState {
+    /// Constructs a new [`FeedState`] from the users and posts providers.
+    pub fn new(users: Arc<dyn UsersProvider>, posts: Arc<dyn PostsProvider>) -> Self {
+        Self { users, posts }
+    }
+}
+
+/// Handles `GET /feed`
+///
+/// Returns posts written by the authors the authenticated user follows, newest first.
+/// Requires a valid [`AuthToken`] bound to an existing user, and the [`flags::FEED`] feature flag.
+///
+/// # Query Parameters
+/// - `page`: 1-based page number (default `1`)
+/// - `per_page`: page size (default `20`, max `100`)
+///
+/// # Response
+/// - `200 OK` with a [`Page`] of posts, and its metadata in headers too (see
+///   [`pagination::respond`])
+/// - `401 Unauthorized` if the token isn't bound to a user
+/// - `404 Not Found` if the feature flag is off
+#[get("")]
+async fn get_feed(
+    req: HttpRequest,
+    auth: AuthToken,
+    global: web::Data<GlobalServerState>,
+    state: web::Data<FeedState>,
+    query: Query<PageQuery>,
+) -> Result<HttpResponse, ApiError> {
+    global.flags.require(flags::FEED)?;
+    let unauthorized = || ApiError::Unauthorized("token isn't bound to a user".to_owned());
+    let user = auth.subject.ok_or_else(unauthorized)?;
+    debug!("Request: get feed of {user}");
+    let authors = state.users.following(&user)?.ok_or_else(unauthorized)?;
+    let (posts, total) = state
+        .posts
+        .get_by_authors(&authors, query.offset(), query.per_page())?;
+    Ok(pagination::respond(&req, &query.page_of(posts, total)))
+}
+
+/// Routes of `/feed`, for [`methods::configure`].
+const ROUTES: &[Route] = &[Route {
+    pattern: "",
+    methods: &[Method::GET],
+}];
+
+/// Registers all `/feed` route handlers into the Actix-Web service configuration.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_feed);
+    methods::configure(cfg, ROUTES);
+}
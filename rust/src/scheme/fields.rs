@@ -0,0 +1,85 @@
+//! Sparse fieldsets: `?fields=id,author,date` on the `GET` endpoints of posts and users, so
+//! list-heavy benchmarks don't pay for fields they don't read.
+//!
+//! The requested fields are picked from each item as it's serialized (see [`Projected`]); the
+//! stored items are untouched. Only JSON responses are projected: protobuf messages always have
+//! every field.
+
+use serde::{Deserialize, Serialize, Serializer, ser::Error};
+use serde_json::Value;
+
+use crate::scheme::{error::ApiError, params::Params};
+
+/// Fields requested with the `fields` query parameter; every field if there's none.
+#[derive(Debug, Clone, Default)]
+pub struct Fields(Option<Vec<String>>);
+
+impl Fields {
+    /// Parses the comma-separated value of `fields`, failing on names not in `known`. An empty
+    /// value requests every field, like no value at all.
+    pub fn parse(value: Option<&str>, known: &[&str]) -> Result<Self, ApiError> {
+        let fields: Vec<String> = value
+            .into_iter()
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_owned)
+            .collect();
+        if let Some(name) = fields.iter().find(|name| !known.contains(&name.as_str())) {
+            return Err(ApiError::BadRequest(format!(
+                "unknown field {name:?}: expected {}",
+                known.join(", ")
+            )));
+        }
+        Ok(Self((!fields.is_empty()).then_some(fields)))
+    }
+
+    /// Returns `true` if every field is requested, so items are serialized as they are.
+    pub fn is_all(&self) -> bool {
+        self.0.is_none()
+    }
+
+    /// Returns `item`, serializing only the requested fields.
+    pub fn of<'a, T>(&'a self, item: &'a T) -> Projected<'a, T> {
+        Projected { item, fields: self }
+    }
+
+    /// Returns `items`, serializing only the requested fields of each.
+    pub fn of_all<'a, T>(&'a self, items: &'a [T]) -> Vec<Projected<'a, T>> {
+        items.iter().map(|item| self.of(item)).collect()
+    }
+}
+
+/// Item serialized with only the requested [`Fields`]; fields which aren't set, e.g. the
+/// `publish_at` of a published post, stay missing.
+#[derive(Debug)]
+pub struct Projected<'a, T> {
+    item: &'a T,
+    fields: &'a Fields,
+}
+
+impl<T: Serialize> Serialize for Projected<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let Some(fields) = &self.fields.0 else {
+            return self.item.serialize(serializer);
+        };
+        match serde_json::to_value(self.item).map_err(S::Error::custom)? {
+            Value::Object(mut object) => {
+                object.retain(|name, _| fields.contains(name));
+                object.serialize(serializer)
+            }
+            value => value.serialize(serializer),
+        }
+    }
+}
+
+/// Query parameters of the endpoints taking only `fields`.
+#[derive(Debug, Deserialize)]
+pub struct FieldsQuery {
+    /// Comma-separated fields to return, see [`Fields`].
+    pub fields: Option<String>,
+}
+
+impl Params for FieldsQuery {
+    const NAMES: &'static [&'static str] = &["fields"];
+}
@@ -0,0 +1,201 @@
+use actix_web::HttpRequest;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, sync::RwLock};
+
+use crate::scheme::error::ApiError;
+
+/// Flag of user lookups by email or nickname (`GET /users?email=...&nickname=...`).
+pub const SEARCH: &str = "enable_search";
+
+/// Flag of the feed of followed authors (`GET /feed`).
+pub const FEED: &str = "enable_feed";
+
+/// Variant of [`LIST_POSTS`] building the whole body in memory before sending it.
+pub const LIST_CLONE: &str = "clone";
+
+/// Variant of [`LIST_POSTS`] encoding the body chunk by chunk while it's sent.
+pub const LIST_STREAMING: &str = "streaming";
+
+/// Experiment of `GET /posts` (JSON responses only).
+pub const LIST_POSTS: Experiment = Experiment {
+    name: "list_posts",
+    variants: [LIST_CLONE, LIST_STREAMING],
+};
+
+/// Variant of [`GET_POST`] decoding the stored post and encoding it again.
+pub const GET_DECODE: &str = "decode";
+
+/// Variant of [`GET_POST`] sending the stored JSON as it is, where the provider keeps it (see
+/// [`PostsProvider::get_json`](crate::scheme::posts::PostsProvider::get_json)).
+pub const GET_RAW: &str = "raw";
+
+/// Experiment of `GET /posts/{id}` (JSON responses at UTC offsets as stored only).
+pub const GET_POST: Experiment = Experiment {
+    name: "get_post",
+    variants: [GET_DECODE, GET_RAW],
+};
+
+/// Header choosing the variant of an experiment for a request, e.g. `X-Variant: streaming`.
+/// Responses of experiments carry it too, naming the variant which served them.
+pub const VARIANT_HEADER: &str = "X-Variant";
+
+/// Known flags with their defaults; endpoints which are there by default stay on, and experiments
+/// send every request to their first variant.
+const DEFAULTS: [(&str, FlagValue); 4] = [
+    (SEARCH, FlagValue::Switch(true)),
+    (FEED, FlagValue::Switch(true)),
+    (LIST_POSTS.name, FlagValue::Split(0)),
+    (GET_POST.name, FlagValue::Split(0)),
+];
+
+/// Two implementations of the same endpoint, compared in one process (see
+/// [`FeatureFlags::variant`]).
+#[derive(Debug, Clone, Copy)]
+pub struct Experiment {
+    /// Name of the flag of the experiment.
+    pub name: &'static str,
+
+    /// The established variant and the one being tried.
+    pub variants: [&'static str; 2],
+}
+
+/// Value of a feature flag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FlagValue {
+    /// Whether an endpoint is enabled.
+    Switch(bool),
+
+    /// Share of requests, in percent, sent to the second variant of an experiment.
+    Split(u8),
+}
+
+/// Feature flags toggling experimental endpoints and splitting requests between the variants of
+/// experiments, per environment or per benchmark run.
+///
+/// Flags are configured with `RUST_SERVER_FEATURE_FLAGS` (see [`FeatureFlags::from_spec`]) and can
+/// be changed at runtime with `PUT /admin/flags`. Handlers check their flag with
+/// [`FeatureFlags::require`]; a disabled endpoint answers `404`, as if it didn't exist.
+#[derive(Debug)]
+pub struct FeatureFlags {
+    flags: RwLock<BTreeMap<&'static str, FlagValue>>,
+}
+
+impl Default for FeatureFlags {
+    fn default() -> Self {
+        Self {
+            flags: RwLock::new(DEFAULTS.into_iter().collect()),
+        }
+    }
+}
+
+impl FeatureFlags {
+    /// Parses flags from a comma-separated list of `<flag>=<on|off>` and `<experiment>=<percent>%`,
+    /// e.g. `enable_search=off,list_posts=25%`; flags which aren't listed keep their defaults.
+    ///
+    /// # Errors
+    /// Returns a description of the first unknown flag or malformed value.
+    pub fn from_spec(spec: &str) -> Result<Self, String> {
+        let flags = Self::default();
+        let values = spec
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let (name, value) = entry
+                    .split_once('=')
+                    .ok_or_else(|| format!("{entry:?} isn't <flag>=<value>"))?;
+                let value = match value.trim() {
+                    "on" | "true" | "1" => FlagValue::Switch(true),
+                    "off" | "false" | "0" => FlagValue::Switch(false),
+                    value => value
+                        .strip_suffix('%')
+                        .and_then(|percent| percent.parse().ok())
+                        .map(FlagValue::Split)
+                        .ok_or_else(|| format!("{value:?} isn't on, off or a percentage"))?,
+                };
+                Ok((name.trim().to_owned(), value))
+            })
+            .collect::<Result<BTreeMap<_, _>, String>>()?;
+        flags.set(values)?;
+        Ok(flags)
+    }
+
+    /// Returns every flag with its current value.
+    pub fn values(&self) -> BTreeMap<&'static str, FlagValue> {
+        self.flags
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone()
+    }
+
+    /// Sets the given flags, leaving the others as they are. Nothing is changed if a flag is
+    /// unknown or gets a value of the wrong kind.
+    ///
+    /// # Errors
+    /// Returns a description of the first unknown flag or wrong value.
+    pub fn set(&self, values: BTreeMap<String, FlagValue>) -> Result<(), String> {
+        let mut flags = self.flags.write().unwrap_or_else(|err| err.into_inner());
+        for (name, value) in values.iter() {
+            match (flags.get(name.as_str()), value) {
+                (None, _) => return Err(format!("unknown feature flag {name}")),
+                (Some(FlagValue::Switch(_)), FlagValue::Switch(_)) => {}
+                (Some(FlagValue::Split(_)), FlagValue::Split(percent)) if *percent <= 100 => {}
+                (Some(FlagValue::Switch(_)), _) => return Err(format!("{name} is on or off")),
+                (Some(FlagValue::Split(_)), _) => {
+                    return Err(format!("{name} is a percentage from 0 to 100"));
+                }
+            }
+        }
+        for (name, value) in values {
+            if let Some(flag) = flags.get_mut(name.as_str()) {
+                *flag = value;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if `flag` is on; unknown flags are off.
+    pub fn is_enabled(&self, flag: &str) -> bool {
+        let flags = self.flags.read().unwrap_or_else(|err| err.into_inner());
+        flags.get(flag) == Some(&FlagValue::Switch(true))
+    }
+
+    /// Fails with [`ApiError::NotFound`] unless `flag` is on, for handlers of flagged endpoints.
+    pub fn require(&self, flag: &str) -> Result<(), ApiError> {
+        if self.is_enabled(flag) {
+            Ok(())
+        } else {
+            Err(ApiError::NotFound)
+        }
+    }
+
+    /// Picks the variant of `experiment` serving `req`: the one named by its [`VARIANT_HEADER`]
+    /// if there is one, otherwise the second variant for the configured share of requests, chosen
+    /// at random, and the first one for the rest.
+    pub fn variant(&self, experiment: &Experiment, req: &HttpRequest) -> &'static str {
+        let requested = req
+            .headers()
+            .get(VARIANT_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|name| {
+                experiment
+                    .variants
+                    .into_iter()
+                    .find(|variant| *variant == name)
+            });
+        if let Some(variant) = requested {
+            return variant;
+        }
+        let split = match self.values().get(experiment.name) {
+            Some(FlagValue::Split(percent)) => *percent,
+            _ => 0,
+        };
+        let [established, tried] = experiment.variants;
+        if split > 0 && rand::random_range(0..100) < split {
+            tried
+        } else {
+            established
+        }
+    }
+}
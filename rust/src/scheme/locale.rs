@@ -0,0 +1,163 @@
+//! Localized messages of error responses.
+//!
+//! Messages live in catalogs embedded from `locales/<language>.ftl`, one `key = message` per line
+//! (a subset of the Fluent syntax), where `{ $name }` stands for a variable part. English, the
+//! language the server writes its messages in, is the reference: a detail is translated by finding
+//! the English message it matches, then filling the variable parts into the message of the same
+//! key in the client's language. Details no catalog knows, e.g. messages of libraries, stay in
+//! English.
+
+use std::{collections::HashMap, sync::OnceLock};
+
+/// Language of the messages written by the server.
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+/// Sources of the catalogs, by language; the first one is [`DEFAULT_LANGUAGE`].
+const SOURCES: [(&str, &str); 2] = [
+    ("en", include_str!("../../locales/en.ftl")),
+    ("de", include_str!("../../locales/de.ftl")),
+];
+
+/// Part of a message.
+#[derive(Debug, PartialEq, Eq)]
+enum Segment {
+    Text(&'static str),
+    Variable(&'static str),
+}
+
+/// Messages of a language, by key.
+type Catalog = HashMap<&'static str, Vec<Segment>>;
+
+/// Returns the parsed catalogs, by language.
+fn catalogs() -> &'static HashMap<&'static str, Catalog> {
+    static CATALOGS: OnceLock<HashMap<&'static str, Catalog>> = OnceLock::new();
+    CATALOGS.get_or_init(|| {
+        SOURCES
+            .iter()
+            .map(|(language, source)| (*language, parse(source)))
+            .collect()
+    })
+}
+
+/// Parses a catalog, skipping comments, blank lines and malformed lines.
+fn parse(source: &'static str) -> Catalog {
+    source
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, message)| (key.trim(), segments(message.trim())))
+        .collect()
+}
+
+/// Splits a message into text and `{ $name }` variables.
+fn segments(mut message: &'static str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    while let Some(start) = message.find("{ $") {
+        let Some(end) = message[start..].find('}').map(|end| start + end) else {
+            break;
+        };
+        if start > 0 {
+            segments.push(Segment::Text(&message[..start]));
+        }
+        segments.push(Segment::Variable(message[start + 3..end].trim()));
+        message = &message[end + 1..];
+    }
+    if !message.is_empty() {
+        segments.push(Segment::Text(message));
+    }
+    segments
+}
+
+/// Matches `text` against the segments of a message, collecting the values of its variables.
+/// Variables match at least one character.
+fn matches<'a>(
+    segments: &[Segment],
+    text: &'a str,
+    values: &mut Vec<(&'static str, &'a str)>,
+) -> bool {
+    match segments {
+        [] => text.is_empty(),
+        [Segment::Text(prefix), rest @ ..] => text
+            .strip_prefix(prefix)
+            .is_some_and(|text| matches(rest, text, values)),
+        [Segment::Variable(name), rest @ ..] => {
+            // Tries every split, shortest value first
+            for (end, _) in text.char_indices().skip(1).chain([(text.len(), ' ')]) {
+                values.push((name, &text[..end]));
+                if matches(rest, &text[end..], values) {
+                    return true;
+                }
+                values.pop();
+            }
+            false
+        }
+    }
+}
+
+/// Fills the variables of a message with `values`; unknown variables are left empty.
+fn fill(segments: &[Segment], values: &[(&'static str, &str)]) -> String {
+    segments
+        .iter()
+        .map(|segment| match segment {
+            Segment::Text(text) => *text,
+            Segment::Variable(name) => values
+                .iter()
+                .find(|(value_name, _)| value_name == name)
+                .map_or("", |(_, value)| value),
+        })
+        .collect()
+}
+
+/// Picks the supported language the client prefers in its `Accept-Language` header (RFC 9110,
+/// section 12.5.4), comparing primary subtags only (`de-AT` is `de`). Falls back to
+/// [`DEFAULT_LANGUAGE`].
+pub fn negotiate(accept_language: &str) -> &'static str {
+    let mut ranges: Vec<(&str, f32)> = accept_language
+        .split(',')
+        .filter_map(|range| {
+            let mut params = range.split(';');
+            let language = params.next()?.trim();
+            let quality = params
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse().ok())
+                .unwrap_or(1.0);
+            (!language.is_empty() && quality > 0.0).then_some((language, quality))
+        })
+        .collect();
+    // Stable, so equally preferred languages keep their order
+    ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranges
+        .into_iter()
+        .find_map(|(range, _)| {
+            let primary = range.split('-').next().unwrap_or(range);
+            SOURCES
+                .iter()
+                .map(|(language, _)| *language)
+                .find(|language| language.eq_ignore_ascii_case(primary) || range == "*")
+        })
+        .unwrap_or(DEFAULT_LANGUAGE)
+}
+
+/// Returns the title of the error `code` in `language`, if the catalog has one.
+pub fn title(language: &str, code: &str) -> Option<String> {
+    let message = catalogs()
+        .get(language)?
+        .get(format!("title-{code}").as_str())?;
+    Some(fill(message, &[]))
+}
+
+/// Translates the English `detail` of an error into `language`, if a catalog message matches it.
+pub fn detail(language: &str, detail: &str) -> Option<String> {
+    let catalogs = catalogs();
+    let translations = catalogs.get(language)?;
+    let mut values = Vec::new();
+    let (key, _) = catalogs
+        .get(DEFAULT_LANGUAGE)?
+        .iter()
+        .find(|(_, message)| {
+            values.clear();
+            matches(message, detail, &mut values)
+        })?;
+    Some(fill(translations.get(key)?, &values))
+}
@@ -0,0 +1,40 @@
+use actix_web::{HttpRequest, dev::ResourceDef};
+
+use crate::scheme::error::ApiError;
+
+/// Name of the route of `GET /posts/{id}` (the `name` of its `#[get]`), the target of the
+/// [`location`] of created posts.
+pub const POST: &str = "post";
+
+/// Name of the route of `GET /users/{id}` (the `name` of its `#[get]`), the target of the
+/// [`location`] of created users.
+pub const USER: &str = "user";
+
+/// Returns the `Location` of the resource `id` created by `req`, served by the route named
+/// `name`: `{id}` below the pattern `req` was routed by, the root of a scope, with the prefixes
+/// the scope is mounted under, so it follows the route wherever it's mounted (e.g.
+/// `/v1/posts/{id}`).
+///
+/// The location is a path without scheme and host, resolved by clients against the URL of the
+/// request (RFC 9110, section 10.2.2), so it stays correct behind proxies which rewrite the host,
+/// and can't be redirected elsewhere, or broken, by a forged `Host` header: unlike
+/// [`HttpRequest::url_for`], it doesn't look at the host at all, so it only fails if the routes
+/// are misconfigured, never after a resource was created because of what a client sent.
+///
+/// # Errors
+/// Returns [`ApiError::Internal`] if `req` wasn't routed or the path isn't served by `name`.
+pub fn location(req: &HttpRequest, name: &str, id: &str) -> Result<String, ApiError> {
+    let scope = req.match_pattern().ok_or_else(|| {
+        ApiError::Internal(format!("no location of {name}: request isn't routed"))
+    })?;
+    let mut path = String::new();
+    let served = ResourceDef::new(format!("{}/{{id}}", scope.trim_end_matches('/')))
+        .resource_path_from_iter(&mut path, &mut [id].iter())
+        && req.resource_map().match_name(&path) == Some(name);
+    if !served {
+        return Err(ApiError::Internal(format!(
+            "no location of {name}: {path} is served by another route"
+        )));
+    }
+    Ok(path)
+}
@@ -0,0 +1,61 @@
+use actix_web::{
+    HttpRequest, HttpResponse, ResponseError,
+    dev::ResourceDef,
+    http::{
+        Method,
+        header::{ALLOW, HeaderValue},
+    },
+    web,
+};
+
+use crate::scheme::error::ApiError;
+
+/// Route of a scope: the pattern of its path relative to the scope, as given to its route macro,
+/// and the methods it's registered for.
+pub struct Route {
+    pub pattern: &'static str,
+    pub methods: &'static [Method],
+}
+
+/// Registers the default service of a scope, answering the requests no route of the scope
+/// handles: `405 Method Not Allowed` with the methods of the path in `Allow` if one of `routes`
+/// matches the path, and `404 Not Found` otherwise.
+///
+/// Routes registered with the attribute macros (`#[get]`, ...) are separate resources even when
+/// they share a path, so actix takes a known path with another method for an unknown path.
+/// `routes` has to list every route of the scope with its methods.
+pub fn configure(cfg: &mut web::ServiceConfig, routes: &'static [Route]) {
+    cfg.default_service(web::to(move |req: HttpRequest| async move {
+        not_handled(&req, routes)
+    }));
+}
+
+/// Answers a request of the scope of `routes` which no route handles.
+fn not_handled(req: &HttpRequest, routes: &[Route]) -> HttpResponse {
+    let path = req.match_info().unprocessed();
+    let mut allowed: Vec<&Method> = routes
+        .iter()
+        .filter(|route| ResourceDef::new(route.pattern).is_match(path))
+        .flat_map(|route| route.methods)
+        .collect();
+    if allowed.is_empty() {
+        return ApiError::NotFound.error_response();
+    }
+    allowed.sort_by_key(|method| method.as_str());
+    allowed.dedup();
+    let allow = allowed
+        .iter()
+        .map(|method| method.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut response = ApiError::MethodNotAllowed(format!(
+        "{} isn't supported here; allowed methods are {allow}",
+        req.method()
+    ))
+    .error_response();
+    response.headers_mut().insert(
+        ALLOW,
+        HeaderValue::from_str(&allow).expect("Method names are valid header values"),
+    );
+    response
+}
@@ -0,0 +1,35 @@
+use actix_web::{HttpResponse, Responder, get, http::Method, web};
+
+use crate::{
+    scheme::{
+        methods::{self, Route},
+        params::{NoParams, Query},
+    },
+    state::GlobalServerState,
+};
+
+/// Handles `GET /metrics`
+///
+/// Returns a JSON snapshot of the server-wide [`Metrics`](crate::state::Metrics).
+///
+/// # Response
+/// - `200 OK` with a JSON object of counters and gauges
+#[get("")]
+async fn get_metrics(
+    state: web::Data<GlobalServerState>,
+    _query: Query<NoParams>,
+) -> impl Responder {
+    HttpResponse::Ok().json(state.metrics.snapshot())
+}
+
+/// Routes of `/metrics`, for [`methods::configure`].
+const ROUTES: &[Route] = &[Route {
+    pattern: "",
+    methods: &[Method::GET],
+}];
+
+/// Registers the `/metrics` route handlers into the Actix-Web service configuration.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_metrics);
+    methods::configure(cfg, ROUTES);
+}
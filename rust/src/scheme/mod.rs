@@ -1,4 +1,21 @@
+pub mod admin;
 pub mod auth;
+pub mod breaker;
+pub mod deadline;
+pub mod error;
+pub mod feed;
+pub mod fields;
+pub mod flags;
+pub mod locale;
+pub mod location;
+pub mod methods;
+pub mod metrics;
+pub mod moderation;
+pub mod pagination;
+pub mod params;
 pub mod posts;
 pub mod provider;
+pub mod replication;
+pub mod retry;
+pub mod transaction;
 pub mod users;
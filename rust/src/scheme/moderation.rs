@@ -0,0 +1,134 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+use tracing::debug;
+
+use crate::{envs, scheme::provider::lock};
+
+/// Number of flagged items kept in the [`ModerationQueue`]; older ones are dropped.
+const QUEUE_CAPACITY: usize = 1000;
+
+/// Decision of a [`ContentModerator`] about a piece of content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    /// The content is fine.
+    Accept,
+
+    /// The content is stored, but listed for review, with the reason.
+    Flag(String),
+
+    /// The content is refused (`422`), with the reason.
+    Reject(String),
+}
+
+/// Moderation of user-provided content, e.g. a profanity or spam filter.
+///
+/// Called inline by the handlers creating and updating posts, so implementations should be quick
+/// and must not block on I/O.
+pub trait ContentModerator: Send + Sync {
+    /// Judges `content`.
+    fn moderate(&self, content: &str) -> Verdict;
+}
+
+/// Moderator matching content against keyword lists, case-insensitively and anywhere in the text.
+///
+/// Configured with `RUST_SERVER_MODERATION_REJECT` and `RUST_SERVER_MODERATION_FLAG`; both lists
+/// are empty by default, accepting everything.
+#[derive(Debug, Default)]
+pub struct KeywordModerator {
+    reject: Vec<String>,
+    flag: Vec<String>,
+}
+
+impl KeywordModerator {
+    /// Creates a moderator rejecting content with any of the `reject` keywords and flagging content
+    /// with any of the `flag` keywords.
+    pub fn new(reject: &[String], flag: &[String]) -> Self {
+        let lowercase = |keywords: &[String]| {
+            keywords
+                .iter()
+                .map(|keyword| keyword.trim().to_lowercase())
+                .filter(|keyword| !keyword.is_empty())
+                .collect()
+        };
+        Self {
+            reject: lowercase(reject),
+            flag: lowercase(flag),
+        }
+    }
+}
+
+impl ContentModerator for KeywordModerator {
+    fn moderate(&self, content: &str) -> Verdict {
+        if self.reject.is_empty() && self.flag.is_empty() {
+            return Verdict::Accept;
+        }
+        let content = content.to_lowercase();
+        let find = |keywords: &[String]| {
+            keywords
+                .iter()
+                .find(|keyword| content.contains(keyword.as_str()))
+                .cloned()
+        };
+        if let Some(keyword) = find(&self.reject) {
+            Verdict::Reject(format!("content contains the banned keyword {keyword:?}"))
+        } else if let Some(keyword) = find(&self.flag) {
+            Verdict::Flag(format!("content contains the keyword {keyword:?}"))
+        } else {
+            Verdict::Accept
+        }
+    }
+}
+
+/// Creates the moderator configured with the `RUST_SERVER_MODERATION_*` environment variables.
+pub fn from_env() -> Arc<dyn ContentModerator> {
+    Arc::new(KeywordModerator::new(
+        &envs::vars::get_moderation_reject(),
+        &envs::vars::get_moderation_flag(),
+    ))
+}
+
+/// Item flagged by a [`ContentModerator`], listed at `GET /admin/moderation/queue`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Flagged {
+    /// Kind of the item, e.g. `post`.
+    pub kind: &'static str,
+
+    /// ID of the item.
+    pub id: String,
+
+    /// Author of the item.
+    pub author: String,
+
+    /// Why the item was flagged.
+    pub reason: String,
+
+    /// When the item was flagged.
+    pub flagged_at: DateTime<Utc>,
+}
+
+/// Items flagged for review, oldest first. Keeps the latest [`QUEUE_CAPACITY`] items.
+#[derive(Debug, Default)]
+pub struct ModerationQueue {
+    items: Mutex<VecDeque<Flagged>>,
+}
+
+impl ModerationQueue {
+    /// Adds a flagged item, dropping the oldest one if the queue is full.
+    pub fn push(&self, item: Flagged) {
+        debug!("Flagged {} {}: {}", item.kind, item.id, item.reason);
+        let mut items = lock(&self.items);
+        if items.len() == QUEUE_CAPACITY {
+            items.pop_front();
+        }
+        items.push_back(item);
+    }
+
+    /// Returns a copy of the flagged items, oldest first.
+    pub fn items(&self) -> Vec<Flagged> {
+        lock(&self.items).iter().cloned().collect()
+    }
+}
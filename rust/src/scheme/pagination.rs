@@ -0,0 +1,92 @@
+use actix_web::{HttpRequest, HttpResponse, http::header};
+use serde::{Deserialize, Serialize};
+
+use crate::scheme::params::Params;
+
+pub use percom_model::Page;
+
+/// Header of paginated responses with the total number of items across all pages.
+pub const TOTAL_COUNT_HEADER: &str = "X-Total-Count";
+
+/// Header of paginated responses with the current page number.
+pub const PAGE_HEADER: &str = "X-Page";
+
+/// Page size used when the client doesn't provide `per_page`.
+const DEFAULT_PER_PAGE: usize = 20;
+
+/// Upper bound for `per_page`, protecting the server from unbounded responses.
+const MAX_PER_PAGE: usize = 100;
+
+/// Query parameters accepted by paginated list endpoints.
+///
+/// Pages are 1-based. Missing values fall back to the first page and [`DEFAULT_PER_PAGE`] items;
+/// `per_page` is clamped to `1..=MAX_PER_PAGE`.
+///
+/// # Example
+/// ```text
+/// GET /feed?page=2&per_page=50
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PageQuery {
+    /// Requested page number (1-based).
+    pub page: Option<usize>,
+
+    /// Requested number of items per page.
+    pub per_page: Option<usize>,
+}
+
+impl Params for PageQuery {
+    const NAMES: &'static [&'static str] = &["page", "per_page"];
+}
+
+impl PageQuery {
+    /// Returns the requested page number, never less than `1`.
+    pub fn page(&self) -> usize {
+        self.page.unwrap_or(1).max(1)
+    }
+
+    /// Returns the effective page size.
+    pub fn per_page(&self) -> usize {
+        self.per_page
+            .unwrap_or(DEFAULT_PER_PAGE)
+            .clamp(1, MAX_PER_PAGE)
+    }
+
+    /// Returns the number of items to skip before the requested page starts.
+    pub fn offset(&self) -> usize {
+        (self.page() - 1).saturating_mul(self.per_page())
+    }
+
+    /// Builds the page of already sliced items requested by the query, with `total` items across
+    /// all pages.
+    pub fn page_of<T>(&self, items: Vec<T>, total: usize) -> Page<T> {
+        Page::new(items, self.page(), self.per_page(), total)
+    }
+}
+
+/// Responds to `req` with `page`: its items and metadata in the body, and the metadata in headers
+/// too, for clients which only look at those:
+/// - [`TOTAL_COUNT_HEADER`] and [`PAGE_HEADER`];
+/// - `Link` to the `prev` and `next` pages (RFC 8288), if there are such, as paths relative to
+///   the server, like `Location`.
+pub fn respond<T: Serialize>(req: &HttpRequest, page: &Page<T>) -> HttpResponse {
+    let link = |number: usize, rel: &str| {
+        format!(
+            "<{}?page={number}&per_page={}>; rel=\"{rel}\"",
+            req.path(),
+            page.per_page
+        )
+    };
+    let links: Vec<String> = [(page.prev(), "prev"), (page.next(), "next")]
+        .into_iter()
+        .filter_map(|(number, rel)| Some(link(number?, rel)))
+        .collect();
+    let mut response = HttpResponse::Ok();
+    response
+        .insert_header((TOTAL_COUNT_HEADER, page.total))
+        .insert_header((PAGE_HEADER, page.page));
+    if !links.is_empty() {
+        response.insert_header((header::LINK, links.join(", ")));
+    }
+    response.json(page)
+}
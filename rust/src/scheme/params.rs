@@ -0,0 +1,94 @@
+use actix_web::{FromRequest, HttpRequest, dev::Payload, web};
+use futures_util::future::{Ready, ready};
+use serde::{Deserialize, de::DeserializeOwned};
+use std::ops::Deref;
+
+use crate::scheme::error::ApiError;
+
+/// What happens to query parameters an endpoint doesn't know (`RUST_SERVER_QUERY_PARAMS`).
+///
+/// Registered as app data, where [`Query`] looks it up; without it, endpoints are lenient.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum QueryMode {
+    /// Unknown parameters are ignored, e.g. `?per-page=5` is the default page size.
+    #[default]
+    Lenient,
+
+    /// Unknown parameters are rejected with `400 Bad Request`, so a misspelled parameter doesn't
+    /// go unnoticed.
+    Strict,
+}
+
+impl QueryMode {
+    /// Returns the mode named `lenient` or `strict`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "lenient" => Some(Self::Lenient),
+            "strict" => Some(Self::Strict),
+            _ => None,
+        }
+    }
+}
+
+/// Query parameters of an endpoint.
+pub trait Params: DeserializeOwned {
+    /// Names of the parameters, as deserialized; the others are unknown to the endpoint.
+    const NAMES: &'static [&'static str];
+}
+
+/// Query parameters of the endpoints which take none.
+#[derive(Debug, Default, Deserialize)]
+pub struct NoParams {}
+
+impl Params for NoParams {
+    const NAMES: &'static [&'static str] = &[];
+}
+
+/// Typed query extractor, like `web::Query`, which also knows the parameters of the endpoint:
+/// in [`QueryMode::Strict`], parameters not in [`Params::NAMES`] are rejected.
+///
+/// Every endpoint extracts its parameters with it, [`NoParams`] if it takes none, so the query
+/// contract of the whole API depends on the mode.
+#[derive(Debug)]
+pub struct Query<T>(pub T);
+
+impl<T> Deref for Query<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Params> Query<T> {
+    /// Parses the query of `req`, checking the names of its parameters in strict mode.
+    fn parse(req: &HttpRequest) -> Result<Self, ApiError> {
+        let query = req.query_string();
+        let mode = req.app_data::<QueryMode>().copied().unwrap_or_default();
+        if mode == QueryMode::Strict {
+            let pairs = web::Query::<Vec<(String, String)>>::from_query(query)
+                .map_err(|err| ApiError::BadRequest(err.to_string()))?;
+            if let Some((name, _)) = pairs.iter().find(|(name, _)| !T::NAMES.contains(&&**name)) {
+                return Err(ApiError::BadRequest(match T::NAMES {
+                    [] => format!("unknown query parameter {name:?}: the endpoint takes none"),
+                    names => format!(
+                        "unknown query parameter {name:?}: expected {}",
+                        names.join(", ")
+                    ),
+                }));
+            }
+        }
+        web::Query::<T>::from_query(query)
+            .map(|params| Self(params.into_inner()))
+            .map_err(|err| ApiError::BadRequest(err.to_string()))
+    }
+}
+
+impl<T: Params> FromRequest for Query<T> {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Self::parse(req))
+    }
+}
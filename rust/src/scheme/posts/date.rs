@@ -0,0 +1,49 @@
+//! Dates of posts.
+//!
+//! Precision, parsing and formatting of dates are shared with the clients (see
+//! [`percom_model::date`]); the server only adds the `?tz=` parameter (see [`Tz`]).
+
+use chrono::{FixedOffset, Offset, Utc};
+
+use crate::scheme::{error::ApiError, posts::Post};
+
+pub use percom_model::date::*;
+
+/// Offset requested with the `tz` query parameter of `GET /posts` and `GET /posts/{id}`, e.g.
+/// `?tz=-05:00` or `?tz=Z`. Without it, dates keep the offset they were written with.
+///
+/// Only fixed offsets are supported, not named zones. Since `+` in a query string decodes to a
+/// space, `?tz=+02:00` has to be sent as `?tz=%2B02:00`, but a leading space is taken for `+` too.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Tz(pub Option<FixedOffset>);
+
+impl Tz {
+    /// Parses the value of the `tz` parameter, if any.
+    pub fn parse(value: Option<&str>) -> Result<Self, ApiError> {
+        value.map(offset).transpose().map(Self)
+    }
+
+    /// Renders the dates of `post` at the requested offset.
+    pub fn apply(self, mut post: Post) -> Post {
+        if let Some(offset) = self.0 {
+            post.date = post.date.with_timezone(&offset);
+        }
+        post
+    }
+}
+
+/// Parses the value of `tz`.
+fn offset(value: &str) -> Result<FixedOffset, ApiError> {
+    let value = match value.strip_prefix(' ') {
+        Some(rest) => format!("+{rest}"),
+        None => value.to_owned(),
+    };
+    match value.as_str() {
+        "Z" | "z" | "UTC" => Ok(Utc.fix()),
+        _ => value.parse().map_err(|_| {
+            ApiError::BadRequest(format!(
+                "tz must be a UTC offset like -05:00 or Z, not {value:?}"
+            ))
+        }),
+    }
+}
@@ -0,0 +1,85 @@
+//! Related resources embedded in `GET /posts/{id}` with `?include=author`, so a client rendering
+//! a post doesn't make another request per relation.
+//!
+//! Relations are resolved through the providers while the request is served; the response is
+//! then a compound [`Document`] rather than the bare post. They're resolved as the equivalent
+//! lookups would be, e.g. the author as `GET /users?nickname=`, so embedding them requires the
+//! same authentication and feature flags.
+
+use serde::Serialize;
+
+use crate::scheme::{
+    error::ApiError,
+    posts::Post,
+    provider::ProviderError,
+    users::{User, UsersProvider},
+};
+
+/// Relations of a post which can be included.
+pub const RELATIONS: &[&str] = &["author"];
+
+/// Relations requested with the `include` query parameter.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Include {
+    /// The registered user whose nickname is the author of the post.
+    author: bool,
+}
+
+impl Include {
+    /// Parses the comma-separated value of `include`, failing on relations not in [`RELATIONS`].
+    pub fn parse(value: Option<&str>) -> Result<Self, ApiError> {
+        let mut include = Self::default();
+        for name in value.into_iter().flat_map(|value| value.split(',')) {
+            match name.trim() {
+                "" => {}
+                "author" => include.author = true,
+                name => {
+                    return Err(ApiError::BadRequest(format!(
+                        "unknown relation {name:?}: expected {}",
+                        RELATIONS.join(", ")
+                    )));
+                }
+            }
+        }
+        Ok(include)
+    }
+
+    /// Returns `true` if no relation is requested, so the post is sent on its own.
+    pub fn is_empty(&self) -> bool {
+        !self.author
+    }
+
+    /// Resolves the requested relations of `post`. Blocks on the providers, so it's meant to be
+    /// called within a [`Deadline`](crate::scheme::deadline::Deadline).
+    pub fn resolve(
+        &self,
+        users: &dyn UsersProvider,
+        post: &Post,
+    ) -> Result<Included, ProviderError> {
+        let mut included = Included::default();
+        if self.author {
+            // Nicknames are unique, so there's at most one
+            let author = users.find(None, Some(&post.author))?.into_iter().next();
+            included.author = Some(author);
+        }
+        Ok(included)
+    }
+}
+
+/// Related resources of a post; only the requested relations are serialized, and a relation
+/// which resolves to nothing, e.g. an author who isn't a registered user, is `null`.
+#[derive(Debug, Default, Serialize)]
+pub struct Included {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<Option<User>>,
+}
+
+/// Compound document of `GET /posts/{id}?include=`: the post and its related resources.
+#[derive(Debug, Serialize)]
+pub struct Document<T> {
+    /// The post, with the requested fields only (see [`Fields`](crate::scheme::fields::Fields)).
+    pub data: T,
+
+    /// Related resources, keyed by relation.
+    pub included: Included,
+}
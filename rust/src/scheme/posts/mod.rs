@@ -1,11 +1,14 @@
-#[cfg(test)]
-mod proptests;
-
-pub mod model;
+pub mod date;
+pub mod include;
+pub mod protobuf;
 pub mod provider;
 pub mod providers;
+pub mod query;
 pub mod routes;
+pub mod sanitize;
+pub mod versions;
 
-pub use model::*;
+pub use percom_model::posts::*;
 pub use provider::*;
 pub use providers::*;
+pub use query::*;
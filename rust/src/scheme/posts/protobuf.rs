@@ -0,0 +1,264 @@
+//! Protobuf form of the `/posts` API (`proto/posts.proto`).
+//!
+//! The messages are written the way `prost-build` generates them, so no `protoc` is needed to
+//! build the server. Handlers negotiate the format per request: request bodies are decoded
+//! according to `Content-Type` ([`Input`]) and responses are encoded according to `Accept`
+//! ([`Format`]); JSON stays the default, so the serialization cost can be compared on the same
+//! endpoints.
+
+use actix_web::{
+    FromRequest, HttpRequest, HttpResponse, HttpResponseBuilder,
+    dev::Payload,
+    http::header::{self, Header},
+    web,
+};
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+use futures_util::future::{LocalBoxFuture, Ready, ready};
+use prost::Message;
+use prost_types::Timestamp;
+
+use crate::scheme::{error::ApiError, posts};
+
+/// Media type of protobuf bodies.
+pub const CONTENT_TYPE: &str = "application/x-protobuf";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum PostStatus {
+    Published = 0,
+    Scheduled = 1,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Post {
+    #[prost(string, tag = "1")]
+    pub id: String,
+    #[prost(string, tag = "2")]
+    pub author: String,
+    #[prost(message, optional, tag = "3")]
+    pub date: Option<Timestamp>,
+    #[prost(string, tag = "4")]
+    pub content: String,
+    #[prost(enumeration = "PostStatus", tag = "5")]
+    pub status: i32,
+    #[prost(message, optional, tag = "6")]
+    pub publish_at: Option<Timestamp>,
+    #[prost(int32, tag = "7")]
+    pub date_offset: i32,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct PostInput {
+    #[prost(string, tag = "1")]
+    pub author: String,
+    #[prost(message, optional, tag = "2")]
+    pub date: Option<Timestamp>,
+    #[prost(string, tag = "3")]
+    pub content: String,
+    #[prost(message, optional, tag = "4")]
+    pub publish_at: Option<Timestamp>,
+    #[prost(int32, tag = "5")]
+    pub date_offset: i32,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct PostList {
+    #[prost(message, repeated, tag = "1")]
+    pub posts: Vec<Post>,
+}
+
+fn timestamp<Tz: TimeZone>(date: DateTime<Tz>) -> Timestamp {
+    let date = posts::date::normalize(date);
+    Timestamp {
+        seconds: date.timestamp(),
+        nanos: date.timestamp_subsec_nanos() as i32,
+    }
+}
+
+fn date(timestamp: Timestamp) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp(timestamp.seconds, u32::try_from(timestamp.nanos).ok()?)
+        .map(posts::date::normalize)
+}
+
+/// Returns the offset of `date` in seconds east of UTC, the `date_offset` of the messages.
+fn offset_seconds(date: &DateTime<FixedOffset>) -> i32 {
+    date.offset().local_minus_utc()
+}
+
+/// Reads `date` with `date_offset`.
+fn local_date(timestamp: Option<Timestamp>, offset: i32) -> Result<DateTime<FixedOffset>, String> {
+    let offset = FixedOffset::east_opt(offset).ok_or("date_offset is out of range")?;
+    let date = date(timestamp.ok_or("date is required")?).ok_or("date is out of range")?;
+    Ok(date.with_timezone(&offset))
+}
+
+impl From<&posts::Post> for Post {
+    fn from(post: &posts::Post) -> Self {
+        Self {
+            id: post.id.clone(),
+            author: post.author.clone(),
+            date: Some(timestamp(post.date)),
+            date_offset: offset_seconds(&post.date),
+            content: post.content.clone(),
+            status: match post.status {
+                posts::PostStatus::Published => PostStatus::Published,
+                posts::PostStatus::Scheduled => PostStatus::Scheduled,
+            } as i32,
+            publish_at: post.publish_at.map(timestamp),
+        }
+    }
+}
+
+// Conversions of the client side, used by the tests
+#[cfg(test)]
+impl From<&posts::PostInput> for PostInput {
+    fn from(input: &posts::PostInput) -> Self {
+        Self {
+            author: input.author.clone(),
+            date: Some(timestamp(input.date)),
+            date_offset: offset_seconds(&input.date),
+            content: input.content.clone(),
+            publish_at: input.publish_at.map(timestamp),
+        }
+    }
+}
+
+impl TryFrom<PostInput> for posts::PostInput {
+    type Error = String;
+
+    fn try_from(input: PostInput) -> Result<Self, Self::Error> {
+        Ok(Self {
+            author: input.author,
+            date: local_date(input.date, input.date_offset)?,
+            content: input.content,
+            publish_at: match input.publish_at {
+                Some(at) => Some(date(at).ok_or("publish_at is out of range")?),
+                None => None,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+impl TryFrom<Post> for posts::Post {
+    type Error = String;
+
+    fn try_from(post: Post) -> Result<Self, Self::Error> {
+        let status = match post.status() {
+            PostStatus::Published => posts::PostStatus::Published,
+            PostStatus::Scheduled => posts::PostStatus::Scheduled,
+        };
+        let input = posts::PostInput::try_from(PostInput {
+            author: post.author,
+            date: post.date,
+            content: post.content,
+            publish_at: post.publish_at,
+            date_offset: post.date_offset,
+        })?;
+        Ok(Self {
+            id: post.id,
+            author: input.author,
+            date: input.date,
+            content: input.content,
+            status,
+            publish_at: input.publish_at,
+        })
+    }
+}
+
+/// Format of a response, negotiated from the request's `Accept` header.
+///
+/// Protobuf is used if the client ranks it above JSON; otherwise (no `Accept`, `*/*`, only
+/// unsupported types) the response is JSON, as before protobuf support.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Protobuf,
+}
+
+impl Format {
+    /// Finishes `response` with `post` as its body.
+    pub fn post(self, mut response: HttpResponseBuilder, post: &posts::Post) -> HttpResponse {
+        match self {
+            Self::Json => response.json(post),
+            Self::Protobuf => response
+                .content_type(CONTENT_TYPE)
+                .body(Post::from(post).encode_to_vec()),
+        }
+    }
+
+    /// Content type of bodies in this format.
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Protobuf => CONTENT_TYPE,
+        }
+    }
+
+    /// Encodes `posts` as a body in this format, to be sent with [`Format::content_type`].
+    pub fn encode_posts(self, posts: &[posts::Post]) -> Result<Vec<u8>, serde_json::Error> {
+        match self {
+            Self::Json => serde_json::to_vec(posts),
+            Self::Protobuf => Ok(PostList {
+                posts: posts.iter().map(Post::from).collect(),
+            }
+            .encode_to_vec()),
+        }
+    }
+}
+
+impl FromRequest for Format {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let format = header::Accept::parse(req)
+            .ok()
+            .and_then(|accept| {
+                accept
+                    .ranked()
+                    .into_iter()
+                    .find_map(|mime| match mime.essence_str() {
+                        CONTENT_TYPE => Some(Self::Protobuf),
+                        "application/json" | "application/*" | "*/*" => Some(Self::Json),
+                        _ => None,
+                    })
+            })
+            .unwrap_or(Self::Json);
+        ready(Ok(format))
+    }
+}
+
+/// Body of `POST /posts` and `PUT /posts/{id}`: protobuf if the `Content-Type` says so, JSON
+/// otherwise.
+///
+/// JSON bodies are extracted exactly as by [`web::Json`], errors included; a protobuf body which
+/// can't be decoded is rejected with [`ApiError::BadRequest`].
+pub struct Input(pub posts::PostInput);
+
+impl FromRequest for Input {
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let protobuf = req
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| {
+                value.split(';').next().unwrap_or_default().trim() == CONTENT_TYPE
+            });
+        if !protobuf {
+            let json = web::Json::<posts::PostInput>::from_request(req, payload);
+            return Box::pin(async move { Ok(Self(json.await?.into_inner())) });
+        }
+        let bytes = web::Bytes::from_request(req, payload);
+        Box::pin(async move {
+            let input = PostInput::decode(bytes.await?)
+                .map_err(|err| ApiError::BadRequest(format!("Invalid protobuf body: {err}")))?;
+            posts::PostInput::try_from(input)
+                .map(Self)
+                .map_err(|err| ApiError::BadRequest(format!("Invalid post: {err}")).into())
+        })
+    }
+}
@@ -1,4 +1,13 @@
-use crate::scheme::{posts::model::*, provider::Provider};
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+
+use crate::scheme::{
+    posts::*,
+    provider::{Provider, ProviderError},
+};
+
+/// Number of hex digits of the entity tags of posts (see [`Tagged`]).
+const ETAG_LEN: usize = 16;
 
 /// Trait for managing blog post resources, providing basic CRUD operations.
 ///
@@ -11,26 +20,113 @@ use crate::scheme::{posts::model::*, provider::Provider};
 /// All methods are synchronous and expected to be cheap and fast for in-memory use cases.
 /// For I/O-bound implementations (e.g., database-backed), async variants might be preferable.
 ///
+/// Every method is fallible: storage failures are reported as [`ProviderError::Internal`], while
+/// lookups that simply find nothing are expressed with `Option` or `bool`.
+///
 /// # Methods
 ///
 /// - [`get_all`] – Returns all available posts.
 /// - [`get`] – Retrieves a specific post by ID.
+/// - [`get_json`] – Retrieves a specific post by ID, serialized as JSON.
+/// - [`get_tagged`] – Retrieves a specific post by ID, serialized as JSON, with its entity tag.
 /// - [`create`] – Creates a new post from the given input.
 /// - [`update`] – Updates an existing post, if found.
 /// - [`delete`] – Removes a post by ID, returning success status.
+/// - [`get_by_authors`] – Returns a date-ordered slice of posts written by any of the given authors.
+/// - [`get_by_date`] – Returns the posts of a period, given as a prefix of their UTC date.
+/// - [`set_author`] – Reassigns posts to another author.
+/// - [`publish_due`] – Publishes scheduled posts whose time has come.
+/// - [`import`] – Stores fully built posts as they are, e.g. from a dataset of another backend.
+/// - [`explain`] – Describes how posts matching a filter combination would be found.
 pub trait PostsProvider: Provider {
     /// Returns a list of all posts.
-    fn get_all(&self) -> Vec<Post>;
+    fn get_all(&self) -> Result<Vec<Post>, ProviderError>;
 
     /// Returns a post by ID, or `None` if not found.
-    fn get(&self, id: &str) -> Option<Post>;
+    fn get(&self, id: &str) -> Result<Option<Post>, ProviderError>;
+
+    /// Returns a post by ID serialized as JSON, exactly as [`Post`] serializes, or `None` if not
+    /// found. Providers keeping serialized posts return their bytes without decoding them; the
+    /// others serialize the result of [`get`](Self::get) with [`to_json`].
+    fn get_json(&self, id: &str) -> Result<Option<Vec<u8>>, ProviderError>;
+
+    /// Returns a post by ID serialized as JSON with its entity tag (see [`Tagged`]), or `None` if
+    /// not found: the bytes of [`get_json`](Self::get_json), or the result of [`get`](Self::get)
+    /// serialized with [`to_json`] if `decode` is set. Both are the same bytes, so caching
+    /// providers serve either from the same entry.
+    fn get_tagged(&self, id: &str, decode: bool) -> Result<Option<Tagged>, ProviderError> {
+        let json = if decode {
+            self.get(id)?.map(|post| to_json(&post)).transpose()?
+        } else {
+            self.get_json(id)?
+        };
+        Ok(json.map(Tagged::new))
+    }
 
     /// Creates a new post and returns it, including the generated ID.
-    fn create(&self, input: PostInput) -> Post;
+    fn create(&self, input: PostInput) -> Result<Post, ProviderError>;
 
     /// Updates an existing post by ID, returning the updated post if successful.
-    fn update(&self, id: &str, input: PostInput) -> Option<Post>;
+    fn update(&self, id: &str, input: PostInput) -> Result<Option<Post>, ProviderError>;
 
     /// Deletes a post by ID. Returns `true` if a post was deleted.
-    fn delete(&self, id: &str) -> bool;
+    fn delete(&self, id: &str) -> Result<bool, ProviderError>;
+
+    /// Returns published posts written by any of `authors`, newest first, skipping `offset` posts and
+    /// returning at most `limit` of them.
+    ///
+    /// The second element of the returned tuple is the total number of matching posts, regardless
+    /// of `offset` and `limit`.
+    fn get_by_authors(
+        &self,
+        authors: &[String],
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<Post>, usize), ProviderError>;
+
+    /// Returns the posts whose UTC date (see [`Post::utc_date`]) starts with `prefix`, e.g. `2024-01`
+    /// for January 2024, oldest first. Scheduled posts are included.
+    fn get_by_date(&self, prefix: &str) -> Result<Vec<Post>, ProviderError>;
+
+    /// Sets `author` on every existing post from `ids`, returning the IDs of the updated posts.
+    ///
+    /// Missing IDs are skipped. Other fields, including the date, are left untouched.
+    fn set_author(&self, ids: &[String], author: &str) -> Result<Vec<String>, ProviderError>;
+
+    /// Switches every scheduled post with `publish_at <= now` to [`PostStatus::Published`] and
+    /// returns the posts which were published by this call.
+    fn publish_due(&self, now: DateTime<Utc>) -> Result<Vec<Post>, ProviderError>;
+
+    /// Stores `posts` as they are, including IDs and statuses, replacing posts with the same IDs.
+    /// Returns the number of stored posts.
+    fn import(&self, posts: Vec<Post>) -> Result<usize, ProviderError>;
+
+    /// Returns the access path (index or scan) the provider would use to find the posts matching
+    /// `query`, without running it. Used for diagnostics only.
+    fn explain(&self, query: &PostsQuery) -> Result<QueryPlan, ProviderError>;
+}
+
+/// A post serialized as JSON, with the entity tag of that version of it (see
+/// [`PostsProvider::get_tagged`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tagged {
+    pub json: Vec<u8>,
+
+    /// Opaque tag of the `ETag` header, without quotes: the first hex digits of the SHA-256 of
+    /// `json`, so any change of the post changes it.
+    pub etag: String,
+}
+
+impl Tagged {
+    /// Tags `json`.
+    pub fn new(json: Vec<u8>) -> Self {
+        let mut etag = hex::encode(Sha256::digest(&json));
+        etag.truncate(ETAG_LEN);
+        Self { json, etag }
+    }
+}
+
+/// Serializes `post` as JSON, for [`PostsProvider::get_json`] of providers keeping decoded posts.
+pub fn to_json(post: &Post) -> Result<Vec<u8>, ProviderError> {
+    serde_json::to_vec(post).map_err(|err| ProviderError::Internal(err.to_string()))
 }
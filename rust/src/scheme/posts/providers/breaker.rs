@@ -0,0 +1,94 @@
+use chrono::{DateTime, Utc};
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+use crate::scheme::{
+    breaker::Breaker,
+    posts::*,
+    provider::{Provider, ProviderError, ProviderStats},
+};
+
+/// [`PostsProvider`] decorator passing every call through a circuit [`Breaker`].
+///
+/// While the breaker is open, calls fail right away with [`ProviderError::Unavailable`] (`503`)
+/// instead of waiting for a backend which keeps failing. Reads are rejected as well: for a remote
+/// backend, they'd fail the same way.
+pub struct BreakerProvider {
+    inner: Arc<dyn PostsProvider>,
+    breaker: Arc<Breaker>,
+}
+
+impl BreakerProvider {
+    /// Wraps `inner` with `breaker`, which may be shared with `/admin/breakers`.
+    pub fn wrapped(inner: Arc<dyn PostsProvider>, breaker: Arc<Breaker>) -> Arc<Self> {
+        Arc::new(Self { inner, breaker })
+    }
+}
+
+impl Provider for BreakerProvider {}
+
+impl ProviderStats for BreakerProvider {
+    fn stats(&self) -> Value {
+        json!({
+            "type": "breaker",
+            "breaker": self.breaker.status(),
+            "inner": self.inner.stats(),
+        })
+    }
+}
+
+impl PostsProvider for BreakerProvider {
+    fn get_all(&self) -> Result<Vec<Post>, ProviderError> {
+        self.breaker.call(|| self.inner.get_all())
+    }
+
+    fn get(&self, id: &str) -> Result<Option<Post>, ProviderError> {
+        self.breaker.call(|| self.inner.get(id))
+    }
+
+    fn get_json(&self, id: &str) -> Result<Option<Vec<u8>>, ProviderError> {
+        self.breaker.call(|| self.inner.get_json(id))
+    }
+
+    fn create(&self, input: PostInput) -> Result<Post, ProviderError> {
+        self.breaker.call(|| self.inner.create(input))
+    }
+
+    fn update(&self, id: &str, input: PostInput) -> Result<Option<Post>, ProviderError> {
+        self.breaker.call(|| self.inner.update(id, input))
+    }
+
+    fn delete(&self, id: &str) -> Result<bool, ProviderError> {
+        self.breaker.call(|| self.inner.delete(id))
+    }
+
+    fn get_by_authors(
+        &self,
+        authors: &[String],
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<Post>, usize), ProviderError> {
+        self.breaker
+            .call(|| self.inner.get_by_authors(authors, offset, limit))
+    }
+
+    fn get_by_date(&self, prefix: &str) -> Result<Vec<Post>, ProviderError> {
+        self.breaker.call(|| self.inner.get_by_date(prefix))
+    }
+
+    fn set_author(&self, ids: &[String], author: &str) -> Result<Vec<String>, ProviderError> {
+        self.breaker.call(|| self.inner.set_author(ids, author))
+    }
+
+    fn publish_due(&self, now: DateTime<Utc>) -> Result<Vec<Post>, ProviderError> {
+        self.breaker.call(|| self.inner.publish_due(now))
+    }
+
+    fn import(&self, posts: Vec<Post>) -> Result<usize, ProviderError> {
+        self.breaker.call(|| self.inner.import(posts))
+    }
+
+    fn explain(&self, query: &PostsQuery) -> Result<QueryPlan, ProviderError> {
+        self.breaker.call(|| self.inner.explain(query))
+    }
+}
@@ -0,0 +1,231 @@
+use chrono::{DateTime, Utc};
+use serde_json::{Value, json};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        Arc, Mutex, MutexGuard,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use crate::scheme::{
+    posts::*,
+    provider::{Provider, ProviderError, ProviderStats},
+};
+
+/// Serialized posts by ID, evicting the least recently used one beyond `capacity`.
+#[derive(Default)]
+struct Lru {
+    /// Bodies by ID, with their entity tags and the tick of their last use.
+    entries: HashMap<String, (Arc<Tagged>, u64)>,
+
+    /// IDs by the tick of their last use, oldest first.
+    order: BTreeMap<u64, String>,
+
+    /// Incremented on every use.
+    tick: u64,
+
+    /// Incremented on every write through the provider, so a body read before a write isn't
+    /// cached after it.
+    version: u64,
+}
+
+impl Lru {
+    fn get(&mut self, id: &str) -> Option<Arc<Tagged>> {
+        self.tick += 1;
+        let (body, used) = self.entries.get_mut(id)?;
+        self.order.remove(used);
+        *used = self.tick;
+        self.order.insert(self.tick, id.to_owned());
+        Some(body.clone())
+    }
+
+    fn insert(&mut self, id: &str, body: Arc<Tagged>, capacity: usize) {
+        self.tick += 1;
+        if let Some((_, used)) = self.entries.insert(id.to_owned(), (body, self.tick)) {
+            self.order.remove(&used);
+        }
+        self.order.insert(self.tick, id.to_owned());
+        while self.entries.len() > capacity {
+            let Some((_, oldest)) = self.order.pop_first() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+    }
+
+    fn remove(&mut self, id: &str) {
+        if let Some((_, used)) = self.entries.remove(id) {
+            self.order.remove(&used);
+        }
+    }
+}
+
+/// [`PostsProvider`] decorator keeping the JSON of recently read posts, with their entity tags
+/// (see [`get_tagged`](PostsProvider::get_tagged)), in a bounded LRU cache, so repeated reads of
+/// hot posts skip the wrapped provider, serialization and hashing entirely. An entry is a version
+/// of a post, keyed by its ID and tagged with its `ETag`; a write makes a new version, with
+/// another tag.
+///
+/// Entries belong to the version of the store they were read at: every write through the
+/// provider drops the entries of the posts it touches, whether it succeeds or not, and bumps the
+/// version, so a body read concurrently with a write isn't cached. Writes which don't report
+/// their posts, like a failed [`publish_due`](PostsProvider::publish_due), clear the cache.
+/// Other methods aren't cached.
+pub struct CacheProvider {
+    inner: Arc<dyn PostsProvider>,
+    lru: Mutex<Lru>,
+
+    /// Maximum number of cached posts.
+    capacity: usize,
+
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl CacheProvider {
+    /// Wraps `inner` with a cache of at most `capacity` posts.
+    pub fn wrapped(inner: Arc<dyn PostsProvider>, capacity: usize) -> Arc<Self> {
+        Arc::new(Self {
+            inner,
+            lru: Mutex::new(Lru::default()),
+            capacity,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        })
+    }
+
+    fn lru(&self) -> MutexGuard<'_, Lru> {
+        self.lru.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Runs the write `change`, dropping the entries of `ids` afterwards, or every entry if `ids`
+    /// returns `None`.
+    fn write<T>(
+        &self,
+        change: impl FnOnce() -> Result<T, ProviderError>,
+        ids: impl FnOnce(&Result<T, ProviderError>) -> Option<Vec<String>>,
+    ) -> Result<T, ProviderError> {
+        let result = change();
+        let mut lru = self.lru();
+        lru.version += 1;
+        match ids(&result) {
+            Some(ids) => ids.iter().for_each(|id| lru.remove(id)),
+            None => {
+                lru.entries.clear();
+                lru.order.clear();
+            }
+        }
+        result
+    }
+}
+
+impl Provider for CacheProvider {}
+
+impl ProviderStats for CacheProvider {
+    fn stats(&self) -> Value {
+        let lru = self.lru();
+        json!({
+            "type": "cache",
+            "capacity": self.capacity,
+            "posts": lru.entries.len(),
+            "bytes": lru.entries.values().map(|(body, _)| body.json.len()).sum::<usize>(),
+            "hits": self.hits.load(Ordering::Relaxed),
+            "misses": self.misses.load(Ordering::Relaxed),
+            "inner": self.inner.stats(),
+        })
+    }
+}
+
+impl PostsProvider for CacheProvider {
+    fn get_all(&self) -> Result<Vec<Post>, ProviderError> {
+        self.inner.get_all()
+    }
+
+    fn get(&self, id: &str) -> Result<Option<Post>, ProviderError> {
+        self.inner.get(id)
+    }
+
+    fn get_json(&self, id: &str) -> Result<Option<Vec<u8>>, ProviderError> {
+        Ok(self.get_tagged(id, false)?.map(|tagged| tagged.json))
+    }
+
+    /// Serves cached posts, caching the others, however they're read; missing posts aren't
+    /// cached.
+    fn get_tagged(&self, id: &str, decode: bool) -> Result<Option<Tagged>, ProviderError> {
+        let version = {
+            let mut lru = self.lru();
+            if let Some(tagged) = lru.get(id) {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(Some(Tagged::clone(&tagged)));
+            }
+            lru.version
+        };
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let Some(tagged) = self.inner.get_tagged(id, decode)? else {
+            return Ok(None);
+        };
+        let mut lru = self.lru();
+        if lru.version == version {
+            lru.insert(id, Arc::new(tagged.clone()), self.capacity);
+        }
+        Ok(Some(tagged))
+    }
+
+    fn create(&self, input: PostInput) -> Result<Post, ProviderError> {
+        // A new ID has nothing cached
+        self.write(|| self.inner.create(input), |_| Some(Vec::new()))
+    }
+
+    fn update(&self, id: &str, input: PostInput) -> Result<Option<Post>, ProviderError> {
+        self.write(
+            || self.inner.update(id, input),
+            |_| Some(vec![id.to_owned()]),
+        )
+    }
+
+    fn delete(&self, id: &str) -> Result<bool, ProviderError> {
+        self.write(|| self.inner.delete(id), |_| Some(vec![id.to_owned()]))
+    }
+
+    fn get_by_authors(
+        &self,
+        authors: &[String],
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<Post>, usize), ProviderError> {
+        self.inner.get_by_authors(authors, offset, limit)
+    }
+
+    fn get_by_date(&self, prefix: &str) -> Result<Vec<Post>, ProviderError> {
+        self.inner.get_by_date(prefix)
+    }
+
+    fn set_author(&self, ids: &[String], author: &str) -> Result<Vec<String>, ProviderError> {
+        self.write(
+            || self.inner.set_author(ids, author),
+            |_| Some(ids.to_vec()),
+        )
+    }
+
+    fn publish_due(&self, now: DateTime<Utc>) -> Result<Vec<Post>, ProviderError> {
+        self.write(
+            || self.inner.publish_due(now),
+            |result| {
+                result
+                    .as_ref()
+                    .ok()
+                    .map(|posts| posts.iter().map(|post| post.id.clone()).collect())
+            },
+        )
+    }
+
+    fn import(&self, posts: Vec<Post>) -> Result<usize, ProviderError> {
+        let ids = posts.iter().map(|post| post.id.clone()).collect();
+        self.write(|| self.inner.import(posts), |_| Some(ids))
+    }
+
+    fn explain(&self, query: &PostsQuery) -> Result<QueryPlan, ProviderError> {
+        self.inner.explain(query)
+    }
+}
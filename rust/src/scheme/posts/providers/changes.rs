@@ -0,0 +1,172 @@
+use chrono::{DateTime, Utc};
+use serde_json::{Value, json};
+use std::{
+    collections::BTreeSet,
+    hash::{BuildHasher, RandomState},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use crate::scheme::{
+    posts::*,
+    provider::{Provider, ProviderError, ProviderStats, lock},
+    replication::{Change, Changes},
+};
+
+/// Number of locks the IDs of posts are spread over (see [`ChangesProvider::locks`]).
+const ID_LOCKS: usize = 64;
+
+/// [`PostsProvider`] decorator publishing every successful write to [`Changes`], so replicas can
+/// follow them (see [`crate::scheme::replication`]).
+///
+/// # Ordering
+/// A write of a post and the publication of its change hold the lock of its ID, so the changes of
+/// a post are published in the order they were stored: replicas applying the stream in order end
+/// up with the primary's version, e.g. an update racing a delete can't bring the post back.
+/// Creations get new IDs, so they aren't locked.
+pub struct ChangesProvider {
+    inner: Arc<dyn PostsProvider>,
+    changes: Arc<Changes>,
+
+    /// Locks of the posts, each guarding the IDs hashed to it.
+    locks: Vec<Mutex<()>>,
+
+    /// Hashes IDs to their locks.
+    hasher: RandomState,
+}
+
+impl ChangesProvider {
+    pub fn wrapped(inner: Arc<dyn PostsProvider>, changes: Arc<Changes>) -> Arc<Self> {
+        Arc::new(Self {
+            inner,
+            changes,
+            locks: (0..ID_LOCKS).map(|_| Mutex::new(())).collect(),
+            hasher: RandomState::new(),
+        })
+    }
+
+    /// Returns the index of the lock of `id`.
+    fn lock_of(&self, id: &str) -> usize {
+        self.hasher.hash_one(id) as usize % self.locks.len()
+    }
+
+    /// Locks the post with `id`.
+    fn lock(&self, id: &str) -> MutexGuard<'_, ()> {
+        lock(&self.locks[self.lock_of(id)])
+    }
+
+    /// Locks the posts with `ids`, taking their locks in order so concurrent callers can't
+    /// deadlock.
+    fn lock_ids<'a>(&self, ids: impl IntoIterator<Item = &'a str>) -> Vec<MutexGuard<'_, ()>> {
+        let indexes: BTreeSet<usize> = ids.into_iter().map(|id| self.lock_of(id)).collect();
+        indexes
+            .into_iter()
+            .map(|index| lock(&self.locks[index]))
+            .collect()
+    }
+
+    /// Locks every post, in the order of [`lock_ids`](Self::lock_ids).
+    fn lock_every(&self) -> Vec<MutexGuard<'_, ()>> {
+        self.locks.iter().map(lock).collect()
+    }
+
+    fn upserted(&self, posts: impl IntoIterator<Item = Post>) {
+        self.changes
+            .publish(posts.into_iter().map(|post| Change::Upsert { post }));
+    }
+}
+
+impl Provider for ChangesProvider {}
+
+impl ProviderStats for ChangesProvider {
+    fn stats(&self) -> Value {
+        json!({
+            "type": "changes",
+            "changes": self.changes.status(),
+            "inner": self.inner.stats(),
+        })
+    }
+}
+
+impl PostsProvider for ChangesProvider {
+    fn get_all(&self) -> Result<Vec<Post>, ProviderError> {
+        self.inner.get_all()
+    }
+
+    fn get(&self, id: &str) -> Result<Option<Post>, ProviderError> {
+        self.inner.get(id)
+    }
+
+    fn get_json(&self, id: &str) -> Result<Option<Vec<u8>>, ProviderError> {
+        self.inner.get_json(id)
+    }
+
+    fn create(&self, input: PostInput) -> Result<Post, ProviderError> {
+        let post = self.inner.create(input)?;
+        self.upserted([post.clone()]);
+        Ok(post)
+    }
+
+    fn update(&self, id: &str, input: PostInput) -> Result<Option<Post>, ProviderError> {
+        let _lock = self.lock(id);
+        let post = self.inner.update(id, input)?;
+        self.upserted(post.clone());
+        Ok(post)
+    }
+
+    fn delete(&self, id: &str) -> Result<bool, ProviderError> {
+        let _lock = self.lock(id);
+        let deleted = self.inner.delete(id)?;
+        if deleted {
+            self.changes.publish([Change::Delete { id: id.to_owned() }]);
+        }
+        Ok(deleted)
+    }
+
+    fn get_by_authors(
+        &self,
+        authors: &[String],
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<Post>, usize), ProviderError> {
+        self.inner.get_by_authors(authors, offset, limit)
+    }
+
+    fn get_by_date(&self, prefix: &str) -> Result<Vec<Post>, ProviderError> {
+        self.inner.get_by_date(prefix)
+    }
+
+    /// Published changes carry the whole posts, which are read back after the update.
+    fn set_author(&self, ids: &[String], author: &str) -> Result<Vec<String>, ProviderError> {
+        let _locks = self.lock_ids(ids.iter().map(String::as_str));
+        let updated = self.inner.set_author(ids, author)?;
+        if self.changes.is_watched() {
+            let mut posts = Vec::with_capacity(updated.len());
+            for id in updated.iter() {
+                posts.extend(self.inner.get(id)?);
+            }
+            self.upserted(posts);
+        }
+        Ok(updated)
+    }
+
+    /// Locks every post, as the posts due are only known once they're published.
+    fn publish_due(&self, now: DateTime<Utc>) -> Result<Vec<Post>, ProviderError> {
+        let _locks = self.lock_every();
+        let published = self.inner.publish_due(now)?;
+        self.upserted(published.clone());
+        Ok(published)
+    }
+
+    /// Posts are only copied while somebody follows the changes.
+    fn import(&self, posts: Vec<Post>) -> Result<usize, ProviderError> {
+        let _locks = self.lock_ids(posts.iter().map(|post| post.id.as_str()));
+        let copies = self.changes.is_watched().then(|| posts.clone());
+        let imported = self.inner.import(posts)?;
+        self.upserted(copies.unwrap_or_default());
+        Ok(imported)
+    }
+
+    fn explain(&self, query: &PostsQuery) -> Result<QueryPlan, ProviderError> {
+        self.inner.explain(query)
+    }
+}
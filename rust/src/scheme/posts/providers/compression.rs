@@ -0,0 +1,167 @@
+//! Transparent compression of the content of stored posts.
+//!
+//! Posts are compressed when they're stored and decompressed whenever they're read, so memory is
+//! traded for CPU on every request. Contents which don't shrink (e.g. short ones) are kept as they
+//! are. The cost shows up in the `content` section of `/metrics` and in the latency reported by
+//! the load generator.
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Instant,
+};
+use tracing::error;
+
+use crate::state::Metrics;
+
+/// Compression level of zstd; the default of the zstd CLI, balancing speed and ratio.
+const ZSTD_LEVEL: i32 = 3;
+
+/// Compression algorithm of stored post content.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Codec {
+    /// Content is stored as it is.
+    #[default]
+    None,
+
+    /// LZ4: fast, moderate ratio.
+    Lz4,
+
+    /// Zstandard: slower, better ratio.
+    Zstd,
+}
+
+impl Codec {
+    /// Returns the codec with the given name (`none`, `lz4` or `zstd`).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(Self::None),
+            "lz4" => Some(Self::Lz4),
+            "zstd" => Some(Self::Zstd),
+            _ => None,
+        }
+    }
+}
+
+/// Content of a stored post.
+pub enum Content {
+    Plain(String),
+
+    /// Content compressed with the codec of the store, along with its original length.
+    Compressed {
+        bytes: Box<[u8]>,
+        len: usize,
+    },
+}
+
+impl Content {
+    /// Returns the number of bytes held.
+    pub fn stored_len(&self) -> usize {
+        match self {
+            Self::Plain(content) => content.len(),
+            Self::Compressed { bytes, .. } => bytes.len(),
+        }
+    }
+
+    /// Returns the length of the original content.
+    pub fn raw_len(&self) -> usize {
+        match self {
+            Self::Plain(content) => content.len(),
+            Self::Compressed { len, .. } => *len,
+        }
+    }
+}
+
+/// Compresses and decompresses post content with a [`Codec`], accounting sizes and time spent in
+/// [`Metrics`].
+#[derive(Clone)]
+pub struct Compression {
+    codec: Codec,
+    metrics: Arc<Metrics>,
+}
+
+impl Compression {
+    pub fn new(codec: Codec, metrics: Arc<Metrics>) -> Self {
+        Self { codec, metrics }
+    }
+
+    /// Compresses `content`, unless compression doesn't make it smaller.
+    pub fn compress(&self, content: String) -> Content {
+        let started = Instant::now();
+        let bytes = match self.codec {
+            Codec::None => None,
+            Codec::Lz4 => Some(lz4_flex::compress(content.as_bytes())),
+            Codec::Zstd => zstd::bulk::compress(content.as_bytes(), ZSTD_LEVEL).ok(),
+        };
+        if self.codec != Codec::None {
+            self.record(
+                &self.metrics.content_compressions,
+                &self.metrics.content_compression_ns,
+                started,
+            );
+        }
+        match bytes {
+            Some(bytes) if bytes.len() < content.len() => Content::Compressed {
+                bytes: bytes.into_boxed_slice(),
+                len: content.len(),
+            },
+            _ => Content::Plain(content),
+        }
+    }
+
+    /// Returns a copy of the original content.
+    pub fn decompress(&self, content: &Content) -> String {
+        let (bytes, len) = match content {
+            Content::Plain(content) => return content.clone(),
+            Content::Compressed { bytes, len } => (bytes, *len),
+        };
+        let started = Instant::now();
+        let decompressed = match self.codec {
+            Codec::None => Ok(bytes.to_vec()),
+            Codec::Lz4 => lz4_flex::decompress(bytes, len).map_err(|err| err.to_string()),
+            Codec::Zstd => zstd::bulk::decompress(bytes, len).map_err(|err| err.to_string()),
+        };
+        self.record(
+            &self.metrics.content_decompressions,
+            &self.metrics.content_decompression_ns,
+            started,
+        );
+        // The content was compressed from a string by the same codec, so this can't fail
+        // unless memory is corrupted
+        decompressed
+            .and_then(|bytes| String::from_utf8(bytes).map_err(|err| err.to_string()))
+            .unwrap_or_else(|err| {
+                error!("Fail to decompress post content: {err}");
+                String::new()
+            })
+    }
+
+    /// Accounts `content` being stored.
+    pub fn stored(&self, content: &Content) {
+        let metrics = &self.metrics;
+        metrics
+            .content_raw_bytes
+            .fetch_add(content.raw_len() as u64, Ordering::Relaxed);
+        metrics
+            .content_stored_bytes
+            .fetch_add(content.stored_len() as u64, Ordering::Relaxed);
+    }
+
+    /// Accounts `content` being dropped.
+    pub fn dropped(&self, content: &Content) {
+        let metrics = &self.metrics;
+        metrics
+            .content_raw_bytes
+            .fetch_sub(content.raw_len() as u64, Ordering::Relaxed);
+        metrics
+            .content_stored_bytes
+            .fetch_sub(content.stored_len() as u64, Ordering::Relaxed);
+    }
+
+    fn record(&self, count: &AtomicU64, total_ns: &AtomicU64, started: Instant) {
+        Metrics::inc(count);
+        total_ns.fetch_add(started.elapsed().as_nanos() as u64, Ordering::Relaxed);
+    }
+}
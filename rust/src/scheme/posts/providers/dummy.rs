@@ -1,10 +1,167 @@
+use chrono::{DateTime, Utc};
+use serde_json::{Value, json};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
+    mem,
     sync::{Arc, RwLock},
 };
 use uuid::Uuid;
 
-use crate::scheme::{posts::*, provider::Provider};
+use super::{
+    compression::{Compression, Content},
+    index::{Index, KeyIndex, OrderedIndex},
+};
+use crate::scheme::{
+    posts::*,
+    provider::{LockWaits, Provider, ProviderError, ProviderStats},
+};
+
+/// Internal storage of [`DummyProvider`].
+///
+/// Posts and their indexes live behind the same lock, so they can never be observed out of sync.
+///
+/// # Indexes
+/// Indexes are declared as fields and listed in [`Store::indexes`]; they're maintained by every
+/// write, which must go through [`Store::insert`], [`Store::remove`] or [`Store::modify`]. Query
+/// methods only look them up.
+///
+/// # Content
+/// The content of a post is stored apart from the rest of it, compressed with the codec of the
+/// store (see [`Compression`]), and restored by [`Store::load`]. Indexes never see it.
+struct Store {
+    /// All posts, keyed by ID.
+    posts: HashMap<String, Stored>,
+
+    /// Author name to IDs of their posts. Used by the feed fan-out.
+    by_author: KeyIndex<String>,
+
+    /// Scheduled posts ordered by publication time. Used by the scheduler to find due posts.
+    scheduled: OrderedIndex<DateTime<Utc>>,
+
+    compression: Compression,
+}
+
+/// A post as it's kept in the [`Store`].
+struct Stored {
+    /// The post with an empty `content`.
+    post: Post,
+
+    content: Content,
+}
+
+impl Store {
+    fn new(compression: Compression) -> Self {
+        Self {
+            posts: HashMap::new(),
+            by_author: KeyIndex::new("by_author", |post| Some(post.author.clone())),
+            scheduled: OrderedIndex::new("scheduled", |post| match post.status {
+                PostStatus::Scheduled => post.publish_at,
+                PostStatus::Published => None,
+            }),
+            compression,
+        }
+    }
+
+    /// Returns all indexes of the store.
+    fn indexes(&mut self) -> [&mut dyn Index; 2] {
+        [&mut self.by_author, &mut self.scheduled]
+    }
+
+    /// Inserts or replaces a post, keeping the indexes consistent.
+    fn insert(&mut self, mut post: Post) {
+        if let Some(prev) = self.posts.remove(&post.id) {
+            self.unindex(&prev.post);
+            self.compression.dropped(&prev.content);
+        }
+        let content = self.compression.compress(mem::take(&mut post.content));
+        self.compression.stored(&content);
+        for index in self.indexes() {
+            index.insert(&post);
+        }
+        self.posts.insert(post.id.clone(), Stored { post, content });
+    }
+
+    /// Removes a post and its index entries. Returns `true` if the post existed.
+    fn remove(&mut self, id: &str) -> bool {
+        match self.posts.remove(id) {
+            Some(prev) => {
+                self.unindex(&prev.post);
+                self.compression.dropped(&prev.content);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Changes a stored post with `change`, keeping the indexes consistent. Returns the changed
+    /// post, or `None` if there's no post with `id`.
+    ///
+    /// `change` gets the post without its content, which is kept as it is.
+    fn modify(&mut self, id: &str, change: impl FnOnce(&mut Post)) -> Option<Post> {
+        let mut stored = self.posts.remove(id)?;
+        self.unindex(&stored.post);
+        change(&mut stored.post);
+        for index in self.indexes() {
+            index.insert(&stored.post);
+        }
+        let post = self.load(&stored);
+        self.posts.insert(stored.post.id.clone(), stored);
+        Some(post)
+    }
+
+    /// Returns a copy of a stored post, including its content.
+    fn load(&self, stored: &Stored) -> Post {
+        Post {
+            content: self.compression.decompress(&stored.content),
+            ..stored.post.clone()
+        }
+    }
+
+    /// Picks the access path for `query`, mirroring the query methods: the author index for author
+    /// filters (the feed), the schedule index for scheduled posts (the scheduler), a full scan
+    /// otherwise.
+    fn plan(&self, query: &PostsQuery) -> QueryPlan {
+        let mut filters = Vec::new();
+        let (access, index, candidates): (_, Option<&dyn Index>, _) = if !query.authors.is_empty() {
+            let authors: HashSet<_> = query.authors.iter().collect();
+            let candidates = authors
+                .into_iter()
+                .map(|author| self.by_author.get(author).count())
+                .sum();
+            (Access::IndexLookup, Some(&self.by_author), candidates)
+        } else if query.due.is_some() || query.status == Some(PostStatus::Scheduled) {
+            let candidates = match &query.due {
+                Some(due) => self.scheduled.up_to(due).count(),
+                None => self.scheduled.len(),
+            };
+            (Access::IndexRange, Some(&self.scheduled), candidates)
+        } else {
+            (Access::FullScan, None, self.posts.len())
+        };
+        // The schedule index holds scheduled posts only and is ordered by publication time, so it
+        // covers both terms
+        if access != Access::IndexRange {
+            filters.extend(query.due.map(|_| "due"));
+        }
+        if access != Access::IndexRange || query.status == Some(PostStatus::Published) {
+            filters.extend(query.status.map(|_| "status"));
+        }
+        filters.extend(query.date.as_ref().map(|_| "date"));
+        QueryPlan {
+            access,
+            index: index.map(|index| index.name()),
+            filters,
+            candidates,
+            total: self.posts.len(),
+        }
+    }
+
+    fn unindex(&mut self, post: &Post) {
+        for index in self.indexes() {
+            index.remove(post);
+        }
+    }
+}
 
 /// In-memory implementation of the [`PostsProvider`] trait for testing and demonstration purposes.
 ///
@@ -13,7 +170,8 @@ use crate::scheme::{posts::*, provider::Provider};
 /// property-based testing, or examples.
 ///
 /// Each method acquires a read or write lock on the underlying store to ensure safe access
-/// across multiple threads.
+/// across multiple threads. A lock poisoned by a panic is recovered from (see
+/// [`read`](crate::scheme::provider::read)), so the provider never fails.
 ///
 /// # Concurrency
 /// Internally uses `Arc<RwLock<HashMap<String, Post>>>`, which allows shared access from multiple threads
@@ -23,17 +181,21 @@ use crate::scheme::{posts::*, provider::Provider};
 /// - Data is not persisted between runs.
 /// - Not optimized for large-scale production use.
 pub struct DummyProvider {
-    store: RwLock<HashMap<String, Post>>,
+    store: RwLock<Store>,
+
+    /// Waits for the lock of `store`.
+    locks: LockWaits,
 }
 
 impl DummyProvider {
-    /// Constructs a new `DummyProvider` instance without wrapping it in an `Arc`.
+    /// Constructs a new `DummyProvider` instance without wrapping it in an `Arc`, storing post
+    /// content with `compression`.
     ///
     /// This constructor is mainly useful for local or internal usage when shared ownership is not required.
-    #[allow(dead_code)]
-    pub fn new() -> Self {
+    pub fn new(compression: Compression) -> Self {
         Self {
-            store: RwLock::new(HashMap::new()),
+            store: RwLock::new(Store::new(compression)),
+            locks: LockWaits::default(),
         }
     }
 
@@ -41,64 +203,195 @@ impl DummyProvider {
     ///
     /// This is the recommended way to instantiate the provider in contexts where shared ownership is needed,
     /// such as within Actix-Web app data or multithreaded test runners.
-    pub fn wrapped() -> Arc<Self> {
-        Arc::new(Self {
-            store: RwLock::new(HashMap::new()),
-        })
+    pub fn wrapped(compression: Compression) -> Arc<Self> {
+        Arc::new(Self::new(compression))
+    }
+
+    /// Stores a fully built post as is, replacing any post with the same ID.
+    ///
+    /// Used by providers layered on top of this one, e.g. to replay persisted posts.
+    pub fn put(&self, post: Post) {
+        self.locks.write(&self.store).insert(post);
     }
 }
 
 impl Provider for DummyProvider {}
 
+impl ProviderStats for DummyProvider {
+    fn stats(&self) -> Value {
+        let store = self.locks.read(&self.store);
+        let (raw, stored) = store.posts.values().fold((0, 0), |(raw, stored), post| {
+            (
+                raw + post.content.raw_len(),
+                stored + post.content.stored_len(),
+            )
+        });
+        json!({
+            "type": "memory",
+            "posts": store.posts.len(),
+            "indexes": {
+                "by_author": store.by_author.len(),
+                "scheduled": store.scheduled.len(),
+            },
+            "content": {
+                "raw_bytes": raw,
+                "stored_bytes": stored,
+            },
+            "locks": self.locks.stats(),
+        })
+    }
+}
+
 impl PostsProvider for DummyProvider {
     /// Returns all stored posts as a `Vec<Post>`, cloned from the internal map.
-    fn get_all(&self) -> Vec<Post> {
-        self.store.read().unwrap().values().cloned().collect()
+    fn get_all(&self) -> Result<Vec<Post>, ProviderError> {
+        let store = self.locks.read(&self.store);
+        Ok(store
+            .posts
+            .values()
+            .map(|stored| store.load(stored))
+            .collect())
     }
 
     /// Returns the post with the specified ID, if it exists.
-    fn get(&self, id: &str) -> Option<Post> {
-        self.store.read().unwrap().get(id).cloned()
+    fn get(&self, id: &str) -> Result<Option<Post>, ProviderError> {
+        let store = self.locks.read(&self.store);
+        Ok(store.posts.get(id).map(|stored| store.load(stored)))
+    }
+
+    fn get_json(&self, id: &str) -> Result<Option<Vec<u8>>, ProviderError> {
+        self.get(id)?.as_ref().map(to_json).transpose()
     }
 
     /// Creates a new post from the given input and stores it under a generated UUID.
     ///
     /// The generated post is returned.
-    fn create(&self, input: PostInput) -> Post {
-        let id = Uuid::new_v4().to_string();
-        let post = Post {
-            id: id.clone(),
-            author: input.author,
-            date: input.date,
-            content: input.content,
-        };
-        self.store.write().unwrap().insert(id.clone(), post.clone());
-        post
+    fn create(&self, input: PostInput) -> Result<Post, ProviderError> {
+        let post = Post::new(Uuid::new_v4().to_string(), input, Utc::now());
+        self.locks.write(&self.store).insert(post.clone());
+        Ok(post)
     }
 
     /// Updates an existing post with the specified ID, replacing it with the provided input.
     ///
     /// Returns the updated post if the ID exists, or `None` otherwise.
-    fn update(&self, id: &str, input: PostInput) -> Option<Post> {
-        let mut store = self.store.write().unwrap();
-        if store.contains_key(id) {
-            let post = Post {
-                id: id.to_string(),
-                author: input.author,
-                date: input.date,
-                content: input.content,
-            };
-            store.insert(id.to_string(), post.clone());
-            Some(post)
+    fn update(&self, id: &str, input: PostInput) -> Result<Option<Post>, ProviderError> {
+        let mut store = self.locks.write(&self.store);
+        if store.posts.contains_key(id) {
+            let post = Post::new(id.to_string(), input, Utc::now());
+            store.insert(post.clone());
+            Ok(Some(post))
         } else {
-            None
+            Ok(None)
         }
     }
 
     /// Deletes the post with the given ID.
     ///
     /// Returns `true` if the post existed and was removed, or `false` if the ID was not found.
-    fn delete(&self, id: &str) -> bool {
-        self.store.write().unwrap().remove(id).is_some()
+    fn delete(&self, id: &str) -> Result<bool, ProviderError> {
+        Ok(self.locks.write(&self.store).remove(id))
+    }
+
+    /// Fans out over the author index, collecting the published posts of every requested author,
+    /// then orders them by date (newest first) and cuts the requested window.
+    fn get_by_authors(
+        &self,
+        authors: &[String],
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<Post>, usize), ProviderError> {
+        let now = Utc::now();
+        let store = self.locks.read(&self.store);
+        let mut posts: Vec<&Stored> = authors
+            .iter()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .flat_map(|author| store.by_author.get(author))
+            .filter_map(|id| store.posts.get(id))
+            .filter(|stored| stored.post.is_published(now))
+            .collect();
+        let total = posts.len();
+        posts.sort_unstable_by(|a, b| {
+            b.post
+                .date
+                .cmp(&a.post.date)
+                .then_with(|| a.post.id.cmp(&b.post.id))
+        });
+        // Only the requested window is decompressed
+        Ok((
+            posts
+                .into_iter()
+                .skip(offset)
+                .take(limit)
+                .map(|stored| store.load(stored))
+                .collect(),
+            total,
+        ))
+    }
+
+    /// Scans all posts, as dates aren't indexed.
+    fn get_by_date(&self, prefix: &str) -> Result<Vec<Post>, ProviderError> {
+        let store = self.locks.read(&self.store);
+        let mut posts: Vec<&Stored> = store
+            .posts
+            .values()
+            .filter(|stored| stored.post.utc_date().starts_with(prefix))
+            .collect();
+        posts.sort_unstable_by(|a, b| {
+            a.post
+                .date
+                .cmp(&b.post.date)
+                .then_with(|| a.post.id.cmp(&b.post.id))
+        });
+        Ok(posts.into_iter().map(|stored| store.load(stored)).collect())
+    }
+
+    /// Modifies each affected post through the store, so the author index follows the new author.
+    fn set_author(&self, ids: &[String], author: &str) -> Result<Vec<String>, ProviderError> {
+        let mut store = self.locks.write(&self.store);
+        Ok(ids
+            .iter()
+            .filter(|id| {
+                store
+                    .modify(id, |post| post.author = author.to_owned())
+                    .is_some()
+            })
+            .cloned()
+            .collect())
+    }
+
+    /// Walks the schedule index up to `now`, so only due posts are touched.
+    fn publish_due(&self, now: DateTime<Utc>) -> Result<Vec<Post>, ProviderError> {
+        // Most ticks have nothing to do; don't block readers for them
+        let nothing_due = self
+            .locks
+            .read(&self.store)
+            .scheduled
+            .first()
+            .is_none_or(|(at, _)| *at > now);
+        if nothing_due {
+            return Ok(Vec::new());
+        }
+        let mut store = self.locks.write(&self.store);
+        let due: Vec<String> = store.scheduled.up_to(&now).cloned().collect();
+        Ok(due
+            .iter()
+            .filter_map(|id| store.modify(id, |post| post.status = PostStatus::Published))
+            .collect())
+    }
+
+    /// Inserts all posts under a single write lock.
+    fn import(&self, posts: Vec<Post>) -> Result<usize, ProviderError> {
+        let mut store = self.locks.write(&self.store);
+        let count = posts.len();
+        for post in posts {
+            store.insert(post);
+        }
+        Ok(count)
+    }
+
+    fn explain(&self, query: &PostsQuery) -> Result<QueryPlan, ProviderError> {
+        Ok(self.locks.read(&self.store).plan(query))
     }
 }
@@ -0,0 +1,138 @@
+//! Secondary indexes of the in-memory posts store.
+//!
+//! An index is declared with a key function extracting the indexed value from a post; posts for
+//! which it returns `None` aren't indexed. The store keeps all its indexes up to date on every
+//! write (see [`Index`]), so query methods only look them up and never maintain them by hand.
+
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    hash::Hash,
+};
+
+use crate::scheme::posts::Post;
+
+/// Index maintained by the store on every write.
+pub trait Index: Send + Sync {
+    /// Name of the index, shown in query plans.
+    fn name(&self) -> &'static str;
+
+    /// Number of indexed posts.
+    fn len(&self) -> usize;
+
+    /// Adds `post` to the index. The post isn't indexed yet.
+    fn insert(&mut self, post: &Post);
+
+    /// Removes `post`, as it was inserted, from the index.
+    fn remove(&mut self, post: &Post);
+}
+
+/// Index grouping posts by a key, for equality lookups (e.g. all posts of an author).
+pub struct KeyIndex<K> {
+    name: &'static str,
+    key: fn(&Post) -> Option<K>,
+    entries: HashMap<K, HashSet<String>>,
+
+    /// Number of IDs over all keys.
+    len: usize,
+}
+
+impl<K: Eq + Hash> KeyIndex<K> {
+    pub fn new(name: &'static str, key: fn(&Post) -> Option<K>) -> Self {
+        Self {
+            name,
+            key,
+            entries: HashMap::new(),
+            len: 0,
+        }
+    }
+
+    /// Returns the IDs of the posts with `key`.
+    pub fn get(&self, key: &K) -> impl Iterator<Item = &String> {
+        self.entries.get(key).into_iter().flatten()
+    }
+}
+
+impl<K: Eq + Hash + Send + Sync> Index for KeyIndex<K> {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn insert(&mut self, post: &Post) {
+        let Some(key) = (self.key)(post) else {
+            return;
+        };
+        if self.entries.entry(key).or_default().insert(post.id.clone()) {
+            self.len += 1;
+        }
+    }
+
+    fn remove(&mut self, post: &Post) {
+        let Some(key) = (self.key)(post) else {
+            return;
+        };
+        if let Some(ids) = self.entries.get_mut(&key) {
+            if ids.remove(&post.id) {
+                self.len -= 1;
+            }
+            if ids.is_empty() {
+                self.entries.remove(&key);
+            }
+        }
+    }
+}
+
+/// Index ordering posts by a key, for range scans (e.g. posts due before a point in time).
+pub struct OrderedIndex<K> {
+    name: &'static str,
+    key: fn(&Post) -> Option<K>,
+    entries: BTreeSet<(K, String)>,
+}
+
+impl<K: Ord + Clone> OrderedIndex<K> {
+    pub fn new(name: &'static str, key: fn(&Post) -> Option<K>) -> Self {
+        Self {
+            name,
+            key,
+            entries: BTreeSet::new(),
+        }
+    }
+
+    /// Returns the smallest key and the ID of its post.
+    pub fn first(&self) -> Option<&(K, String)> {
+        self.entries.first()
+    }
+
+    /// Returns the IDs of the posts with keys up to `max`, in key order.
+    pub fn up_to<'a>(&'a self, max: &'a K) -> impl Iterator<Item = &'a String> {
+        self.entries
+            .iter()
+            .take_while(move |(key, _)| key <= max)
+            .map(|(_, id)| id)
+    }
+}
+
+impl<K: Ord + Clone + Send + Sync> Index for OrderedIndex<K> {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn insert(&mut self, post: &Post) {
+        if let Some(key) = (self.key)(post) {
+            self.entries.insert((key, post.id.clone()));
+        }
+    }
+
+    fn remove(&mut self, post: &Post) {
+        if let Some(key) = (self.key)(post) {
+            self.entries.remove(&(key, post.id.clone()));
+        }
+    }
+}
@@ -0,0 +1,530 @@
+use chrono::{DateTime, Utc};
+use redb::{
+    Database, ReadableTable, ReadableTableMetadata, Table, TableDefinition, WriteTransaction,
+};
+use serde_json::{Value, json};
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::scheme::{
+    posts::{versions::Stored, *},
+    provider::{Provider, ProviderError, ProviderStats},
+};
+
+/// Posts by ID, as versioned JSON (see [`Stored`]).
+const POSTS: TableDefinition<&str, &[u8]> = TableDefinition::new("posts");
+
+/// Index of posts by UTC date (see [`date_key`]) and ID, for scans of a period.
+const BY_DATE: TableDefinition<(&str, &str), ()> = TableDefinition::new("by_date");
+
+/// Index of posts by author, UTC date and ID, for the feed. The value is the publication time of
+/// a scheduled post and empty for a published one, so the feed skips hidden posts without
+/// reading them.
+const BY_AUTHOR: TableDefinition<(&str, &str, &str), &str> = TableDefinition::new("by_author");
+
+/// Index of scheduled posts by publication time and ID, for the scheduler.
+const SCHEDULED: TableDefinition<(&str, &str), ()> = TableDefinition::new("scheduled");
+
+/// Properties of the store, see [`PRECISION`].
+const META: TableDefinition<&str, &str> = TableDefinition::new("meta");
+
+/// Key in [`META`] of the date precision (see [`date::Precision`]) the posts were written with,
+/// or [`MIXED`] once the store was opened with another one.
+const PRECISION: &str = "precision";
+
+/// Value of [`PRECISION`] for stores holding dates of both precisions.
+const MIXED: &str = "mixed";
+
+/// Persistent implementation of the [`PostsProvider`] trait on an embedded key-value store
+/// ([redb](https://docs.rs/redb)), a middle ground between the in-memory providers and an external
+/// database: the posts live in a single file and only the pages being used are read into memory.
+///
+/// Posts are stored as versioned JSON, like WAL records, next to ordered indexes kept in the same
+/// transaction: by date for [`get_by_date`](PostsProvider::get_by_date), which walks the range of
+/// its prefix, by author for the feed and by publication time for the scheduler. Listing iterates
+/// over the posts table instead of copying a map. Content compression doesn't apply.
+///
+/// # Durability
+/// Every mutation is a transaction, committed (and synced to the device) before it's answered; a
+/// failed mutation leaves the store untouched, so retries are safe.
+///
+/// # Concurrency
+/// Readers see the last committed state and never wait; writers are serialized by the store.
+///
+/// # Reads without decoding
+/// [`get_json`](PostsProvider::get_json) serves the stored JSON as it is, with the `version` cut
+/// off, as long as every post was written with the current date precision; the store records the
+/// precision it's created with, and a store ever opened with another one decodes posts instead.
+pub struct KvProvider {
+    db: Database,
+
+    /// Path of the database file.
+    path: PathBuf,
+
+    /// Whether stored posts are served without decoding (see the documentation of the type).
+    pass_through: bool,
+}
+
+/// Reports a storage error as [`ProviderError::Internal`].
+fn internal(err: impl Into<redb::Error>) -> ProviderError {
+    ProviderError::Internal(err.into().to_string())
+}
+
+/// Decodes a stored post.
+fn decode(bytes: &[u8]) -> Result<Post, ProviderError> {
+    serde_json::from_slice::<Stored>(bytes)
+        .map(|Stored(post)| post)
+        .map_err(|err| ProviderError::Internal(format!("corrupted post: {err}")))
+}
+
+/// Returns the post with `id` from `posts`, if any.
+fn load(
+    posts: &impl ReadableTable<&'static str, &'static [u8]>,
+    id: &str,
+) -> Result<Option<Post>, ProviderError> {
+    match posts.get(id).map_err(internal)? {
+        Some(bytes) => decode(bytes.value()).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Tables of a write transaction.
+struct Tables<'txn> {
+    posts: Table<'txn, &'static str, &'static [u8]>,
+    by_date: Table<'txn, (&'static str, &'static str), ()>,
+    by_author: Table<'txn, (&'static str, &'static str, &'static str), &'static str>,
+    scheduled: Table<'txn, (&'static str, &'static str), ()>,
+}
+
+impl<'txn> Tables<'txn> {
+    fn open(txn: &'txn WriteTransaction) -> Result<Self, ProviderError> {
+        Ok(Self {
+            posts: txn.open_table(POSTS).map_err(internal)?,
+            by_date: txn.open_table(BY_DATE).map_err(internal)?,
+            by_author: txn.open_table(BY_AUTHOR).map_err(internal)?,
+            scheduled: txn.open_table(SCHEDULED).map_err(internal)?,
+        })
+    }
+
+    /// Inserts or replaces a post, keeping the indexes consistent.
+    fn put(&mut self, post: &Post) -> Result<(), ProviderError> {
+        if let Some(prev) = load(&self.posts, &post.id)? {
+            self.unindex(&prev)?;
+        }
+        let bytes = serde_json::to_vec(&Stored(post.clone()))
+            .map_err(|err| ProviderError::Internal(err.to_string()))?;
+        self.posts
+            .insert(post.id.as_str(), bytes.as_slice())
+            .map_err(internal)?;
+        let date = post.utc_date();
+        let id = post.id.as_str();
+        self.by_date
+            .insert((date.as_str(), id), ())
+            .map_err(internal)?;
+        let publish_at = scheduled_at(post);
+        self.by_author
+            .insert(
+                (post.author.as_str(), date.as_str(), id),
+                publish_at.as_deref().unwrap_or_default(),
+            )
+            .map_err(internal)?;
+        if let Some(at) = publish_at {
+            self.scheduled
+                .insert((at.as_str(), id), ())
+                .map_err(internal)?;
+        }
+        Ok(())
+    }
+
+    /// Removes a post and its index entries. Returns `true` if the post existed.
+    fn remove(&mut self, id: &str) -> Result<bool, ProviderError> {
+        let Some(prev) = load(&self.posts, id)? else {
+            return Ok(false);
+        };
+        self.unindex(&prev)?;
+        self.posts.remove(id).map_err(internal)?;
+        Ok(true)
+    }
+
+    fn unindex(&mut self, post: &Post) -> Result<(), ProviderError> {
+        let date = post.utc_date();
+        let id = post.id.as_str();
+        self.by_date.remove((date.as_str(), id)).map_err(internal)?;
+        self.by_author
+            .remove((post.author.as_str(), date.as_str(), id))
+            .map_err(internal)?;
+        if let Some(at) = scheduled_at(post) {
+            self.scheduled.remove((at.as_str(), id)).map_err(internal)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the key of the publication time of a scheduled post.
+fn scheduled_at(post: &Post) -> Option<String> {
+    match post.status {
+        PostStatus::Scheduled => post.publish_at.map(date_key),
+        PostStatus::Published => None,
+    }
+}
+
+impl KvProvider {
+    /// Opens (or creates) the store at `path` and returns the provider wrapped in an `Arc`.
+    ///
+    /// # Errors
+    /// Returns an `io::Error` if the file can't be opened, e.g. because another process holds it.
+    pub fn open(path: &Path) -> io::Result<Arc<Self>> {
+        let db = Database::create(path).map_err(io::Error::other)?;
+        // Read transactions can't create tables
+        let txn = db.begin_write().map_err(io::Error::other)?;
+        let empty = Tables::open(&txn)
+            .map_err(io::Error::other)?
+            .posts
+            .is_empty()
+            .map_err(io::Error::other)?;
+        let pass_through = {
+            let mut meta = txn.open_table(META).map_err(io::Error::other)?;
+            let current = date::precision().name();
+            let stored = meta
+                .get(PRECISION)
+                .map_err(io::Error::other)?
+                .map(|value| value.value().to_owned());
+            let precision = match stored.as_deref() {
+                Some(stored) if stored != current => MIXED,
+                // Posts written before the precision was recorded may have either
+                None if !empty => MIXED,
+                _ => current,
+            };
+            meta.insert(PRECISION, precision)
+                .map_err(io::Error::other)?;
+            precision == current
+        };
+        txn.commit().map_err(io::Error::other)?;
+        let provider = Self {
+            db,
+            path: path.to_owned(),
+            pass_through,
+        };
+        debug!(
+            "Key-value store {} opened: {} posts",
+            path.display(),
+            provider.count().map_err(io::Error::other)?
+        );
+        Ok(Arc::new(provider))
+    }
+
+    /// Runs `change` in a write transaction, committing it if `change` succeeds.
+    fn write<T>(
+        &self,
+        change: impl FnOnce(&mut Tables) -> Result<T, ProviderError>,
+    ) -> Result<T, ProviderError> {
+        let txn = self.db.begin_write().map_err(internal)?;
+        let result = {
+            let mut tables = Tables::open(&txn)?;
+            change(&mut tables)?
+        };
+        txn.commit().map_err(internal)?;
+        Ok(result)
+    }
+
+    fn count(&self) -> Result<u64, ProviderError> {
+        let txn = self.db.begin_read().map_err(internal)?;
+        txn.open_table(POSTS)
+            .map_err(internal)?
+            .len()
+            .map_err(internal)
+    }
+
+    /// Picks the access path for `query`, mirroring the query methods: the author index for author
+    /// filters, the schedule index for scheduled posts, the date index for a period and a full scan
+    /// otherwise.
+    fn plan(&self, query: &PostsQuery) -> Result<QueryPlan, ProviderError> {
+        let txn = self.db.begin_read().map_err(internal)?;
+        let total = txn
+            .open_table(POSTS)
+            .map_err(internal)?
+            .len()
+            .map_err(internal)? as usize;
+        let mut filters = Vec::new();
+        let (access, index, candidates) = if !query.authors.is_empty() {
+            let by_author = txn.open_table(BY_AUTHOR).map_err(internal)?;
+            let mut candidates = 0;
+            for author in query.authors.iter().collect::<HashSet<_>>() {
+                for entry in by_author
+                    .range((author.as_str(), "", "")..)
+                    .map_err(internal)?
+                {
+                    if entry.map_err(internal)?.0.value().0 != author {
+                        break;
+                    }
+                    candidates += 1;
+                }
+            }
+            (Access::IndexLookup, Some("by_author"), candidates)
+        } else if query.due.is_some() || query.status == Some(PostStatus::Scheduled) {
+            let scheduled = txn.open_table(SCHEDULED).map_err(internal)?;
+            let candidates = match &query.due {
+                Some(due) => {
+                    let due = date_key(*due);
+                    let mut candidates = 0;
+                    for entry in scheduled.iter().map_err(internal)? {
+                        if entry.map_err(internal)?.0.value().0 > due.as_str() {
+                            break;
+                        }
+                        candidates += 1;
+                    }
+                    candidates
+                }
+                None => scheduled.len().map_err(internal)? as usize,
+            };
+            (Access::IndexRange, Some("scheduled"), candidates)
+        } else if let Some(prefix) = &query.date {
+            let by_date = txn.open_table(BY_DATE).map_err(internal)?;
+            let mut candidates = 0;
+            for entry in by_date.range((prefix.as_str(), "")..).map_err(internal)? {
+                let (key, _) = entry.map_err(internal)?;
+                if !key.value().0.starts_with(prefix.as_str()) {
+                    break;
+                }
+                candidates += 1;
+            }
+            (Access::IndexRange, Some("by_date"), candidates)
+        } else {
+            (Access::FullScan, None, total)
+        };
+        // The schedule index holds scheduled posts only and is ordered by publication time, so it
+        // covers both terms; the date index covers the date
+        if index != Some("scheduled") {
+            filters.extend(query.due.map(|_| "due"));
+        }
+        if index != Some("scheduled") || query.status == Some(PostStatus::Published) {
+            filters.extend(query.status.map(|_| "status"));
+        }
+        if index != Some("by_date") {
+            filters.extend(query.date.as_ref().map(|_| "date"));
+        }
+        Ok(QueryPlan {
+            access,
+            index,
+            filters,
+            candidates,
+            total,
+        })
+    }
+}
+
+impl Provider for KvProvider {}
+
+impl ProviderStats for KvProvider {
+    fn stats(&self) -> Value {
+        let lens = || -> Result<[u64; 4], ProviderError> {
+            let txn = self.db.begin_read().map_err(internal)?;
+            let len = |len: Result<u64, redb::StorageError>| len.map_err(internal);
+            Ok([
+                len(txn.open_table(POSTS).map_err(internal)?.len())?,
+                len(txn.open_table(BY_DATE).map_err(internal)?.len())?,
+                len(txn.open_table(BY_AUTHOR).map_err(internal)?.len())?,
+                len(txn.open_table(SCHEDULED).map_err(internal)?.len())?,
+            ])
+        };
+        let (posts, indexes) = match lens() {
+            Ok([posts, by_date, by_author, scheduled]) => (
+                json!(posts),
+                json!({ "by_date": by_date, "by_author": by_author, "scheduled": scheduled }),
+            ),
+            Err(err) => (json!(null), json!({ "error": err.to_string() })),
+        };
+        json!({
+            "type": "kv",
+            "path": self.path.display().to_string(),
+            "bytes": fs::metadata(&self.path).map(|meta| meta.len()).ok(),
+            "posts": posts,
+            "indexes": indexes,
+            "pass_through": self.pass_through,
+        })
+    }
+}
+
+impl PostsProvider for KvProvider {
+    /// Iterates over the posts table in ID order.
+    fn get_all(&self) -> Result<Vec<Post>, ProviderError> {
+        let txn = self.db.begin_read().map_err(internal)?;
+        let posts = txn.open_table(POSTS).map_err(internal)?;
+        posts
+            .iter()
+            .map_err(internal)?
+            .map(|entry| decode(entry.map_err(internal)?.1.value()))
+            .collect()
+    }
+
+    fn get(&self, id: &str) -> Result<Option<Post>, ProviderError> {
+        let txn = self.db.begin_read().map_err(internal)?;
+        load(&txn.open_table(POSTS).map_err(internal)?, id)
+    }
+
+    /// Cuts the `version` off the stored JSON instead of decoding it, unless the store holds
+    /// dates of another precision or the post was written by an older version.
+    fn get_json(&self, id: &str) -> Result<Option<Vec<u8>>, ProviderError> {
+        let txn = self.db.begin_read().map_err(internal)?;
+        let posts = txn.open_table(POSTS).map_err(internal)?;
+        let Some(bytes) = posts.get(id).map_err(internal)? else {
+            return Ok(None);
+        };
+        let raw = self
+            .pass_through
+            .then(|| Stored::unversioned(bytes.value()))
+            .flatten();
+        match raw {
+            Some(json) => Ok(Some(json)),
+            None => to_json(&decode(bytes.value())?).map(Some),
+        }
+    }
+
+    fn create(&self, input: PostInput) -> Result<Post, ProviderError> {
+        let post = Post::new(Uuid::new_v4().to_string(), input, Utc::now());
+        self.write(|tables| tables.put(&post))?;
+        Ok(post)
+    }
+
+    fn update(&self, id: &str, input: PostInput) -> Result<Option<Post>, ProviderError> {
+        self.write(|tables| {
+            if load(&tables.posts, id)?.is_none() {
+                return Ok(None);
+            }
+            let post = Post::new(id.to_owned(), input, Utc::now());
+            tables.put(&post)?;
+            Ok(Some(post))
+        })
+    }
+
+    fn delete(&self, id: &str) -> Result<bool, ProviderError> {
+        self.write(|tables| tables.remove(id))
+    }
+
+    /// Walks the author index of every requested author, ordering the published entries by date
+    /// (newest first); only the posts of the requested window are read.
+    fn get_by_authors(
+        &self,
+        authors: &[String],
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<Post>, usize), ProviderError> {
+        let now = date_key(Utc::now());
+        let txn = self.db.begin_read().map_err(internal)?;
+        let by_author = txn.open_table(BY_AUTHOR).map_err(internal)?;
+        let mut entries = Vec::new();
+        for author in authors.iter().collect::<HashSet<_>>() {
+            for entry in by_author
+                .range((author.as_str(), "", "")..)
+                .map_err(internal)?
+            {
+                let (key, publish_at) = entry.map_err(internal)?;
+                let (entry_author, date, id) = key.value();
+                if entry_author != author {
+                    break;
+                }
+                let publish_at = publish_at.value();
+                if publish_at.is_empty() || publish_at <= now.as_str() {
+                    entries.push((date.to_owned(), id.to_owned()));
+                }
+            }
+        }
+        let total = entries.len();
+        entries.sort_unstable_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+        let posts = txn.open_table(POSTS).map_err(internal)?;
+        let mut window = Vec::new();
+        for (_, id) in entries.into_iter().skip(offset).take(limit) {
+            window.extend(load(&posts, &id)?);
+        }
+        Ok((window, total))
+    }
+
+    /// Walks the range of `prefix` in the date index.
+    fn get_by_date(&self, prefix: &str) -> Result<Vec<Post>, ProviderError> {
+        let txn = self.db.begin_read().map_err(internal)?;
+        let by_date = txn.open_table(BY_DATE).map_err(internal)?;
+        let posts = txn.open_table(POSTS).map_err(internal)?;
+        let mut found = Vec::new();
+        for entry in by_date.range((prefix, "")..).map_err(internal)? {
+            let (key, _) = entry.map_err(internal)?;
+            let (date, id) = key.value();
+            if !date.starts_with(prefix) {
+                break;
+            }
+            found.extend(load(&posts, id)?);
+        }
+        Ok(found)
+    }
+
+    fn set_author(&self, ids: &[String], author: &str) -> Result<Vec<String>, ProviderError> {
+        self.write(|tables| {
+            let mut updated = Vec::new();
+            for id in ids {
+                let Some(mut post) = load(&tables.posts, id)? else {
+                    continue;
+                };
+                post.author = author.to_owned();
+                tables.put(&post)?;
+                updated.push(id.clone());
+            }
+            Ok(updated)
+        })
+    }
+
+    /// Walks the schedule index up to `now`; most ticks have nothing to do and don't start a
+    /// write transaction.
+    fn publish_due(&self, now: DateTime<Utc>) -> Result<Vec<Post>, ProviderError> {
+        let now = date_key(now);
+        let nothing_due = {
+            let txn = self.db.begin_read().map_err(internal)?;
+            let scheduled = txn.open_table(SCHEDULED).map_err(internal)?;
+            match scheduled.first().map_err(internal)? {
+                Some((key, _)) => key.value().0 > now.as_str(),
+                None => true,
+            }
+        };
+        if nothing_due {
+            return Ok(Vec::new());
+        }
+        self.write(|tables| {
+            let mut due = Vec::new();
+            for entry in tables.scheduled.iter().map_err(internal)? {
+                let (key, _) = entry.map_err(internal)?;
+                let (at, id) = key.value();
+                if at > now.as_str() {
+                    break;
+                }
+                due.push(id.to_owned());
+            }
+            let mut published = Vec::new();
+            for id in due {
+                if let Some(mut post) = load(&tables.posts, &id)? {
+                    post.status = PostStatus::Published;
+                    tables.put(&post)?;
+                    published.push(post);
+                }
+            }
+            Ok(published)
+        })
+    }
+
+    /// Stores all posts in a single transaction.
+    fn import(&self, posts: Vec<Post>) -> Result<usize, ProviderError> {
+        self.write(|tables| {
+            for post in posts.iter() {
+                tables.put(post)?;
+            }
+            Ok(posts.len())
+        })
+    }
+
+    fn explain(&self, query: &PostsQuery) -> Result<QueryPlan, ProviderError> {
+        self.plan(query)
+    }
+}
@@ -0,0 +1,332 @@
+use chrono::{DateTime, FixedOffset, Utc};
+use memmap2::Mmap;
+use serde_json::{Value, json};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tracing::debug;
+
+use crate::scheme::{
+    posts::{versions::Stored, *},
+    provider::{Provider, ProviderError, ProviderStats},
+};
+
+/// What [`MmapProvider`] keeps in memory about a post; the post itself stays in the file.
+struct Record {
+    /// Position of the JSON line of the post in the file.
+    range: Range<usize>,
+
+    date: DateTime<FixedOffset>,
+
+    /// Publication time of a scheduled post.
+    scheduled: Option<DateTime<Utc>>,
+
+    /// Whether the line is exactly what this build writes for the post (see
+    /// [`Stored::is_canonical`]), so [`get_json`](PostsProvider::get_json) can serve it as it is.
+    canonical: bool,
+}
+
+impl Record {
+    /// Returns `true` if the post is visible at `now` (see [`Post::is_published`]).
+    fn is_published(&self, now: DateTime<Utc>) -> bool {
+        self.scheduled.is_none_or(|at| at <= now)
+    }
+}
+
+/// Read-only implementation of the [`PostsProvider`] trait serving a JSON Lines dataset (as
+/// written by `GET /admin/posts/export?format=jsonl` or `server gen-dataset`) from a memory-mapped
+/// file, for benchmarks of static content.
+///
+/// Opening the file parses every post once, to check it and to build indexes of line positions:
+/// by ID, by author for the feed and by date for
+/// [`get_by_date`](PostsProvider::get_by_date). Afterwards only these positions are kept in
+/// memory; the posts are decoded from the mapping on every read, so the data lives in the page
+/// cache, shared with other processes mapping the same file, rather than on the heap. A post listed
+/// twice is served as its last line, like an import would store it.
+///
+/// Lines written by this build are served by [`get_json`](PostsProvider::get_json) straight from
+/// the mapping, without being decoded and encoded again; older versions and dates of another
+/// precision (see [`date::Precision`]) go through [`Post`].
+///
+/// Every write fails with [`ProviderError::Internal`]; the server runs read-only with this
+/// provider, so writes are answered with `405` before they get here. Scheduled posts become
+/// visible at their time, but keep their status, as nothing is written.
+///
+/// # Safety of the mapping
+/// The file must not be modified while it's served: changes would show through the mapping and
+/// truncating it would crash the process.
+pub struct MmapProvider {
+    map: Mmap,
+
+    /// Path of the dataset.
+    path: PathBuf,
+
+    /// Posts in the order of their first line.
+    records: Vec<Record>,
+
+    /// Index into `records` by ID.
+    ids: HashMap<String, usize>,
+
+    /// Indexes into `records` by author.
+    by_author: HashMap<String, Vec<usize>>,
+
+    /// Indexes into `records` with the UTC date of their post (see [`date_key`]), in date order.
+    by_date: Vec<(String, usize)>,
+}
+
+/// Error of writes, which this provider doesn't support.
+fn read_only() -> ProviderError {
+    ProviderError::Internal("the memory-mapped dataset is read-only".to_owned())
+}
+
+impl MmapProvider {
+    /// Maps the JSON Lines dataset at `path`, indexes it and returns the provider wrapped in an
+    /// `Arc`. Empty lines are skipped.
+    ///
+    /// # Errors
+    /// Returns an `io::Error` if the file can't be mapped or a line isn't a post, naming the line.
+    pub fn open(path: &Path) -> io::Result<Arc<Self>> {
+        let file = File::open(path)?;
+        // SAFETY: the file is only read through the mapping, and isn't expected to change while
+        // it's served (see the documentation of the type)
+        let map = unsafe { Mmap::map(&file)? };
+        let mut records = Vec::new();
+        let mut authors = Vec::new();
+        let mut ids = HashMap::new();
+        let mut start = 0;
+        for (idx, line) in map.split(|b| *b == b'\n').enumerate() {
+            let range = start..start + line.len();
+            start = range.end + 1;
+            if line.trim_ascii().is_empty() {
+                continue;
+            }
+            let Stored(post) = serde_json::from_slice(line).map_err(|err| {
+                io::Error::other(format!("{} line {}: {err}", path.display(), idx + 1))
+            })?;
+            let canonical = Stored::is_canonical(&post, line);
+            let record = Record {
+                range,
+                canonical,
+                date: post.date,
+                scheduled: match post.status {
+                    PostStatus::Scheduled => post.publish_at,
+                    PostStatus::Published => None,
+                },
+            };
+            match ids.get(&post.id) {
+                Some(&pos) => {
+                    records[pos] = record;
+                    authors[pos] = post.author;
+                }
+                None => {
+                    ids.insert(post.id, records.len());
+                    records.push(record);
+                    authors.push(post.author);
+                }
+            }
+        }
+        let mut by_author: HashMap<String, Vec<usize>> = HashMap::new();
+        for (pos, author) in authors.into_iter().enumerate() {
+            by_author.entry(author).or_default().push(pos);
+        }
+        let mut by_date: Vec<(String, usize)> = records
+            .iter()
+            .enumerate()
+            .map(|(pos, record)| (date_key(record.date.with_timezone(&Utc)), pos))
+            .collect();
+        by_date.sort_unstable();
+        debug!(
+            "Dataset {} mapped: {} posts, {} bytes",
+            path.display(),
+            records.len(),
+            map.len()
+        );
+        Ok(Arc::new(Self {
+            map,
+            path: path.to_owned(),
+            records,
+            ids,
+            by_author,
+            by_date,
+        }))
+    }
+
+    /// Decodes the post of `record` from the mapping.
+    fn load(&self, record: &Record) -> Result<Post, ProviderError> {
+        serde_json::from_slice::<Stored>(&self.map[record.range.clone()])
+            .map(|Stored(post)| post)
+            .map_err(|err| ProviderError::Internal(format!("corrupted dataset: {err}")))
+    }
+
+    /// Returns the positions in `by_date` of the posts whose UTC date starts with `prefix`.
+    fn date_range(&self, prefix: &str) -> Range<usize> {
+        let start = self
+            .by_date
+            .partition_point(|(date, _)| date.as_str() < prefix);
+        let len = self.by_date[start..]
+            .iter()
+            .take_while(|(date, _)| date.starts_with(prefix))
+            .count();
+        start..start + len
+    }
+}
+
+impl Provider for MmapProvider {}
+
+impl ProviderStats for MmapProvider {
+    fn stats(&self) -> Value {
+        json!({
+            "type": "mmap",
+            "path": self.path.display().to_string(),
+            "bytes": self.map.len(),
+            "posts": self.records.len(),
+            "canonical": self.records.iter().filter(|record| record.canonical).count(),
+            "indexes": {
+                "by_author": self.by_author.values().map(Vec::len).sum::<usize>(),
+                "by_date": self.by_date.len(),
+            },
+        })
+    }
+}
+
+impl PostsProvider for MmapProvider {
+    /// Decodes every post, in file order.
+    fn get_all(&self) -> Result<Vec<Post>, ProviderError> {
+        self.records
+            .iter()
+            .map(|record| self.load(record))
+            .collect()
+    }
+
+    fn get(&self, id: &str) -> Result<Option<Post>, ProviderError> {
+        self.ids
+            .get(id)
+            .map(|idx| self.load(&self.records[*idx]))
+            .transpose()
+    }
+
+    /// Cuts the `version` off canonical lines instead of decoding them.
+    fn get_json(&self, id: &str) -> Result<Option<Vec<u8>>, ProviderError> {
+        let Some(record) = self.ids.get(id).map(|idx| &self.records[*idx]) else {
+            return Ok(None);
+        };
+        let raw = record
+            .canonical
+            .then(|| Stored::unversioned(&self.map[record.range.clone()]))
+            .flatten();
+        match raw {
+            Some(json) => Ok(Some(json)),
+            None => to_json(&self.load(record)?).map(Some),
+        }
+    }
+
+    fn create(&self, _input: PostInput) -> Result<Post, ProviderError> {
+        Err(read_only())
+    }
+
+    fn update(&self, _id: &str, _input: PostInput) -> Result<Option<Post>, ProviderError> {
+        Err(read_only())
+    }
+
+    fn delete(&self, _id: &str) -> Result<bool, ProviderError> {
+        Err(read_only())
+    }
+
+    /// Orders the published posts of the authors by the dates kept in memory; only the posts of
+    /// the requested window are decoded.
+    fn get_by_authors(
+        &self,
+        authors: &[String],
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<Post>, usize), ProviderError> {
+        let now = Utc::now();
+        let mut found: Vec<usize> = authors
+            .iter()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .filter_map(|author| self.by_author.get(author))
+            .flatten()
+            .copied()
+            .filter(|idx| self.records[*idx].is_published(now))
+            .collect();
+        let total = found.len();
+        found.sort_unstable_by(|a, b| {
+            self.records[*b]
+                .date
+                .cmp(&self.records[*a].date)
+                .then_with(|| a.cmp(b))
+        });
+        let posts = found
+            .into_iter()
+            .skip(offset)
+            .take(limit)
+            .map(|idx| self.load(&self.records[idx]))
+            .collect::<Result<_, _>>()?;
+        Ok((posts, total))
+    }
+
+    /// Binary-searches the date index for the range of `prefix`.
+    fn get_by_date(&self, prefix: &str) -> Result<Vec<Post>, ProviderError> {
+        self.by_date[self.date_range(prefix)]
+            .iter()
+            .map(|(_, idx)| self.load(&self.records[*idx]))
+            .collect()
+    }
+
+    fn set_author(&self, _ids: &[String], _author: &str) -> Result<Vec<String>, ProviderError> {
+        Err(read_only())
+    }
+
+    /// Nothing is published, as nothing can be written; scheduled posts are visible from their
+    /// time on regardless (see [`Post::is_published`]).
+    fn publish_due(&self, _now: DateTime<Utc>) -> Result<Vec<Post>, ProviderError> {
+        Ok(Vec::new())
+    }
+
+    fn import(&self, _posts: Vec<Post>) -> Result<usize, ProviderError> {
+        Err(read_only())
+    }
+
+    /// The author and date indexes are used like those of the other providers; scheduled posts
+    /// aren't indexed, as they're never published.
+    fn explain(&self, query: &PostsQuery) -> Result<QueryPlan, ProviderError> {
+        let mut filters = Vec::new();
+        let (access, index, candidates) = if !query.authors.is_empty() {
+            let candidates = query
+                .authors
+                .iter()
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .filter_map(|author| self.by_author.get(author))
+                .map(Vec::len)
+                .sum();
+            (Access::IndexLookup, Some("by_author"), candidates)
+        } else if let Some(prefix) = &query.date {
+            (
+                Access::IndexRange,
+                Some("by_date"),
+                self.date_range(prefix).len(),
+            )
+        } else {
+            (Access::FullScan, None, self.records.len())
+        };
+        filters.extend(query.due.map(|_| "due"));
+        filters.extend(query.status.map(|_| "status"));
+        if index != Some("by_date") {
+            filters.extend(query.date.as_ref().map(|_| "date"));
+        }
+        Ok(QueryPlan {
+            access,
+            index,
+            filters,
+            candidates,
+            total: self.records.len(),
+        })
+    }
+}
@@ -1,3 +1,24 @@
+pub mod breaker;
+pub mod cache;
+pub mod changes;
+pub mod compression;
 pub mod dummy;
+mod index;
+pub mod kv;
+pub mod mmap;
+pub mod retry;
+pub mod sharded;
+pub mod tiered;
+pub mod wal;
 
+pub use breaker::*;
+pub use cache::*;
+pub use changes::*;
+pub use compression::*;
 pub use dummy::*;
+pub use kv::*;
+pub use mmap::*;
+pub use retry::*;
+pub use sharded::*;
+pub use tiered::*;
+pub use wal::*;
@@ -0,0 +1,100 @@
+use chrono::{DateTime, Utc};
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+use crate::scheme::{
+    posts::*,
+    provider::{Provider, ProviderError, ProviderStats},
+    retry::Retrier,
+};
+
+/// [`PostsProvider`] decorator retrying calls which fail with transient storage errors (see
+/// [`Retrier`]).
+///
+/// Mutations are retried as well, so the wrapped provider must leave its state untouched when a
+/// call fails; otherwise a retried `create` could store a post twice. [`WalProvider`] does so by
+/// taking the record of a failed mutation back out of its log, including a record which only
+/// made it into the write-behind buffer. Imports aren't retried.
+///
+/// Wrap it around a [`BreakerProvider`], not the other way round: the breaker then sees every
+/// failed attempt, and retries stop as soon as it opens.
+pub struct RetryProvider {
+    inner: Arc<dyn PostsProvider>,
+    retrier: Retrier,
+}
+
+impl RetryProvider {
+    /// Wraps `inner` with `retrier`.
+    pub fn wrapped(inner: Arc<dyn PostsProvider>, retrier: Retrier) -> Arc<Self> {
+        Arc::new(Self { inner, retrier })
+    }
+}
+
+impl Provider for RetryProvider {}
+
+impl ProviderStats for RetryProvider {
+    fn stats(&self) -> Value {
+        json!({
+            "type": "retry",
+            "retrier": self.retrier.status(),
+            "inner": self.inner.stats(),
+        })
+    }
+}
+
+impl PostsProvider for RetryProvider {
+    fn get_all(&self) -> Result<Vec<Post>, ProviderError> {
+        self.retrier.call(|| self.inner.get_all())
+    }
+
+    fn get(&self, id: &str) -> Result<Option<Post>, ProviderError> {
+        self.retrier.call(|| self.inner.get(id))
+    }
+
+    fn get_json(&self, id: &str) -> Result<Option<Vec<u8>>, ProviderError> {
+        self.retrier.call(|| self.inner.get_json(id))
+    }
+
+    fn create(&self, input: PostInput) -> Result<Post, ProviderError> {
+        self.retrier.call(|| self.inner.create(input.clone()))
+    }
+
+    fn update(&self, id: &str, input: PostInput) -> Result<Option<Post>, ProviderError> {
+        self.retrier.call(|| self.inner.update(id, input.clone()))
+    }
+
+    fn delete(&self, id: &str) -> Result<bool, ProviderError> {
+        self.retrier.call(|| self.inner.delete(id))
+    }
+
+    fn get_by_authors(
+        &self,
+        authors: &[String],
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<Post>, usize), ProviderError> {
+        self.retrier
+            .call(|| self.inner.get_by_authors(authors, offset, limit))
+    }
+
+    fn get_by_date(&self, prefix: &str) -> Result<Vec<Post>, ProviderError> {
+        self.retrier.call(|| self.inner.get_by_date(prefix))
+    }
+
+    fn set_author(&self, ids: &[String], author: &str) -> Result<Vec<String>, ProviderError> {
+        self.retrier.call(|| self.inner.set_author(ids, author))
+    }
+
+    fn publish_due(&self, now: DateTime<Utc>) -> Result<Vec<Post>, ProviderError> {
+        self.retrier.call(|| self.inner.publish_due(now))
+    }
+
+    fn import(&self, posts: Vec<Post>) -> Result<usize, ProviderError> {
+        // Not retried: the whole dataset would have to be copied for every attempt
+        self.inner.import(posts)
+    }
+
+    fn explain(&self, query: &PostsQuery) -> Result<QueryPlan, ProviderError> {
+        self.retrier.call(|| self.inner.explain(query))
+    }
+}
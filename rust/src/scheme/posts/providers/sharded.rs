@@ -0,0 +1,201 @@
+use chrono::{DateTime, Utc};
+use serde_json::{Value, json};
+use std::{
+    collections::BTreeSet,
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::Arc,
+};
+
+use crate::scheme::{
+    posts::*,
+    provider::{Provider, ProviderError, ProviderStats},
+};
+
+/// [`PostsProvider`] splitting posts over one shard per server worker, so workers don't contend
+/// for the same locks when their clients write (see `RUST_SERVER_STATE_MODE=worker-local`).
+///
+/// Every worker gets its own view (see [`ShardedProvider::worker`]) creating posts in its local
+/// shard; as a client's keep-alive connection stays on one worker, its writes stay on one shard.
+/// Every post lives in exactly one shard: lookups by ID ask the local shard first and then the
+/// others, updates and deletions go to the shard holding the post, and listings merge all shards.
+/// Imported posts stay in the shard holding them, new ones go to the shard their ID hashes to.
+pub struct ShardedProvider {
+    shards: Arc<[Arc<dyn PostsProvider>]>,
+
+    /// Index of the shard receiving created posts.
+    local: usize,
+}
+
+impl ShardedProvider {
+    /// Returns the view of the first worker over `shards`, which must not be empty.
+    pub fn wrapped(shards: Vec<Arc<dyn PostsProvider>>) -> Arc<Self> {
+        assert!(!shards.is_empty(), "no shards");
+        Arc::new(Self {
+            shards: shards.into(),
+            local: 0,
+        })
+    }
+
+    /// Returns the view of the same shards for `worker`, creating posts in its own shard.
+    pub fn worker(&self, worker: usize) -> Arc<Self> {
+        Arc::new(Self {
+            shards: self.shards.clone(),
+            local: worker % self.shards.len(),
+        })
+    }
+
+    /// Returns the indexes of the shards, the local one first.
+    fn nearest(&self) -> impl Iterator<Item = usize> + use<> {
+        let (local, len) = (self.local, self.shards.len());
+        (0..len).map(move |idx| (local + idx) % len)
+    }
+
+    /// Returns the index of the shard storing the post with `id`, if any.
+    fn locate(&self, id: &str) -> Result<Option<usize>, ProviderError> {
+        for idx in self.nearest() {
+            if self.shards[idx].get(id)?.is_some() {
+                return Ok(Some(idx));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns the index of the shard a new post with `id` is imported into.
+    fn home(&self, id: &str) -> usize {
+        let mut hasher = DefaultHasher::new();
+        id.hash(&mut hasher);
+        (hasher.finish() % self.shards.len() as u64) as usize
+    }
+}
+
+impl Provider for ShardedProvider {}
+
+impl ProviderStats for ShardedProvider {
+    fn stats(&self) -> Value {
+        json!({
+            "type": "sharded",
+            "local": self.local,
+            "shards": self.shards.iter().map(|shard| shard.stats()).collect::<Vec<_>>(),
+        })
+    }
+}
+
+impl PostsProvider for ShardedProvider {
+    fn get_all(&self) -> Result<Vec<Post>, ProviderError> {
+        let mut posts = Vec::new();
+        for shard in self.shards.iter() {
+            posts.extend(shard.get_all()?);
+        }
+        Ok(posts)
+    }
+
+    fn get(&self, id: &str) -> Result<Option<Post>, ProviderError> {
+        for idx in self.nearest() {
+            if let Some(post) = self.shards[idx].get(id)? {
+                return Ok(Some(post));
+            }
+        }
+        Ok(None)
+    }
+
+    fn get_json(&self, id: &str) -> Result<Option<Vec<u8>>, ProviderError> {
+        for idx in self.nearest() {
+            if let Some(json) = self.shards[idx].get_json(id)? {
+                return Ok(Some(json));
+            }
+        }
+        Ok(None)
+    }
+
+    fn create(&self, input: PostInput) -> Result<Post, ProviderError> {
+        self.shards[self.local].create(input)
+    }
+
+    fn update(&self, id: &str, input: PostInput) -> Result<Option<Post>, ProviderError> {
+        match self.locate(id)? {
+            Some(idx) => self.shards[idx].update(id, input),
+            None => Ok(None),
+        }
+    }
+
+    fn delete(&self, id: &str) -> Result<bool, ProviderError> {
+        match self.locate(id)? {
+            Some(idx) => self.shards[idx].delete(id),
+            None => Ok(false),
+        }
+    }
+
+    /// Takes the first `offset + limit` posts of each shard and merges them.
+    fn get_by_authors(
+        &self,
+        authors: &[String],
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<Post>, usize), ProviderError> {
+        let window = offset.saturating_add(limit);
+        let (mut posts, mut total) = (Vec::new(), 0);
+        for shard in self.shards.iter() {
+            let (found, count) = shard.get_by_authors(authors, 0, window)?;
+            posts.extend(found);
+            total += count;
+        }
+        posts.sort_unstable_by(|a, b| b.date.cmp(&a.date).then_with(|| a.id.cmp(&b.id)));
+        Ok((posts.into_iter().skip(offset).take(limit).collect(), total))
+    }
+
+    fn get_by_date(&self, prefix: &str) -> Result<Vec<Post>, ProviderError> {
+        let mut posts = Vec::new();
+        for shard in self.shards.iter() {
+            posts.extend(shard.get_by_date(prefix)?);
+        }
+        posts.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.id.cmp(&b.id)));
+        Ok(posts)
+    }
+
+    fn set_author(&self, ids: &[String], author: &str) -> Result<Vec<String>, ProviderError> {
+        let mut updated = BTreeSet::new();
+        for shard in self.shards.iter() {
+            updated.extend(shard.set_author(ids, author)?);
+        }
+        Ok(ids
+            .iter()
+            .filter(|id| updated.contains(*id))
+            .cloned()
+            .collect())
+    }
+
+    fn publish_due(&self, now: DateTime<Utc>) -> Result<Vec<Post>, ProviderError> {
+        let mut published = Vec::new();
+        for shard in self.shards.iter() {
+            published.extend(shard.publish_due(now)?);
+        }
+        Ok(published)
+    }
+
+    fn import(&self, posts: Vec<Post>) -> Result<usize, ProviderError> {
+        let mut batches = vec![Vec::new(); self.shards.len()];
+        for post in posts {
+            let idx = match self.locate(&post.id)? {
+                Some(idx) => idx,
+                None => self.home(&post.id),
+            };
+            batches[idx].push(post);
+        }
+        let mut imported = 0;
+        for (shard, batch) in self.shards.iter().zip(batches) {
+            imported += shard.import(batch)?;
+        }
+        Ok(imported)
+    }
+
+    /// Sums up the plans of all shards; the access path is the one of the first shard.
+    fn explain(&self, query: &PostsQuery) -> Result<QueryPlan, ProviderError> {
+        let mut plan = self.shards[0].explain(query)?;
+        for shard in &self.shards[1..] {
+            let other = shard.explain(query)?;
+            plan.candidates += other.candidates;
+            plan.total += other.total;
+        }
+        Ok(plan)
+    }
+}
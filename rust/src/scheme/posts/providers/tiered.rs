@@ -0,0 +1,354 @@
+use actix_web::{rt, web};
+use chrono::{DateTime, Utc};
+use serde_json::{Value, json};
+use std::{
+    collections::BTreeSet,
+    hash::{BuildHasher, RandomState},
+    sync::{
+        Arc, Mutex, MutexGuard,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+use tracing::{debug, error};
+use uuid::Uuid;
+
+use crate::scheme::{
+    posts::*,
+    provider::{Provider, ProviderError, ProviderStats, lock},
+};
+
+/// Number of locks the IDs of posts are spread over (see [`TieredProvider::locks`]).
+const ID_LOCKS: usize = 64;
+
+/// Policy deciding which tier of a [`TieredProvider`] stores a post.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Routing {
+    /// Posts dated within `max_age` are hot; older ones are cold. Posts getting older are moved to
+    /// the cold tier by [`TieredProvider::demote`].
+    Age { max_age: Duration },
+
+    /// Posts whose ID starts with one of `hot` are hot; others are cold. A post never moves.
+    IdPrefix { hot: Vec<String> },
+}
+
+impl Routing {
+    /// Parses `age:<milliseconds>` or `prefix:<prefix>,<prefix>,...`.
+    pub fn from_spec(spec: &str) -> Option<Self> {
+        match spec.split_once(':')? {
+            ("age", ms) => Some(Self::Age {
+                max_age: Duration::from_millis(ms.trim().parse().ok()?),
+            }),
+            ("prefix", prefixes) => Some(Self::IdPrefix {
+                hot: prefixes
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|prefix| !prefix.is_empty())
+                    .map(str::to_owned)
+                    .collect(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if `post` belongs to the hot tier at `now`.
+    fn is_hot(&self, post: &Post, now: DateTime<Utc>) -> bool {
+        match self {
+            Self::Age { max_age } => now
+                .signed_duration_since(post.date)
+                .to_std()
+                .map_or(true, |age| age <= *max_age),
+            Self::IdPrefix { hot } => hot.iter().any(|prefix| post.id.starts_with(prefix)),
+        }
+    }
+
+    fn stats(&self) -> Value {
+        match self {
+            Self::Age { max_age } => json!({ "age_ms": max_age.as_millis() as u64 }),
+            Self::IdPrefix { hot } => json!({ "prefixes": hot }),
+        }
+    }
+}
+
+/// [`PostsProvider`] federating two providers as storage tiers, e.g. a small in-memory hot tier in
+/// front of a persistent cold one, so tiered storage can be modeled and measured.
+///
+/// Every post lives in exactly one tier, picked by the [`Routing`] policy when it's written.
+/// Lookups by ID ask the hot tier first; listings merge both tiers. With [`Routing::Age`], posts
+/// are routed by their date, and [`TieredProvider::spawn_demotion`] moves them to the cold tier as
+/// they age.
+///
+/// # Consistency
+/// Moving a post between tiers (on update or demotion) stores it in the target tier before
+/// deleting it from the source one: a concurrent listing may see it twice, but never miss it.
+/// Moves and writes of a post hold the lock of its ID, and demotion reads the post again under
+/// it, so a write racing a move is neither lost nor left behind in the other tier.
+pub struct TieredProvider {
+    hot: Arc<dyn PostsProvider>,
+    cold: Arc<dyn PostsProvider>,
+    routing: Routing,
+
+    /// Locks of the posts, each guarding the IDs hashed to it.
+    locks: Vec<Mutex<()>>,
+
+    /// Hashes IDs to their locks.
+    hasher: RandomState,
+
+    /// Number of posts moved from the hot to the cold tier by demotion.
+    demoted: AtomicU64,
+}
+
+impl TieredProvider {
+    pub fn wrapped(
+        hot: Arc<dyn PostsProvider>,
+        cold: Arc<dyn PostsProvider>,
+        routing: Routing,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            hot,
+            cold,
+            routing,
+            locks: (0..ID_LOCKS).map(|_| Mutex::new(())).collect(),
+            hasher: RandomState::new(),
+            demoted: AtomicU64::new(0),
+        })
+    }
+
+    /// Returns the index of the lock of `id`.
+    fn lock_of(&self, id: &str) -> usize {
+        self.hasher.hash_one(id) as usize % self.locks.len()
+    }
+
+    /// Locks the post with `id`.
+    fn lock(&self, id: &str) -> MutexGuard<'_, ()> {
+        lock(&self.locks[self.lock_of(id)])
+    }
+
+    /// Locks the posts with `ids`, taking their locks in order so concurrent callers can't
+    /// deadlock.
+    fn lock_ids<'a>(&self, ids: impl IntoIterator<Item = &'a str>) -> Vec<MutexGuard<'_, ()>> {
+        let indexes: BTreeSet<usize> = ids.into_iter().map(|id| self.lock_of(id)).collect();
+        indexes
+            .into_iter()
+            .map(|index| lock(&self.locks[index]))
+            .collect()
+    }
+
+    /// Locks every post, in the order of [`lock_ids`](Self::lock_ids).
+    fn lock_every(&self) -> Vec<MutexGuard<'_, ()>> {
+        self.locks.iter().map(lock).collect()
+    }
+
+    /// Returns the tier `post` belongs to.
+    fn tier(&self, post: &Post) -> &Arc<dyn PostsProvider> {
+        if self.routing.is_hot(post, Utc::now()) {
+            &self.hot
+        } else {
+            &self.cold
+        }
+    }
+
+    /// Returns the tier storing the post with `id`, if any.
+    fn locate(&self, id: &str) -> Result<Option<&Arc<dyn PostsProvider>>, ProviderError> {
+        for tier in [&self.hot, &self.cold] {
+            if tier.get(id)?.is_some() {
+                return Ok(Some(tier));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Moves hot posts which are too old for the hot tier to the cold one. Returns the number of
+    /// moved posts.
+    ///
+    /// Does nothing with [`Routing::IdPrefix`], whose posts never change tier.
+    pub fn demote(&self) -> Result<usize, ProviderError> {
+        if !matches!(self.routing, Routing::Age { .. }) {
+            return Ok(0);
+        }
+        let now = Utc::now();
+        let aged: Vec<String> = self
+            .hot
+            .get_all()?
+            .into_iter()
+            .filter(|post| !self.routing.is_hot(post, now))
+            .map(|post| post.id)
+            .collect();
+        let mut demoted = 0;
+        for id in aged {
+            // The post may have changed since it was listed
+            let _lock = self.lock(&id);
+            let Some(post) = self.hot.get(&id)? else {
+                continue;
+            };
+            if self.routing.is_hot(&post, now) {
+                continue;
+            }
+            self.cold.import(vec![post])?;
+            self.hot.delete(&id)?;
+            demoted += 1;
+            self.demoted.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(demoted)
+    }
+
+    /// Starts a task on the current Actix runtime which demotes aged posts every `interval` (see
+    /// [`TieredProvider::demote`]).
+    ///
+    /// The demotion itself runs on the blocking pool.
+    pub fn spawn_demotion(self: &Arc<Self>, interval: Duration) {
+        let provider = self.clone();
+        rt::spawn(async move {
+            let mut ticks = rt::time::interval(interval);
+            loop {
+                ticks.tick().await;
+                let provider = provider.clone();
+                match web::block(move || provider.demote()).await {
+                    Ok(Ok(0)) => {}
+                    Ok(Ok(demoted)) => debug!("Moved {demoted} posts to the cold tier"),
+                    Ok(Err(err)) => error!("Post demotion failed: {err}"),
+                    Err(err) => error!("Fail to run post demotion: {err}"),
+                }
+            }
+        });
+    }
+}
+
+impl Provider for TieredProvider {}
+
+impl ProviderStats for TieredProvider {
+    fn stats(&self) -> Value {
+        json!({
+            "type": "tiered",
+            "routing": self.routing.stats(),
+            "demoted": self.demoted.load(Ordering::Relaxed),
+            "hot": self.hot.stats(),
+            "cold": self.cold.stats(),
+        })
+    }
+}
+
+impl PostsProvider for TieredProvider {
+    fn get_all(&self) -> Result<Vec<Post>, ProviderError> {
+        let mut posts = self.hot.get_all()?;
+        posts.extend(self.cold.get_all()?);
+        Ok(posts)
+    }
+
+    fn get(&self, id: &str) -> Result<Option<Post>, ProviderError> {
+        match self.hot.get(id)? {
+            Some(post) => Ok(Some(post)),
+            None => self.cold.get(id),
+        }
+    }
+
+    fn get_json(&self, id: &str) -> Result<Option<Vec<u8>>, ProviderError> {
+        match self.hot.get_json(id)? {
+            Some(json) => Ok(Some(json)),
+            None => self.cold.get_json(id),
+        }
+    }
+
+    /// The ID is generated here, so the post can be routed by it before it's stored.
+    fn create(&self, input: PostInput) -> Result<Post, ProviderError> {
+        let post = Post::new(Uuid::new_v4().to_string(), input, Utc::now());
+        self.tier(&post).import(vec![post.clone()])?;
+        Ok(post)
+    }
+
+    /// Moves the post if the new version belongs to the other tier.
+    fn update(&self, id: &str, input: PostInput) -> Result<Option<Post>, ProviderError> {
+        let _lock = self.lock(id);
+        let Some(current) = self.locate(id)? else {
+            return Ok(None);
+        };
+        let post = Post::new(id.to_owned(), input.clone(), Utc::now());
+        let target = self.tier(&post);
+        if Arc::ptr_eq(current, target) {
+            return current.update(id, input);
+        }
+        target.import(vec![post.clone()])?;
+        current.delete(id)?;
+        Ok(Some(post))
+    }
+
+    fn delete(&self, id: &str) -> Result<bool, ProviderError> {
+        let _lock = self.lock(id);
+        // Both tiers are asked, in case a move was interrupted
+        let hot = self.hot.delete(id)?;
+        let cold = self.cold.delete(id)?;
+        Ok(hot || cold)
+    }
+
+    /// Takes the first `offset + limit` posts of each tier and merges them.
+    fn get_by_authors(
+        &self,
+        authors: &[String],
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<Post>, usize), ProviderError> {
+        let window = offset.saturating_add(limit);
+        let (mut posts, hot_total) = self.hot.get_by_authors(authors, 0, window)?;
+        let (cold, cold_total) = self.cold.get_by_authors(authors, 0, window)?;
+        posts.extend(cold);
+        posts.sort_unstable_by(|a, b| b.date.cmp(&a.date).then_with(|| a.id.cmp(&b.id)));
+        Ok((
+            posts.into_iter().skip(offset).take(limit).collect(),
+            hot_total + cold_total,
+        ))
+    }
+
+    fn get_by_date(&self, prefix: &str) -> Result<Vec<Post>, ProviderError> {
+        let mut posts = self.hot.get_by_date(prefix)?;
+        posts.extend(self.cold.get_by_date(prefix)?);
+        posts.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.id.cmp(&b.id)));
+        Ok(posts)
+    }
+
+    fn set_author(&self, ids: &[String], author: &str) -> Result<Vec<String>, ProviderError> {
+        let _locks = self.lock_ids(ids.iter().map(String::as_str));
+        let mut updated: BTreeSet<String> = self.hot.set_author(ids, author)?.into_iter().collect();
+        updated.extend(self.cold.set_author(ids, author)?);
+        Ok(ids
+            .iter()
+            .filter(|id| updated.contains(*id))
+            .cloned()
+            .collect())
+    }
+
+    /// Locks every post, as the posts due are only known once they're published.
+    fn publish_due(&self, now: DateTime<Utc>) -> Result<Vec<Post>, ProviderError> {
+        let _locks = self.lock_every();
+        let mut published = self.hot.publish_due(now)?;
+        published.extend(self.cold.publish_due(now)?);
+        Ok(published)
+    }
+
+    /// With [`Routing::Age`], a replaced post may change tier, so its ID is deleted from the other
+    /// tier.
+    fn import(&self, posts: Vec<Post>) -> Result<usize, ProviderError> {
+        let now = Utc::now();
+        let _locks = self.lock_ids(posts.iter().map(|post| post.id.as_str()));
+        let (hot, cold): (Vec<Post>, Vec<Post>) = posts
+            .into_iter()
+            .partition(|post| self.routing.is_hot(post, now));
+        if matches!(self.routing, Routing::Age { .. }) {
+            for post in hot.iter() {
+                self.cold.delete(&post.id)?;
+            }
+            for post in cold.iter() {
+                self.hot.delete(&post.id)?;
+            }
+        }
+        Ok(self.hot.import(hot)? + self.cold.import(cold)?)
+    }
+
+    /// Sums up the plans of both tiers; the access path is the one of the hot tier.
+    fn explain(&self, query: &PostsQuery) -> Result<QueryPlan, ProviderError> {
+        let mut plan = self.hot.explain(query)?;
+        let cold = self.cold.explain(query)?;
+        plan.candidates += cold.candidates;
+        plan.total += cold.total;
+        Ok(plan)
+    }
+}
@@ -0,0 +1,546 @@
+use actix_web::{rt, web};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, atomic::Ordering},
+    time::{Duration, Instant},
+};
+use tracing::{debug, error, warn};
+use uuid::Uuid;
+
+use crate::{
+    scheme::{
+        posts::{versions::Stored, *},
+        provider::{LockWaits, Provider, ProviderError, ProviderStats},
+    },
+    state::Metrics,
+};
+
+/// A single entry of the write-ahead log.
+///
+/// The log is a JSON Lines file; each line describes the latest state of one post. Posts are
+/// versioned (see [`versions`]), so logs written by older builds can be replayed.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum Record {
+    /// The post was created or changed; the record holds its full new state.
+    Put { post: Stored },
+
+    /// The post was deleted.
+    Delete { id: String },
+}
+
+/// Size of the log buffer in batched mode at which it's flushed, even if the batch isn't full.
+const BATCH_BUFFER_CAPACITY: usize = 1024 * 1024;
+
+/// Open log file together with bookkeeping used to decide when to compact it.
+struct Log {
+    file: File,
+
+    /// Appended records which haven't been written to the file yet.
+    buffer: Vec<u8>,
+
+    /// Number of records in the log file.
+    records: u64,
+
+    /// Size of the log file, in bytes, including the buffered records.
+    bytes: u64,
+
+    /// Number of appended records which haven't been flushed to the OS yet.
+    pending: u64,
+
+    /// Number of pending records which triggers a flush; `1` flushes every record right away.
+    batch: u64,
+
+    /// Number of upcoming writes which fail, to test how failures are handled.
+    #[cfg(test)]
+    failing_writes: u32,
+}
+
+impl Log {
+    /// Opens `file` for appending records in batches of `batch` (see [`WalProvider::open`]).
+    fn new(file: File, records: u64, bytes: u64, batch: u64) -> Self {
+        let buffer = if batch > 1 {
+            Vec::with_capacity(BATCH_BUFFER_CAPACITY)
+        } else {
+            Vec::new()
+        };
+        Self {
+            file,
+            buffer,
+            records,
+            bytes,
+            pending: 0,
+            batch,
+            #[cfg(test)]
+            failing_writes: 0,
+        }
+    }
+
+    /// Appends a record, flushing it to the OS together with other pending records once the batch
+    /// is full.
+    ///
+    /// If the flush fails, the record is taken back out of the log, as the mutation it describes
+    /// fails: a later flush mustn't write it, nor must a retry of the mutation write it twice.
+    /// Records appended before it stay pending.
+    fn append(&mut self, record: &Record, metrics: &Metrics) -> io::Result<()> {
+        let mut line = serde_json::to_vec(record).map_err(io::Error::other)?;
+        line.push(b'\n');
+        let start = self.buffer.len();
+        self.buffer.extend_from_slice(&line);
+        self.records += 1;
+        self.bytes += line.len() as u64;
+        self.pending += 1;
+        if self.pending >= self.batch || self.buffer.len() >= BATCH_BUFFER_CAPACITY {
+            self.flush(metrics).inspect_err(|_| {
+                self.buffer.truncate(start);
+                self.records -= 1;
+                self.bytes -= line.len() as u64;
+                self.pending -= 1;
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Flushes pending records to the OS as a single write.
+    ///
+    /// If the write fails, whatever part of it reached the file is cut off again, so the records
+    /// are written exactly once by the next flush.
+    fn flush(&mut self, metrics: &Metrics) -> io::Result<()> {
+        if self.pending == 0 {
+            return Ok(());
+        }
+        let flushed = self.bytes - self.buffer.len() as u64;
+        self.write().inspect_err(|_| {
+            Metrics::inc(&metrics.wal_flush_errors);
+            if let Err(err) = self.file.set_len(flushed) {
+                error!("Fail to cut a partial write off the WAL: {err}");
+            }
+        })?;
+        self.buffer.clear();
+        Metrics::inc(&metrics.wal_flushes);
+        metrics
+            .wal_flushed_records
+            .fetch_add(self.pending, Ordering::Relaxed);
+        self.pending = 0;
+        Ok(())
+    }
+
+    /// Writes the buffer to the file.
+    fn write(&mut self) -> io::Result<()> {
+        #[cfg(test)]
+        if self.failing_writes > 0 {
+            self.failing_writes -= 1;
+            return Err(io::Error::other("injected write failure"));
+        }
+        self.file.write_all(&self.buffer)
+    }
+}
+
+/// Persistent implementation of the [`PostsProvider`] trait based on an append-only log.
+///
+/// The current state is kept in memory (in a [`DummyProvider`]), so reads never touch the disk.
+/// Every mutation is appended to the log file before it's applied in memory; on startup the log is
+/// replayed to rebuild the state.
+///
+/// Since updates and deletions only append, the log keeps growing with obsolete records. A periodic
+/// compaction (see [`WalProvider::spawn_compaction`]) rewrites the log from the current state.
+/// Compaction statistics are reported through the `wal` section of [`Metrics`].
+///
+/// # Durability
+/// Records are flushed to the OS after every write, but not synced to the device; the log survives
+/// process crashes, but not necessarily power loss. A torn last line is ignored on replay and cut
+/// off the log, so records appended afterwards are replayed again.
+///
+/// A mutation is applied in memory only after its record was appended; if appending fails, the
+/// mutation fails with [`ProviderError::Internal`] and the state is left untouched: the record,
+/// and whatever part of it was written, is taken back out of the log.
+///
+/// # Batching
+/// Optionally, the log works in write-behind mode: records are buffered and flushed together, as a
+/// single write, once `batch` records are pending or by a periodic flush (see
+/// [`WalProvider::spawn_flusher`]). This trades durability for throughput: mutations are applied
+/// and answered before their records reach the OS, so a crash loses the pending ones. All
+/// mutations go through the same buffer, so the order of records is kept. A failed flush is
+/// retried with the next one, except for the record of the mutation whose append triggered it:
+/// that mutation fails, so its record is dropped rather than written later. `wal.pending`,
+/// `wal.flushes`, `wal.flushed_records` and `wal.flush_errors` of [`Metrics`] show the queue depth
+/// and batch sizes.
+///
+/// # Concurrency
+/// All mutations are serialized by the log mutex, which guarantees that the order of records in
+/// the log matches the order in which changes were applied. Reads only take the in-memory lock.
+pub struct WalProvider {
+    /// In-memory copy of the current state.
+    memory: DummyProvider,
+
+    /// Path of the log file.
+    path: PathBuf,
+
+    /// The log file; also serializes all mutations.
+    log: Mutex<Log>,
+
+    /// Destination of compaction statistics.
+    metrics: Arc<Metrics>,
+
+    /// Waits for the lock of `log`.
+    locks: LockWaits,
+}
+
+impl WalProvider {
+    /// Opens (or creates) the log at `path`, replays it and returns the provider wrapped in an `Arc`.
+    ///
+    /// Records are flushed in batches of `batch` (see [Batching](WalProvider#batching)); with
+    /// `1`, every record is flushed right away.
+    ///
+    /// The in-memory copy stores post content with `compression`; log records stay plain JSON.
+    ///
+    /// # Errors
+    /// Returns an `io::Error` if the log can't be read or opened for appending.
+    pub fn open(
+        path: &Path,
+        compression: Compression,
+        metrics: Arc<Metrics>,
+        batch: usize,
+    ) -> io::Result<Arc<Self>> {
+        let memory = DummyProvider::new(compression);
+        let mut records = 0;
+        // End of the last record replayed; whatever follows is cut off before appending, so new
+        // records don't land behind a torn one, where the next replay would stop short of them
+        let mut replayed = 0;
+        if path.exists() {
+            let mut reader = BufReader::new(File::open(path)?);
+            let mut line = Vec::new();
+            loop {
+                line.clear();
+                let read = reader.read_until(b'\n', &mut line)?;
+                if read == 0 {
+                    break;
+                }
+                // Records are written with their line break, so one without it is torn, even if
+                // what was written of it happens to parse
+                let record = match line.strip_suffix(b"\n") {
+                    Some(line) => serde_json::from_slice::<Record>(line).map_err(|e| e.to_string()),
+                    None => Err("the last record is incomplete".to_owned()),
+                };
+                match record {
+                    Ok(Record::Put { post: Stored(post) }) => memory.put(post),
+                    Ok(Record::Delete { id }) => {
+                        memory.delete(&id).map_err(io::Error::other)?;
+                    }
+                    Err(err) => {
+                        warn!("Ignoring the rest of {}: {err}", path.display());
+                        break;
+                    }
+                }
+                replayed += read as u64;
+                records += 1;
+            }
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let mut bytes = file.metadata()?.len();
+        if bytes > replayed {
+            warn!(
+                "Truncating {} to the {replayed} bytes replayed, dropping {} bytes",
+                path.display(),
+                bytes - replayed
+            );
+            file.set_len(replayed)?;
+            bytes = replayed;
+        }
+        debug!("WAL {} replayed: {records} records", path.display());
+        let provider = Self {
+            memory,
+            path: path.to_owned(),
+            log: Mutex::new(Log::new(file, records, bytes, batch.max(1) as u64)),
+            metrics,
+            locks: LockWaits::default(),
+        };
+        provider.report(records, bytes);
+        Ok(Arc::new(provider))
+    }
+
+    /// Starts a task on the current Actix runtime which compacts the log every `interval`.
+    ///
+    /// The compaction itself runs on the blocking pool.
+    pub fn spawn_compaction(self: &Arc<Self>, interval: Duration) {
+        let provider = self.clone();
+        rt::spawn(async move {
+            let mut ticks = rt::time::interval(interval);
+            // The first tick completes immediately; right after replay there is nothing to gain
+            ticks.tick().await;
+            loop {
+                ticks.tick().await;
+                let provider = provider.clone();
+                match web::block(move || provider.compact()).await {
+                    Ok(Ok(())) => {}
+                    Ok(Err(err)) => error!("WAL compaction failed: {err}"),
+                    Err(err) => error!("Fail to run WAL compaction: {err}"),
+                }
+            }
+        });
+    }
+
+    /// Starts a task on the current Actix runtime which flushes pending records every `interval`,
+    /// so records of a batch that doesn't fill up are written within `interval` as well.
+    ///
+    /// The flush itself runs on the blocking pool.
+    pub fn spawn_flusher(self: &Arc<Self>, interval: Duration) {
+        let provider = self.clone();
+        rt::spawn(async move {
+            let mut ticks = rt::time::interval(interval);
+            loop {
+                ticks.tick().await;
+                let provider = provider.clone();
+                match web::block(move || provider.flush()).await {
+                    Ok(Ok(())) => {}
+                    Ok(Err(err)) => error!("Fail to flush WAL: {err}"),
+                    Err(err) => error!("Fail to run WAL flush: {err}"),
+                }
+            }
+        });
+    }
+
+    /// Flushes pending records to the OS.
+    ///
+    /// # Errors
+    /// Returns an `io::Error` if the records can't be written; they stay pending then.
+    pub fn flush(&self) -> io::Result<()> {
+        let mut log = self.locks.lock(&self.log);
+        log.flush(&self.metrics)?;
+        self.metrics.wal_pending.store(0, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Rewrites the log so that it contains exactly one record per existing post.
+    ///
+    /// The new log is written to a temporary file, synced and atomically renamed over the old one.
+    /// Mutations are blocked while compaction runs; reads are not. If the log has no obsolete
+    /// records, nothing is done.
+    ///
+    /// # Errors
+    /// Returns an `io::Error` if the new log can't be written; the old log stays in place then.
+    pub fn compact(&self) -> io::Result<()> {
+        let started = Instant::now();
+        let mut log = self.locks.lock(&self.log);
+        let posts = self.memory.get_all().map_err(io::Error::other)?;
+        if log.records <= posts.len() as u64 {
+            return Ok(());
+        }
+        let tmp = self.path.with_extension("compacting");
+        let mut writer = BufWriter::new(File::create(&tmp)?);
+        let mut bytes = 0;
+        for post in posts.iter() {
+            let mut line = serde_json::to_vec(&Record::Put {
+                post: Stored(post.clone()),
+            })
+            .map_err(io::Error::other)?;
+            line.push(b'\n');
+            writer.write_all(&line)?;
+            bytes += line.len() as u64;
+        }
+        writer
+            .into_inner()
+            .map_err(|err| err.into_error())?
+            .sync_all()?;
+        fs::rename(&tmp, &self.path)?;
+        let reclaimed = log.bytes.saturating_sub(bytes);
+        let compacted = Log::new(
+            OpenOptions::new().append(true).open(&self.path)?,
+            posts.len() as u64,
+            bytes,
+            log.batch,
+        );
+        // Pending records are part of the new log already, as they're applied in memory; drop them
+        // instead of writing them to the replaced file
+        *log = compacted;
+        self.metrics.wal_pending.store(0, Ordering::Relaxed);
+        let elapsed = started.elapsed();
+        debug!(
+            "WAL compacted in {} ms: {} records, {reclaimed} bytes reclaimed",
+            elapsed.as_millis(),
+            posts.len()
+        );
+        Metrics::inc(&self.metrics.wal_compactions);
+        self.metrics
+            .wal_reclaimed_bytes
+            .fetch_add(reclaimed, Ordering::Relaxed);
+        self.metrics
+            .wal_last_compaction_ms
+            .store(elapsed.as_millis() as u64, Ordering::Relaxed);
+        self.report(log.records, log.bytes);
+        Ok(())
+    }
+
+    /// Makes the next `writes` writes to the log fail.
+    #[cfg(test)]
+    pub fn fail_writes(&self, writes: u32) {
+        self.locks.lock(&self.log).failing_writes = writes;
+    }
+
+    /// Appends a record while the log lock is held, reporting the new log size.
+    fn append(&self, log: &mut Log, record: Record) -> Result<(), ProviderError> {
+        log.append(&record, &self.metrics).inspect_err(|err| {
+            error!("Fail to append to WAL {}: {err}", self.path.display());
+        })?;
+        self.metrics
+            .wal_pending
+            .store(log.pending, Ordering::Relaxed);
+        self.report(log.records, log.bytes);
+        Ok(())
+    }
+
+    fn report(&self, records: u64, bytes: u64) {
+        self.metrics.wal_records.store(records, Ordering::Relaxed);
+        self.metrics.wal_bytes.store(bytes, Ordering::Relaxed);
+    }
+}
+
+impl Provider for WalProvider {}
+
+impl ProviderStats for WalProvider {
+    fn stats(&self) -> Value {
+        let log = self.locks.lock(&self.log);
+        json!({
+            "type": "wal",
+            "path": self.path.display().to_string(),
+            "log": {
+                "records": log.records,
+                "bytes": log.bytes,
+                "pending": log.pending,
+                "batch": log.batch,
+            },
+            "compactions": self.metrics.wal_compactions.load(Ordering::Relaxed),
+            "locks": self.locks.stats(),
+            "inner": self.memory.stats(),
+        })
+    }
+}
+
+impl PostsProvider for WalProvider {
+    fn get_all(&self) -> Result<Vec<Post>, ProviderError> {
+        self.memory.get_all()
+    }
+
+    fn get(&self, id: &str) -> Result<Option<Post>, ProviderError> {
+        self.memory.get(id)
+    }
+
+    fn get_json(&self, id: &str) -> Result<Option<Vec<u8>>, ProviderError> {
+        self.memory.get_json(id)
+    }
+
+    fn create(&self, input: PostInput) -> Result<Post, ProviderError> {
+        let post = Post::new(Uuid::new_v4().to_string(), input, Utc::now());
+        let mut log = self.locks.lock(&self.log);
+        self.append(
+            &mut log,
+            Record::Put {
+                post: Stored(post.clone()),
+            },
+        )?;
+        self.memory.put(post.clone());
+        Ok(post)
+    }
+
+    fn update(&self, id: &str, input: PostInput) -> Result<Option<Post>, ProviderError> {
+        let mut log = self.locks.lock(&self.log);
+        if self.memory.get(id)?.is_none() {
+            return Ok(None);
+        }
+        let post = Post::new(id.to_owned(), input, Utc::now());
+        self.append(
+            &mut log,
+            Record::Put {
+                post: Stored(post.clone()),
+            },
+        )?;
+        self.memory.put(post.clone());
+        Ok(Some(post))
+    }
+
+    fn delete(&self, id: &str) -> Result<bool, ProviderError> {
+        let mut log = self.locks.lock(&self.log);
+        if self.memory.get(id)?.is_none() {
+            return Ok(false);
+        }
+        self.append(&mut log, Record::Delete { id: id.to_owned() })?;
+        self.memory.delete(id)
+    }
+
+    fn get_by_authors(
+        &self,
+        authors: &[String],
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<Post>, usize), ProviderError> {
+        self.memory.get_by_authors(authors, offset, limit)
+    }
+
+    fn get_by_date(&self, prefix: &str) -> Result<Vec<Post>, ProviderError> {
+        self.memory.get_by_date(prefix)
+    }
+
+    /// Posts are rewritten one by one; if appending fails midway, the posts handled so far keep
+    /// the new author.
+    fn set_author(&self, ids: &[String], author: &str) -> Result<Vec<String>, ProviderError> {
+        let mut log = self.locks.lock(&self.log);
+        let mut updated = Vec::new();
+        for id in ids {
+            let Some(mut post) = self.memory.get(id)? else {
+                continue;
+            };
+            post.author = author.to_owned();
+            self.append(
+                &mut log,
+                Record::Put {
+                    post: Stored(post.clone()),
+                },
+            )?;
+            self.memory.put(post);
+            updated.push(id.clone());
+        }
+        Ok(updated)
+    }
+
+    /// Publishes in memory first; if the records can't be appended (or the process dies before),
+    /// the posts are replayed as scheduled after a restart and published again.
+    fn publish_due(&self, now: DateTime<Utc>) -> Result<Vec<Post>, ProviderError> {
+        let mut log = self.locks.lock(&self.log);
+        let published = self.memory.publish_due(now)?;
+        for post in published.iter() {
+            self.append(
+                &mut log,
+                Record::Put {
+                    post: Stored(post.clone()),
+                },
+            )?;
+        }
+        Ok(published)
+    }
+
+    /// Posts are logged one by one; if appending fails midway, the posts handled so far stay
+    /// imported.
+    fn import(&self, posts: Vec<Post>) -> Result<usize, ProviderError> {
+        let mut log = self.locks.lock(&self.log);
+        let count = posts.len();
+        for post in posts {
+            self.append(
+                &mut log,
+                Record::Put {
+                    post: Stored(post.clone()),
+                },
+            )?;
+            self.memory.put(post);
+        }
+        Ok(count)
+    }
+
+    fn explain(&self, query: &PostsQuery) -> Result<QueryPlan, ProviderError> {
+        self.memory.explain(query)
+    }
+}
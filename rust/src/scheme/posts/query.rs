@@ -0,0 +1,96 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::str::FromStr;
+
+use crate::scheme::posts::PostStatus;
+
+/// Filter combination over posts, as accepted by `GET /admin/explain`.
+///
+/// Written as comma-separated `field=value` terms, all of which must match:
+/// - `author=<name>`: posts of the author; repeated, posts of any of the authors (the feed);
+/// - `status=published|scheduled`: posts with the status (`GET /posts` lists published posts);
+/// - `due=<RFC 3339 time>|now`: scheduled posts to be published by then (the scheduler);
+/// - `date=<prefix>`: posts of a period, e.g. `2024-01` (`GET /posts?date=...`).
+///
+/// An empty query matches all posts.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PostsQuery {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub authors: Vec<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<PostStatus>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due: Option<DateTime<Utc>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+}
+
+impl FromStr for PostsQuery {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut query = Self::default();
+        for term in s.split(',').map(str::trim).filter(|term| !term.is_empty()) {
+            let (field, value) = term
+                .split_once('=')
+                .ok_or_else(|| format!("expected field=value, got {term:?}"))?;
+            match field.trim() {
+                "author" => query.authors.push(value.trim().to_owned()),
+                "status" => {
+                    query.status = Some(match value.trim() {
+                        "published" => PostStatus::Published,
+                        "scheduled" => PostStatus::Scheduled,
+                        other => return Err(format!("unknown status {other:?}")),
+                    })
+                }
+                "due" => {
+                    query.due = Some(match value.trim() {
+                        "now" => Utc::now(),
+                        time => time
+                            .parse()
+                            .map_err(|err| format!("invalid due time {time:?}: {err}"))?,
+                    })
+                }
+                "date" => query.date = Some(value.trim().to_owned()),
+                other => return Err(format!("unknown field {other:?}")),
+            }
+        }
+        Ok(query)
+    }
+}
+
+/// How a provider finds the posts matching a [`PostsQuery`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Access {
+    /// Looks up the keys of the query in an index.
+    IndexLookup,
+
+    /// Walks a range of an ordered index.
+    IndexRange,
+
+    /// Reads every stored post.
+    FullScan,
+}
+
+/// Access path a provider would use for a [`PostsQuery`], as returned by `GET /admin/explain`.
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryPlan {
+    pub access: Access,
+
+    /// Name of the index used, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<&'static str>,
+
+    /// Fields of the query checked on every candidate post, as the access doesn't cover them.
+    pub filters: Vec<&'static str>,
+
+    /// Number of posts read by the access, before the filters are applied.
+    pub candidates: usize,
+
+    /// Number of stored posts.
+    pub total: usize,
+}
@@ -1,8 +1,50 @@
-use actix_web::{HttpResponse, Responder, delete, get, post, put, web};
-use std::sync::Arc;
+use actix_web::{
+    FromRequest, HttpMessage, HttpRequest, HttpResponse, delete, get,
+    http::{
+        Method,
+        header::{self, EntityTag},
+    },
+    post, put, web,
+};
+use chrono::Utc;
+use futures_util::stream;
+use serde::Deserialize;
+use std::{io, sync::Arc, time::Instant};
 use tracing::debug;
+use uuid::Uuid;
 
-use crate::scheme::{auth::AuthToken, posts::*};
+use crate::{
+    connection,
+    jobs::{Job, JobQueue},
+    offload::Offload,
+    scheme::{
+        auth::AuthToken,
+        deadline::Deadline,
+        error::ApiError,
+        fields::Fields,
+        flags::{self, GET_POST, LIST_POSTS, VARIANT_HEADER},
+        location::{self, location},
+        methods::{self, Route},
+        moderation::{ContentModerator, Flagged, ModerationQueue, Verdict},
+        params::{NoParams, Params, Query},
+        posts::{
+            date::Tz,
+            include::{Document, Include},
+            protobuf::{Format, Input},
+            sanitize::Sanitizer,
+            *,
+        },
+        provider::ProviderError,
+    },
+    state::{GlobalServerState, Metrics},
+};
+
+/// Header of `POST /posts` choosing the ID of the new post, e.g. so a router knows the shard of
+/// the post before it's created.
+pub const POST_ID_HEADER: &str = "X-Post-Id";
+
+/// Number of posts encoded into a single chunk of a streamed `GET /posts` response.
+const LIST_CHUNK: usize = 1000;
 
 /// Shared application state for the `/posts` route group.
 ///
@@ -12,6 +54,21 @@ use crate::scheme::{auth::AuthToken, posts::*};
 pub struct PostsState {
     /// The backend provider that implements all operations for managing blog posts.
     pub provider: Arc<dyn PostsProvider>,
+
+    /// Queue receiving background jobs triggered by post changes (e.g. notifications).
+    pub jobs: JobQueue,
+
+    /// If set, deleting a missing post succeeds, so retried deletions don't fail with `404`.
+    pub idempotent_delete: bool,
+
+    /// Sanitization of the content of created and updated posts.
+    pub sanitizer: Sanitizer,
+
+    /// Moderator judging the content of created and updated posts.
+    pub moderator: Arc<dyn ContentModerator>,
+
+    /// Queue of posts flagged by the moderator.
+    pub moderation: Arc<ModerationQueue>,
 }
 
 impl PostsState {
@@ -19,96 +76,431 @@ impl PostsState {
     ///
     /// # Parameters
     /// - `provider`: An `Arc`-wrapped implementation of [`PostsProvider`]
+    /// - `jobs`: Handle of the background job queue
+    /// - `idempotent_delete`: Whether `DELETE /posts/{id}` succeeds for missing posts
+    /// - `sanitizer`: Sanitization of post content (see [`Sanitizer`])
+    /// - `moderator`: Moderator of post content (see [`ContentModerator`])
+    /// - `moderation`: Queue receiving flagged posts
     ///
     /// # Returns
     /// A new [`PostsState`] instance.
-    pub fn new(provider: Arc<dyn PostsProvider>) -> Self {
-        Self { provider }
+    pub fn new(
+        provider: Arc<dyn PostsProvider>,
+        jobs: JobQueue,
+        idempotent_delete: bool,
+        sanitizer: Sanitizer,
+        moderator: Arc<dyn ContentModerator>,
+        moderation: Arc<ModerationQueue>,
+    ) -> Self {
+        Self {
+            provider,
+            jobs,
+            idempotent_delete,
+            sanitizer,
+            moderator,
+            moderation,
+        }
+    }
+
+    /// Sanitizes and moderates the input of a created or updated post. Returns the input to store
+    /// and, if the moderator flagged it, the reason.
+    fn review(&self, input: PostInput) -> Result<(PostInput, Option<String>), ApiError> {
+        let input = self
+            .sanitizer
+            .apply(input)
+            .map_err(ApiError::UnprocessableEntity)?;
+        match self.moderator.moderate(&input.content) {
+            Verdict::Accept => Ok((input, None)),
+            Verdict::Flag(reason) => Ok((input, Some(reason))),
+            Verdict::Reject(reason) => Err(ApiError::UnprocessableEntity(reason)),
+        }
     }
+
+    /// Adds a stored post to the moderation queue if it was flagged.
+    fn flag(&self, post: &Post, reason: Option<String>) {
+        if let Some(reason) = reason {
+            self.moderation.push(Flagged {
+                kind: "post",
+                id: post.id.clone(),
+                author: post.author.clone(),
+                reason,
+                flagged_at: Utc::now(),
+            });
+        }
+    }
+}
+
+/// Optional filter accepted by `GET /posts`.
+#[derive(Debug, Deserialize)]
+struct ListQuery {
+    /// Prefix of the UTC date of the listed posts, e.g. `2024-01` (see
+    /// [`PostsProvider::get_by_date`]).
+    date: Option<String>,
+
+    /// UTC offset to render the dates at, see [`Tz`].
+    tz: Option<String>,
+
+    /// Comma-separated fields of the posts to return, see [`Fields`].
+    fields: Option<String>,
+}
+
+impl Params for ListQuery {
+    const NAMES: &'static [&'static str] = &["date", "tz", "fields"];
+}
+
+/// Query parameters of `GET /posts/{id}`.
+#[derive(Debug, Deserialize)]
+struct PostQuery {
+    /// UTC offset to render the dates at, see [`Tz`].
+    tz: Option<String>,
+
+    /// Comma-separated fields of the post to return, see [`Fields`].
+    fields: Option<String>,
+
+    /// Comma-separated relations to embed, see [`Include`].
+    include: Option<String>,
+}
+
+impl Params for PostQuery {
+    const NAMES: &'static [&'static str] = &["tz", "fields", "include"];
 }
 
 /// Handles `GET /posts`
 ///
-/// Returns a JSON array containing all available posts.
+/// Returns a JSON array containing all published posts. Scheduled posts are hidden until their
+/// `publish_at` time.
+///
+/// Like the other post endpoints, it answers with protobuf instead if the client prefers it
+/// (see [`Format`]).
+///
+/// JSON responses without `fields` are the [`LIST_POSTS`] experiment: the array is either built in memory and sent
+/// at once (`clone`) or encoded chunk by chunk while it's sent (`streaming`), chosen per request
+/// (see [`FeatureFlags::variant`](flags::FeatureFlags::variant)); both send the same body, and the
+/// time of each is recorded in the `experiments` section of `GET /metrics`. Either way, the body
+/// is encoded on the offload pool (see [`Offload`]).
+///
+/// # Query Parameters
+/// - `tz`: Offset to render dates at, e.g. `-05:00` (see [`Tz`])
+/// - `date`: Lists only the posts whose UTC date starts with it, e.g. `2024-01`, oldest first
+/// - `fields`: Fields of the posts to return, e.g. `id,author,date` (see [`Fields`])
+///
+/// # Request Headers
+/// - [`VARIANT_HEADER`]: Variant to serve the request with, e.g. `streaming`
 ///
 /// # Response
-/// - `200 OK` with JSON array of [`Post`] objects
+/// - `200 OK` with JSON array of [`Post`] objects, and the variant in [`VARIANT_HEADER`]
+/// - `400 Bad Request` if `tz` isn't a UTC offset, or `fields` names an unknown field
 #[get("")]
-async fn list_posts(state: web::Data<PostsState>) -> impl Responder {
-    let posts = state.provider.get_all();
-    HttpResponse::Ok().json(posts)
+async fn list_posts(
+    req: HttpRequest,
+    state: web::Data<PostsState>,
+    global: web::Data<GlobalServerState>,
+    deadline: Deadline,
+    format: Format,
+    query: Query<ListQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let started = Instant::now();
+    let tz = Tz::parse(query.tz.as_deref())?;
+    let fields = Fields::parse(query.fields.as_deref(), Post::FIELDS)?;
+    let now = Utc::now();
+    let provider = state.provider.clone();
+    let posts: Vec<Post> = deadline
+        .run(move || match &query.date {
+            Some(prefix) => provider.get_by_date(prefix),
+            None => provider.get_all(),
+        })
+        .await?
+        .into_iter()
+        .filter(|post| post.is_published(now))
+        .map(|post| tz.apply(post))
+        .collect();
+    let variant = (format == Format::Json && fields.is_all())
+        .then(|| global.flags.variant(&LIST_POSTS, &req));
+    let mut response = HttpResponse::Ok();
+    response.content_type(format.content_type());
+    if let Some(variant) = variant {
+        response.insert_header((VARIANT_HEADER, variant));
+    }
+    if variant == Some(flags::LIST_STREAMING) {
+        let body = stream_posts(
+            posts,
+            global.offload.clone(),
+            global.metrics.clone(),
+            started,
+        );
+        return Ok(response.streaming(connection::watch(&req, body, global.metrics.clone())));
+    }
+    // Rendering a large list is CPU-heavy
+    let body = global
+        .offload
+        .run(move || match format {
+            Format::Json if !fields.is_all() => serde_json::to_vec(&fields.of_all(&posts)),
+            _ => format.encode_posts(&posts),
+        })
+        .await?
+        .map_err(|err| ApiError::Internal(format!("failed to encode posts: {err}")))?;
+    if let Some(variant) = variant {
+        global
+            .metrics
+            .record_variant(LIST_POSTS.name, variant, started.elapsed());
+    }
+    Ok(response.body(body))
+}
+
+/// Encodes `posts` as a JSON array, [`LIST_CHUNK`] posts at a time on the offload pool, recording
+/// the response of the streaming variant of [`LIST_POSTS`] once the array is complete.
+fn stream_posts(
+    posts: Vec<Post>,
+    offload: Offload,
+    metrics: Arc<Metrics>,
+    started: Instant,
+) -> impl futures_util::Stream<Item = Result<web::Bytes, io::Error>> {
+    stream::unfold(Some((posts.into_iter(), true)), move |state| {
+        let (offload, metrics) = (offload.clone(), metrics.clone());
+        async move {
+            let (mut posts, first) = state?;
+            let chunk: Vec<_> = posts.by_ref().take(LIST_CHUNK).collect();
+            if chunk.is_empty() {
+                metrics.record_variant(LIST_POSTS.name, flags::LIST_STREAMING, started.elapsed());
+                let end = if first { "[]" } else { "]" };
+                return Some((Ok(web::Bytes::from_static(end.as_bytes())), None));
+            }
+            let bytes = offload
+                .run(move || {
+                    let mut bytes = Vec::new();
+                    for (i, post) in chunk.iter().enumerate() {
+                        bytes.push(if first && i == 0 { b'[' } else { b',' });
+                        serde_json::to_writer(&mut bytes, post)?;
+                    }
+                    Ok::<_, serde_json::Error>(web::Bytes::from(bytes))
+                })
+                .await
+                .map_err(|err| io::Error::other(err.to_string()))
+                .and_then(|bytes| bytes.map_err(io::Error::other));
+            let next = bytes.is_ok().then_some((posts, false));
+            Some((bytes, next))
+        }
+    })
 }
 
 /// Handles `POST /posts`
 ///
-/// Creates a new blog post from the request body.
+/// Creates a new blog post from the request body and enqueues notifications for the author's followers.
 /// Requires a valid [`AuthToken`] (simulated in this implementation).
 ///
+/// If `publish_at` is in the future, the post is created as scheduled; followers are notified
+/// once the scheduler publishes it.
+///
+/// The ID is generated, unless the client chooses it with the [`POST_ID_HEADER`] header (a UUID).
+/// Chosen IDs are expected to be fresh UUIDs: concurrent requests choosing the same ID may both
+/// succeed, the last one winning.
+///
+/// The content goes through the configured [`Sanitizer`] and [`ContentModerator`] first; flagged
+/// posts are stored and listed at `GET /admin/moderation/queue`.
+///
 /// # Request Body
-/// Expects a JSON payload conforming to [`PostInput`].
+/// Expects a JSON payload conforming to [`PostInput`], or its protobuf form (see [`Input`]).
 ///
 /// # Response
 /// - `201 Created` with the created [`Post`] as JSON
-/// - `Location` header pointing to the newly created resource
+/// - `Location` header with the path of the created post (see [`location`])
+/// - `400 Bad Request` if the chosen ID isn't a UUID or a date isn't strict RFC 3339
+/// - `409 Conflict` if a post with the chosen ID exists
+/// - `422 Unprocessable Entity` if the sanitizer or the moderator rejects the content
 #[post("")]
 async fn create_post(
     _auth: AuthToken,
+    req: HttpRequest,
     state: web::Data<PostsState>,
-    body: web::Json<PostInput>,
-) -> impl Responder {
+    deadline: Deadline,
+    format: Format,
+    Input(input): Input,
+    _query: Query<NoParams>,
+) -> Result<HttpResponse, ApiError> {
     debug!("Request: create post");
-    let post = state.provider.create(body.into_inner());
-    HttpResponse::Created()
-        .append_header(("Location", format!("/posts/{}", post.id)))
-        .json(post)
+    let id = req
+        .headers()
+        .get(POST_ID_HEADER)
+        .map(|id| {
+            id.to_str()
+                .ok()
+                .and_then(|id| Uuid::parse_str(id).ok())
+                .ok_or_else(|| ApiError::BadRequest(format!("{POST_ID_HEADER} must be a UUID")))
+        })
+        .transpose()?;
+    let (input, flagged) = state.review(input)?;
+    let provider = state.provider.clone();
+    let post = match id {
+        None => deadline.run(move || provider.create(input)).await?,
+        Some(id) => {
+            deadline
+                .run(move || {
+                    let id = id.to_string();
+                    if provider.get(&id)?.is_some() {
+                        return Err(ProviderError::Conflict(format!("post {id} exists")));
+                    }
+                    let post = Post::new(id, input, Utc::now());
+                    provider.import(vec![post.clone()])?;
+                    Ok(post)
+                })
+                .await?
+        }
+    };
+    state.flag(&post, flagged);
+    if post.status == PostStatus::Published {
+        state.jobs.enqueue(Job::PostCreated(post.clone()));
+    }
+    let mut response = HttpResponse::Created();
+    response.append_header((header::LOCATION, location(&req, location::POST, &post.id)?));
+    Ok(format.post(response, &post))
 }
 
 /// Handles `GET /posts/{id}`
 ///
 /// Retrieves a blog post by its ID.
 ///
+/// JSON responses without `tz`, `fields` and `include` are the [`GET_POST`] experiment: the post
+/// is either decoded and encoded again (`decode`) or sent as the provider stores it (`raw`, see
+/// [`PostsProvider::get_json`]), chosen like the variants of [`list_posts`]; both send the same
+/// body, and the time of each is recorded in the `experiments` section of `GET /metrics`. Both
+/// read through the cache of serialized posts, if enabled (see [`CacheProvider`]), and carry the
+/// `ETag` of the post (see [`Tagged`]), so clients can revalidate it with `If-None-Match`.
+///
 /// # Path Parameters
 /// - `id`: The unique identifier of the post
 ///
+/// # Query Parameters
+/// - `tz`: Offset to render dates at, e.g. `-05:00` (see [`Tz`])
+/// - `fields`: Fields of the post to return, e.g. `id,author,date` (see [`Fields`])
+/// - `include`: Relations to embed, e.g. `author` (see [`Include`]); requires a valid
+///   [`AuthToken`], and the [`flags::SEARCH`] feature flag as the author is looked up by nickname
+///
+/// # Request Headers
+/// - [`VARIANT_HEADER`]: Variant to serve the request with, e.g. `raw`
+/// - `If-None-Match`: Entity tags of versions of the post the client has
+///
 /// # Response
-/// - `200 OK` with the post as JSON
-/// - `404 Not Found` if the post does not exist
-#[get("/{id}")]
-async fn get_post(state: web::Data<PostsState>, path: web::Path<String>) -> impl Responder {
+/// - `200 OK` with the post as JSON, and the variant in [`VARIANT_HEADER`] if there was one;
+///   with `include`, a [`Document`] of the post and its relations
+/// - `304 Not Modified` without a body if the post has one of the tags of `If-None-Match`
+/// - `400 Bad Request` if `tz` isn't a UTC offset, or `fields` or `include` name an unknown field
+///   or relation
+/// - `401 Unauthorized` if `include` is given without a valid token
+/// - `404 Not Found` if the post does not exist, or `include` is given but the feature flag is off
+#[get("/{id}", name = "post")]
+async fn get_post(
+    req: HttpRequest,
+    state: web::Data<PostsState>,
+    global: web::Data<GlobalServerState>,
+    deadline: Deadline,
+    path: web::Path<String>,
+    format: Format,
+    query: Query<PostQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let started = Instant::now();
+    let tz = Tz::parse(query.tz.as_deref())?;
+    let fields = Fields::parse(query.fields.as_deref(), Post::FIELDS)?;
+    let include = Include::parse(query.include.as_deref())?;
+    if !include.is_empty() {
+        AuthToken::extract(&req).await?;
+        global.flags.require(flags::SEARCH)?;
+    }
     let id = path.into_inner();
     debug!("Request: get post {}", id);
-    match state.provider.get(&id) {
-        Some(post) => HttpResponse::Ok().json(post),
-        None => HttpResponse::NotFound().finish(),
+    let provider = state.provider.clone();
+    let variant =
+        (format == Format::Json && tz.0.is_none() && fields.is_all() && include.is_empty())
+            .then(|| global.flags.variant(&GET_POST, &req));
+    if let Some(variant) = variant {
+        let decode = variant == flags::GET_DECODE;
+        let tagged = deadline
+            .run(move || provider.get_tagged(&id, decode))
+            .await?
+            .ok_or(ApiError::NotFound)?;
+        let etag = EntityTag::new_strong(tagged.etag);
+        let fresh = match req.get_header::<header::IfNoneMatch>() {
+            Some(header::IfNoneMatch::Any) => true,
+            Some(header::IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(&etag)),
+            None => false,
+        };
+        let mut response = if fresh {
+            HttpResponse::NotModified()
+        } else {
+            HttpResponse::Ok()
+        };
+        response
+            .insert_header(header::ETag(etag))
+            .insert_header((VARIANT_HEADER, variant));
+        let response = if fresh {
+            response.finish()
+        } else {
+            response
+                .content_type(format.content_type())
+                .body(tagged.json)
+        };
+        global
+            .metrics
+            .record_variant(GET_POST.name, variant, started.elapsed());
+        return Ok(response);
     }
+    let users = global.provider.clone();
+    let (post, included) = deadline
+        .run(move || {
+            let Some(post) = provider.get(&id)? else {
+                return Ok(None);
+            };
+            let included = include.resolve(users.as_ref(), &post)?;
+            Ok(Some((post, included)))
+        })
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    let mut response = HttpResponse::Ok();
+    let post = tz.apply(post);
+    Ok(match format {
+        Format::Json if !include.is_empty() => response.json(Document {
+            data: fields.of(&post),
+            included,
+        }),
+        Format::Json if !fields.is_all() => response.json(fields.of(&post)),
+        _ => format.post(response, &post),
+    })
 }
 
 /// Handles `PUT /posts/{id}`
 ///
-/// Updates an existing blog post with new data.
+/// Updates an existing blog post with new data, sanitized and moderated like in [`create_post`].
 /// Requires a valid [`AuthToken`] (simulated).
 ///
 /// # Path Parameters
 /// - `id`: The ID of the post to update
 ///
 /// # Request Body
-/// JSON payload matching [`PostInput`]
+/// JSON payload matching [`PostInput`], or its protobuf form (see [`Input`])
 ///
 /// # Response
 /// - `200 OK` with updated post
 /// - `404 Not Found` if the post does not exist
+/// - `422 Unprocessable Entity` if the sanitizer or the moderator rejects the content
 #[put("/{id}")]
 async fn update_post(
     _auth: AuthToken,
     state: web::Data<PostsState>,
+    deadline: Deadline,
     path: web::Path<String>,
-    body: web::Json<PostInput>,
-) -> impl Responder {
+    format: Format,
+    Input(input): Input,
+    _query: Query<NoParams>,
+) -> Result<HttpResponse, ApiError> {
     let id = path.into_inner();
     debug!("Request: update post {}", id);
-    match state.provider.update(&id, body.into_inner()) {
-        Some(post) => HttpResponse::Ok().json(post),
-        None => HttpResponse::NotFound().finish(),
+    let (input, flagged) = state.review(input)?;
+    let provider = state.provider.clone();
+    match deadline.run(move || provider.update(&id, input)).await? {
+        Some(post) => {
+            state.flag(&post, flagged);
+            Ok(format.post(HttpResponse::Ok(), &post))
+        }
+        None => Err(ApiError::NotFound),
     }
 }
 
@@ -117,25 +509,45 @@ async fn update_post(
 /// Deletes a blog post by ID.
 /// Requires a valid [`AuthToken`] (simulated).
 ///
+/// In idempotent mode (`RUST_SERVER_IDEMPOTENT_DELETE=1`), deleting a missing post also succeeds,
+/// so clients retrying a deletion whose response was lost don't get a `404`.
+///
 /// # Path Parameters
 /// - `id`: The ID of the post to delete
 ///
 /// # Response
 /// - `204 No Content` if deletion was successful
-/// - `404 Not Found` if the post does not exist
+/// - `404 Not Found` if the post does not exist (strict mode only)
+/// - `500 Internal Server Error` if the provider fails
+/// - `504 Gateway Timeout` if the provider misses the request deadline (see [`Deadline`])
 #[delete("/{id}")]
 async fn delete_post(
     _auth: AuthToken,
     state: web::Data<PostsState>,
+    deadline: Deadline,
     path: web::Path<String>,
-) -> impl Responder {
-    if state.provider.delete(&path.into_inner()) {
-        HttpResponse::NoContent().finish()
+    _query: Query<NoParams>,
+) -> Result<HttpResponse, ApiError> {
+    let (provider, id) = (state.provider.clone(), path.into_inner());
+    if deadline.run(move || provider.delete(&id)).await? || state.idempotent_delete {
+        Ok(HttpResponse::NoContent().finish())
     } else {
-        HttpResponse::NotFound().finish()
+        Err(ApiError::NotFound)
     }
 }
 
+/// Routes of `/posts`, for [`methods::configure`].
+const ROUTES: &[Route] = &[
+    Route {
+        pattern: "",
+        methods: &[Method::GET, Method::POST],
+    },
+    Route {
+        pattern: "/{id}",
+        methods: &[Method::GET, Method::PUT, Method::DELETE],
+    },
+];
+
 /// Registers all `/posts` route handlers into the Actix-Web service configuration.
 ///
 /// This function should be called from the main application setup to bind
@@ -146,4 +558,5 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(get_post);
     cfg.service(update_post);
     cfg.service(delete_post);
+    methods::configure(cfg, ROUTES);
 }
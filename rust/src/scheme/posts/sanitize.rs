@@ -0,0 +1,186 @@
+use crate::scheme::posts::PostInput;
+
+/// Elements whose content is dropped along with their tags when HTML is stripped.
+const DROPPED_ELEMENTS: [&str; 2] = ["script", "style"];
+
+/// What [`Sanitizer`] does with unsafe content.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SanitizeMode {
+    /// Content is stored as it is.
+    #[default]
+    Off,
+
+    /// Unsafe parts are removed and the rest is stored.
+    Clean,
+
+    /// Posts with unsafe content are rejected.
+    Reject,
+}
+
+impl SanitizeMode {
+    /// Returns the mode named `off`, `clean` or `reject`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "off" => Some(Self::Off),
+            "clean" => Some(Self::Clean),
+            "reject" => Some(Self::Reject),
+            _ => None,
+        }
+    }
+}
+
+/// Sanitization of the content of created and updated posts, so it's safe to render.
+///
+/// Control characters other than line breaks and tabs are always unsafe; with `strip_html`, so
+/// are HTML tags and comments, and the content of `<script>` and `<style>` elements. Entities are
+/// kept as they are, since they render as text.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sanitizer {
+    pub mode: SanitizeMode,
+    pub strip_html: bool,
+}
+
+impl Sanitizer {
+    /// Applies the sanitizer to `input`. In [`SanitizeMode::Reject`], the error tells what's
+    /// unsafe about the content.
+    pub fn apply(&self, mut input: PostInput) -> Result<PostInput, String> {
+        if self.mode == SanitizeMode::Off {
+            return Ok(input);
+        }
+        let mut content: String = input
+            .content
+            .chars()
+            .filter(|c| !c.is_control() || matches!(c, '\n' | '\r' | '\t'))
+            .collect();
+        if self.mode == SanitizeMode::Reject && content.len() != input.content.len() {
+            return Err("content contains control characters".to_owned());
+        }
+        if self.strip_html {
+            let stripped = strip_html(&content);
+            if self.mode == SanitizeMode::Reject && stripped != content {
+                return Err("content contains HTML".to_owned());
+            }
+            content = stripped;
+        }
+        input.content = content;
+        Ok(input)
+    }
+}
+
+/// Removes tags, comments and dropped elements (see [`DROPPED_ELEMENTS`]) from `text`. A `<` which
+/// doesn't start a tag (e.g. `a < b`) is kept; an unterminated tag is removed up to the end.
+///
+/// Removing a tag can join a kept `<` with the text after it into a new tag, e.g. `<<b>img>`, so
+/// the text is stripped again until nothing changes.
+fn strip_html(text: &str) -> String {
+    let mut stripped = strip_tags(text);
+    loop {
+        let again = strip_tags(&stripped);
+        if again == stripped {
+            return stripped;
+        }
+        stripped = again;
+    }
+}
+
+/// A single pass of [`strip_html`].
+fn strip_tags(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        output.push_str(&rest[..start]);
+        let tag = &rest[start..];
+        let starts_tag = tag[1..]
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '/' || c == '!');
+        if !starts_tag {
+            output.push('<');
+            rest = &tag[1..];
+            continue;
+        }
+        let end = if tag.starts_with("<!--") {
+            tag.find("-->").map(|end| end + 3)
+        } else {
+            tag.find('>').map(|end| end + 1)
+        };
+        let Some(end) = end else {
+            return output;
+        };
+        rest = &tag[end..];
+        let name: String = tag[1..]
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        if DROPPED_ELEMENTS.contains(&name.as_str()) && !tag[..end].ends_with("/>") {
+            let closing = format!("</{name}");
+            rest = match rest.to_ascii_lowercase().find(&closing) {
+                Some(close) => rest[close..]
+                    .find('>')
+                    .map_or("", |end| &rest[close + end + 1..]),
+                None => "",
+            };
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::strip_html;
+
+    // Removes tags, with their attributes, and comments, keeping the text around them.
+    #[test]
+    fn tags() {
+        assert_eq!(strip_html("<p>Hello, <b>world</b>!</p>"), "Hello, world!");
+        assert_eq!(strip_html(r#"<a href="x" onclick='y'>link</a>"#), "link");
+        assert_eq!(strip_html("a<br/>b<!-- <b>c</b> -->d"), "abd");
+        assert_eq!(strip_html("<!DOCTYPE html>text"), "text");
+    }
+
+    // Keeps a `<` which doesn't start a tag, and entities, which render as text.
+    #[test]
+    fn text() {
+        assert_eq!(strip_html("a < b, 1 <2, <3"), "a < b, 1 <2, <3");
+        assert_eq!(strip_html("trailing <"), "trailing <");
+        assert_eq!(
+            strip_html("&lt;script&gt;alert(1)&lt;/script&gt; &amp;"),
+            "&lt;script&gt;alert(1)&lt;/script&gt; &amp;"
+        );
+    }
+
+    // Drops `<script>` and `<style>` elements with their content, whatever the case of their tags.
+    #[test]
+    fn dropped_elements() {
+        assert_eq!(strip_html("a<script>alert('<b>')</script>b"), "ab");
+        assert_eq!(strip_html("a<SCRIPT type=x>alert(1)</Script >b"), "ab");
+        assert_eq!(strip_html("a<style>p { color: red }</style>b"), "ab");
+        assert_eq!(strip_html("a<script src=x />b"), "ab");
+        // Only elements of that name, not ones whose name starts with it
+        assert_eq!(strip_html("<scripts>a</scripts>"), "a");
+        // Without a closing tag, everything after the opening one is dropped
+        assert_eq!(strip_html("a<script>alert(1)"), "a");
+    }
+
+    // Removes unterminated tags and comments up to the end of the text.
+    #[test]
+    fn unterminated() {
+        assert_eq!(strip_html("text<img src=x onerror=alert(1)"), "text");
+        assert_eq!(strip_html("text<!-- <b>comment"), "text");
+    }
+
+    // Strips tags which only appear once the tags inside them are removed, so nesting can't
+    // smuggle a tag through.
+    #[test]
+    fn nested() {
+        assert_eq!(strip_html("<<b>img src=x onerror=alert(1)>"), "");
+        assert_eq!(
+            strip_html("<<script>x</script>script>alert(1)</script>"),
+            ""
+        );
+        assert_eq!(strip_html("<<<b>b>i>text"), "text");
+        assert_eq!(strip_html("<b><i>text</i></b>"), "text");
+    }
+}
@@ -0,0 +1,129 @@
+//! Versions of the stored form of [`Post`].
+//!
+//! Stored posts (WAL records and JSON Lines datasets) carry the `version` of the model which wrote
+//! them, so that logs and snapshots written by older builds still load after the model changes:
+//! each older version is kept as a frozen struct with an upgrade to the next one, and
+//! [`Stored`] reads any known version, upgrading it to the current model.
+//!
+//! When the model changes, freeze the current shape as `PostV<n>`, add an upgrade from it and bump
+//! [`CURRENT_VERSION`].
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+use serde_json::Value;
+
+use crate::scheme::posts::{Post, PostStatus, date};
+
+/// Version of the stored form written by this build.
+pub const CURRENT_VERSION: u64 = 2;
+
+/// Posts written before versioning: dates are UTC, and posts written before scheduled publication
+/// have neither `status` nor `publish_at`. Records without `version` are read as this version.
+#[derive(Debug, Deserialize)]
+pub struct PostV1 {
+    pub id: String,
+    pub author: String,
+    #[serde(deserialize_with = "rfc3339_utc")]
+    pub date: DateTime<Utc>,
+    pub content: String,
+    #[serde(default)]
+    pub status: PostStatus,
+    #[serde(default, deserialize_with = "date::deserialize_utc")]
+    pub publish_at: Option<DateTime<Utc>>,
+}
+
+/// The current version: `date` keeps its offset (see [`date`]). Frozen into a struct of its own
+/// once the model changes.
+pub type PostV2 = Post;
+
+impl From<PostV1> for PostV2 {
+    fn from(post: PostV1) -> Self {
+        Self {
+            id: post.id,
+            author: post.author,
+            date: post.date.fixed_offset(),
+            content: post.content,
+            status: post.status,
+            publish_at: post.publish_at,
+        }
+    }
+}
+
+/// Deserializes a date of [`PostV1`], converting it to UTC.
+fn rfc3339_utc<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<Utc>, D::Error> {
+    date::deserialize(deserializer).map(|date| date.to_utc())
+}
+
+/// A post as it's stored: the current model, tagged with [`CURRENT_VERSION`] when written and
+/// upgraded from older versions when read.
+#[derive(Debug, Clone)]
+pub struct Stored(pub Post);
+
+/// Written form of [`Stored`].
+#[derive(Serialize)]
+struct Tagged<'a> {
+    version: u64,
+    #[serde(flatten)]
+    post: &'a Post,
+}
+
+impl Serialize for Stored {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Tagged {
+            version: CURRENT_VERSION,
+            post: &self.0,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Stored {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut value = Value::deserialize(deserializer)?;
+        let version = match value
+            .as_object_mut()
+            .and_then(|post| post.remove("version"))
+        {
+            None => 1,
+            Some(version) => version
+                .as_u64()
+                .ok_or_else(|| D::Error::custom(format!("invalid post version {version}")))?,
+        };
+        let post = match version {
+            1 => serde_json::from_value::<PostV1>(value).map(Post::from),
+            2 => serde_json::from_value::<PostV2>(value),
+            _ => {
+                return Err(D::Error::custom(format!(
+                    "unsupported post version {version} (this build reads up to \
+                     {CURRENT_VERSION}), written by a newer build?"
+                )));
+            }
+        };
+        post.map(Self).map_err(D::Error::custom)
+    }
+}
+
+impl Stored {
+    /// Returns `true` if `bytes` are exactly what [`Stored`] writes for `post` with this build:
+    /// the current version, field order and date precision, so that [`Stored::unversioned`] can
+    /// serve them as they are.
+    pub fn is_canonical(post: &Post, bytes: &[u8]) -> bool {
+        let tagged = Tagged {
+            version: CURRENT_VERSION,
+            post,
+        };
+        serde_json::to_vec(&tagged).is_ok_and(|written| written == bytes)
+    }
+
+    /// Returns the JSON of the post in `bytes`, written by [`Stored`] with this build, as [`Post`]
+    /// serializes it, by cutting the `version` off instead of decoding it. Returns `None` if
+    /// `bytes` don't start with the current version.
+    pub fn unversioned(bytes: &[u8]) -> Option<Vec<u8>> {
+        let prefix = format!("{{\"version\":{CURRENT_VERSION},");
+        let fields = bytes.strip_prefix(prefix.as_bytes())?;
+        let mut json = Vec::with_capacity(fields.len() + 1);
+        json.push(b'{');
+        json.extend_from_slice(fields);
+        Some(json)
+    }
+}
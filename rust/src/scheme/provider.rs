@@ -1,7 +1,150 @@
+use serde_json::{Value, json};
+use std::{
+    sync::{
+        Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+use tracing::warn;
+
 /// Base trait for all provider implementations, regardless of the specific API resource they handle.
 ///
 /// This trait serves as a common abstraction layer for components that supply or manage data used
 /// in request handling logic (e.g., posts, users, etc.).
 ///
-/// All implementors must be both `Send` and `Sync`, ensuring they can be safely shared across threads.
-pub trait Provider: Send + Sync {}
+/// All implementors must be both `Send` and `Sync`, ensuring they can be safely shared across threads,
+/// and report their internals through [`ProviderStats`].
+pub trait Provider: ProviderStats + Send + Sync {}
+
+/// Internal statistics of a provider, shown at `GET /admin/providers`.
+///
+/// Unlike `/metrics`, which counts what happens, stats describe what a provider holds: item
+/// counts, index sizes, time spent waiting for its locks, compactions, etc. Providers wrapping
+/// another one include its stats under `inner`.
+pub trait ProviderStats {
+    /// Returns the current statistics as a JSON object with a `type` field naming the provider.
+    fn stats(&self) -> Value;
+}
+
+/// Error reported by providers when an operation can't be applied to the stored data.
+///
+/// Lookups that simply find nothing are expressed with `Option` and are not errors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProviderError {
+    /// The operation conflicts with existing data, e.g. a unique field is already taken.
+    /// Carries a human-readable description of the conflict.
+    Conflict(String),
+
+    /// The provider failed for reasons unrelated to the request, e.g. an I/O error of the storage.
+    /// Carries a description for logs; it isn't meant to be shown to clients.
+    Internal(String),
+
+    /// The storage is known to be failing and the call wasn't attempted (see
+    /// [`Breaker`](crate::scheme::breaker::Breaker)). Carries the time after which it's worth
+    /// trying again.
+    Unavailable { retry_after: Duration },
+}
+
+impl std::fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Conflict(msg) => write!(f, "conflict: {msg}"),
+            Self::Internal(msg) => write!(f, "internal: {msg}"),
+            Self::Unavailable { retry_after } => {
+                write!(f, "unavailable, retry after {retry_after:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProviderError {}
+
+impl From<std::io::Error> for ProviderError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Internal(err.to_string())
+    }
+}
+
+/// Acquires a read lock, recovering from poisoning.
+///
+/// A lock gets poisoned when a thread panics while holding it. Providers only mutate their stores
+/// with operations that either complete or leave the store untouched, so the data behind a poisoned
+/// lock is still consistent; recovering keeps a single panic from failing every later request.
+pub fn read<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    lock.read().unwrap_or_else(|err| {
+        warn!("Recovering from a poisoned lock");
+        lock.clear_poison();
+        err.into_inner()
+    })
+}
+
+/// Acquires a write lock, recovering from poisoning (see [`read`]).
+pub fn write<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    lock.write().unwrap_or_else(|err| {
+        warn!("Recovering from a poisoned lock");
+        lock.clear_poison();
+        err.into_inner()
+    })
+}
+
+/// Locks a mutex, recovering from poisoning (see [`read`]).
+pub fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|err| {
+        warn!("Recovering from a poisoned lock");
+        mutex.clear_poison();
+        err.into_inner()
+    })
+}
+
+/// Time spent waiting for the locks of a provider, reported in its [`ProviderStats`].
+///
+/// Its methods acquire a lock like [`read`], [`write`] and [`lock`], measuring how long it took.
+#[derive(Debug, Default)]
+pub struct LockWaits {
+    /// Number of acquired locks.
+    acquired: AtomicU64,
+
+    /// Total time spent waiting for them, in nanoseconds.
+    wait_ns: AtomicU64,
+}
+
+impl LockWaits {
+    /// Acquires a read lock (see [`read`]).
+    pub fn read<'a, T>(&self, lock: &'a RwLock<T>) -> RwLockReadGuard<'a, T> {
+        let started = Instant::now();
+        let guard = read(lock);
+        self.record(started);
+        guard
+    }
+
+    /// Acquires a write lock (see [`write`]).
+    pub fn write<'a, T>(&self, lock: &'a RwLock<T>) -> RwLockWriteGuard<'a, T> {
+        let started = Instant::now();
+        let guard = write(lock);
+        self.record(started);
+        guard
+    }
+
+    /// Locks a mutex (see [`lock`]).
+    pub fn lock<'a, T>(&self, mutex: &'a Mutex<T>) -> MutexGuard<'a, T> {
+        let started = Instant::now();
+        let guard = lock(mutex);
+        self.record(started);
+        guard
+    }
+
+    /// Returns the statistics as JSON.
+    pub fn stats(&self) -> Value {
+        json!({
+            "acquired": self.acquired.load(Ordering::Relaxed),
+            "wait_ns": self.wait_ns.load(Ordering::Relaxed),
+        })
+    }
+
+    fn record(&self, started: Instant) {
+        self.acquired.fetch_add(1, Ordering::Relaxed);
+        self.wait_ns
+            .fetch_add(started.elapsed().as_nanos() as u64, Ordering::Relaxed);
+    }
+}
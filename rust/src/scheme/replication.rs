@@ -0,0 +1,283 @@
+//! Replication of posts between running instances.
+//!
+//! Every instance publishes the changes of its posts to [`Changes`] (see
+//! [`ChangesProvider`](crate::scheme::posts::ChangesProvider)), which `GET /admin/changes` streams
+//! to subscribers as server-sent events. An instance started with `RUST_SERVER_REPLICATE_FROM` is a
+//! replica: it loads a snapshot of its primary, then applies the primary's change stream to its own
+//! provider (see [`crate::jobs::replication`]). Its progress is tracked by [`Replica`] and shown at
+//! `GET /admin/replication`.
+//!
+//! Replication is asynchronous: a replica serves reads which may lag behind the primary, and
+//! writes sent to a replica aren't sent back to the primary.
+
+use actix_web::{rt, web};
+use chrono::{DateTime, Utc};
+use futures_util::{Stream, stream};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    io,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::sync::{
+    broadcast::{self, error::RecvError},
+    watch,
+};
+use tracing::debug;
+
+use crate::scheme::{posts::Post, provider::lock};
+
+/// Number of changes a subscriber may fall behind before it's disconnected (and has to resync).
+const CHANNEL_CAPACITY: usize = 4096;
+
+/// How often the change stream reports the latest sequence number, also keeping idle connections
+/// alive.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Change of a single post.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Change {
+    /// A post was created, updated or imported; carries its new version.
+    Upsert { post: Post },
+
+    /// A post was deleted.
+    Delete { id: String },
+}
+
+/// A [`Change`] as streamed to subscribers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeEvent {
+    /// Sequence number, increasing by one with every published change.
+    pub seq: u64,
+
+    /// When the change was published.
+    pub at: DateTime<Utc>,
+
+    #[serde(flatten)]
+    pub change: Change,
+}
+
+/// Sequence number reported by the heartbeats of the change stream.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Heartbeat {
+    /// Sequence number of the latest published change.
+    pub seq: u64,
+}
+
+/// Broadcast channel of the changes of the posts of this instance.
+///
+/// Changes are only recorded while somebody subscribes; a new subscriber loads a snapshot first
+/// anyway.
+pub struct Changes {
+    sender: broadcast::Sender<Arc<ChangeEvent>>,
+
+    /// Sequence number of the latest change; sending happens under this lock, so subscribers
+    /// receive changes in sequence order.
+    seq: Mutex<u64>,
+
+    /// Set once the server shuts down, ending all streams.
+    closed: watch::Sender<bool>,
+}
+
+impl Default for Changes {
+    fn default() -> Self {
+        Self {
+            sender: broadcast::Sender::new(CHANNEL_CAPACITY),
+            seq: Mutex::new(0),
+            closed: watch::Sender::new(false),
+        }
+    }
+}
+
+impl Changes {
+    /// Returns `true` if anybody subscribed, i.e. if changes are worth building.
+    pub fn is_watched(&self) -> bool {
+        self.sender.receiver_count() > 0
+    }
+
+    /// Publishes `changes` to all subscribers, in order.
+    pub fn publish(&self, changes: impl IntoIterator<Item = Change>) {
+        if !self.is_watched() {
+            return;
+        }
+        let at = Utc::now();
+        let mut seq = lock(&self.seq);
+        for change in changes {
+            *seq += 1;
+            // Fails only if every subscriber is gone in the meantime
+            let _ = self.sender.send(Arc::new(ChangeEvent {
+                seq: *seq,
+                at,
+                change,
+            }));
+        }
+    }
+
+    /// Returns the sequence number of the latest change.
+    pub fn seq(&self) -> u64 {
+        *lock(&self.seq)
+    }
+
+    /// Returns the sequence number and the number of subscribers, as shown at
+    /// `GET /admin/replication`.
+    pub fn status(&self) -> serde_json::Value {
+        json!({
+            "seq": self.seq(),
+            "subscribers": self.sender.receiver_count(),
+        })
+    }
+
+    /// Subscribes to changes and returns them as a `text/event-stream` body: `change` events with
+    /// a [`ChangeEvent`] and, every [`HEARTBEAT_INTERVAL`], `heartbeat` events with a
+    /// [`Heartbeat`].
+    ///
+    /// A subscriber falling more than [`CHANNEL_CAPACITY`] changes behind gets a `lagged` event
+    /// and the stream ends, so it doesn't silently miss changes.
+    pub fn subscribe(self: Arc<Self>) -> impl Stream<Item = Result<web::Bytes, io::Error>> {
+        let state = (
+            self.sender.subscribe(),
+            rt::time::interval(HEARTBEAT_INTERVAL),
+            self.closed.subscribe(),
+            self,
+        );
+        stream::unfold(Some(state), |state| async move {
+            let (mut receiver, mut heartbeats, mut closed, changes) = state?;
+            let event = tokio::select! {
+                _ = closed.wait_for(|closed| *closed) => return None,
+                event = receiver.recv() => match event {
+                    Ok(event) => sse("change", &*event),
+                    Err(RecvError::Lagged(missed)) => {
+                        return Some((Ok(sse("lagged", &json!({ "missed": missed }))), None));
+                    }
+                    Err(RecvError::Closed) => return None,
+                },
+                _ = heartbeats.tick() => sse("heartbeat", &Heartbeat { seq: changes.seq() }),
+            };
+            Some((Ok(event), Some((receiver, heartbeats, closed, changes))))
+        })
+    }
+
+    /// Starts a task on the current Actix runtime which ends all streams once the server is asked
+    /// to stop (`SIGINT` or `SIGTERM`).
+    ///
+    /// Otherwise, the graceful shutdown of the server would wait for the streams of replicas,
+    /// which never end, until its timeout.
+    pub fn close_on_shutdown(self: &Arc<Self>) {
+        let changes = self.clone();
+        rt::spawn(async move {
+            #[cfg(unix)]
+            {
+                use rt::signal::unix::{SignalKind, signal};
+                let Ok(mut terminate) = signal(SignalKind::terminate()) else {
+                    return;
+                };
+                tokio::select! {
+                    _ = rt::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+            }
+            #[cfg(not(unix))]
+            let _ = rt::signal::ctrl_c().await;
+            debug!("Closing change streams");
+            changes.closed.send_replace(true);
+        });
+    }
+}
+
+/// Encodes a server-sent event.
+fn sse<T: Serialize>(name: &str, data: &T) -> web::Bytes {
+    // JSON without pretty-printing has no new lines, so it fits a single `data` field
+    let data = serde_json::to_string(data).unwrap_or_default();
+    web::Bytes::from(format!("event: {name}\ndata: {data}\n\n"))
+}
+
+/// Replication progress of a replica, as shown at `GET /admin/replication`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReplicaStatus {
+    /// Base URL of the primary.
+    pub primary: String,
+
+    /// Whether the change stream of the primary is being received.
+    pub connected: bool,
+
+    /// Number of snapshots loaded, i.e. of (re)connections to the primary.
+    pub syncs: u64,
+
+    /// Sequence number of the last applied change of the primary.
+    pub applied_seq: u64,
+
+    /// Latest sequence number of the primary known to the replica.
+    pub primary_seq: u64,
+
+    /// Number of changes of the primary not applied yet.
+    pub lag_changes: u64,
+
+    /// Time between the primary publishing the last applied change and the replica applying it,
+    /// in milliseconds. Assumes the clocks of both instances are in sync.
+    pub lag_ms: u64,
+
+    /// Number of changes applied since the replica started.
+    pub applied: u64,
+
+    /// Why the last connection to the primary ended, if it did.
+    pub last_error: Option<String>,
+}
+
+/// Tracks the progress of a replica; updated by [`crate::jobs::replication`].
+pub struct Replica {
+    status: Mutex<ReplicaStatus>,
+}
+
+impl Replica {
+    pub fn new(primary: String) -> Self {
+        Self {
+            status: Mutex::new(ReplicaStatus {
+                primary,
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Records a snapshot of the primary being loaded, after subscribing to its changes at
+    /// sequence number `seq`.
+    ///
+    /// Sequence numbers start over, since the primary may have been restarted.
+    pub fn synced(&self, seq: u64) {
+        let mut status = lock(&self.status);
+        status.connected = true;
+        status.syncs += 1;
+        status.applied_seq = seq;
+        status.primary_seq = seq;
+        status.lag_changes = 0;
+    }
+
+    /// Records a heartbeat of the primary.
+    pub fn heard(&self, heartbeat: &Heartbeat) {
+        let mut status = lock(&self.status);
+        status.primary_seq = status.primary_seq.max(heartbeat.seq);
+        status.lag_changes = status.primary_seq.saturating_sub(status.applied_seq);
+    }
+
+    /// Records `count` changes up to `event` being applied.
+    pub fn applied(&self, event: &ChangeEvent, count: usize) {
+        let mut status = lock(&self.status);
+        status.applied += count as u64;
+        status.applied_seq = status.applied_seq.max(event.seq);
+        status.primary_seq = status.primary_seq.max(event.seq);
+        status.lag_changes = status.primary_seq.saturating_sub(status.applied_seq);
+        status.lag_ms = (Utc::now() - event.at).num_milliseconds().max(0) as u64;
+    }
+
+    /// Records the connection to the primary being lost.
+    pub fn disconnected(&self, err: String) {
+        let mut status = lock(&self.status);
+        status.connected = false;
+        status.last_error = Some(err);
+    }
+
+    pub fn status(&self) -> ReplicaStatus {
+        lock(&self.status).clone()
+    }
+}
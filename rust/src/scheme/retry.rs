@@ -0,0 +1,126 @@
+use rand::Rng;
+use serde_json::{Value, json};
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+use tracing::{debug, warn};
+
+use crate::{
+    scheme::provider::{ProviderError, lock},
+    state::Metrics,
+};
+
+/// Number of retries the budget can save up, so bursts of failures after a quiet period can still
+/// be retried.
+const BUDGET_CAPACITY: f64 = 10.0;
+
+/// Retry policy of a storage backend (see [`Retrier`]).
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt.
+    pub max_retries: u32,
+
+    /// Upper bound of the delay before the first retry, doubled with every further retry.
+    pub backoff: Duration,
+
+    /// Upper bound of any delay.
+    pub max_backoff: Duration,
+
+    /// Long-term share of calls which may be retried, e.g. `0.2` for one retry per five calls.
+    pub budget: f64,
+}
+
+/// Retries of calls to a storage backend which fail with transient errors.
+///
+/// Only [`ProviderError::Internal`] is retried: conflicts won't go away, and
+/// [`ProviderError::Unavailable`] means a circuit breaker already gave up on the backend. Delays
+/// use exponential backoff with full jitter, so retries of concurrent calls don't arrive together.
+///
+/// Retries are limited by a budget: every call earns [`RetryPolicy::budget`] tokens, up to
+/// [`BUDGET_CAPACITY`], and every retry spends one. When a backend fails for good, retries stop
+/// once the savings are spent instead of multiplying its load.
+///
+/// Delays block the calling thread, like the calls themselves; with
+/// `RUST_SERVER_PROVIDER_DEADLINE_MS`, that's a thread of the blocking pool.
+pub struct Retrier {
+    /// Name of the backend, used in logs.
+    name: &'static str,
+
+    policy: RetryPolicy,
+
+    /// Tokens available for retries.
+    tokens: Mutex<f64>,
+
+    metrics: Arc<Metrics>,
+}
+
+impl Retrier {
+    pub fn new(name: &'static str, policy: RetryPolicy, metrics: Arc<Metrics>) -> Self {
+        Self {
+            name,
+            policy,
+            tokens: Mutex::new(BUDGET_CAPACITY),
+            metrics,
+        }
+    }
+
+    /// Runs `call`, retrying it according to the policy. Returns the result of the last attempt.
+    pub fn call<T>(
+        &self,
+        mut call: impl FnMut() -> Result<T, ProviderError>,
+    ) -> Result<T, ProviderError> {
+        {
+            let mut tokens = lock(&self.tokens);
+            *tokens = (*tokens + self.policy.budget).min(BUDGET_CAPACITY);
+        }
+        let mut retry = 0;
+        loop {
+            let err = match call() {
+                Err(err @ ProviderError::Internal(_)) if retry < self.policy.max_retries => err,
+                result => return result,
+            };
+            if !self.withdraw() {
+                warn!("Retry budget of {} is exhausted: {err}", self.name);
+                Metrics::inc(&self.metrics.storage_retries_denied);
+                return Err(err);
+            }
+            let delay = self.delay(retry);
+            debug!("Retrying a call to {} in {delay:?}: {err}", self.name);
+            Metrics::inc(&self.metrics.storage_retries);
+            thread::sleep(delay);
+            retry += 1;
+        }
+    }
+
+    /// Returns the policy and the tokens left in the budget as JSON.
+    pub fn status(&self) -> Value {
+        json!({
+            "name": self.name,
+            "max_retries": self.policy.max_retries,
+            "budget": self.policy.budget,
+            "tokens": *lock(&self.tokens),
+        })
+    }
+
+    /// Takes a token for a retry, if there's one.
+    fn withdraw(&self) -> bool {
+        let mut tokens = lock(&self.tokens);
+        if *tokens < 1.0 {
+            return false;
+        }
+        *tokens -= 1.0;
+        true
+    }
+
+    /// Returns the delay before retry number `retry` (starting at `0`).
+    fn delay(&self, retry: u32) -> Duration {
+        let ceiling = self
+            .policy
+            .backoff
+            .saturating_mul(1 << retry.min(16))
+            .min(self.policy.max_backoff);
+        ceiling.mul_f64(rand::rng().random_range(0.0..=1.0))
+    }
+}
@@ -0,0 +1,91 @@
+use tracing::{debug, warn};
+
+/// Compensating action registered by a successfully applied step.
+type Undo = Box<dyn FnOnce() + Send>;
+
+/// Cross-provider transaction built from compensating steps (a saga).
+///
+/// Providers don't share storage, so a mutation spanning several of them can't be made atomic.
+/// Instead, each step is applied immediately and registers an `undo` action. If a later step fails,
+/// or the transaction is dropped without [`Transaction::commit`], all applied steps are undone in
+/// reverse order.
+///
+/// Intermediate states are visible to concurrent readers; the transaction only guarantees that
+/// the overall operation is either completed or rolled back.
+///
+/// # Example
+/// ```ignore
+/// let mut tx = Transaction::new("delete user");
+/// let ids = tx.step("reassign posts", || Ok(posts.reassign(..)), move |ids| posts.restore(ids))?;
+/// tx.step("delete user", || users.delete(id).ok_or(NotFound), |_| {})?;
+/// tx.commit();
+/// ```
+pub struct Transaction {
+    /// Name used in logs.
+    name: &'static str,
+
+    /// Compensating actions of applied steps, in order of application.
+    undo: Vec<(&'static str, Undo)>,
+}
+
+impl Transaction {
+    /// Starts a new, empty transaction.
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            undo: Vec::new(),
+        }
+    }
+
+    /// Applies a step of the transaction.
+    ///
+    /// On success, `undo` is registered with a copy of the step's output and the output is returned.
+    /// On failure, all previously applied steps are rolled back and the error is returned.
+    pub fn step<T, E>(
+        &mut self,
+        step: &'static str,
+        apply: impl FnOnce() -> Result<T, E>,
+        undo: impl FnOnce(T) + Send + 'static,
+    ) -> Result<T, E>
+    where
+        T: Clone + Send + 'static,
+    {
+        match apply() {
+            Ok(output) => {
+                debug!("Transaction \"{}\": step \"{step}\" applied", self.name);
+                let registered = output.clone();
+                self.undo.push((step, Box::new(move || undo(registered))));
+                Ok(output)
+            }
+            Err(err) => {
+                warn!("Transaction \"{}\": step \"{step}\" failed", self.name);
+                self.rollback();
+                Err(err)
+            }
+        }
+    }
+
+    /// Completes the transaction, discarding all compensating actions.
+    pub fn commit(mut self) {
+        debug!("Transaction \"{}\": committed", self.name);
+        self.undo.clear();
+    }
+
+    /// Undoes all applied steps in reverse order.
+    fn rollback(&mut self) {
+        while let Some((step, undo)) = self.undo.pop() {
+            warn!(
+                "Transaction \"{}\": rolling back step \"{step}\"",
+                self.name
+            );
+            undo();
+        }
+    }
+}
+
+impl Drop for Transaction {
+    /// Rolls back applied steps of a transaction which wasn't committed.
+    fn drop(&mut self) {
+        self.rollback();
+    }
+}
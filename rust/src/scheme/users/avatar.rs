@@ -0,0 +1,37 @@
+use image::{ImageError, ImageFormat, imageops::FilterType};
+use std::io::Cursor;
+
+/// Maximum accepted size of an uploaded avatar, in bytes.
+pub const MAX_AVATAR_SIZE: usize = 2 * 1024 * 1024;
+
+/// Width and height of the generated thumbnail, in pixels. The aspect ratio is preserved.
+const THUMBNAIL_SIZE: u32 = 128;
+
+/// Content type of stored thumbnails; all uploads are re-encoded as PNG.
+pub const THUMBNAIL_CONTENT_TYPE: &str = "image/png";
+
+/// Maps the `Content-Type` of an upload to a supported image format.
+///
+/// Returns `None` for content types that can't be used as an avatar.
+pub fn format_from_content_type(content_type: &str) -> Option<ImageFormat> {
+    match content_type {
+        "image/png" => Some(ImageFormat::Png),
+        "image/jpeg" => Some(ImageFormat::Jpeg),
+        _ => None,
+    }
+}
+
+/// Decodes an uploaded image, scales it down to fit [`THUMBNAIL_SIZE`] and encodes the result as PNG.
+///
+/// This is CPU-bound work and should be called from the blocking pool (e.g. via `web::block`)
+/// rather than from a reactor thread.
+///
+/// # Errors
+/// Returns an [`ImageError`] if the data isn't a valid image of the given format or can't be re-encoded.
+pub fn make_thumbnail(bytes: &[u8], format: ImageFormat) -> Result<Vec<u8>, ImageError> {
+    let image = image::load_from_memory_with_format(bytes, format)?;
+    let thumbnail = image.resize(THUMBNAIL_SIZE, THUMBNAIL_SIZE, FilterType::Triangle);
+    let mut output = Cursor::new(Vec::new());
+    thumbnail.write_to(&mut output, ImageFormat::Png)?;
+    Ok(output.into_inner())
+}
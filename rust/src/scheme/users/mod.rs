@@ -1,11 +1,8 @@
-#[cfg(test)]
-mod proptests;
-
-pub mod model;
+pub mod avatar;
 pub mod provider;
 pub mod providers;
 pub mod routes;
 
-pub use model::*;
+pub use percom_model::users::*;
 pub use provider::*;
 pub use providers::*;
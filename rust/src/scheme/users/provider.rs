@@ -1,4 +1,7 @@
-use crate::scheme::{provider::Provider, users::model::*};
+use crate::scheme::{
+    provider::{Provider, ProviderError},
+    users::*,
+};
 
 /// Trait for managing user-related resources and basic authentication logic.
 ///
@@ -12,24 +15,99 @@ use crate::scheme::{provider::Provider, users::model::*};
 ///
 /// - [`get_all`] — Returns all users.
 /// - [`get`] — Retrieves a user by ID.
+/// - [`find`] — Looks users up by email and/or nickname.
 /// - [`create`] — Creates a new user from input data.
+/// - [`update`] — Replaces the data of an existing user.
+/// - [`delete`] — Removes a user together with the data owned by them.
 /// - [`is_token_valid`] — Verifies the validity of an authorization token.
+/// - [`token_subject`] — Resolves the user a token belongs to.
+/// - [`revoke_tokens`] — Invalidates all tokens issued to a user.
+/// - [`set_disabled`] / [`is_disabled`] — Disable and re-enable the account of a user.
+/// - [`follow`] / [`unfollow`] / [`following`] — Manage the authors a user follows.
+/// - [`followers`] — Returns the users following an author.
+/// - [`remove_followers`] — Makes every user stop following an author.
+/// - [`set_avatar`] / [`get_avatar`] — Store and retrieve the user's avatar thumbnail.
+///
+/// Every method is fallible: storage failures are reported as [`ProviderError::Internal`], while
+/// lookups that simply find nothing are expressed with `Option` or `bool`.
 ///
 /// # Notes
 /// - This trait is intentionally minimal and can be expanded to support password auth, roles, profiles, etc.
 /// - The `is_token_valid` method can be used by request extractors like [`AuthToken`] to perform authentication checks.
 pub trait UsersProvider: Provider {
     /// Returns a list of all users.
-    fn get_all(&self) -> Vec<User>;
+    fn get_all(&self) -> Result<Vec<User>, ProviderError>;
 
     /// Returns a user by ID, or `None` if not found.
-    fn get(&self, id: &str) -> Option<User>;
+    fn get(&self, id: &str) -> Result<Option<User>, ProviderError>;
+
+    /// Returns users matching all given criteria; `None` criteria are ignored.
+    ///
+    /// Emails are compared case-insensitively, nicknames exactly.
+    fn find(&self, email: Option<&str>, nickname: Option<&str>)
+    -> Result<Vec<User>, ProviderError>;
 
     /// Creates a new user and returns the resulting object.
-    fn create(&self, input: UserInput) -> User;
+    ///
+    /// Fails with [`ProviderError::Conflict`] if the email or the nickname is already used by another
+    /// user.
+    fn create(&self, input: UserInput) -> Result<User, ProviderError>;
+
+    /// Updates an existing user, returning the updated object, or `None` if the user does not exist.
+    ///
+    /// Fails with [`ProviderError::Conflict`] if the email or the nickname is already used by another
+    /// user.
+    fn update(&self, id: &str, input: UserInput) -> Result<Option<User>, ProviderError>;
+
+    /// Deletes a user, including their follows and avatar, and returns the removed user.
+    ///
+    /// Returns `None` if the user does not exist.
+    fn delete(&self, id: &str) -> Result<Option<User>, ProviderError>;
 
     /// Validates the given token.
     ///
     /// Returns `true` if the token is considered valid; otherwise, `false`.
-    fn is_token_valid(&self, _token: &str) -> bool;
+    fn is_token_valid(&self, _token: &str) -> Result<bool, ProviderError>;
+
+    /// Returns the ID of the user the token was issued to, or `None` if the token isn't bound to a user.
+    fn token_subject(&self, token: &str) -> Result<Option<String>, ProviderError>;
+
+    /// Invalidates all tokens issued to the user with the given ID.
+    fn revoke_tokens(&self, id: &str) -> Result<(), ProviderError>;
+
+    /// Disables or re-enables the account of the user; the tokens of a disabled user stay valid,
+    /// but aren't accepted until the user is enabled again.
+    ///
+    /// Returns `false` if the user does not exist.
+    fn set_disabled(&self, id: &str, disabled: bool) -> Result<bool, ProviderError>;
+
+    /// Returns `true` if the account of the user is disabled.
+    fn is_disabled(&self, id: &str) -> Result<bool, ProviderError>;
+
+    /// Adds `author` to the list of authors followed by the user.
+    ///
+    /// Returns `false` if the user does not exist.
+    fn follow(&self, id: &str, author: &str) -> Result<bool, ProviderError>;
+
+    /// Removes `author` from the list of authors followed by the user.
+    ///
+    /// Returns `false` if the user does not exist or didn't follow the author.
+    fn unfollow(&self, id: &str, author: &str) -> Result<bool, ProviderError>;
+
+    /// Returns the authors followed by the user, or `None` if the user does not exist.
+    fn following(&self, id: &str) -> Result<Option<Vec<String>>, ProviderError>;
+
+    /// Returns all users following `author`.
+    fn followers(&self, author: &str) -> Result<Vec<User>, ProviderError>;
+
+    /// Removes `author` from the followed authors of all users, returning the IDs of the affected users.
+    fn remove_followers(&self, author: &str) -> Result<Vec<String>, ProviderError>;
+
+    /// Stores the avatar of the user, replacing the previous one.
+    ///
+    /// Returns `false` if the user does not exist.
+    fn set_avatar(&self, id: &str, avatar: Vec<u8>) -> Result<bool, ProviderError>;
+
+    /// Returns the avatar of the user, or `None` if the user does not exist or has no avatar.
+    fn get_avatar(&self, id: &str) -> Result<Option<Vec<u8>>, ProviderError>;
 }
@@ -1,17 +1,89 @@
+use serde_json::{Value, json};
 use std::{
-    collections::HashMap,
+    collections::{BTreeSet, HashMap, HashSet},
     sync::{Arc, RwLock},
 };
 use uuid::Uuid;
 
-use crate::scheme::{provider::Provider, users::*};
+use crate::scheme::{
+    provider::{LockWaits, Provider, ProviderError, ProviderStats},
+    users::*,
+};
+
+/// Internal storage of [`DummyProvider`].
+#[derive(Default)]
+struct Store {
+    /// All users, keyed by ID.
+    users: HashMap<String, User>,
+
+    /// Secondary index: normalized (lowercased) email to user ID. Enforces email uniqueness.
+    by_email: HashMap<String, String>,
+
+    /// Secondary index: nickname to user ID. Enforces nickname uniqueness, as posts and follows
+    /// name their author by nickname.
+    by_nickname: HashMap<String, String>,
+
+    /// Authors followed by each user, keyed by user ID.
+    follows: HashMap<String, BTreeSet<String>>,
+
+    /// Encoded avatar thumbnails, keyed by user ID.
+    avatars: HashMap<String, Vec<u8>>,
+
+    /// Tokens which are no longer accepted.
+    revoked: HashSet<String>,
+
+    /// IDs of the users whose accounts are disabled.
+    disabled: HashSet<String>,
+}
+
+impl Store {
+    /// Fails if `email` is taken by a user other than `id`.
+    fn check_email(&self, email: &str, id: Option<&str>) -> Result<(), ProviderError> {
+        match self.by_email.get(&normalize_email(email)) {
+            Some(owner) if Some(owner.as_str()) != id => Err(ProviderError::Conflict(format!(
+                "email {email} is already registered"
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fails if `nickname` is taken by a user other than `id`.
+    fn check_nickname(&self, nickname: &str, id: Option<&str>) -> Result<(), ProviderError> {
+        match self.by_nickname.get(nickname) {
+            Some(owner) if Some(owner.as_str()) != id => Err(ProviderError::Conflict(format!(
+                "nickname {nickname} is already registered"
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Inserts or replaces a user, keeping the email and nickname indexes consistent.
+    fn insert(&mut self, user: User) {
+        if let Some(prev) = self.users.get(&user.id) {
+            self.by_email.remove(&normalize_email(&prev.email));
+            self.by_nickname.remove(&prev.nickname);
+        }
+        self.by_email
+            .insert(normalize_email(&user.email), user.id.clone());
+        self.by_nickname
+            .insert(user.nickname.clone(), user.id.clone());
+        self.users.insert(user.id.clone(), user);
+    }
+}
+
+/// Normalizes an email for indexing, making lookups and uniqueness checks case-insensitive.
+fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
 
 /// In-memory implementation of the [`UsersProvider`] trait for testing and demonstration.
 ///
-/// This provider uses a thread-safe `HashMap` to store user records in memory.  
+/// This provider uses a thread-safe `HashMap` to store user records in memory.
 /// It does not perform any persistent storage and is not intended for production use.
 ///
-/// Token validation is stubbed to always return `true`, simulating an "authenticated" request.
+/// Token validation is stubbed to accept every token which wasn't explicitly revoked, simulating
+/// an "authenticated" request. A token equal to the ID of an existing user is treated as issued to that user, which is enough
+/// to drive per-user endpoints such as `/feed`.
 ///
 /// # Purpose
 /// - To demonstrate how the `/users` endpoint group could be implemented.
@@ -19,8 +91,12 @@ use crate::scheme::{provider::Provider, users::*};
 ///
 /// # Concurrency
 /// Internally guarded by `RwLock` to allow safe concurrent read/write access from multiple threads.
+/// A lock poisoned by a panic is recovered from, so only conflicts are reported as errors.
 pub struct DummyProvider {
-    store: RwLock<HashMap<String, User>>,
+    store: RwLock<Store>,
+
+    /// Waits for the lock of `store`.
+    locks: LockWaits,
 }
 
 impl DummyProvider {
@@ -28,7 +104,8 @@ impl DummyProvider {
     #[allow(dead_code)]
     pub fn new() -> Self {
         Self {
-            store: RwLock::new(HashMap::new()),
+            store: RwLock::new(Store::default()),
+            locks: LockWaits::default(),
         }
     }
     /// Creates a new `DummyProvider` wrapped in an `Arc`.
@@ -36,42 +113,238 @@ impl DummyProvider {
     /// Useful for sharing across threads or injecting into Actix-Web app state.
     pub fn wrapped() -> Arc<Self> {
         Arc::new(Self {
-            store: RwLock::new(HashMap::new()),
+            store: RwLock::new(Store::default()),
+            locks: LockWaits::default(),
         })
     }
 }
 
 impl Provider for DummyProvider {}
 
+impl ProviderStats for DummyProvider {
+    fn stats(&self) -> Value {
+        let store = self.locks.read(&self.store);
+        json!({
+            "type": "memory",
+            "users": store.users.len(),
+            "indexes": {
+                "by_email": store.by_email.len(),
+                "by_nickname": store.by_nickname.len(),
+            },
+            "follows": store.follows.values().map(BTreeSet::len).sum::<usize>(),
+            "avatars": store.avatars.len(),
+            "revoked_tokens": store.revoked.len(),
+            "disabled_users": store.disabled.len(),
+            "locks": self.locks.stats(),
+        })
+    }
+}
+
 impl UsersProvider for DummyProvider {
     /// Returns all stored users.
-    fn get_all(&self) -> Vec<User> {
-        self.store.read().unwrap().values().cloned().collect()
+    fn get_all(&self) -> Result<Vec<User>, ProviderError> {
+        Ok(self
+            .locks
+            .read(&self.store)
+            .users
+            .values()
+            .cloned()
+            .collect())
     }
 
     /// Returns a user by ID, if present.
-    fn get(&self, id: &str) -> Option<User> {
-        self.store.read().unwrap().get(id).cloned()
+    fn get(&self, id: &str) -> Result<Option<User>, ProviderError> {
+        Ok(self.locks.read(&self.store).users.get(id).cloned())
+    }
+
+    /// Uses the email index for email lookups and the nickname index for nickname-only lookups.
+    fn find(
+        &self,
+        email: Option<&str>,
+        nickname: Option<&str>,
+    ) -> Result<Vec<User>, ProviderError> {
+        let store = self.locks.read(&self.store);
+        let matches_nickname = |user: &&User| nickname.is_none_or(|n| user.nickname == n);
+        Ok(match email {
+            Some(email) => store
+                .by_email
+                .get(&normalize_email(email))
+                .and_then(|id| store.users.get(id))
+                .filter(matches_nickname)
+                .cloned()
+                .into_iter()
+                .collect(),
+            None => match nickname {
+                Some(nickname) => store
+                    .by_nickname
+                    .get(nickname)
+                    .and_then(|id| store.users.get(id))
+                    .cloned()
+                    .into_iter()
+                    .collect(),
+                None => store.users.values().cloned().collect(),
+            },
+        })
     }
 
     /// Creates a new user with a generated UUID and stores it.
     ///
     /// The resulting `User` is returned.
-    fn create(&self, input: UserInput) -> User {
-        let id = Uuid::new_v4().to_string();
-        let post = User {
-            id: id.clone(),
+    fn create(&self, input: UserInput) -> Result<User, ProviderError> {
+        let mut store = self.locks.write(&self.store);
+        store.check_email(&input.email, None)?;
+        store.check_nickname(&input.nickname, None)?;
+        let user = User {
+            id: Uuid::new_v4().to_string(),
+            nickname: input.nickname,
+            email: input.email,
+        };
+        store.insert(user.clone());
+        Ok(user)
+    }
+
+    /// Replaces the user's nickname and email, re-indexing both.
+    fn update(&self, id: &str, input: UserInput) -> Result<Option<User>, ProviderError> {
+        let mut store = self.locks.write(&self.store);
+        if !store.users.contains_key(id) {
+            return Ok(None);
+        }
+        store.check_email(&input.email, Some(id))?;
+        store.check_nickname(&input.nickname, Some(id))?;
+        let user = User {
+            id: id.to_owned(),
             nickname: input.nickname,
             email: input.email,
         };
-        self.store.write().unwrap().insert(id.clone(), post.clone());
-        post
+        store.insert(user.clone());
+        Ok(Some(user))
     }
 
-    /// Always returns `true` as a placeholder implementation.
+    /// Returns `true` for every token which wasn't revoked, as a placeholder implementation.
     ///
     /// This method simulates successful token validation for all inputs.
-    fn is_token_valid(&self, _token: &str) -> bool {
-        true
+    fn is_token_valid(&self, token: &str) -> Result<bool, ProviderError> {
+        Ok(!self.locks.read(&self.store).revoked.contains(token))
+    }
+
+    /// Removes the user record and everything keyed by the user's ID.
+    fn delete(&self, id: &str) -> Result<Option<User>, ProviderError> {
+        let mut store = self.locks.write(&self.store);
+        let Some(user) = store.users.remove(id) else {
+            return Ok(None);
+        };
+        store.by_email.remove(&normalize_email(&user.email));
+        store.by_nickname.remove(&user.nickname);
+        store.follows.remove(id);
+        store.avatars.remove(id);
+        store.disabled.remove(id);
+        Ok(Some(user))
+    }
+
+    /// Treats the token as a user ID and returns it if such a user exists.
+    fn token_subject(&self, token: &str) -> Result<Option<String>, ProviderError> {
+        Ok(self
+            .locks
+            .read(&self.store)
+            .users
+            .contains_key(token)
+            .then(|| token.to_owned()))
+    }
+
+    /// The only token bound to a user is the user's ID, so it's the one being revoked.
+    fn revoke_tokens(&self, id: &str) -> Result<(), ProviderError> {
+        self.locks.write(&self.store).revoked.insert(id.to_owned());
+        Ok(())
+    }
+
+    /// Adds the user to or removes it from the set of disabled users.
+    fn set_disabled(&self, id: &str, disabled: bool) -> Result<bool, ProviderError> {
+        let mut store = self.locks.write(&self.store);
+        if !store.users.contains_key(id) {
+            return Ok(false);
+        }
+        if disabled {
+            store.disabled.insert(id.to_owned());
+        } else {
+            store.disabled.remove(id);
+        }
+        Ok(true)
+    }
+
+    fn is_disabled(&self, id: &str) -> Result<bool, ProviderError> {
+        Ok(self.locks.read(&self.store).disabled.contains(id))
+    }
+
+    /// Records that the user follows `author`.
+    fn follow(&self, id: &str, author: &str) -> Result<bool, ProviderError> {
+        let mut store = self.locks.write(&self.store);
+        if !store.users.contains_key(id) {
+            return Ok(false);
+        }
+        store
+            .follows
+            .entry(id.to_owned())
+            .or_default()
+            .insert(author.to_owned());
+        Ok(true)
+    }
+
+    /// Removes `author` from the set of followed authors.
+    fn unfollow(&self, id: &str, author: &str) -> Result<bool, ProviderError> {
+        Ok(self
+            .locks
+            .write(&self.store)
+            .follows
+            .get_mut(id)
+            .is_some_and(|authors| authors.remove(author)))
+    }
+
+    /// Returns followed authors in alphabetical order.
+    fn following(&self, id: &str) -> Result<Option<Vec<String>>, ProviderError> {
+        let store = self.locks.read(&self.store);
+        Ok(store.users.contains_key(id).then(|| {
+            store
+                .follows
+                .get(id)
+                .map(|authors| authors.iter().cloned().collect())
+                .unwrap_or_default()
+        }))
+    }
+
+    /// Scans the follows of all users, as there is no reverse index of followers.
+    fn followers(&self, author: &str) -> Result<Vec<User>, ProviderError> {
+        let store = self.locks.read(&self.store);
+        Ok(store
+            .follows
+            .iter()
+            .filter(|(_, authors)| authors.contains(author))
+            .filter_map(|(id, _)| store.users.get(id).cloned())
+            .collect())
+    }
+
+    /// Scans the follows of all users, as there is no reverse index of followers.
+    fn remove_followers(&self, author: &str) -> Result<Vec<String>, ProviderError> {
+        Ok(self
+            .locks
+            .write(&self.store)
+            .follows
+            .iter_mut()
+            .filter_map(|(id, authors)| authors.remove(author).then(|| id.clone()))
+            .collect())
+    }
+
+    /// Stores the avatar bytes as is.
+    fn set_avatar(&self, id: &str, avatar: Vec<u8>) -> Result<bool, ProviderError> {
+        let mut store = self.locks.write(&self.store);
+        if !store.users.contains_key(id) {
+            return Ok(false);
+        }
+        store.avatars.insert(id.to_owned(), avatar);
+        Ok(true)
+    }
+
+    /// Returns a copy of the stored avatar bytes.
+    fn get_avatar(&self, id: &str) -> Result<Option<Vec<u8>>, ProviderError> {
+        Ok(self.locks.read(&self.store).avatars.get(id).cloned())
     }
 }
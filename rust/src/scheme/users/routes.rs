@@ -1,7 +1,30 @@
-use actix_web::{HttpResponse, Responder, get, post, web};
+use actix_web::{
+    HttpRequest, HttpResponse, delete, get,
+    http::{Method, header},
+    post, put, web,
+};
+use serde::Deserialize;
 use std::sync::Arc;
+use tracing::warn;
 
-use crate::scheme::{auth::AuthToken, users::*};
+use crate::{
+    scheme::{
+        auth::AuthToken,
+        error::ApiError,
+        fields::{Fields, FieldsQuery},
+        flags,
+        location::{self, location},
+        methods::{self, Route},
+        params::{NoParams, Params, Query},
+        posts::PostsProvider,
+        transaction::Transaction,
+        users::*,
+    },
+    state::GlobalServerState,
+};
+
+/// Author name assigned to the posts of deleted users.
+pub const DELETED_AUTHOR: &str = "[deleted]";
 
 /// Shared application state for the `/users` route group.
 ///
@@ -11,33 +34,76 @@ use crate::scheme::{auth::AuthToken, users::*};
 pub struct UsersState {
     /// Backend provider responsible for user-related operations.
     pub provider: Arc<dyn UsersProvider>,
+
+    /// Posts provider, used to clean up the posts of deleted users.
+    pub posts: Arc<dyn PostsProvider>,
 }
 
 impl UsersState {
-    /// Constructs a new [`UsersState`] with the given provider.
+    /// Constructs a new [`UsersState`] with the given providers.
     ///
     /// # Parameters
     /// - `provider`: An `Arc`-wrapped object implementing [`UsersProvider`].
+    /// - `posts`: An `Arc`-wrapped object implementing [`PostsProvider`].
     ///
     /// # Returns
     /// A new `UsersState` instance.
-    pub fn new(provider: Arc<dyn UsersProvider>) -> Self {
-        Self { provider }
+    pub fn new(provider: Arc<dyn UsersProvider>, posts: Arc<dyn PostsProvider>) -> Self {
+        Self { provider, posts }
     }
 }
 
+/// Optional lookup criteria accepted by `GET /users`.
+#[derive(Debug, Deserialize)]
+struct UsersQuery {
+    /// Exact email to look up (case-insensitive).
+    email: Option<String>,
+
+    /// Exact nickname to look up.
+    nickname: Option<String>,
+
+    /// Comma-separated fields of the users to return, see [`Fields`].
+    fields: Option<String>,
+}
+
+impl Params for UsersQuery {
+    const NAMES: &'static [&'static str] = &["email", "nickname", "fields"];
+}
+
 /// Handles `GET /users`
 ///
 /// Requires a valid [`AuthToken`] to be present in the request.
 ///
-/// Returns a list of all users stored in the system.
+/// Returns a list of all users stored in the system, or only the users matching the given criteria.
+///
+/// # Query Parameters
+/// - `email`: only users with this email (case-insensitive)
+/// - `nickname`: only users with this nickname
+/// - `fields`: fields of the users to return, e.g. `id,nickname` (see [`Fields`])
+///
+/// Lookups by `email` or `nickname` are behind the [`flags::SEARCH`] feature flag.
 ///
 /// # Response
 /// - `200 OK` with a JSON array of [`User`] objects
+/// - `400 Bad Request` if `fields` names an unknown field
+/// - `404 Not Found` if a lookup is asked for, but the feature flag is off
 #[get("")]
-async fn list_users(_auth: AuthToken, state: web::Data<UsersState>) -> impl Responder {
-    let users = state.provider.get_all();
-    HttpResponse::Ok().json(users)
+async fn list_users(
+    _auth: AuthToken,
+    global: web::Data<GlobalServerState>,
+    state: web::Data<UsersState>,
+    query: Query<UsersQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let fields = Fields::parse(query.fields.as_deref(), User::FIELDS)?;
+    let users = if query.email.is_none() && query.nickname.is_none() {
+        state.provider.get_all()?
+    } else {
+        global.flags.require(flags::SEARCH)?;
+        state
+            .provider
+            .find(query.email.as_deref(), query.nickname.as_deref())?
+    };
+    Ok(HttpResponse::Ok().json(fields.of_all(&users)))
 }
 
 /// Handles `POST /users`
@@ -50,13 +116,19 @@ async fn list_users(_auth: AuthToken, state: web::Data<UsersState>) -> impl Resp
 ///
 /// # Response
 /// - `201 Created` with the created [`User`] object
-/// - Includes `Location` header with the URI of the created resource
+/// - Includes `Location` header with the path of the created user (see [`location`])
+/// - `409 Conflict` if the email or the nickname is already registered
 #[post("")]
-async fn create_user(state: web::Data<UsersState>, body: web::Json<UserInput>) -> impl Responder {
-    let user = state.provider.create(body.into_inner());
-    HttpResponse::Created()
-        .append_header(("Location", format!("/users/{}", user.id)))
-        .json(user)
+async fn create_user(
+    req: HttpRequest,
+    state: web::Data<UsersState>,
+    body: web::Json<UserInput>,
+    _query: Query<NoParams>,
+) -> Result<HttpResponse, ApiError> {
+    let user = state.provider.create(body.into_inner())?;
+    Ok(HttpResponse::Created()
+        .append_header((header::LOCATION, location(&req, location::USER, &user.id)?))
+        .json(user))
 }
 
 /// Handles `GET /users/{id}`
@@ -66,26 +138,314 @@ async fn create_user(state: web::Data<UsersState>, body: web::Json<UserInput>) -
 /// # Path Parameters
 /// - `id`: The identifier of the user to fetch
 ///
+/// # Query Parameters
+/// - `fields`: fields of the user to return, e.g. `id,nickname` (see [`Fields`])
+///
 /// # Response
 /// - `200 OK` with the corresponding [`User`] object
+/// - `400 Bad Request` if `fields` names an unknown field
 /// - `404 Not Found` if the user does not exist
-#[get("/{id}")]
+#[get("/{id}", name = "user")]
 async fn get_user(
     _auth: AuthToken,
     state: web::Data<UsersState>,
     path: web::Path<String>,
-) -> impl Responder {
-    match state.provider.get(&path.into_inner()) {
-        Some(user) => HttpResponse::Ok().json(user),
-        None => HttpResponse::NotFound().finish(),
+    query: Query<FieldsQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let fields = Fields::parse(query.fields.as_deref(), User::FIELDS)?;
+    match state.provider.get(&path.into_inner())? {
+        Some(user) => Ok(HttpResponse::Ok().json(fields.of(&user))),
+        None => Err(ApiError::NotFound),
     }
 }
 
+/// Handles `PUT /users/{id}`
+///
+/// Replaces the nickname and email of an existing user. Requires a valid [`AuthToken`].
+///
+/// # Request Body
+/// JSON payload matching [`UserInput`]
+///
+/// # Response
+/// - `200 OK` with the updated [`User`]
+/// - `404 Not Found` if the user does not exist
+/// - `409 Conflict` if the email or the nickname is already registered by another user
+#[put("/{id}")]
+async fn update_user(
+    _auth: AuthToken,
+    state: web::Data<UsersState>,
+    path: web::Path<String>,
+    body: web::Json<UserInput>,
+    _query: Query<NoParams>,
+) -> Result<HttpResponse, ApiError> {
+    match state
+        .provider
+        .update(&path.into_inner(), body.into_inner())?
+    {
+        Some(user) => Ok(HttpResponse::Ok().json(user)),
+        None => Err(ApiError::NotFound),
+    }
+}
+
+/// Handles `DELETE /users/{id}`
+///
+/// Deletes a user account and cascades the deletion to the data related to it:
+/// - posts authored under the user's nickname are reassigned to [`DELETED_AUTHOR`];
+/// - the user disappears from the followed authors of other users;
+/// - the user record, follows and avatar are removed;
+/// - tokens issued to the user are revoked.
+///
+/// The steps span both providers and are run as a [`Transaction`], so a failure rolls back the
+/// steps already applied. Requires a valid [`AuthToken`].
+///
+/// Posts and follows name their author by nickname only; nicknames are unique (see
+/// [`UsersProvider::create`]), so the posts and follows of the nickname are the user's.
+///
+/// # Response
+/// - `204 No Content` if the account was deleted
+/// - `404 Not Found` if the user does not exist
+#[delete("/{id}")]
+async fn delete_user(
+    _auth: AuthToken,
+    state: web::Data<UsersState>,
+    path: web::Path<String>,
+    _query: Query<NoParams>,
+) -> Result<HttpResponse, ApiError> {
+    let user = state
+        .provider
+        .get(&path.into_inner())?
+        .ok_or(ApiError::NotFound)?;
+    delete_account(&state, user)?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Runs the cascading deletion of [`delete_user`] as a transaction.
+///
+/// Undo steps are best effort: their failures are logged and otherwise ignored.
+fn delete_account(state: &UsersState, user: User) -> Result<(), ApiError> {
+    let (posts, users) = (state.posts.clone(), state.provider.clone());
+    let ids: Vec<String> = posts
+        .get_by_authors(std::slice::from_ref(&user.nickname), 0, usize::MAX)?
+        .0
+        .into_iter()
+        .map(|post| post.id)
+        .collect();
+    let mut tx = Transaction::new("delete user");
+    tx.step(
+        "reassign posts",
+        || {
+            posts
+                .set_author(&ids, DELETED_AUTHOR)
+                .map_err(ApiError::from)
+        },
+        {
+            let (posts, nickname) = (posts.clone(), user.nickname.clone());
+            move |ids: Vec<String>| {
+                if let Err(err) = posts.set_author(&ids, &nickname) {
+                    warn!("Fail to restore the author of posts: {err}");
+                }
+            }
+        },
+    )?;
+    tx.step(
+        "remove followers",
+        || {
+            users
+                .remove_followers(&user.nickname)
+                .map_err(ApiError::from)
+        },
+        {
+            let (users, nickname) = (users.clone(), user.nickname.clone());
+            move |followers: Vec<String>| {
+                for follower in followers {
+                    if let Err(err) = users.follow(&follower, &nickname) {
+                        warn!("Fail to restore a follower of {nickname}: {err}");
+                    }
+                }
+            }
+        },
+    )?;
+    tx.step(
+        "delete user",
+        || users.delete(&user.id)?.ok_or(ApiError::NotFound),
+        |_| {},
+    )?;
+    tx.commit();
+    // Revoking can't be undone, so it only runs once the account is gone
+    users.revoke_tokens(&user.id)?;
+    Ok(())
+}
+
+/// Handles `GET /users/{id}/following`
+///
+/// Returns the authors followed by the user. Requires a valid [`AuthToken`].
+///
+/// # Response
+/// - `200 OK` with a JSON array of author names
+/// - `404 Not Found` if the user does not exist
+#[get("/{id}/following")]
+async fn list_following(
+    _auth: AuthToken,
+    state: web::Data<UsersState>,
+    path: web::Path<String>,
+    _query: Query<NoParams>,
+) -> Result<HttpResponse, ApiError> {
+    match state.provider.following(&path.into_inner())? {
+        Some(authors) => Ok(HttpResponse::Ok().json(authors)),
+        None => Err(ApiError::NotFound),
+    }
+}
+
+/// Handles `PUT /users/{id}/following/{author}`
+///
+/// Subscribes the user to posts of `author`; these posts then show up in the user's `/feed`.
+/// Following an already followed author is a no-op. Requires a valid [`AuthToken`].
+///
+/// # Response
+/// - `204 No Content` on success
+/// - `404 Not Found` if the user does not exist
+#[put("/{id}/following/{author}")]
+async fn follow_author(
+    _auth: AuthToken,
+    state: web::Data<UsersState>,
+    path: web::Path<(String, String)>,
+    _query: Query<NoParams>,
+) -> Result<HttpResponse, ApiError> {
+    let (id, author) = path.into_inner();
+    if state.provider.follow(&id, &author)? {
+        Ok(HttpResponse::NoContent().finish())
+    } else {
+        Err(ApiError::NotFound)
+    }
+}
+
+/// Handles `DELETE /users/{id}/following/{author}`
+///
+/// Unsubscribes the user from posts of `author`. Requires a valid [`AuthToken`].
+///
+/// # Response
+/// - `204 No Content` on success
+/// - `404 Not Found` if the user does not exist or doesn't follow the author
+#[delete("/{id}/following/{author}")]
+async fn unfollow_author(
+    _auth: AuthToken,
+    state: web::Data<UsersState>,
+    path: web::Path<(String, String)>,
+    _query: Query<NoParams>,
+) -> Result<HttpResponse, ApiError> {
+    let (id, author) = path.into_inner();
+    if state.provider.unfollow(&id, &author)? {
+        Ok(HttpResponse::NoContent().finish())
+    } else {
+        Err(ApiError::NotFound)
+    }
+}
+
+/// Handles `PUT /users/{id}/avatar`
+///
+/// Accepts a raw image upload, scales it down to a thumbnail on the blocking pool and stores the
+/// result as the user's avatar. Requires a valid [`AuthToken`].
+///
+/// # Request Body
+/// Raw image bytes, at most [`avatar::MAX_AVATAR_SIZE`], with `Content-Type` `image/png` or `image/jpeg`.
+///
+/// # Response
+/// - `204 No Content` when the avatar is stored
+/// - `400 Bad Request` if the body isn't a valid image of the declared type
+/// - `404 Not Found` if the user does not exist
+/// - `413 Payload Too Large` if the upload exceeds the size limit
+/// - `415 Unsupported Media Type` for other content types
+#[put("/{id}/avatar")]
+async fn upload_avatar(
+    _auth: AuthToken,
+    req: HttpRequest,
+    state: web::Data<UsersState>,
+    path: web::Path<String>,
+    body: web::Bytes,
+    _query: Query<NoParams>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+    let format = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(avatar::format_from_content_type)
+        .ok_or(ApiError::UnsupportedMediaType)?;
+    // Don't spend CPU on images of unknown users
+    if state.provider.get(&id)?.is_none() {
+        return Err(ApiError::NotFound);
+    }
+    let thumbnail = web::block(move || avatar::make_thumbnail(&body, format))
+        .await
+        .map_err(|err| ApiError::Internal(format!("fail to generate avatar thumbnail: {err}")))?
+        .map_err(|err| ApiError::BadRequest(err.to_string()))?;
+    if state.provider.set_avatar(&id, thumbnail)? {
+        Ok(HttpResponse::NoContent().finish())
+    } else {
+        Err(ApiError::NotFound)
+    }
+}
+
+/// Handles `GET /users/{id}/avatar`
+///
+/// Returns the avatar thumbnail of the user as PNG.
+///
+/// # Response
+/// - `200 OK` with the `image/png` thumbnail
+/// - `404 Not Found` if the user does not exist or has no avatar
+#[get("/{id}/avatar")]
+async fn get_avatar(
+    state: web::Data<UsersState>,
+    path: web::Path<String>,
+    _query: Query<NoParams>,
+) -> Result<HttpResponse, ApiError> {
+    match state.provider.get_avatar(&path.into_inner())? {
+        Some(bytes) => Ok(HttpResponse::Ok()
+            .content_type(avatar::THUMBNAIL_CONTENT_TYPE)
+            .body(bytes)),
+        None => Err(ApiError::NotFound),
+    }
+}
+
+/// Routes of `/users`, for [`methods::configure`].
+const ROUTES: &[Route] = &[
+    Route {
+        pattern: "",
+        methods: &[Method::GET, Method::POST],
+    },
+    Route {
+        pattern: "/{id}",
+        methods: &[Method::GET, Method::PUT, Method::DELETE],
+    },
+    Route {
+        pattern: "/{id}/following",
+        methods: &[Method::GET],
+    },
+    Route {
+        pattern: "/{id}/following/{author}",
+        methods: &[Method::PUT, Method::DELETE],
+    },
+    Route {
+        pattern: "/{id}/avatar",
+        methods: &[Method::GET, Method::PUT],
+    },
+];
+
 /// Registers the `/users` routes to the Actix-Web service configuration.
 ///
 /// Should be called during application setup to attach all user-related handlers.
 pub fn configure(cfg: &mut web::ServiceConfig) {
+    // Raw uploads (avatars) are larger than the default payload limit
+    cfg.app_data(web::PayloadConfig::new(avatar::MAX_AVATAR_SIZE));
     cfg.service(list_users);
     cfg.service(create_user);
     cfg.service(get_user);
+    cfg.service(update_user);
+    cfg.service(delete_user);
+    cfg.service(list_following);
+    cfg.service(follow_author);
+    cfg.service(unfollow_author);
+    cfg.service(upload_avatar);
+    cfg.service(get_avatar);
+    methods::configure(cfg, ROUTES);
 }
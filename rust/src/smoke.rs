@@ -0,0 +1,240 @@
+use chrono::Utc;
+use percom_model::urls;
+use reqwest::{
+    Client, RequestBuilder, StatusCode,
+    header::{AUTHORIZATION, HeaderMap, HeaderValue},
+};
+use serde::de::DeserializeOwned;
+use std::{io, net::TcpListener};
+
+use crate::scheme::{
+    pagination::Page,
+    posts::{Post, PostInput},
+    users::{User, UserInput},
+};
+
+/// Client of the server under smoke test.
+struct Smoke {
+    client: Client,
+    base: String,
+}
+
+impl Smoke {
+    fn url(&self, path: &str) -> String {
+        format!("{}{path}", self.base)
+    }
+
+    /// Sends the request and checks the response status.
+    async fn expect(
+        &self,
+        request: RequestBuilder,
+        status: StatusCode,
+    ) -> Result<reqwest::Response, String> {
+        let response = request.send().await.map_err(|err| err.to_string())?;
+        if response.status() != status {
+            return Err(format!("expected {status}, got {}", response.status()));
+        }
+        Ok(response)
+    }
+
+    /// Sends the request, checks the response status and parses the JSON body.
+    async fn expect_json<T: DeserializeOwned>(
+        &self,
+        request: RequestBuilder,
+        status: StatusCode,
+    ) -> Result<T, String> {
+        self.expect(request, status)
+            .await?
+            .json()
+            .await
+            .map_err(|err| err.to_string())
+    }
+
+    /// Runs the lifecycle, stopping at the first failed step.
+    ///
+    /// Every step is printed, so a failure points at the broken endpoint.
+    async fn lifecycle(&self) -> Result<(), String> {
+        let suffix = uuid::Uuid::new_v4();
+        let input = UserInput {
+            nickname: format!("smoke-{suffix}"),
+            email: format!("smoke-{suffix}@example.com"),
+        };
+        let user: User = step(
+            "create user",
+            self.expect_json(
+                self.client
+                    .post(self.url(&urls::users::list()))
+                    .json(&input),
+                StatusCode::CREATED,
+            ),
+        )
+        .await?;
+        step("get user", async {
+            let fetched: User = self
+                .expect_json(
+                    self.client.get(self.url(&urls::users::by_id(&user.id))),
+                    StatusCode::OK,
+                )
+                .await?;
+            check(
+                fetched.email == user.email && fetched.nickname == user.nickname,
+                "fetched user differs from the created one",
+            )
+        })
+        .await?;
+        let user: User = step(
+            "update user",
+            self.expect_json(
+                self.client
+                    .put(self.url(&urls::users::by_id(&user.id)))
+                    .json(&UserInput {
+                        nickname: format!("{}-updated", input.nickname),
+                        email: input.email.clone(),
+                    }),
+                StatusCode::OK,
+            ),
+        )
+        .await?;
+        let post: Post = step(
+            "create post",
+            self.expect_json(
+                self.client
+                    .post(self.url(&urls::posts::list()))
+                    .json(&PostInput {
+                        author: user.nickname.clone(),
+                        date: Utc::now().fixed_offset(),
+                        content: "smoke".to_owned(),
+                        publish_at: None,
+                    }),
+                StatusCode::CREATED,
+            ),
+        )
+        .await?;
+        let post_url = self.url(&urls::posts::by_id(&post.id));
+        step("get post", async {
+            let fetched: Post = self
+                .expect_json(self.client.get(&post_url), StatusCode::OK)
+                .await?;
+            check(fetched.content == post.content, "post content differs")
+        })
+        .await?;
+        step("update post", async {
+            let updated: Post = self
+                .expect_json(
+                    self.client.put(&post_url).json(&PostInput {
+                        author: user.nickname.clone(),
+                        date: Utc::now().fixed_offset(),
+                        content: "smoke-updated".to_owned(),
+                        publish_at: None,
+                    }),
+                    StatusCode::OK,
+                )
+                .await?;
+            check(updated.content == "smoke-updated", "post wasn't updated")
+        })
+        .await?;
+        step("list posts", async {
+            let posts: Vec<Post> = self
+                .expect_json(
+                    self.client.get(self.url(&urls::posts::list())),
+                    StatusCode::OK,
+                )
+                .await?;
+            check(posts.iter().any(|p| p.id == post.id), "post isn't listed")
+        })
+        .await?;
+        step(
+            "follow author",
+            self.expect(
+                self.client
+                    .put(self.url(&urls::users::follow(&user.id, &user.nickname))),
+                StatusCode::NO_CONTENT,
+            ),
+        )
+        .await?;
+        step("get feed", async {
+            // The dummy users provider binds a token equal to the user's ID to that user
+            let feed: Page<Post> = self
+                .expect_json(
+                    self.client
+                        .get(self.url(&urls::feed::list()))
+                        .header(AUTHORIZATION, format!("Bearer {}", user.id)),
+                    StatusCode::OK,
+                )
+                .await?;
+            check(
+                feed.items.iter().any(|p| p.id == post.id),
+                "post of a followed author is missing in the feed",
+            )
+        })
+        .await?;
+        step("delete post", async {
+            self.expect(self.client.delete(&post_url), StatusCode::NO_CONTENT)
+                .await?;
+            self.expect(self.client.get(&post_url), StatusCode::NOT_FOUND)
+                .await
+                .map(|_| ())
+        })
+        .await?;
+        step("delete user", async {
+            let user_url = self.url(&urls::users::by_id(&user.id));
+            self.expect(self.client.delete(&user_url), StatusCode::NO_CONTENT)
+                .await?;
+            self.expect(self.client.get(&user_url), StatusCode::NOT_FOUND)
+                .await
+                .map(|_| ())
+        })
+        .await?;
+        Ok(())
+    }
+}
+
+/// Runs a step of the lifecycle and prints its outcome.
+async fn step<T>(name: &str, step: impl Future<Output = Result<T, String>>) -> Result<T, String> {
+    let result = step.await;
+    match &result {
+        Ok(_) => println!("PASS {name}"),
+        Err(err) => println!("FAIL {name}: {err}"),
+    }
+    result
+}
+
+fn check(condition: bool, msg: &str) -> Result<(), String> {
+    if condition {
+        Ok(())
+    } else {
+        Err(msg.to_owned())
+    }
+}
+
+/// Starts the server on an ephemeral local port, runs a full post/user lifecycle against it and
+/// stops it again (`server smoke`).
+///
+/// The server is set up exactly as by a regular start, so the check covers the configuration from
+/// the environment as well. Meant as a container health check and as a quick sanity gate before
+/// long benchmarks.
+///
+/// # Returns
+/// `true` if every step passed. `Err` is returned only if the server couldn't be started.
+pub async fn run() -> io::Result<bool> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let addr = listener.local_addr()?;
+    let (server, _) = crate::start(move || Ok(listener), &crate::ServeOptions::default())?;
+    let handle = server.handle();
+    actix_web::rt::spawn(server);
+    // Endpoints accept any token; the feed overrides it to act as the created user
+    let mut headers = HeaderMap::new();
+    headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer smoke"));
+    let client = Client::builder()
+        .default_headers(headers)
+        .build()
+        .map_err(io::Error::other)?;
+    let smoke = Smoke {
+        client,
+        base: format!("http://{addr}"),
+    };
+    let passed = smoke.lifecycle().await.is_ok();
+    handle.stop(true).await;
+    println!("Smoke test {}", if passed { "passed" } else { "failed" });
+    Ok(passed)
+}
@@ -0,0 +1,95 @@
+use serde_json::{Value, json};
+use std::time::Duration;
+
+/// Number of bits of a value kept below its highest set bit: every power of two of microseconds
+/// is split into `2^SUB_BITS` buckets, so a bucket is at most 12.5% wide.
+const SUB_BITS: u32 = 3;
+
+/// Number of buckets of every power of two.
+const SUB_BUCKETS: u64 = 1 << SUB_BITS;
+
+/// Latency histogram in microseconds with log-linear buckets, like HdrHistogram with a precision
+/// of one significant (octal) digit.
+///
+/// Percentiles are reported as the upper bound of the bucket they fall into, capped to the
+/// largest recorded value, so they never understate the latency.
+#[derive(Debug, Default, Clone)]
+pub struct Histogram {
+    /// Counts by bucket index (see [`Histogram::index`]), grown as larger values are recorded.
+    counts: Vec<u64>,
+
+    total: u64,
+    sum_us: u64,
+    max_us: u64,
+}
+
+impl Histogram {
+    /// Returns the bucket of `us`: values below [`SUB_BUCKETS`] have a bucket of their own.
+    fn index(us: u64) -> usize {
+        if us < SUB_BUCKETS {
+            return us as usize;
+        }
+        let exp = u64::BITS - 1 - us.leading_zeros();
+        let sub = (us >> (exp - SUB_BITS)) & (SUB_BUCKETS - 1);
+        ((exp - SUB_BITS + 1) as u64 * SUB_BUCKETS + sub) as usize
+    }
+
+    /// Returns the largest value of the bucket `index`.
+    fn upper(index: usize) -> u64 {
+        let index = index as u64;
+        if index < SUB_BUCKETS {
+            return index;
+        }
+        let shift = index / SUB_BUCKETS - 1;
+        let lower = (SUB_BUCKETS + index % SUB_BUCKETS) << shift;
+        lower + (1 << shift) - 1
+    }
+
+    /// Records one value.
+    pub fn record(&mut self, elapsed: Duration) {
+        let us = u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX);
+        let index = Self::index(us);
+        if self.counts.len() <= index {
+            self.counts.resize(index + 1, 0);
+        }
+        self.counts[index] += 1;
+        self.total += 1;
+        self.sum_us = self.sum_us.saturating_add(us);
+        self.max_us = self.max_us.max(us);
+    }
+
+    /// Returns the value below which `quantile` (between 0 and 1) of the values fall, in
+    /// microseconds; `0` if nothing was recorded.
+    pub fn percentile(&self, quantile: f64) -> u64 {
+        let rank = ((quantile * self.total as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (index, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return Self::upper(index).min(self.max_us);
+            }
+        }
+        0
+    }
+
+    /// Returns the summary of the values as JSON, along with the non-empty buckets as
+    /// `[upper bound, count]` pairs, so histograms of several servers can be merged.
+    pub fn snapshot(&self) -> Value {
+        let buckets: Vec<_> = self
+            .counts
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(index, count)| [Self::upper(index), *count])
+            .collect();
+        json!({
+            "count": self.total,
+            "mean_us": self.sum_us.checked_div(self.total).unwrap_or(0),
+            "p50_us": self.percentile(0.5),
+            "p90_us": self.percentile(0.9),
+            "p99_us": self.percentile(0.99),
+            "max_us": self.max_us,
+            "buckets": buckets,
+        })
+    }
+}
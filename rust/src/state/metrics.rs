@@ -0,0 +1,437 @@
+use actix_web::http::StatusCode;
+use serde_json::{Value, json};
+use std::{
+    collections::BTreeMap,
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use crate::{middleware::lanes::Lane, state::histogram::Histogram};
+
+/// Maximum number of benchmark runs kept in the `runs` metric; the run seen first is dropped to
+/// make room for a new one.
+const MAX_RUNS: usize = 64;
+
+/// Key of the `runs` metric counting requests without a benchmark run ID.
+pub const NO_RUN: &str = "(none)";
+
+/// Server-wide counters and gauges, exposed as JSON at `GET /metrics`.
+///
+/// All values are plain atomics updated with relaxed ordering: they are meant for monitoring
+/// benchmark runs, not for synchronization.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    /// Number of requests whose handler panicked.
+    pub http_panics: AtomicU64,
+
+    /// Number of requests rejected by the IP access control list (see
+    /// [`Acl`](crate::middleware::acl::Acl)).
+    pub http_acl_denied: AtomicU64,
+
+    /// Number of requests rejected because their signature is missing or invalid (see
+    /// [`Signing`](crate::middleware::signature::Signing)).
+    pub http_signature_failures: AtomicU64,
+
+    /// Number of writes rejected during maintenance (see
+    /// [`Maintenance`](crate::middleware::maintenance::Maintenance)).
+    pub http_maintenance_rejected: AtomicU64,
+
+    /// Number of requests rejected because their client is locked out after repeated
+    /// authentication failures (see [`AuthFailures`](crate::middleware::auth_failures::AuthFailures)).
+    pub http_auth_locked: AtomicU64,
+
+    /// Number of requests mirrored to the shadow backend (see
+    /// [`Mirror`](crate::middleware::mirror::Mirror)).
+    pub mirror_sent: AtomicU64,
+
+    /// Number of mirrored requests the shadow backend didn't answer.
+    pub mirror_failed: AtomicU64,
+
+    /// Number of requests which weren't mirrored because too many copies were pending.
+    pub mirror_dropped: AtomicU64,
+
+    /// Number of requests which weren't mirrored because their bodies were too large.
+    pub mirror_oversized: AtomicU64,
+
+    /// Number of jobs waiting in the job queue.
+    pub jobs_queue_depth: AtomicU64,
+
+    /// Number of jobs processed by the job workers.
+    pub jobs_processed: AtomicU64,
+
+    /// Number of jobs rejected because the job queue was full.
+    pub jobs_dropped: AtomicU64,
+
+    /// Number of notifications delivered by the notifier.
+    pub notifications_sent: AtomicU64,
+
+    /// Number of notifications the notifier failed to deliver.
+    pub notifications_failed: AtomicU64,
+
+    /// Number of records in the posts WAL (only with the `wal` posts provider).
+    pub wal_records: AtomicU64,
+
+    /// Size of the posts WAL, in bytes.
+    pub wal_bytes: AtomicU64,
+
+    /// Number of completed WAL compactions.
+    pub wal_compactions: AtomicU64,
+
+    /// Total number of bytes reclaimed by WAL compactions.
+    pub wal_reclaimed_bytes: AtomicU64,
+
+    /// Duration of the last WAL compaction, in milliseconds.
+    pub wal_last_compaction_ms: AtomicU64,
+
+    /// Number of WAL records waiting to be flushed (batched mode only).
+    pub wal_pending: AtomicU64,
+
+    /// Number of WAL flushes; each writes one or more records.
+    pub wal_flushes: AtomicU64,
+
+    /// Total number of records written by WAL flushes.
+    pub wal_flushed_records: AtomicU64,
+
+    /// Number of failed WAL flushes.
+    pub wal_flush_errors: AtomicU64,
+
+    /// Number of provider calls abandoned because the request deadline passed.
+    pub provider_timeouts: AtomicU64,
+
+    /// Number of provider calls abandoned because the client disconnected.
+    pub provider_cancelled: AtomicU64,
+
+    /// Number of retried storage calls (see [`Retrier`](crate::scheme::retry::Retrier)).
+    pub storage_retries: AtomicU64,
+
+    /// Number of failed storage calls which weren't retried because the retry budget was spent.
+    pub storage_retries_denied: AtomicU64,
+
+    /// Number of streaming responses being sent.
+    pub streams_open: AtomicU64,
+
+    /// Number of streaming responses dropped before they were finished, e.g. because the client
+    /// disconnected.
+    pub streams_abandoned: AtomicU64,
+
+    /// Total size of the content of stored posts, in bytes.
+    pub content_raw_bytes: AtomicU64,
+
+    /// Memory taken by the content of stored posts after compression, in bytes.
+    pub content_stored_bytes: AtomicU64,
+
+    /// Number of compressed post contents (see `RUST_SERVER_CONTENT_COMPRESSION`).
+    pub content_compressions: AtomicU64,
+
+    /// Total time spent compressing post contents, in nanoseconds.
+    pub content_compression_ns: AtomicU64,
+
+    /// Number of decompressed post contents.
+    pub content_decompressions: AtomicU64,
+
+    /// Total time spent decompressing post contents, in nanoseconds.
+    pub content_decompression_ns: AtomicU64,
+
+    /// Number of requests being handled, by lane (see [`Lane`](crate::middleware::lanes::Lane)).
+    pub lanes_active: [AtomicU64; 4],
+
+    /// Number of requests waiting for a slot of their lane, by lane.
+    pub lanes_queued: [AtomicU64; 4],
+
+    /// Number of CPU-heavy jobs which may run at a time (see [`Offload`](crate::offload::Offload)).
+    pub offload_size: AtomicU64,
+
+    /// Number of offloaded jobs running.
+    pub offload_running: AtomicU64,
+
+    /// Number of offloaded jobs waiting for a free slot.
+    pub offload_queued: AtomicU64,
+
+    /// Number of completed offloaded jobs.
+    pub offload_completed: AtomicU64,
+
+    /// Total time offloaded jobs waited for a free slot, in nanoseconds.
+    pub offload_wait_ns: AtomicU64,
+
+    /// Responses of the variants of experiments, by experiment and variant (see
+    /// [`FeatureFlags::variant`](crate::scheme::flags::FeatureFlags::variant)).
+    pub variants: Mutex<BTreeMap<(&'static str, &'static str), VariantStats>>,
+
+    /// Responses by route, e.g. `GET /posts/{id}` (see
+    /// [`count_requests`](crate::middleware::route_stats::count_requests)).
+    pub routes: Mutex<BTreeMap<String, RouteStats>>,
+
+    /// Responses by benchmark run ID, or [`NO_RUN`] (see
+    /// [`tag_runs`](crate::middleware::run_id::tag_runs)).
+    pub runs: Mutex<BTreeMap<String, RunStats>>,
+
+    /// Incremented for every run added to `runs`, to tell which one was seen first.
+    runs_seen: AtomicU64,
+}
+
+/// Responses of one benchmark run.
+#[derive(Debug, Default, Clone)]
+pub struct RunStats {
+    /// Number of `5xx` responses.
+    pub server_errors: u64,
+
+    /// Time from the arrival of a request to its response, body excluded.
+    pub latency: Histogram,
+
+    /// Order in which the run was seen, to drop the oldest one first.
+    seen: u64,
+}
+
+/// Responses of one route.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RouteStats {
+    /// Number of responses, whatever their status.
+    pub requests: u64,
+
+    /// Number of `4xx` responses.
+    pub client_errors: u64,
+
+    /// Number of `5xx` responses.
+    pub server_errors: u64,
+}
+
+/// Responses of one variant of an experiment.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct VariantStats {
+    /// Number of responses sent completely.
+    pub requests: u64,
+
+    /// Total time from the start of the handler to the end of the response body, in nanoseconds.
+    pub total_ns: u64,
+}
+
+impl Metrics {
+    /// Increments a counter or gauge by one.
+    pub fn inc(value: &AtomicU64) {
+        value.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Decrements a gauge by one.
+    pub fn dec(value: &AtomicU64) {
+        value.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// Records a response of `variant` of `experiment` which took `elapsed`.
+    pub fn record_variant(
+        &self,
+        experiment: &'static str,
+        variant: &'static str,
+        elapsed: Duration,
+    ) {
+        let mut variants = self.variants.lock().unwrap_or_else(|err| err.into_inner());
+        let stats = variants.entry((experiment, variant)).or_default();
+        stats.requests += 1;
+        stats.total_ns += elapsed.as_nanos() as u64;
+    }
+
+    /// Records a response of `route` with `status`.
+    pub fn record_route(&self, route: String, status: StatusCode) {
+        let mut routes = self.routes.lock().unwrap_or_else(|err| err.into_inner());
+        let stats = routes.entry(route).or_default();
+        stats.requests += 1;
+        if status.is_client_error() {
+            stats.client_errors += 1;
+        } else if status.is_server_error() {
+            stats.server_errors += 1;
+        }
+    }
+
+    /// Records a response of the benchmark run `run` (or [`NO_RUN`]) with `status` which took
+    /// `elapsed`.
+    pub fn record_run(&self, run: &str, status: StatusCode, elapsed: Duration) {
+        let mut runs = self.runs.lock().unwrap_or_else(|err| err.into_inner());
+        if !runs.contains_key(run) {
+            if runs.len() >= MAX_RUNS
+                && let Some(oldest) = runs
+                    .iter()
+                    .filter(|(name, _)| *name != NO_RUN)
+                    .min_by_key(|(_, stats)| stats.seen)
+                    .map(|(name, _)| name.clone())
+            {
+                runs.remove(&oldest);
+            }
+            let seen = self.runs_seen.fetch_add(1, Ordering::Relaxed);
+            runs.insert(
+                run.to_owned(),
+                RunStats {
+                    seen,
+                    ..RunStats::default()
+                },
+            );
+        }
+        let stats = runs.get_mut(run).expect("The run was just inserted");
+        if status.is_server_error() {
+            stats.server_errors += 1;
+        }
+        stats.latency.record(elapsed);
+    }
+
+    /// Returns the responses by benchmark run as JSON.
+    pub fn runs_snapshot(&self) -> Value {
+        let runs = self.runs.lock().unwrap_or_else(|err| err.into_inner());
+        let runs: BTreeMap<_, _> = runs
+            .iter()
+            .map(|(run, stats)| {
+                (
+                    run.as_str(),
+                    json!({
+                        "server_errors": stats.server_errors,
+                        "latency": stats.latency.snapshot(),
+                    }),
+                )
+            })
+            .collect();
+        json!(runs)
+    }
+
+    /// Returns a copy of the responses by route.
+    pub fn routes(&self) -> BTreeMap<String, RouteStats> {
+        self.routes
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone()
+    }
+
+    /// Returns a point-in-time copy of all values as JSON.
+    pub fn snapshot(&self) -> Value {
+        let get = |value: &AtomicU64| value.load(Ordering::Relaxed);
+        let (raw, stored) = (
+            get(&self.content_raw_bytes),
+            get(&self.content_stored_bytes),
+        );
+        let mut experiments: BTreeMap<_, BTreeMap<_, _>> = BTreeMap::new();
+        for ((experiment, variant), stats) in self
+            .variants
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .iter()
+        {
+            let mean_ms = if stats.requests == 0 {
+                0.0
+            } else {
+                stats.total_ns as f64 / stats.requests as f64 / 1e6
+            };
+            experiments.entry(*experiment).or_default().insert(
+                *variant,
+                json!({ "requests": stats.requests, "mean_ms": mean_ms }),
+            );
+        }
+        let routes: BTreeMap<_, _> = self
+            .routes()
+            .into_iter()
+            .map(|(route, stats)| {
+                (
+                    route,
+                    json!({
+                        "requests": stats.requests,
+                        "client_errors": stats.client_errors,
+                        "server_errors": stats.server_errors,
+                    }),
+                )
+            })
+            .collect();
+        let lanes: BTreeMap<_, _> = Lane::ALL
+            .into_iter()
+            .map(|lane| {
+                let index = lane.index();
+                (
+                    lane.name(),
+                    json!({
+                        "active": get(&self.lanes_active[index]),
+                        "queued": get(&self.lanes_queued[index]),
+                    }),
+                )
+            })
+            .collect();
+        json!({
+            "http": {
+                "panics": get(&self.http_panics),
+                "acl_denied": get(&self.http_acl_denied),
+                "signature_failures": get(&self.http_signature_failures),
+                "maintenance_rejected": get(&self.http_maintenance_rejected),
+                "auth_locked": get(&self.http_auth_locked),
+            },
+            "mirror": {
+                "sent": get(&self.mirror_sent),
+                "failed": get(&self.mirror_failed),
+                "dropped": get(&self.mirror_dropped),
+                "oversized": get(&self.mirror_oversized),
+            },
+            "jobs": {
+                "queue_depth": get(&self.jobs_queue_depth),
+                "processed": get(&self.jobs_processed),
+                "dropped": get(&self.jobs_dropped),
+            },
+            "notifications": {
+                "sent": get(&self.notifications_sent),
+                "failed": get(&self.notifications_failed),
+            },
+            "wal": {
+                "records": get(&self.wal_records),
+                "bytes": get(&self.wal_bytes),
+                "compactions": get(&self.wal_compactions),
+                "reclaimed_bytes": get(&self.wal_reclaimed_bytes),
+                "last_compaction_ms": get(&self.wal_last_compaction_ms),
+                "pending": get(&self.wal_pending),
+                "flushes": get(&self.wal_flushes),
+                "flushed_records": get(&self.wal_flushed_records),
+                "flush_errors": get(&self.wal_flush_errors),
+            },
+            "provider": {
+                "timeouts": get(&self.provider_timeouts),
+                "cancelled": get(&self.provider_cancelled),
+                "retries": get(&self.storage_retries),
+                "retries_denied": get(&self.storage_retries_denied),
+            },
+            "streams": {
+                "open": get(&self.streams_open),
+                "abandoned": get(&self.streams_abandoned),
+            },
+            "content": {
+                "raw_bytes": raw,
+                "stored_bytes": stored,
+                "ratio": if stored == 0 { 1.0 } else { raw as f64 / stored as f64 },
+                "compressions": get(&self.content_compressions),
+                "compression_ns": get(&self.content_compression_ns),
+                "decompressions": get(&self.content_decompressions),
+                "decompression_ns": get(&self.content_decompression_ns),
+            },
+            "lanes": lanes,
+            "offload": {
+                "size": get(&self.offload_size),
+                "running": get(&self.offload_running),
+                "queued": get(&self.offload_queued),
+                "completed": get(&self.offload_completed),
+                "wait_ns": get(&self.offload_wait_ns),
+            },
+            "experiments": experiments,
+            "routes": routes,
+            "runs": self.runs_snapshot(),
+        })
+    }
+}
+
+/// Gauge incremented while the value lives, so it's right even if the work it tracks is cancelled.
+pub struct Gauge<'a>(&'a AtomicU64);
+
+impl<'a> Gauge<'a> {
+    /// Increments `value` until the returned guard is dropped.
+    pub fn track(value: &'a AtomicU64) -> Self {
+        Metrics::inc(value);
+        Self(value)
+    }
+}
+
+impl Drop for Gauge<'_> {
+    fn drop(&mut self) {
+        Metrics::dec(self.0);
+    }
+}
@@ -1,17 +1,71 @@
-use std::sync::Arc;
+pub mod histogram;
+pub mod metrics;
 
-use crate::scheme::users::UsersProvider;
+use std::{sync::Arc, time::Duration};
 
+use crate::{
+    client_ip::TrustedProxies,
+    middleware::{
+        acl::Acl, auth_failures::AuthFailures, lanes::Lanes, maintenance::Maintenance,
+        mirror::Mirror, read_only::ReadOnly, signature::Signing, slow_clients::ClientLimits,
+        work::WorkFactors,
+    },
+    offload::Offload,
+    scheme::{flags::FeatureFlags, provider::ProviderError, users::UsersProvider},
+};
+pub use metrics::*;
+
+/// State shared by every scope and middleware; built field by field, so no two are mixed up.
 #[derive(Clone)]
 pub struct GlobalServerState {
     pub provider: Arc<dyn UsersProvider>,
+    pub metrics: Arc<Metrics>,
+
+    /// Deadline of provider calls of a request, if enabled (see [`Deadline`](crate::scheme::deadline::Deadline)).
+    pub provider_deadline: Option<Duration>,
+
+    /// Set if the server rejects writes (see [`reject_writes`](crate::middleware::read_only::reject_writes)).
+    pub read_only: Option<ReadOnly>,
+
+    /// Proxies whose forwarding headers tell the client address (see [`ClientIp`](crate::client_ip::ClientIp)).
+    pub trusted_proxies: TrustedProxies,
+
+    /// IP access control list (see [`check_acl`](crate::middleware::acl::check_acl)).
+    pub acl: Arc<Acl>,
+
+    /// Set if requests must be signed (see [`verify_signature`](crate::middleware::signature::verify_signature)).
+    pub signing: Option<Signing>,
+
+    /// Feature flags of experimental endpoints (see [`FeatureFlags`]).
+    pub flags: Arc<FeatureFlags>,
+
+    /// Artificial CPU work of endpoints (see [`burn_cpu`](crate::middleware::work::burn_cpu)).
+    pub work: Arc<WorkFactors>,
+
+    /// Pool of CPU-heavy work, off the worker threads (see [`Offload`]).
+    pub offload: Offload,
+
+    /// Concurrency limits per class of requests (see [`limit_lanes`](crate::middleware::lanes::limit_lanes)).
+    pub lanes: Lanes,
+
+    /// Limits of slow or oversized clients (see [`guard_slow_clients`](crate::middleware::slow_clients::guard_slow_clients)).
+    pub client_limits: ClientLimits,
+
+    /// Maintenance mode, rejecting writes (see [`Maintenance`]).
+    pub maintenance: Arc<Maintenance>,
+
+    /// Set if requests are mirrored to a shadow backend (see [`mirror_requests`](crate::middleware::mirror::mirror_requests)).
+    pub mirror: Option<Mirror>,
+
+    /// Rejected authentication attempts and lockouts (see [`AuthFailures`]).
+    pub auth_failures: Arc<AuthFailures>,
 }
 
 impl GlobalServerState {
-    pub fn new(provider: Arc<dyn UsersProvider>) -> GlobalServerState {
-        Self { provider }
-    }
-    pub fn is_token_valid<S: AsRef<str>>(&self, token: S) -> bool {
+    pub fn is_token_valid<S: AsRef<str>>(&self, token: S) -> Result<bool, ProviderError> {
         self.provider.is_token_valid(token.as_ref())
     }
+    pub fn token_subject<S: AsRef<str>>(&self, token: S) -> Result<Option<String>, ProviderError> {
+        self.provider.token_subject(token.as_ref())
+    }
 }
@@ -0,0 +1,108 @@
+//! Session summary written when the server shuts down, so every benchmark run leaves a
+//! machine-readable artifact on the server side too.
+//!
+//! The summary is written next to the log file of the session (`<start time>.summary.json`), or
+//! to `RUST_SERVER_SUMMARY_PATH`. It holds the uptime, the responses by route (see
+//! [`count_requests`](crate::middleware::route_stats::count_requests)) with their error counts,
+//! the latency histograms of the benchmark runs (see
+//! [`tag_runs`](crate::middleware::run_id::tag_runs)), the peak RSS of the process and the stats of
+//! the providers, as `GET /admin/providers` returns them. Nothing is written if the process is
+//! killed rather than stopped, e.g. with `SIGKILL`.
+
+use chrono::{DateTime, Utc};
+use serde_json::{Value, json};
+use std::{
+    fs, io,
+    path::Path,
+    sync::{Arc, atomic::Ordering},
+    time::Instant,
+};
+
+use crate::{
+    scheme::{posts::PostsProvider, users::UsersProvider},
+    state::{Metrics, RouteStats},
+};
+
+/// What a server session needs to summarize itself once it's over.
+pub struct Session {
+    started_at: DateTime<Utc>,
+    started: Instant,
+    metrics: Arc<Metrics>,
+    posts: Arc<dyn PostsProvider>,
+    users: Arc<dyn UsersProvider>,
+}
+
+impl Session {
+    /// Starts a session now.
+    pub fn start(
+        metrics: Arc<Metrics>,
+        posts: Arc<dyn PostsProvider>,
+        users: Arc<dyn UsersProvider>,
+    ) -> Self {
+        Self {
+            started_at: Utc::now(),
+            started: Instant::now(),
+            metrics,
+            posts,
+            users,
+        }
+    }
+
+    /// Returns the summary of the session so far.
+    pub fn summary(&self) -> Value {
+        let routes = self.metrics.routes();
+        let total = |count: fn(&RouteStats) -> u64| -> u64 { routes.values().map(count).sum() };
+        json!({
+            "started_at": self.started_at,
+            "stopped_at": Utc::now(),
+            "uptime_secs": self.started.elapsed().as_secs_f64(),
+            "requests": {
+                "total": total(|stats| stats.requests),
+                "routes": routes
+                    .iter()
+                    .map(|(route, stats)| (route.clone(), json!(stats.requests)))
+                    .collect::<serde_json::Map<_, _>>(),
+            },
+            "errors": {
+                "client": total(|stats| stats.client_errors),
+                "server": total(|stats| stats.server_errors),
+                "panics": self.metrics.http_panics.load(Ordering::Relaxed),
+                "routes": routes
+                    .iter()
+                    .filter(|(_, stats)| stats.client_errors + stats.server_errors > 0)
+                    .map(|(route, stats)| {
+                        (
+                            route.clone(),
+                            json!({ "client": stats.client_errors, "server": stats.server_errors }),
+                        )
+                    })
+                    .collect::<serde_json::Map<_, _>>(),
+            },
+            "runs": self.metrics.runs_snapshot(),
+            "peak_rss_bytes": peak_rss(),
+            "providers": { "posts": self.posts.stats(), "users": self.users.stats() },
+        })
+    }
+
+    /// Writes the summary to `path` as pretty-printed JSON.
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let summary = serde_json::to_vec_pretty(&self.summary()).map_err(io::Error::other)?;
+        fs::write(path, summary)?;
+        tracing::info!("Wrote the session summary to {}", path.display());
+        Ok(())
+    }
+}
+
+/// Returns the peak resident set size of the process in bytes (`VmHWM`), on Linux only.
+fn peak_rss() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let kib = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kib * 1024)
+}
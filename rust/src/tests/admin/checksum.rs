@@ -0,0 +1,59 @@
+use chrono::{DateTime, Utc};
+use percom_model::{posts::PostInput, users::User};
+
+use crate::scheme::{admin::checksum, posts::Post};
+
+/// Returns a post with the given ID and content.
+fn post(id: &str, author: &str, content: &str) -> Post {
+    let date = DateTime::parse_from_rfc3339("2024-05-01T12:00:00Z").unwrap();
+    let input = PostInput {
+        author: author.to_owned(),
+        date,
+        content: content.to_owned(),
+        publish_at: None,
+    };
+    Post::new(id.to_owned(), input, Utc::now())
+}
+
+/// Returns a user with the given ID and content, following `following`.
+fn user(id: &str, nickname: &str, following: &[&str]) -> (User, Vec<String>) {
+    let user = User {
+        id: id.to_owned(),
+        nickname: nickname.to_owned(),
+        email: format!("{nickname}@example.com"),
+    };
+    (
+        user,
+        following.iter().map(|&author| author.to_owned()).collect(),
+    )
+}
+
+// Checksums two datasets with the same content, stored under different IDs and in a different
+// order, as two backends replaying the same traffic would, and checks that the checksums match
+// while a change of content doesn't.
+#[test]
+fn same_content() {
+    let posts = [post("1", "alice", "hello"), post("2", "bob", "hi")];
+    let replayed = [post("b", "bob", "hi"), post("a", "alice", "hello")];
+    assert_eq!(
+        checksum::posts(&posts).unwrap(),
+        checksum::posts(&replayed).unwrap()
+    );
+    let edited = [post("1", "alice", "hello"), post("2", "bob", "hi!")];
+    assert_ne!(
+        checksum::posts(&posts).unwrap(),
+        checksum::posts(&edited).unwrap()
+    );
+
+    let users = [user("1", "alice", &["bob", "carol"]), user("2", "bob", &[])];
+    let replayed = [user("b", "bob", &[]), user("a", "alice", &["carol", "bob"])];
+    assert_eq!(
+        checksum::users(&users).unwrap(),
+        checksum::users(&replayed).unwrap()
+    );
+    let edited = [user("1", "alice", &["bob"]), user("2", "bob", &[])];
+    assert_ne!(
+        checksum::users(&users).unwrap(),
+        checksum::users(&edited).unwrap()
+    );
+}
@@ -0,0 +1,107 @@
+use actix_web::{
+    App, HttpResponse,
+    http::{Method, StatusCode, header::RETRY_AFTER},
+    middleware::from_fn,
+    test, web,
+};
+use percom_model::urls;
+use serde_json::json;
+use std::sync::atomic::Ordering;
+
+use crate::{
+    middleware::maintenance::{MaintenanceStatus, reject_writes_in_maintenance},
+    scheme,
+    tests::{authorization, state},
+};
+
+// Turns the maintenance mode on in-process, checking that writes are rejected with `503` and the
+// configured `Retry-After` and counted, while reads and `/admin` writes pass, that setting the mode
+// again keeps its start and takes the new `Retry-After`, and that writes pass once it's turned off.
+#[actix_web::test]
+async fn maintenance_rejects_writes() {
+    let state = web::Data::new(state());
+    let app = test::init_service(
+        App::new()
+            .app_data(state.clone())
+            .service(web::scope(urls::admin::SCOPE).configure(scheme::admin::routes::configure))
+            .route(&urls::posts::list(), web::get().to(HttpResponse::Ok))
+            .route(&urls::posts::list(), web::post().to(HttpResponse::Created))
+            .route(
+                &urls::posts::by_id("x"),
+                web::delete().to(HttpResponse::NoContent),
+            )
+            .wrap(from_fn(reject_writes_in_maintenance)),
+    )
+    .await;
+    let set = |enabled: bool, retry_after_secs: u64| {
+        test::TestRequest::post()
+            .uri(&urls::admin::maintenance())
+            .insert_header(("Authorization", authorization()))
+            .set_json(json!({ "enabled": enabled, "retry_after_secs": retry_after_secs }))
+            .to_request()
+    };
+    let rejected = || {
+        state
+            .metrics
+            .http_maintenance_rejected
+            .load(Ordering::Relaxed)
+    };
+
+    let status: MaintenanceStatus = test::call_and_read_body_json(&app, set(true, 7)).await;
+    assert!(status.enabled);
+    assert_eq!(status.retry_after_secs, 7);
+    let since = status.since.unwrap();
+
+    for (method, uri) in [
+        (Method::POST, urls::posts::list()),
+        (Method::DELETE, urls::posts::by_id("x")),
+    ] {
+        let request = test::TestRequest::default()
+            .method(method.clone())
+            .uri(&uri)
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(
+            response.status(),
+            StatusCode::SERVICE_UNAVAILABLE,
+            "{method} {uri}"
+        );
+        assert_eq!(
+            response.headers().get(RETRY_AFTER).unwrap(),
+            "7",
+            "{method} {uri}"
+        );
+    }
+    assert_eq!(rejected(), 2);
+    let request = test::TestRequest::get()
+        .uri(&urls::posts::list())
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, request).await.status(),
+        StatusCode::OK
+    );
+
+    // Setting the mode again is an `/admin` write, which passes
+    let status: MaintenanceStatus = test::call_and_read_body_json(&app, set(true, 9)).await;
+    assert_eq!(status.since, Some(since));
+    assert_eq!(status.retry_after_secs, 9);
+    let request = test::TestRequest::post()
+        .uri(&urls::posts::list())
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.headers().get(RETRY_AFTER).unwrap(), "9");
+    assert_eq!(rejected(), 3);
+
+    let status: MaintenanceStatus = test::call_and_read_body_json(&app, set(false, 9)).await;
+    assert!(!status.enabled);
+    assert_eq!(status.since, None);
+    let request = test::TestRequest::post()
+        .uri(&urls::posts::list())
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, request).await.status(),
+        StatusCode::CREATED
+    );
+    assert_eq!(rejected(), 3);
+}
@@ -0,0 +1,804 @@
+mod checksum;
+mod maintenance;
+
+use chrono::Utc;
+use percom_model::{PageRequest, urls};
+use reqwest::{Client, Method, StatusCode};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::{
+    envs::vars::get_client_url,
+    middleware::auth_failures::token_key,
+    scheme::{
+        posts::{Post, PostInput, PostStatus},
+        users::UserInput,
+    },
+    tests::{api, authorization, endpoint},
+};
+
+// Exports the dataset in both formats, re-imports a post under a new ID from each of them and checks
+// that the imported post matches the original, including content which needs escaping.
+#[tokio::test]
+async fn export_import_roundtrip() {
+    let client = Client::new();
+    let url = get_client_url();
+    let author = format!("exporter-{}", Uuid::new_v4());
+    let post: Post = client
+        .post(endpoint(&urls::posts::list()))
+        .header("Authorization", authorization())
+        .json(&PostInput {
+            author: author.clone(),
+            date: Utc::now().fixed_offset(),
+            content: "quoted \"text\", commas,\nnew lines and ünïcödé".to_owned(),
+            publish_at: None,
+        })
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    for (format, content_type) in [("csv", "text/csv"), ("jsonl", "application/x-ndjson")] {
+        let export = client
+            .get(format!("http://{url}/admin/posts/export?format={format}"))
+            .header("Authorization", authorization())
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(export.status(), StatusCode::OK);
+        let export = export.text().await.unwrap();
+        assert!(
+            export.contains(&post.id),
+            "{format} export misses {}",
+            post.id
+        );
+
+        // Re-import the exported record under a new ID; the rest of the dataset is dropped to keep
+        // the test independent of other tests' data
+        let id = Uuid::new_v4().to_string();
+        let dataset = match format {
+            "csv" => {
+                let mut reader = csv::Reader::from_reader(export.as_bytes());
+                let headers = reader.headers().unwrap().clone();
+                let mut writer = csv::Writer::from_writer(Vec::new());
+                writer.write_record(&headers).unwrap();
+                for record in reader.records() {
+                    let record = record.unwrap();
+                    if &record[0] == post.id.as_str() {
+                        let mut fields: Vec<&str> = record.iter().collect();
+                        fields[0] = &id;
+                        writer.write_record(&fields).unwrap();
+                    }
+                }
+                String::from_utf8(writer.into_inner().unwrap()).unwrap()
+            }
+            _ => export
+                .lines()
+                .filter_map(|line| serde_json::from_str::<Post>(line).ok())
+                .filter(|p| p.id == post.id)
+                .map(|p| {
+                    serde_json::to_string(&Post {
+                        id: id.clone(),
+                        ..p
+                    })
+                    .unwrap()
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        };
+        let response = client
+            .post(format!("http://{url}/admin/posts/import"))
+            .header("Authorization", authorization())
+            .header("Content-Type", content_type)
+            .body(dataset)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.json::<serde_json::Value>().await.unwrap()["imported"],
+            1
+        );
+
+        let imported: Post = client
+            .get(endpoint(&urls::posts::by_id(&id)))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(imported.author, post.author);
+        assert_eq!(imported.content, post.content, "{format} changed content");
+        assert_eq!(imported.date, post.date, "{format} changed date");
+        assert_eq!(imported.status, post.status);
+    }
+
+    // A malformed dataset is rejected as a whole
+    let response = client
+        .post(format!("http://{url}/admin/posts/import?format=jsonl"))
+        .header("Authorization", authorization())
+        .body("{\"id\":\"x\"}\nnot json")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+// Explains the filter combinations of the list endpoints: the feed looks posts up in the author
+// index, the scheduler walks the schedule index and the public listing scans all posts.
+// Imports posts in the stored forms of older builds (without `version`, with UTC dates and
+// without `status`) next to the current one, and checks that a version from the future is refused.
+#[tokio::test]
+async fn versioned_import() {
+    let client = Client::new();
+    let url = get_client_url();
+    let (legacy, current) = (Uuid::new_v4().to_string(), Uuid::new_v4().to_string());
+    let dataset = [
+        format!(
+            r#"{{"id":"{legacy}","author":"legacy","date":"2024-05-01T10:00:00.123456789Z","content":"v1"}}"#
+        ),
+        format!(
+            r#"{{"version":2,"id":"{current}","author":"current","date":"2024-05-01T12:00:00.123456+02:00","content":"v2","status":"published"}}"#
+        ),
+    ]
+    .join("\n");
+    let import = |dataset: String| {
+        client
+            .post(format!("http://{url}/admin/posts/import?format=jsonl"))
+            .header("Authorization", authorization())
+            .body(dataset)
+            .send()
+    };
+    let response = import(dataset).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    for (id, content) in [(&legacy, "v1"), (&current, "v2")] {
+        let post: Post = client
+            .get(endpoint(&urls::posts::by_id(id)))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(post.content, content);
+        assert_eq!(post.status, PostStatus::Published);
+        assert_eq!(
+            post.date.to_utc().to_rfc3339(),
+            "2024-05-01T10:00:00.123456+00:00"
+        );
+    }
+
+    let future = format!(
+        r#"{{"version":99,"id":"{}","content":"?"}}"#,
+        Uuid::new_v4()
+    );
+    let response = import(future).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(response.text().await.unwrap().contains("version 99"));
+
+    for id in [legacy, current] {
+        client
+            .delete(endpoint(&urls::posts::by_id(&id)))
+            .header("Authorization", authorization())
+            .send()
+            .await
+            .unwrap();
+    }
+}
+
+#[tokio::test]
+async fn explain_access_paths() {
+    let client = Client::new();
+    let url = get_client_url();
+    let author = format!("explained-{}", Uuid::new_v4());
+    for _ in 0..2 {
+        let response = client
+            .post(endpoint(&urls::posts::list()))
+            .header("Authorization", authorization())
+            .json(&PostInput {
+                author: author.clone(),
+                date: Utc::now().fixed_offset(),
+                content: "explained".to_owned(),
+                publish_at: None,
+            })
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+
+    let explain = |query: String| {
+        client
+            .get(format!("http://{url}/admin/explain"))
+            .header("Authorization", authorization())
+            .query(&[("query", query)])
+            .send()
+    };
+    let plan = |response: serde_json::Value| response["plan"].clone();
+
+    let feed = explain(format!("author={author},author={author},status=published"))
+        .await
+        .unwrap();
+    assert_eq!(feed.status(), StatusCode::OK);
+    let feed = plan(feed.json().await.unwrap());
+    assert_eq!(feed["access"], "index_lookup");
+    assert_eq!(feed["index"], "by_author");
+    assert_eq!(feed["candidates"], 2);
+    assert_eq!(feed["filters"], serde_json::json!(["status"]));
+
+    let due = plan(
+        explain("due=now".to_owned())
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap(),
+    );
+    assert_eq!(due["access"], "index_range");
+    assert_eq!(due["index"], "scheduled");
+
+    let listing = plan(
+        explain("status=published".to_owned())
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap(),
+    );
+    assert_eq!(listing["access"], "full_scan");
+    assert_eq!(listing["candidates"], listing["total"]);
+
+    let malformed = explain("color=red".to_owned()).await.unwrap();
+    assert_eq!(malformed.status(), StatusCode::BAD_REQUEST);
+}
+
+// Provider stats cover both providers and follow the stored data.
+#[tokio::test]
+async fn provider_stats() {
+    let client = Client::new();
+    let url = get_client_url();
+    let response = client
+        .post(endpoint(&urls::posts::list()))
+        .header("Authorization", authorization())
+        .json(&PostInput {
+            author: format!("counted-{}", Uuid::new_v4()),
+            date: Utc::now().fixed_offset(),
+            content: "counted".to_owned(),
+            publish_at: None,
+        })
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let response = client
+        .get(format!("http://{url}/admin/providers"))
+        .header("Authorization", authorization())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let stats: serde_json::Value = response.json().await.unwrap();
+    assert!(stats["posts"]["type"].is_string());
+    assert!(stats["users"]["type"].is_string());
+    // Whatever decorators (circuit breaker, change feed, cache) wrap it, the in-memory or key-value
+    // store holds the post; with tiered storage, a new post is in the hot tier, and with
+    // worker-local state, in the shard of the worker which served the request
+    let mut posts = &stats["posts"];
+    while posts["type"] != "memory" && posts["type"] != "kv" {
+        assert!(posts.is_object(), "no posts store in {stats}");
+        posts = match posts["type"].as_str() {
+            Some("tiered") => &posts["hot"],
+            Some("sharded") => posts["shards"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .max_by_key(|shard| shard["posts"].as_u64())
+                .unwrap(),
+            _ => &posts["inner"],
+        };
+    }
+    assert!(posts["posts"].as_u64().unwrap() >= 1);
+    assert!(posts["indexes"]["by_author"].as_u64().unwrap() >= 1);
+    if posts["type"] == "memory" {
+        assert!(posts["locks"]["acquired"].as_u64().unwrap() >= 1);
+    }
+}
+
+// The change stream opens with a heartbeat, then carries the writes of posts in order.
+#[tokio::test]
+async fn change_stream() {
+    let client = Client::new();
+    let url = get_client_url();
+    let mut stream = client
+        .get(format!("http://{url}/admin/changes"))
+        .header("Authorization", authorization())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(stream.status(), StatusCode::OK);
+    let mut received = String::new();
+    while !received.contains("event: heartbeat") {
+        received.push_str(&String::from_utf8_lossy(
+            &stream.chunk().await.unwrap().unwrap(),
+        ));
+    }
+
+    let replication: serde_json::Value = client
+        .get(format!("http://{url}/admin/replication"))
+        .header("Authorization", authorization())
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(replication["role"], "primary");
+    assert_eq!(replication["read_only"], false);
+    assert!(replication["changes"]["subscribers"].as_u64().unwrap() >= 1);
+
+    let post: Post = client
+        .post(endpoint(&urls::posts::list()))
+        .header("Authorization", authorization())
+        .json(&PostInput {
+            author: format!("replicated-{}", Uuid::new_v4()),
+            date: Utc::now().fixed_offset(),
+            content: "replicated".to_owned(),
+            publish_at: None,
+        })
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let response = client
+        .delete(endpoint(&urls::posts::by_id(&post.id)))
+        .header("Authorization", authorization())
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+
+    // Other tests write concurrently, so only the changes of this post are picked
+    let mut ops = Vec::new();
+    while ops.len() < 2 {
+        let chunk = tokio::time::timeout(Duration::from_secs(5), stream.chunk())
+            .await
+            .expect("no change received")
+            .unwrap()
+            .unwrap();
+        received.push_str(&String::from_utf8_lossy(&chunk));
+        while let Some(end) = received.find("\n\n") {
+            let event: String = received.drain(..end + 2).collect();
+            let Some(data) = event.strip_prefix("event: change\ndata: ") else {
+                continue;
+            };
+            let change: serde_json::Value = serde_json::from_str(data.trim()).unwrap();
+            if change["id"] == post.id.as_str() || change["post"]["id"] == post.id.as_str() {
+                ops.push(change["op"].as_str().unwrap().to_owned());
+            }
+        }
+    }
+    assert_eq!(ops, ["upsert", "delete"]);
+}
+
+// Reads the feature flags and checks that unknown flags and values of the wrong kind are refused
+// without changing any flag.
+// Flags aren't turned off here, as other tests use the flagged endpoints concurrently.
+#[tokio::test]
+async fn feature_flags() {
+    let client = Client::new();
+    let url = get_client_url();
+    let get = || async {
+        client
+            .get(format!("http://{url}/admin/flags"))
+            .header("Authorization", authorization())
+            .send()
+            .await
+            .unwrap()
+            .json::<serde_json::Value>()
+            .await
+            .unwrap()
+    };
+    let put = |flags: serde_json::Value| {
+        client
+            .put(format!("http://{url}/admin/flags"))
+            .header("Authorization", authorization())
+            .json(&flags)
+            .send()
+    };
+    let flags = get().await;
+    assert_eq!(flags["enable_search"], true);
+    assert_eq!(flags["enable_feed"], true);
+    assert!(flags["list_posts"].is_u64(), "{flags}");
+
+    let response = put(serde_json::json!({ "enable_search": true }))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.json::<serde_json::Value>().await.unwrap(), flags);
+
+    let unknown = serde_json::json!({ "enable_feed": false, "enable_teleport": true });
+    assert_eq!(
+        put(unknown).await.unwrap().status(),
+        StatusCode::BAD_REQUEST
+    );
+    for wrong in [
+        serde_json::json!({ "list_posts": true }),
+        serde_json::json!({ "list_posts": 101 }),
+    ] {
+        assert_eq!(put(wrong).await.unwrap().status(), StatusCode::BAD_REQUEST);
+    }
+    assert_eq!(get().await, flags);
+}
+
+// Locks a path no other test uses down to another network and back, checking that the ACL is
+// enforced before routing (`403` instead of `404`), also on encoded paths, and the previous rules
+// are restored.
+#[tokio::test]
+async fn acl() {
+    let client = Client::new();
+    let url = get_client_url();
+    let put = |rules: serde_json::Value| {
+        client
+            .put(format!("http://{url}/admin/acl"))
+            .header("Authorization", authorization())
+            .json(&rules)
+            .send()
+    };
+    let probe = || client.get(format!("http://{url}/acl-probe/nothing")).send();
+    let previous: serde_json::Value = client
+        .get(format!("http://{url}/admin/acl"))
+        .header("Authorization", authorization())
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(probe().await.unwrap().status(), StatusCode::NOT_FOUND);
+
+    let rules = serde_json::json!({ "rules": [{ "path": "/acl-probe", "allow": ["10.0.0.0/8"] }] });
+    let response = put(rules).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let applied: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(applied["rules"][0]["allow"][0], "10.0.0.0/8");
+    assert_eq!(applied["rules"][0]["deny"], serde_json::json!([]));
+    assert_eq!(probe().await.unwrap().status(), StatusCode::FORBIDDEN);
+    // Encoded and doubled prefixes are matched as the path they decode to
+    for path in [
+        "/%61cl-probe/nothing",
+        "//acl-probe/nothing",
+        "/acl%2Dprobe/%2E%2E/acl-probe",
+    ] {
+        let response = client
+            .get(format!("http://{url}{path}"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN, "{path}");
+    }
+
+    let rules = serde_json::json!({ "rules": [{
+        "path": "/acl-probe",
+        "allow": ["127.0.0.1", "::1"],
+        "deny": ["10.0.0.0/8"],
+    }] });
+    assert_eq!(put(rules).await.unwrap().status(), StatusCode::OK);
+    assert_eq!(probe().await.unwrap().status(), StatusCode::NOT_FOUND);
+
+    let malformed =
+        serde_json::json!({ "rules": [{ "path": "/acl-probe", "deny": ["10.0.0.0/33"] }] });
+    assert_eq!(
+        put(malformed).await.unwrap().status(),
+        StatusCode::BAD_REQUEST
+    );
+
+    assert_eq!(put(previous).await.unwrap().status(), StatusCode::OK);
+}
+
+// Checks that `/metrics` reports every lane, counting the request reading them as an active health
+// request.
+#[tokio::test]
+async fn lanes() {
+    let metrics: serde_json::Value = Client::new()
+        .get(format!("http://{}/metrics", get_client_url()))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let lanes = &metrics["lanes"];
+    for lane in ["health", "read", "write", "bulk"] {
+        assert!(lanes[lane]["queued"].is_u64(), "{lanes}");
+    }
+    assert!(lanes["health"]["active"].as_u64().unwrap() >= 1, "{lanes}");
+}
+
+// Requests are counted by route pattern and status class in `/metrics`, which the session summary
+// written on shutdown is built from.
+#[tokio::test]
+async fn route_counts() {
+    let client = Client::new();
+    let url = get_client_url();
+    let routes = || async {
+        client
+            .get(format!("http://{url}/metrics"))
+            .send()
+            .await
+            .unwrap()
+            .json::<serde_json::Value>()
+            .await
+            .unwrap()["routes"]
+            .clone()
+    };
+    let missing = endpoint(&urls::posts::by_id(&Uuid::new_v4().to_string()));
+    assert_eq!(
+        client.get(&missing).send().await.unwrap().status(),
+        StatusCode::NOT_FOUND
+    );
+    let before = routes().await;
+    assert_eq!(
+        client.get(&missing).send().await.unwrap().status(),
+        StatusCode::NOT_FOUND
+    );
+    let after = routes().await;
+    // Other tests read posts concurrently, so counts only grow by at least this request
+    let count = |routes: &serde_json::Value, field: &str| {
+        routes["GET /posts/{id}"][field].as_u64().unwrap()
+    };
+    assert!(
+        count(&after, "requests") > count(&before, "requests"),
+        "{after}"
+    );
+    assert!(
+        count(&after, "client_errors") > count(&before, "client_errors"),
+        "{after}"
+    );
+    assert!(
+        after["GET /metrics"]["requests"].as_u64().unwrap() >= 1,
+        "{after}"
+    );
+}
+
+// Requests are attributed to the run named by their `X-Benchmark-Run-Id` header, or by an earlier
+// request of their connection, and every run gets its own latency histogram in `/metrics`.
+#[tokio::test]
+async fn run_ids() {
+    let url = get_client_url();
+    let (first, second) = (
+        format!("test/{}", Uuid::new_v4()),
+        format!("test/{}", Uuid::new_v4()),
+    );
+    // One connection per client, kept alive between requests
+    let tagged = Client::builder().pool_max_idle_per_host(1).build().unwrap();
+    let response = tagged
+        .get(endpoint(&urls::posts::list()))
+        .header("X-Benchmark-Run-Id", &first)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    response.bytes().await.unwrap();
+    for _ in 0..2 {
+        let response = tagged
+            .get(endpoint(&urls::posts::by_id(&Uuid::new_v4().to_string())))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        response.bytes().await.unwrap();
+    }
+    let other = Client::new();
+    let response = other
+        .get(endpoint(&urls::posts::list()))
+        .header("X-Benchmark-Run-Id", &second)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = other
+        .get(endpoint(&urls::posts::list()))
+        .header("X-Benchmark-Run-Id", "not a run id")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let metrics: serde_json::Value = Client::new()
+        .get(format!("http://{url}/metrics"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let runs = &metrics["runs"];
+    assert_eq!(runs[&first]["latency"]["count"], 3, "{runs}");
+    assert_eq!(runs[&second]["latency"]["count"], 1, "{runs}");
+    let latency = &runs[&first]["latency"];
+    assert!(latency["p50_us"].as_u64().unwrap() <= latency["max_us"].as_u64().unwrap());
+    let bucketed: u64 = latency["buckets"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|bucket| bucket[1].as_u64().unwrap())
+        .sum();
+    assert_eq!(bucketed, 3, "{latency}");
+}
+
+// The runtime reported by the server is consistent with the features it was built with.
+#[tokio::test]
+async fn runtime() {
+    let response = Client::new()
+        .get(format!("http://{}/admin/runtime", get_client_url()))
+        .header("Authorization", authorization())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let runtime: serde_json::Value = response.json().await.unwrap();
+    let io_uring = runtime["features"]
+        .as_array()
+        .unwrap()
+        .contains(&serde_json::json!("io-uring"));
+    match runtime["runtime"].as_str() {
+        Some("tokio-uring") => assert!(io_uring, "{runtime}"),
+        Some("tokio") => assert!(!io_uring || runtime["os"] != "linux", "{runtime}"),
+        _ => panic!("unknown runtime in {runtime}"),
+    }
+    assert!(runtime["workers"].as_u64().unwrap() >= 1, "{runtime}");
+}
+
+// Reads the maintenance mode and leaves it off. It isn't turned on here, as other tests write
+// concurrently; see `maintenance::maintenance_rejects_writes` for the mode turned on in-process.
+#[tokio::test]
+async fn maintenance() {
+    let client = Client::new();
+    let url = endpoint(&urls::admin::maintenance());
+    let status: serde_json::Value = client
+        .get(&url)
+        .header("Authorization", authorization())
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(status["enabled"], false);
+    assert!(status["since"].is_null(), "{status}");
+
+    let response = client
+        .post(&url)
+        .header("Authorization", authorization())
+        .json(&serde_json::json!({ "enabled": false, "retry_after_secs": 5 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let status: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(status["enabled"], false);
+}
+
+// Reads the checksum of the state. Its values change as other tests write concurrently, so only
+// its shape is checked.
+#[tokio::test]
+async fn checksum() {
+    let checksum: serde_json::Value = Client::new()
+        .get(format!("http://{}/admin/checksum", get_client_url()))
+        .header("Authorization", authorization())
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    for hash in [
+        &checksum["hash"],
+        &checksum["posts"]["hash"],
+        &checksum["users"]["hash"],
+    ] {
+        assert_eq!(hash.as_str().unwrap().len(), 64, "{checksum}");
+    }
+    assert!(checksum["posts"]["count"].is_u64(), "{checksum}");
+}
+
+// Sends a request with a token bound to no user to `GET /feed` and checks that
+// `GET /admin/auth-failures` counts it by route and by hashed token, without locking the client
+// out (lockouts are off for the server under test; see `tests::auth_failures`).
+#[tokio::test]
+async fn auth_failures_counted() {
+    let client = Client::new();
+    let token = Uuid::new_v4().to_string();
+    let response = client
+        .get(endpoint(&urls::feed::list()))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let report: serde_json::Value = client
+        .get(endpoint("/admin/auth-failures"))
+        .header("Authorization", authorization())
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(report["tokens"][token_key(&token)], 1);
+    assert!(report["tokens"].get(&token).is_none());
+    assert!(
+        report["routes"]["GET /feed"]["unauthorized"]
+            .as_u64()
+            .unwrap()
+            >= 1
+    );
+    assert!(report["lockout_failures"].is_null());
+    assert_eq!(report["locked"], serde_json::json!([]));
+}
+
+// Walks a user through the account lifecycle: disabled users are rejected with `403` and listed
+// as disabled until they're enabled again, and logging a user out rejects their token with `401`.
+#[tokio::test]
+async fn account_lifecycle() {
+    let api = api();
+    let nickname = format!("managed-{}", Uuid::new_v4());
+    let user = api
+        .create_user(&UserInput {
+            email: format!("{nickname}@example.com"),
+            nickname,
+        })
+        .await
+        .unwrap();
+    let as_user = api.with_bearer(&user.id);
+    let feed = || as_user.feed(PageRequest::default());
+    feed().await.unwrap();
+
+    let post = |path: String| api.send(api.request(Method::POST, &path));
+    let account: serde_json::Value = post(urls::admin::disable_user(&user.id))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(account["id"], user.id.as_str());
+    assert_eq!(account["disabled"], true);
+    let err = feed().await.unwrap_err();
+    assert_eq!(err.status(), Some(StatusCode::FORBIDDEN));
+    assert!(!api.introspect(&user.id).await.unwrap().active);
+
+    let accounts: Vec<serde_json::Value> = api
+        .send(api.request(Method::GET, &urls::admin::users()))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let listed = accounts
+        .iter()
+        .find(|account| account["id"] == user.id.as_str())
+        .unwrap();
+    assert_eq!(listed["nickname"], user.nickname.as_str());
+    assert_eq!(listed["disabled"], true);
+
+    post(urls::admin::enable_user(&user.id)).await.unwrap();
+    feed().await.unwrap();
+
+    let response = post(urls::admin::logout_user(&user.id)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let err = feed().await.unwrap_err();
+    assert_eq!(err.status(), Some(StatusCode::UNAUTHORIZED));
+
+    let err = post(urls::admin::disable_user("missing"))
+        .await
+        .unwrap_err();
+    assert_eq!(err.status(), Some(StatusCode::NOT_FOUND));
+}
@@ -0,0 +1,136 @@
+use actix_web::{App, HttpResponse, middleware::from_fn, test, web};
+use percom_model::{Introspection, UserInput, urls};
+use reqwest::{Method, StatusCode};
+use uuid::Uuid;
+
+use crate::{
+    envs::vars::get_client_token,
+    middleware::{
+        auth_failures::token_key,
+        maintenance::{MaintenanceStatus, reject_writes_in_maintenance},
+        read_only::{ReadOnly, reject_writes},
+    },
+    scheme,
+    tests::{api, authorization, state},
+};
+
+// Introspects the test token, and the token of a new user before and after the user is deleted,
+// checking the subject and validity of each, and that introspecting requires a token of the caller.
+#[tokio::test]
+async fn introspect() {
+    let api = api();
+    let introspection = api.introspect(&get_client_token()).await.unwrap();
+    assert!(introspection.active);
+    assert_eq!(introspection.sub, None);
+    assert_eq!(introspection.token_type.as_deref(), Some("Bearer"));
+    assert_eq!(introspection.exp, None);
+
+    let nickname = format!("introspected-{}", Uuid::new_v4());
+    let user = api
+        .create_user(&UserInput {
+            email: format!("{nickname}@example.com"),
+            nickname,
+        })
+        .await
+        .unwrap();
+    let introspection = api.introspect(&user.id).await.unwrap();
+    assert!(introspection.active);
+    assert_eq!(introspection.sub.as_deref(), Some(user.id.as_str()));
+
+    // Deleting the user revokes its token
+    api.delete_user(&user.id).await.unwrap();
+    let introspection = api.introspect(&user.id).await.unwrap();
+    assert_eq!(introspection, Introspection::default());
+
+    let err = api
+        .send(
+            api.anonymous(Method::POST, &urls::auth::introspect())
+                .form(&[("token", get_client_token())]),
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err.status(), Some(StatusCode::UNAUTHORIZED));
+}
+
+// Introspects a token the server never issued, checking that it counts as a failed authentication
+// attempt, so probing tokens is subject to the lockout too.
+#[tokio::test]
+async fn introspect_counted() {
+    let api = api();
+    // The dummy auth accepts any token which isn't revoked, so a revoked one is probed
+    let nickname = format!("probed-{}", Uuid::new_v4());
+    let user = api
+        .create_user(&UserInput {
+            email: format!("{nickname}@example.com"),
+            nickname,
+        })
+        .await
+        .unwrap();
+    api.delete_user(&user.id).await.unwrap();
+    assert!(!api.introspect(&user.id).await.unwrap().active);
+
+    let report: serde_json::Value = api
+        .send(api.request(Method::GET, "/admin/auth-failures"))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(report["tokens"][token_key(&user.id)], 1);
+    assert!(
+        report["routes"]["POST /auth/introspect"]["unauthorized"]
+            .as_u64()
+            .unwrap()
+            >= 1
+    );
+}
+
+// Introspects a token in maintenance mode and on a read-only instance, checking that neither
+// takes the introspection for a write, while another `POST` is still rejected.
+#[actix_web::test]
+async fn introspect_read_only() {
+    for maintenance in [false, true] {
+        let mut state = state();
+        if maintenance {
+            state.maintenance.set(&MaintenanceStatus {
+                enabled: true,
+                retry_after_secs: 1,
+                since: None,
+            });
+        } else {
+            state.read_only = Some(ReadOnly::default());
+        }
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .service(web::scope(urls::auth::SCOPE).configure(scheme::auth::routes::configure))
+                .route(&urls::posts::list(), web::post().to(HttpResponse::Created))
+                .wrap(from_fn(reject_writes_in_maintenance))
+                .wrap(from_fn(reject_writes)),
+        )
+        .await;
+
+        let request = test::TestRequest::post()
+            .uri(&urls::auth::introspect())
+            .insert_header(("Authorization", authorization()))
+            .set_form([("token", get_client_token())])
+            .to_request();
+        let introspection: Introspection = test::call_and_read_body_json(&app, request).await;
+        assert!(introspection.active, "maintenance: {maintenance}");
+
+        let request = test::TestRequest::post()
+            .uri(&urls::posts::list())
+            .to_request();
+        let expected = if maintenance {
+            StatusCode::SERVICE_UNAVAILABLE
+        } else {
+            StatusCode::METHOD_NOT_ALLOWED
+        };
+        let status = test::call_service(&app, request).await.status();
+        assert_eq!(
+            status.as_u16(),
+            expected.as_u16(),
+            "maintenance: {maintenance}"
+        );
+    }
+}
@@ -0,0 +1,107 @@
+use actix_web::{
+    App, HttpResponse,
+    http::{StatusCode, header},
+    middleware::from_fn,
+    test, web,
+};
+use std::{sync::Arc, time::Duration};
+
+use crate::{
+    middleware::auth_failures::{AuthFailures, Lockout, OTHER, track_auth_failures},
+    state::GlobalServerState,
+    tests::state,
+};
+
+/// Failures locking a client out in these tests.
+const FAILURES: u32 = 3;
+
+/// Returns the state of a server whose clients are locked out after [`FAILURES`] failures, or
+/// never if `lockout` is `false`.
+fn state_with(lockout: bool) -> web::Data<GlobalServerState> {
+    let mut state = state();
+    state.auth_failures = Arc::new(AuthFailures::new(lockout.then_some(Lockout {
+        failures: FAILURES,
+        duration: Duration::from_secs(60),
+    })));
+    web::Data::new(state)
+}
+
+// Rejects requests of a client until it's locked out, and checks that it's then answered with
+// `429` and `Retry-After` before the handler is reached, while other clients are not.
+#[actix_web::test]
+async fn lockout() {
+    let state = state_with(true);
+    let app = test::init_service(
+        App::new()
+            .app_data(state.clone())
+            .wrap(from_fn(track_auth_failures))
+            .route("/probe", web::get().to(HttpResponse::Unauthorized)),
+    )
+    .await;
+    let probe = |ip: &str| {
+        test::TestRequest::get()
+            .uri("/probe")
+            .peer_addr(format!("{ip}:4000").parse().unwrap())
+            .insert_header(("Authorization", "Bearer guessed"))
+            .to_request()
+    };
+
+    for _ in 0..FAILURES {
+        let response = test::call_service(&app, probe("203.0.113.7")).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+    let response = test::call_service(&app, probe("203.0.113.7")).await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: u64 = response
+        .headers()
+        .get(header::RETRY_AFTER)
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!((1..=60).contains(&retry_after), "{retry_after}");
+    let response = test::call_service(&app, probe("203.0.113.8")).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let report = state.auth_failures.report();
+    assert_eq!(report.locked.len(), 1);
+    assert_eq!(report.locked[0].ip.to_string(), "203.0.113.7");
+    // Requests of locked out clients aren't failures of their own
+    assert_eq!(
+        report.routes["GET /probe"].unauthorized,
+        u64::from(FAILURES) + 1
+    );
+
+    state.auth_failures.reset();
+    let response = test::call_service(&app, probe("203.0.113.7")).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+// Rejects requests with more distinct tokens than are counted on their own, and checks that the
+// rest are counted under `other`.
+#[actix_web::test]
+async fn bounded_counts() {
+    let state = state_with(false);
+    let app = test::init_service(
+        App::new()
+            .app_data(state.clone())
+            .wrap(from_fn(track_auth_failures))
+            .route("/probe", web::get().to(HttpResponse::Unauthorized)),
+    )
+    .await;
+    let tokens = 1010;
+    for token in 0..tokens {
+        let request = test::TestRequest::get()
+            .uri("/probe")
+            .insert_header(("Authorization", format!("Bearer guessed-{token}")))
+            .to_request();
+        test::call_service(&app, request).await;
+    }
+
+    let report = state.auth_failures.report();
+    assert_eq!(report.tokens.len(), 1001);
+    assert_eq!(report.tokens[OTHER], 10);
+    assert_eq!(report.tokens.values().sum::<u64>(), tokens);
+    assert_eq!(report.routes["GET /probe"].unauthorized, tokens);
+}
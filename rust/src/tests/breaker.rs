@@ -0,0 +1,26 @@
+use std::{panic, thread, time::Duration};
+
+use crate::scheme::{breaker::Breaker, provider::ProviderError};
+
+/// Cooldown of the breakers of these tests.
+const COOLDOWN: Duration = Duration::from_millis(20);
+
+// Opens a breaker and lets its probe panic, checking that the panic counts as a failed probe, so
+// the breaker opens again rather than staying half-open, and the next probe can close it.
+#[test]
+fn panicking_probe() {
+    let breaker = Breaker::new("test", 1, COOLDOWN);
+    let failed = breaker.call(|| Err::<(), _>(ProviderError::Internal("down".to_owned())));
+    assert!(failed.is_err());
+    assert_eq!(breaker.status().state, "open");
+
+    thread::sleep(COOLDOWN);
+    let probe = panic::catch_unwind(|| breaker.call(|| -> Result<(), ProviderError> { panic!() }));
+    assert!(probe.is_err());
+    assert_eq!(breaker.status().state, "open");
+    assert_eq!(breaker.status().opened, 2);
+
+    thread::sleep(COOLDOWN);
+    assert!(breaker.call(|| Ok(())).is_ok());
+    assert_eq!(breaker.status().state, "closed");
+}
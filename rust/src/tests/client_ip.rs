@@ -0,0 +1,91 @@
+use actix_web::http::header::{HeaderMap, HeaderName, HeaderValue};
+use std::net::IpAddr;
+
+use crate::client_ip::{ForwardedHeader, TrustedProxies};
+
+/// Address of the trusted proxy the requests come from.
+const PROXY: &str = "10.0.0.1";
+
+/// Resolves the client of a request from [`PROXY`] with `headers`, trusting `10.0.0.0/8`.
+fn resolve(header: ForwardedHeader, headers: &[(&'static str, &'static str)]) -> IpAddr {
+    let proxies = TrustedProxies::from_spec("10.0.0.0/8", header).unwrap();
+    let mut map = HeaderMap::new();
+    for (name, value) in headers {
+        map.append(
+            HeaderName::from_static(name),
+            HeaderValue::from_static(value),
+        );
+    }
+    proxies.resolve(PROXY.parse().unwrap(), &map)
+}
+
+// Checks that the chain is walked from the right up to the first untrusted address, so entries the
+// client prepended itself are ignored.
+#[test]
+fn spoofed_chain() {
+    let ip = |value: &str| value.parse::<IpAddr>().unwrap();
+    let xff = ForwardedHeader::XForwardedFor;
+    assert_eq!(resolve(xff, &[]), ip(PROXY));
+    assert_eq!(
+        resolve(xff, &[("x-forwarded-for", "203.0.113.7")]),
+        ip("203.0.113.7")
+    );
+    // The client sent `X-Forwarded-For: 127.0.0.1`, the proxy appended its real address
+    assert_eq!(
+        resolve(xff, &[("x-forwarded-for", "127.0.0.1, 203.0.113.7")]),
+        ip("203.0.113.7")
+    );
+    // Through two trusted proxies
+    assert_eq!(
+        resolve(
+            xff,
+            &[("x-forwarded-for", "127.0.0.1, 203.0.113.7, 10.0.0.2")]
+        ),
+        ip("203.0.113.7")
+    );
+    assert_eq!(
+        resolve(xff, &[("x-forwarded-for", "unknown, 10.0.0.2")]),
+        ip("10.0.0.2")
+    );
+
+    let untrusted = TrustedProxies::from_spec("", xff).unwrap();
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        HeaderName::from_static("x-forwarded-for"),
+        HeaderValue::from_static("127.0.0.1"),
+    );
+    assert_eq!(untrusted.resolve(ip(PROXY), &headers), ip(PROXY));
+}
+
+// Checks that only the configured header is believed, as the proxy passes the other one through
+// as the client sent it.
+#[test]
+fn spoofed_header() {
+    let ip = |value: &str| value.parse::<IpAddr>().unwrap();
+    let spoofed = [
+        ("forwarded", "for=127.0.0.1"),
+        ("x-forwarded-for", "203.0.113.7"),
+    ];
+    assert_eq!(
+        resolve(ForwardedHeader::XForwardedFor, &spoofed),
+        ip("203.0.113.7")
+    );
+    let spoofed = [
+        ("x-forwarded-for", "127.0.0.1"),
+        (
+            "forwarded",
+            "for=127.0.0.2, for=\"[2001:db8::7]:4711\";proto=https",
+        ),
+    ];
+    assert_eq!(
+        resolve(ForwardedHeader::Forwarded, &spoofed),
+        ip("2001:db8::7")
+    );
+    assert_eq!(
+        resolve(
+            ForwardedHeader::Forwarded,
+            &[("x-forwarded-for", "127.0.0.1")]
+        ),
+        ip(PROXY)
+    );
+}
@@ -0,0 +1,83 @@
+use chrono::{Duration, Utc};
+use percom_client::model::{PageRequest, PostInput, UserInput, urls};
+use reqwest::{Method, StatusCode, header::LINK};
+use uuid::Uuid;
+
+use crate::{
+    scheme::pagination::{PAGE_HEADER, TOTAL_COUNT_HEADER},
+    tests::api,
+};
+
+// Checks that `GET /feed` returns only posts of followed authors, newest first, and that pagination
+// metadata, in the body and in the headers, reflects the whole feed rather than the current page.
+#[tokio::test]
+async fn feed() {
+    let api = api();
+
+    // Register a user; with dummy auth, the user's ID works as their bearer token
+    let user = api
+        .create_user(&UserInput {
+            nickname: format!("reader-{}", Uuid::new_v4()),
+            email: format!("{}@example.com", Uuid::new_v4()),
+        })
+        .await
+        .unwrap();
+    let reader = api.with_bearer(&user.id);
+
+    // Unique author names keep the test isolated from parallel runs
+    let followed = Uuid::new_v4().to_string();
+    let ignored = Uuid::new_v4().to_string();
+    reader.follow(&user.id, &followed).await.unwrap();
+
+    let now = Utc::now();
+    let mut expected = Vec::new();
+    for (idx, author) in [&followed, &ignored, &followed, &followed]
+        .iter()
+        .enumerate()
+    {
+        let post = api
+            .create_post(&PostInput {
+                author: author.to_string(),
+                date: (now + Duration::seconds(idx as i64)).fixed_offset(),
+                content: format!("post #{idx}"),
+                publish_at: None,
+            })
+            .await
+            .unwrap();
+        if *author == &followed {
+            expected.push(post.id);
+        }
+    }
+    expected.reverse();
+
+    let page = reader.feed(PageRequest::new(1, 2)).await.unwrap();
+    assert_eq!(page.total, 3);
+    assert_eq!(
+        page.items.iter().map(|p| &p.id).collect::<Vec<_>>(),
+        expected.iter().take(2).collect::<Vec<_>>()
+    );
+
+    let path = format!("{}?page=2&per_page=1", urls::feed::list());
+    let response = reader
+        .send(reader.request(Method::GET, &path))
+        .await
+        .unwrap();
+    let headers = response.headers();
+    assert_eq!(headers[TOTAL_COUNT_HEADER], "3");
+    assert_eq!(headers[PAGE_HEADER], "2");
+    assert_eq!(
+        headers[LINK],
+        "</feed?page=1&per_page=1>; rel=\"prev\", </feed?page=3&per_page=1>; rel=\"next\""
+    );
+    let path = format!("{}?per_page=5", urls::feed::list());
+    let response = reader
+        .send(reader.request(Method::GET, &path))
+        .await
+        .unwrap();
+    assert_eq!(response.headers()[PAGE_HEADER], "1");
+    assert!(response.headers().get(LINK).is_none());
+
+    // A token that doesn't belong to any user has no feed
+    let err = api.feed(PageRequest::default()).await.unwrap_err();
+    assert_eq!(err.status(), Some(StatusCode::UNAUTHORIZED));
+}
@@ -0,0 +1,54 @@
+use chrono::Utc;
+use percom_client::{
+    Client,
+    model::{PostInput, UserInput},
+};
+use reqwest::Method;
+use serde_json::Value;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::tests::api;
+
+async fn notifications_sent(api: &Client) -> u64 {
+    let metrics: Value = api
+        .send(api.request(Method::GET, "/metrics"))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    metrics["notifications"]["sent"].as_u64().unwrap()
+}
+
+// Creates a post by a followed author and waits until the job queue delivers the notification.
+#[tokio::test]
+async fn post_created_notification() {
+    let api = api();
+    let author = Uuid::new_v4().to_string();
+    let follower = api
+        .create_user(&UserInput {
+            nickname: format!("follower-{}", Uuid::new_v4()),
+            email: format!("{}@example.com", Uuid::new_v4()),
+        })
+        .await
+        .unwrap();
+    api.follow(&follower.id, &author).await.unwrap();
+
+    let before = notifications_sent(&api).await;
+    api.create_post(&PostInput {
+        author,
+        date: Utc::now().fixed_offset(),
+        content: "hello followers".to_owned(),
+        publish_at: None,
+    })
+    .await
+    .unwrap();
+    for _ in 0..50 {
+        if notifications_sent(&api).await > before {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("notification wasn't delivered");
+}
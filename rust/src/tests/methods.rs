@@ -0,0 +1,43 @@
+use percom_model::urls;
+use reqwest::{Method, StatusCode, header::ALLOW};
+use serde_json::Value;
+
+use crate::tests::api;
+
+// Sends methods the routes don't support to known paths, checking they're refused with `405` and
+// the supported methods in `Allow`, while unknown paths are still `404`.
+#[tokio::test]
+async fn method_not_allowed() {
+    let api = api();
+    let id = uuid::Uuid::new_v4().to_string();
+    for (method, path, allow) in [
+        (Method::PATCH, urls::posts::by_id(&id), "DELETE, GET, PUT"),
+        (Method::DELETE, urls::posts::list(), "GET, POST"),
+        (Method::POST, urls::users::following(&id), "GET"),
+        (
+            Method::GET,
+            urls::users::follow(&id, "author"),
+            "DELETE, PUT",
+        ),
+        (Method::POST, urls::feed::list(), "GET"),
+        (Method::DELETE, "/admin/runtime".to_owned(), "GET"),
+    ] {
+        let response = api.request(method.clone(), &path).send().await.unwrap();
+        assert_eq!(
+            response.status(),
+            StatusCode::METHOD_NOT_ALLOWED,
+            "{method} {path}"
+        );
+        assert_eq!(response.headers()[ALLOW], allow, "{method} {path}");
+        let problem: Value = response.json().await.unwrap();
+        assert_eq!(problem["code"], "method_not_allowed");
+    }
+
+    for path in [
+        format!("{}/a/b", urls::posts::by_id(&id)),
+        "/nowhere".to_owned(),
+    ] {
+        let err = api.send(api.request(Method::GET, &path)).await.unwrap_err();
+        assert_eq!(err.status(), Some(StatusCode::NOT_FOUND), "{path}");
+    }
+}
@@ -0,0 +1,90 @@
+use actix_web::{
+    App, HttpServer,
+    dev::Payload,
+    middleware::from_fn,
+    test,
+    web::{self, Bytes},
+};
+use futures_util::{StreamExt, stream};
+use std::{
+    sync::{Arc, Mutex, atomic::Ordering},
+    time::Duration,
+};
+
+use crate::{
+    middleware::mirror::{Mirror, mirror_requests},
+    tests::state,
+};
+
+/// Largest body mirrored in these tests.
+const MAX_BODY_SIZE: usize = 16;
+
+/// Answers with the size of the body of the request.
+async fn echo(body: Bytes) -> String {
+    body.len().to_string()
+}
+
+// Mirrors requests to a shadow backend started by the test, and checks that a small body is
+// copied, while a body growing past the limit chunk by chunk isn't, yet reaches the handler whole.
+#[actix_web::test]
+async fn oversized_bodies() {
+    let received: Arc<Mutex<Vec<Bytes>>> = Arc::default();
+    let shadow = {
+        let received = received.clone();
+        HttpServer::new(move || {
+            let received = received.clone();
+            App::new().default_service(web::to(move |body: Bytes| {
+                received.lock().unwrap().push(body);
+                async { "" }
+            }))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap()
+    };
+    let url = format!("http://{}", shadow.addrs()[0]);
+    actix_web::rt::spawn(shadow.run());
+
+    let mut state = state();
+    state.mirror = Some(Mirror::new(url, 100, MAX_BODY_SIZE));
+    let state = web::Data::new(state);
+    let app = test::init_service(
+        App::new()
+            .app_data(state.clone())
+            .wrap(from_fn(mirror_requests))
+            .route("/echo", web::post().to(echo)),
+    )
+    .await;
+
+    let request = test::TestRequest::post()
+        .uri("/echo")
+        .set_payload("small")
+        .to_request();
+    assert_eq!(test::call_and_read_body(&app, request).await, "5");
+    for _ in 0..50 {
+        if state.metrics.mirror_sent.load(Ordering::Relaxed) > 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(state.metrics.mirror_sent.load(Ordering::Relaxed), 1);
+    assert_eq!(*received.lock().unwrap(), [Bytes::from("small")]);
+
+    // Without `Content-Length`, the body is only found too large while it's read
+    let chunks = (0..4).map(|_| Ok(Bytes::from(vec![b'x'; MAX_BODY_SIZE / 2 + 1])));
+    let (request, _) = test::TestRequest::post()
+        .uri("/echo")
+        .to_request()
+        .replace_payload(Payload::from(stream::iter(chunks).boxed_local()));
+    let expected = (4 * (MAX_BODY_SIZE / 2 + 1)).to_string();
+    assert_eq!(test::call_and_read_body(&app, request).await, expected);
+    let request = test::TestRequest::post()
+        .uri("/echo")
+        .set_payload(vec![b'x'; MAX_BODY_SIZE + 1])
+        .to_request();
+    let expected = (MAX_BODY_SIZE + 1).to_string();
+    assert_eq!(test::call_and_read_body(&app, request).await, expected);
+    assert_eq!(state.metrics.mirror_oversized.load(Ordering::Relaxed), 2);
+    assert_eq!(state.metrics.mirror_sent.load(Ordering::Relaxed), 1);
+    assert_eq!(received.lock().unwrap().len(), 1);
+}
@@ -1,2 +1,80 @@
+mod admin;
+mod auth;
+mod auth_failures;
+mod breaker;
+mod client_ip;
+mod feed;
+mod jobs;
+mod methods;
+mod mirror;
+mod panics;
+mod params;
+mod paths;
 mod posts;
+mod providers;
+mod signature;
+mod slow_clients;
+mod transports;
 mod users;
+
+use percom_client::Client;
+use std::sync::Arc;
+
+use crate::{
+    client_ip::TrustedProxies,
+    envs::vars::{get_client_token, get_client_url},
+    middleware::{
+        acl::Acl, auth_failures::AuthFailures, lanes::Lanes, slow_clients::ClientLimits,
+        work::WorkFactors,
+    },
+    offload::Offload,
+    scheme::{flags::FeatureFlags, users::providers::dummy::DummyProvider},
+    state::{GlobalServerState, Metrics},
+};
+
+/// Returns a client of the server under test, authenticated with the token of the tests (see
+/// [`get_client_token`]).
+fn api() -> Client {
+    Client::builder(format!("http://{}", get_client_url()))
+        .bearer(get_client_token())
+        .build()
+        .unwrap()
+}
+
+/// Returns the value of the `Authorization` header authenticating requests with the token of the
+/// tests (see [`get_client_token`]).
+fn authorization() -> String {
+    format!("Bearer {}", get_client_token())
+}
+
+/// Returns the URL of `path` (see [`percom_model::urls`]) on the server under test.
+fn endpoint(path: &str) -> String {
+    format!("http://{}{path}", get_client_url())
+}
+
+/// Returns the state of a server with the default configuration, for tests of middleware run
+/// in-process rather than against the server under test; tests change the fields they need.
+fn state() -> GlobalServerState {
+    let metrics = Arc::new(Metrics::default());
+    GlobalServerState {
+        provider: Arc::new(DummyProvider::new()),
+        metrics: metrics.clone(),
+        provider_deadline: None,
+        read_only: None,
+        trusted_proxies: TrustedProxies::default(),
+        acl: Arc::new(Acl::default()),
+        signing: None,
+        flags: Arc::new(FeatureFlags::default()),
+        work: Arc::new(WorkFactors::default()),
+        offload: Offload::new(0, metrics),
+        lanes: Lanes::default(),
+        client_limits: ClientLimits {
+            read_timeout: None,
+            body_timeout: None,
+            max_header_size: usize::MAX,
+        },
+        maintenance: Arc::default(),
+        mirror: None,
+        auth_failures: Arc::new(AuthFailures::default()),
+    }
+}
@@ -0,0 +1,56 @@
+use actix_web::{App, HttpServer, middleware::from_fn, web};
+use reqwest::{StatusCode, header::CONTENT_TYPE};
+use std::sync::atomic::Ordering;
+
+use crate::{
+    middleware::catch_panic::catch_panic, scheme::error::PROBLEM_CONTENT_TYPE, tests::state,
+};
+
+/// Panics, as a buggy handler would.
+async fn panicking() -> &'static str {
+    panic!("boom")
+}
+
+// Serves a handler which panics on a single worker, checking that the client gets a problem+json
+// `500` instead of a dropped connection, that the panic is counted, and that the worker keeps
+// serving afterwards, also on the same connection.
+#[actix_web::test]
+async fn panics_answered() {
+    let state = web::Data::new(state());
+    let server = {
+        let state = state.clone();
+        HttpServer::new(move || {
+            App::new()
+                .app_data(state.clone())
+                .route("/panic", web::get().to(panicking))
+                .route("/ok", web::get().to(|| async { "ok" }))
+                .wrap(from_fn(catch_panic))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap()
+    };
+    let url = format!("http://{}", server.addrs()[0]);
+    actix_web::rt::spawn(server.run());
+
+    // A single connection, reused for every request
+    let client = reqwest::Client::builder()
+        .pool_max_idle_per_host(1)
+        .build()
+        .unwrap();
+    for round in 1..=3 {
+        let response = client.get(format!("{url}/panic")).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(response.headers()[CONTENT_TYPE], PROBLEM_CONTENT_TYPE);
+        let problem: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(problem["status"], 500);
+        assert_eq!(problem["code"], "internal");
+        // What panicked stays in the logs
+        assert!(problem.get("detail").is_none(), "{problem}");
+        assert_eq!(state.metrics.http_panics.load(Ordering::Relaxed), round);
+
+        let response = client.get(format!("{url}/ok")).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.text().await.unwrap(), "ok");
+    }
+}
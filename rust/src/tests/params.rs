@@ -0,0 +1,28 @@
+use percom_model::urls;
+use reqwest::{Method, StatusCode};
+
+use crate::tests::api;
+
+// Checks that query parameters unknown to an endpoint are ignored, as with the default
+// `RUST_SERVER_QUERY_PARAMS=lenient`, while the known ones are still validated.
+#[tokio::test]
+async fn unknown_params_ignored() {
+    let api = api();
+    for path in [
+        format!("{}?per-page=5", urls::posts::list()),
+        format!("{}?date=2024&verbose=1", urls::posts::list()),
+        format!("{}?pretty", urls::metrics::get()),
+    ] {
+        let response = api.send(api.request(Method::GET, &path)).await;
+        assert!(response.is_ok(), "{path}: {:?}", response.err());
+    }
+
+    let err = api
+        .send(api.request(
+            Method::GET,
+            &format!("{}?tz=later&verbose=1", urls::posts::list()),
+        ))
+        .await
+        .unwrap_err();
+    assert_eq!(err.status(), Some(StatusCode::BAD_REQUEST));
+}
@@ -0,0 +1,66 @@
+use actix_web::{
+    App, HttpMessage, HttpRequest,
+    middleware::{Condition, from_fn},
+    test, web,
+};
+use percom_model::urls;
+use reqwest::{Method, StatusCode};
+
+use crate::{
+    middleware::normalize::{OriginalPath, Slashes, keep_original_path, lowercase_scope},
+    tests::api,
+};
+
+// Checks that paths with trailing or repeated slashes are routed like the normalized ones, as
+// with the default `RUST_SERVER_TRAILING_SLASH=trim`, and that the first segment is
+// case-sensitive unless `RUST_SERVER_LOWERCASE_PATHS` is set (it isn't for the tests).
+#[tokio::test]
+async fn normalized_paths() {
+    let api = api();
+    let id = uuid::Uuid::new_v4().to_string();
+    for path in [
+        format!("{}/", urls::posts::list()),
+        format!("/{}", urls::posts::list()),
+        format!("{}//", urls::metrics::get()),
+    ] {
+        let response = api.send(api.request(Method::GET, &path)).await;
+        assert!(response.is_ok(), "{path}: {:?}", response.err());
+    }
+    let err = api
+        .send(api.request(Method::GET, &format!("{}/", urls::posts::by_id(&id))))
+        .await
+        .unwrap_err();
+    assert_eq!(err.status(), Some(StatusCode::NOT_FOUND));
+
+    let err = api
+        .send(api.request(Method::GET, "/Posts"))
+        .await
+        .unwrap_err();
+    assert_eq!(err.status(), Some(StatusCode::NOT_FOUND));
+}
+
+// Sends paths which normalization rewrites through its middlewares, registered as the server
+// does, checking that handlers see them normalized while the path as sent is kept.
+#[actix_web::test]
+async fn original_paths() {
+    let app = test::init_service(
+        App::new()
+            .default_service(web::to(|req: HttpRequest| async move {
+                let original = req.extensions().get::<OriginalPath>().cloned();
+                format!("{} {}", req.path(), original.unwrap().0)
+            }))
+            .wrap(Slashes::Trim.middleware())
+            .wrap(Condition::new(true, from_fn(lowercase_scope)))
+            .wrap(from_fn(keep_original_path)),
+    )
+    .await;
+    for (sent, routed) in [
+        ("/Posts/?page=1", "/posts"),
+        ("//posts//x/", "/posts/x"),
+        ("/posts", "/posts"),
+    ] {
+        let request = test::TestRequest::get().uri(sent).to_request();
+        let body = test::call_and_read_body(&app, request).await;
+        assert_eq!(body, format!("{routed} {sent}"));
+    }
+}
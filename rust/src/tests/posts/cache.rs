@@ -0,0 +1,63 @@
+use percom_client::model::PostInput;
+use percom_model::urls;
+use reqwest::{
+    Method, StatusCode,
+    header::{ETAG, IF_NONE_MATCH},
+};
+
+use crate::tests::api;
+
+// Reads a post through both variants of `get_post` before and after it's updated and deleted,
+// checking that it carries the same `ETag` either way, that `If-None-Match` with it is answered
+// with `304`, and that no stale body or tag is served once the post changed. Hits of the cache, if
+// the server has one, are tested on the provider (see `tests::providers::cache`).
+#[tokio::test]
+async fn cached_reads() {
+    let api = api();
+    let input = |content: &str| PostInput {
+        author: "cache".to_owned(),
+        date: "2024-05-01T12:00:00Z".parse().unwrap(),
+        content: content.to_owned(),
+        publish_at: None,
+    };
+    let post = api.create_post(&input("first")).await.unwrap();
+    let get = |variant: &'static str, etag: Option<String>| {
+        let mut request = api
+            .request(Method::GET, &urls::posts::by_id(&post.id))
+            .header("X-Variant", variant);
+        if let Some(etag) = etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        api.send(request)
+    };
+
+    let response = get("raw", None).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let etag = response.headers()[ETAG].to_str().unwrap().to_owned();
+    assert_eq!(
+        response.json::<percom_client::model::Post>().await.unwrap(),
+        post
+    );
+    for variant in ["decode", "raw"] {
+        let response = get(variant, None).await.unwrap();
+        assert_eq!(response.headers()[ETAG], etag.as_str());
+        let response = get(variant, Some(etag.clone())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[ETAG], etag.as_str());
+        assert!(response.bytes().await.unwrap().is_empty());
+    }
+
+    let updated = api.update_post(&post.id, &input("second")).await.unwrap();
+    assert_eq!(updated.content, "second");
+    for variant in ["decode", "raw"] {
+        let response = get(variant, Some(etag.clone())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_ne!(response.headers()[ETAG], etag.as_str());
+        let body: percom_client::model::Post = response.json().await.unwrap();
+        assert_eq!(body, updated);
+    }
+
+    api.delete_post(&post.id).await.unwrap();
+    let err = get("raw", Some(etag)).await.unwrap_err();
+    assert_eq!(err.status(), Some(StatusCode::NOT_FOUND));
+}
@@ -0,0 +1,57 @@
+use chrono::Utc;
+use percom_model::urls;
+use reqwest::{Client, StatusCode};
+use uuid::Uuid;
+
+use crate::{
+    scheme::posts::{Post, PostInput, routes::POST_ID_HEADER},
+    tests::{authorization, endpoint},
+};
+
+// Creates a post under an ID chosen by the client, as the shard router does, and checks that the
+// ID can't be taken twice and must be a UUID.
+#[tokio::test]
+async fn chosen_id() {
+    let client = Client::new();
+    let url = endpoint(&urls::posts::list());
+    let input = PostInput {
+        author: "chooser".to_owned(),
+        date: Utc::now().fixed_offset(),
+        content: "chosen".to_owned(),
+        publish_at: None,
+    };
+    let create = |id: String| {
+        client
+            .post(&url)
+            .header("Authorization", authorization())
+            .header(POST_ID_HEADER, id)
+            .json(&input)
+            .send()
+    };
+    let id = Uuid::new_v4().to_string();
+    let response = create(id.clone()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let post: Post = response.json().await.unwrap();
+    assert_eq!(post.id, id);
+    let stored: Post = client
+        .get(format!("{url}/{id}"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(stored.content, "chosen");
+
+    let taken = create(id.clone()).await.unwrap();
+    assert_eq!(taken.status(), StatusCode::CONFLICT);
+    let malformed = create("not-a-uuid".to_owned()).await.unwrap();
+    assert_eq!(malformed.status(), StatusCode::BAD_REQUEST);
+
+    client
+        .delete(format!("{url}/{id}"))
+        .header("Authorization", authorization())
+        .send()
+        .await
+        .unwrap();
+}
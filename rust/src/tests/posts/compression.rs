@@ -0,0 +1,74 @@
+use chrono::Utc;
+use flate2::{Compression, write::GzEncoder};
+use percom_model::urls;
+use reqwest::{Client, StatusCode, header};
+use std::io::Write;
+
+use crate::{
+    scheme::posts::{Post, PostInput},
+    tests::{authorization, endpoint},
+};
+
+fn gzip(body: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(body).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn brotli(body: &[u8]) -> Vec<u8> {
+    let mut compressed = Vec::new();
+    brotli::BrotliCompress(&mut &body[..], &mut compressed, &Default::default()).unwrap();
+    compressed
+}
+
+fn input(content: String) -> Vec<u8> {
+    serde_json::to_vec(&PostInput {
+        author: "compression".to_owned(),
+        date: Utc::now().fixed_offset(),
+        content,
+        publish_at: None,
+    })
+    .unwrap()
+}
+
+async fn create(client: &Client, encoding: &str, body: Vec<u8>) -> reqwest::Response {
+    client
+        .post(endpoint(&urls::posts::list()))
+        .header("Authorization", authorization())
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::CONTENT_ENCODING, encoding)
+        .body(body)
+        .send()
+        .await
+        .unwrap()
+}
+
+// Creates posts with gzip and brotli compressed bodies, and checks that unsupported codings and
+// bodies exceeding the limit once decompressed are rejected.
+#[tokio::test]
+async fn compressed_bodies() {
+    let client = Client::new();
+    for (encoding, compress) in [("gzip", gzip as fn(&[u8]) -> Vec<u8>), ("br", brotli)] {
+        let content = format!("compressed with {encoding} ").repeat(100);
+        let response = create(&client, encoding, compress(&input(content.clone()))).await;
+        assert_eq!(response.status(), StatusCode::CREATED, "{encoding}");
+        let post: Post = response.json().await.unwrap();
+        assert_eq!(post.content, content);
+        client
+            .delete(endpoint(&urls::posts::by_id(&post.id)))
+            .header("Authorization", authorization())
+            .send()
+            .await
+            .unwrap();
+    }
+
+    let response = create(&client, "compress", input("x".to_owned())).await;
+    assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert!(response.headers().contains_key(header::ACCEPT_ENCODING));
+
+    // A few KiB on the wire, beyond the default limit of 2 MiB once decompressed
+    let bomb = gzip(&input(" ".repeat(3 * 1024 * 1024)));
+    assert!(bomb.len() < 64 * 1024);
+    let response = create(&client, "gzip", bomb).await;
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
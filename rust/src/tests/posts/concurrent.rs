@@ -0,0 +1,200 @@
+use chrono::Utc;
+use percom_model::urls;
+use reqwest::{Client, StatusCode};
+use std::{
+    collections::{HashMap, HashSet},
+    time::Instant,
+};
+
+use crate::{
+    scheme::posts::{Post, PostInput},
+    tests::{authorization, endpoint},
+};
+
+/// Number of posts all writers compete for.
+const IDS: usize = 8;
+
+/// Number of concurrent writers.
+const WRITERS: usize = 32;
+
+/// Number of operations performed by each writer.
+const OPS: usize = 40;
+
+/// Outcome of a single write, as observed by the client.
+struct Write {
+    id: String,
+    /// `Some(content)` for updates, `None` for deletions.
+    content: Option<String>,
+    status: StatusCode,
+    sent: Instant,
+    received: Instant,
+}
+
+fn input(author: &str, content: String) -> PostInput {
+    PostInput {
+        author: author.to_owned(),
+        date: Utc::now().fixed_offset(),
+        content,
+        publish_at: None,
+    }
+}
+
+// Many writers perform interleaved updates and deletions on the same few posts, then the final state
+// is checked against what the clients were told:
+//
+// - at most one deletion of a post succeeds, and a deleted post is gone from both `GET /posts/{id}`
+//   and `GET /posts` (no ghost entries); with `RUST_SERVER_IDEMPOTENT_DELETE=1`, deleting a deleted
+//   post succeeds too, so only the latter is checked then;
+// - an update sent after the first successful deletion had completed fails with `404`;
+// - a surviving post holds the content of an acknowledged update (or its initial content if none
+//   was acknowledged), so nothing is invented;
+// - no acknowledged update was sent after the winning update had completed, so nothing is lost
+//   beyond last-writer-wins among overlapping updates.
+//
+// The test checks whichever posts provider the server runs with (`RUST_SERVER_POSTS_PROVIDER`).
+#[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+async fn concurrent_writers() {
+    let client = Client::new();
+    let author = format!("writer-{}", uuid::Uuid::new_v4());
+
+    // The mode of the server under test, told by deleting a post which never existed
+    let idempotent_delete = client
+        .delete(endpoint(&urls::posts::by_id(
+            &uuid::Uuid::new_v4().to_string(),
+        )))
+        .header("Authorization", authorization())
+        .send()
+        .await
+        .unwrap()
+        .status()
+        == StatusCode::NO_CONTENT;
+
+    let mut ids = Vec::new();
+    for _ in 0..IDS {
+        let post: Post = client
+            .post(endpoint(&urls::posts::list()))
+            .header("Authorization", authorization())
+            .json(&input(&author, "initial".to_owned()))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        ids.push(post.id);
+    }
+
+    let mut writers = Vec::new();
+    for writer in 0..WRITERS {
+        let client = client.clone();
+        let author = author.clone();
+        let ids = ids.clone();
+        writers.push(tokio::spawn(async move {
+            let mut writes = Vec::new();
+            for op in 0..OPS {
+                // Deterministic, but well interleaved across writers
+                let id = ids[(writer * 7 + op * 3) % IDS].clone();
+                let delete = (writer + op) % 13 == 0;
+                let content = (!delete).then(|| format!("{writer}-{op}"));
+                let sent = Instant::now();
+                let request = match &content {
+                    Some(content) => client
+                        .put(endpoint(&urls::posts::by_id(&id)))
+                        .json(&input(&author, content.clone())),
+                    None => client.delete(endpoint(&urls::posts::by_id(&id))),
+                };
+                let status = request
+                    .header("Authorization", authorization())
+                    .send()
+                    .await
+                    .unwrap()
+                    .status();
+                writes.push(Write {
+                    id,
+                    content,
+                    status,
+                    sent,
+                    received: Instant::now(),
+                });
+            }
+            writes
+        }));
+    }
+    let mut writes: HashMap<String, Vec<Write>> = HashMap::new();
+    for writer in writers {
+        for write in writer.await.unwrap() {
+            writes.entry(write.id.clone()).or_default().push(write);
+        }
+    }
+
+    let listed: HashSet<String> = client
+        .get(endpoint(&urls::posts::list()))
+        .send()
+        .await
+        .unwrap()
+        .json::<Vec<Post>>()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|post| post.id)
+        .collect();
+
+    for id in ids.iter() {
+        let writes = writes.remove(id).unwrap_or_default();
+        let mut deletions: Vec<&Write> = writes
+            .iter()
+            .filter(|w| w.content.is_none() && w.status == StatusCode::NO_CONTENT)
+            .collect();
+        // The post was gone once the first one completed, whatever the mode
+        deletions.sort_by_key(|w| w.received);
+        let updates: Vec<&Write> = writes
+            .iter()
+            .filter(|w| w.content.is_some() && w.status == StatusCode::OK)
+            .collect();
+        for write in writes.iter() {
+            assert!(
+                [
+                    StatusCode::OK,
+                    StatusCode::NO_CONTENT,
+                    StatusCode::NOT_FOUND
+                ]
+                .contains(&write.status),
+                "unexpected status {} for {id}",
+                write.status
+            );
+        }
+        if !idempotent_delete {
+            assert!(deletions.len() <= 1, "{id} deleted more than once");
+        }
+
+        let response = client
+            .get(endpoint(&urls::posts::by_id(id)))
+            .send()
+            .await
+            .unwrap();
+        if let Some(deletion) = deletions.first() {
+            assert_eq!(response.status(), StatusCode::NOT_FOUND, "{id} resurrected");
+            assert!(!listed.contains(id), "ghost entry {id} in GET /posts");
+            assert!(
+                updates.iter().all(|w| w.sent < deletion.received),
+                "{id} updated after its deletion completed"
+            );
+            continue;
+        }
+        assert_eq!(response.status(), StatusCode::OK, "{id} lost");
+        assert!(listed.contains(id), "{id} missing in GET /posts");
+        let content = response.json::<Post>().await.unwrap().content;
+        if updates.is_empty() {
+            assert_eq!(content, "initial", "{id} changed without updates");
+            continue;
+        }
+        let winner = updates
+            .iter()
+            .find(|w| w.content.as_deref() == Some(content.as_str()))
+            .unwrap_or_else(|| panic!("{id} holds unacknowledged content {content}"));
+        assert!(
+            updates.iter().all(|w| w.sent < winner.received),
+            "{id} holds {content}, though an acknowledged update was sent after it completed"
+        );
+    }
+}
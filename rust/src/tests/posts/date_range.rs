@@ -0,0 +1,59 @@
+use percom_model::urls;
+use reqwest::{Client, StatusCode};
+use serde_json::{Value, json};
+
+use crate::tests::{authorization, endpoint};
+
+// Creates posts in a month no other test uses and lists them with `?date=`: the prefix matches
+// the UTC date, so a post written late on the last day of the month at a negative offset belongs
+// to the next one, and the posts come oldest first.
+#[tokio::test]
+async fn date_range() {
+    let client = Client::new();
+    let url = endpoint(&urls::posts::list());
+    let mut ids = Vec::new();
+    for date in [
+        "1901-03-20T10:00:00Z",
+        "1901-03-01T08:00:00+02:00",
+        "1901-03-31T23:30:00-02:00",
+        "1901-02-28T23:00:00Z",
+    ] {
+        let response = client
+            .post(&url)
+            .header("Authorization", authorization())
+            .json(&json!({ "author": "dated", "date": date, "content": date }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let post: Value = response.json().await.unwrap();
+        ids.push(post["id"].as_str().unwrap().to_owned());
+    }
+
+    let list = |prefix: &str| {
+        let request = client.get(&url).query(&[("date", prefix)]).send();
+        async move {
+            let posts: Vec<Value> = request.await.unwrap().json().await.unwrap();
+            posts
+                .into_iter()
+                .map(|post| post["content"].as_str().unwrap().to_owned())
+                .collect::<Vec<_>>()
+        }
+    };
+    assert_eq!(
+        list("1901-03").await,
+        ["1901-03-01T08:00:00+02:00", "1901-03-20T10:00:00Z"]
+    );
+    assert_eq!(list("1901-04-01T01").await, ["1901-03-31T23:30:00-02:00"]);
+    assert_eq!(list("1901-0").await.len(), 4);
+    assert!(list("1901-05").await.is_empty());
+
+    for id in ids {
+        client
+            .delete(format!("{url}/{id}"))
+            .header("Authorization", authorization())
+            .send()
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,74 @@
+use actix_web::{App, http::StatusCode, test, web};
+use chrono::Utc;
+use percom_model::urls;
+use std::sync::Arc;
+
+use crate::{
+    jobs::{JobQueue, LogNotifier},
+    scheme::{
+        self,
+        moderation::{KeywordModerator, ModerationQueue},
+        posts::{Codec, Compression, DummyProvider, PostInput, PostsProvider, routes::PostsState},
+        users::providers::dummy::DummyProvider as DummyUsers,
+    },
+    tests::state,
+};
+
+// Deletes a post twice, in strict and in idempotent mode (`RUST_SERVER_IDEMPOTENT_DELETE`),
+// checking that only strict mode answers the second deletion with `404`, and that both delete
+// the post.
+#[actix_web::test]
+async fn idempotent_delete() {
+    for idempotent in [false, true] {
+        let state = state();
+        let posts = DummyProvider::wrapped(Compression::new(Codec::None, state.metrics.clone()));
+        let jobs = JobQueue::start(
+            0,
+            1,
+            Arc::new(LogNotifier {}),
+            Arc::new(DummyUsers::new()),
+            state.metrics.clone(),
+        );
+        let posts_state = PostsState::new(
+            posts.clone(),
+            jobs,
+            idempotent,
+            Default::default(),
+            Arc::new(KeywordModerator::default()),
+            Arc::new(ModerationQueue::default()),
+        );
+        let app = test::init_service(
+            App::new().app_data(web::Data::new(state)).service(
+                web::scope(urls::posts::SCOPE)
+                    .app_data(web::Data::new(posts_state))
+                    .configure(scheme::posts::routes::configure),
+            ),
+        )
+        .await;
+        let post = posts
+            .create(PostInput {
+                author: "author".to_owned(),
+                date: Utc::now().fixed_offset(),
+                content: "content".to_owned(),
+                publish_at: None,
+            })
+            .unwrap();
+
+        let delete = || {
+            test::TestRequest::delete()
+                .uri(&urls::posts::by_id(&post.id))
+                .insert_header(("Authorization", "Bearer token"))
+                .to_request()
+        };
+        let status = test::call_service(&app, delete()).await.status();
+        assert_eq!(status, StatusCode::NO_CONTENT, "idempotent: {idempotent}");
+        assert!(posts.get(&post.id).unwrap().is_none());
+        let status = test::call_service(&app, delete()).await.status();
+        let expected = if idempotent {
+            StatusCode::NO_CONTENT
+        } else {
+            StatusCode::NOT_FOUND
+        };
+        assert_eq!(status, expected, "idempotent: {idempotent}");
+    }
+}
@@ -0,0 +1,43 @@
+use chrono::Utc;
+use percom_model::urls;
+use reqwest::{Method, StatusCode};
+use serde_json::{Value, json};
+
+use crate::{scheme::posts::PostInput, tests::api};
+
+// Lists and gets a post with `?fields=`, checking that only the requested fields are sent, and that
+// unknown fields are rejected.
+#[tokio::test]
+async fn sparse_fields() {
+    let api = api();
+    let post = api
+        .create_post(&PostInput {
+            author: "sparse".to_owned(),
+            date: Utc::now().fixed_offset(),
+            content: "left out".to_owned(),
+            publish_at: None,
+        })
+        .await
+        .unwrap();
+    let get = |path: String| api.send(api.request(Method::GET, &path));
+
+    let listed: Vec<Value> = get(format!("{}?fields=id,author", urls::posts::list()))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let listed = listed.iter().find(|item| item["id"] == post.id).unwrap();
+    assert_eq!(listed, &json!({"id": post.id, "author": "sparse"}));
+
+    let path = format!("{}?fields=date,%20content", urls::posts::by_id(&post.id));
+    let single: Value = get(path).await.unwrap().json().await.unwrap();
+    assert_eq!(single.as_object().unwrap().len(), 2);
+    assert_eq!(single["content"], "left out");
+
+    let err = get(format!("{}?fields=id,secret", urls::posts::list()))
+        .await
+        .unwrap_err();
+    assert_eq!(err.status(), Some(StatusCode::BAD_REQUEST));
+    api.delete_post(&post.id).await.unwrap();
+}
@@ -0,0 +1,70 @@
+use chrono::Utc;
+use percom_model::{UserInput, urls};
+use reqwest::{Method, StatusCode};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{
+    scheme::posts::PostInput,
+    tests::{api, endpoint},
+};
+
+// Gets a post with `?include=author`, checking that the author is embedded when they're a
+// registered user, `null` otherwise, and that anonymous requests and unknown relations are
+// rejected.
+#[tokio::test]
+async fn embedded_author() {
+    let api = api();
+    let nickname = Uuid::new_v4().to_string();
+    let user = api
+        .create_user(&UserInput {
+            nickname: nickname.clone(),
+            email: format!("{nickname}@example.com"),
+        })
+        .await
+        .unwrap();
+    let mut posts = Vec::new();
+    for author in [nickname.clone(), Uuid::new_v4().to_string()] {
+        let post = api
+            .create_post(&PostInput {
+                author,
+                date: Utc::now().fixed_offset(),
+                content: "with relations".to_owned(),
+                publish_at: None,
+            })
+            .await
+            .unwrap();
+        posts.push(post);
+    }
+    let get = |path: String| api.send(api.request(Method::GET, &path));
+
+    let path = format!("{}?include=author", urls::posts::by_id(&posts[0].id));
+    let document: Value = get(path).await.unwrap().json().await.unwrap();
+    assert_eq!(document["data"]["id"], posts[0].id);
+    assert_eq!(document["included"]["author"]["id"], user.id);
+
+    let path = format!(
+        "{}?include=author&fields=id",
+        urls::posts::by_id(&posts[1].id)
+    );
+    let document: Value = get(path).await.unwrap().json().await.unwrap();
+    assert_eq!(document["data"].as_object().unwrap().len(), 1);
+    assert!(document["included"]["author"].is_null());
+
+    let path = format!("{}?include=author", urls::posts::by_id(&posts[0].id));
+    let anonymous = reqwest::Client::new()
+        .get(endpoint(&path))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(anonymous.status(), StatusCode::UNAUTHORIZED);
+
+    let path = format!("{}?include=comments", urls::posts::by_id(&posts[0].id));
+    let err = get(path).await.unwrap_err();
+    assert_eq!(err.status(), Some(StatusCode::BAD_REQUEST));
+
+    for post in &posts {
+        api.delete_post(&post.id).await.unwrap();
+    }
+    api.delete_user(&user.id).await.unwrap();
+}
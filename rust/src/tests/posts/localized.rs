@@ -0,0 +1,62 @@
+use chrono::Utc;
+use percom_model::urls;
+use reqwest::{Client, StatusCode, header::CONTENT_LANGUAGE};
+use serde_json::Value;
+
+use crate::{
+    scheme::posts::{PostInput, routes::POST_ID_HEADER},
+    tests::{authorization, endpoint},
+};
+
+// Checks that error responses follow `Accept-Language`, translating the title and known details,
+// while the error code stays the same in every language.
+#[tokio::test]
+async fn localized() {
+    let client = Client::new();
+    let url = endpoint(&urls::posts::list());
+
+    let missing = client
+        .get(format!("{url}/missing"))
+        .header("Accept-Language", "de-DE, en;q=0.5")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+    assert_eq!(missing.headers()[CONTENT_LANGUAGE], "de");
+    let problem: Value = missing.json().await.unwrap();
+    assert_eq!(problem["code"], "not_found");
+    assert_eq!(problem["title"], "Nicht gefunden");
+
+    let input = PostInput {
+        author: "localized".to_owned(),
+        date: Utc::now().fixed_offset(),
+        content: "localized".to_owned(),
+        publish_at: None,
+    };
+    let malformed = |language: &'static str| {
+        client
+            .post(&url)
+            .header("Authorization", authorization())
+            .header("Accept-Language", language)
+            .header(POST_ID_HEADER, "not-a-uuid")
+            .json(&input)
+            .send()
+    };
+    let problem: Value = malformed("de").await.unwrap().json().await.unwrap();
+    assert_eq!(problem["code"], "bad_request");
+    assert_eq!(
+        problem["detail"],
+        format!("{POST_ID_HEADER} muss eine UUID sein")
+    );
+
+    // Unsupported languages fall back to English
+    let response = malformed("fr, de;q=0").await.unwrap();
+    assert!(response.headers().get(CONTENT_LANGUAGE).is_none());
+    let problem: Value = response.json().await.unwrap();
+    assert_eq!(problem["code"], "bad_request");
+    assert_eq!(problem["title"], "Bad Request");
+    assert_eq!(
+        problem["detail"],
+        format!("{POST_ID_HEADER} must be a UUID")
+    );
+}
@@ -0,0 +1,41 @@
+use chrono::Utc;
+use percom_model::urls;
+use reqwest::{Method, StatusCode, Url, header::LOCATION};
+
+use crate::{
+    scheme::posts::{Post, PostInput},
+    tests::api,
+};
+
+// Creates a post and follows its `Location`, resolved against the URL of the request, checking
+// that it leads to the created post and that a forged `Host` doesn't leak into it.
+#[tokio::test]
+async fn location_resolves() {
+    let api = api();
+    let created = api.url(&urls::posts::list());
+    let response = api
+        .send(
+            api.request(Method::POST, &urls::posts::list())
+                .header("Host", "attacker.example")
+                .json(&PostInput {
+                    author: "locator".to_owned(),
+                    date: Utc::now().fixed_offset(),
+                    content: "where am I".to_owned(),
+                    publish_at: None,
+                }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let location = response.headers()[LOCATION].to_str().unwrap().to_owned();
+    let post: Post = response.json().await.unwrap();
+    assert_eq!(location, urls::posts::by_id(&post.id));
+
+    let target = Url::parse(&created).unwrap().join(&location).unwrap();
+    let response = api
+        .send(api.anonymous(Method::GET, target.path()))
+        .await
+        .unwrap();
+    assert_eq!(response.json::<Post>().await.unwrap(), post);
+    api.delete_post(&post.id).await.unwrap();
+}
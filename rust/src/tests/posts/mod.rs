@@ -1,23 +1,33 @@
+mod cache;
+mod chosen_id;
+mod compression;
+mod concurrent;
+mod date_range;
+mod deletion;
+mod fields;
+mod include;
+mod localized;
+mod location;
+mod protobuf;
+mod scheduled;
+mod slow_clients;
 mod stat;
+mod time_zones;
+mod variants;
 
 use actix_web::http::StatusCode;
-use chrono::{DateTime, Timelike, Utc};
+use percom_model::urls;
 use proptest::prelude::*;
 use reqwest::Client;
 use std::time::Instant;
 use tokio::runtime::Runtime;
 
 use crate::{
-    envs::vars::get_client_url,
     scheme::posts::{Post, PostInput},
+    tests::{authorization, endpoint},
 };
 use stat::*;
 
-fn truncate_to_micros(dt: DateTime<Utc>) -> DateTime<Utc> {
-    dt.with_nanosecond(dt.timestamp_subsec_micros() * 1000)
-        .unwrap()
-}
-
 // End-to-end property-based test that exercises the full lifecycle of post management.
 //
 // The test executes the following scenario for a randomly generated batch of posts:
@@ -65,7 +75,8 @@ proptest! {
         let rt = Runtime::new().unwrap();
         rt.block_on(async {
             let client = Client::new();
-            let mut measuremnt: Vec<TimeMeasument> = Vec::new();
+            // Flushed to the statistics on drop, even if an assertion fails midway
+            let mut measuremnt = Recorder::default();
             let mut times = Vec::new();
             let mut ids = Vec::new();
 
@@ -75,8 +86,8 @@ proptest! {
                     let start = Instant::now();
                     // Create a post
                     let response = client
-                        .post(format!("http://{}/posts", get_client_url()))
-                        .header("Authorization", "Bearer fake_test_token")
+                        .post(endpoint(&urls::posts::list()))
+                        .header("Authorization", authorization())
                         .json(post)
                         .send()
                         .await;
@@ -87,15 +98,16 @@ proptest! {
                     let response = response.unwrap();
                     let status = response.status();
                     assert_eq!(status.as_u16(), StatusCode::CREATED, "unexpected status: {status}");
-                    times.push(start.elapsed().as_nanos());
                     // println!("Post created in {} ms",start.elapsed().as_millis());
                     // Get a post
-                    let published: Post = response.json().await.unwrap();
+                    let (timing, body) = read_timed(start, response).await;
+                    times.push(timing);
+                    let published: Post = serde_json::from_slice(&body).unwrap();
 
                     // Check post
                     assert_eq!(post.author, published.author);
                     assert_eq!(post.content, published.content);
-                    assert_eq!(truncate_to_micros(post.date), truncate_to_micros(published.date));
+                    assert_eq!(post.date, published.date);
 
                     // Check unique of id
                     assert!(!ids.contains(&published.id));
@@ -115,8 +127,8 @@ proptest! {
                     let start = Instant::now();
                     // Get a post
                     let response = client
-                        .get(format!("http://{}/posts/{id}", get_client_url()))
-                        .header("Authorization", "Bearer fake_test_token")
+                        .get(endpoint(&urls::posts::by_id(id)))
+                        .header("Authorization", authorization())
                         .send()
                         .await;
                     // Check network status
@@ -126,15 +138,16 @@ proptest! {
                     let response = response.unwrap();
                     let status = response.status();
                     assert_eq!(status.as_u16(), StatusCode::OK, "unexpected status: {status}");
-                    times.push(start.elapsed().as_nanos());
                     // println!("Post gotten in {} ms",start.elapsed().as_millis());
                     // Get a post
-                    let post: Post = response.json().await.unwrap();
+                    let (timing, body) = read_timed(start, response).await;
+                    times.push(timing);
+                    let post: Post = serde_json::from_slice(&body).unwrap();
 
                     // Check post
                     assert_eq!(post.author, posts[idx].author);
                     assert_eq!(post.content, posts[idx].content);
-                    assert_eq!(truncate_to_micros(post.date), truncate_to_micros(posts[idx].date));
+                    assert_eq!(post.date, posts[idx].date);
 
                 }
 
@@ -149,9 +162,9 @@ proptest! {
                     let start = Instant::now();
                     // Update a post
                     let response = client
-                        .put(format!("http://{}/posts/{id}", get_client_url()))
-                        .header("Authorization", "Bearer fake_test_token")
-                        .json(&PostInput {  content: "-".to_owned(), author: "-".to_owned(), date: posts[idx].date.to_owned()})
+                        .put(endpoint(&urls::posts::by_id(id)))
+                        .header("Authorization", authorization())
+                        .json(&PostInput {  content: "-".to_owned(), author: "-".to_owned(), date: posts[idx].date.to_owned(), publish_at: None })
                         .send()
                         .await;
                     // Check network status
@@ -161,15 +174,16 @@ proptest! {
                     let response = response.unwrap();
                     let status = response.status();
                     assert_eq!(status.as_u16(), StatusCode::OK, "unexpected status: {status}");
-                    times.push(start.elapsed().as_nanos());
                     // println!("Post updated in {} ms",start.elapsed().as_millis());
                     // Get a post
-                    let post: Post = response.json().await.unwrap();
+                    let (timing, body) = read_timed(start, response).await;
+                    times.push(timing);
+                    let post: Post = serde_json::from_slice(&body).unwrap();
 
                     // Check post
                     assert_eq!(post.author, "-");
                     assert_eq!(post.content, "-");
-                    assert_eq!(truncate_to_micros(post.date), truncate_to_micros(posts[idx].date));
+                    assert_eq!(post.date, posts[idx].date);
 
                 }
 
@@ -181,8 +195,8 @@ proptest! {
             {
                 let start = Instant::now();
                 let response = client
-                    .get(format!("http://{}/posts", get_client_url() ))
-                    .header("Authorization", "Bearer fake_test_token")
+                    .get(endpoint(&urls::posts::list()))
+                    .header("Authorization", authorization())
                     .send()
                     .await;
                 // Check network status
@@ -192,11 +206,12 @@ proptest! {
                 let response = response.unwrap();
                 let status = response.status();
                 assert_eq!(status.as_u16(), StatusCode::OK, "unexpected status: {status}");
-                measuremnt.push(TimeMeasument::ListPost(start.elapsed().as_nanos()));
                 // println!("Post list is gotten in {} ms",start.elapsed().as_millis());
 
                 // Get a posts list
-                let all: Vec<Post> = response.json().await.unwrap();
+                let (timing, body) = read_timed(start, response).await;
+                measuremnt.push(TimeMeasument::ListPost(timing));
+                let all: Vec<Post> = serde_json::from_slice(&body).unwrap();
 
                 for id in ids.iter() {
                     let actual = all.iter().find(|post| &post.id == id).unwrap();
@@ -215,8 +230,8 @@ proptest! {
                     let start = Instant::now();
                     // Remove a post
                     let response = client
-                        .delete(format!("http://{}/posts/{id}", get_client_url()))
-                        .header("Authorization", "Bearer fake_test_token")
+                        .delete(endpoint(&urls::posts::by_id(id)))
+                        .header("Authorization", authorization())
                         .send()
                         .await;
                     // Check network status
@@ -226,7 +241,7 @@ proptest! {
                     let response = response.unwrap();
                     let status = response.status();
                     assert_eq!(status.as_u16(), StatusCode::NO_CONTENT, "unexpected status: {status}");
-                    times.push(start.elapsed().as_nanos());
+                    times.push(read_timed(start, response).await.0);
                     // println!("Post deleted in {} ms",start.elapsed().as_millis());
 
                 }
@@ -237,8 +252,8 @@ proptest! {
             // Get all posts
             {
                 let response = client
-                    .get(format!("http://{}/posts", get_client_url() ))
-                    .header("Authorization", "Bearer fake_test_token")
+                    .get(endpoint(&urls::posts::list()))
+                    .header("Authorization", authorization())
                     .send()
                     .await;
                 // Check network status
@@ -257,8 +272,7 @@ proptest! {
             }
 
             // Add statistics
-            statistics().write().unwrap().append(measuremnt);
-            statistics().write().unwrap().report();
+            drop(measuremnt);
 
         });
     }
@@ -0,0 +1,94 @@
+use chrono::{Timelike, Utc};
+use percom_model::urls;
+use prost::Message;
+use reqwest::{Client, StatusCode, header};
+
+use crate::{
+    scheme::posts::{Post, PostInput, protobuf},
+    tests::{authorization, endpoint},
+};
+
+// Creates, updates and lists a post with protobuf bodies and checks that JSON is still served by
+// default for the same post.
+#[tokio::test]
+async fn protobuf_roundtrip() {
+    let client = Client::new();
+    let input = PostInput {
+        author: "protobuf".to_owned(),
+        // Protobuf timestamps keep nanoseconds, but the providers may store microseconds only
+        date: Utc::now().with_nanosecond(0).unwrap().fixed_offset(),
+        content: "encoded".to_owned(),
+        publish_at: None,
+    };
+    let response = client
+        .post(endpoint(&urls::posts::list()))
+        .header("Authorization", authorization())
+        .header(header::CONTENT_TYPE, protobuf::CONTENT_TYPE)
+        .header(header::ACCEPT, protobuf::CONTENT_TYPE)
+        .body(protobuf::PostInput::from(&input).encode_to_vec())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        protobuf::CONTENT_TYPE
+    );
+    let created =
+        Post::try_from(protobuf::Post::decode(response.bytes().await.unwrap()).unwrap()).unwrap();
+    assert_eq!(created.author, input.author);
+    assert_eq!(created.content, input.content);
+    assert_eq!(created.date, input.date);
+    let url = endpoint(&urls::posts::by_id(&created.id));
+
+    let updated = client
+        .put(&url)
+        .header("Authorization", authorization())
+        .header(header::CONTENT_TYPE, protobuf::CONTENT_TYPE)
+        .body(
+            protobuf::PostInput::from(&PostInput {
+                content: "re-encoded".to_owned(),
+                ..input.clone()
+            })
+            .encode_to_vec(),
+        )
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(updated.status(), StatusCode::OK);
+    // Protobuf request, but no protobuf in `Accept`: the response is JSON
+    let updated: Post = updated.json().await.unwrap();
+    assert_eq!(updated.content, "re-encoded");
+
+    let list = client
+        .get(endpoint(&urls::posts::list()))
+        .header(
+            header::ACCEPT,
+            "application/x-protobuf, application/json;q=0.5",
+        )
+        .send()
+        .await
+        .unwrap()
+        .bytes()
+        .await
+        .unwrap();
+    let list = protobuf::PostList::decode(list).unwrap();
+    assert!(list.posts.iter().any(|post| post.id == created.id));
+
+    let invalid = client
+        .put(&url)
+        .header("Authorization", authorization())
+        .header(header::CONTENT_TYPE, protobuf::CONTENT_TYPE)
+        .body(vec![0xff, 0xff, 0xff])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(invalid.status(), StatusCode::BAD_REQUEST);
+
+    client
+        .delete(&url)
+        .header("Authorization", authorization())
+        .send()
+        .await
+        .unwrap();
+}
@@ -0,0 +1,61 @@
+use chrono::{Duration, Utc};
+use percom_model::urls;
+use reqwest::Client;
+use std::time;
+
+use crate::{
+    scheme::posts::{Post, PostInput, PostStatus},
+    tests::{authorization, endpoint},
+};
+
+async fn list(client: &Client) -> Vec<Post> {
+    client
+        .get(endpoint(&urls::posts::list()))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap()
+}
+
+// Creates a post scheduled shortly in the future and checks that it stays hidden from `GET /posts`
+// until the scheduler publishes it.
+#[tokio::test]
+async fn scheduled_publication() {
+    let client = Client::new();
+    let now = Utc::now();
+    let post: Post = client
+        .post(endpoint(&urls::posts::list()))
+        .header("Authorization", authorization())
+        .json(&PostInput {
+            author: "scheduler".to_owned(),
+            date: now.fixed_offset(),
+            content: "from the future".to_owned(),
+            publish_at: Some(now + Duration::milliseconds(1500)),
+        })
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(post.status, PostStatus::Scheduled);
+    assert!(!list(&client).await.iter().any(|p| p.id == post.id));
+
+    // Default scheduler interval is one second
+    tokio::time::sleep(time::Duration::from_millis(3000)).await;
+    let published = list(&client)
+        .await
+        .into_iter()
+        .find(|p| p.id == post.id)
+        .expect("post is published");
+    assert_eq!(published.status, PostStatus::Published);
+
+    client
+        .delete(endpoint(&urls::posts::by_id(&post.id)))
+        .header("Authorization", authorization())
+        .send()
+        .await
+        .unwrap();
+}
@@ -0,0 +1,23 @@
+use percom_model::urls;
+use reqwest::{Client, StatusCode};
+use serde_json::Value;
+
+use crate::tests::endpoint;
+
+// Sends headers over the default limit of 16 KiB and checks that the request is refused with
+// `431`. Stalled bodies aren't tested here, as they take the whole read timeout.
+#[tokio::test]
+async fn oversized_headers() {
+    let response = Client::new()
+        .get(endpoint(&urls::posts::list()))
+        .header("X-Padding", "a".repeat(20 * 1024))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(
+        response.status(),
+        StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE
+    );
+    let problem: Value = response.json().await.unwrap();
+    assert_eq!(problem["code"], "request_header_fields_too_large");
+}
@@ -2,33 +2,61 @@ use std::{
     env,
     fs::File,
     io::Write,
-    sync::{OnceLock, RwLock},
+    sync::{OnceLock, PoisonError, RwLock},
+    time::Instant,
 };
 
 use chrono::Utc;
+use reqwest::Response;
 
 use crate::envs;
 
+/// Timing of a single request, split at the moment the response headers arrived.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Timing {
+    /// Time to first byte: from sending the request until the response headers are received
+    /// (in nanoseconds). Dominated by server processing.
+    pub ttfb: u128,
+
+    /// Time spent reading the response body after the headers (in nanoseconds). Dominated by
+    /// payload size and transfer.
+    pub body: u128,
+}
+
+/// Reads the whole body of `response` to a request sent at `start` and returns it with the timing.
+///
+/// Must be called right after the response was received, as the time until the call counts as
+/// [`Timing::ttfb`]. Deserialization of the returned body isn't measured.
+pub async fn read_timed(start: Instant, response: Response) -> (Timing, Vec<u8>) {
+    let ttfb = start.elapsed().as_nanos();
+    let body = response.bytes().await.unwrap().to_vec();
+    let timing = Timing {
+        ttfb,
+        body: start.elapsed().as_nanos() - ttfb,
+    };
+    (timing, body)
+}
+
 /// Enum representing measured response times for different types of operations in the post lifecycle.
 ///
-/// Each variant holds timing data (in nanoseconds) for a particular kind of API request.
+/// Each variant holds timing data for a particular kind of API request.
 /// The data is collected during the test run and used to compute aggregated performance statistics.
 #[allow(clippy::enum_variant_names)]
 pub enum TimeMeasument {
-    /// Response times for all `POST /posts` operations (in nanoseconds).
-    CreatePost(Vec<u128>),
+    /// Response times for all `POST /posts` operations.
+    CreatePost(Vec<Timing>),
 
     /// Response times for all `GET /posts/{id}` operations.
-    GetPost(Vec<u128>),
+    GetPost(Vec<Timing>),
 
     /// Response times for all `PUT /posts/{id}` operations.
-    UpdatePost(Vec<u128>),
+    UpdatePost(Vec<Timing>),
 
     /// Response time for a single `GET /posts` request (list all posts).
-    ListPost(u128),
+    ListPost(Timing),
 
     /// Response times for all `DELETE /posts/{id}` operations.
-    DeletePost(Vec<u128>),
+    DeletePost(Vec<Timing>),
 }
 
 /// Aggregated metrics for a single operation type (e.g., Create, Update, Delete).
@@ -38,8 +66,10 @@ pub enum TimeMeasument {
 #[derive(Default)]
 pub struct TestCase {
     count: usize,
-    total_time: u128,
-    avg_time: u128,
+    total_ttfb: u128,
+    total_body: u128,
+    avg_ttfb: u128,
+    avg_body: u128,
     alias: String,
 }
 
@@ -56,20 +86,24 @@ impl TestCase {
     }
 
     /// Adds multiple measurements to the test case.
-    pub fn update_from_times(&mut self, times: &[u128]) {
-        self.count += times.len();
-        self.total_time += times.iter().sum::<u128>();
+    pub fn update_from_times(&mut self, times: &[Timing]) {
+        times.iter().for_each(|time| self.update_from_time(time));
     }
 
     /// Adds a single measurement to the test case.
-    pub fn update_from_time(&mut self, time: &u128) {
+    pub fn update_from_time(&mut self, time: &Timing) {
         self.count += 1;
-        self.total_time += time;
+        self.total_ttfb += time.ttfb;
+        self.total_body += time.body;
     }
 
-    /// Calculates the average latency (`avg_time`) based on the total and count.
+    /// Calculates the average latencies based on the totals and count.
+    ///
+    /// Operations without measurements (e.g. not reached by an aborted test case) average to `0`.
     pub fn calc(&mut self) {
-        self.avg_time = self.total_time / self.count as u128;
+        let avg = |total: u128| total.checked_div(self.count as u128).unwrap_or_default();
+        self.avg_ttfb = avg(self.total_ttfb);
+        self.avg_body = avg(self.total_body);
     }
 }
 
@@ -91,7 +125,8 @@ impl Statistics {
 
     /// Prints a performance report, showing total and average latencies per operation.
     ///
-    /// Latency is printed in both nanoseconds and milliseconds for easier interpretation.
+    /// Besides the full latency, the averages of time to first byte (server processing) and body
+    /// reading (payload transfer) are printed separately.
     pub fn report(&mut self) {
         let mut create_post = TestCase::new("CreatePost".to_owned());
         let mut get_post = TestCase::new("GetPost".to_owned());
@@ -124,10 +159,10 @@ impl Statistics {
         delete_post.calc();
         println!("\n=== Performance Report ===\n");
         println!(
-            "{:<15} | {:>10} | {:>12} | {:>10} | {:>12} | {:>10}",
-            "Operation", "Count", "Total (ns)", "Avg (ns)", "Total (ms)", "Avg (ms)"
+            "{:<15} | {:>10} | {:>12} | {:>10} | {:>14} | {:>14}",
+            "Operation", "Count", "Total (ms)", "Avg (ms)", "Avg TTFB (ms)", "Avg body (ms)"
         );
-        println!("{}", "-".repeat(80));
+        println!("{}", "-".repeat(90));
 
         for tc in [
             &create_post,
@@ -136,24 +171,39 @@ impl Statistics {
             &list_post,
             &delete_post,
         ] {
-            let total_ms = tc.total_time as f64 / 1_000_000.0;
-            let avg_ms = tc.avg_time as f64 / 1_000_000.0;
-
             println!(
-                "{:<15} | {:>10} | {:>12} | {:>10} | {:>12.2} | {:>10.2}",
-                tc.alias, tc.count, tc.total_time, tc.avg_time, total_ms, avg_ms
+                "{:<15} | {:>10} | {:>12.2} | {:>10.2} | {:>14.2} | {:>14.2}",
+                tc.alias,
+                tc.count,
+                ms(tc.total_ttfb + tc.total_body),
+                ms(tc.avg_ttfb + tc.avg_body),
+                ms(tc.avg_ttfb),
+                ms(tc.avg_body)
             );
         }
         println!("\n");
-        self.write(vec![
-            create_post.avg_time as f64 / 1_000_000.0,
-            get_post.avg_time as f64 / 1_000_000.0,
-            update_post.avg_time as f64 / 1_000_000.0,
-            list_post.avg_time as f64 / 1_000_000.0,
-            delete_post.avg_time as f64 / 1_000_000.0,
-        ]);
+        let cases = [
+            &create_post,
+            &get_post,
+            &update_post,
+            &list_post,
+            &delete_post,
+        ];
+        // The first five columns keep their historical meaning (time until the response arrived),
+        // the body reading times are appended after them
+        self.write(
+            cases
+                .iter()
+                .map(|tc| ms(tc.avg_ttfb))
+                .chain(cases.iter().map(|tc| ms(tc.avg_body)))
+                .collect(),
+        );
     }
 
+    /// Appends a CSV row to the stat data file, creating the file on first use.
+    ///
+    /// Doesn't panic on I/O errors (they're printed instead), as it also runs while unwinding from
+    /// a failed test case (see [`Recorder`]).
     fn write(&mut self, row: Vec<f64>) {
         if !envs::vars::write_test_data() {
             return;
@@ -161,26 +211,59 @@ impl Statistics {
         let mut file = if let Some(file) = self.file.take() {
             file
         } else {
-            let filename =
-                env::temp_dir().join(format!("{}.csv", Utc::now().timestamp().to_string()));
-            File::create(filename).expect("Stat data file has been created")
+            let filename = env::temp_dir().join(format!("{}.csv", Utc::now().timestamp()));
+            match File::create(&filename) {
+                Ok(file) => file,
+                Err(err) => {
+                    eprintln!("Fail to create {}: {err}", filename.display());
+                    return;
+                }
+            }
         };
-        file.write_all(
-            format!(
-                "{}\n",
-                row.into_iter()
-                    .map(|v| v.to_string())
-                    .collect::<Vec<_>>()
-                    .join(",")
-            )
-            .as_bytes(),
-        )
-        .expect("Stat data hs been written");
-        file.flush().expect("Stat data hs been flushed");
+        let line = format!(
+            "{}\n",
+            row.into_iter()
+                .map(|v| v.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        );
+        if let Err(err) = file.write_all(line.as_bytes()).and_then(|_| file.flush()) {
+            eprintln!("Fail to write stat data: {err}");
+        }
         self.file = Some(file);
     }
 }
 
+/// Converts nanoseconds to milliseconds.
+fn ms(nanos: u128) -> f64 {
+    nanos as f64 / 1_000_000.0
+}
+
+/// Collects the measurements of a single test case and hands them over to [`statistics`] on drop.
+///
+/// Being a drop guard, it also flushes when the test case panics (e.g. on a failed assertion), so
+/// the measurements taken before the failure still make it into the report and the CSV file.
+#[derive(Default)]
+pub struct Recorder {
+    times: Vec<TimeMeasument>,
+}
+
+impl Recorder {
+    /// Adds a batch of measurements of the current test case.
+    pub fn push(&mut self, times: TimeMeasument) {
+        self.times.push(times);
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        // A panic while unwinding aborts the process, so don't fail on a poisoned lock
+        let mut statistics = statistics().write().unwrap_or_else(PoisonError::into_inner);
+        statistics.append(std::mem::take(&mut self.times));
+        statistics.report();
+    }
+}
+
 /// Returns a singleton instance of the shared `Statistics` object.
 ///
 /// Internally uses a `OnceLock<RwLock<Statistics>>` to provide thread-safe global access.
@@ -0,0 +1,54 @@
+use percom_model::urls;
+use reqwest::{Client, StatusCode};
+use serde_json::{Value, json};
+
+use crate::tests::{authorization, endpoint};
+
+// Creates a post dated at a non-UTC offset, checks that the offset is echoed as written (truncated
+// to the default precision, microseconds) and that `?tz=` renders the date at another offset, and
+// that dates without an offset are rejected.
+#[tokio::test]
+async fn time_zones() {
+    let client = Client::new();
+    let url = endpoint(&urls::posts::list());
+    let create = |date: &str| {
+        client
+            .post(&url)
+            .header("Authorization", authorization())
+            .json(&json!({ "author": "zoned", "date": date, "content": "zoned" }))
+            .send()
+    };
+
+    let response = create("2024-05-01T12:00:00.123456789+02:00").await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let post: Value = response.json().await.unwrap();
+    assert_eq!(post["date"], "2024-05-01T12:00:00.123456+02:00");
+    let id = post["id"].as_str().unwrap();
+
+    let get = |tz: &str| client.get(format!("{url}/{id}?tz={tz}")).send();
+    let post: Value = get("-05:00").await.unwrap().json().await.unwrap();
+    assert_eq!(post["date"], "2024-05-01T05:00:00.123456-05:00");
+    let post: Value = get("%2B09:30").await.unwrap().json().await.unwrap();
+    assert_eq!(post["date"], "2024-05-01T19:30:00.123456+09:30");
+    let post: Value = get("Z").await.unwrap().json().await.unwrap();
+    assert_eq!(post["date"], "2024-05-01T10:00:00.123456Z");
+    let named = get("Europe/Berlin").await.unwrap();
+    assert_eq!(named.status(), StatusCode::BAD_REQUEST);
+
+    for date in ["2024-05-01T12:00:00", "2024-05-01", "yesterday"] {
+        let response = create(date).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{date}");
+        let problem: Value = response.json().await.unwrap();
+        assert!(
+            problem["detail"].as_str().unwrap().contains("RFC 3339"),
+            "{problem}"
+        );
+    }
+
+    client
+        .delete(format!("{url}/{id}"))
+        .header("Authorization", authorization())
+        .send()
+        .await
+        .unwrap();
+}
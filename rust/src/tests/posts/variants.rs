@@ -0,0 +1,134 @@
+use percom_model::urls;
+use reqwest::{Client, StatusCode};
+use serde_json::{Value, json};
+
+use crate::{
+    scheme::posts::Post,
+    tests::{authorization, endpoint},
+};
+
+// Lists the posts with each variant of the `list_posts` experiment, checking that the variant is
+// echoed, that both bodies are complete arrays containing a fresh post, and that the responses are
+// counted per variant in the metrics, their bodies encoded on the offload pool.
+#[tokio::test]
+async fn list_variants() {
+    let client = Client::new();
+    let post: Value = client
+        .post(endpoint(&urls::posts::list()))
+        .header("Authorization", authorization())
+        .json(&json!({ "author": "variant", "date": "2024-05-01T12:00:00Z", "content": "variant" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let id = post["id"].as_str().unwrap();
+
+    for variant in ["clone", "streaming"] {
+        let response = client
+            .get(endpoint(&urls::posts::list()))
+            .header("X-Variant", variant)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["x-variant"], variant);
+        let posts: Vec<Post> = response.json().await.unwrap();
+        assert!(posts.iter().any(|post| post.id == id), "{variant}");
+
+        let metrics: Value = client
+            .get(endpoint(&urls::metrics::get()))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let stats = &metrics["experiments"]["list_posts"][variant];
+        assert!(stats["requests"].as_u64().unwrap() >= 1, "{metrics}");
+        assert!(
+            metrics["offload"]["completed"].as_u64().unwrap() >= 1,
+            "{metrics}"
+        );
+    }
+
+    client
+        .delete(endpoint(&urls::posts::by_id(id)))
+        .header("Authorization", authorization())
+        .send()
+        .await
+        .unwrap();
+}
+
+// Reads a post with each variant of the `get_post` experiment, checking that the variant is
+// echoed, that both send the same post and that they're counted per variant in the metrics; a
+// response at another offset isn't part of the experiment.
+#[tokio::test]
+async fn get_variants() {
+    let client = Client::new();
+    let post: Value = client
+        .post(endpoint(&urls::posts::list()))
+        .header("Authorization", authorization())
+        .json(
+            &json!({ "author": "variant", "date": "2024-05-01T12:00:00+02:00", "content": "raw" }),
+        )
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let id = post["id"].as_str().unwrap();
+
+    for variant in ["decode", "raw"] {
+        let response = client
+            .get(endpoint(&urls::posts::by_id(id)))
+            .header("X-Variant", variant)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["x-variant"], variant);
+        assert_eq!(
+            response.headers()["content-type"],
+            "application/json",
+            "{variant}"
+        );
+        assert_eq!(response.json::<Value>().await.unwrap(), post, "{variant}");
+
+        let metrics: Value = client
+            .get(endpoint(&urls::metrics::get()))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let stats = &metrics["experiments"]["get_post"][variant];
+        assert!(stats["requests"].as_u64().unwrap() >= 1, "{metrics}");
+    }
+
+    let response = client
+        .get(format!("{}?tz=Z", endpoint(&urls::posts::by_id(id))))
+        .header("X-Variant", "raw")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().get("x-variant").is_none());
+
+    client
+        .delete(endpoint(&urls::posts::by_id(id)))
+        .header("Authorization", authorization())
+        .send()
+        .await
+        .unwrap();
+    let response = client
+        .get(endpoint(&urls::posts::by_id(id)))
+        .header("X-Variant", "raw")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
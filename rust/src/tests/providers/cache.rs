@@ -0,0 +1,45 @@
+use crate::scheme::{
+    posts::{CacheProvider, Codec, DummyProvider, PostsProvider, to_json},
+    provider::ProviderStats,
+};
+
+use super::{compression, input};
+
+// Reads a post through the cache before and after it's updated and deleted, checking that
+// repeated reads are hits whichever way they're read, that the entity tag follows the version of
+// the post, and that no stale version is served.
+#[test]
+fn tagged_reads() {
+    let (compression, _) = compression(Codec::None);
+    let cache = CacheProvider::wrapped(DummyProvider::wrapped(compression), 16);
+    let post = cache.create(input("cache", "first")).unwrap();
+    let counts = || {
+        let stats = cache.stats();
+        (
+            stats["hits"].as_u64().unwrap(),
+            stats["misses"].as_u64().unwrap(),
+        )
+    };
+
+    let first = cache.get_tagged(&post.id, true).unwrap().unwrap();
+    assert_eq!(first.json, to_json(&post).unwrap());
+    assert_eq!(
+        cache.get_tagged(&post.id, false).unwrap(),
+        Some(first.clone())
+    );
+    assert_eq!(cache.get_json(&post.id).unwrap(), Some(first.json.clone()));
+    assert_eq!(counts(), (2, 1));
+
+    let updated = cache
+        .update(&post.id, input("cache", "second"))
+        .unwrap()
+        .unwrap();
+    let second = cache.get_tagged(&post.id, false).unwrap().unwrap();
+    assert_eq!(second.json, to_json(&updated).unwrap());
+    assert_ne!(second.etag, first.etag);
+    assert_eq!(counts(), (2, 2));
+
+    assert!(cache.delete(&post.id).unwrap());
+    assert_eq!(cache.get_tagged(&post.id, false).unwrap(), None);
+    assert_eq!(cache.stats()["posts"], 0);
+}
@@ -0,0 +1,150 @@
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use serde_json::Value;
+use std::{
+    pin::pin,
+    sync::{Arc, Mutex, OnceLock},
+    thread,
+    time::Duration,
+};
+
+use super::{compression, input};
+use crate::scheme::{
+    posts::{
+        ChangesProvider, Codec, DummyProvider, Post, PostInput, PostsProvider, PostsQuery,
+        QueryPlan,
+    },
+    provider::{Provider, ProviderError, ProviderStats},
+    replication::{Change, ChangeEvent, Changes},
+};
+
+/// Write started by [`Stalling`] while an update is stored but not returned yet.
+type Hook = Box<dyn FnOnce() + Send>;
+
+/// Provider running a hook once it has stored an update, before returning it, as a write racing
+/// the update would.
+struct Stalling {
+    inner: Arc<DummyProvider>,
+    hook: Mutex<Option<Hook>>,
+}
+
+impl Provider for Stalling {}
+
+impl ProviderStats for Stalling {
+    fn stats(&self) -> Value {
+        self.inner.stats()
+    }
+}
+
+impl PostsProvider for Stalling {
+    fn get_all(&self) -> Result<Vec<Post>, ProviderError> {
+        self.inner.get_all()
+    }
+
+    fn get(&self, id: &str) -> Result<Option<Post>, ProviderError> {
+        self.inner.get(id)
+    }
+
+    fn get_json(&self, id: &str) -> Result<Option<Vec<u8>>, ProviderError> {
+        self.inner.get_json(id)
+    }
+
+    fn create(&self, input: PostInput) -> Result<Post, ProviderError> {
+        self.inner.create(input)
+    }
+
+    fn update(&self, id: &str, input: PostInput) -> Result<Option<Post>, ProviderError> {
+        let post = self.inner.update(id, input)?;
+        if let Some(hook) = self.hook.lock().unwrap().take() {
+            hook();
+        }
+        Ok(post)
+    }
+
+    fn delete(&self, id: &str) -> Result<bool, ProviderError> {
+        self.inner.delete(id)
+    }
+
+    fn get_by_authors(
+        &self,
+        authors: &[String],
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<Post>, usize), ProviderError> {
+        self.inner.get_by_authors(authors, offset, limit)
+    }
+
+    fn get_by_date(&self, prefix: &str) -> Result<Vec<Post>, ProviderError> {
+        self.inner.get_by_date(prefix)
+    }
+
+    fn set_author(&self, ids: &[String], author: &str) -> Result<Vec<String>, ProviderError> {
+        self.inner.set_author(ids, author)
+    }
+
+    fn publish_due(&self, now: DateTime<Utc>) -> Result<Vec<Post>, ProviderError> {
+        self.inner.publish_due(now)
+    }
+
+    fn import(&self, posts: Vec<Post>) -> Result<usize, ProviderError> {
+        self.inner.import(posts)
+    }
+
+    fn explain(&self, query: &PostsQuery) -> Result<QueryPlan, ProviderError> {
+        self.inner.explain(query)
+    }
+}
+
+// Deletes a post from another thread while its update is stored but not published yet, checking
+// that the delete waits for the update, and that the changes are published in the order they were
+// stored, so a replica following them doesn't bring the deleted post back.
+#[actix_web::test]
+async fn update_racing_delete() {
+    let inner = DummyProvider::wrapped(compression(Codec::None).0);
+    let post = inner.create(input("changes", "created")).unwrap();
+    let id = post.id.clone();
+    let provider: Arc<OnceLock<Arc<ChangesProvider>>> = Arc::default();
+    let deletion: Arc<Mutex<Option<thread::JoinHandle<bool>>>> = Arc::default();
+    let hook: Hook = {
+        let (provider, deletion, id) = (provider.clone(), deletion.clone(), id.clone());
+        Box::new(move || {
+            let provider = provider.get().unwrap().clone();
+            let handle = thread::spawn(move || provider.delete(&id).unwrap());
+            *deletion.lock().unwrap() = Some(handle);
+            // Leaves the delete time to get ahead of the update if nothing holds it back
+            thread::sleep(Duration::from_millis(100));
+        })
+    };
+    let stalling = Arc::new(Stalling {
+        inner: inner.clone(),
+        hook: Mutex::new(Some(hook)),
+    });
+    let changes = Arc::new(Changes::default());
+    let _ = provider.set(ChangesProvider::wrapped(stalling, changes.clone()));
+    let mut stream = pin!(changes.subscribe());
+
+    provider
+        .get()
+        .unwrap()
+        .update(&id, input("changes", "updated"))
+        .unwrap()
+        .unwrap();
+    let deleted = deletion.lock().unwrap().take().unwrap().join().unwrap();
+    assert!(deleted);
+    assert!(inner.get(&id).unwrap().is_none());
+
+    let mut published = Vec::new();
+    while published.len() < 2 {
+        let event = stream.next().await.unwrap().unwrap();
+        let event = std::str::from_utf8(&event).unwrap();
+        if let Some(data) = event.strip_prefix("event: change\ndata: ") {
+            published.push(serde_json::from_str::<ChangeEvent>(data.trim_end()).unwrap());
+        }
+    }
+    assert!(published[0].seq < published[1].seq);
+    assert!(matches!(
+        &published[0].change,
+        Change::Upsert { post } if post.id == id && post.content == "updated"
+    ));
+    assert!(matches!(&published[1].change, Change::Delete { id: deleted } if *deleted == id));
+}
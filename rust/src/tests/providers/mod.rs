@@ -0,0 +1,36 @@
+//! Tests of posts providers used directly, without the server under test.
+
+mod cache;
+mod changes;
+mod tiered;
+mod wal;
+
+use chrono::Utc;
+use std::{path::PathBuf, sync::Arc};
+use uuid::Uuid;
+
+use crate::{
+    scheme::posts::{Codec, Compression, PostInput},
+    state::Metrics,
+};
+
+/// Returns a path in the temporary directory no other test uses, named after `name`.
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("percom-{name}-{}", Uuid::new_v4()))
+}
+
+/// Returns a post input by `author` with `content`, dated now.
+fn input(author: &str, content: &str) -> PostInput {
+    PostInput {
+        author: author.to_owned(),
+        date: Utc::now().fixed_offset(),
+        content: content.to_owned(),
+        publish_at: None,
+    }
+}
+
+/// Returns a compression with `codec`, and the metrics it reports to.
+fn compression(codec: Codec) -> (Compression, Arc<Metrics>) {
+    let metrics = Arc::new(Metrics::default());
+    (Compression::new(codec, metrics.clone()), metrics)
+}
@@ -0,0 +1,127 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use serde_json::Value;
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use super::{compression, input};
+use crate::scheme::{
+    posts::{
+        Codec, DummyProvider, Post, PostInput, PostsProvider, PostsQuery, QueryPlan,
+        tiered::{Routing, TieredProvider},
+    },
+    provider::{Provider, ProviderError, ProviderStats},
+};
+
+/// Write run by [`Racing`] on the provider it wraps.
+type Hook = Box<dyn FnOnce(&DummyProvider) + Send>;
+
+/// Hot tier running a hook once it has listed its posts, as a write racing a demotion would.
+struct Racing {
+    inner: Arc<DummyProvider>,
+    hook: Mutex<Option<Hook>>,
+}
+
+impl Provider for Racing {}
+
+impl ProviderStats for Racing {
+    fn stats(&self) -> Value {
+        self.inner.stats()
+    }
+}
+
+impl PostsProvider for Racing {
+    fn get_all(&self) -> Result<Vec<Post>, ProviderError> {
+        let posts = self.inner.get_all()?;
+        if let Some(hook) = self.hook.lock().unwrap().take() {
+            hook(&self.inner);
+        }
+        Ok(posts)
+    }
+
+    fn get(&self, id: &str) -> Result<Option<Post>, ProviderError> {
+        self.inner.get(id)
+    }
+
+    fn get_json(&self, id: &str) -> Result<Option<Vec<u8>>, ProviderError> {
+        self.inner.get_json(id)
+    }
+
+    fn create(&self, input: PostInput) -> Result<Post, ProviderError> {
+        self.inner.create(input)
+    }
+
+    fn update(&self, id: &str, input: PostInput) -> Result<Option<Post>, ProviderError> {
+        self.inner.update(id, input)
+    }
+
+    fn delete(&self, id: &str) -> Result<bool, ProviderError> {
+        self.inner.delete(id)
+    }
+
+    fn get_by_authors(
+        &self,
+        authors: &[String],
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<Post>, usize), ProviderError> {
+        self.inner.get_by_authors(authors, offset, limit)
+    }
+
+    fn get_by_date(&self, prefix: &str) -> Result<Vec<Post>, ProviderError> {
+        self.inner.get_by_date(prefix)
+    }
+
+    fn set_author(&self, ids: &[String], author: &str) -> Result<Vec<String>, ProviderError> {
+        self.inner.set_author(ids, author)
+    }
+
+    fn publish_due(&self, now: DateTime<Utc>) -> Result<Vec<Post>, ProviderError> {
+        self.inner.publish_due(now)
+    }
+
+    fn import(&self, posts: Vec<Post>) -> Result<usize, ProviderError> {
+        self.inner.import(posts)
+    }
+
+    fn explain(&self, query: &PostsQuery) -> Result<QueryPlan, ProviderError> {
+        self.inner.explain(query)
+    }
+}
+
+// Updates an aged hot post after a demotion listed it but before it moved it, checking that the
+// update moves to the cold tier with the post instead of being lost, and that the post is stored
+// once.
+#[test]
+fn demotion_racing_update() {
+    let aged = || PostInput {
+        date: (Utc::now() - TimeDelta::hours(1)).fixed_offset(),
+        ..input("tiered", "listed")
+    };
+    let hot = DummyProvider::wrapped(compression(Codec::None).0);
+    let cold = DummyProvider::wrapped(compression(Codec::None).0);
+    let post = hot.create(aged()).unwrap();
+    let id = post.id.clone();
+    let racing = Arc::new(Racing {
+        inner: hot.clone(),
+        hook: Mutex::new(Some(Box::new(move |hot: &DummyProvider| {
+            let updated = PostInput {
+                content: "updated".to_owned(),
+                ..aged()
+            };
+            hot.update(&id, updated).unwrap().unwrap();
+        }))),
+    });
+    let routing = Routing::Age {
+        max_age: Duration::from_secs(60),
+    };
+    let tiered = TieredProvider::wrapped(racing, cold.clone(), routing);
+
+    assert_eq!(tiered.demote().unwrap(), 1);
+    assert!(hot.get_all().unwrap().is_empty());
+    let stored = cold.get_all().unwrap();
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].id, post.id);
+    assert_eq!(stored[0].content, "updated");
+}
@@ -0,0 +1,93 @@
+use std::{fs, io::Write, time::Duration};
+
+use super::{compression, input, temp_path};
+use crate::scheme::{
+    posts::{Codec, PostsProvider, RetryProvider, WalProvider},
+    retry::{Retrier, RetryPolicy},
+};
+
+// Tears the last record of a log as a crash would, checking that the torn bytes are cut off on
+// replay, so a record appended afterwards survives the next restart.
+#[test]
+fn torn_tail() {
+    let path = temp_path("wal").with_extension("jsonl");
+    let open = || {
+        let (compression, metrics) = compression(Codec::None);
+        WalProvider::open(&path, compression, metrics, 1).unwrap()
+    };
+    let first = open().create(input("wal", "before the crash")).unwrap();
+    let intact = fs::metadata(&path).unwrap().len();
+    let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+    file.write_all(br#"{"op":"put","post":{"id":"torn"#)
+        .unwrap();
+    drop(file);
+
+    let provider = open();
+    assert_eq!(fs::metadata(&path).unwrap().len(), intact);
+    let second = provider.create(input("wal", "after the crash")).unwrap();
+    drop(provider);
+
+    let provider = open();
+    let mut ids: Vec<String> = provider
+        .get_all()
+        .unwrap()
+        .into_iter()
+        .map(|post| post.id)
+        .collect();
+    ids.sort();
+    let mut expected = vec![first.id, second.id];
+    expected.sort();
+    assert_eq!(ids, expected);
+    fs::remove_file(&path).unwrap();
+}
+
+// Fails the first write of a create retried over the WAL, checking that the retry doesn't leave a
+// duplicate record in the log.
+#[test]
+fn retried_create() {
+    let path = temp_path("wal").with_extension("jsonl");
+    let (compression, metrics) = compression(Codec::None);
+    let wal = WalProvider::open(&path, compression, metrics.clone(), 1).unwrap();
+    let policy = RetryPolicy {
+        max_retries: 2,
+        backoff: Duration::from_millis(1),
+        max_backoff: Duration::from_millis(1),
+        budget: 1.0,
+    };
+    let retry = RetryProvider::wrapped(wal.clone(), Retrier::new("wal", policy, metrics));
+    wal.fail_writes(1);
+    let post = retry.create(input("wal", "retried")).unwrap();
+
+    let log = fs::read_to_string(&path).unwrap();
+    assert_eq!(log.lines().count(), 1);
+    assert!(log.contains(&post.id));
+    fs::remove_file(&path).unwrap();
+}
+
+// Fails the flush of a full batch in write-behind mode, checking that the mutation which filled it
+// fails without a trace, while the records of the answered mutations before it are written once
+// by the next flush.
+#[test]
+fn failed_batch() {
+    let path = temp_path("wal").with_extension("jsonl");
+    let (compression, metrics) = compression(Codec::None);
+    let wal = WalProvider::open(&path, compression, metrics, 3).unwrap();
+    let answered = [
+        wal.create(input("wal", "first")).unwrap(),
+        wal.create(input("wal", "second")).unwrap(),
+    ];
+    wal.fail_writes(1);
+    assert!(wal.create(input("wal", "failed")).is_err());
+    assert_eq!(wal.get_all().unwrap().len(), 2);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "");
+
+    wal.fail_writes(1);
+    assert!(wal.flush().is_err());
+    wal.flush().unwrap();
+    wal.flush().unwrap();
+    let log = fs::read_to_string(&path).unwrap();
+    assert_eq!(log.lines().count(), 2);
+    assert!(answered.iter().all(|post| log.contains(&post.id)));
+    assert!(!log.contains("failed"));
+    fs::remove_file(&path).unwrap();
+}
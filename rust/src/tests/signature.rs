@@ -0,0 +1,113 @@
+use actix_web::{
+    App,
+    http::StatusCode,
+    middleware::{Condition, from_fn},
+    test,
+    web::{self, Bytes},
+};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::time::Duration;
+
+use crate::{
+    middleware::{
+        normalize::{Slashes, keep_original_path, lowercase_scope},
+        signature::{CLIENT_HEADER, SIGNATURE_HEADER, Signing, TIMESTAMP_HEADER, verify_signature},
+    },
+    tests::state,
+};
+
+/// Client and secret the server of these tests knows.
+const CLIENT: &str = "client";
+const SECRET: &str = "secret";
+
+/// Request signed by the client, which may send something else than it signed.
+struct Signed<'a> {
+    /// Path and query, as signed.
+    path: &'a str,
+
+    /// Body, as signed.
+    body: &'a str,
+
+    /// Seconds the signature is older than the request.
+    age: i64,
+}
+
+impl Signed<'_> {
+    /// Returns the signed request, with `path` and `body` instead of the signed ones.
+    fn request(&self, path: &str, body: &str) -> test::TestRequest {
+        let timestamp = (Utc::now().timestamp() - self.age).to_string();
+        let mut mac = Hmac::<Sha256>::new_from_slice(SECRET.as_bytes()).unwrap();
+        mac.update(&Signing::message(
+            &timestamp,
+            "POST",
+            self.path,
+            self.body.as_bytes(),
+        ));
+        test::TestRequest::post()
+            .uri(path)
+            .insert_header((CLIENT_HEADER, CLIENT))
+            .insert_header((TIMESTAMP_HEADER, timestamp))
+            .insert_header((SIGNATURE_HEADER, hex::encode(mac.finalize().into_bytes())))
+            .set_payload(body.to_owned())
+    }
+}
+
+// Sends requests signed right and wrong through the signature and normalization middlewares,
+// registered as the server does, checking that only the ones sent as signed, in time, pass: the
+// signature covers the path as sent, also when normalization rewrites it for routing.
+#[actix_web::test]
+async fn signatures() {
+    let mut state = state();
+    state.signing = Signing::from_spec(
+        &format!("{CLIENT}:{SECRET}"),
+        Duration::from_secs(300),
+        1024,
+    )
+    .unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(state))
+            .route("/posts", web::post().to(|body: Bytes| async move { body }))
+            .route(
+                "/posts/{id}",
+                web::post().to(|body: Bytes| async move { body }),
+            )
+            .wrap(from_fn(verify_signature))
+            .wrap(Slashes::Trim.middleware())
+            .wrap(Condition::new(true, from_fn(lowercase_scope)))
+            .wrap(from_fn(keep_original_path)),
+    )
+    .await;
+    let send = async |signed: Signed<'_>, path: &str, body: &str| {
+        let request = signed.request(path, body).to_request();
+        test::call_service(&app, request).await.status()
+    };
+    let signed = |path, body| Signed { path, body, age: 0 };
+
+    for path in [
+        "/posts",
+        "/posts?page=1",
+        "/posts/",
+        "/Posts/x",
+        "//posts//x/",
+    ] {
+        let status = send(signed(path, "content"), path, "content").await;
+        assert_eq!(status, StatusCode::OK, "{path}");
+    }
+    let status = send(signed("/posts", "content"), "/posts", "tampered").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "tampered body");
+    let status = send(signed("/posts/x", "content"), "/posts/y", "content").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "tampered path");
+    // Normalization makes both the same route, but the client signed the other one
+    let status = send(signed("/posts", "content"), "/posts/", "content").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "normalized path");
+    let stale = Signed {
+        path: "/posts",
+        body: "content",
+        age: 3600,
+    };
+    let status = send(stale, "/posts", "content").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "stale timestamp");
+}
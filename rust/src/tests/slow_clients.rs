@@ -0,0 +1,73 @@
+use actix_web::{
+    App, HttpResponse,
+    dev::Payload,
+    http::StatusCode,
+    middleware::from_fn,
+    test,
+    web::{self, Bytes},
+};
+use futures_util::{StreamExt, stream};
+use std::time::Duration;
+
+use crate::{middleware::slow_clients::guard_slow_clients, tests::state};
+
+/// Sends a body of `chunks` bytes, one every `interval`, with the given limits, and returns the
+/// status and the detail of the response.
+async fn upload(
+    read_timeout: Option<Duration>,
+    body_timeout: Option<Duration>,
+    chunks: usize,
+    interval: Duration,
+) -> (StatusCode, String) {
+    let mut state = state();
+    state.client_limits.read_timeout = read_timeout;
+    state.client_limits.body_timeout = body_timeout;
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(state))
+            .wrap(from_fn(guard_slow_clients))
+            .route(
+                "/upload",
+                web::post().to(|body: Bytes| async move { HttpResponse::Ok().body(body) }),
+            ),
+    )
+    .await;
+    let body = stream::iter(0..chunks).then(move |_| async move {
+        tokio::time::sleep(interval).await;
+        Ok(Bytes::from_static(b"x"))
+    });
+    let (request, _) = test::TestRequest::post()
+        .uri("/upload")
+        .to_request()
+        .replace_payload(Payload::from(body.boxed_local()));
+    match test::try_call_service(&app, request).await {
+        Ok(response) => (response.status(), String::new()),
+        Err(err) => {
+            let response = err.error_response();
+            let status = response.status();
+            let body = actix_web::body::to_bytes(response.into_body())
+                .await
+                .unwrap();
+            let problem: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            (status, problem["detail"].to_string())
+        }
+    }
+}
+
+// Sends bodies which stall, trickle or arrive in time, checking that only the first two are
+// answered with `408`, each with the detail of the limit it hit.
+#[actix_web::test]
+async fn body_timeouts() {
+    let ms = Duration::from_millis;
+    let (status, detail) = upload(Some(ms(50)), None, 2, ms(200)).await;
+    assert_eq!(status, StatusCode::REQUEST_TIMEOUT, "{detail}");
+    assert!(detail.contains("stalled for more than 50 ms"), "{detail}");
+
+    // Every chunk arrives well within the stall timeout, but the body never ends
+    let (status, detail) = upload(Some(ms(100)), Some(ms(150)), 20, ms(30)).await;
+    assert_eq!(status, StatusCode::REQUEST_TIMEOUT, "{detail}");
+    assert!(detail.contains("took more than 150 ms"), "{detail}");
+
+    let (status, _) = upload(Some(ms(100)), Some(ms(1000)), 3, ms(10)).await;
+    assert_eq!(status, StatusCode::OK);
+}
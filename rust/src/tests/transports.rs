@@ -0,0 +1,97 @@
+use percom_model::urls;
+use reqwest::{Client, StatusCode, Version};
+use serde_json::{Value, json};
+
+use crate::{
+    envs::vars::get_client_url,
+    tests::{authorization, endpoint},
+};
+
+/// Creates and deletes a post at `base` over `client`, checking the responses were sent with
+/// `version`; the post is read back over HTTP/1.1 in between, so both transports share the state.
+async fn round_trip(client: Client, base: &str, version: Version) {
+    let response = client
+        .post(format!("{base}{}", urls::posts::list()))
+        .version(version)
+        .header("Authorization", authorization())
+        .json(&json!({ "author": "transport", "date": "2024-05-01T12:00:00Z", "content": "h" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert_eq!(response.version(), version);
+    let post: Value = response.json().await.unwrap();
+    let id = post["id"].as_str().unwrap();
+
+    let response = Client::new()
+        .get(endpoint(&urls::posts::by_id(id)))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.json::<Value>().await.unwrap(), post);
+
+    let response = client
+        .delete(format!("{base}{}", urls::posts::by_id(id)))
+        .version(version)
+        .header("Authorization", authorization())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert_eq!(response.version(), version);
+}
+
+// Sends requests over every HTTP version the server reports at `GET /admin/runtime`: HTTP/2 with
+// prior knowledge (`RUST_SERVER_H2C`) and, in builds with the `http3` feature, HTTP/3 on the same
+// port over UDP (`RUST_SERVER_H3_ADDR`), where oversized bodies must be rejected too.
+#[tokio::test]
+async fn transports() {
+    let runtime: Value = Client::new()
+        .get(format!("http://{}/admin/runtime", get_client_url()))
+        .header("Authorization", authorization())
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let transports = runtime["transports"].as_array().unwrap();
+    assert!(transports.contains(&json!("http1")), "{runtime}");
+
+    round_trip(
+        Client::builder().http1_only().build().unwrap(),
+        &format!("http://{}", get_client_url()),
+        Version::HTTP_11,
+    )
+    .await;
+    if transports.contains(&json!("http2")) {
+        round_trip(
+            Client::builder().http2_prior_knowledge().build().unwrap(),
+            &format!("http://{}", get_client_url()),
+            Version::HTTP_2,
+        )
+        .await;
+    }
+    #[cfg(feature = "http3")]
+    if transports.contains(&json!("http3")) {
+        let client = Client::builder()
+            .use_rustls_tls()
+            .danger_accept_invalid_certs(true)
+            .http3_prior_knowledge()
+            .build()
+            .unwrap();
+        let base = format!("https://{}", get_client_url());
+        round_trip(client.clone(), &base, Version::HTTP_3).await;
+        // Bodies are read before the app sees them, so the size limit is checked while reading
+        let response = client
+            .post(format!("{base}{}", urls::posts::list()))
+            .version(Version::HTTP_3)
+            .header("Authorization", authorization())
+            .body(vec![b' '; crate::envs::vars::get_max_body_size() + 1])
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}
@@ -1 +1,289 @@
+use chrono::Utc;
+use image::{DynamicImage, ImageFormat};
+use percom_client::model::{PostInput, User, UserInput};
+use percom_model::urls;
+use reqwest::{
+    Method, StatusCode, Url,
+    header::{HOST, LOCATION},
+};
+use std::io::Cursor;
+use uuid::Uuid;
 
+use crate::{scheme::users::routes::DELETED_AUTHOR, tests::api};
+
+// Checks email and nickname uniqueness on create/update and the `GET /users?email=&nickname=`
+// lookup.
+#[tokio::test]
+async fn uniqueness_and_search() {
+    let api = api();
+    let email = format!("{}@example.com", Uuid::new_v4());
+    let nickname = Uuid::new_v4().to_string();
+    let other_nickname = format!("other-{}", Uuid::new_v4());
+
+    let user = api
+        .create_user(&UserInput {
+            nickname: nickname.clone(),
+            email: email.clone(),
+        })
+        .await
+        .unwrap();
+
+    // Same email with different case is a duplicate
+    let err = api
+        .create_user(&UserInput {
+            nickname: other_nickname.clone(),
+            email: email.to_uppercase(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.status(), Some(StatusCode::CONFLICT));
+
+    // So is the same nickname, as posts and follows name their author by it
+    let err = api
+        .create_user(&UserInput {
+            nickname: nickname.clone(),
+            email: format!("{}@example.com", Uuid::new_v4()),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.status(), Some(StatusCode::CONFLICT));
+
+    // Lookup by email and nickname
+    for (email, nickname) in [
+        (Some(email.to_uppercase()), None),
+        (None, Some(nickname.as_str())),
+        (Some(email.clone()), Some(nickname.as_str())),
+    ] {
+        let found = api.find_users(email.as_deref(), nickname).await.unwrap();
+        assert_eq!(found.len(), 1, "query: {email:?} {nickname:?}");
+        assert_eq!(found[0].id, user.id);
+    }
+    let found = api
+        .find_users(Some(&email), Some(&other_nickname))
+        .await
+        .unwrap();
+    assert!(found.is_empty());
+
+    // Updating another user to the taken email or nickname is rejected, keeping own ones is fine
+    let other = api
+        .create_user(&UserInput {
+            nickname: other_nickname.clone(),
+            email: format!("{}@example.com", Uuid::new_v4()),
+        })
+        .await
+        .unwrap();
+    for input in [
+        UserInput {
+            nickname: other_nickname.clone(),
+            email: email.clone(),
+        },
+        UserInput {
+            nickname: nickname.clone(),
+            email: other.email.clone(),
+        },
+    ] {
+        let err = api.update_user(&other.id, &input).await.unwrap_err();
+        assert_eq!(err.status(), Some(StatusCode::CONFLICT), "{input:?}");
+    }
+    let renamed_nickname = format!("renamed-{}", Uuid::new_v4());
+    let renamed = api
+        .update_user(
+            &user.id,
+            &UserInput {
+                nickname: renamed_nickname.clone(),
+                email,
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(renamed.nickname, renamed_nickname);
+
+    // The old nickname is free again
+    api.update_user(
+        &other.id,
+        &UserInput {
+            nickname,
+            email: other.email.clone(),
+        },
+    )
+    .await
+    .unwrap();
+}
+
+// Uploads a PNG avatar and checks that a downscaled PNG thumbnail is served back.
+#[tokio::test]
+async fn avatar_upload() {
+    let api = api();
+    let user = api
+        .create_user(&UserInput {
+            nickname: format!("avatar-{}", Uuid::new_v4()),
+            email: format!("{}@example.com", Uuid::new_v4()),
+        })
+        .await
+        .unwrap();
+    let path = urls::users::avatar(&user.id);
+
+    let mut upload = Cursor::new(Vec::new());
+    DynamicImage::new_rgb8(640, 320)
+        .write_to(&mut upload, ImageFormat::Png)
+        .unwrap();
+    let response = api
+        .send(
+            api.request(Method::PUT, &path)
+                .header("Content-Type", "image/png")
+                .body(upload.into_inner()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let response = api.send(api.anonymous(Method::GET, &path)).await.unwrap();
+    let thumbnail =
+        image::load_from_memory_with_format(&response.bytes().await.unwrap(), ImageFormat::Png)
+            .unwrap();
+    assert_eq!((thumbnail.width(), thumbnail.height()), (128, 64));
+
+    let err = api
+        .send(
+            api.request(Method::PUT, &path)
+                .header("Content-Type", "text/plain")
+                .body("not an image"),
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err.status(), Some(StatusCode::UNSUPPORTED_MEDIA_TYPE));
+}
+
+// Deletes an account and checks that the deletion cascades to posts, followers and tokens.
+#[tokio::test]
+async fn account_deletion() {
+    let api = api();
+    let mut users = Vec::new();
+    for _ in 0..2 {
+        let user = api
+            .create_user(&UserInput {
+                nickname: Uuid::new_v4().to_string(),
+                email: format!("{}@example.com", Uuid::new_v4()),
+            })
+            .await
+            .unwrap();
+        users.push(user);
+    }
+    let (author, follower) = (&users[0], &users[1]);
+    let post = api
+        .create_post(&PostInput {
+            author: author.nickname.clone(),
+            date: Utc::now().fixed_offset(),
+            content: "to be orphaned".to_owned(),
+            publish_at: None,
+        })
+        .await
+        .unwrap();
+    api.follow(&follower.id, &author.nickname).await.unwrap();
+
+    api.delete_user(&author.id).await.unwrap();
+
+    let orphan = api.get_post(&post.id).await.unwrap();
+    assert_eq!(orphan.author, DELETED_AUTHOR);
+    assert!(api.following(&follower.id).await.unwrap().is_empty());
+
+    // The deleted user's token is revoked, and the account is gone
+    let err = api
+        .with_bearer(&author.id)
+        .get_user(&author.id)
+        .await
+        .unwrap_err();
+    assert_eq!(err.status(), Some(StatusCode::UNAUTHORIZED));
+    let err = api.delete_user(&author.id).await.unwrap_err();
+    assert_eq!(err.status(), Some(StatusCode::NOT_FOUND));
+}
+
+// Follows an author whose nickname has characters which aren't allowed in a path segment as they
+// are, checking that the client's URLs keep the nickname one segment.
+#[tokio::test]
+async fn follow_escaped_nickname() {
+    let api = api();
+    let author = format!("a b/{}?#%", Uuid::new_v4());
+    let follower = api
+        .create_user(&UserInput {
+            nickname: Uuid::new_v4().to_string(),
+            email: format!("{}@example.com", Uuid::new_v4()),
+        })
+        .await
+        .unwrap();
+    api.follow(&follower.id, &author).await.unwrap();
+    assert_eq!(
+        api.following(&follower.id).await.unwrap(),
+        [author.as_str()]
+    );
+    api.unfollow(&follower.id, &author).await.unwrap();
+    assert!(api.following(&follower.id).await.unwrap().is_empty());
+}
+
+// Follows the `Location` of a created user, resolved against the URL of the request. The request
+// carries a `Host` no URL can have, which the location mustn't depend on.
+#[tokio::test]
+async fn location_resolves() {
+    let api = api();
+    let response = api
+        .send(
+            api.request(Method::POST, &urls::users::list())
+                .header(HOST, "bad host")
+                .json(&UserInput {
+                    nickname: format!("located-{}", Uuid::new_v4()),
+                    email: format!("{}@example.com", Uuid::new_v4()),
+                }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let location = response.headers()[LOCATION].to_str().unwrap().to_owned();
+    let user: User = response.json().await.unwrap();
+    let target = Url::parse(&api.url(&urls::users::list()))
+        .unwrap()
+        .join(&location)
+        .unwrap();
+    assert_eq!(target.path(), urls::users::by_id(&user.id));
+    let fetched = api
+        .send(api.request(Method::GET, target.path()))
+        .await
+        .unwrap()
+        .json::<User>()
+        .await
+        .unwrap();
+    assert_eq!(fetched, user);
+    api.delete_user(&user.id).await.unwrap();
+}
+
+// Gets and lists a user with `?fields=`, checking that only the requested fields are sent.
+#[tokio::test]
+async fn sparse_fields() {
+    let api = api();
+    let nickname = Uuid::new_v4().to_string();
+    let user = api
+        .create_user(&UserInput {
+            nickname: nickname.clone(),
+            email: format!("{nickname}@example.com"),
+        })
+        .await
+        .unwrap();
+    let get = |path: String| api.send(api.request(Method::GET, &path));
+
+    let single: serde_json::Value =
+        get(format!("{}?fields=nickname", urls::users::by_id(&user.id)))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+    assert_eq!(single, serde_json::json!({"nickname": nickname}));
+
+    let listed: Vec<serde_json::Value> = get(format!("{}?fields=id", urls::users::list()))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(listed.contains(&serde_json::json!({"id": user.id})));
+    api.delete_user(&user.id).await.unwrap();
+}
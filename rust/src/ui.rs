@@ -0,0 +1,19 @@
+use actix_web::web;
+
+/// Registers the demo frontend under `/ui`: static files exercising the API from a browser, so
+/// demos don't require `curl`.
+///
+/// Only available with the `ui` cargo feature; the files are served from `RUST_SERVER_UI_DIR`
+/// (`ui` by default, see the `ui` directory of the repository). Without the feature, nothing is
+/// registered and `/ui` answers `404`.
+#[cfg(feature = "ui")]
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        actix_files::Files::new("/ui", crate::envs::vars::get_ui_dir())
+            // Not redirected to `/ui/`, which trailing slash normalization would undo
+            .index_file("index.html"),
+    );
+}
+
+#[cfg(not(feature = "ui"))]
+pub fn configure(_cfg: &mut web::ServiceConfig) {}
@@ -0,0 +1,90 @@
+// Demo frontend of the PerCom API, served under `/ui` with the `ui` cargo feature.
+
+const $ = (id) => document.getElementById(id);
+
+function status(text, error = false) {
+    $("status").textContent = text;
+    $("status").className = error ? "error" : "";
+}
+
+// Sends a request to the API and returns the parsed JSON body, if any.
+async function api(method, path, body) {
+    const headers = { Authorization: `Bearer ${$("token").value}` };
+    if (body !== undefined) {
+        headers["Content-Type"] = "application/json";
+    }
+    const started = performance.now();
+    const response = await fetch(path, {
+        method,
+        headers,
+        body: body === undefined ? undefined : JSON.stringify(body),
+    });
+    const elapsed = (performance.now() - started).toFixed(1);
+    const text = await response.text();
+    const json = text ? JSON.parse(text) : null;
+    if (!response.ok) {
+        status(`${method} ${path}: ${response.status} ${json?.detail ?? json?.title ?? ""}`, true);
+        throw new Error(`${method} ${path} failed with ${response.status}`);
+    }
+    status(`${method} ${path}: ${response.status} in ${elapsed} ms`);
+    return json;
+}
+
+function render(posts) {
+    const list = $("posts");
+    list.replaceChildren();
+    for (const post of posts) {
+        const item = document.createElement("li");
+        const meta = document.createElement("div");
+        meta.className = "meta";
+        meta.textContent = `${post.author} · ${new Date(post.date).toLocaleString()} · ${post.status}`;
+        const content = document.createElement("p");
+        content.textContent = post.content;
+        const remove = document.createElement("button");
+        remove.textContent = "Delete";
+        remove.onclick = async () => {
+            await api("DELETE", `/posts/${post.id}`);
+            await refresh();
+        };
+        item.append(meta, content, remove);
+        list.append(item);
+    }
+}
+
+async function refresh() {
+    render(await api("GET", "/posts"));
+}
+
+$("refresh").onclick = refresh;
+
+$("feed").onclick = async () => {
+    const page = await api("GET", "/feed");
+    render(page.items);
+};
+
+$("user-form").onsubmit = async (event) => {
+    event.preventDefault();
+    const form = new FormData(event.target);
+    const user = await api("POST", "/users", {
+        nickname: form.get("nickname"),
+        email: form.get("email"),
+    });
+    $("token").value = user.id;
+    event.target.reset();
+};
+
+$("post-form").onsubmit = async (event) => {
+    event.preventDefault();
+    const form = new FormData(event.target);
+    const publishAt = form.get("publish_at");
+    await api("POST", "/posts", {
+        author: form.get("author"),
+        date: new Date().toISOString(),
+        content: form.get("content"),
+        publish_at: publishAt ? new Date(publishAt).toISOString() : undefined,
+    });
+    event.target.reset();
+    await refresh();
+};
+
+refresh().catch(() => {});